@@ -0,0 +1,58 @@
+//! Counts heap allocations of a full fixture parse with [dhat](https://crates.io/crates/dhat),
+//! into owned records and borrowed ones, and of the fixture with its record lines repeated
+//! for the allocations per record
+//!
+//! Run with `cargo bench --bench alloc`; `dhat-heap.json` can be opened in dhat's viewer.
+
+use std::fs;
+
+#[global_allocator]
+static ALLOC: dhat::Alloc = dhat::Alloc;
+
+fn main() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+    let fixture = fs::read_to_string(path).unwrap();
+
+    // Each record line 100 times, so that allocations of section headers are amortized
+    let repeated: String = fixture
+        .split_inclusive('\n')
+        .map(|line| {
+            let times = if line.starts_with(|ch: char| ch.is_ascii_digit()) {
+                100
+            } else {
+                1
+            };
+            line.repeat(times)
+        })
+        .collect();
+
+    let _profiler = dhat::Profiler::new_heap();
+    for (label, feed) in &[("fixture", &fixture), ("records x100", &repeated)] {
+        report(&format!("{}, owned", label), || {
+            amfi::nav_from_str(feed).filter(Result::is_ok).count()
+        });
+        report(&format!("{}, borrowed", label), || {
+            let mut records = 0;
+            amfi::for_each_record(feed.as_bytes(), |record| records += record.is_ok() as usize);
+            records
+        });
+    }
+}
+
+/// Prints the allocations of `parse`, which returns the number of records parsed
+fn report(label: &str, parse: impl FnOnce() -> usize) {
+    let before = dhat::HeapStats::get();
+    let records = parse();
+    let after = dhat::HeapStats::get();
+
+    let blocks = after.total_blocks - before.total_blocks;
+    let bytes = after.total_bytes - before.total_bytes;
+    println!(
+        "{}: {} records: {} allocations ({:.1} per record), {} bytes",
+        label,
+        records,
+        blocks,
+        blocks as f64 / records as f64,
+        bytes
+    );
+}
@@ -0,0 +1,51 @@
+//! Compares `amc_list` with a full parse of a large synthetic feed
+//!
+//! Run with `cargo bench --bench amc_list`.
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Fixture body repeated to about the size of the daily feed
+const REPEAT: usize = 500;
+const ROUNDS: u32 = 10;
+
+fn time<F: FnMut() -> usize>(name: &str, mut f: F) -> Duration {
+    let start = Instant::now();
+    let mut count = 0;
+    for _ in 0..ROUNDS {
+        count = f();
+    }
+    let elapsed = start.elapsed() / ROUNDS;
+    println!("{:<12} {:>10.3?} per run ({} items)", name, elapsed, count);
+    elapsed
+}
+
+fn main() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+    let fixture = fs::read_to_string(path).unwrap();
+    let body_start = fixture.find("Open Ended").unwrap();
+    let mut feed = fixture[..body_start].to_string();
+    for _ in 0..REPEAT {
+        feed.push_str(&fixture[body_start..]);
+    }
+    let feed_path = std::env::temp_dir().join(format!("amfi-bench-{}.txt", std::process::id()));
+    fs::write(&feed_path, &feed).unwrap();
+    println!("feed: {} bytes", feed.len());
+
+    let list = time("amc_list", || {
+        let file = fs::File::open(&feed_path).unwrap();
+        amfi::amc_list(file)
+            .unwrap()
+            .iter()
+            .map(|amc| amc.record_count)
+            .sum()
+    });
+    let parse = time("full parse", || {
+        amfi::nav_from_file(&feed_path)
+            .unwrap()
+            .filter(Result::is_ok)
+            .count()
+    });
+    fs::remove_file(&feed_path).unwrap();
+    println!("speedup: {:.1}x", parse.as_secs_f64() / list.as_secs_f64());
+}
@@ -0,0 +1,75 @@
+//! Parser baseline: full fixture into owned and borrowed records, a single record line, the
+//! line classifier through `amc_list` and reading a feed file through buffers of different
+//! sizes
+//!
+//! Run with `cargo bench --bench parse`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use std::fs;
+use std::path::PathBuf;
+
+const LINE: &str =
+    "120466;INF846K01AC2;INF846K01AD0;Axis Bluechip Fund - Direct Plan - IDCW;21.9800;21-Jun-2024";
+
+fn bench(c: &mut Criterion) {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+    let fixture = fs::read_to_string(path).unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(fixture.len() as u64));
+    group.bench_function("fixture", |b| {
+        b.iter(|| amfi::nav_from_str(black_box(&fixture)).count())
+    });
+    group.bench_function("fixture_borrowed", |b| {
+        b.iter(|| {
+            let mut records = 0;
+            amfi::for_each_record(black_box(fixture.as_bytes()), |_| records += 1);
+            records
+        })
+    });
+    group.finish();
+
+    let feed = feed_file(&fixture);
+    let len = fs::metadata(&feed).unwrap().len();
+    let mut group = c.benchmark_group("read");
+    group.throughput(Throughput::Bytes(len));
+    for &kib in &[8, 64] {
+        group.bench_function(format!("buffer_{}k", kib), |b| {
+            b.iter(|| {
+                amfi::nav_from_file(&feed)
+                    .unwrap()
+                    .with_buffer_capacity(kib * 1024)
+                    .count()
+            })
+        });
+    }
+    group.bench_function("read_ahead", |b| {
+        b.iter(|| amfi::nav_from_file(&feed).unwrap().read_ahead(true).count())
+    });
+    group.finish();
+    fs::remove_file(&feed).unwrap();
+
+    c.bench_function("parse_line", |b| {
+        b.iter(|| amfi::NavRecord::parse_line(black_box(LINE)))
+    });
+    // Classifies every line without parsing records
+    c.bench_function("amc_list", |b| {
+        b.iter(|| amfi::amc_list(black_box(fixture.as_bytes())).unwrap().len())
+    });
+}
+
+/// File of about 2 MB, the size of the daily feed, of the header of `fixture` and its sections
+/// over and over
+fn feed_file(fixture: &str) -> PathBuf {
+    let (header, sections) = fixture.split_once('\n').unwrap();
+    let mut feed = format!("{}\n", header);
+    while feed.len() < 2_000_000 {
+        feed.push_str(sections);
+    }
+    let path = std::env::temp_dir().join(format!("amfi-bench-{}.txt", std::process::id()));
+    fs::write(&path, feed).unwrap();
+    path
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
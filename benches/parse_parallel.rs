@@ -0,0 +1,39 @@
+//! Compares `parse_parallel` with the sequential iterator on a multi-day feed
+//!
+//! Run with `cargo bench --features rayon --bench parse_parallel`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::fs;
+
+/// Number of days concatenated, each a copy of the fixture body
+const DAYS: i64 = 500;
+
+fn multi_day_feed() -> String {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+    let fixture = fs::read_to_string(path).unwrap();
+    let body_start = fixture.find("Open Ended").unwrap();
+    let first = amfi::NavDate::from_ymd(2024, 6, 21).unwrap().epoch_days();
+    let mut feed = fixture[..body_start].to_string();
+    for day in 0..DAYS {
+        let date = amfi::NavDate::from_epoch_days(first - day).unwrap();
+        let date = date.feed_format().to_string();
+        feed.push_str(&fixture[body_start..].replace("21-Jun-2024", &date));
+    }
+    feed
+}
+
+fn bench(c: &mut Criterion) {
+    let feed = multi_day_feed();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(feed.len() as u64));
+    group.bench_function("sequential", |b| {
+        b.iter(|| amfi::nav_from_str(&feed).filter(Result::is_ok).count())
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| amfi::parse_parallel(&feed).records.len())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
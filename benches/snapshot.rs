@@ -0,0 +1,25 @@
+//! Loading a saved snapshot against parsing the text feed again
+//!
+//! Run with `cargo bench --bench snapshot --features bincode`.
+
+use amfi::NavSnapshot;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn bench(c: &mut Criterion) {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+    let saved = std::env::temp_dir().join(format!("amfi-bench-{}.snapshot", std::process::id()));
+    NavSnapshot::from_file(path).unwrap().save(&saved).unwrap();
+
+    let mut group = c.benchmark_group("snapshot");
+    group.bench_function("parse", |b| {
+        b.iter(|| NavSnapshot::from_file(black_box(path)).unwrap())
+    });
+    group.bench_function("load", |b| {
+        b.iter(|| NavSnapshot::load(black_box(&saved)).unwrap())
+    });
+    group.finish();
+    std::fs::remove_file(&saved).unwrap();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+//! Prints `src/amc_ids.rs` from the fund houses currently listed by the AMFI portal
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut amcs = amfi::fetch_amc_ids()?;
+    amcs.sort_by_key(|(_, name)| name.to_lowercase());
+    println!("//! Fund house ids of the AMFI portal");
+    println!("//!");
+    println!("//! Generated from the `mf` options of the AMC selector on the portal's NAV history download");
+    println!("//! page, <https://www.amfiindia.com/nav-history-download>. Regenerate with");
+    println!("//! `cargo run --example amc_ids > src/amc_ids.rs` when AMFI adds fund houses.");
+    println!();
+    println!("/// Portal id and name, as published in the feed, of each fund house");
+    println!("pub(crate) const AMC_IDS: &[(u32, &str)] = &[");
+    for (id, name) in amcs {
+        println!("    ({}, {:?}),", id, name);
+    }
+    println!("];");
+    Ok(())
+}
@@ -0,0 +1,9 @@
+//! Arbitrary bytes through the full iterator, as read by `nav_from_file`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = amfi::nav_from_reader(data).count();
+});
@@ -0,0 +1,15 @@
+//! Single record lines, see `NavRecord::parse_line`, and as records of the history layout
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+/// Column header of the bulk history download, whose records have the name second
+const HISTORY_HEADER: &str = "Scheme Code;Scheme Name;ISIN Div Payout/ISIN Growth;\
+    ISIN Div Reinvestment;Net Asset Value;Repurchase Price;Sale Price;Date";
+
+fuzz_target!(|line: &str| {
+    let _ = amfi::NavRecord::parse_line(line);
+    let feed = format!("{}\nAxis Mutual Fund\n{}\n", HISTORY_HEADER, line);
+    let _ = amfi::nav_from_str(&feed).count();
+});
@@ -0,0 +1,13 @@
+//! Section headers, followed by a record so the parsed section is used
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|header: &str| {
+    let feed = format!(
+        "Open Ended Schemes{}\n120465;INF846K01AB4;-;Fund;61.43;21-Jun-2024\n",
+        header
+    );
+    let _ = amfi::nav_from_str(&feed).count();
+});
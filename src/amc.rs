@@ -0,0 +1,524 @@
+//! Asset Management Company (AMC) names
+//!
+//! Section headers in the feed carry the full fund house name, e.g.
+//! `Aditya Birla Sun Life Mutual Fund`. Joins against other datasets need a canonical short
+//! form, which [`normalize_amc`](fn.normalize_amc.html) and
+//! [`AmcNormalizer`](struct.AmcNormalizer.html) provide, and
+//! [`AmcAliases`](struct.AmcAliases.html) keys records across renames for grouping them.
+//! [`amc_list`](fn.amc_list.html)
+//! lists the AMCs of a feed without parsing its records. [`AmcId`](struct.AmcId.html) maps
+//! them to the ids of the portal's per-AMC downloads.
+
+use crate::amc_ids::AMC_IDS;
+use crate::group::collapse_whitespace;
+use crate::{LineType, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+
+/// Trailing words dropped from AMC names, longest first
+const SUFFIXES: &[&str] = &[
+    "asset management company limited",
+    "asset management company ltd",
+    "asset management company",
+    "asset management limited",
+    "asset management ltd",
+    "asset management",
+    "mutual fund",
+];
+
+/// Words kept in upper case when re-casing uniformly cased names
+const ACRONYMS: &[&str] = &[
+    "BNP", "DSP", "HDFC", "HSBC", "ICICI", "IDBI", "IDFC", "IIFL", "ITI", "JM", "L&T", "LIC", "NJ",
+    "PGIM", "PPFAS", "SBI", "UTI",
+];
+
+/// Well known fund house renames, keyed by normalized short name
+const RENAMES: &[(&str, &str)] = &[
+    ("Reliance", "Nippon India"),
+    ("L&T", "HSBC"),
+    ("IDFC", "Bandhan"),
+    ("Principal", "Sundaram"),
+    ("IDBI", "LIC"),
+    ("BNP Paribas", "Baroda BNP Paribas"),
+    ("Baroda", "Baroda BNP Paribas"),
+    ("DHFL Pramerica", "PGIM India"),
+    ("Essel", "Navi"),
+    ("Indiabulls", "Groww"),
+    ("Yes", "WhiteOak Capital"),
+];
+
+/// Normalizes AMC name with built-in aliases
+///
+/// Shorthand for `AmcNormalizer::new().normalize(name)`.
+pub fn normalize_amc(name: &str) -> String {
+    AmcNormalizer::new().normalize(name)
+}
+
+/// AMC name normalizer with an extensible alias table
+///
+/// Normalization collapses whitespace, drops the trailing `Mutual Fund`/`Asset Management`
+/// suffixes, re-cases names published entirely in upper or lower case, and finally maps known
+/// renames (e.g. `Reliance Mutual Fund` to `Nippon India`).
+///
+/// ```rust
+/// let normalizer = amfi::AmcNormalizer::new().alias("Kotak Mahindra", "Kotak");
+/// assert_eq!(normalizer.normalize("Kotak Mahindra Mutual Fund"), "Kotak");
+/// assert_eq!(normalizer.normalize("Reliance Mutual Fund"), "Nippon India");
+/// ```
+#[derive(Debug, Clone)]
+pub struct AmcNormalizer {
+    aliases: HashMap<String, String>,
+}
+
+impl Default for AmcNormalizer {
+    fn default() -> Self {
+        RENAMES
+            .iter()
+            .fold(AmcNormalizer::empty(), |n, &(from, to)| n.alias(from, to))
+    }
+}
+
+impl AmcNormalizer {
+    /// Normalizer with the built-in rename table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalizer without any aliases
+    pub fn empty() -> Self {
+        AmcNormalizer {
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Adds (or replaces) an alias
+    ///
+    /// `from` is matched after normalization, so both `Reliance` and `Reliance Mutual Fund`
+    /// refer to the same entry. `to` is used verbatim.
+    pub fn alias<F: AsRef<str>, T: Into<String>>(mut self, from: F, to: T) -> Self {
+        self.aliases.insert(alias_key(from.as_ref()), to.into());
+        self
+    }
+
+    /// Canonical short form of `name`
+    pub fn normalize(&self, name: &str) -> String {
+        let short = short_name(name);
+        match self.aliases.get(&short.to_lowercase()) {
+            Some(canonical) => canonical.clone(),
+            None => short,
+        }
+    }
+}
+
+/// How [`AmcAliases`](struct.AmcAliases.html) keys treat the `Mutual Fund` suffix of AMC
+/// headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmcSuffixPolicy {
+    /// Keys are the headers as published, whitespace collapsed, renamed houses under their
+    /// new name with the suffix of the header, e.g. `Nippon India Mutual Fund`
+    #[default]
+    Keep,
+    /// Keys are the short names of [`AmcNormalizer`](struct.AmcNormalizer.html), e.g.
+    /// `Nippon India`
+    Strip,
+}
+
+/// Grouping key of an AMC header, see [`AmcAliases::key`](struct.AmcAliases.html#method.key)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmcKey {
+    /// Key grouping the header with those of the same fund house
+    pub key: String,
+    /// Header as published, if an alias renamed it
+    pub aliased_from: Option<String>,
+}
+
+/// Keys of AMC headers grouping a fund house across its renames
+///
+/// Feeds of several years name a house by each of its names, e.g. `Reliance Mutual Fund` and
+/// later `Nippon India Mutual Fund`. Keys map former names to current ones with the
+/// built-in rename table of [`AmcNormalizer`](struct.AmcNormalizer.html), extended with
+/// [`alias`](#method.alias). Used by
+/// [`group_by_amc_with`](fn.group_by_amc_with.html) and
+/// [`NavHistory::amc_aliases`](struct.NavHistory.html#method.amc_aliases).
+///
+/// ```rust
+/// use amfi::{AmcAliases, AmcSuffixPolicy};
+///
+/// let aliases = AmcAliases::new();
+/// let key = aliases.key("Reliance Mutual Fund");
+/// assert_eq!(key.key, "Nippon India Mutual Fund");
+/// assert_eq!(key.aliased_from.as_deref(), Some("Reliance Mutual Fund"));
+/// assert_eq!(aliases.key("Axis Mutual Fund").aliased_from, None);
+///
+/// let aliases = aliases.suffix(AmcSuffixPolicy::Strip).alias("Kotak Mahindra", "Kotak");
+/// assert_eq!(aliases.key("L&T Mutual Fund").key, "HSBC");
+/// assert_eq!(aliases.key("Kotak Mahindra Mutual Fund").key, "Kotak");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AmcAliases {
+    normalizer: AmcNormalizer,
+    suffix: AmcSuffixPolicy,
+}
+
+impl AmcAliases {
+    /// Aliases of the built-in rename table, keeping suffixes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// No aliases, keys are the headers as published with whitespace collapsed, or their
+    /// short names
+    pub fn empty() -> Self {
+        AmcAliases {
+            normalizer: AmcNormalizer::empty(),
+            suffix: AmcSuffixPolicy::default(),
+        }
+    }
+
+    /// Adds (or replaces) an alias, as
+    /// [`AmcNormalizer::alias`](struct.AmcNormalizer.html#method.alias) does
+    ///
+    /// `to` is the short name, suffixed as the header renamed under
+    /// [`AmcSuffixPolicy::Keep`](enum.AmcSuffixPolicy.html#variant.Keep).
+    pub fn alias<F: AsRef<str>, T: Into<String>>(mut self, from: F, to: T) -> Self {
+        self.normalizer = self.normalizer.alias(from, to);
+        self
+    }
+
+    /// Sets how keys treat suffixes, [`Keep`](enum.AmcSuffixPolicy.html#variant.Keep) by
+    /// default
+    pub fn suffix(mut self, policy: AmcSuffixPolicy) -> Self {
+        self.suffix = policy;
+        self
+    }
+
+    /// Key of the AMC header `amc`
+    pub fn key(&self, amc: &str) -> AmcKey {
+        let collapsed = collapse_whitespace(amc);
+        let short = short_name(&collapsed);
+        let alias = self.normalizer.aliases.get(&short.to_lowercase());
+        let key = match (self.suffix, alias) {
+            (AmcSuffixPolicy::Keep, Some(to)) => {
+                let suffix = &collapsed[strip_suffixes(&collapsed).len()..];
+                format!("{}{}", to, suffix)
+            }
+            (AmcSuffixPolicy::Keep, None) => collapsed.into_owned(),
+            (AmcSuffixPolicy::Strip, Some(to)) => to.clone(),
+            (AmcSuffixPolicy::Strip, None) => short,
+        };
+        AmcKey {
+            key,
+            aliased_from: alias.map(|_| amc.to_string()),
+        }
+    }
+}
+
+fn alias_key(name: &str) -> String {
+    short_name(name).to_lowercase()
+}
+
+/// Collapsed, suffix-free and consistently cased name
+fn short_name(name: &str) -> String {
+    let collapsed = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let stripped = strip_suffixes(&collapsed);
+    let has_upper = stripped.chars().any(char::is_uppercase);
+    let has_lower = stripped.chars().any(char::is_lowercase);
+    if has_upper && has_lower {
+        stripped.to_string()
+    } else {
+        stripped
+            .split(' ')
+            .map(recase_word)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn strip_suffixes(name: &str) -> &str {
+    let mut name = name.trim_end_matches('.').trim_end();
+    'outer: loop {
+        for suffix in SUFFIXES {
+            if name.len() > suffix.len() {
+                let at = name.len() - suffix.len();
+                if name.is_char_boundary(at)
+                    && name[at..].eq_ignore_ascii_case(suffix)
+                    && name[..at].ends_with(' ')
+                {
+                    name = name[..at].trim_end();
+                    continue 'outer;
+                }
+            }
+        }
+        return name;
+    }
+}
+
+fn recase_word(word: &str) -> String {
+    let upper = word.to_uppercase();
+    if ACRONYMS.contains(&upper.as_str()) {
+        return upper;
+    }
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Numeric id of a fund house on the AMFI portal, its `mf` query parameter
+///
+/// Used to download the NAV history of a single AMC, see
+/// [`nav_history_for_amc`](fn.nav_history_for_amc.html). The feed doesn't carry these ids;
+/// a table of them is bundled, see [`amc_ids`](fn.amc_ids.html).
+///
+/// ```rust
+/// let id = amfi::AmcId::find("ICICI Prudential Mutual Fund").unwrap();
+/// assert_eq!(amfi::AmcId::find("icici prudential"), Some(id));
+/// assert_eq!(id.name(), Some("ICICI Prudential Mutual Fund"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AmcId(pub u32);
+
+impl AmcId {
+    /// Id of the fund house named `name` in the bundled table
+    ///
+    /// Names are compared [normalized](fn.normalize_amc.html), so suffixes, case and former
+    /// names don't matter. Failing an exact match, a name that starts exactly one table entry
+    /// word for word, e.g. `Kotak`, resolves to it.
+    pub fn find(name: &str) -> Option<AmcId> {
+        let normalizer = AmcNormalizer::new();
+        let wanted = normalizer.normalize(name).to_lowercase();
+        if wanted.is_empty() {
+            return None;
+        }
+        let names: Vec<(AmcId, String)> = amc_ids()
+            .map(|(id, name)| (id, normalizer.normalize(name).to_lowercase()))
+            .collect();
+        if let Some(&(id, _)) = names.iter().find(|(_, name)| *name == wanted) {
+            return Some(id);
+        }
+        let prefix = format!("{} ", wanted);
+        let mut starting = names.iter().filter(|(_, name)| name.starts_with(&prefix));
+        match (starting.next(), starting.next()) {
+            (Some(&(id, _)), None) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Name of the fund house in the bundled table, as published in the feed
+    pub fn name(self) -> Option<&'static str> {
+        amc_ids().find(|&(id, _)| id == self).map(|(_, name)| name)
+    }
+}
+
+/// Bundled table of portal ids and fund house names, in order of name
+///
+/// The table is generated from the portal; fund houses added since the crate was released
+/// are missing, and can be listed with `fetch_amc_ids` of the `blocking` feature.
+///
+/// ```rust
+/// assert!(amfi::amc_ids().any(|(_, name)| name == "Axis Mutual Fund"));
+/// ```
+pub fn amc_ids() -> impl Iterator<Item = (AmcId, &'static str)> {
+    AMC_IDS.iter().map(|&(id, name)| (AmcId(id), name))
+}
+
+impl std::fmt::Display for AmcId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// AMC present in a feed, see [`amc_list`](fn.amc_list.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmcEntry {
+    /// AMC name as published
+    pub name: String,
+    /// Scheme sections the AMC appears in
+    pub scheme_sections: usize,
+    /// Record lines under the AMC, including malformed ones
+    pub record_count: usize,
+}
+
+/// Lists AMCs of a feed in order of first appearance
+///
+/// Lines are classified like [`NavRecordIterator`](struct.NavRecordIterator.html) does, but
+/// record lines are only counted, never parsed, which makes this several times faster than
+/// a full parse.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let amcs = amfi::amc_list(std::fs::File::open("fixtures/NAVOpen.txt")?)?;
+/// assert_eq!(amcs[0].name, "Axis Mutual Fund");
+/// # Ok(())
+/// # }
+/// ```
+pub fn amc_list<R: Read>(reader: R) -> Result<Vec<AmcEntry>> {
+    let mut reader = BufReader::new(reader);
+    let mut entries: Vec<AmcEntry> = Vec::new();
+    // Section in which each entry was last counted
+    let mut last_section: Vec<usize> = Vec::new();
+    let mut section = 0;
+    let mut current: Option<usize> = None;
+    let mut line = String::new();
+    while {
+        line.clear();
+        reader.read_line(&mut line)? > 0
+    } {
+        match LineType::of(&line) {
+            LineType::Record => {
+                if let Some(index) = current {
+                    entries[index].record_count += 1;
+                }
+            }
+            LineType::Scheme => section += 1,
+            LineType::Amc => {
+                let name = line.trim();
+                let index = match entries.iter().position(|entry| entry.name == name) {
+                    Some(index) => index,
+                    None => {
+                        entries.push(AmcEntry {
+                            name: name.to_string(),
+                            scheme_sections: 0,
+                            record_count: 0,
+                        });
+                        last_section.push(usize::MAX);
+                        entries.len() - 1
+                    }
+                };
+                if last_section[index] != section {
+                    last_section[index] = section;
+                    entries[index].scheme_sections += 1;
+                }
+                current = Some(index);
+            }
+            LineType::Blank | LineType::Header => (),
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_suffixes_and_whitespace() {
+        assert_eq!(
+            normalize_amc("  Aditya Birla Sun Life   Mutual Fund "),
+            "Aditya Birla Sun Life"
+        );
+        assert_eq!(
+            normalize_amc("Quantum Asset Management Company Ltd."),
+            "Quantum"
+        );
+        assert_eq!(normalize_amc("Mutual Fund"), "Mutual Fund");
+    }
+
+    #[test]
+    fn keys_follow_renames() {
+        let aliases = AmcAliases::new();
+        let key = aliases.key(" Reliance  Mutual Fund");
+        assert_eq!(key.key, "Nippon India Mutual Fund");
+        assert_eq!(key.aliased_from.as_deref(), Some(" Reliance  Mutual Fund"));
+        assert_eq!(aliases.key("RELIANCE").key, "Nippon India");
+        assert_eq!(
+            aliases.key("Nippon India Mutual Fund"),
+            AmcKey {
+                key: "Nippon India Mutual Fund".to_string(),
+                aliased_from: None,
+            }
+        );
+        assert_eq!(aliases.key("Axis  Mutual Fund ").key, "Axis Mutual Fund");
+
+        let strip = AmcAliases::empty().suffix(AmcSuffixPolicy::Strip);
+        assert_eq!(strip.key("Reliance Mutual Fund").key, "Reliance");
+        assert_eq!(strip.key("Reliance Mutual Fund").aliased_from, None);
+        assert_eq!(strip.key("AXIS MUTUAL FUND").key, "Axis");
+    }
+
+    #[test]
+    fn recases_uniform_names() {
+        assert_eq!(
+            normalize_amc("ICICI PRUDENTIAL MUTUAL FUND"),
+            "ICICI Prudential"
+        );
+        assert_eq!(normalize_amc("hdfc mutual fund"), "HDFC");
+        assert_eq!(
+            normalize_amc("WhiteOak Capital Mutual Fund"),
+            "WhiteOak Capital"
+        );
+    }
+
+    #[test]
+    fn fixture_amc_list() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let amcs = amc_list(std::fs::File::open(path).unwrap()).unwrap();
+        let names: Vec<&str> = amcs.iter().map(|amc| amc.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Axis Mutual Fund",
+                "ICICI Prudential Mutual Fund",
+                "HDFC Mutual Fund",
+                "Aditya Birla Sun Life Mutual Fund",
+                "SBI Mutual Fund",
+                "Nippon India Mutual Fund",
+            ]
+        );
+        assert_eq!(
+            amcs[0],
+            AmcEntry {
+                name: "Axis Mutual Fund".to_string(),
+                scheme_sections: 3,
+                record_count: 5,
+            }
+        );
+        assert_eq!(amcs.iter().map(|amc| amc.record_count).sum::<usize>(), 30);
+    }
+
+    #[test]
+    fn amc_id_lookup() {
+        let id = |name| AmcId::find(name).and_then(AmcId::name);
+        assert_eq!(
+            id("ICICI Prudential Mutual Fund"),
+            Some("ICICI Prudential Mutual Fund")
+        );
+        assert_eq!(id("AXIS MUTUAL FUND"), Some("Axis Mutual Fund"));
+        assert_eq!(id("Reliance Mutual Fund"), Some("Nippon India Mutual Fund"));
+        assert_eq!(id("Kotak"), Some("Kotak Mahindra Mutual Fund"));
+        assert_eq!(id("quant"), Some("quant Mutual Fund"));
+        assert_eq!(id("Unknown Mutual Fund"), None);
+        assert_eq!(id(""), None);
+        assert_eq!(AmcId(u32::MAX).name(), None);
+
+        // Every AMC of the fixture resolves
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        for amc in amc_list(std::fs::File::open(path).unwrap()).unwrap() {
+            let found = AmcId::find(&amc.name).and_then(AmcId::name);
+            assert_eq!(found, Some(amc.name.as_str()));
+        }
+
+        // Ids and names are unique
+        let mut ids: Vec<_> = amc_ids().map(|(id, _)| id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), amc_ids().count());
+        for (id, name) in amc_ids() {
+            assert_eq!(AmcId::find(name), Some(id));
+        }
+    }
+
+    #[test]
+    fn known_and_custom_aliases() {
+        assert_eq!(normalize_amc("Reliance Mutual Fund"), "Nippon India");
+        assert_eq!(normalize_amc("L&T Mutual Fund"), "HSBC");
+
+        let normalizer = AmcNormalizer::empty().alias("Axis Mutual Fund", "Axis AMC");
+        assert_eq!(normalizer.normalize("AXIS MUTUAL FUND"), "Axis AMC");
+        assert_eq!(normalizer.normalize("Reliance Mutual Fund"), "Reliance");
+    }
+}
@@ -0,0 +1,52 @@
+//! Fund house ids of the AMFI portal
+//!
+//! Generated from the `mf` options of the AMC selector on the portal's NAV history download
+//! page, <https://www.amfiindia.com/nav-history-download>, as of June 2024. Regenerate with
+//! `cargo run --example amc_ids > src/amc_ids.rs` when AMFI adds fund houses.
+
+/// Portal id and name, as published in the feed, of each fund house
+pub(crate) const AMC_IDS: &[(u32, &str)] = &[
+    (68, "360 ONE Mutual Fund"),
+    (3, "Aditya Birla Sun Life Mutual Fund"),
+    (53, "Axis Mutual Fund"),
+    (79, "Bajaj Finserv Mutual Fund"),
+    (48, "Bandhan Mutual Fund"),
+    (59, "Bank of India Mutual Fund"),
+    (4, "Baroda BNP Paribas Mutual Fund"),
+    (32, "Canara Robeco Mutual Fund"),
+    (6, "DSP Mutual Fund"),
+    (47, "Edelweiss Mutual Fund"),
+    (27, "Franklin Templeton Mutual Fund"),
+    (62, "Groww Mutual Fund"),
+    (9, "HDFC Mutual Fund"),
+    (77, "Helios Mutual Fund"),
+    (37, "HSBC Mutual Fund"),
+    (20, "ICICI Prudential Mutual Fund"),
+    (42, "Invesco Mutual Fund"),
+    (70, "ITI Mutual Fund"),
+    (16, "JM Financial Mutual Fund"),
+    (17, "Kotak Mahindra Mutual Fund"),
+    (18, "LIC Mutual Fund"),
+    (69, "Mahindra Manulife Mutual Fund"),
+    (45, "Mirae Asset Mutual Fund"),
+    (55, "Motilal Oswal Mutual Fund"),
+    (54, "Navi Mutual Fund"),
+    (21, "Nippon India Mutual Fund"),
+    (73, "NJ Mutual Fund"),
+    (80, "Old Bridge Mutual Fund"),
+    (58, "PGIM India Mutual Fund"),
+    (64, "PPFAS Mutual Fund"),
+    (13, "quant Mutual Fund"),
+    (41, "Quantum Mutual Fund"),
+    (74, "Samco Mutual Fund"),
+    (22, "SBI Mutual Fund"),
+    (66, "Shriram Mutual Fund"),
+    (33, "Sundaram Mutual Fund"),
+    (25, "Tata Mutual Fund"),
+    (26, "Taurus Mutual Fund"),
+    (72, "Trust Mutual Fund"),
+    (61, "Union Mutual Fund"),
+    (28, "UTI Mutual Fund"),
+    (76, "WhiteOak Capital Mutual Fund"),
+    (78, "Zerodha Mutual Fund"),
+];
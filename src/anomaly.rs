@@ -0,0 +1,487 @@
+//! Anomalies of a feed found by comparing it with the previous day's
+
+use crate::{
+    is_business_day, normalize_amc, AssetClass, NavDate, NavRecord, NavSnapshot, SchemeCode,
+    SubCategory,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Thresholds of [`detect_anomalies`](fn.detect_anomalies.html)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyConfig {
+    /// Largest plausible daily NAV move in percent of cash-like debt schemes: overnight,
+    /// liquid, money market, ultra short and low duration, floater and arbitrage funds;
+    /// `1.0` by default
+    pub cash_max_move_pct: f64,
+    /// Largest plausible daily NAV move in percent of other debt schemes, `5.0` by default
+    pub debt_max_move_pct: f64,
+    /// Largest plausible daily NAV move in percent of hybrid and solution oriented schemes,
+    /// `10.0` by default
+    pub hybrid_max_move_pct: f64,
+    /// Largest plausible daily NAV move in percent of equity and other schemes, e.g. index
+    /// funds, ETFs and FoFs, `20.0` by default
+    pub equity_max_move_pct: f64,
+    /// Largest plausible daily NAV move in percent of sub-categories, overriding the above;
+    /// empty by default
+    pub max_move_pct: BTreeMap<SubCategory, f64>,
+    /// Business days a NAV may stay exactly unchanged, `3` by default
+    pub stale_days: u32,
+    /// Largest plausible drop in percent of the records of an AMC, `10.0` by default
+    pub max_amc_drop_pct: f64,
+    /// Fewest records yesterday of an AMC whose drop is checked, `10` by default, as one
+    /// scheme more or less is a large share of a small AMC
+    pub min_amc_records: usize,
+    /// Days records may be off the feed date, see
+    /// [`FeedSummary::check_date_skew`](struct.FeedSummary.html#method.check_date_skew);
+    /// `3` by default
+    pub max_date_skew: u32,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            cash_max_move_pct: 1.0,
+            debt_max_move_pct: 5.0,
+            hybrid_max_move_pct: 10.0,
+            equity_max_move_pct: 20.0,
+            max_move_pct: BTreeMap::new(),
+            stale_days: 3,
+            max_amc_drop_pct: 10.0,
+            min_amc_records: 10,
+            max_date_skew: 3,
+        }
+    }
+}
+
+impl AnomalyConfig {
+    /// Largest plausible daily NAV move in percent of schemes of `record`'s category
+    pub fn max_move_pct_of(&self, record: &NavRecord) -> f64 {
+        let category = record.category_typed();
+        if let Some(&pct) = self.max_move_pct.get(&category.sub) {
+            return pct;
+        }
+        match (category.class, category.sub) {
+            (
+                AssetClass::Debt,
+                SubCategory::Overnight
+                | SubCategory::Liquid
+                | SubCategory::MoneyMarket
+                | SubCategory::UltraShortDuration
+                | SubCategory::LowDuration
+                | SubCategory::Floater,
+            )
+            | (_, SubCategory::Arbitrage) => self.cash_max_move_pct,
+            (AssetClass::Debt, _) => self.debt_max_move_pct,
+            (AssetClass::Hybrid, _) | (AssetClass::SolutionOriented, _) => self.hybrid_max_move_pct,
+            (AssetClass::Equity, _) | (AssetClass::Other, _) => self.equity_max_move_pct,
+        }
+    }
+}
+
+/// Anomaly found by [`detect_anomalies`](fn.detect_anomalies.html)
+///
+/// With the `serde` feature it serializes with its kind in a `kind` field, e.g.
+/// `{"kind":"stale_nav","code":120465,...}`, for alerting.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "kind", rename_all = "snake_case")
+)]
+pub enum Anomaly {
+    /// NAV moved more than plausible for the scheme's category
+    NavMove {
+        /// Scheme code
+        code: SchemeCode,
+        /// Scheme name today
+        name: String,
+        /// AMC today
+        amc: String,
+        /// Category today
+        category: String,
+        /// NAV yesterday
+        old_nav: f64,
+        /// NAV today
+        new_nav: f64,
+        /// Change in percent of `old_nav`
+        pct_change: f64,
+        /// Largest plausible change in percent, see
+        /// [`AnomalyConfig::max_move_pct_of`](struct.AnomalyConfig.html#method.max_move_pct_of)
+        max_pct: f64,
+    },
+    /// NAV exactly unchanged for more than `stale_days` business days
+    StaleNav {
+        /// Scheme code
+        code: SchemeCode,
+        /// Scheme name today
+        name: String,
+        /// AMC today
+        amc: String,
+        /// NAV of both days
+        nav: f64,
+        /// NAV date yesterday, since when the NAV is known unchanged
+        since: NavDate,
+        /// Business days from `since` to the feed date today
+        business_days: u32,
+    },
+    /// AMC has fewer records than yesterday by more than `max_amc_drop_pct`
+    AmcShrank {
+        /// AMC, normalized, see [`normalize_amc`](fn.normalize_amc.html)
+        amc: String,
+        /// Records yesterday
+        yesterday: usize,
+        /// Records today, `0` if the AMC is gone
+        today: usize,
+        /// Drop in percent of `yesterday`
+        drop_pct: f64,
+    },
+    /// Records today are further off the feed date than `max_date_skew` days
+    DateSkew {
+        /// NAV date of most records
+        feed_date: NavDate,
+        /// Largest number of days a record is off `feed_date`
+        skew: u32,
+        /// Records not dated `feed_date`
+        off_date_records: usize,
+    },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Anomaly::NavMove {
+                code,
+                ref name,
+                old_nav,
+                new_nav,
+                pct_change,
+                max_pct,
+                ..
+            } => write!(
+                f,
+                "NAV of scheme {} ({}) moved {:+.2}% from {} to {}, beyond {}%",
+                code, name, pct_change, old_nav, new_nav, max_pct
+            ),
+            Anomaly::StaleNav {
+                code,
+                ref name,
+                nav,
+                since,
+                business_days,
+                ..
+            } => write!(
+                f,
+                "NAV of scheme {} ({}) is {} since {}, {} business days",
+                code, name, nav, since, business_days
+            ),
+            Anomaly::AmcShrank {
+                ref amc,
+                yesterday,
+                today,
+                drop_pct,
+            } => write!(
+                f,
+                "{} has {} records, {} yesterday ({:.1}% fewer)",
+                amc, today, yesterday, drop_pct
+            ),
+            Anomaly::DateSkew {
+                feed_date,
+                skew,
+                off_date_records,
+            } => write!(
+                f,
+                "{} records are up to {} days off feed date {}",
+                off_date_records, skew, feed_date
+            ),
+        }
+    }
+}
+
+/// Compares the feed of `today` with that of `yesterday`, the previous business day's
+///
+/// Finds, in this order:
+///
+/// - date skew of `today` beyond `max_date_skew`;
+/// - AMCs, by normalized name, of at least `min_amc_records` records yesterday with more
+///   than `max_amc_drop_pct` fewer today, including AMCs gone;
+/// - schemes, in `today`'s order and matched by code, whose NAV moved more than the threshold
+///   of their category, see
+///   [`AnomalyConfig::max_move_pct_of`](struct.AnomalyConfig.html#method.max_move_pct_of);
+/// - schemes whose NAV is exactly yesterday's, with yesterday's NAV date `stale_days` or more
+///   business days before today's feed date. Two feeds only show the NAV unchanged since
+///   yesterday's NAV date, which the feed keeps while a scheme's NAV is not updated: NAVs
+///   republished unchanged with a new date every day are caught by `stale_days` of `1`.
+///
+/// Records without a valid NAV, see
+/// [`NavRecord::has_valid_nav`](struct.NavRecord.html#method.has_valid_nav), are left out.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::{Anomaly, AnomalyConfig, NavSnapshot};
+///
+/// let yesterday = NavSnapshot::from_file("fixtures/NAVOpen.txt")?;
+/// let mut records = yesterday.records.clone();
+/// // A liquid fund moving 3%
+/// let liquid = records.iter_mut().find(|record| record.code == 120_389).unwrap();
+/// liquid.nav *= 1.03;
+/// liquid.date = amfi::NavDate::from_ymd(2024, 6, 24).unwrap();
+/// let today: NavSnapshot = records.into_iter().map(Ok).collect();
+///
+/// let anomalies = amfi::detect_anomalies(&today, &yesterday, &AnomalyConfig::default());
+/// assert!(matches!(anomalies[..], [Anomaly::NavMove { code, .. }] if code == 120_389));
+/// # Ok(())
+/// # }
+/// ```
+pub fn detect_anomalies(
+    today: &NavSnapshot,
+    yesterday: &NavSnapshot,
+    config: &AnomalyConfig,
+) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    let skew = today.stats.check_date_skew(config.max_date_skew);
+    if let (true, Some(feed_date)) = (skew.is_excessive(), skew.feed_date) {
+        anomalies.push(Anomaly::DateSkew {
+            feed_date,
+            skew: skew.skew,
+            off_date_records: skew.off_date_records,
+        });
+    }
+
+    let counts_today = amc_counts(today);
+    for (amc, &before) in &amc_counts(yesterday) {
+        let after = counts_today.get(amc).copied().unwrap_or(0);
+        let drop_pct = before.saturating_sub(after) as f64 * 100.0 / before as f64;
+        if before >= config.min_amc_records && drop_pct > config.max_amc_drop_pct {
+            anomalies.push(Anomaly::AmcShrank {
+                amc: amc.clone(),
+                yesterday: before,
+                today: after,
+                drop_pct,
+            });
+        }
+    }
+
+    let mut previous: HashMap<SchemeCode, &NavRecord> = HashMap::new();
+    for record in yesterday.iter().filter(|record| record.has_valid_nav()) {
+        previous.entry(record.code).or_insert(record);
+    }
+    let mut stale = Vec::new();
+    for record in today.iter().filter(|record| record.has_valid_nav()) {
+        let old = match previous.get(&record.code) {
+            Some(old) => old,
+            None => continue,
+        };
+        if record.nav == old.nav {
+            let until = today.feed_date.unwrap_or(record.date);
+            let business_days = business_days_between(old.date, until);
+            if business_days >= config.stale_days {
+                stale.push(Anomaly::StaleNav {
+                    code: record.code,
+                    name: record.name.clone(),
+                    amc: record.amc.clone(),
+                    nav: record.nav,
+                    since: old.date,
+                    business_days,
+                });
+            }
+            continue;
+        }
+        let pct_change = (record.nav - old.nav) / old.nav * 100.0;
+        let max_pct = config.max_move_pct_of(record);
+        if pct_change.abs() > max_pct {
+            anomalies.push(Anomaly::NavMove {
+                code: record.code,
+                name: record.name.clone(),
+                amc: record.amc.clone(),
+                category: record.category.clone(),
+                old_nav: old.nav,
+                new_nav: record.nav,
+                pct_change,
+                max_pct,
+            });
+        }
+    }
+    anomalies.extend(stale);
+    anomalies
+}
+
+/// Records of each normalized AMC name
+fn amc_counts(snapshot: &NavSnapshot) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for record in snapshot.iter() {
+        *counts.entry(normalize_amc(&record.amc)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Business days after `from` up to and including `to`
+fn business_days_between(from: NavDate, to: NavDate) -> u32 {
+    let mut days = 0;
+    let mut date = from;
+    while date < to {
+        date = match date.succ() {
+            Some(next) => next,
+            None => break,
+        };
+        if is_business_day(date) {
+            days += 1;
+        }
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NavDate {
+        NavDate::from_ymd(2024, 6, day).unwrap()
+    }
+
+    /// Record of `amc` in `category`, as of `day` of June 2024
+    fn record(code: u32, amc: &str, category: &str, nav: f64, day: u32) -> NavRecord {
+        let mut record = NavRecord::fixture()
+            .with_code(code)
+            .with_nav(nav)
+            .with_date(date(day));
+        record.amc = amc.to_string();
+        record.category = category.to_string();
+        record.scheme = None;
+        record
+    }
+
+    fn snapshot(records: Vec<NavRecord>) -> NavSnapshot {
+        records.into_iter().map(Ok).collect()
+    }
+
+    fn detect(today: Vec<NavRecord>, yesterday: Vec<NavRecord>) -> Vec<Anomaly> {
+        let config = AnomalyConfig {
+            min_amc_records: 2,
+            ..AnomalyConfig::default()
+        };
+        detect_anomalies(&snapshot(today), &snapshot(yesterday), &config)
+    }
+
+    #[test]
+    fn nav_moves_by_category() {
+        let yesterday = vec![
+            record(1, "Axis Mutual Fund", "Liquid Fund", 100.0, 20),
+            record(2, "Axis Mutual Fund", "Small Cap Fund", 100.0, 20),
+            record(3, "Axis Mutual Fund", "Gilt Fund", 100.0, 20),
+        ];
+        let today = vec![
+            record(1, "Axis Mutual Fund", "Liquid Fund", 103.0, 21),
+            record(2, "Axis Mutual Fund", "Small Cap Fund", 91.0, 21),
+            record(3, "Axis Mutual Fund", "Gilt Fund", 106.0, 21),
+        ];
+        let anomalies = detect(today.clone(), yesterday.clone());
+        let moved: Vec<(u32, f64)> = anomalies
+            .iter()
+            .map(|anomaly| match anomaly {
+                Anomaly::NavMove { code, max_pct, .. } => (code.get(), *max_pct),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        // A smallcap fund losing 9% is a bad day, not an error
+        assert_eq!(moved, [(1, 1.0), (3, 5.0)]);
+        match &anomalies[0] {
+            Anomaly::NavMove { pct_change, .. } => assert!((pct_change - 3.0).abs() < 1e-9),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let config = AnomalyConfig {
+            max_move_pct: vec![(SubCategory::SmallCap, 5.0)].into_iter().collect(),
+            ..AnomalyConfig::default()
+        };
+        let anomalies = detect_anomalies(&snapshot(today), &snapshot(yesterday), &config);
+        assert_eq!(anomalies.len(), 3);
+    }
+
+    #[test]
+    fn stale_navs() {
+        // 14 June is a Friday, 17 June a holiday, so 18 to 21 June are 4 business days
+        let yesterday = vec![
+            record(1, "Axis Mutual Fund", "Liquid Fund", 1000.0, 14),
+            record(2, "Axis Mutual Fund", "Liquid Fund", 1000.0, 20),
+            record(3, "Axis Mutual Fund", "Liquid Fund", 1000.0, 19),
+        ];
+        let today = vec![
+            record(1, "Axis Mutual Fund", "Liquid Fund", 1000.0, 14),
+            record(2, "Axis Mutual Fund", "Liquid Fund", 1000.0, 21),
+            record(3, "Axis Mutual Fund", "Liquid Fund", 1000.0, 21),
+        ];
+        let anomalies = detect(today, yesterday);
+        // Its NAV date is off the feed date too
+        assert!(matches!(anomalies[0], Anomaly::DateSkew { skew: 7, .. }));
+        assert_eq!(
+            anomalies[1..],
+            [Anomaly::StaleNav {
+                code: SchemeCode::from(1),
+                name: "Axis Bluechip Fund - Direct Plan - Growth".to_string(),
+                amc: "Axis Mutual Fund".to_string(),
+                nav: 1000.0,
+                since: date(14),
+                business_days: 4,
+            }]
+        );
+        assert_eq!(business_days_between(date(20), date(21)), 1);
+        assert_eq!(business_days_between(date(21), date(21)), 0);
+    }
+
+    #[test]
+    fn shrinking_amcs_and_date_skew() {
+        let axis = |code| record(code, "Axis Mutual Fund", "Large Cap Fund", 10.0, 21);
+        let hdfc = |code| record(code, "HDFC Mutual Fund", "Large Cap Fund", 10.0, 21);
+        let sbi = |code| record(code, "SBI Mutual Fund", "Large Cap Fund", 10.0, 21);
+        let yesterday = vec![axis(1), axis(2), axis(3), hdfc(4), hdfc(5), sbi(6)];
+        let mut today = vec![axis(1), axis(2), sbi(6)];
+        today.extend((10..20).map(sbi));
+        today.push(record(7, "SBI Mutual Fund", "Large Cap Fund", 10.0, 14));
+        let anomalies = detect(today, yesterday);
+        assert_eq!(
+            anomalies[..3],
+            [
+                Anomaly::DateSkew {
+                    feed_date: date(21),
+                    skew: 7,
+                    off_date_records: 1,
+                },
+                Anomaly::AmcShrank {
+                    amc: "Axis".to_string(),
+                    yesterday: 3,
+                    today: 2,
+                    drop_pct: 100.0 / 3.0,
+                },
+                Anomaly::AmcShrank {
+                    amc: "HDFC".to_string(),
+                    yesterday: 2,
+                    today: 0,
+                    drop_pct: 100.0,
+                },
+            ]
+        );
+        assert_eq!(anomalies.len(), 3);
+        assert_eq!(
+            anomalies[1].to_string(),
+            "Axis has 2 records, 3 yesterday (33.3% fewer)"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_with_kind() {
+        let anomaly = Anomaly::AmcShrank {
+            amc: "HDFC".to_string(),
+            yesterday: 2,
+            today: 0,
+            drop_pct: 100.0,
+        };
+        let json = serde_json::to_value(&anomaly).unwrap();
+        assert_eq!(json["kind"], "amc_shrank");
+        assert_eq!(json["yesterday"], 2);
+        assert_eq!(serde_json::from_value::<Anomaly>(json).unwrap(), anomaly);
+    }
+}
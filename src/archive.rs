@@ -0,0 +1,300 @@
+//! Archiving downloaded feeds with a manifest to verify them by later
+
+use crate::download::{download_nav_at, with_suffix};
+use crate::{nav_from_reader, summarize, FetchMeta, NavDate, Result, BASE_URL};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// What a downloaded feed was when archived, kept next to it in `<path>.manifest.json`
+///
+/// See [`download_nav_with_manifest_at`](fn.download_nav_with_manifest_at.html) and
+/// [`verify_archive`](fn.verify_archive.html).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ArchiveManifest {
+    /// SHA-256 of the bytes of the feed, lowercase hex
+    pub sha256: String,
+    /// Size of the feed in bytes
+    pub len: u64,
+    /// URL the feed was downloaded from
+    pub url: String,
+    /// When the download started
+    pub started_at: DateTime<Utc>,
+    /// When the download completed
+    pub fetched_at: DateTime<Utc>,
+    /// Validators and size of the response
+    pub http: FetchMeta,
+    /// Records parsed
+    pub records: usize,
+    /// Parse errors
+    pub errors: usize,
+    /// NAV date of the feed, see
+    /// [`FeedSummary::feed_date`](struct.FeedSummary.html#method.feed_date)
+    pub feed_date: Option<NavDate>,
+}
+
+impl ArchiveManifest {
+    /// Path of the manifest of the feed archived at `path`
+    pub fn path_for<P: AsRef<Path>>(path: P) -> PathBuf {
+        with_suffix(path.as_ref(), ".manifest.json")
+    }
+
+    /// Reads the manifest at `path`, e.g. one of [`path_for`](#method.path_for)
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = io::BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(file).map_err(io::Error::from)?)
+    }
+
+    /// Writes the manifest to `path` as pretty-printed JSON
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::from)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// What [`verify_archive`](fn.verify_archive.html) found of an archived feed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VerifyReport {
+    /// SHA-256 of the file, lowercase hex
+    pub sha256: String,
+    /// Whether it is that of the manifest
+    pub hash_matches: bool,
+    /// Size of the file in bytes
+    pub len: u64,
+    /// Whether it is that of the manifest
+    pub len_matches: bool,
+    /// Records parsed from the file
+    pub records: usize,
+    /// Parse errors
+    pub errors: usize,
+    /// Whether both counts are those of the manifest
+    pub records_match: bool,
+    /// NAV date of the file, see
+    /// [`FeedSummary::feed_date`](struct.FeedSummary.html#method.feed_date)
+    pub feed_date: Option<NavDate>,
+}
+
+impl VerifyReport {
+    /// Whether the file is the feed the manifest was written for
+    pub fn is_intact(&self) -> bool {
+        self.hash_matches && self.len_matches && self.records_match
+    }
+}
+
+/// Downloads the feed of the [AMFI](https://www.amfiindia.com) portal to the file at `path`
+/// with a manifest
+///
+/// See [`download_nav_with_manifest_at`](fn.download_nav_with_manifest_at.html).
+pub fn download_nav_with_manifest<P: AsRef<Path>>(path: P) -> Result<ArchiveManifest> {
+    download_nav_with_manifest_at(BASE_URL, path)
+}
+
+/// Downloads the feed at `url` to the file at `path` as
+/// [`download_nav_at`](fn.download_nav_at.html) does, then writes its manifest to
+/// `<path>.manifest.json`
+///
+/// The file is read back once, hashing and parsing it as it streams. Parse errors don't
+/// fail the download, they are counted in the manifest.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// let manifest = amfi::download_nav_with_manifest("NAVAll.txt")?;
+/// println!("{} records, sha256 {}", manifest.records, manifest.sha256);
+///
+/// let kept = amfi::ArchiveManifest::read(amfi::ArchiveManifest::path_for("NAVAll.txt"))?;
+/// assert!(amfi::verify_archive("NAVAll.txt", &kept)?.is_intact());
+/// # Ok(())
+/// # }
+/// ```
+pub fn download_nav_with_manifest_at<T, P>(url: T, path: P) -> Result<ArchiveManifest>
+where
+    T: AsRef<str>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let started_at = SystemTime::now().into();
+    let http = download_nav_at(url.as_ref(), path)?;
+    let fetched_at = SystemTime::now().into();
+    let digest = FileDigest::of(path)?;
+    let manifest = ArchiveManifest {
+        sha256: digest.sha256,
+        len: digest.len,
+        url: url.as_ref().to_string(),
+        started_at,
+        fetched_at,
+        http,
+        records: digest.records,
+        errors: digest.errors,
+        feed_date: digest.feed_date,
+    };
+    manifest.write(ArchiveManifest::path_for(path))?;
+    Ok(manifest)
+}
+
+/// Checks the feed archived at `path` against its `manifest`, hashing and parsing it again
+///
+/// A feed changed on disk fails to hash to the manifest's; the record counts tell whether
+/// the change lost records. Fails only if the file can't be read.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// let path = "archive/NAVAll-2024-06-21.txt";
+/// let manifest = amfi::ArchiveManifest::read(amfi::ArchiveManifest::path_for(path))?;
+/// let report = amfi::verify_archive(path, &manifest)?;
+/// if !report.hash_matches {
+///     eprintln!("{} changed, {} of {} records", path, report.records, manifest.records);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn verify_archive<P: AsRef<Path>>(path: P, manifest: &ArchiveManifest) -> Result<VerifyReport> {
+    let digest = FileDigest::of(path.as_ref())?;
+    Ok(VerifyReport {
+        hash_matches: digest.sha256.eq_ignore_ascii_case(&manifest.sha256),
+        len_matches: digest.len == manifest.len,
+        records_match: (digest.records, digest.errors) == (manifest.records, manifest.errors),
+        sha256: digest.sha256,
+        len: digest.len,
+        records: digest.records,
+        errors: digest.errors,
+        feed_date: digest.feed_date,
+    })
+}
+
+/// Hash and record counts of a feed file
+struct FileDigest {
+    sha256: String,
+    len: u64,
+    records: usize,
+    errors: usize,
+    feed_date: Option<NavDate>,
+}
+
+impl FileDigest {
+    /// Digest of the file at `path`, read once
+    fn of(path: &Path) -> Result<Self> {
+        let mut reader = HashingReader {
+            inner: File::open(path)?,
+            hasher: Sha256::new(),
+            len: 0,
+        };
+        let summary = summarize(nav_from_reader(&mut reader));
+        // Parsing may end before the file does
+        io::copy(&mut reader, &mut io::sink())?;
+        Ok(FileDigest {
+            sha256: format!("{:x}", reader.hasher.finalize()),
+            len: reader.len,
+            records: summary.records,
+            errors: summary.errors,
+            feed_date: summary.feed_date(),
+        })
+    }
+}
+
+/// Reader hashing the bytes read through it
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    len: u64,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.len += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    const FEED: &[u8] = include_bytes!("../fixtures/NAVOpen.txt");
+
+    /// Serves the feed once, returning its URL
+    fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/NAVAll.txt", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+            let mut response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"a\"\r\nConnection: close\r\n\r\n",
+                FEED.len()
+            )
+            .into_bytes();
+            response.extend_from_slice(FEED);
+            stream.write_all(&response).unwrap();
+        });
+        url
+    }
+
+    fn target(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("amfi-archive-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn pristine_archives_verify() {
+        let path = target("pristine.txt");
+        let url = serve();
+        let manifest = download_nav_with_manifest_at(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), FEED);
+        assert_eq!(manifest.sha256, format!("{:x}", Sha256::digest(FEED)));
+        assert_eq!((manifest.len, manifest.records), (FEED.len() as u64, 30));
+        assert_eq!(manifest.feed_date, NavDate::from_ymd(2024, 6, 21));
+        assert_eq!(manifest.http.etag.as_deref(), Some("\"a\""));
+        assert_eq!(manifest.url, url);
+        assert!(manifest.started_at <= manifest.fetched_at);
+
+        let kept = ArchiveManifest::read(ArchiveManifest::path_for(&path)).unwrap();
+        assert_eq!(kept, manifest);
+        let report = verify_archive(&path, &kept).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.sha256, manifest.sha256);
+        assert_eq!(report.feed_date, manifest.feed_date);
+    }
+
+    #[test]
+    fn flipped_bytes_are_caught() {
+        let path = target("flipped.txt");
+        let url = serve();
+        let manifest = download_nav_with_manifest_at(&url, &path).unwrap();
+
+        // A digit of a NAV changes the hash only
+        let text = String::from_utf8(FEED.to_vec()).unwrap();
+        let nav = text.find(";21-Jun-2024").unwrap() - 1;
+        let mut flipped = FEED.to_vec();
+        flipped[nav] = if flipped[nav] == b'9' { b'8' } else { b'9' };
+        fs::write(&path, &flipped).unwrap();
+        let report = verify_archive(&path, &manifest).unwrap();
+        assert!(!report.hash_matches);
+        assert!(report.len_matches && report.records_match);
+        assert!(!report.is_intact());
+
+        // A date no longer a date loses its record
+        let date = text.find("21-Jun-2024").unwrap();
+        flipped[date] = b'x';
+        fs::write(&path, &flipped).unwrap();
+        let report = verify_archive(&path, &manifest).unwrap();
+        assert!(!report.hash_matches && !report.records_match);
+        assert_eq!((report.records, report.errors), (29, 1));
+    }
+}
@@ -0,0 +1,394 @@
+//! Downloading the feeds of a range of past days to a directory
+
+use crate::download::with_suffix;
+use crate::fetch::{get_history_body, HISTORY_URL};
+use crate::{
+    is_business_day, DecodePolicy, Error, NavDate, NavRecordIterator, NavSnapshot, RateLimiter,
+    Result, SnapshotSource,
+};
+use reqwest::StatusCode;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Settings of [`backfill`](fn.backfill.html)
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// use std::time::Duration;
+///
+/// let limiter = amfi::RateLimiter::new(Duration::from_secs(10)).jitter(Duration::from_secs(5));
+/// let options = amfi::BackfillOptions::new(limiter)
+///     .retries(5)
+///     .retry_delay(Duration::from_secs(60));
+/// let from = amfi::NavDate::from_ymd(2024, 1, 1).unwrap();
+/// let to = amfi::NavDate::from_ymd(2024, 3, 31).unwrap();
+/// let report = amfi::backfill("archive", from, to, &options)?;
+/// for (date, error) in report.failed() {
+///     eprintln!("{}: {}", date, error);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BackfillOptions {
+    url: String,
+    rate_limiter: RateLimiter,
+    retries: u32,
+    retry_delay: Duration,
+}
+
+impl BackfillOptions {
+    /// Downloads from the history report of the AMFI portal, each request waiting for
+    /// `limiter`, retrying failed days 3 times from 30 seconds on
+    ///
+    /// The limiter is required: a backfill makes a request per business day of its range,
+    /// which the portal must not get in a burst.
+    pub fn new(limiter: RateLimiter) -> Self {
+        BackfillOptions {
+            url: HISTORY_URL.to_string(),
+            rate_limiter: limiter,
+            retries: 3,
+            retry_delay: Duration::from_secs(30),
+        }
+    }
+
+    /// Downloads from `url` instead of the AMFI portal, queried as `<url>?frmdt=21-Jun-2024`
+    pub fn url<T: Into<String>>(mut self, url: T) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Retries the download of a day `n` times when the request fails, or the server answers
+    /// with a `5xx` or `429` status
+    pub fn retries(mut self, n: u32) -> Self {
+        self.retries = n;
+        self
+    }
+
+    /// Waits `delay` before the first retry of a day, twice as long before each next one, on
+    /// top of the rate limiter
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// URL of the feed of `date`
+    fn url_of(&self, date: NavDate) -> String {
+        format!("{}?frmdt={}", self.url, date.feed_format())
+    }
+}
+
+/// What [`backfill`](fn.backfill.html) did for a day
+#[derive(Debug)]
+pub enum BackfillOutcome {
+    /// Downloaded and written
+    Fetched {
+        /// Records parsed
+        records: usize,
+        /// Parse errors
+        errors: usize,
+    },
+    /// Already in the directory, not downloaded again
+    Skipped,
+    /// No business day, see [`is_business_day`](fn.is_business_day.html), not downloaded
+    Holiday,
+    /// The portal has no records for the day, nothing written
+    NoData,
+    /// Download failed, after retries if it could be retried
+    Failed(Error),
+}
+
+/// Outcome of every day of a [`backfill`](fn.backfill.html)
+#[derive(Debug, Default)]
+pub struct BackfillReport {
+    /// Days of the range in order, with what was done for each
+    pub days: Vec<(NavDate, BackfillOutcome)>,
+}
+
+impl BackfillReport {
+    /// Days downloaded and written
+    pub fn fetched(&self) -> usize {
+        self.days
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, BackfillOutcome::Fetched { .. }))
+            .count()
+    }
+
+    /// Days whose download failed, with the error
+    pub fn failed(&self) -> Vec<(NavDate, &Error)> {
+        self.days
+            .iter()
+            .filter_map(|(date, outcome)| match outcome {
+                BackfillOutcome::Failed(error) => Some((*date, error)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether no download failed, so that running again only retries days without data
+    pub fn is_complete(&self) -> bool {
+        self.failed().is_empty()
+    }
+}
+
+/// Downloads the feed of every business day from `from` to `to`, both included, to `dir`
+///
+/// Each day is fetched from the history report for all fund houses and kept as published in
+/// `NAV-<date>.txt` and as a snapshot in `NAV-<date>.snapshot`, see
+/// [`NavSnapshot::load`](struct.NavSnapshot.html#method.load), `<date>` being e.g.
+/// `2024-06-21`. Files are written whole or not at all, the snapshot last, so a day with a
+/// snapshot is complete: days already there are skipped, and a backfill cut short resumes
+/// where it stopped when run again. Days without records or failing are retried by the next
+/// run.
+///
+/// Requests wait for the rate limiter of `options`; failed ones are retried as set there.
+/// A day failing after its retries is reported and the backfill goes on. Fails, leaving the
+/// days not yet done, only if `dir` can't be created or written to.
+pub fn backfill<P: AsRef<Path>>(
+    dir: P,
+    from: NavDate,
+    to: NavDate,
+    options: &BackfillOptions,
+) -> Result<BackfillReport> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut report = BackfillReport::default();
+    let mut day = Some(from);
+    while let Some(date) = day.filter(|&date| date <= to) {
+        let outcome = if !is_business_day(date) {
+            BackfillOutcome::Holiday
+        } else if day_path(dir, date, "snapshot").exists() {
+            BackfillOutcome::Skipped
+        } else {
+            backfill_day(dir, date, options)?
+        };
+        trace_event!(info, %date, outcome = ?outcome, "backfilled day");
+        report.days.push((date, outcome));
+        day = date.succ();
+    }
+    Ok(report)
+}
+
+/// Downloads and writes the feed of `date`, failing only if writing does
+fn backfill_day(dir: &Path, date: NavDate, options: &BackfillOptions) -> Result<BackfillOutcome> {
+    let url = options.url_of(date);
+    let mut delay = options.retry_delay;
+    let mut attempt = 0;
+    let body = loop {
+        options.rate_limiter.wait();
+        match get_history_body(&url) {
+            Err(error) if attempt < options.retries && is_transient(&error) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(error) => return Ok(BackfillOutcome::Failed(error)),
+            Ok(None) => return Ok(BackfillOutcome::NoData),
+            Ok(Some(body)) => break body,
+        }
+    };
+    let (body, charset) = body;
+    let mut records = NavRecordIterator::with_charset(Cursor::new(&body[..]), charset)
+        .decode(DecodePolicy::Lossy);
+    if let Err(error) = records.check_content(&url) {
+        return Ok(BackfillOutcome::Failed(error));
+    }
+    let source = SnapshotSource::Url {
+        url,
+        meta: Default::default(),
+        response: None,
+    };
+    let snapshot = NavSnapshot::from_results(records, source, SystemTime::now().into());
+
+    write_whole(&day_path(dir, date, "txt"), |path| {
+        fs::write(path, &body).map_err(Error::from)
+    })?;
+    write_whole(&day_path(dir, date, "snapshot"), |path| snapshot.save(path))?;
+    Ok(BackfillOutcome::Fetched {
+        records: snapshot.records.len(),
+        errors: snapshot.errors.len(),
+    })
+}
+
+/// Whether a download failing with `error` may succeed when tried again
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::ReqwestError(..) | Error::IoError(..) => true,
+        Error::HttpError { status, .. } => {
+            status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => false,
+    }
+}
+
+/// File of the feed of `date` in `dir`, with `extension`
+fn day_path(dir: &Path, date: NavDate, extension: &str) -> PathBuf {
+    dir.join(format!("NAV-{}.{}", date, extension))
+}
+
+/// Writes the file at `path` with `write` to `<path>.part`, then renames it
+fn write_whole<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    let part = with_suffix(path, ".part");
+    write(&part)?;
+    fs::rename(&part, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    const FEED: &str = include_str!("../fixtures/NAVOpen.txt");
+
+    /// Serves responses by the `frmdt` of the requests, `503` the first time for dates in
+    /// `flaky`, returning the server's URL and the number of requests per date
+    fn serve(flaky: &'static [&'static str]) -> (String, Arc<Mutex<HashMap<String, usize>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/history", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(HashMap::new()));
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                let date = request
+                    .split("frmdt=")
+                    .nth(1)
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .unwrap_or("")
+                    .to_string();
+                let count = {
+                    let mut log = log.lock().unwrap();
+                    let count = log.entry(date.clone()).or_insert(0);
+                    *count += 1;
+                    *count
+                };
+                let (status, body) = if flaky.contains(&date.as_str()) && count == 1 {
+                    ("503 Service Unavailable", "Try again".to_string())
+                } else if date == "21-Jun-2024" {
+                    ("200 OK", "No Record Found\r\n".to_string())
+                } else {
+                    ("200 OK", FEED.replace("21-Jun-2024", &date))
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (url, requests)
+    }
+
+    fn target(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("amfi-backfill-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn options(url: &str) -> BackfillOptions {
+        BackfillOptions::new(RateLimiter::new(Duration::from_millis(1)))
+            .url(url)
+            .retry_delay(Duration::from_millis(1))
+    }
+
+    fn outcomes(report: &BackfillReport) -> Vec<(String, &'static str)> {
+        report
+            .days
+            .iter()
+            .map(|(date, outcome)| {
+                let outcome = match outcome {
+                    BackfillOutcome::Fetched { .. } => "fetched",
+                    BackfillOutcome::Skipped => "skipped",
+                    BackfillOutcome::Holiday => "holiday",
+                    BackfillOutcome::NoData => "no data",
+                    BackfillOutcome::Failed(..) => "failed",
+                };
+                (date.to_string(), outcome)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn backfills_and_resumes() {
+        let (url, requests) = serve(&["24-Jun-2024"]);
+        let dir = target("resumes");
+        let from = NavDate::from_ymd(2024, 6, 20).unwrap();
+        let to = NavDate::from_ymd(2024, 6, 24).unwrap();
+
+        let report = backfill(&dir, from, to, &options(&url)).unwrap();
+        let expected = [
+            ("2024-06-20", "fetched"),
+            ("2024-06-21", "no data"),
+            ("2024-06-22", "holiday"),
+            ("2024-06-23", "holiday"),
+            ("2024-06-24", "fetched"),
+        ];
+        let expected: Vec<_> = expected.iter().map(|&(d, o)| (d.to_string(), o)).collect();
+        assert_eq!(outcomes(&report), expected);
+        assert!(report.is_complete());
+        assert_eq!(report.fetched(), 2);
+        match report.days[0].1 {
+            BackfillOutcome::Fetched { records, errors } => assert_eq!((records, errors), (30, 0)),
+            ref other => panic!("{:?}", other),
+        }
+        // Retried once after the 503
+        assert_eq!(requests.lock().unwrap()["24-Jun-2024"], 2);
+
+        let raw = fs::read_to_string(dir.join("NAV-2024-06-24.txt")).unwrap();
+        assert_eq!(raw, FEED.replace("21-Jun-2024", "24-Jun-2024"));
+        let snapshot = NavSnapshot::load(dir.join("NAV-2024-06-24.snapshot")).unwrap();
+        assert_eq!(snapshot.feed_date, NavDate::from_ymd(2024, 6, 24));
+        assert_eq!(snapshot.len(), 30);
+        assert!(!dir.join("NAV-2024-06-21.txt").exists());
+        assert!(!dir.join("NAV-2024-06-24.snapshot.part").exists());
+
+        // Days written are skipped, days without data asked again
+        let report = backfill(&dir, from, to, &options(&url)).unwrap();
+        let outcomes = outcomes(&report);
+        assert_eq!(outcomes[0].1, "skipped");
+        assert_eq!(outcomes[1].1, "no data");
+        assert_eq!(outcomes[4].1, "skipped");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests["20-Jun-2024"], 1);
+        assert_eq!(requests["21-Jun-2024"], 2);
+    }
+
+    #[test]
+    fn failed_days_are_reported() {
+        let (url, requests) = serve(&["20-Jun-2024"]);
+        let dir = target("fails");
+        let day = NavDate::from_ymd(2024, 6, 20).unwrap();
+
+        let report = backfill(&dir, day, day, &options(&url).retries(0)).unwrap();
+        let failed = report.failed();
+        assert_eq!(failed.len(), 1);
+        assert!(matches!(failed[0].1, Error::HttpError { status, .. } if *status == 503));
+        assert!(!report.is_complete());
+        assert!(!dir.join("NAV-2024-06-20.snapshot").exists());
+
+        // The next run retries it
+        let report = backfill(&dir, day, day, &options(&url).retries(0)).unwrap();
+        assert_eq!(report.fetched(), 1);
+        assert_eq!(requests.lock().unwrap()["20-Jun-2024"], 2);
+    }
+}
@@ -0,0 +1,351 @@
+//! Downloading several feeds at once into one snapshot
+
+use crate::fetch::get_records_async;
+use crate::{
+    Error, FetchOptions, NavRecord, NavSnapshot, Provenance, RecordSource, Result, SnapshotSource,
+    SourceConflict, SourceRegistry, Sourced,
+};
+use chrono::{DateTime, Utc};
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::SystemTime;
+
+/// Downloads and parses the feeds at `urls` concurrently, merging their records into one
+/// snapshot
+///
+/// Feeds are downloaded as by [`daily_nav_with_async`](fn.daily_nav_with_async.html) with
+/// `options`, whose URL is ignored, at most
+/// [`concurrency`](struct.FetchOptions.html#method.concurrency) at once, e.g. the open and
+/// close ended feeds or the chunks of a long history. Records are sorted by scheme code, then
+/// date; of records with the same code and date only the first, in the order of `urls`, is
+/// kept. The snapshot's [`provenance`](struct.NavSnapshot.html#structfield.provenance) tells
+/// the URL of each record, and the duplicates dropped whose NAV differs from the one kept.
+///
+/// A feed that can't be downloaded doesn't fail the others: its error is appended to the
+/// snapshot's `errors` after the parse errors, which are all wrapped in
+/// [`Error::InUrl`](enum.Error.html#variant.InUrl) with the URL of their feed. The snapshot's
+/// `stats` count the parse errors only. Fails with the error of the first URL if none could be
+/// downloaded.
+///
+/// ```rust,no_run
+/// # async fn run() -> Result<(), amfi::Error> {
+/// let urls = [
+///     "https://portal.amfiindia.com/spages/NAVOpen.txt",
+///     "https://portal.amfiindia.com/spages/NAVClose.txt",
+/// ];
+/// let options = amfi::FetchOptions::new().concurrency(2);
+/// let snapshot = amfi::fetch_all_async(&urls, &options).await?;
+/// for error in &snapshot.errors {
+///     eprintln!("{}", error);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn fetch_all_async<T: AsRef<str>>(
+    urls: &[T],
+    options: &FetchOptions,
+) -> Result<NavSnapshot> {
+    let fetched_at = SystemTime::now().into();
+    let downloads = urls.iter().map(|url| async move {
+        let url = url.as_ref();
+        let records = get_records_async(url, options.rate_limiter.as_ref(), options.sniff_content)
+            .await
+            .map(Iterator::collect);
+        (url.to_string(), records)
+    });
+    let fetched = join_bounded(downloads.collect(), options.concurrency).await;
+    merge(fetched, fetched_at)
+}
+
+/// Snapshot of the records of `fetched` feeds, each with its URL, read at `fetched_at`
+fn merge(
+    fetched: Vec<(String, Result<Vec<Result<NavRecord>>>)>,
+    fetched_at: DateTime<Utc>,
+) -> Result<NavSnapshot> {
+    let mut urls = Vec::new();
+    let mut registry = SourceRegistry::new();
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    let mut failures = Vec::new();
+    for (url, feed) in fetched {
+        match feed {
+            Ok(feed) => {
+                let source_id = registry.register(RecordSource::Url(url.clone()));
+                for result in feed {
+                    match result {
+                        Ok(record) => records.push(Sourced { source_id, record }),
+                        Err(error) => errors.push(Error::InUrl {
+                            url: url.clone(),
+                            error: Box::new(error),
+                        }),
+                    }
+                }
+                urls.push(url);
+            }
+            Err(error) => failures.push(Error::InUrl {
+                url,
+                error: Box::new(error),
+            }),
+        }
+    }
+    if urls.is_empty() && !failures.is_empty() {
+        return Err(failures.remove(0));
+    }
+
+    // Stable, so the first of duplicates stays first
+    records.sort_by_key(|sourced| (sourced.record.code, sourced.record.date));
+    let mut conflicts = Vec::new();
+    records.dedup_by(|later, first| {
+        let duplicate =
+            later.record.code == first.record.code && later.record.date == first.record.date;
+        if duplicate {
+            conflicts.extend(SourceConflict::of(first, later));
+        }
+        duplicate
+    });
+    let sources = records.iter().map(|sourced| sourced.source_id).collect();
+    let results = records
+        .into_iter()
+        .map(|sourced| Ok(sourced.record))
+        .chain(errors.into_iter().map(Err));
+    let mut snapshot = NavSnapshot::from_results(results, SnapshotSource::Urls(urls), fetched_at);
+    snapshot.errors.extend(failures);
+    snapshot.provenance = Some(Provenance {
+        registry,
+        sources,
+        conflicts,
+    });
+    Ok(snapshot)
+}
+
+/// Outputs of `futures`, in order, polling at most `limit` of them at once
+///
+/// Futures run on the task awaiting this one rather than being spawned, so no particular
+/// runtime is needed.
+async fn join_bounded<F: Future>(futures: Vec<F>, limit: usize) -> Vec<F::Output> {
+    let limit = limit.max(1);
+    let mut pending: Vec<Option<Pin<Box<F>>>> = futures
+        .into_iter()
+        .map(|future| Some(Box::pin(future)))
+        .collect();
+    let mut outputs: Vec<Option<F::Output>> = pending.iter().map(|_| None).collect();
+    poll_fn(|cx| {
+        // The first futures not done are those started, as each is done in turn
+        let mut running = 0;
+        for (slot, output) in pending.iter_mut().zip(outputs.iter_mut()) {
+            if running == limit {
+                break;
+            }
+            if let Some(future) = slot {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(done) => {
+                        *output = Some(done);
+                        *slot = None;
+                    }
+                    Poll::Pending => running += 1,
+                }
+            }
+        }
+        if running == 0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+    outputs
+        .into_iter()
+        .map(|output| output.expect("every future is done"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav_from_file;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::task::Context;
+
+    /// Future pending `polls` times, counting those running alongside it in `running`
+    struct Counted {
+        polls: usize,
+        started: bool,
+        running: Rc<Cell<usize>>,
+        most: Rc<Cell<usize>>,
+        output: usize,
+    }
+
+    impl Future for Counted {
+        type Output = usize;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+            if !self.started {
+                self.started = true;
+                self.running.set(self.running.get() + 1);
+                self.most.set(self.most.get().max(self.running.get()));
+            }
+            if self.polls == 0 {
+                self.running.set(self.running.get() - 1);
+                return Poll::Ready(self.output);
+            }
+            self.polls -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[tokio::test]
+    async fn bounds_concurrency() {
+        let running = Rc::new(Cell::new(0));
+        let most = Rc::new(Cell::new(0));
+        let futures: Vec<Counted> = (0..10)
+            .map(|output| Counted {
+                polls: (output * 7) % 4,
+                started: false,
+                running: running.clone(),
+                most: most.clone(),
+                output,
+            })
+            .collect();
+        let outputs = join_bounded(futures, 3).await;
+        assert_eq!(outputs, (0..10).collect::<Vec<_>>());
+        assert_eq!(most.get(), 3);
+        assert_eq!(running.get(), 0);
+
+        assert!(join_bounded(Vec::<Counted>::new(), 3).await.is_empty());
+    }
+
+    fn feed(name: &str) -> Result<Vec<Result<NavRecord>>> {
+        let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        Ok(nav_from_file(path)?.collect())
+    }
+
+    fn refused() -> Result<Vec<Result<NavRecord>>> {
+        Err(Error::UnexpectedContent {
+            first_line: "<html>".to_string(),
+            url: None,
+        })
+    }
+
+    #[test]
+    fn merges_sources() {
+        let fetched = vec![
+            (
+                "https://a/NAVHistory.txt".to_string(),
+                feed("NAVHistory.txt"),
+            ),
+            ("https://b/NAVOpen.txt".to_string(), feed("NAVOpen.txt")),
+            ("https://c/NAVOpen.txt".to_string(), refused()),
+            ("https://d/NAVOpen.txt".to_string(), feed("NAVOpen.txt")),
+        ];
+        let snapshot = merge(fetched, SystemTime::now().into()).unwrap();
+
+        // Records of NAVOpen.txt also in NAVHistory.txt are those of the first
+        let history: Vec<NavRecord> = feed("NAVHistory.txt")
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        let open: Vec<NavRecord> = feed("NAVOpen.txt").unwrap().into_iter().flatten().collect();
+        let mut keys: Vec<_> = history
+            .iter()
+            .chain(&open)
+            .map(|record| (record.code, record.date))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        let merged: Vec<_> = snapshot.iter().map(|r| (r.code, r.date)).collect();
+        assert_eq!(merged, keys);
+        assert!(merged.len() < history.len() + open.len());
+        for record in &history {
+            assert!(snapshot.contains(record));
+        }
+        assert_eq!(snapshot.stats.records, merged.len());
+
+        // The garbage line of the history, then the refused source
+        assert_eq!(snapshot.stats.errors, 1);
+        match &snapshot.errors[..] {
+            [Error::InUrl { url, .. }, Error::InUrl { url: failed, error }] => {
+                assert_eq!(url, "https://a/NAVHistory.txt");
+                assert_eq!(failed, "https://c/NAVOpen.txt");
+                assert!(matches!(**error, Error::UnexpectedContent { .. }));
+            }
+            other => panic!("{:?}", other),
+        }
+        match &snapshot.source {
+            SnapshotSource::Urls(urls) => assert_eq!(urls.len(), 3),
+            other => panic!("{:?}", other),
+        }
+
+        // The second copy of NAVOpen.txt is never a source
+        let provenance = snapshot.provenance.as_ref().unwrap();
+        assert_eq!(provenance.registry.len(), 3);
+        assert_eq!(provenance.sources.len(), snapshot.len());
+        for (index, record) in snapshot.iter().enumerate() {
+            let source = snapshot.source_of(index).unwrap().to_string();
+            if history.contains(record) {
+                assert_eq!(source, "https://a/NAVHistory.txt");
+            } else {
+                assert_eq!(source, "https://b/NAVOpen.txt");
+            }
+        }
+        assert_eq!(snapshot.source_of(snapshot.len()), None);
+    }
+
+    #[test]
+    fn reports_conflicting_sources() {
+        let mut revised = feed("NAVOpen.txt").unwrap();
+        if let Some(Ok(record)) = revised.first_mut() {
+            record.nav += 1.0;
+        }
+        let revised_nav = revised[0].as_ref().unwrap().nav;
+        let fetched = vec![
+            ("https://a/NAVOpen.txt".to_string(), feed("NAVOpen.txt")),
+            ("https://b/NAVOpen.txt".to_string(), Ok(revised)),
+        ];
+        let snapshot = merge(fetched, SystemTime::now().into()).unwrap();
+        let provenance = snapshot.provenance.unwrap();
+        match &provenance.conflicts[..] {
+            [conflict] => {
+                assert_eq!(conflict.kept.record.nav + 1.0, revised_nav);
+                assert_eq!(conflict.dropped.record.nav, revised_nav);
+                let dropped = provenance.registry.get(conflict.dropped.source_id);
+                assert_eq!(
+                    dropped,
+                    Some(&RecordSource::Url("https://b/NAVOpen.txt".to_string()))
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(provenance.sources.iter().all(|&id| id.index() == 0));
+    }
+
+    #[test]
+    fn fails_when_every_source_fails() {
+        let fetched = vec![
+            ("https://a/".to_string(), refused()),
+            ("https://b/".to_string(), refused()),
+        ];
+        match merge(fetched, SystemTime::now().into()) {
+            Err(Error::InUrl { url, .. }) => assert_eq!(url, "https://a/"),
+            other => panic!("{:?}", other),
+        }
+        assert!(merge(Vec::new(), SystemTime::now().into())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_unreachable_sources() {
+        let urls = [
+            "http://127.0.0.1:1/NAVOpen.txt",
+            "http://127.0.0.1:1/NAVClose.txt",
+        ];
+        match fetch_all_async(&urls, &FetchOptions::new().concurrency(2)).await {
+            Err(Error::InUrl { url, .. }) => assert_eq!(url, urls[0]),
+            other => panic!("{:?}", other),
+        }
+    }
+}
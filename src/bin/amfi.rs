@@ -0,0 +1,348 @@
+//! Command line interface to the `amfi` library
+//!
+//! Built with the `cli` feature: `cargo install amfi --features cli`.
+
+use amfi::{
+    AmcProgress, Column, Error, FundPlan, NavIndex, NavRecord, NavRecordIterator, NavWriter,
+    ParseStats,
+};
+use std::cell::RefCell;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::process;
+use std::rc::Rc;
+
+const USAGE: &str = "\
+Usage: amfi [OPTIONS] <COMMAND>
+
+Commands:
+  fetch [INPUT]       Write records in NAVAll format, as a table on a terminal
+  json [INPUT]        Convert to JSON Lines
+  csv [INPUT]         Convert to CSV
+  filter [INPUT]      Print records as a table
+  diff OLD NEW        Print NAV changes between two feeds
+
+INPUT is a URL or a file path, the AMFI portal by default.
+
+Options:
+  --amc TEXT          Only AMCs containing TEXT, repeatable
+  --category TEXT     Only categories containing TEXT, repeatable
+  --plan PLAN         Only `direct` or `regular` plans
+  -o, --output FILE   Write to FILE instead of stdout
+  -q, --quiet         Do not report parse errors on stderr
+  --table             Write `fetch` records as a table to any output
+  --stats             Print record and error counts and throughput on stderr
+  --progress          Print each AMC as its records are read on stderr
+  -h, --help          Print this help
+
+Exit codes:
+  0  success
+  1  I/O or other error
+  2  invalid arguments
+  3  network failure
+  4  HTTP error status or error page
+  5  some lines failed to parse";
+
+/// Process exit codes
+mod exit {
+    pub const ERROR: i32 = 1;
+    pub const USAGE: i32 = 2;
+    pub const NETWORK: i32 = 3;
+    pub const HTTP: i32 = 4;
+    pub const PARSE: i32 = 5;
+}
+
+type Records = Box<dyn Iterator<Item = Result<NavRecord, Error>>>;
+
+#[derive(Debug, Default)]
+struct Options {
+    command: String,
+    inputs: Vec<String>,
+    amc: Vec<String>,
+    category: Vec<String>,
+    plan: Option<FundPlan>,
+    output: Option<String>,
+    quiet: bool,
+    stats: bool,
+    progress: bool,
+    table: bool,
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("{} requires a value", name))
+            };
+            match arg.as_str() {
+                "--amc" => options.amc.push(value(&arg)?),
+                "--category" => options.category.push(value(&arg)?),
+                "--plan" => {
+                    let plan = value(&arg)?;
+                    options.plan = Some(plan.parse().map_err(|err| format!("{}", err))?);
+                }
+                "-o" | "--output" => options.output = Some(value(&arg)?),
+                "-q" | "--quiet" => options.quiet = true,
+                "--stats" => options.stats = true,
+                "--progress" => options.progress = true,
+                "--table" => options.table = true,
+                "-h" | "--help" => return Err(String::new()),
+                _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+                _ => positional.push(arg),
+            }
+        }
+        let mut positional = positional.into_iter();
+        options.command = positional
+            .next()
+            .ok_or_else(|| "missing command".to_string())?;
+        options.inputs = positional.collect();
+        let inputs = match options.command.as_str() {
+            "fetch" | "json" | "csv" | "filter" => 0..=1,
+            "diff" => 2..=2,
+            command => return Err(format!("unknown command {}", command)),
+        };
+        if !inputs.contains(&options.inputs.len()) {
+            return Err(format!("wrong number of inputs for {}", options.command));
+        }
+        Ok(options)
+    }
+
+    fn filter<R: Read>(&self, mut records: NavRecordIterator<R>) -> NavRecordIterator<R> {
+        for amc in &self.amc {
+            records = records.filter_amc(amc);
+        }
+        for category in &self.category {
+            records = records.filter_category(category);
+        }
+        match self.plan {
+            Some(plan) => records.filter_plan(plan),
+            None => records,
+        }
+    }
+
+    /// Parses `input`, the AMFI portal if `None`, adding its statistics to `parses` once read
+    fn open(&self, input: Option<&String>, parses: &Parses) -> Result<Records, Error> {
+        Ok(match input {
+            None => self.measured(self.filter(amfi::daily_nav()?), None, parses),
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                self.measured(self.filter(amfi::nav_from_url(url)?), None, parses)
+            }
+            Some(path) => {
+                // Files are cheap to read twice, downloads are not
+                let amcs = if self.progress {
+                    Some(amfi::amc_list(File::open(path)?)?.len())
+                } else {
+                    None
+                };
+                self.measured(self.filter(amfi::nav_from_file(path)?), amcs, parses)
+            }
+        })
+    }
+
+    /// `records`, adding their statistics to `parses` once read to the end, and printing
+    /// progress out of `amcs` if asked to
+    fn measured<R: Read + 'static>(
+        &self,
+        records: NavRecordIterator<R>,
+        amcs: Option<usize>,
+        parses: &Parses,
+    ) -> Records {
+        let parses = Rc::clone(parses);
+        let mut done = false;
+        let progress = self.progress;
+        let records = records.on_amc_done(move |amc| {
+            if progress {
+                eprintln!("{}", progress_line(amc));
+            }
+        });
+        let mut records = match amcs {
+            Some(total) => records.amc_total(total),
+            None => records,
+        };
+        Box::new(std::iter::from_fn(move || {
+            let item = records.next();
+            if item.is_none() && !done {
+                done = true;
+                parses.borrow_mut().push(records.get_ref().stats());
+            }
+            item
+        }))
+    }
+
+    fn output(&self) -> Result<Box<dyn Write>, Error> {
+        Ok(match self.output {
+            Some(ref path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(BufWriter::new(io::stdout())),
+        })
+    }
+}
+
+/// Statistics of the inputs read to their end
+type Parses = Rc<RefCell<Vec<ParseStats>>>;
+
+/// Line printed by `--progress` as the section of an AMC ends
+fn progress_line(amc: &AmcProgress) -> String {
+    let of = match amc.total {
+        Some(total) => format!("{} of {}", amc.index, total),
+        None => amc.index.to_string(),
+    };
+    format!(
+        "finished {} ({} AMCs done): {} records, {} in all",
+        amc.amc, of, amc.records, amc.total_records
+    )
+}
+
+/// Counts records and reports parse errors
+#[derive(Debug, Default)]
+struct Report {
+    quiet: bool,
+    records: usize,
+    errors: usize,
+    parses: Parses,
+}
+
+impl Report {
+    fn track(&mut self, records: Records) -> impl Iterator<Item = Result<NavRecord, Error>> + '_ {
+        records.inspect(move |result| match result {
+            Ok(_) => self.records += 1,
+            Err(error) => {
+                self.errors += 1;
+                if !self.quiet {
+                    eprintln!("{}", error);
+                }
+            }
+        })
+    }
+}
+
+fn run(options: &Options, report: &mut Report) -> Result<(), Error> {
+    // Open inputs first so a failed download does not leave an empty output file behind
+    let mut inputs = Vec::new();
+    for index in 0..options.inputs.len().max(1) {
+        inputs.push(options.open(options.inputs.get(index), &report.parses)?);
+    }
+    let mut inputs = inputs.into_iter();
+    let records = inputs.next().expect("at least one input");
+    let mut out = options.output()?;
+    match options.command.as_str() {
+        "fetch" if options.table || options.output.is_none() && io::stdout().is_terminal() => {
+            let records: Vec<NavRecord> = report.track(records).filter_map(Result::ok).collect();
+            amfi::render_table(&records, Column::DEFAULT, &mut out)?;
+        }
+        "fetch" => {
+            let mut writer = NavWriter::new(out);
+            for record in report.track(records).filter_map(Result::ok) {
+                writer.write_record(&record)?;
+            }
+            out = writer.finish()?;
+        }
+        "json" => {
+            amfi::write_jsonl(report.track(records), &mut out)?;
+        }
+        "csv" => {
+            amfi::write_csv_stream(report.track(records), &mut out)?;
+        }
+        "filter" => {
+            for record in report.track(records).filter_map(Result::ok) {
+                writeln!(
+                    out,
+                    "{:>8}  {:>12.4}  {}  {}",
+                    record.code.to_string(),
+                    record.nav,
+                    record.date,
+                    record.name
+                )?;
+            }
+        }
+        "diff" => {
+            let old: NavIndex = report.track(records).filter_map(Result::ok).collect();
+            let new: NavIndex = report
+                .track(inputs.next().expect("two inputs"))
+                .filter_map(Result::ok)
+                .collect();
+            let diff = amfi::diff_navs(&old, &new);
+            for delta in diff.deltas.iter().filter(|delta| delta.abs_change != 0.0) {
+                let pct = delta
+                    .pct_change
+                    .map_or("-".to_string(), |pct| format!("{:+.2}%", pct));
+                writeln!(
+                    out,
+                    "{:>8}  {:>12.4}  {:>12.4}  {:>8}  {}",
+                    delta.code.to_string(),
+                    delta.old_nav,
+                    delta.new_nav,
+                    pct,
+                    delta.name
+                )?;
+            }
+            for (sign, records) in &[('+', &diff.added), ('-', &diff.removed)] {
+                for record in records.iter() {
+                    writeln!(
+                        out,
+                        "{} {:>6}  {}",
+                        sign,
+                        record.code.to_string(),
+                        record.name
+                    )?;
+                }
+            }
+        }
+        _ => unreachable!("command validated by Options::parse"),
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::ReqwestError(_) => exit::NETWORK,
+        Error::HttpError { .. } | Error::UnexpectedContent { url: Some(_), .. } => exit::HTTP,
+        _ => exit::ERROR,
+    }
+}
+
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) if message.is_empty() => {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        Err(message) => {
+            eprintln!("amfi: {}\n\n{}", message, USAGE);
+            process::exit(exit::USAGE);
+        }
+    };
+    let mut report = Report {
+        quiet: options.quiet,
+        ..Report::default()
+    };
+    let result = run(&options, &mut report);
+    if options.stats {
+        eprintln!("records: {}, errors: {}", report.records, report.errors);
+        for stats in report.parses.borrow().iter() {
+            if let Some(fetch) = stats.fetch_duration {
+                eprintln!("fetched in {:?}", fetch);
+            }
+            if let (Some(parse), Some(throughput)) = (stats.parse_duration, stats.throughput()) {
+                eprintln!(
+                    "parsed {} bytes in {:?}: {}",
+                    stats.bytes_read, parse, throughput
+                );
+            }
+        }
+    }
+    let code = match result {
+        Err(error) => {
+            eprintln!("amfi: {}", error);
+            exit_code(&error)
+        }
+        Ok(()) if report.errors > 0 => exit::PARSE,
+        Ok(()) => 0,
+    };
+    process::exit(code);
+}
@@ -0,0 +1,217 @@
+//! Indian business days, on which NAVs are published
+//!
+//! Business days are weekdays other than the trading holidays of the National Stock Exchange
+//! listed in `data/holidays.txt`, compiled into the crate. Holidays of years not listed yet
+//! are unknown, so only weekends are left out of them.
+
+use crate::NavDate;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Trading holidays as published, see `data/holidays.txt`
+const HOLIDAYS: &str = include_str!("../data/holidays.txt");
+
+/// Hour of the day, IST, by which the feed of a business day is published
+///
+/// Fund houses upload NAVs to AMFI by 11 PM IST.
+pub const FEED_PUBLISHED_BY_HOUR: u32 = 23;
+
+/// Holidays, sorted
+fn holidays() -> &'static [NavDate] {
+    static PARSED: OnceLock<Vec<NavDate>> = OnceLock::new();
+    PARSED.get_or_init(|| parse_holidays(HOLIDAYS))
+}
+
+/// Dates of the lines of a holiday list, sorted; lines that don't start with one are skipped
+fn parse_holidays(text: &str) -> Vec<NavDate> {
+    let mut dates: Vec<NavDate> = text
+        .lines()
+        .filter_map(|line| line.split_whitespace().next()?.parse().ok())
+        .collect();
+    dates.sort();
+    dates
+}
+
+/// Whether NAVs are published for `date`: a weekday that is not a listed holiday
+///
+/// ```rust
+/// use amfi::NavDate;
+///
+/// assert!(amfi::is_business_day(NavDate::from_ymd(2024, 6, 21).unwrap()));
+/// // Saturday
+/// assert!(!amfi::is_business_day(NavDate::from_ymd(2024, 6, 22).unwrap()));
+/// // Bakri Id
+/// assert!(!amfi::is_business_day(NavDate::from_ymd(2024, 6, 17).unwrap()));
+/// ```
+pub fn is_business_day(date: NavDate) -> bool {
+    is_weekday(date) && holidays().binary_search(&date).is_err()
+}
+
+/// Last business day before `date`, `None` before year 1
+///
+/// ```rust
+/// use amfi::NavDate;
+///
+/// let monday = NavDate::from_ymd(2024, 6, 24).unwrap();
+/// let friday = NavDate::from_ymd(2024, 6, 21).unwrap();
+/// assert_eq!(amfi::previous_business_day(monday), Some(friday));
+/// ```
+pub fn previous_business_day(date: NavDate) -> Option<NavDate> {
+    let mut day = date.epoch_days();
+    loop {
+        day -= 1;
+        let date = NavDate::from_epoch_days(day)?;
+        if is_business_day(date) {
+            return Some(date);
+        }
+    }
+}
+
+/// NAV date of the latest feed that should be published at `now`
+///
+/// The feed of a business day is published in the evening, by
+/// [`FEED_PUBLISHED_BY_HOUR`](constant.FEED_PUBLISHED_BY_HOUR.html) IST: from then on it is
+/// that day's, before then and on other days the previous business day's. So a Saturday
+/// expects Friday's feed, as does a Monday before 11 PM.
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// // Saturday, 22 June 2024, 10 AM IST
+/// let now = UNIX_EPOCH + Duration::from_secs(1_719_030_600);
+/// assert_eq!(amfi::expected_feed_date(now), amfi::NavDate::from_ymd(2024, 6, 21));
+/// ```
+pub fn expected_feed_date(now: SystemTime) -> Option<NavDate> {
+    let seconds = match now.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    };
+    let today = NavDate::from_unix_ist(seconds)?;
+    let published = today.unix_midnight_ist() + i64::from(FEED_PUBLISHED_BY_HOUR) * 3600;
+    if is_business_day(today) && seconds >= published {
+        Some(today)
+    } else {
+        previous_business_day(today)
+    }
+}
+
+/// Number of business days after the earlier of `a` and `b` up to the later
+pub(crate) fn business_days_between(a: NavDate, b: NavDate) -> i64 {
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+    let weekdays = weekdays_before(b.epoch_days() + 1) - weekdays_before(a.epoch_days() + 1);
+    let holidays = holidays();
+    let after = |date: NavDate| holidays.partition_point(|&holiday| holiday <= date);
+    let closed = holidays[after(a)..after(b)]
+        .iter()
+        .filter(|&&holiday| is_weekday(holiday))
+        .count();
+    weekdays - closed as i64
+}
+
+fn is_weekday(date: NavDate) -> bool {
+    // 1970-01-01 was a Thursday
+    (date.epoch_days() + 3).rem_euclid(7) < 5
+}
+
+/// Number of weekdays from the Monday of 1969-12-29 up to the day before `epoch_days`,
+/// negative before it
+fn weekdays_before(epoch_days: i64) -> i64 {
+    let days = epoch_days + 3;
+    days.div_euclid(7) * 5 + days.rem_euclid(7).min(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn date(year: i32, month: u32, day: u32) -> NavDate {
+        NavDate::from_ymd(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn holiday_list_is_valid() {
+        let listed = HOLIDAYS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .count();
+        let holidays = holidays();
+        assert_eq!(holidays.len(), listed, "every listed line is a date");
+        assert!(holidays.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(holidays.iter().all(|&holiday| is_weekday(holiday)));
+        assert!(holidays.contains(&date(2024, 6, 17)));
+    }
+
+    #[test]
+    fn current_year_is_listed() {
+        // Fails once a year starts without its holidays in `data/holidays.txt`
+        let year = NavDate::today().year();
+        assert!(
+            holidays().iter().any(|holiday| holiday.year() == year),
+            "no holidays listed for {}",
+            year
+        );
+    }
+
+    #[test]
+    fn previous_business_days() {
+        let cases = [
+            // Tuesday after the Bakri Id Monday
+            (date(2024, 6, 18), date(2024, 6, 14)),
+            (date(2024, 6, 22), date(2024, 6, 21)),
+            (date(2024, 6, 24), date(2024, 6, 21)),
+            (date(2024, 6, 25), date(2024, 6, 24)),
+            // Over Diwali
+            (date(2025, 10, 23), date(2025, 10, 20)),
+        ];
+        for &(day, previous) in &cases {
+            assert_eq!(previous_business_day(day), Some(previous), "{}", day);
+        }
+        assert_eq!(previous_business_day(date(1, 1, 1)), None);
+    }
+
+    #[test]
+    fn expected_feed_dates() {
+        let at = |date: NavDate, hour: i64, minute: i64| {
+            let seconds = date.unix_midnight_ist() + hour * 3600 + minute * 60;
+            UNIX_EPOCH + Duration::from_secs(seconds as u64)
+        };
+        let friday = date(2024, 6, 21);
+        let thursday = date(2024, 6, 20);
+        assert_eq!(expected_feed_date(at(friday, 22, 59)), Some(thursday));
+        assert_eq!(expected_feed_date(at(friday, 23, 0)), Some(friday));
+        // The weekend and Monday before the evening expect Friday's feed
+        for &(day, hour) in &[(22, 10), (23, 23), (24, 0), (24, 22)] {
+            let now = at(date(2024, 6, day), hour, 0);
+            assert_eq!(expected_feed_date(now), Some(friday), "{} {}", day, hour);
+        }
+        assert_eq!(
+            expected_feed_date(at(date(2024, 6, 24), 23, 30)),
+            Some(date(2024, 6, 24))
+        );
+        // On a holiday the feed is the previous business day's all day
+        let bakri_id = date(2024, 6, 17);
+        assert_eq!(
+            expected_feed_date(at(bakri_id, 23, 30)),
+            Some(date(2024, 6, 14))
+        );
+    }
+
+    #[test]
+    fn counts_business_days() {
+        let friday = date(2024, 6, 21);
+        assert_eq!(business_days_between(friday, date(2024, 6, 23)), 0);
+        assert_eq!(business_days_between(date(2024, 6, 24), friday), 1);
+        // Bakri Id on Monday the 17th
+        assert_eq!(
+            business_days_between(date(2024, 6, 14), date(2024, 6, 18)),
+            1
+        );
+        assert_eq!(
+            business_days_between(date(2024, 6, 3), date(2024, 6, 28)),
+            18
+        );
+        assert_eq!(business_days_between(friday, friday), 0);
+    }
+}
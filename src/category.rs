@@ -0,0 +1,751 @@
+//! Typed SEBI scheme categories
+//!
+//! The raw `category` string of a [`NavRecord`](../struct.NavRecord.html) varies in spacing and
+//! capitalization across feed days. [`SchemeCategory`](struct.SchemeCategory.html) maps it onto
+//! the SEBI categorization so records can be grouped reliably.
+
+use crate::SchemeHeader;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Broad asset class of a scheme category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AssetClass {
+    /// Equity Schemes
+    Equity,
+    /// Debt Schemes
+    Debt,
+    /// Hybrid Schemes
+    Hybrid,
+    /// Solution Oriented Schemes
+    SolutionOriented,
+    /// Other Schemes (Index Funds, ETFs, FoFs) and unrecognized groups
+    Other,
+}
+
+/// Scheme group of a scheme section header, the text before its category, e.g. `Equity
+/// Scheme` of `Open Ended Schemes(Equity Scheme - Large Cap Fund)`
+///
+/// ```rust
+/// use amfi::{AssetClass, SchemeGroup};
+///
+/// assert_eq!(SchemeGroup::new(" solution  oriented scheme"), SchemeGroup::SolutionOriented);
+/// assert_eq!(SchemeGroup::new("Other Scheme").asset_class(), AssetClass::Other);
+/// assert_eq!(SchemeGroup::new("Interval Scheme"), SchemeGroup::Other("Interval Scheme".to_string()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SchemeGroup {
+    /// Equity Scheme
+    Equity,
+    /// Debt Scheme
+    Debt,
+    /// Hybrid Scheme
+    Hybrid,
+    /// Solution Oriented Scheme
+    SolutionOriented,
+    /// Other Scheme, of index funds, ETFs and FoFs
+    OtherScheme,
+    /// Unrecognized group, trimmed raw text
+    Other(String),
+}
+
+impl SchemeGroup {
+    /// Maps a raw scheme group, e.g. `Equity Scheme`
+    ///
+    /// Matching ignores case, spacing, punctuation and a trailing `Scheme` or `Schemes`.
+    pub fn new(raw: &str) -> Self {
+        let key = normalize(raw);
+        let key = key
+            .strip_suffix("schemes")
+            .or_else(|| key.strip_suffix("scheme"))
+            .unwrap_or(&key)
+            .trim_end();
+        match key {
+            "equity" => SchemeGroup::Equity,
+            "debt" => SchemeGroup::Debt,
+            "hybrid" => SchemeGroup::Hybrid,
+            "solution oriented" | "solution" => SchemeGroup::SolutionOriented,
+            "other" => SchemeGroup::OtherScheme,
+            _ => SchemeGroup::Other(raw.trim().to_string()),
+        }
+    }
+
+    /// Asset class of the schemes of the group, [`AssetClass::Other`](enum.AssetClass.html#variant.Other)
+    /// for `Other Scheme` and unrecognized groups
+    pub fn asset_class(&self) -> AssetClass {
+        match self {
+            SchemeGroup::Equity => AssetClass::Equity,
+            SchemeGroup::Debt => AssetClass::Debt,
+            SchemeGroup::Hybrid => AssetClass::Hybrid,
+            SchemeGroup::SolutionOriented => AssetClass::SolutionOriented,
+            SchemeGroup::OtherScheme | SchemeGroup::Other(..) => AssetClass::Other,
+        }
+    }
+
+    /// Whether the group was not recognized
+    pub fn is_other(&self) -> bool {
+        matches!(self, SchemeGroup::Other(..))
+    }
+}
+
+/// Normalized SEBI sub-category
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SubCategory {
+    /// Multi Cap Fund
+    MultiCap,
+    /// Large Cap Fund
+    LargeCap,
+    /// Large & Mid Cap Fund
+    LargeAndMidCap,
+    /// Mid Cap Fund
+    MidCap,
+    /// Small Cap Fund
+    SmallCap,
+    /// Flexi Cap Fund
+    FlexiCap,
+    /// Dividend Yield Fund
+    DividendYield,
+    /// Value Fund
+    Value,
+    /// Contra Fund
+    Contra,
+    /// Focused Fund
+    Focused,
+    /// Sectoral/Thematic
+    SectoralThematic,
+    /// Equity Linked Savings Scheme
+    Elss,
+    /// Overnight Fund
+    Overnight,
+    /// Liquid Fund
+    Liquid,
+    /// Ultra Short Duration Fund
+    UltraShortDuration,
+    /// Low Duration Fund
+    LowDuration,
+    /// Money Market Fund
+    MoneyMarket,
+    /// Short Duration Fund
+    ShortDuration,
+    /// Medium Duration Fund
+    MediumDuration,
+    /// Medium to Long Duration Fund
+    MediumToLongDuration,
+    /// Long Duration Fund
+    LongDuration,
+    /// Dynamic Bond
+    DynamicBond,
+    /// Corporate Bond Fund
+    CorporateBond,
+    /// Credit Risk Fund
+    CreditRisk,
+    /// Banking and PSU Fund
+    BankingAndPsu,
+    /// Gilt Fund
+    Gilt,
+    /// Gilt Fund with 10 year constant duration
+    GiltConstantDuration,
+    /// Floater Fund
+    Floater,
+    /// Conservative Hybrid Fund
+    ConservativeHybrid,
+    /// Balanced Hybrid Fund
+    BalancedHybrid,
+    /// Aggressive Hybrid Fund
+    AggressiveHybrid,
+    /// Dynamic Asset Allocation or Balanced Advantage
+    BalancedAdvantage,
+    /// Multi Asset Allocation
+    MultiAssetAllocation,
+    /// Arbitrage Fund
+    Arbitrage,
+    /// Equity Savings
+    EquitySavings,
+    /// Retirement Fund
+    Retirement,
+    /// Children's Fund
+    Childrens,
+    /// Index Funds
+    IndexFund,
+    /// Gold ETF
+    GoldEtf,
+    /// Other ETFs
+    Etf,
+    /// FoF Overseas
+    FofOverseas,
+    /// FoF Domestic
+    FofDomestic,
+    /// Legacy (pre-2018) Income category
+    Income,
+    /// Legacy (pre-2018) Growth category
+    Growth,
+    /// Legacy (pre-2018) Balanced category
+    Balanced,
+    /// Unrecognized category, trimmed raw text
+    Other(String),
+}
+
+/// Typed scheme category derived from the scheme section header
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SchemeCategory {
+    /// Asset class
+    pub class: AssetClass,
+    /// Sub-category
+    pub sub: SubCategory,
+}
+
+impl SchemeCategory {
+    /// Maps raw `scheme` (e.g. `Equity Scheme`) and `category` (e.g. `Large Cap Fund`) strings
+    ///
+    /// See [`of_group`](#method.of_group), `scheme` being mapped with
+    /// [`SchemeGroup::new`](enum.SchemeGroup.html#method.new).
+    pub fn new(scheme: Option<&str>, category: &str) -> Self {
+        SchemeCategory::of_group(scheme.map(SchemeGroup::new).as_ref(), category)
+    }
+
+    /// Maps a raw `category` (e.g. `Large Cap Fund`) of `group`
+    ///
+    /// Matching ignores case, spacing and punctuation. Categories are looked up by group and
+    /// name, as names may repeat across groups; without a group, or of an unrecognized one,
+    /// by name alone. Unknown categories, and known ones of another group, become
+    /// [`SubCategory::Other`](enum.SubCategory.html#variant.Other), with the asset class
+    /// taken from `group` when possible.
+    pub fn of_group(group: Option<&SchemeGroup>, category: &str) -> Self {
+        match lookup(group, &normalize(category)) {
+            Some((class, sub)) => SchemeCategory { class, sub },
+            None => SchemeCategory {
+                class: group.map_or(AssetClass::Other, SchemeGroup::asset_class),
+                sub: SubCategory::Other(category.trim().to_string()),
+            },
+        }
+    }
+
+    /// Whether the category was not found in the mapping table
+    pub fn is_other(&self) -> bool {
+        matches!(self.sub, SubCategory::Other(..))
+    }
+}
+
+/// Kind of a Fund of Funds scheme, one investing in units of other mutual funds
+///
+/// See [`NavRecord::fof`](struct.NavRecord.html#structfield.fof).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FofKind {
+    /// Investing in Indian mutual funds
+    Domestic,
+    /// Investing in funds abroad
+    Overseas,
+}
+
+impl FofKind {
+    /// Kind given by a `FoF Domestic` or `FoF Overseas` category, `None` for other categories
+    pub fn of_category(category: &SchemeCategory) -> Option<FofKind> {
+        match category.sub {
+            SubCategory::FofDomestic => Some(FofKind::Domestic),
+            SubCategory::FofOverseas => Some(FofKind::Overseas),
+            _ => None,
+        }
+    }
+
+    /// Kind given by the category of scheme `header`, see [`of_category`](#method.of_category)
+    pub(crate) fn of_header(header: &SchemeHeader) -> Option<FofKind> {
+        // Checked for every scheme header, so mapped only when the category may be a FoF one,
+        // i.e. names `fof` once apostrophes are dropped
+        let category = &header.category;
+        if !crate::contains_ignore_ascii_case(category, "fof")
+            && !category.contains(&['\'', '\u{2019}'][..])
+        {
+            return None;
+        }
+        FofKind::of_category(&header.scheme_category())
+    }
+
+    /// Kind guessed from a scheme name containing `Fund of Funds` or `FoF`, overseas when
+    /// the name also points abroad, e.g. `Nasdaq 100` or `International`
+    ///
+    /// ```rust
+    /// use amfi::FofKind;
+    ///
+    /// let name = "Motilal Oswal Nasdaq 100 Fund of Fund- Direct Plan Growth";
+    /// assert_eq!(FofKind::from_name(name), Some(FofKind::Overseas));
+    /// assert_eq!(FofKind::from_name("ICICI Prudential Passive Multi-Asset FoF"), Some(FofKind::Domestic));
+    /// assert_eq!(FofKind::from_name("Axis Bluechip Fund - Direct Plan - Growth"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<FofKind> {
+        // Checked for every record, so tokenized only when the name may be a FoF, i.e. has
+        // the `of` of both spellings
+        if !crate::contains_ignore_ascii_case(name, "of")
+            || (!crate::contains_ignore_ascii_case(name, "fof")
+                && !crate::contains_ignore_ascii_case(name, "fund of fun"))
+        {
+            return None;
+        }
+        let tokens: Vec<String> = name
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let fof = tokens.iter().any(|token| token == "fof")
+            || tokens.windows(3).any(|window| {
+                window[0] == "fund" && window[1] == "of" && window[2].starts_with("fund")
+            });
+        if !fof {
+            None
+        } else if tokens
+            .iter()
+            .any(|token| OVERSEAS_TOKENS.contains(&token.as_str()))
+        {
+            Some(FofKind::Overseas)
+        } else {
+            Some(FofKind::Domestic)
+        }
+    }
+
+    /// Kind from the category headers of a record, else from its name
+    pub(crate) fn of_scheme(scheme: Option<&str>, category: &str, name: &str) -> Option<FofKind> {
+        FofKind::of_category(&SchemeCategory::new(scheme, category))
+            .or_else(|| FofKind::from_name(name))
+    }
+}
+
+/// How a scheme invests, see [`NavRecord::vehicle`](struct.NavRecord.html#method.vehicle)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum VehicleKind {
+    /// Actively managed fund
+    ActiveFund,
+    /// Index fund tracking an index
+    IndexFund,
+    /// Exchange traded fund, whose NAV differs from its market price
+    Etf,
+    /// Fund of Funds, see [`FofKind`](enum.FofKind.html)
+    Fof,
+}
+
+impl VehicleKind {
+    /// Kind given by a category, `None` for unrecognized and legacy (pre-2018) categories
+    /// which don't tell
+    pub fn of_category(category: &SchemeCategory) -> Option<VehicleKind> {
+        match category.sub {
+            SubCategory::IndexFund => Some(VehicleKind::IndexFund),
+            SubCategory::GoldEtf | SubCategory::Etf => Some(VehicleKind::Etf),
+            SubCategory::FofDomestic | SubCategory::FofOverseas => Some(VehicleKind::Fof),
+            SubCategory::Income
+            | SubCategory::Growth
+            | SubCategory::Balanced
+            | SubCategory::Other(..) => None,
+            _ => Some(VehicleKind::ActiveFund),
+        }
+    }
+
+    /// Kind of a record in `category` of `scheme`, see
+    /// [`NavRecord::vehicle`](struct.NavRecord.html#method.vehicle)
+    pub(crate) fn of_record(
+        scheme: Option<&str>,
+        category: &str,
+        fof: bool,
+        name: &str,
+    ) -> VehicleKind {
+        match VehicleKind::of_category(&SchemeCategory::new(scheme, category)) {
+            Some(VehicleKind::ActiveFund) if fof => VehicleKind::Fof,
+            Some(kind) => kind,
+            None if fof => VehicleKind::Fof,
+            None => VehicleKind::from_name(name),
+        }
+    }
+
+    /// Kind guessed from a scheme name: `ETF` (or `BeES`) tokens, then those of a FoF, see
+    /// [`FofKind::from_name`](enum.FofKind.html#method.from_name), then `Index`, `Nifty` or
+    /// `Sensex`
+    ///
+    /// ```rust
+    /// use amfi::VehicleKind;
+    ///
+    /// assert_eq!(VehicleKind::from_name("SBI Nifty 50 ETF"), VehicleKind::Etf);
+    /// assert_eq!(VehicleKind::from_name("UTI Nifty Next 50 Fund"), VehicleKind::IndexFund);
+    /// assert_eq!(VehicleKind::from_name("Axis Bluechip Fund"), VehicleKind::ActiveFund);
+    /// ```
+    pub fn from_name(name: &str) -> VehicleKind {
+        let tokens: Vec<String> = name
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let any = |words: &[&str]| tokens.iter().any(|token| words.contains(&token.as_str()));
+        if any(&["etf", "etfs", "bees"]) {
+            VehicleKind::Etf
+        } else if FofKind::from_name(name).is_some() {
+            VehicleKind::Fof
+        } else if any(&["index", "nifty", "sensex"]) {
+            VehicleKind::IndexFund
+        } else {
+            VehicleKind::ActiveFund
+        }
+    }
+}
+
+/// Name tokens of FoFs investing abroad
+const OVERSEAS_TOKENS: &[&str] = &[
+    "overseas",
+    "international",
+    "global",
+    "world",
+    "us",
+    "usa",
+    "nasdaq",
+    "nyse",
+    "emerging",
+    "asia",
+    "asean",
+    "europe",
+    "european",
+    "china",
+    "japan",
+    "taiwan",
+    "brazil",
+];
+
+/// Lowercased alphanumeric tokens with `&` spelled out and `fund`/`funds` dropped
+fn normalize(s: &str) -> String {
+    let mut key = Key {
+        text: String::with_capacity(s.len() + 4),
+        word: None,
+    };
+    for ch in s.chars() {
+        match ch {
+            '\'' | '\u{2019}' => {}
+            _ if ch.is_ascii() => key.push(ch.to_ascii_lowercase()),
+            _ => ch.to_lowercase().for_each(|ch| key.push(ch)),
+        }
+    }
+    key.end_word();
+    key.text
+}
+
+/// Normalized name being written by [`normalize`]
+struct Key {
+    text: String,
+    /// Start of the word being written, after its separating space
+    word: Option<usize>,
+}
+
+impl Key {
+    /// Appends lowercased `ch`
+    fn push(&mut self, ch: char) {
+        if ch == '&' {
+            self.end_word();
+            self.push_str("and");
+            self.end_word();
+        } else if ch.is_alphanumeric() {
+            self.push_str(ch.encode_utf8(&mut [0; 4]));
+        } else {
+            self.end_word();
+        }
+    }
+
+    fn push_str(&mut self, part: &str) {
+        if self.word.is_none() {
+            if !self.text.is_empty() {
+                self.text.push(' ');
+            }
+            self.word = Some(self.text.len());
+        }
+        self.text.push_str(part);
+    }
+
+    /// Ends the word being written, dropping it if it is `fund` or `funds`
+    fn end_word(&mut self) {
+        if let Some(start) = self.word.take() {
+            if matches!(&self.text[start..], "fund" | "funds") {
+                self.text.truncate(start.saturating_sub(1));
+            }
+        }
+    }
+}
+
+/// Sub-category of the category of normalized name `key` in `group`
+fn lookup(group: Option<&SchemeGroup>, key: &str) -> Option<(AssetClass, SubCategory)> {
+    let mut listed = CATEGORIES.iter().filter(|(_, name, _)| *name == key);
+    let (group, _, sub) = match group {
+        Some(group) if !group.is_other() => listed.find(|(of, _, _)| of == group)?,
+        _ => listed.next()?,
+    };
+    Some((group.asset_class(), sub.clone()))
+}
+
+/// SEBI categorization labels (October 2017 circular and later amendments) by group, with
+/// legacy (pre-2018) categories in the group of their asset class
+const CATEGORIES: &[(SchemeGroup, &str, SubCategory)] = {
+    use self::SchemeGroup as G;
+    use self::SubCategory as S;
+    &[
+        (G::Equity, "multi cap", S::MultiCap),
+        (G::Equity, "large cap", S::LargeCap),
+        (G::Equity, "large and mid cap", S::LargeAndMidCap),
+        (G::Equity, "mid cap", S::MidCap),
+        (G::Equity, "small cap", S::SmallCap),
+        (G::Equity, "flexi cap", S::FlexiCap),
+        (G::Equity, "dividend yield", S::DividendYield),
+        (G::Equity, "value", S::Value),
+        (G::Equity, "contra", S::Contra),
+        (G::Equity, "focused", S::Focused),
+        (G::Equity, "sectoral thematic", S::SectoralThematic),
+        (G::Equity, "sectoral", S::SectoralThematic),
+        (G::Equity, "thematic", S::SectoralThematic),
+        (G::Equity, "elss", S::Elss),
+        (G::Debt, "overnight", S::Overnight),
+        (G::Debt, "liquid", S::Liquid),
+        (G::Debt, "ultra short duration", S::UltraShortDuration),
+        (G::Debt, "low duration", S::LowDuration),
+        (G::Debt, "money market", S::MoneyMarket),
+        (G::Debt, "short duration", S::ShortDuration),
+        (G::Debt, "medium duration", S::MediumDuration),
+        (G::Debt, "medium to long duration", S::MediumToLongDuration),
+        (G::Debt, "long duration", S::LongDuration),
+        (G::Debt, "dynamic bond", S::DynamicBond),
+        (G::Debt, "corporate bond", S::CorporateBond),
+        (G::Debt, "credit risk", S::CreditRisk),
+        (G::Debt, "banking and psu", S::BankingAndPsu),
+        (G::Debt, "gilt", S::Gilt),
+        (
+            G::Debt,
+            "gilt with 10 year constant duration",
+            S::GiltConstantDuration,
+        ),
+        (G::Debt, "floater", S::Floater),
+        (G::Hybrid, "conservative hybrid", S::ConservativeHybrid),
+        (G::Hybrid, "balanced hybrid", S::BalancedHybrid),
+        (G::Hybrid, "aggressive hybrid", S::AggressiveHybrid),
+        (
+            G::Hybrid,
+            "dynamic asset allocation or balanced advantage",
+            S::BalancedAdvantage,
+        ),
+        (G::Hybrid, "balanced advantage", S::BalancedAdvantage),
+        (G::Hybrid, "dynamic asset allocation", S::BalancedAdvantage),
+        (G::Hybrid, "multi asset allocation", S::MultiAssetAllocation),
+        (G::Hybrid, "arbitrage", S::Arbitrage),
+        (G::Hybrid, "equity savings", S::EquitySavings),
+        (G::SolutionOriented, "retirement", S::Retirement),
+        (G::SolutionOriented, "childrens", S::Childrens),
+        (G::OtherScheme, "index", S::IndexFund),
+        (G::OtherScheme, "gold etf", S::GoldEtf),
+        (G::OtherScheme, "gold etfs", S::GoldEtf),
+        (G::OtherScheme, "other etfs", S::Etf),
+        (G::OtherScheme, "other etf", S::Etf),
+        (G::OtherScheme, "etfs", S::Etf),
+        (G::OtherScheme, "etf", S::Etf),
+        (G::OtherScheme, "fof overseas", S::FofOverseas),
+        (G::OtherScheme, "fof domestic", S::FofDomestic),
+        (G::Debt, "income", S::Income),
+        (G::Equity, "growth", S::Growth),
+        (G::Hybrid, "balanced", S::Balanced),
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spacing_and_case_variants() {
+        let expected = SchemeCategory {
+            class: AssetClass::Equity,
+            sub: SubCategory::LargeAndMidCap,
+        };
+        assert_eq!(SchemeCategory::new(None, "Large & Mid Cap Fund"), expected);
+        assert_eq!(
+            SchemeCategory::new(None, " large  &  mid cap fund "),
+            expected
+        );
+        assert_eq!(
+            SchemeCategory::new(Some("Solution Oriented Scheme"), "Children’s Fund").sub,
+            SubCategory::Childrens
+        );
+    }
+
+    #[test]
+    fn scheme_groups() {
+        for (raw, group) in &[
+            ("Equity Scheme", SchemeGroup::Equity),
+            (" debt  SCHEMES ", SchemeGroup::Debt),
+            ("Hybrid", SchemeGroup::Hybrid),
+            ("Solution-Oriented Scheme", SchemeGroup::SolutionOriented),
+            ("Other Scheme", SchemeGroup::OtherScheme),
+        ] {
+            assert_eq!(SchemeGroup::new(raw), *group, "{}", raw);
+        }
+        let fund = SchemeGroup::new(" Fund Scheme ");
+        assert_eq!(fund, SchemeGroup::Other("Fund Scheme".to_string()));
+        assert_eq!(fund.asset_class(), AssetClass::Other);
+        assert!(fund.is_other() && !SchemeGroup::OtherScheme.is_other());
+    }
+
+    #[test]
+    fn categories_are_keyed_by_group() {
+        let liquid = SchemeCategory {
+            class: AssetClass::Debt,
+            sub: SubCategory::Liquid,
+        };
+        assert_eq!(
+            SchemeCategory::new(Some("Debt Scheme"), "Liquid Fund"),
+            liquid
+        );
+        assert_eq!(SchemeCategory::new(None, "Liquid Fund"), liquid);
+        assert_eq!(
+            SchemeCategory::new(Some("Fund Scheme"), "Liquid Fund"),
+            liquid
+        );
+        assert_eq!(
+            SchemeCategory::new(Some("Equity Scheme"), "Liquid Fund"),
+            SchemeCategory {
+                class: AssetClass::Equity,
+                sub: SubCategory::Other("Liquid Fund".to_string()),
+            }
+        );
+        assert_eq!(
+            SchemeCategory::of_group(Some(&SchemeGroup::Debt), "Income").sub,
+            SubCategory::Income
+        );
+    }
+
+    #[test]
+    fn unknown_category_keeps_class_from_scheme() {
+        let category = SchemeCategory::new(Some(" Debt Scheme"), " Target Maturity ");
+        assert_eq!(category.class, AssetClass::Debt);
+        assert_eq!(
+            category.sub,
+            SubCategory::Other("Target Maturity".to_string())
+        );
+        assert!(category.is_other());
+    }
+
+    #[test]
+    fn fof_kinds() {
+        let overseas = SchemeCategory::new(Some("Other Scheme"), "FoF Overseas");
+        assert_eq!(FofKind::of_category(&overseas), Some(FofKind::Overseas));
+        let domestic = SchemeCategory::new(Some("Other Scheme"), " FoF  Domestic ");
+        assert_eq!(FofKind::of_category(&domestic), Some(FofKind::Domestic));
+        let index = SchemeCategory::new(Some("Other Scheme"), "Index Funds");
+        assert_eq!(FofKind::of_category(&index), None);
+
+        for &(name, kind) in &[
+            (
+                "Edelweiss Greater China Equity Off-shore Fund - Direct Plan - Growth Option",
+                None,
+            ),
+            (
+                "Mirae Asset NYSE FANG+ ETF Fund of Fund - Direct Plan",
+                Some(FofKind::Overseas),
+            ),
+            (
+                "Kotak Global Emerging Market Overseas Equity Omni FOF - Direct Plan - Growth",
+                Some(FofKind::Overseas),
+            ),
+            (
+                "HDFC Asset Allocator Fund of Funds - Direct Plan - Growth Option",
+                Some(FofKind::Domestic),
+            ),
+            ("SBI Gold Fund - DIRECT PLAN - GROWTH", None),
+            (
+                "Aditya Birla Sun Life Financial Planning FOF Aggressive Plan-Direct Plan-Growth",
+                Some(FofKind::Domestic),
+            ),
+            ("Tata Fofana Fund", None),
+        ] {
+            assert_eq!(FofKind::from_name(name), kind, "{}", name);
+        }
+    }
+
+    #[test]
+    fn vehicle_kinds() {
+        let of = |category: &str| VehicleKind::of_category(&SchemeCategory::new(None, category));
+        assert_eq!(of("Index Funds"), Some(VehicleKind::IndexFund));
+        assert_eq!(of("Gold ETF"), Some(VehicleKind::Etf));
+        assert_eq!(of("Other  ETFs"), Some(VehicleKind::Etf));
+        assert_eq!(of("FoF Domestic"), Some(VehicleKind::Fof));
+        assert_eq!(of("Large Cap Fund"), Some(VehicleKind::ActiveFund));
+        assert_eq!(of("Growth"), None);
+        assert_eq!(of(""), None);
+
+        for &(name, kind) in &[
+            ("Nippon India ETF Gold BeES", VehicleKind::Etf),
+            ("Bharat Bond ETF - April 2030", VehicleKind::Etf),
+            (
+                "ICICI Prudential Nifty Next 50 Index Fund - Direct Plan - Growth",
+                VehicleKind::IndexFund,
+            ),
+            (
+                "HDFC Index Fund-S&P BSE SENSEX Plan",
+                VehicleKind::IndexFund,
+            ),
+            (
+                "Edelweiss Bharat Bond FOF - April 2031 - Direct Plan - Growth",
+                VehicleKind::Fof,
+            ),
+            (
+                "Axis Gold Fund - Direct Plan - Growth Option",
+                VehicleKind::ActiveFund,
+            ),
+        ] {
+            assert_eq!(VehicleKind::from_name(name), kind, "{}", name);
+        }
+    }
+
+    #[test]
+    fn fixture_categories_are_known() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        for record in crate::nav_from_file(path).unwrap() {
+            let record = record.unwrap();
+            let category = record.category_typed();
+            assert!(
+                !category.is_other(),
+                "unmapped category `{}`",
+                record.category
+            );
+        }
+    }
+
+    #[test]
+    fn fixture_groups_are_known() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let feed = std::fs::read_to_string(path).unwrap();
+        let headers: Vec<_> = feed
+            .lines()
+            .filter(|line| line.contains("Schemes("))
+            .map(|line| crate::parse_scheme_header(line).unwrap())
+            .collect();
+        assert!(!headers.is_empty());
+        for header in headers {
+            let group = header.group();
+            assert!(
+                group.as_ref().is_some_and(|group| !group.is_other()),
+                "unmapped group of `{}`",
+                header
+            );
+            assert_eq!(header.scheme_category().class, group.unwrap().asset_class());
+        }
+        for record in crate::nav_from_file(path).unwrap() {
+            let record = record.unwrap();
+            assert_eq!(
+                record.scheme_group().unwrap().asset_class(),
+                record.category_typed().class
+            );
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Minimal write sets keeping a table of the latest record per scheme in sync
+
+use crate::{NavRecord, SchemeCode};
+use std::collections::HashMap;
+
+/// Comparison of NAVs and prices by [`changes_between`](fn.changes_between.html)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NavTolerance {
+    /// Equal values only, NaN being equal to NaN
+    #[default]
+    Exact,
+    /// Values at most this far apart
+    Epsilon(f64),
+}
+
+impl NavTolerance {
+    /// Whether `old` and `new` are the same value
+    fn same(self, old: f64, new: f64) -> bool {
+        if old.is_nan() || new.is_nan() {
+            return old.is_nan() && new.is_nan();
+        }
+        match self {
+            NavTolerance::Exact => old == new,
+            NavTolerance::Epsilon(epsilon) => (old - new).abs() <= epsilon,
+        }
+    }
+
+    fn same_price(self, old: Option<f64>, new: Option<f64>) -> bool {
+        match (old, new) {
+            (Some(old), Some(new)) => self.same(old, new),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Writes turning a table of the previous records into one of the current records, see
+/// [`changes_between`](fn.changes_between.html)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangeSet {
+    /// Records new or changed, in current order
+    pub upserts: Vec<NavRecord>,
+    /// Schemes no longer listed, in previous order
+    pub deletes: Vec<SchemeCode>,
+}
+
+impl ChangeSet {
+    /// Whether there is nothing to write
+    pub fn is_empty(&self) -> bool {
+        self.upserts.is_empty() && self.deletes.is_empty()
+    }
+
+    /// Applies the writes to `table`, keyed by scheme code
+    pub fn apply(&self, table: &mut HashMap<SchemeCode, NavRecord>) {
+        for code in &self.deletes {
+            table.remove(code);
+        }
+        for record in &self.upserts {
+            table.insert(record.code, record.clone());
+        }
+    }
+}
+
+/// Upserts and deletes turning a table of the `previous` records into one of the `current`
+/// records
+///
+/// Tables hold one record per scheme code, the last one listed. A current record is an upsert
+/// if its code is new or any field differs, NAV and prices compared with `tolerance` and
+/// `source_line`, `segment` and `recovered` ignored as by `==`; a previous code missing from
+/// the current records is a delete. Records are matched by code only, in time linear in the
+/// number of records; see [`diff_navs`](fn.diff_navs.html) for changes of NAV matched by ISIN
+/// too.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::NavTolerance;
+///
+/// let previous: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?.collect::<Result<_, _>>()?;
+/// let mut current = previous.clone();
+/// current[0].nav += 0.01;
+/// current.pop();
+///
+/// let changes = amfi::changes_between(&previous, &current, NavTolerance::Exact);
+/// assert_eq!(changes.upserts, [current[0].clone()]);
+/// assert_eq!(changes.deletes, [previous[29].code]);
+/// let changes = amfi::changes_between(&previous, &current, NavTolerance::Epsilon(0.05));
+/// assert!(changes.upserts.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub fn changes_between(
+    previous: &[NavRecord],
+    current: &[NavRecord],
+    tolerance: NavTolerance,
+) -> ChangeSet {
+    let old = latest(previous);
+    let new = latest(current);
+    let upserts = current
+        .iter()
+        .filter(|record| std::ptr::eq(new[&record.code], *record))
+        .filter(|record| match old.get(&record.code) {
+            Some(old) => !same(old, record, tolerance),
+            None => true,
+        })
+        .cloned()
+        .collect();
+    let deletes = previous
+        .iter()
+        .filter(|record| std::ptr::eq(old[&record.code], *record))
+        .filter(|record| !new.contains_key(&record.code))
+        .map(|record| record.code)
+        .collect();
+    ChangeSet { upserts, deletes }
+}
+
+/// Last record of each code of `records`
+fn latest(records: &[NavRecord]) -> HashMap<SchemeCode, &NavRecord> {
+    let mut latest = HashMap::with_capacity(records.len());
+    for record in records {
+        latest.insert(record.code, record);
+    }
+    latest
+}
+
+/// Whether `new` has the fields of `old`, NAV and prices compared with `tolerance`
+fn same(old: &NavRecord, new: &NavRecord, tolerance: NavTolerance) -> bool {
+    // Destructured so that new fields aren't forgotten
+    let NavRecord {
+        code,
+        isin,
+        isin_dr,
+        name,
+        nav,
+        date,
+        amc,
+        category,
+        scheme,
+        maturity,
+        plan,
+        plan_source,
+        option,
+        fof,
+        repurchase_price,
+        sale_price,
+        raw,
+        source_line: _,
+        segment: _,
+        recovered: _,
+    } = new;
+    tolerance.same(old.nav, *nav)
+        && tolerance.same_price(old.repurchase_price, *repurchase_price)
+        && tolerance.same_price(old.sale_price, *sale_price)
+        && old.code == *code
+        && old.date == *date
+        && old.isin == *isin
+        && old.isin_dr == *isin_dr
+        && old.name == *name
+        && old.amc == *amc
+        && old.category == *category
+        && old.scheme == *scheme
+        && old.maturity == *maturity
+        && old.plan == *plan
+        && old.plan_source == *plan_source
+        && old.option == *option
+        && old.fof == *fof
+        && old.raw == *raw
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FundPlan, NavDate, NavRecordBuilder};
+    use proptest::prelude::*;
+
+    fn record(code: u32, nav: f64, name: &str) -> NavRecord {
+        NavRecordBuilder::default()
+            .code(code)
+            .name(name)
+            .nav(nav)
+            .date(NavDate::from_ymd(2024, 6, 21).unwrap())
+            .amc("Axis Mutual Fund")
+            .category("Large Cap Fund")
+            .plan(FundPlan::Regular)
+            .build()
+            .unwrap()
+    }
+
+    fn table(records: &[NavRecord]) -> HashMap<SchemeCode, NavRecord> {
+        records.iter().map(|r| (r.code, r.clone())).collect()
+    }
+
+    #[test]
+    fn minimal_writes() {
+        let previous = vec![
+            record(1, 10.0, "A"),
+            record(2, 20.0, "B"),
+            record(3, 30.0, "C").with_nav(f64::NAN),
+            record(4, 40.0, "D"),
+        ];
+        let mut moved = record(2, 20.0, "B");
+        moved.source_line = Some(99);
+        let current = vec![
+            record(3, 30.0, "C").with_nav(f64::NAN),
+            moved,
+            record(1, 10.0, "A renamed"),
+            record(5, 50.0, "E"),
+            record(5, 50.5, "E"),
+            record(4, 40.001, "D"),
+        ];
+        let changes = changes_between(&previous, &current, NavTolerance::Exact);
+        let upserts: Vec<_> = changes.upserts.iter().map(|r| (r.code, r.nav)).collect();
+        assert_eq!(
+            upserts,
+            [
+                (SchemeCode::from(1), 10.0),
+                (SchemeCode::from(5), 50.5),
+                (SchemeCode::from(4), 40.001)
+            ]
+        );
+        assert!(changes.deletes.is_empty());
+
+        let changes = changes_between(&previous, &current, NavTolerance::Epsilon(0.01));
+        assert_eq!(changes.upserts.len(), 2);
+        let changes = changes_between(&current, &previous, NavTolerance::Epsilon(0.01));
+        assert_eq!(changes.deletes, [SchemeCode::from(5)]);
+        assert!(changes_between(&current, &current, NavTolerance::Exact).is_empty());
+    }
+
+    fn records() -> impl Strategy<Value = Vec<NavRecord>> {
+        let fields = (0_u32..12, 0_u8..4, prop::bool::ANY, 0_u8..3);
+        prop::collection::vec(fields, 0..24).prop_map(|records| {
+            records
+                .into_iter()
+                .map(|(code, nav, isin, name)| {
+                    let mut record = record(code, f64::from(nav) / 4.0, &name.to_string());
+                    if isin {
+                        record.isin = Some(crate::RawIsin::new("INF846K01AB4"));
+                    }
+                    record
+                })
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn applying_changes_gives_current(previous in records(), current in records()) {
+            let changes = changes_between(&previous, &current, NavTolerance::Exact);
+            let mut synced = table(&previous);
+            changes.apply(&mut synced);
+            prop_assert_eq!(&synced, &table(&current));
+
+            // Every write is needed
+            let old = table(&previous);
+            for record in &changes.upserts {
+                prop_assert_ne!(old.get(&record.code), Some(record));
+            }
+            for code in &changes.deletes {
+                prop_assert!(old.contains_key(code) && !synced.contains_key(code));
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Text encoding of downloaded feeds
+
+#[cfg(feature = "encoding")]
+use encoding_rs::{Encoding, UTF_8};
+use log::warn;
+
+/// What decoding a line does with bytes that are not valid UTF-8, see
+/// [`NavRecordIterator::decode`](struct.NavRecordIterator.html#method.decode)
+///
+/// Feeds downloaded are decoded lossily by default, as published feeds have had stray
+/// bytes; other feeds strictly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodePolicy {
+    /// The line is an [`Error::InvalidUtf8`](enum.Error.html#variant.InvalidUtf8)
+    #[default]
+    Strict,
+    /// Invalid bytes are replaced with `U+FFFD`, counted in
+    /// [`ParseStats::replaced_chars`](struct.ParseStats.html#structfield.replaced_chars)
+    Lossy,
+}
+
+/// Encoding of the bytes of a feed line
+///
+/// Only downloaded feeds declare a charset, others are always UTF-8.
+#[cfg_attr(not(any(feature = "blocking", feature = "async")), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Charset {
+    /// UTF-8, invalid bytes handled by the [`DecodePolicy`]
+    Utf8,
+    /// Another ASCII-compatible encoding, e.g. `windows-1252`
+    #[cfg(feature = "encoding")]
+    Other(&'static Encoding),
+}
+
+impl Charset {
+    /// Charset of a `Content-Type` header value, UTF-8 if it has no `charset` parameter
+    ///
+    /// Charsets other than UTF-8 need the `encoding` feature; without it, or when the label
+    /// is unknown, a warning is logged and the feed is read as UTF-8.
+    #[cfg_attr(not(any(feature = "blocking", feature = "async")), allow(dead_code))]
+    pub(crate) fn of_content_type(content_type: &str) -> Self {
+        let label = content_type.split(';').skip(1).find_map(|param| {
+            let mut pair = param.splitn(2, '=');
+            match (pair.next(), pair.next()) {
+                (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("charset") => {
+                    Some(value.trim().trim_matches('"'))
+                }
+                _ => None,
+            }
+        });
+        label.map_or(Charset::Utf8, Charset::of_label)
+    }
+
+    #[cfg(feature = "encoding")]
+    fn of_label(label: &str) -> Self {
+        match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) if encoding == UTF_8 => Charset::Utf8,
+            Some(encoding) if encoding.is_ascii_compatible() => Charset::Other(encoding),
+            _ => {
+                warn!("unsupported charset {}, reading feed as UTF-8", label);
+                Charset::Utf8
+            }
+        }
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    fn of_label(label: &str) -> Self {
+        if label.eq_ignore_ascii_case("utf-8") || label.eq_ignore_ascii_case("utf8") {
+            Charset::Utf8
+        } else {
+            warn!(
+                "charset {} needs the `encoding` feature, reading feed as UTF-8",
+                label
+            );
+            Charset::Utf8
+        }
+    }
+
+    /// Appends the decoded `bytes` to `line`, returning the number of `U+FFFD` put in for
+    /// invalid bytes, `None` if there are some and `policy` is strict
+    pub(crate) fn decode(
+        self,
+        bytes: &[u8],
+        line: &mut String,
+        policy: DecodePolicy,
+    ) -> Option<usize> {
+        match self {
+            Charset::Utf8 => {
+                if let Ok(text) = std::str::from_utf8(bytes) {
+                    line.push_str(text);
+                    return Some(0);
+                }
+                if policy == DecodePolicy::Strict {
+                    return None;
+                }
+                let mut replaced = 0;
+                for chunk in bytes.utf8_chunks() {
+                    line.push_str(chunk.valid());
+                    if !chunk.invalid().is_empty() {
+                        line.push(char::REPLACEMENT_CHARACTER);
+                        replaced += 1;
+                    }
+                }
+                Some(replaced)
+            }
+            #[cfg(feature = "encoding")]
+            Charset::Other(encoding) => {
+                let (text, replaced) = encoding.decode_without_bom_handling(bytes);
+                line.push_str(&text);
+                Some(usize::from(replaced))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed with an en-dash and ® in windows-1252 and a stray invalid UTF-8 byte
+    const FEED: &[u8] = b"Axis Mutual Fund\r\n\
+        120465;INF846K01AB4;-;Axis Bluechip Fund \x96 Direct Plan\xae - Growth;61.43;21-Jun-2024\r\n\
+        120466;INF846K01AC2;INF846K01AD0;Axis Bluechip Fund - Direct Plan - IDCW;20.05;21-Jun-2024\r\n";
+
+    #[cfg(feature = "encoding")]
+    fn names(charset: Charset) -> Vec<crate::Result<String>> {
+        crate::NavRecordIterator::with_charset(FEED, charset)
+            .map(|record| record.map(|record| record.name))
+            .collect()
+    }
+
+    #[test]
+    fn charset_of_content_type() {
+        assert_eq!(Charset::of_content_type("text/plain"), Charset::Utf8);
+        assert_eq!(
+            Charset::of_content_type("text/plain; charset=UTF-8"),
+            Charset::Utf8
+        );
+        assert_eq!(
+            Charset::of_content_type("text/plain;format=flowed; Charset=\"utf-8\""),
+            Charset::Utf8
+        );
+        #[cfg(feature = "encoding")]
+        {
+            assert_eq!(
+                Charset::of_content_type("text/plain; charset=windows-1252"),
+                Charset::Other(encoding_rs::WINDOWS_1252)
+            );
+            assert_eq!(
+                Charset::of_content_type("text/plain; charset=utf-16"),
+                Charset::Utf8
+            );
+        }
+        assert_eq!(
+            Charset::of_content_type("text/plain; charset=klingon"),
+            Charset::Utf8
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let strict: Vec<_> = crate::NavRecordIterator::with_charset(FEED, Charset::Utf8).collect();
+        assert_eq!(strict.len(), 2);
+        assert!(matches!(
+            strict[0],
+            Err(crate::Error::InvalidUtf8 { at_line: 2 })
+        ));
+        assert!(strict[1].is_ok());
+
+        let mut records =
+            crate::NavRecordIterator::with_charset(FEED, Charset::Utf8).decode(DecodePolicy::Lossy);
+        let name = records.next().unwrap().unwrap().name;
+        assert_eq!(
+            name,
+            "Axis Bluechip Fund \u{FFFD} Direct Plan\u{FFFD} - Growth"
+        );
+        assert_eq!(records.lossy_lines(), 1);
+        assert_eq!(records.by_ref().count(), 1);
+        assert_eq!(records.stats().replaced_chars, 2);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn windows_1252() {
+        let names = names(Charset::of_content_type("text/plain; charset=windows-1252"));
+        assert_eq!(
+            names[0].as_ref().unwrap(),
+            "Axis Bluechip Fund \u{2013} Direct Plan\u{AE} - Growth"
+        );
+        assert!(names[1].is_ok());
+    }
+}
@@ -0,0 +1,139 @@
+//! Records of a feed in batches of a fixed size, see
+//! [`NavRecordIterator::chunks`](struct.NavRecordIterator.html#method.chunks)
+
+use crate::{Error, NavRecord, NavRecordIterator};
+use std::io::Read;
+
+/// Records parsed together, with the errors met while parsing them
+#[derive(Debug, Default)]
+pub struct Batch {
+    /// Records in feed order, as many as the chunk size except in the last batch
+    pub records: Vec<NavRecord>,
+    /// Errors of the lines between the first and last record, or after the last record in the
+    /// last batch, in feed order
+    pub errors: Vec<Error>,
+}
+
+impl Batch {
+    /// Whether the batch has neither records nor errors
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// Iterator over batches of records, see
+/// [`NavRecordIterator::chunks`](struct.NavRecordIterator.html#method.chunks)
+pub struct NavChunks<T: Read> {
+    records: NavRecordIterator<T>,
+    size: usize,
+}
+
+impl<T: Read> NavChunks<T> {
+    pub(crate) fn new(records: NavRecordIterator<T>, size: usize) -> Self {
+        assert!(size != 0, "chunk size must be non-zero");
+        NavChunks { records, size }
+    }
+}
+
+impl<T: Read> Iterator for NavChunks<T> {
+    type Item = Batch;
+
+    fn next(&mut self) -> Option<Batch> {
+        let mut batch = Batch::default();
+        while batch.records.len() < self.size {
+            match self.records.next() {
+                Some(Ok(record)) => batch.records.push(record),
+                Some(Err(error)) => batch.errors.push(error),
+                None => break,
+            }
+        }
+        if batch.is_empty() {
+            None
+        } else {
+            Some(batch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::fixture_path;
+    use crate::{nav_from_file, NavRecord, Result};
+
+    #[test]
+    fn chunks_keep_sections() {
+        let expected: Vec<NavRecord> = nav_from_file(fixture_path("NAVOpen.txt"))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        for size in &[1, 2, 3, expected.len(), expected.len() + 1] {
+            let batches: Vec<_> = nav_from_file(fixture_path("NAVOpen.txt"))
+                .unwrap()
+                .chunks(*size)
+                .collect();
+            assert_eq!(batches.len(), expected.len().div_ceil(*size));
+            let (last, full) = batches.split_last().unwrap();
+            assert!(full.iter().all(|batch| batch.records.len() == *size));
+            assert!(!last.records.is_empty() && last.records.len() <= *size);
+            assert!(batches.iter().all(|batch| batch.errors.is_empty()));
+
+            let records: Vec<NavRecord> = batches.into_iter().flat_map(|b| b.records).collect();
+            assert_eq!(records, expected);
+            for (record, expected) in records.iter().zip(&expected) {
+                assert_eq!(record.amc, expected.amc);
+                assert_eq!(record.category, expected.category);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_collect_errors() {
+        let results: Vec<_> = nav_from_file(fixture_path("NAVHalfGarbage.txt"))
+            .unwrap()
+            .collect();
+        let ok = results.iter().filter(|r| r.is_ok()).count();
+        let errors = results.len() - ok;
+        assert!(errors > 0);
+
+        let batches: Vec<_> = nav_from_file(fixture_path("NAVHalfGarbage.txt"))
+            .unwrap()
+            .chunks(2)
+            .collect();
+        assert_eq!(batches.iter().map(|b| b.records.len()).sum::<usize>(), ok);
+        assert_eq!(
+            batches.iter().map(|b| b.errors.len()).sum::<usize>(),
+            errors
+        );
+        assert!(batches.iter().all(|batch| !batch.is_empty()));
+
+        // Errors go in the batch being filled when they occur, a batch closing at its last record
+        let mut expected = vec![(Vec::new(), Vec::new())];
+        for result in results {
+            let (records, errors) = expected.last_mut().unwrap();
+            match result {
+                Ok(record) => records.push(record),
+                Err(error) => errors.push(error.to_string()),
+            }
+            if records.len() == 2 {
+                expected.push((Vec::new(), Vec::new()));
+            }
+        }
+        expected.retain(|(records, errors)| !records.is_empty() || !errors.is_empty());
+        let batches: Vec<_> = batches
+            .into_iter()
+            .map(|batch| {
+                let errors = batch.errors.iter().map(ToString::to_string).collect();
+                (batch.records, errors)
+            })
+            .collect();
+        assert_eq!(batches, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be non-zero")]
+    fn chunks_of_zero() {
+        let _ = nav_from_file(fixture_path("NAVOpen.txt"))
+            .unwrap()
+            .chunks(0);
+    }
+}
@@ -0,0 +1,70 @@
+//! AMFI scheme code
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// AMFI scheme code
+///
+/// Distinct type so that lookup and diff APIs can't be handed an arbitrary integer.
+/// Serializes as a plain number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SchemeCode(u32);
+
+impl SchemeCode {
+    /// Numeric value
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for SchemeCode {
+    fn from(code: u32) -> Self {
+        SchemeCode(code)
+    }
+}
+
+impl From<SchemeCode> for u32 {
+    fn from(code: SchemeCode) -> Self {
+        code.0
+    }
+}
+
+impl PartialEq<u32> for SchemeCode {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl FromStr for SchemeCode {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim().parse().map(SchemeCode)
+    }
+}
+
+impl fmt::Display for SchemeCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let code: SchemeCode = " 119551".parse().unwrap();
+        assert_eq!(code, SchemeCode::from(119_551));
+        assert_eq!(code, 119_551);
+        assert_eq!(code.to_string(), "119551");
+        assert!("11955a".parse::<SchemeCode>().is_err());
+        assert!(SchemeCode::from(100) < SchemeCode::from(20_000));
+    }
+}
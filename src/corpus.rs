@@ -0,0 +1,443 @@
+//! Sample feed lines and the records they parse into, for testing wrappers of the parser
+//!
+//! Each [`Case`](struct.Case.html) is a fragment of a feed, its section headers then one
+//! record line, gathered from the edge cases of published feeds: legacy layouts, rows
+//! without ISINs, bare scheme headers, segregated portfolios, NAVs that are no numbers,
+//! interval schemes and categories with parentheses. Crates wrapping the parser run the same
+//! expectations against their own output:
+//!
+//! ```rust
+//! for case in amfi::corpus::cases() {
+//!     let result = amfi::nav_from_str(case.input).last().unwrap();
+//!     if let Err(mismatch) = case.check(&result) {
+//!         panic!("{}: {}", case.name, mismatch);
+//!     }
+//! }
+//! ```
+//!
+//! Available with the `test-corpus` feature; the crate's own tests run every case.
+
+use crate::{Field, FundMaturity, FundPlan, NavRecord, Result};
+
+/// Feed fragment and what its record line parses into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Case {
+    /// Short name of the edge case, e.g. `legacy_blank_prices`
+    pub name: &'static str,
+    /// Section headers and one record line, the last line
+    pub input: &'static str,
+    /// Record of the last line, or the field its parse fails at
+    pub expected: std::result::Result<ExpectedRecord, Field>,
+}
+
+/// Fields of the record a [`Case`](struct.Case.html) parses into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExpectedRecord {
+    /// Scheme code
+    pub code: u32,
+    /// Payout or growth ISIN
+    pub isin: Option<&'static str>,
+    /// Reinvestment ISIN
+    pub isin_dr: Option<&'static str>,
+    /// Scheme name
+    pub name: &'static str,
+    /// NAV
+    pub nav: f64,
+    /// Repurchase price of the legacy layout
+    pub repurchase_price: Option<f64>,
+    /// Sale price of the legacy layout
+    pub sale_price: Option<f64>,
+    /// NAV date, `YYYY-MM-DD`
+    pub date: &'static str,
+    /// AMC
+    pub amc: &'static str,
+    /// Category
+    pub category: &'static str,
+    /// Scheme group of the category
+    pub scheme: Option<&'static str>,
+    /// Maturity
+    pub maturity: Option<FundMaturity>,
+    /// Plan
+    pub plan: FundPlan,
+}
+
+impl Case {
+    /// Checks `result`, the parse of the last line of [`input`](#structfield.input), against
+    /// the expectation, describing the first difference
+    pub fn check(&self, result: &Result<NavRecord>) -> std::result::Result<(), String> {
+        match (&self.expected, result) {
+            (Ok(expected), Ok(record)) => expected.check(record),
+            (Err(field), Err(crate::Error::ParseFailure { field: found, .. })) => {
+                if found == field {
+                    Ok(())
+                } else {
+                    Err(format!("failed at {} instead of {}", found, field))
+                }
+            }
+            (Ok(_), Err(error)) => Err(format!("failed: {}", error)),
+            (Err(field), Ok(record)) => Err(format!(
+                "parsed {} instead of failing at {}",
+                record.code, field
+            )),
+            (Err(field), Err(error)) => {
+                Err(format!("failed with `{}` instead of at {}", error, field))
+            }
+        }
+    }
+}
+
+impl ExpectedRecord {
+    /// Checks the fields of `record`, describing the first that differs
+    pub fn check(&self, record: &NavRecord) -> std::result::Result<(), String> {
+        fn differs<T: PartialEq + std::fmt::Debug>(
+            field: &str,
+            expected: T,
+            found: T,
+        ) -> std::result::Result<(), String> {
+            if expected == found {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{}: expected {:?}, found {:?}",
+                    field, expected, found
+                ))
+            }
+        }
+        fn isin(isin: &Option<crate::RawIsin>) -> Option<&str> {
+            isin.as_ref().map(|isin| isin.as_str())
+        }
+        differs("code", self.code, record.code.get())?;
+        differs("isin", self.isin, isin(&record.isin))?;
+        differs("isin_dr", self.isin_dr, isin(&record.isin_dr))?;
+        differs("name", self.name, &record.name)?;
+        differs("nav", self.nav, record.nav)?;
+        differs(
+            "repurchase_price",
+            self.repurchase_price,
+            record.repurchase_price,
+        )?;
+        differs("sale_price", self.sale_price, record.sale_price)?;
+        differs("date", self.date, &record.date.to_string())?;
+        differs("amc", self.amc, &record.amc)?;
+        differs("category", self.category, &record.category)?;
+        differs("scheme", self.scheme, record.scheme.as_deref())?;
+        differs("maturity", self.maturity, record.maturity)?;
+        differs("plan", self.plan, record.plan)
+    }
+}
+
+/// Record of the Axis Bluechip Fund Direct Growth line of 21 June 2024, varied by the cases
+const BLUECHIP: ExpectedRecord = ExpectedRecord {
+    code: 120_465,
+    isin: Some("INF846K01AB4"),
+    isin_dr: None,
+    name: "Axis Bluechip Fund - Direct Plan - Growth",
+    nav: 61.43,
+    repurchase_price: None,
+    sale_price: None,
+    date: "2024-06-21",
+    amc: "Axis Mutual Fund",
+    category: "Large Cap Fund",
+    scheme: Some("Equity Scheme"),
+    maturity: Some(FundMaturity::OpenEnded),
+    plan: FundPlan::Direct,
+};
+
+static CASES: &[Case] = &[
+    Case {
+        name: "modern",
+        input: "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.4300;21-Jun-2024\n",
+        expected: Ok(BLUECHIP),
+    },
+    Case {
+        name: "crlf_and_trailing_separators",
+        input: "Open Ended Schemes(Equity Scheme - Large Cap Fund)\r\n\r\n\
+                Axis Mutual Fund\r\n\r\n\
+                120465 ; INF846K01AB4 ; - ; Axis Bluechip Fund - Direct Plan - Growth ; 61.4300 ; 21-Jun-2024;;\r\n",
+        expected: Ok(BLUECHIP),
+    },
+    Case {
+        name: "both_isins",
+        input: "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120466;INF846K01AC2;INF846K01AD0;Axis Bluechip Fund - Direct Plan - IDCW;21.9800;21-Jun-2024\n",
+        expected: Ok(ExpectedRecord {
+            code: 120_466,
+            isin: Some("INF846K01AC2"),
+            isin_dr: Some("INF846K01AD0"),
+            name: "Axis Bluechip Fund - Direct Plan - IDCW",
+            nav: 21.98,
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "isin_less",
+        input: "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120465;-;---;Axis Bluechip Fund - Direct Plan - Growth;61.4300;21-Jun-2024\n",
+        expected: Ok(ExpectedRecord {
+            isin: None,
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "segregated_portfolio",
+        input: "Open Ended Schemes(Debt Scheme - Credit Risk Fund)\n\
+                Axis Mutual Fund\n\
+                149090;-;-;Axis Credit Risk Fund - Segregated Portfolio 1 - Direct Plan - Growth;0.0000;21-Jun-2024\n",
+        expected: Ok(ExpectedRecord {
+            code: 149_090,
+            isin: None,
+            name: "Axis Credit Risk Fund - Segregated Portfolio 1 - Direct Plan - Growth",
+            nav: 0.0,
+            category: "Credit Risk Fund",
+            scheme: Some("Debt Scheme"),
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "na_nav",
+        input: "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;N.A.;21-Jun-2024\n",
+        expected: Err(Field::Nav),
+    },
+    Case {
+        name: "negative_nav",
+        input: "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;-61.4300;21-Jun-2024\n",
+        expected: Err(Field::Nav),
+    },
+    Case {
+        name: "interval_fund",
+        input: "Interval Fund Schemes(Income)\n\
+                Axis Mutual Fund\n\
+                118544;INF846K01QW6;-;Axis Fixed Income Opportunities Interval Fund - Direct Plan - Growth;13.2050;21-Jun-2024\n",
+        expected: Ok(ExpectedRecord {
+            code: 118_544,
+            isin: Some("INF846K01QW6"),
+            name: "Axis Fixed Income Opportunities Interval Fund - Direct Plan - Growth",
+            nav: 13.205,
+            category: "Income",
+            scheme: None,
+            maturity: None,
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "nested_parentheses_category",
+        input: "Open Ended Schemes(Other Scheme - FoF (Overseas))\n\
+                Axis Mutual Fund\n\
+                149339;INF846K01YH1;-;Axis Global Innovation Fund of Fund - Direct Plan - Growth;12.5100;21-Jun-2024\n",
+        expected: Ok(ExpectedRecord {
+            code: 149_339,
+            isin: Some("INF846K01YH1"),
+            name: "Axis Global Innovation Fund of Fund - Direct Plan - Growth",
+            nav: 12.51,
+            category: "FoF (Overseas)",
+            scheme: Some("Other Scheme"),
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "semicolon_in_name",
+        input: "Close Ended Schemes(Income)\n\
+                Axis Mutual Fund\n\
+                100001;INF846K01ZZ0;-;Axis Fixed Term Plan; Series 1 - Growth;10.0100;02-Jan-2012\n",
+        expected: Ok(ExpectedRecord {
+            code: 100_001,
+            isin: Some("INF846K01ZZ0"),
+            name: "Axis Fixed Term Plan; Series 1 - Growth",
+            nav: 10.01,
+            date: "2012-01-02",
+            category: "Income",
+            scheme: None,
+            maturity: Some(FundMaturity::CloseEnded),
+            plan: FundPlan::Regular,
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "legacy_prices",
+        input: "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date\n\
+                Open Ended Schemes(Income)\n\
+                Axis Mutual Fund\n\
+                100027;INF846K01AE8;-;Axis Income Fund - Growth;14.2100;14.0700;14.3500;02-Jan-2009\n",
+        expected: Ok(ExpectedRecord {
+            code: 100_027,
+            isin: Some("INF846K01AE8"),
+            name: "Axis Income Fund - Growth",
+            nav: 14.21,
+            repurchase_price: Some(14.07),
+            sale_price: Some(14.35),
+            date: "2009-01-02",
+            category: "Income",
+            scheme: None,
+            plan: FundPlan::Regular,
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "legacy_blank_prices",
+        input: "Open Ended Schemes(Income)\n\
+                Axis Mutual Fund\n\
+                100027;INF846K01AE8;-;Axis Income Fund - Growth;14.2100;;N.A.;02-Jan-2009\n",
+        expected: Ok(ExpectedRecord {
+            code: 100_027,
+            isin: Some("INF846K01AE8"),
+            name: "Axis Income Fund - Growth",
+            nav: 14.21,
+            date: "2009-01-02",
+            category: "Income",
+            scheme: None,
+            plan: FundPlan::Regular,
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "legacy_placeholder_prices",
+        input: "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date\n\
+                Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.4300; - ;na;21-Jun-2024\n",
+        expected: Ok(BLUECHIP),
+    },
+    Case {
+        name: "legacy_bad_price",
+        input: "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date\n\
+                Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.4300;61.43x;;21-Jun-2024\n",
+        expected: Err(Field::RepurchasePrice),
+    },
+    Case {
+        name: "bare_scheme_header",
+        input: "Close Ended Schemes\n\
+                Axis Mutual Fund\n\
+                100001;INF846K01ZZ0;-;Axis Fixed Term Plan Series 1 - Growth;10.0100;02-Jan-2012\n",
+        expected: Ok(ExpectedRecord {
+            code: 100_001,
+            isin: Some("INF846K01ZZ0"),
+            name: "Axis Fixed Term Plan Series 1 - Growth",
+            nav: 10.01,
+            date: "2012-01-02",
+            category: "",
+            scheme: None,
+            maturity: Some(FundMaturity::CloseEnded),
+            plan: FundPlan::Regular,
+            ..BLUECHIP
+        }),
+    },
+    Case {
+        name: "bad_date",
+        input: "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                Axis Mutual Fund\n\
+                120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.4300;31-Jun-2024\n",
+        expected: Err(Field::Date),
+    },
+];
+
+/// Every case of the corpus
+pub fn cases() -> &'static [Case] {
+    CASES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_case_parses_as_expected() {
+        for case in cases() {
+            let results: Vec<_> = crate::nav_from_str(case.input).collect();
+            assert_eq!(results.len(), 1, "{}: {:?}", case.name, results);
+            if let Err(mismatch) = case.check(&results[0]) {
+                panic!("{}: {}", case.name, mismatch);
+            }
+        }
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        for case in cases() {
+            let result = crate::nav_from_reader(case.input.as_bytes())
+                .with_buffer_capacity(1)
+                .last()
+                .unwrap();
+            if let Err(mismatch) = case.check(&result) {
+                panic!("{}: {}", case.name, mismatch);
+            }
+        }
+    }
+
+    #[test]
+    fn line_by_line() {
+        for case in cases() {
+            let mut records = crate::nav_from_str(case.input);
+            let parsed = records.next().unwrap();
+            let line = case.input.lines().last().unwrap();
+            let result = crate::parse_record_line(line, crate::Layout::of_record(line))
+                .map(|partial| partial.into_record(records.current_section()));
+            if let Err(mismatch) = case.check(&result) {
+                panic!("{}: {}", case.name, mismatch);
+            }
+            match (parsed, result) {
+                (Ok(mut parsed), Ok(record)) => {
+                    parsed.source_line = None;
+                    assert_eq!(parsed, record, "{}", case.name);
+                }
+                (
+                    Err(crate::Error::ParseFailure { at_byte, .. }),
+                    Err(crate::Error::ParseFailure {
+                        at_byte: at, line, ..
+                    }),
+                ) => assert_eq!((at_byte, line), (at, None), "{}", case.name),
+                other => panic!("{}: {:?}", case.name, other),
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn in_parallel() {
+        for case in cases() {
+            let mut feed = crate::parse_parallel(case.input);
+            let result = match (feed.records.pop(), feed.errors.pop()) {
+                (Some(record), None) => Ok(record),
+                (None, Some((_, error))) => Err(error),
+                other => panic!("{}: {:?}", case.name, other),
+            };
+            if let Err(mismatch) = case.check(&result) {
+                panic!("{}: {}", case.name, mismatch);
+            }
+        }
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut names: Vec<_> = cases().iter().map(|case| case.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), cases().len());
+    }
+
+    #[test]
+    fn mismatches_are_described() {
+        let case = &cases()[0];
+        let mut record = crate::nav_from_str(case.input).next().unwrap().unwrap();
+        record.nav = 61.44;
+        assert_eq!(
+            case.check(&Ok(record)).unwrap_err(),
+            "nav: expected 61.43, found 61.44"
+        );
+        let na = cases().iter().find(|case| case.name == "na_nav").unwrap();
+        assert_eq!(
+            na.check(&crate::nav_from_str(case.input).next().unwrap())
+                .unwrap_err(),
+            "parsed 120465 instead of failing at nav"
+        );
+    }
+}
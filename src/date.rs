@@ -0,0 +1,527 @@
+//! Calendar date of a NAV, independent of any date library
+//!
+//! [`NavDate`](struct.NavDate.html) converts to and from `chrono::NaiveDate` with the
+//! `chrono` feature and `time::Date` with the `time` feature.
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Days from 0000-03-01 to 1970-01-01, see `epoch_days`
+const EPOCH_SHIFT: i64 = 719_468;
+
+/// Offset of Indian Standard Time from UTC, in seconds
+pub(crate) const IST_OFFSET: i32 = 5 * 3600 + 30 * 60;
+
+/// Calendar date of a NAV, years 1 to 9999
+///
+/// Displays and parses (`FromStr`) as ISO 8601, e.g. `2024-06-21`; the feed writes dates as
+/// `21-Jun-2024`, see [`parse_feed`](#method.parse_feed) and
+/// [`feed_format`](#method.feed_format).
+///
+/// ```rust
+/// use amfi::NavDate;
+///
+/// let date = NavDate::from_ymd(2024, 6, 21).unwrap();
+/// assert_eq!(NavDate::parse_feed("21-Jun-2024"), Ok(date));
+/// assert_eq!(date.to_string(), "2024-06-21");
+/// assert_eq!(date.feed_format().to_string(), "21-Jun-2024");
+/// assert_eq!(date.epoch_days(), 19_895);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NavDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+/// Error parsing or converting a [`NavDate`](struct.NavDate.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NavDateError {
+    /// Text is not a date in the expected format
+    Invalid(String),
+    /// Date is before year 1 or after year 9999
+    OutOfRange(String),
+}
+
+impl fmt::Display for NavDateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NavDateError::Invalid(ref text) => write!(f, "invalid date `{}`", text),
+            NavDateError::OutOfRange(ref date) => write!(f, "date `{}` is out of range", date),
+        }
+    }
+}
+
+impl std::error::Error for NavDateError {}
+
+impl NavDate {
+    /// Date from year, month (1 to 12) and day, `None` if there is no such date
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<NavDate> {
+        let valid = (1..=9999).contains(&year)
+            && (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month);
+        if valid {
+            Some(NavDate {
+                year: year as u16,
+                month: month as u8,
+                day: day as u8,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Year
+    pub fn year(&self) -> i32 {
+        i32::from(self.year)
+    }
+
+    /// Month, 1 to 12
+    pub fn month(&self) -> u32 {
+        u32::from(self.month)
+    }
+
+    /// Day of the month, starting at 1
+    pub fn day(&self) -> u32 {
+        u32::from(self.day)
+    }
+
+    /// Signed number of days since 1970-01-01
+    pub fn epoch_days(&self) -> i64 {
+        // Days from civil, Howard Hinnant's algorithm on years starting in March
+        let (month, day) = (i64::from(self.month), i64::from(self.day));
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year / 400;
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - EPOCH_SHIFT
+    }
+
+    /// Date `days` after 1970-01-01, `None` out of range
+    pub fn from_epoch_days(days: i64) -> Option<NavDate> {
+        // Years 1 to 9999, checked before the arithmetic can overflow
+        if !(-719_162..=2_932_896).contains(&days) {
+            return None;
+        }
+        let days = days + EPOCH_SHIFT;
+        let era = days / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        NavDate::from_ymd(year as i32, month as u32, day as u32)
+    }
+
+    /// Unix timestamp of midnight at the start of this date in Indian Standard Time
+    ///
+    /// NAV dates are Indian business days, so the day starts at midnight IST, 18:30 UTC on the
+    /// previous day. UTC midnight would be 05:30 IST and a timestamp read back in a timezone
+    /// west of UTC would land on the previous calendar day.
+    ///
+    /// ```rust
+    /// let date = amfi::NavDate::from_ymd(2024, 7, 1).unwrap();
+    /// // 2024-06-30T18:30:00Z
+    /// assert_eq!(date.unix_midnight_ist(), 1_719_772_200);
+    /// ```
+    pub fn unix_midnight_ist(&self) -> i64 {
+        self.epoch_days() * 86_400 - i64::from(IST_OFFSET)
+    }
+
+    /// Date in Indian Standard Time at Unix timestamp `seconds`, `None` out of range
+    pub fn from_unix_ist(seconds: i64) -> Option<NavDate> {
+        let local = seconds.checked_add(i64::from(IST_OFFSET))?;
+        NavDate::from_epoch_days(local.div_euclid(86_400))
+    }
+
+    /// Current date in India by the system clock
+    pub(crate) fn today() -> NavDate {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() as i64);
+        NavDate::from_unix_ist(seconds).expect("system clock before year 10000")
+    }
+
+    /// The next day, `None` after 9999-12-31
+    pub fn succ(&self) -> Option<NavDate> {
+        NavDate::from_epoch_days(self.epoch_days() + 1)
+    }
+
+    /// Parses the feed format `DD-Mon-YYYY`, e.g. `21-Jun-2024`
+    ///
+    /// Month names are English, in any case, abbreviated or in full (`21-june-2024`); the
+    /// day may have one digit.
+    pub fn parse_feed(text: &str) -> Result<NavDate, NavDateError> {
+        let invalid = || NavDateError::Invalid(text.to_string());
+        let mut parts = text.splitn(3, '-');
+        let (day, month, year) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(day), Some(month), Some(year)) => (day, month, year),
+            _ => return Err(invalid()),
+        };
+        let month = MONTHS
+            .iter()
+            .position(|name| {
+                month.eq_ignore_ascii_case(name) || month.eq_ignore_ascii_case(&name[..3])
+            })
+            .ok_or_else(invalid)?;
+        let date = match (number(day, 1, 2), number(year, 4, 4)) {
+            (Some(day), Some(year)) => NavDate::from_ymd(year as i32, month as u32 + 1, day),
+            _ => None,
+        };
+        date.ok_or_else(invalid)
+    }
+
+    /// Displays as in the feed, e.g. `21-Jun-2024`
+    pub fn feed_format(self) -> impl fmt::Display {
+        FeedFormat(self)
+    }
+}
+
+/// Number of `min` to `max` ASCII digits
+fn number(text: &str, min: usize, max: usize) -> Option<u32> {
+    let digits = text.len() >= min && text.len() <= max;
+    if digits && text.bytes().all(|byte| byte.is_ascii_digit()) {
+        text.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+struct FeedFormat(NavDate);
+
+impl fmt::Display for FeedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let date = self.0;
+        let month = &MONTHS[date.month as usize - 1][..3];
+        write!(f, "{:02}-{}-{:04}", date.day, month, date.year)
+    }
+}
+
+impl fmt::Display for NavDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl fmt::Debug for NavDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Parses ISO 8601 `YYYY-MM-DD`, month and day may have one digit
+impl FromStr for NavDate {
+    type Err = NavDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '-');
+        let date = match (parts.next(), parts.next(), parts.next()) {
+            (Some(year), Some(month), Some(day)) => {
+                match (number(year, 4, 4), number(month, 1, 2), number(day, 1, 2)) {
+                    (Some(year), Some(month), Some(day)) => {
+                        NavDate::from_ymd(year as i32, month, day)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        date.ok_or_else(|| NavDateError::Invalid(s.to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<NavDate> for chrono::NaiveDate {
+    fn from(date: NavDate) -> Self {
+        chrono::NaiveDate::from_ymd_opt(date.year(), date.month(), date.day())
+            .expect("years 1 to 9999 are in chrono's range")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::convert::TryFrom<chrono::NaiveDate> for NavDate {
+    type Error = NavDateError;
+
+    fn try_from(date: chrono::NaiveDate) -> Result<Self, Self::Error> {
+        use chrono::Datelike;
+        NavDate::from_ymd(date.year(), date.month(), date.day())
+            .ok_or_else(|| NavDateError::OutOfRange(date.to_string()))
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<NavDate> for time::Date {
+    fn from(date: NavDate) -> Self {
+        use std::convert::TryFrom;
+        let month = time::Month::try_from(date.month).expect("month is 1 to 12");
+        time::Date::from_calendar_date(date.year(), month, date.day)
+            .expect("years 1 to 9999 are in time's range")
+    }
+}
+
+#[cfg(feature = "time")]
+impl std::convert::TryFrom<time::Date> for NavDate {
+    type Error = NavDateError;
+
+    fn try_from(date: time::Date) -> Result<Self, Self::Error> {
+        let month = u8::from(date.month());
+        NavDate::from_ymd(date.year(), u32::from(month), u32::from(date.day()))
+            .ok_or_else(|| NavDateError::OutOfRange(date.to_string()))
+    }
+}
+
+/// ISO 8601 string, e.g. `"2024-06-21"`
+#[cfg(feature = "serde")]
+impl Serialize for NavDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for NavDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
+
+/// String of format `date`, as serialized
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for NavDate {
+    fn schema_name() -> String {
+        "NavDate".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            format: Some("date".to_string()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch_days_round_trip() {
+        let epoch = NavDate::from_ymd(1970, 1, 1).unwrap();
+        assert_eq!(epoch.epoch_days(), 0);
+        assert_eq!(NavDate::from_epoch_days(0), Some(epoch));
+        let first = NavDate::from_ymd(1, 1, 1).unwrap();
+        let last = NavDate::from_ymd(9999, 12, 31).unwrap();
+        assert_eq!(NavDate::from_epoch_days(first.epoch_days()), Some(first));
+        assert_eq!(NavDate::from_epoch_days(last.epoch_days()), Some(last));
+        assert_eq!(NavDate::from_epoch_days(first.epoch_days() - 1), None);
+        assert_eq!(last.succ(), None);
+        assert_eq!(NavDate::from_epoch_days(i64::MIN), None);
+
+        // Consecutive days across month, leap and century boundaries
+        let mut date = NavDate::from_ymd(1899, 12, 1).unwrap();
+        for days in date.epoch_days()..NavDate::from_ymd(2101, 3, 1).unwrap().epoch_days() {
+            assert_eq!(date.epoch_days(), days);
+            let next = date.succ().unwrap();
+            assert!(next > date);
+            date = next;
+        }
+        assert_eq!(NavDate::from_ymd(2000, 2, 29).unwrap().epoch_days(), 11_016);
+        assert_eq!(NavDate::from_ymd(1900, 2, 29), None);
+    }
+
+    #[test]
+    fn feed_format() {
+        let date = NavDate::from_ymd(2024, 6, 1).unwrap();
+        assert_eq!(date.feed_format().to_string(), "01-Jun-2024");
+        for text in &["01-Jun-2024", "1-jun-2024", "01-JUNE-2024"] {
+            assert_eq!(NavDate::parse_feed(text), Ok(date), "{}", text);
+        }
+        for text in &[
+            "",
+            "01-Jun",
+            "32-Jun-2024",
+            "01-Jux-2024",
+            "01-Jun-24",
+            "+1-Jun-2024",
+        ] {
+            assert_eq!(
+                NavDate::parse_feed(text),
+                Err(NavDateError::Invalid(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn month_boundary_timestamps() {
+        let june_30 = NavDate::from_ymd(2024, 6, 30).unwrap();
+        let july_1 = NavDate::from_ymd(2024, 7, 1).unwrap();
+        assert_eq!(
+            (june_30.epoch_days(), july_1.epoch_days()),
+            (19_904, 19_905)
+        );
+        assert_eq!(june_30.unix_midnight_ist(), 1_719_685_800);
+        assert_eq!(july_1.unix_midnight_ist(), 1_719_772_200);
+        assert_eq!(
+            NavDate::from_unix_ist(july_1.unix_midnight_ist()),
+            Some(july_1)
+        );
+        assert_eq!(NavDate::from_unix_ist(1_719_772_199), Some(june_30));
+        // UTC midnight of July 1st is still July 1st in India
+        assert_eq!(NavDate::from_unix_ist(19_905 * 86_400), Some(july_1));
+        let first = NavDate::from_ymd(1, 1, 1).unwrap();
+        assert_eq!(
+            NavDate::from_unix_ist(first.unix_midnight_ist()),
+            Some(first)
+        );
+        assert_eq!(NavDate::from_unix_ist(first.unix_midnight_ist() - 1), None);
+        assert_eq!(NavDate::from_unix_ist(i64::MAX), None);
+    }
+
+    #[test]
+    fn iso_format() {
+        let date = NavDate::from_ymd(2024, 6, 21).unwrap();
+        assert_eq!(date.to_string(), "2024-06-21");
+        assert_eq!(format!("{:?}", date), "2024-06-21");
+        assert_eq!("2024-06-21".parse(), Ok(date));
+        assert_eq!("2024-6-21".parse(), Ok(date));
+        assert!("2024-06-31".parse::<NavDate>().is_err());
+        assert!("21-06-2024".parse::<NavDate>().is_err());
+    }
+
+    /// Checks a date backend against `NavDate`: fixture dates, parsed by the backend itself,
+    /// convert both ways and serialize identically with every `amfi::serde` helper
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    macro_rules! backend_tests {
+        ($name:ident, $date:ty, $parse:expr) => {
+            mod $name {
+                use super::NavDate;
+                use std::convert::TryFrom;
+
+                type Date = $date;
+
+                fn fixture_dates() -> Vec<(NavDate, Date)> {
+                    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+                    let records = crate::nav_from_file(path).unwrap();
+                    let mut texts: Vec<String> = records
+                        .map(|record| record.unwrap().date.feed_format().to_string())
+                        .collect();
+                    texts.extend(
+                        [
+                            "01-Jan-0001",
+                            "29-Feb-2000",
+                            "31-Dec-1999",
+                            "01-Mar-2100",
+                            "31-Dec-9999",
+                        ]
+                        .iter()
+                        .map(|text| text.to_string()),
+                    );
+                    let parse: fn(&str) -> Date = $parse;
+                    texts
+                        .iter()
+                        .map(|text| (NavDate::parse_feed(text).unwrap(), parse(text)))
+                        .collect()
+                }
+
+                #[test]
+                fn parse_and_convert() {
+                    for (date, backend) in fixture_dates() {
+                        assert_eq!(Date::from(date), backend);
+                        assert_eq!(NavDate::try_from(backend), Ok(date));
+                        assert_eq!(date.to_string(), backend.to_string());
+                    }
+                }
+
+                #[cfg(feature = "serde")]
+                #[test]
+                fn serde_helpers() {
+                    use crate::serde::{
+                        date_ddmmmyyyy, date_epoch_days, date_iso, date_unix_midnight_ist,
+                    };
+                    use serde_json::value::Serializer;
+
+                    for (date, backend) in fixture_dates() {
+                        let iso = date_iso::serialize(&date, Serializer).unwrap();
+                        assert_eq!(date_iso::serialize(&backend, Serializer).unwrap(), iso);
+                        let parsed: Date = date_iso::deserialize(iso).unwrap();
+                        assert_eq!(parsed, backend);
+
+                        let feed = date_ddmmmyyyy::serialize(&date, Serializer).unwrap();
+                        assert_eq!(
+                            date_ddmmmyyyy::serialize(&backend, Serializer).unwrap(),
+                            feed
+                        );
+                        let parsed: Date = date_ddmmmyyyy::deserialize(feed).unwrap();
+                        assert_eq!(parsed, backend);
+
+                        let days = date_epoch_days::serialize(&date, Serializer).unwrap();
+                        assert_eq!(
+                            date_epoch_days::serialize(&backend, Serializer).unwrap(),
+                            days
+                        );
+                        let parsed: Date = date_epoch_days::deserialize(days).unwrap();
+                        assert_eq!(parsed, backend);
+
+                        let seconds = date_unix_midnight_ist::serialize(&date, Serializer).unwrap();
+                        assert_eq!(
+                            date_unix_midnight_ist::serialize(&backend, Serializer).unwrap(),
+                            seconds
+                        );
+                        let parsed: Date = date_unix_midnight_ist::deserialize(seconds).unwrap();
+                        assert_eq!(parsed, backend);
+                    }
+                }
+            }
+        };
+    }
+
+    #[cfg(feature = "chrono")]
+    backend_tests!(chrono_tests, chrono::NaiveDate, |text| {
+        chrono::NaiveDate::parse_from_str(text, "%d-%b-%Y").unwrap()
+    });
+
+    #[cfg(feature = "time")]
+    backend_tests!(time_tests, time::Date, |text| {
+        let format = time::macros::format_description!("[day]-[month repr:short]-[year]");
+        time::Date::parse(text, format).unwrap()
+    });
+}
@@ -0,0 +1,175 @@
+//! Day-over-day NAV changes between two snapshots
+
+use crate::{NavIndex, NavRecord, SchemeCode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Old NAVs closer to zero than this have no percent change
+const MIN_NAV: f64 = 1e-6;
+
+/// NAV change of one scheme
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NavDelta {
+    /// Scheme code in the new snapshot
+    pub code: SchemeCode,
+    /// Scheme name in the new snapshot
+    pub name: String,
+    /// NAV in the old snapshot
+    pub old_nav: f64,
+    /// NAV in the new snapshot
+    pub new_nav: f64,
+    /// `new_nav - old_nav`
+    pub abs_change: f64,
+    /// Change in percent of `old_nav`, `None` when `old_nav` is (nearly) zero
+    pub pct_change: Option<f64>,
+    /// Days from the old to the new NAV date
+    pub days_between: i64,
+}
+
+impl NavDelta {
+    fn new(old: &NavRecord, new: &NavRecord) -> Self {
+        let abs_change = new.nav - old.nav;
+        let pct_change = Some(abs_change / old.nav * 100.0)
+            .filter(|pct| old.nav.abs() >= MIN_NAV && pct.is_finite());
+        NavDelta {
+            code: new.code,
+            name: new.name.clone(),
+            old_nav: old.nav,
+            new_nav: new.nav,
+            abs_change,
+            pct_change,
+            days_between: new.date.epoch_days() - old.date.epoch_days(),
+        }
+    }
+}
+
+/// Result of [`diff_navs`](fn.diff_navs.html)
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NavDiff {
+    /// Schemes present in both snapshots, in old scheme code order
+    pub deltas: Vec<NavDelta>,
+    /// Schemes only in the new snapshot, in scheme code order
+    pub added: Vec<NavRecord>,
+    /// Schemes only in the old snapshot, in scheme code order
+    pub removed: Vec<NavRecord>,
+}
+
+/// Compares NAVs of two snapshots
+///
+/// Schemes are matched by code. A scheme whose code is missing from the other snapshot is
+/// matched by ISIN (growth or reinvestment) instead, provided that record's code is not in
+/// the old snapshot itself nor matched already. Unmatched schemes are reported as added or
+/// removed.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let old: amfi::NavIndex = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+///     .filter_map(Result::ok)
+///     .collect();
+/// let diff = amfi::diff_navs(&old, &old);
+/// assert!(diff.deltas.iter().all(|delta| delta.abs_change == 0.0));
+/// assert!(diff.added.is_empty() && diff.removed.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub fn diff_navs(old: &NavIndex, new: &NavIndex) -> NavDiff {
+    let mut diff = NavDiff::default();
+    let mut matched = HashSet::new();
+    for record in old {
+        let counterpart = new.get_by_code(record.code).or_else(|| {
+            record
+                .isin
+                .iter()
+                .chain(record.isin_dr.iter())
+                .filter_map(|isin| new.get_by_isin(isin.as_str()))
+                .find(|found| {
+                    old.get_by_code(found.code).is_none() && !matched.contains(&found.code)
+                })
+        });
+        match counterpart {
+            Some(counterpart) => {
+                matched.insert(counterpart.code);
+                diff.deltas.push(NavDelta::new(record, counterpart));
+            }
+            None => diff.removed.push(record.clone()),
+        }
+    }
+    diff.added = new
+        .iter()
+        .filter(|record| !matched.contains(&record.code))
+        .cloned()
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+
+    #[test]
+    fn changes_added_and_removed() {
+        let old = fixture_records("NAVOpen.txt");
+        let mut new = old.clone();
+        for record in &mut new {
+            record.date = record.date.succ().unwrap();
+        }
+        new[0].nav = 67.573;
+        let removed = new.remove(1).code;
+        let mut renumbered = new.pop().unwrap();
+        renumbered.code = 999_999.into();
+        new.push(renumbered);
+        let mut added = new[0].clone();
+        added.code = 1.into();
+        added.isin = None;
+        new.push(added);
+
+        let diff = diff_navs(&old.into_iter().collect(), &new.into_iter().collect());
+        assert_eq!(diff.deltas.len(), 29);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].code, removed);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].code, 1);
+
+        let delta = diff.deltas.iter().find(|d| d.code == 120_465).unwrap();
+        assert!((delta.abs_change - 6.143).abs() < 1e-9);
+        assert!((delta.pct_change.unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(delta.days_between, 1);
+        assert!(diff.deltas.iter().any(|d| d.code == 999_999));
+    }
+
+    #[test]
+    fn shared_isin_matches_once() {
+        let old = fixture_records("NAVOpen.txt");
+        let mut first = old[0].clone().with_code(1);
+        first.isin_dr = None;
+        let mut second = first.clone().with_code(2);
+        second.nav = 1.0;
+        let renumbered = old[0].clone().with_code(3);
+        let old: NavIndex = vec![first, second].into_iter().collect();
+        let new: NavIndex = Some(renumbered).into_iter().collect();
+
+        let diff = diff_navs(&old, &new);
+        assert_eq!(diff.deltas.len(), 1);
+        assert_eq!(diff.deltas[0].code, 3);
+        assert_eq!(diff.deltas[0].old_nav, old.get_by_code(1).unwrap().nav);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].code, 2);
+        assert!(diff.added.is_empty());
+    }
+
+    #[test]
+    fn zero_old_nav_has_no_pct_change() {
+        let old: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        let delta = diff_navs(&old, &old)
+            .deltas
+            .into_iter()
+            .find(|delta| delta.code == 149_003)
+            .unwrap();
+        assert_eq!(delta.old_nav, 0.0);
+        assert_eq!(delta.pct_change, None);
+    }
+}
@@ -0,0 +1,350 @@
+//! Downloading the feed to a file, resuming downloads cut short
+
+use crate::fetch::response_error;
+use crate::{Error, FetchMeta, Result, BASE_URL};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{self, HeaderMap};
+use reqwest::StatusCode;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Downloads the feed of the [AMFI](https://www.amfiindia.com) portal to the file at `path`
+///
+/// See [`download_nav_at`](fn.download_nav_at.html).
+pub fn download_nav_to<P: AsRef<Path>>(path: P) -> Result<FetchMeta> {
+    download_nav_at(BASE_URL, path)
+}
+
+/// Downloads the feed at `url` to the file at `path`, returning its metadata
+///
+/// The feed is written to `<path>.part` and renamed to `path` once its size is that the
+/// server announced. A download cut short keeps the part, and the next call resumes it with
+/// a `Range` request bound by `If-Range` to the `ETag`, or else `Last-Modified`, of the
+/// response it started from, kept in `<path>.part.validator`. The download restarts from
+/// the first byte if the feed changed since, if the server ignores `Range` and sends it
+/// whole, or if the first response had no validator to bind to.
+///
+/// An interrupted body fails with [`Error::IoError`](enum.Error.html#variant.IoError), a
+/// body ending short of its announced size with
+/// [`Error::IncompleteDownload`](enum.Error.html#variant.IncompleteDownload); either way the
+/// part is kept for the next call.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// let meta = amfi::download_nav_at("https://portal.amfiindia.com/spages/NAVAll.txt", "NAVAll.txt")?;
+/// println!("{:?} bytes", meta.content_length);
+/// let records = amfi::nav_from_file("NAVAll.txt")?;
+/// # drop(records);
+/// # Ok(())
+/// # }
+/// ```
+pub fn download_nav_at<T, P>(url: T, path: P) -> Result<FetchMeta>
+where
+    T: AsRef<str>,
+    P: AsRef<Path>,
+{
+    let url = url.as_ref();
+    let path = path.as_ref();
+    let part = with_suffix(path, ".part");
+    let validator_path = with_suffix(path, ".part.validator");
+    let client = Client::new();
+
+    let resume = match (fs::metadata(&part), fs::read_to_string(&validator_path)) {
+        (Ok(metadata), Ok(validator)) if metadata.len() > 0 && !validator.trim().is_empty() => {
+            Some((metadata.len(), validator.trim().to_string()))
+        }
+        _ => None,
+    };
+    let (mut response, start) = match resume {
+        Some((len, validator)) => {
+            trace_event!(info, url, bytes = len, "resuming download");
+            let response = client
+                .get(url)
+                .header(header::RANGE, format!("bytes={}-", len))
+                .header(header::IF_RANGE, validator.as_str())
+                .send()?;
+            if resumes(&response, len, &validator) {
+                (response, len)
+            } else if response.status() == StatusCode::PARTIAL_CONTENT
+                || response.status() == StatusCode::RANGE_NOT_SATISFIABLE
+            {
+                // Not the rest of the part, start over
+                (client.get(url).send()?, 0)
+            } else {
+                // Range ignored, or the feed changed
+                (response, 0)
+            }
+        }
+        None => (client.get(url).send()?, 0),
+    };
+    if !response.status().is_success() {
+        return Err(response_error(response, url));
+    }
+
+    let meta = FetchMeta::from_headers(response.headers());
+    let mut file = if start > 0 {
+        OpenOptions::new().append(true).open(&part)?
+    } else {
+        // Kept before the body, to resume a download cut short
+        match validator(response.headers()) {
+            Some(validator) => fs::write(&validator_path, validator)?,
+            None => remove_if_present(&validator_path)?,
+        }
+        File::create(&part)?
+    };
+    let received = start + io::copy(&mut response, &mut file)?;
+    file.sync_all()?;
+    drop(file);
+    if let Some(expected) = meta.content_length {
+        if received != expected {
+            if received > expected {
+                // Longer than the feed, so no start of it
+                remove_if_present(&part)?;
+            }
+            return Err(Error::IncompleteDownload {
+                url: url.to_string(),
+                received,
+                expected,
+            });
+        }
+    }
+    fs::rename(&part, path)?;
+    remove_if_present(&validator_path)?;
+    trace_event!(info, url, bytes = received, "downloaded feed");
+    Ok(meta)
+}
+
+/// Whether `response` to a request for the bytes from `len` on, if still `validator`, is
+/// the rest of the part
+fn resumes(response: &Response, len: u64, validator: &str) -> bool {
+    response.status() == StatusCode::PARTIAL_CONTENT
+        && range_start(response.headers()) == Some(len)
+        // Servers may answer with the range regardless of `If-Range`
+        && self::validator(response.headers()).is_none_or(|current| current == validator)
+}
+
+/// Validator to bind a later `Range` request to: a strong `ETag`, else `Last-Modified`
+fn validator(headers: &HeaderMap) -> Option<String> {
+    let text = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    text(header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| text(header::LAST_MODIFIED))
+        .map(str::to_string)
+}
+
+/// First byte of a partial response, from its `Content-Range`
+fn range_start(headers: &HeaderMap) -> Option<u64> {
+    let range = headers.get(header::CONTENT_RANGE)?.to_str().ok()?;
+    let range = range.trim().strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
+/// `path` with `suffix` appended to its file name
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Removes the file at `path`, unless there is none
+fn remove_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// Request to the test server: its `Range` and `If-Range` headers
+    type Request = (Option<String>, Option<String>);
+
+    /// Serves one connection per response given by `respond` for each request in turn,
+    /// returning the server's URL and the requests it got
+    fn serve<F>(responses: usize, respond: F) -> (String, Arc<Mutex<Vec<Request>>>)
+    where
+        F: Fn(usize, &Request) -> Vec<u8> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/NAVAll.txt", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for i in 0..responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = (None, None);
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    let (name, value) = line.split_once(':').unwrap_or((&line, ""));
+                    let value = Some(value.trim().to_string());
+                    match name.to_ascii_lowercase().as_str() {
+                        "range" => request.0 = value,
+                        "if-range" => request.1 = value,
+                        _ => {}
+                    }
+                    line.clear();
+                }
+                let response = respond(i, &request);
+                log.lock().unwrap().push(request);
+                // A client seeing a body cut short may be gone already
+                let _ = stream.write_all(&response);
+            }
+        });
+        (url, requests)
+    }
+
+    const FEED: &[u8] = include_bytes!("../fixtures/NAVOpen.txt");
+
+    /// 200 response of the feed tagged `etag`, its body cut after `len` bytes
+    fn whole(etag: &str, len: usize) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: {}\r\nConnection: close\r\n\r\n",
+            FEED.len(),
+            etag
+        )
+        .into_bytes();
+        response.extend_from_slice(&FEED[..len]);
+        response
+    }
+
+    /// 206 response of the feed tagged `etag` from byte `start` on
+    fn rest(etag: &str, start: usize) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\n\
+             ETag: {}\r\nConnection: close\r\n\r\n",
+            FEED.len() - start,
+            start,
+            FEED.len() - 1,
+            FEED.len(),
+            etag
+        )
+        .into_bytes();
+        response.extend_from_slice(&FEED[start..]);
+        response
+    }
+
+    /// Range requested by a request, if any
+    fn range(request: &Request) -> Option<usize> {
+        let range = request.0.as_deref()?.strip_prefix("bytes=")?;
+        range.trim_end_matches('-').parse().ok()
+    }
+
+    fn target(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("amfi-download-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        for stale in [
+            path.clone(),
+            with_suffix(&path, ".part"),
+            with_suffix(&path, ".part.validator"),
+        ] {
+            remove_if_present(&stale).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn resumes_where_cut() {
+        let (url, requests) = serve(2, |i, request| match (i, range(request)) {
+            (0, _) => whole("\"a\"", 1000),
+            (_, Some(start)) if request.1.as_deref() == Some("\"a\"") => rest("\"a\"", start),
+            _ => whole("\"a\"", FEED.len()),
+        });
+        let path = target("resumes.txt");
+        assert!(download_nav_at(&url, &path).is_err());
+        assert_eq!(
+            fs::metadata(with_suffix(&path, ".part")).unwrap().len(),
+            1000
+        );
+        assert!(!path.exists());
+
+        let meta = download_nav_at(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), FEED);
+        assert_eq!(meta.content_length, Some(FEED.len() as u64));
+        assert_eq!(meta.etag.as_deref(), Some("\"a\""));
+        assert!(!with_suffix(&path, ".part").exists());
+        assert!(!with_suffix(&path, ".part.validator").exists());
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[1].0.as_deref(), Some("bytes=1000-"));
+    }
+
+    #[test]
+    fn restarts_if_range_is_ignored() {
+        let (url, requests) = serve(2, |i, _| {
+            whole("\"a\"", if i == 0 { 1000 } else { FEED.len() })
+        });
+        let path = target("ignored.txt");
+        assert!(download_nav_at(&url, &path).is_err());
+        download_nav_at(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), FEED);
+        assert_eq!(requests.lock().unwrap()[1].1.as_deref(), Some("\"a\""));
+    }
+
+    #[test]
+    fn restarts_if_the_feed_changed() {
+        // Answers ranges whatever `If-Range` says, the second time with another ETag
+        let (url, requests) = serve(3, |i, request| match (i, range(request)) {
+            (0, _) => whole("\"a\"", 1000),
+            (1, Some(start)) => rest("\"b\"", start),
+            _ => whole("\"b\"", FEED.len()),
+        });
+        let path = target("changed.txt");
+        assert!(download_nav_at(&url, &path).is_err());
+        let meta = download_nav_at(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), FEED);
+        assert_eq!(meta.etag.as_deref(), Some("\"b\""));
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2], (None, None));
+    }
+
+    #[test]
+    fn short_bodies_are_incomplete() {
+        // The start of the feed only, announced as such
+        let short = FEED.len() - 100;
+        let (url, _) = serve(2, move |i, request| match (i, range(request)) {
+            (0, _) => {
+                let mut response = format!(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\
+                     Content-Range: bytes 0-{}/{}\r\nETag: \"a\"\r\nConnection: close\r\n\r\n",
+                    short,
+                    short - 1,
+                    FEED.len()
+                )
+                .into_bytes();
+                response.extend_from_slice(&FEED[..short]);
+                response
+            }
+            (_, Some(start)) => rest("\"a\"", start),
+            _ => whole("\"a\"", FEED.len()),
+        });
+        let path = target("short.txt");
+        match download_nav_at(&url, &path) {
+            Err(Error::IncompleteDownload {
+                received, expected, ..
+            }) => assert_eq!((received, expected), (short as u64, FEED.len() as u64)),
+            other => panic!("{:?}", other),
+        }
+        download_nav_at(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), FEED);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ETAG,
+            header::HeaderValue::from_str("W/\"a\"").unwrap(),
+        );
+        assert_eq!(validator(&headers), None);
+        let range = header::HeaderValue::from_str("bytes 1000-1999/2000").unwrap();
+        headers.insert(header::CONTENT_RANGE, range);
+        assert_eq!(range_start(&headers), Some(1000));
+    }
+}
@@ -0,0 +1,524 @@
+//! Records joined with the AMFI scheme master, for details the NAV feed lacks
+
+use crate::{Error, Isin, NavDate, NavRecord, Result, SchemeCode};
+use csv::ReaderBuilder;
+use log::warn;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+/// Scheme of the scheme master, see [`SchemeMaster`](struct.SchemeMaster.html)
+///
+/// Texts are trimmed, blank cells are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MasterEntry {
+    /// Scheme code
+    pub code: SchemeCode,
+    /// Valid ISINs, growth or payout first
+    pub isins: Vec<Isin>,
+    /// AMC, e.g. `Axis Mutual Fund`
+    pub amc: Option<String>,
+    /// Scheme name without plan and option, e.g. `Axis Bluechip Fund`
+    pub scheme_name: Option<String>,
+    /// Scheme name as in the NAV feed
+    pub nav_name: Option<String>,
+    /// E.g. `Open Ended`
+    pub scheme_type: Option<String>,
+    /// SEBI category, e.g. `Equity Scheme - Large Cap Fund`
+    pub sebi_category: Option<String>,
+    /// Code of the SEBI category, e.g. `EQ-LC`
+    pub category_code: Option<String>,
+    /// Benchmark index, e.g. `NIFTY 100 TRI`
+    pub benchmark: Option<String>,
+    /// Minimum investment, as published
+    pub minimum_amount: Option<String>,
+    /// Launch date
+    pub launch_date: Option<NavDate>,
+    /// Closure date, of close ended schemes
+    pub closure_date: Option<NavDate>,
+}
+
+/// Column of the scheme master
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MasterColumn {
+    Code,
+    Isins,
+    Amc,
+    SchemeName,
+    NavName,
+    SchemeType,
+    SebiCategory,
+    CategoryCode,
+    Benchmark,
+    MinimumAmount,
+    LaunchDate,
+    ClosureDate,
+}
+
+impl MasterColumn {
+    /// Column of a header `name`, compared lowercased without other than letters and digits
+    fn of(name: &str) -> Option<Self> {
+        let name: String = name
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|ch| ch.to_ascii_lowercase())
+            .collect();
+        let column = match name.as_str() {
+            "code" | "schemecode" | "amficode" => MasterColumn::Code,
+            "amc" | "amcname" | "fundhouse" => MasterColumn::Amc,
+            "schemename" => MasterColumn::SchemeName,
+            "schemenavname" | "navname" => MasterColumn::NavName,
+            "schemetype" => MasterColumn::SchemeType,
+            "schemecategory" | "category" | "sebicategory" => MasterColumn::SebiCategory,
+            "categorycode" | "sebicategorycode" | "schemecategorycode" => {
+                MasterColumn::CategoryCode
+            }
+            "benchmark" | "schemebenchmark" | "benchmarkindex" => MasterColumn::Benchmark,
+            "schememinimumamount" | "minimumamount" => MasterColumn::MinimumAmount,
+            "launchdate" => MasterColumn::LaunchDate,
+            "closuredate" => MasterColumn::ClosureDate,
+            _ if name.contains("isin") => MasterColumn::Isins,
+            _ => return None,
+        };
+        Some(column)
+    }
+}
+
+/// Scheme master of AMFI, keyed by scheme code and ISIN
+///
+/// AMFI publishes the launch date, SEBI category and other details of every scheme in a CSV
+/// file apart from the NAV feed. Columns are found by name in its header row, in any case and
+/// spacing, e.g. `Code`, `Scheme Category`, `Launch Date`; unknown columns are ignored and
+/// missing ones leave their fields `None`, so trimmed or extended files, e.g. with a
+/// `Benchmark` or `SEBI Category Code` column, read too. Only a scheme code column is
+/// required.
+///
+/// Cells are normalized as spreadsheets leave them: codes may be padded or end in `.0`,
+/// ISINs may be lowercase, and an ISIN column may hold both ISINs of a scheme, separated or
+/// not. Dates are `21-Jun-2024` or `2024-06-21`. Rows without a valid code are logged and
+/// skipped; of rows of the same code the first is kept.
+#[derive(Debug, Clone, Default)]
+pub struct SchemeMaster {
+    entries: Vec<MasterEntry>,
+    by_code: HashMap<SchemeCode, usize>,
+    by_isin: HashMap<Isin, usize>,
+}
+
+impl SchemeMaster {
+    /// Reads the scheme master CSV from `reader`
+    ///
+    /// Fails with [`Error::InvalidMaster`](enum.Error.html#variant.InvalidMaster) if the
+    /// header has no scheme code column, and with
+    /// [`Error::CsvError`](enum.Error.html#variant.CsvError) on I/O errors. Text that is not
+    /// UTF-8 is decoded lossily.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let file = std::fs::File::open("fixtures/SchemeMaster.csv")?;
+    /// let master = amfi::SchemeMaster::from_csv(file)?;
+    /// let entry = master.get(120_465).unwrap();
+    /// assert_eq!(entry.benchmark.as_deref(), Some("NIFTY 100 TRI"));
+    /// assert_eq!(master.get_by_isin("INF846K01AD0").unwrap().code, 120_466);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_csv<R: Read>(reader: R) -> Result<SchemeMaster> {
+        let mut reader = ReaderBuilder::new().flexible(true).from_reader(reader);
+        let columns: Vec<Option<MasterColumn>> = reader
+            .byte_headers()?
+            .iter()
+            .map(|name| MasterColumn::of(&String::from_utf8_lossy(name)))
+            .collect();
+        if !columns.contains(&Some(MasterColumn::Code)) {
+            return Err(Error::InvalidMaster("no scheme code column".to_string()));
+        }
+        let mut master = SchemeMaster::default();
+        for row in reader.byte_records() {
+            let row = row?;
+            let line = row.position().map_or(0, |position| position.line());
+            let cells = columns.iter().zip(row.iter()).filter_map(|(column, cell)| {
+                column.map(|column| (column, String::from_utf8_lossy(cell)))
+            });
+            match entry(cells) {
+                Some(entry) => master.insert(entry),
+                None => warn!("Scheme master line {} has no valid scheme code", line),
+            }
+        }
+        Ok(master)
+    }
+
+    fn insert(&mut self, entry: MasterEntry) {
+        if self.by_code.contains_key(&entry.code) {
+            warn!(
+                "Scheme master has scheme {} again, kept the first",
+                entry.code
+            );
+            return;
+        }
+        let index = self.entries.len();
+        self.by_code.insert(entry.code, index);
+        for isin in &entry.isins {
+            self.by_isin.entry(isin.clone()).or_insert(index);
+        }
+        self.entries.push(entry);
+    }
+
+    /// Schemes in the order read
+    pub fn entries(&self) -> &[MasterEntry] {
+        &self.entries
+    }
+
+    /// Number of schemes
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no schemes
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Scheme of `code`
+    pub fn get<C: Into<SchemeCode>>(&self, code: C) -> Option<&MasterEntry> {
+        self.by_code
+            .get(&code.into())
+            .map(|&index| &self.entries[index])
+    }
+
+    /// Scheme of `isin`, the first read if several have it
+    pub fn get_by_isin(&self, isin: &str) -> Option<&MasterEntry> {
+        self.by_isin.get(isin).map(|&index| &self.entries[index])
+    }
+
+    /// Index of the entry of `record`, by code else by either ISIN
+    fn find(&self, record: &NavRecord) -> Option<(usize, MatchedBy)> {
+        if let Some(&index) = self.by_code.get(&record.code) {
+            return Some((index, MatchedBy::Code));
+        }
+        let mut isins = record.isin.iter().chain(record.isin_dr.iter());
+        isins
+            .find_map(|isin| self.by_isin.get(isin.valid()?))
+            .map(|&index| (index, MatchedBy::Isin))
+    }
+}
+
+/// Entry of the `cells` of a row, `None` without a valid code
+fn entry<'a, I>(cells: I) -> Option<MasterEntry>
+where
+    I: Iterator<Item = (MasterColumn, std::borrow::Cow<'a, str>)>,
+{
+    let mut code = None;
+    let mut entry = MasterEntry {
+        code: SchemeCode::from(0),
+        isins: Vec::new(),
+        amc: None,
+        scheme_name: None,
+        nav_name: None,
+        scheme_type: None,
+        sebi_category: None,
+        category_code: None,
+        benchmark: None,
+        minimum_amount: None,
+        launch_date: None,
+        closure_date: None,
+    };
+    for (column, cell) in cells {
+        let cell = cell.trim();
+        let text = || match cell {
+            "" | "-" => None,
+            cell => Some(cell.to_string()),
+        };
+        match column {
+            MasterColumn::Code => code = code.or_else(|| parse_code(cell)),
+            MasterColumn::Isins => {
+                for isin in parse_isins(cell) {
+                    if !entry.isins.contains(&isin) {
+                        entry.isins.push(isin);
+                    }
+                }
+            }
+            MasterColumn::Amc => entry.amc = text(),
+            MasterColumn::SchemeName => entry.scheme_name = text(),
+            MasterColumn::NavName => entry.nav_name = text(),
+            MasterColumn::SchemeType => entry.scheme_type = text(),
+            MasterColumn::SebiCategory => entry.sebi_category = text(),
+            MasterColumn::CategoryCode => entry.category_code = text(),
+            MasterColumn::Benchmark => entry.benchmark = text(),
+            MasterColumn::MinimumAmount => entry.minimum_amount = text(),
+            MasterColumn::LaunchDate => entry.launch_date = parse_date(cell),
+            MasterColumn::ClosureDate => entry.closure_date = parse_date(cell),
+        }
+    }
+    entry.code = code?;
+    Some(entry)
+}
+
+/// Scheme code of a cell, padded or with a zero fraction as spreadsheets write numbers
+fn parse_code(cell: &str) -> Option<SchemeCode> {
+    let digits = match cell.split_once('.') {
+        Some((digits, fraction)) if fraction.bytes().all(|byte| byte == b'0') => digits,
+        Some(_) => return None,
+        None => cell,
+    };
+    digits.parse().ok()
+}
+
+/// Valid ISINs of a cell: letters and digits only, in 12 character runs
+fn parse_isins(cell: &str) -> Vec<Isin> {
+    let chars: Vec<char> = cell
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|ch| ch.to_ascii_uppercase())
+        .collect();
+    if !chars.len().is_multiple_of(12) {
+        warn!("Scheme master ISINs `{}` are not 12 characters each", cell);
+        return Vec::new();
+    }
+    chars
+        .chunks(12)
+        .filter_map(|chunk| chunk.iter().collect::<String>().parse().ok())
+        .collect()
+}
+
+/// Date of a cell, `21-Jun-2024` or `2024-06-21`, `None` if blank or invalid
+fn parse_date(cell: &str) -> Option<NavDate> {
+    if cell.is_empty() || cell == "-" {
+        return None;
+    }
+    let date = NavDate::parse_feed(cell).or_else(|_| cell.parse());
+    if date.is_err() {
+        warn!("Scheme master date `{}` is invalid", cell);
+    }
+    date.ok()
+}
+
+/// How a record found its scheme master entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MatchedBy {
+    /// Same scheme code
+    Code,
+    /// Same ISIN, the code differing, e.g. of a scheme renumbered since
+    Isin,
+}
+
+/// Record with the details of its scheme master entry, see [`enrich`](fn.enrich.html)
+///
+/// Details are `None` if the record has no entry or the entry lacks them.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EnrichedRecord {
+    /// Record of the feed
+    pub record: NavRecord,
+    /// How the entry was found, `None` without one
+    pub matched_by: Option<MatchedBy>,
+    /// Scheme code of the entry, differing from the record's if matched by ISIN
+    pub master_code: Option<SchemeCode>,
+    /// E.g. `Open Ended`
+    pub scheme_type: Option<String>,
+    /// SEBI category, e.g. `Equity Scheme - Large Cap Fund`
+    pub sebi_category: Option<String>,
+    /// Code of the SEBI category
+    pub category_code: Option<String>,
+    /// Benchmark index
+    pub benchmark: Option<String>,
+    /// Minimum investment, as published
+    pub minimum_amount: Option<String>,
+    /// Launch date
+    pub launch_date: Option<NavDate>,
+    /// Closure date, of close ended schemes
+    pub closure_date: Option<NavDate>,
+}
+
+impl EnrichedRecord {
+    fn new(record: NavRecord, found: Option<(&MasterEntry, MatchedBy)>) -> Self {
+        let entry = found.map(|(entry, _)| entry);
+        EnrichedRecord {
+            matched_by: found.map(|(_, matched_by)| matched_by),
+            master_code: entry.map(|entry| entry.code),
+            scheme_type: entry.and_then(|entry| entry.scheme_type.clone()),
+            sebi_category: entry.and_then(|entry| entry.sebi_category.clone()),
+            category_code: entry.and_then(|entry| entry.category_code.clone()),
+            benchmark: entry.and_then(|entry| entry.benchmark.clone()),
+            minimum_amount: entry.and_then(|entry| entry.minimum_amount.clone()),
+            launch_date: entry.and_then(|entry| entry.launch_date),
+            closure_date: entry.and_then(|entry| entry.closure_date),
+            record,
+        }
+    }
+}
+
+/// Outcome of [`enrich`](fn.enrich.html)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Enrichment {
+    /// Every record, in input order, with the details of its entry
+    pub records: Vec<EnrichedRecord>,
+    /// Codes of records without an entry, in input order
+    pub unmatched_records: Vec<SchemeCode>,
+    /// Codes of entries no record matched, in master order
+    pub unmatched_master: Vec<SchemeCode>,
+}
+
+/// Joins `records` with their entries of `master`
+///
+/// A record matches the entry of its scheme code or, lacking one, the entry of either of its
+/// valid ISINs, as schemes renumbered since the master was published keep their ISINs.
+/// Records without an entry are kept with no details, their codes reported in
+/// `unmatched_records`; entries no record matched, e.g. of schemes wound up, are reported in
+/// `unmatched_master`.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::{MatchedBy, SchemeMaster};
+///
+/// let master = SchemeMaster::from_csv(std::fs::File::open("fixtures/SchemeMaster.csv")?)?;
+/// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?.filter_map(Result::ok);
+/// let enriched = amfi::enrich(records, &master);
+/// let bluechip = &enriched.records[0];
+/// assert_eq!(bluechip.matched_by, Some(MatchedBy::Code));
+/// assert_eq!(bluechip.launch_date.unwrap().to_string(), "2013-01-01");
+/// assert_eq!(enriched.unmatched_master, [100_001]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn enrich<I>(records: I, master: &SchemeMaster) -> Enrichment
+where
+    I: IntoIterator<Item = NavRecord>,
+{
+    let mut enrichment = Enrichment::default();
+    let mut matched = HashSet::new();
+    for record in records {
+        let found = master.find(&record);
+        match found {
+            Some((index, _)) => {
+                matched.insert(index);
+            }
+            None => enrichment.unmatched_records.push(record.code),
+        }
+        let found = found.map(|(index, matched_by)| (&master.entries[index], matched_by));
+        enrichment.records.push(EnrichedRecord::new(record, found));
+    }
+    enrichment.unmatched_master = master
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !matched.contains(index))
+        .map(|(_, entry)| entry.code)
+        .collect();
+    enrichment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn isins(entry: &MasterEntry) -> Vec<&str> {
+        entry.isins.iter().map(Isin::as_str).collect()
+    }
+
+    fn master() -> SchemeMaster {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/SchemeMaster.csv");
+        SchemeMaster::from_csv(std::fs::File::open(path).unwrap()).unwrap()
+    }
+
+    fn records() -> Vec<NavRecord> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        crate::nav_from_file(path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn normalizes_master_cells() {
+        let master = master();
+        // The duplicate of 120465 and the row without a code are skipped
+        assert_eq!(master.len(), 8);
+
+        let entry = master.get(120_466).unwrap();
+        assert_eq!(isins(entry), ["INF846K01AC2", "INF846K01AD0"]);
+        assert_eq!(
+            entry.sebi_category.as_deref(),
+            Some("Equity Scheme - Large Cap Fund")
+        );
+        assert_eq!(entry.category_code.as_deref(), Some("EQ-LC"));
+        assert_eq!(entry.closure_date, None);
+        assert_eq!(
+            master.get(120_465).unwrap().benchmark.as_deref(),
+            Some("NIFTY 100 TRI")
+        );
+        // Padded, with a zero fraction, lowercase
+        assert_eq!(isins(master.get(112_277).unwrap()), ["INF846K01AE8"]);
+        assert_eq!(isins(master.get(120_586).unwrap()), ["INF109K01AF8"]);
+        assert_eq!(master.get_by_isin("INF109K01AH4").unwrap().code, 999_108);
+
+        let hdfc = master.get(118_955).unwrap();
+        assert_eq!(
+            (hdfc.launch_date, hdfc.category_code.as_deref()),
+            (None, None)
+        );
+        let closed = master.get(100_001).unwrap();
+        assert_eq!(closed.closure_date, NavDate::from_ymd(2015, 3, 11));
+
+        assert_eq!(parse_code("120465.00"), Some(SchemeCode::from(120_465)));
+        assert_eq!(parse_code("120465.5"), None);
+        assert!(parse_isins("INF846K01AB4INF846K01AC").is_empty());
+        assert_eq!(parse_date("2024-06-21"), NavDate::from_ymd(2024, 6, 21));
+    }
+
+    #[test]
+    fn joins_by_code_then_isin() {
+        let enriched = enrich(records(), &master());
+        assert_eq!(enriched.records.len(), 30);
+        let by_code: HashMap<u32, &EnrichedRecord> = enriched
+            .records
+            .iter()
+            .map(|record| (record.record.code.get(), record))
+            .collect();
+
+        let bluechip = by_code[&120_465];
+        assert_eq!(bluechip.matched_by, Some(MatchedBy::Code));
+        assert_eq!(bluechip.master_code, Some(SchemeCode::from(120_465)));
+        assert_eq!(bluechip.scheme_type.as_deref(), Some("Open Ended"));
+        assert_eq!(bluechip.launch_date, NavDate::from_ymd(2013, 1, 1));
+
+        // Renumbered in the master, found by ISIN
+        let renumbered = by_code[&108_466];
+        assert_eq!(renumbered.matched_by, Some(MatchedBy::Isin));
+        assert_eq!(renumbered.master_code, Some(SchemeCode::from(999_108)));
+        assert_eq!(renumbered.launch_date, NavDate::from_ymd(2008, 5, 23));
+
+        let unmatched = by_code[&145_812];
+        assert_eq!(unmatched.matched_by, None);
+        assert_eq!(unmatched.sebi_category, None);
+
+        let matched = [
+            120_465, 120_466, 112_277, 120_586, 108_466, 118_955, 119_727,
+        ];
+        let expected: Vec<SchemeCode> = records()
+            .iter()
+            .map(|record| record.code)
+            .filter(|code| !matched.contains(&code.get()))
+            .collect();
+        assert_eq!(enriched.unmatched_records, expected);
+        assert_eq!(enriched.unmatched_master, [100_001]);
+    }
+
+    #[test]
+    fn requires_a_code_column() {
+        let csv = "Scheme Name,ISIN\nAxis Bluechip Fund,INF846K01AB4\n";
+        assert!(matches!(
+            SchemeMaster::from_csv(csv.as_bytes()),
+            Err(Error::InvalidMaster(_))
+        ));
+        let master = SchemeMaster::from_csv("scheme_code\n120465\n".as_bytes()).unwrap();
+        assert!(master.get(120_465).unwrap().isins.is_empty());
+
+        let enriched = enrich(Vec::new(), &master);
+        assert!(enriched.records.is_empty());
+        assert_eq!(enriched.unmatched_master, [120_465]);
+    }
+}
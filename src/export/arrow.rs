@@ -0,0 +1,192 @@
+//! Arrow and Parquet export
+
+use crate::{NavRecord, Result};
+use arrow::array::{
+    ArrayRef, Date32Array, DictionaryArray, Float64Array, StringArray, UInt32Array,
+};
+use arrow::datatypes::{DataType, Field, Int8Type, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+#[cfg(feature = "parquet")]
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+use std::sync::Arc;
+#[cfg(feature = "parquet")]
+use std::{fs::File, path::Path};
+
+/// Records per Parquet row group
+#[cfg(feature = "parquet")]
+pub const ROW_GROUP_SIZE: usize = 4096;
+
+fn dictionary() -> DataType {
+    DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8))
+}
+
+/// Arrow schema of [`to_record_batch`](fn.to_record_batch.html) output, exported as `arrow_schema`
+///
+/// | column     | type                    | nullable |
+/// |------------|-------------------------|----------|
+/// | `code`     | `UInt32`                | no       |
+/// | `isin`     | `Utf8`                  | yes      |
+/// | `isin_dr`  | `Utf8`                  | yes      |
+/// | `name`     | `Utf8`                  | no       |
+/// | `nav`      | `Float64`               | no       |
+/// | `date`     | `Date32`                | no       |
+/// | `amc`      | `Utf8`                  | no       |
+/// | `category` | `Utf8`                  | no       |
+/// | `scheme`   | `Utf8`                  | yes      |
+/// | `maturity` | `Dictionary(Int8, Utf8)`| yes      |
+/// | `plan`     | `Dictionary(Int8, Utf8)`| no       |
+/// | `option`   | `Utf8`                  | yes      |
+///
+/// ISINs are kept as published, valid or not. Enums hold their `Display` strings.
+pub fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("code", DataType::UInt32, false),
+        Field::new("isin", DataType::Utf8, true),
+        Field::new("isin_dr", DataType::Utf8, true),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("nav", DataType::Float64, false),
+        Field::new("date", DataType::Date32, false),
+        Field::new("amc", DataType::Utf8, false),
+        Field::new("category", DataType::Utf8, false),
+        Field::new("scheme", DataType::Utf8, true),
+        Field::new("maturity", dictionary(), true),
+        Field::new("plan", dictionary(), false),
+        Field::new("option", DataType::Utf8, true),
+    ]))
+}
+
+/// Converts records into an Arrow record batch with the [`arrow_schema`](fn.arrow_schema.html) layout
+pub fn to_record_batch(records: &[NavRecord]) -> Result<RecordBatch> {
+    fn strings<'a, F>(records: &'a [NavRecord], f: F) -> ArrayRef
+    where
+        F: Fn(&'a NavRecord) -> Option<&'a str>,
+    {
+        Arc::new(records.iter().map(f).collect::<StringArray>())
+    }
+    let maturity: Vec<Option<String>> = records
+        .iter()
+        .map(|r| r.maturity.map(|maturity| maturity.to_string()))
+        .collect();
+    let plan: Vec<String> = records.iter().map(|r| r.plan.to_string()).collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            records
+                .iter()
+                .map(|r| r.code.get())
+                .collect::<UInt32Array>(),
+        ),
+        strings(records, |r| r.isin.as_ref().map(|isin| isin.as_str())),
+        strings(records, |r| r.isin_dr.as_ref().map(|isin| isin.as_str())),
+        strings(records, |r| Some(r.name.as_str())),
+        Arc::new(records.iter().map(|r| r.nav).collect::<Float64Array>()),
+        Arc::new(Date32Array::from(
+            records
+                .iter()
+                .map(NavRecord::date_epoch_days)
+                .collect::<Vec<_>>(),
+        )),
+        strings(records, |r| Some(r.amc.as_str())),
+        strings(records, |r| Some(r.category.as_str())),
+        strings(records, |r| r.scheme.as_deref()),
+        Arc::new(
+            maturity
+                .iter()
+                .map(Option::as_deref)
+                .collect::<DictionaryArray<Int8Type>>(),
+        ),
+        Arc::new(
+            plan.iter()
+                .map(String::as_str)
+                .collect::<DictionaryArray<Int8Type>>(),
+        ),
+        strings(records, |r| r.option.as_deref()),
+    ];
+    Ok(RecordBatch::try_new(schema(), columns)?)
+}
+
+/// Streams records into a Parquet file at `path`, returning the number of records written
+///
+/// Records are buffered and written in row groups of
+/// [`ROW_GROUP_SIZE`](constant.ROW_GROUP_SIZE.html), so memory use does not grow with the
+/// feed size. An existing file is truncated.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<I, P>(records: I, path: P) -> Result<usize>
+where
+    I: IntoIterator<Item = NavRecord>,
+    P: AsRef<Path>,
+{
+    let properties = WriterProperties::builder()
+        .set_max_row_group_size(ROW_GROUP_SIZE)
+        .build();
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, schema(), Some(properties))?;
+    let mut chunk = Vec::with_capacity(ROW_GROUP_SIZE);
+    let mut written = 0;
+    for record in records {
+        chunk.push(record);
+        if chunk.len() == ROW_GROUP_SIZE {
+            writer.write(&to_record_batch(&chunk)?)?;
+            written += chunk.len();
+            chunk.clear();
+        }
+    }
+    if !chunk.is_empty() {
+        writer.write(&to_record_batch(&chunk)?)?;
+        written += chunk.len();
+    }
+    writer.close()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Date32Type, UInt32Type};
+
+    #[test]
+    fn fixture_to_record_batch() {
+        let batch = to_record_batch(&fixture_records("NAVOpen.txt")).unwrap();
+        assert_eq!(batch.num_rows(), 30);
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(
+            batch.column(0).as_primitive::<UInt32Type>().value(0),
+            120_465
+        );
+        assert!(batch.column(2).is_null(0));
+        let date = batch.column(5).as_primitive::<Date32Type>().value(0);
+        assert_eq!(
+            crate::NavDate::from_epoch_days(date.into()),
+            crate::NavDate::from_ymd(2024, 6, 21)
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn fixture_parquet_round_trip() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let records = fixture_records("NAVOpen.txt");
+        let path = std::env::temp_dir().join(format!("amfi-{}.parquet", std::process::id()));
+        assert_eq!(write_parquet(records.clone(), &path).unwrap(), 30);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 30);
+
+        let batch = &batches[0];
+        let names = batch.column(3).as_string::<i32>();
+        let navs = batch
+            .column(4)
+            .as_primitive::<arrow::datatypes::Float64Type>();
+        assert_eq!(names.value(1), records[1].name);
+        assert_eq!(navs.value(1), records[1].nav);
+        assert_eq!(batch.column(1).as_string::<i32>().value(1), "INF846K01AC2");
+    }
+}
@@ -0,0 +1,177 @@
+//! CSV export
+
+use super::RecordSink;
+use crate::{Error, NavRecord, Result};
+use csv::Writer;
+use std::io::Write;
+
+/// CSV header row, one column per [`NavRecord`](../struct.NavRecord.html) field
+const HEADER: [&str; 12] = [
+    "code", "isin", "isin_dr", "name", "nav", "date", "amc", "category", "scheme", "maturity",
+    "plan", "option",
+];
+
+/// Writes records as CSV with a header row
+///
+/// Enums are rendered via their `Display` impls, dates as ISO 8601 and `None` as empty cells.
+pub fn write_csv<I, W>(records: I, w: W) -> Result<()>
+where
+    I: IntoIterator<Item = NavRecord>,
+    W: Write,
+{
+    let errors = write_csv_stream(records.into_iter().map(Ok), w)?;
+    debug_assert!(errors.is_empty());
+    Ok(())
+}
+
+/// Writes parse results as CSV, e.g. straight from a
+/// [`NavRecordIterator`](../struct.NavRecordIterator.html)
+///
+/// Records are written as they arrive; per-record errors are collected and returned once the
+/// input is exhausted. Write failures abort immediately.
+pub fn write_csv_stream<I, W>(results: I, w: W) -> Result<Vec<Error>>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    W: Write,
+{
+    let mut writer = Writer::from_writer(w);
+    writer.write_record(HEADER)?;
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(record) => writer.write_record(row(&record))?,
+            Err(error) => errors.push(error),
+        }
+    }
+    writer.flush()?;
+    Ok(errors)
+}
+
+/// [`RecordSink`](trait.RecordSink.html) writing CSV with a header row, as
+/// [`write_csv`](fn.write_csv.html) does
+///
+/// The header row is written with the first record, or by `finish` if there is none.
+pub struct CsvSink<W: Write> {
+    writer: Writer<W>,
+    header_written: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    /// Wraps `writer`
+    pub fn new(writer: W) -> Self {
+        CsvSink {
+            writer: Writer::from_writer(writer),
+            header_written: false,
+        }
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        if !self.header_written {
+            self.writer.write_record(HEADER)?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> RecordSink for CsvSink<W> {
+    fn write_record(&mut self, record: &NavRecord) -> Result<()> {
+        self.write_header()?;
+        self.writer.write_record(row(record))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn row(record: &NavRecord) -> [String; 12] {
+    fn cell<T: ToString>(value: &Option<T>) -> String {
+        value.as_ref().map(T::to_string).unwrap_or_default()
+    }
+    [
+        record.code.to_string(),
+        cell(&record.isin),
+        cell(&record.isin_dr),
+        record.name.clone(),
+        record.nav.to_string(),
+        record.date.to_string(),
+        record.amc.clone(),
+        record.category.clone(),
+        cell(&record.scheme),
+        cell(&record.maturity),
+        record.plan.to_string(),
+        cell(&record.option),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_path, fixture_records};
+    use crate::{FundPlan, NavDate, NavRecordBuilder};
+
+    #[test]
+    fn fixture_to_csv() {
+        let path = fixture_path("NAVOpen.txt");
+        let mut out = Vec::new();
+        let errors = write_csv_stream(crate::nav_from_file(&path).unwrap(), &mut out).unwrap();
+        assert!(errors.is_empty());
+
+        let mut reader = csv::Reader::from_reader(&out[..]);
+        assert_eq!(reader.headers().unwrap(), &HEADER[..]);
+        let rows: Vec<csv::StringRecord> = reader.records().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 30);
+        assert_eq!(&rows[0][0], "120465");
+        assert_eq!(&rows[0][2], "");
+        assert_eq!(&rows[0][5], "2024-06-21");
+        assert_eq!(&rows[0][9], "Open Ended");
+        assert_eq!(&rows[0][10], "Direct");
+        assert_eq!(&rows[1][2], "INF846K01AD0");
+    }
+
+    #[test]
+    fn sink_writes_as_write_csv() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut expected = Vec::new();
+        write_csv(records.clone(), &mut expected).unwrap();
+
+        let mut out = Vec::new();
+        let mut sink = CsvSink::new(&mut out);
+        for record in &records {
+            sink.write_record(record).unwrap();
+        }
+        sink.finish().unwrap();
+        drop(sink);
+        assert_eq!(out, expected);
+
+        let mut empty = Vec::new();
+        CsvSink::new(&mut empty).finish().unwrap();
+        assert_eq!(String::from_utf8(empty).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn quoting() {
+        let record = NavRecordBuilder::default()
+            .code(1)
+            .name(r#"Fund, "Series 2""#)
+            .nav(10.0)
+            .date(NavDate::from_ymd(2024, 6, 21).unwrap())
+            .amc("AMC")
+            .category("Category")
+            .plan(FundPlan::Regular)
+            .build()
+            .unwrap();
+        let mut out = Vec::new();
+        write_csv(vec![record], &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(r#""Fund, ""Series 2""""#));
+
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        let row = reader.records().next().unwrap().unwrap();
+        assert_eq!(&row[3], r#"Fund, "Series 2""#);
+    }
+}
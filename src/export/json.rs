@@ -0,0 +1,269 @@
+//! Streaming serialization of a whole feed
+
+use super::WriteStats;
+use crate::{to_nested, Error, NavRecord, Result};
+use log::warn;
+use serde::ser::{Error as _, SerializeSeq};
+use serde::Serializer;
+use std::io::{self, Write};
+
+/// Handling of parse errors met while streaming records into a serializer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseErrorPolicy {
+    /// Fail the serialization with the first parse error
+    #[default]
+    Abort,
+    /// Log, count and leave out records that fail to parse
+    Skip,
+}
+
+/// Serializes records as one sequence, each as soon as it is parsed
+///
+/// Records are never collected, so a whole feed is serialized in constant memory. The
+/// sequence length isn't known up front, so formats requiring it, e.g. bincode, are not
+/// supported. Returns the serializer's output and the records written and skipped; with
+/// [`ParseErrorPolicy::Abort`](enum.ParseErrorPolicy.html#variant.Abort) a parse error fails
+/// the serialization with a custom error of the serializer.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::ParseErrorPolicy;
+///
+/// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?;
+/// let mut out = Vec::new();
+/// let mut serializer = serde_json::Serializer::new(&mut out);
+/// let (_, stats) = amfi::serialize_records(records, &mut serializer, ParseErrorPolicy::Abort)
+///     .map_err(std::io::Error::from)?;
+/// assert_eq!(stats.records, 30);
+/// assert!(out.starts_with(b"[{"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn serialize_records<I, S>(
+    records: I,
+    serializer: S,
+    policy: ParseErrorPolicy,
+) -> std::result::Result<(S::Ok, WriteStats), S::Error>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    S: Serializer,
+{
+    let mut failure = None;
+    serialize_seq(records, serializer, policy, &mut failure)
+}
+
+/// Streams records as a JSON array to `w`, see
+/// [`serialize_records`](fn.serialize_records.html)
+///
+/// With [`ParseErrorPolicy::Abort`](enum.ParseErrorPolicy.html#variant.Abort) the parse error
+/// itself is returned, and `w` is left with an unterminated array. Serialization and write
+/// errors are I/O errors. The writer is flushed before returning.
+pub fn to_json_writer<I, W>(records: I, mut w: W, policy: ParseErrorPolicy) -> Result<WriteStats>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    W: Write,
+{
+    let mut failure = None;
+    let mut serializer = serde_json::Serializer::new(&mut w);
+    match serialize_seq(records, &mut serializer, policy, &mut failure) {
+        Ok(((), stats)) => {
+            w.flush()?;
+            Ok(stats)
+        }
+        Err(error) => Err(failure.unwrap_or_else(|| Error::IoError(io::Error::from(error)))),
+    }
+}
+
+/// Serializes records as a JSON array string, see [`to_json_writer`](fn.to_json_writer.html)
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::{NavRecord, ParseErrorPolicy};
+///
+/// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?;
+/// let (json, stats) = amfi::to_json_string(records, ParseErrorPolicy::Skip)?;
+/// let parsed: Vec<NavRecord> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(parsed.len(), stats.records);
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_json_string<I>(records: I, policy: ParseErrorPolicy) -> Result<(String, WriteStats)>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut out = Vec::new();
+    let stats = to_json_writer(records, &mut out, policy)?;
+    // serde_json only writes valid UTF-8
+    let json = String::from_utf8(out).expect("JSON is valid UTF-8");
+    Ok((json, stats))
+}
+
+/// Writes records as a JSON array of AMCs, each with its scheme sections and their records,
+/// see [`to_nested`](fn.to_nested.html)
+///
+/// Records are collected first, so unlike [`to_json_writer`](fn.to_json_writer.html) nothing
+/// is written when [`ParseErrorPolicy::Abort`](enum.ParseErrorPolicy.html#variant.Abort)
+/// returns a parse error. Serialization and write errors are I/O errors. The writer is
+/// flushed before returning.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::ParseErrorPolicy;
+///
+/// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?;
+/// let mut out = Vec::new();
+/// let stats = amfi::write_nested_json(records, &mut out, ParseErrorPolicy::Abort)?;
+/// assert_eq!(stats.records, 30);
+/// assert!(out.starts_with(br#"[{"name":"#));
+/// # Ok(())
+/// # }
+/// ```
+pub fn write_nested_json<I, W>(records: I, mut w: W, policy: ParseErrorPolicy) -> Result<WriteStats>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    W: Write,
+{
+    let (feed, errors) = to_nested(records);
+    let mut stats = WriteStats {
+        records: feed.len(),
+        ..WriteStats::default()
+    };
+    for error in errors {
+        match policy {
+            ParseErrorPolicy::Abort => return Err(error),
+            ParseErrorPolicy::Skip => {
+                warn!("{}", error);
+                stats.count_error(&error);
+            }
+        }
+    }
+    serde_json::to_writer(&mut w, &feed).map_err(io::Error::from)?;
+    w.flush()?;
+    Ok(stats)
+}
+
+/// Serializes records, keeping the parse error that aborted in `failure`
+fn serialize_seq<I, S>(
+    records: I,
+    serializer: S,
+    policy: ParseErrorPolicy,
+    failure: &mut Option<Error>,
+) -> std::result::Result<(S::Ok, WriteStats), S::Error>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    S: Serializer,
+{
+    let mut stats = WriteStats::default();
+    let mut seq = serializer.serialize_seq(None)?;
+    for result in records {
+        match result {
+            Ok(record) => {
+                seq.serialize_element(&record)?;
+                stats.records += 1;
+            }
+            Err(error) => match policy {
+                ParseErrorPolicy::Abort => {
+                    let custom = S::Error::custom(&error);
+                    *failure = Some(error);
+                    return Err(custom);
+                }
+                ParseErrorPolicy::Skip => {
+                    warn!("{}", error);
+                    stats.count_error(&error);
+                }
+            },
+        }
+    }
+    seq.end().map(|ok| (ok, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_path, fixture_records};
+
+    const FEED: &str = "Axis Mutual Fund\n\
+                        120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                        120466;garbage;\n\
+                        112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n";
+
+    #[test]
+    fn fixture_round_trip() {
+        let path = fixture_path("NAVOpen.txt");
+        let expected = fixture_records("NAVOpen.txt");
+        let (json, stats) = to_json_string(
+            crate::nav_from_file(&path).unwrap(),
+            ParseErrorPolicy::Abort,
+        )
+        .unwrap();
+        assert_eq!(
+            stats,
+            WriteStats {
+                records: 30,
+                errors: 0,
+                too_many_errors: false,
+            }
+        );
+        let parsed: Vec<NavRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, expected);
+
+        let value = serialize_records(
+            crate::nav_from_file(&path).unwrap(),
+            serde_json::value::Serializer,
+            ParseErrorPolicy::Abort,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(value.as_array().unwrap().len(), 30);
+    }
+
+    #[test]
+    fn nested_json() {
+        let mut out = Vec::new();
+        let stats =
+            write_nested_json(crate::nav_from_str(FEED), &mut out, ParseErrorPolicy::Skip).unwrap();
+        assert_eq!(
+            stats,
+            WriteStats {
+                records: 2,
+                errors: 1,
+                too_many_errors: false,
+            }
+        );
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value[0]["name"], "Axis Mutual Fund");
+        assert_eq!(value[0]["sections"][0]["records"][1]["code"], 112_277);
+
+        let mut out = Vec::new();
+        let error = write_nested_json(crate::nav_from_str(FEED), &mut out, ParseErrorPolicy::Abort)
+            .unwrap_err();
+        assert!(matches!(error, Error::ParseFailure { .. }));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let (json, stats) =
+            to_json_string(crate::nav_from_str(FEED), ParseErrorPolicy::Skip).unwrap();
+        assert_eq!(
+            stats,
+            WriteStats {
+                records: 2,
+                errors: 1,
+                too_many_errors: false,
+            }
+        );
+        let parsed: Vec<NavRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[1].code.get(), 112_277);
+
+        let error = to_json_string(crate::nav_from_str(FEED), ParseErrorPolicy::Abort).unwrap_err();
+        assert!(matches!(error, Error::ParseFailure { line: Some(3), .. }));
+        let error = serialize_records(
+            crate::nav_from_str(FEED),
+            serde_json::value::Serializer,
+            ParseErrorPolicy::Abort,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("garbage"));
+    }
+}
@@ -0,0 +1,229 @@
+//! JSON Lines export
+
+use super::{RecordSink, WriteStats};
+use crate::{NavRecord, Result};
+use log::warn;
+use std::io::{self, Write};
+
+/// Streams records as newline delimited JSON, e.g. straight from a
+/// [`NavRecordIterator`](../struct.NavRecordIterator.html)
+///
+/// Each record is serialized completely before its line is written, so a failure never leaves
+/// a partial line behind. Parse and serialization errors are logged, counted and skipped;
+/// write errors abort. Records whose NAV or prices are `NaN` or infinite are serialization
+/// errors, as JSON has no such numbers and their `null` would not read back. The writer is
+/// flushed before returning.
+pub fn write_jsonl<I, W>(results: I, mut w: W) -> Result<WriteStats>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    W: Write,
+{
+    let mut stats = WriteStats::default();
+    let mut line = Vec::new();
+    for result in results {
+        let record = match result {
+            Ok(record) => record,
+            Err(error) => {
+                warn!("{}", error);
+                stats.count_error(&error);
+                continue;
+            }
+        };
+        if !is_finite(&record) {
+            warn!("Error serializing scheme {}: NAV not finite", record.code);
+            stats.errors += 1;
+            continue;
+        }
+        line.clear();
+        if let Err(error) = serde_json::to_writer(&mut line, &record) {
+            warn!("Error serializing scheme {}: {}", record.code, error);
+            stats.errors += 1;
+            continue;
+        }
+        line.push(b'\n');
+        w.write_all(&line)?;
+        stats.records += 1;
+    }
+    w.flush()?;
+    Ok(stats)
+}
+
+/// Whether the NAV and prices of `record` are numbers JSON can hold
+fn is_finite(record: &NavRecord) -> bool {
+    record.nav.is_finite()
+        && [record.repurchase_price, record.sale_price]
+            .iter()
+            .flatten()
+            .all(|price| price.is_finite())
+}
+
+/// [`RecordSink`](trait.RecordSink.html) writing newline delimited JSON, as
+/// [`write_jsonl`](fn.write_jsonl.html) does
+///
+/// Serialization errors, non-finite NAVs included, fail the write, leaving no partial line
+/// behind.
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+    line: Vec<u8>,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    /// Wraps `writer`
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink {
+            writer,
+            line: Vec::new(),
+        }
+    }
+
+    /// Underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RecordSink for JsonLinesSink<W> {
+    fn write_record(&mut self, record: &NavRecord) -> Result<()> {
+        if !is_finite(record) {
+            let reason = format!("NAV of scheme {} not finite", record.code);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, reason).into());
+        }
+        self.line.clear();
+        serde_json::to_writer(&mut self.line, record).map_err(io::Error::from)?;
+        self.line.push(b'\n');
+        self.writer.write_all(&self.line)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_path;
+    use crate::{NavRecordIterator, ZeroNavPolicy};
+
+    #[test]
+    fn fixture_to_jsonl() {
+        let path = fixture_path("NAVOpen.txt");
+        let mut out = Vec::new();
+        let stats = write_jsonl(crate::nav_from_file(&path).unwrap(), &mut out).unwrap();
+        assert_eq!(
+            stats,
+            WriteStats {
+                records: 30,
+                errors: 0,
+                too_many_errors: false,
+            }
+        );
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 30);
+        assert_eq!(lines[0]["code"], 120_465);
+    }
+
+    #[test]
+    fn sink_writes_as_write_jsonl() {
+        let path = fixture_path("NAVOpen.txt");
+        let mut expected = Vec::new();
+        write_jsonl(crate::nav_from_file(&path).unwrap(), &mut expected).unwrap();
+
+        let mut sink = JsonLinesSink::new(Vec::new());
+        for record in crate::nav_from_file(&path).unwrap() {
+            sink.write_record(&record.unwrap()).unwrap();
+        }
+        sink.finish().unwrap();
+        assert_eq!(sink.into_inner(), expected);
+    }
+
+    #[test]
+    fn parse_errors_are_skipped() {
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n";
+        let mut out = Vec::new();
+        let stats = write_jsonl(NavRecordIterator::new(feed.as_bytes()), &mut out).unwrap();
+        assert_eq!(
+            stats,
+            WriteStats {
+                records: 2,
+                errors: 1,
+                too_many_errors: false,
+            }
+        );
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn infinite_navs_are_parse_errors() {
+        // serde_json would write `null` for an infinite NAV, which reads back as no number
+        let feed = format!(
+            "Axis Mutual Fund\n\
+             120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;1{}.5;21-Jun-2024\n\
+             112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n",
+            "0".repeat(400)
+        );
+        let mut out = Vec::new();
+        let stats = write_jsonl(NavRecordIterator::new(feed.as_bytes()), &mut out).unwrap();
+        assert_eq!((stats.records, stats.errors), (1, 1));
+        let line: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(line["nav"], 54.12);
+    }
+
+    #[test]
+    fn non_finite_navs_are_errors() {
+        // Zero NAVs treated as missing are errors of the iterator, not NaN NAVs
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;0.0000;21-Jun-2024\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n";
+        let records =
+            NavRecordIterator::new(feed.as_bytes()).zero_nav_policy(ZeroNavPolicy::TreatAsMissing);
+        let mut out = Vec::new();
+        let stats = write_jsonl(records, &mut out).unwrap();
+        assert_eq!((stats.records, stats.errors), (1, 1));
+        let text = String::from_utf8(out).unwrap();
+        let read: Vec<NavRecord> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(read[0].nav, 54.12);
+
+        // NaN set on a record some other way
+        let nan = NavRecord::fixture().with_nav(f64::NAN);
+        let mut out = Vec::new();
+        let stats = write_jsonl(vec![Ok(nan.clone()), Ok(NavRecord::fixture())], &mut out).unwrap();
+        assert_eq!((stats.records, stats.errors), (1, 1));
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 1);
+        let mut sink = JsonLinesSink::new(Vec::new());
+        assert!(sink.write_record(&nan).is_err());
+        assert!(sink.into_inner().is_empty());
+    }
+
+    #[test]
+    fn stops_at_error_limit() {
+        let path = fixture_path("NAVHalfGarbage.txt");
+        let records = crate::nav_from_file(&path)
+            .unwrap()
+            .max_error_ratio(0.25, 4);
+        let mut out = Vec::new();
+        let stats = write_jsonl(records, &mut out).unwrap();
+        // Two good lines and two bad ones, then the verdict
+        assert_eq!(
+            stats,
+            WriteStats {
+                records: 2,
+                errors: 3,
+                too_many_errors: true,
+            }
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//! Exporting records to other formats
+
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "serde")]
+mod jsonl;
+mod shard;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "arrow")]
+pub use self::arrow::{schema as arrow_schema, to_record_batch};
+#[cfg(feature = "parquet")]
+pub use self::arrow::{write_parquet, ROW_GROUP_SIZE};
+#[cfg(feature = "csv")]
+pub use self::csv::{write_csv, write_csv_stream, CsvSink};
+#[cfg(feature = "serde")]
+pub use self::json::{
+    serialize_records, to_json_string, to_json_writer, write_nested_json, ParseErrorPolicy,
+};
+#[cfg(feature = "serde")]
+pub use self::jsonl::{write_jsonl, JsonLinesSink};
+pub use self::shard::{shard_by_amc, RecordSink, ShardStats};
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::{write_sqlite, SqliteStats};
+
+/// Outcome of a streaming export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteStats {
+    /// Records written
+    pub records: usize,
+    /// Records skipped due to parse or serialization errors
+    pub errors: usize,
+    /// Whether the input ended early with
+    /// [`Error::TooManyErrors`](../enum.Error.html#variant.TooManyErrors), counted in `errors`
+    pub too_many_errors: bool,
+}
+
+impl WriteStats {
+    /// Counts a record skipped for `error`
+    fn count_error(&mut self, error: &crate::Error) {
+        self.errors += 1;
+        if let crate::Error::TooManyErrors { .. } = error {
+            self.too_many_errors = true;
+        }
+    }
+}
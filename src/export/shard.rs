@@ -0,0 +1,222 @@
+//! Records written to one sink per AMC
+
+use super::WriteStats;
+use crate::{AmcNormalizer, Error, NavRecord, Result};
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+
+/// Destination of records in some format, e.g. a shard of [`shard_by_amc`](fn.shard_by_amc.html)
+///
+/// Implemented by [`JsonLinesSink`](struct.JsonLinesSink.html) with the `serde` feature and
+/// [`CsvSink`](struct.CsvSink.html) with the `csv` feature, and by boxed sinks, so shards may
+/// differ in format.
+pub trait RecordSink {
+    /// Writes `record`
+    fn write_record(&mut self, record: &NavRecord) -> Result<()>;
+
+    /// Writes whatever is left and flushes, once after the last record
+    fn finish(&mut self) -> Result<()>;
+}
+
+impl<S: RecordSink + ?Sized> RecordSink for Box<S> {
+    fn write_record(&mut self, record: &NavRecord) -> Result<()> {
+        (**self).write_record(record)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        (**self).finish()
+    }
+}
+
+/// Outcome of [`shard_by_amc`](fn.shard_by_amc.html)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardStats {
+    /// Records written to all shards and parse errors skipped
+    pub written: WriteStats,
+    /// Records written to each shard, by normalized AMC name
+    pub shards: BTreeMap<String, usize>,
+}
+
+/// Writes each record to the sink of its AMC, opened by `open` with the AMC name normalized
+/// by [`normalize_amc`](fn.normalize_amc.html), e.g. `HDFC` for `HDFC Mutual Fund`
+///
+/// Sinks are opened as the first record of their AMC arrives, so AMCs without records get
+/// none, and finished once the input is exhausted; they are dropped, closing files, before
+/// returning. Parse errors are logged, counted and skipped. An error opening, writing or
+/// finishing a sink aborts with
+/// [`Error::InShard`](enum.Error.html#variant.InShard) naming its AMC; sinks are not
+/// finished then.
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "serde")]
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::JsonLinesSink;
+/// use std::fs::File;
+/// use std::io::BufWriter;
+///
+/// let stats = amfi::shard_by_amc(amfi::nav_from_file("NAVAll.txt")?, |amc| {
+///     let file = File::create(format!("{}.jsonl", amc.to_lowercase().replace(' ', "-")))?;
+///     Ok(JsonLinesSink::new(BufWriter::new(file)))
+/// })?;
+/// for (amc, records) in &stats.shards {
+///     println!("{}: {} records", amc, records);
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "serde"))]
+/// # fn main() {}
+/// ```
+pub fn shard_by_amc<I, F, S>(results: I, mut open: F) -> Result<ShardStats>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    F: FnMut(&str) -> Result<S>,
+    S: RecordSink,
+{
+    let normalizer = AmcNormalizer::new();
+    let mut written = WriteStats::default();
+    // Shards in the order opened, and the shard of each AMC name as published
+    let mut shards: Vec<(String, S, usize)> = Vec::new();
+    let mut by_amc: HashMap<String, usize> = HashMap::new();
+    for result in results {
+        let record = match result {
+            Ok(record) => record,
+            Err(error) => {
+                warn!("{}", error);
+                written.count_error(&error);
+                continue;
+            }
+        };
+        let index = match by_amc.get(&record.amc) {
+            Some(&index) => index,
+            None => {
+                let amc = normalizer.normalize(&record.amc);
+                let index = match shards.iter().position(|(name, ..)| *name == amc) {
+                    Some(index) => index,
+                    None => {
+                        let sink = open(&amc).map_err(|error| in_shard(&amc, error))?;
+                        shards.push((amc, sink, 0));
+                        shards.len() - 1
+                    }
+                };
+                by_amc.insert(record.amc.clone(), index);
+                index
+            }
+        };
+        let (amc, sink, records) = &mut shards[index];
+        sink.write_record(&record)
+            .map_err(|error| in_shard(amc, error))?;
+        *records += 1;
+        written.records += 1;
+    }
+    for (amc, sink, _) in &mut shards {
+        sink.finish().map_err(|error| in_shard(amc, error))?;
+    }
+    Ok(ShardStats {
+        written,
+        shards: shards
+            .into_iter()
+            .map(|(amc, _, records)| (amc, records))
+            .collect(),
+    })
+}
+
+fn in_shard(amc: &str, error: Error) -> Error {
+    Error::InShard {
+        amc: amc.to_string(),
+        error: Box::new(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Sink keeping the codes written, shared with the test
+    #[derive(Clone, Default)]
+    struct Codes {
+        codes: Rc<RefCell<Vec<u32>>>,
+        finished: Rc<RefCell<bool>>,
+        fail_at: Option<usize>,
+    }
+
+    impl RecordSink for Codes {
+        fn write_record(&mut self, record: &NavRecord) -> Result<()> {
+            let mut codes = self.codes.borrow_mut();
+            if Some(codes.len()) == self.fail_at {
+                return Err(std::io::Error::other("disk full").into());
+            }
+            codes.push(record.code.into());
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            *self.finished.borrow_mut() = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shards_by_normalized_amc() {
+        let records = fixture_records("NAVOpen.txt");
+        let sinks: RefCell<BTreeMap<String, Codes>> = RefCell::default();
+        let stats = shard_by_amc(records.iter().cloned().map(Ok), |amc| {
+            let sink = Codes::default();
+            let previous = sinks.borrow_mut().insert(amc.to_string(), sink.clone());
+            assert!(previous.is_none(), "{} opened twice", amc);
+            Ok(sink)
+        })
+        .unwrap();
+
+        let sinks = sinks.into_inner();
+        assert_eq!(
+            sinks.keys().collect::<Vec<_>>(),
+            [
+                "Aditya Birla Sun Life",
+                "Axis",
+                "HDFC",
+                "ICICI Prudential",
+                "Nippon India",
+                "SBI"
+            ]
+        );
+        for (amc, sink) in &sinks {
+            let expected: Vec<u32> = records
+                .iter()
+                .filter(|record| crate::normalize_amc(&record.amc) == *amc)
+                .map(|record| record.code.into())
+                .collect();
+            assert_eq!(*sink.codes.borrow(), expected, "{}", amc);
+            assert_eq!(stats.shards[amc], expected.len());
+            assert!(*sink.finished.borrow());
+        }
+        assert_eq!(stats.written.records, 30);
+        assert_eq!(stats.shards.values().sum::<usize>(), 30);
+    }
+
+    #[test]
+    fn write_errors_name_the_shard() {
+        let records = fixture_records("NAVOpen.txt");
+        let result = shard_by_amc(records.iter().cloned().map(Ok), |amc| {
+            Ok(Codes {
+                fail_at: if amc == "HDFC" { Some(1) } else { None },
+                ..Codes::default()
+            })
+        });
+        match result {
+            Err(Error::InShard { amc, error }) => {
+                assert_eq!(amc, "HDFC");
+                assert!(matches!(*error, Error::IoError(_)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let result = shard_by_amc(records.iter().cloned().map(Ok), |amc| match amc {
+            "SBI" => Err(std::io::Error::other("permission denied").into()),
+            _ => Ok(Codes::default()),
+        });
+        assert!(matches!(result, Err(Error::InShard { amc, .. }) if amc == "SBI"));
+    }
+}
@@ -0,0 +1,153 @@
+//! SQLite export
+
+use crate::{NavRecord, Result};
+use rusqlite::{params, Connection};
+
+/// Outcome of [`write_sqlite`](fn.write_sqlite.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SqliteStats {
+    /// Rows for new `(code, date)` keys
+    pub inserted: usize,
+    /// Existing rows overwritten
+    pub updated: usize,
+}
+
+/// Columns after the `(code, date)` key, in [`NavRecord`](../struct.NavRecord.html) order
+const COLUMNS: [&str; 10] = [
+    "isin", "isin_dr", "name", "nav", "amc", "category", "scheme", "maturity", "plan", "option",
+];
+
+/// Writes records into `table`, creating it if needed
+///
+/// The table has one column per `NavRecord` field with `(code, date)` as primary key. Dates
+/// are stored as ISO 8601 text and enums via their `Display` impls. Records whose key already
+/// exists overwrite the stored row, so loading the same feed twice is idempotent.
+///
+/// All rows are written in a single transaction; on error nothing is committed.
+pub fn write_sqlite<I>(records: I, conn: &Connection, table: &str) -> Result<SqliteStats>
+where
+    I: IntoIterator<Item = NavRecord>,
+{
+    let table = quote(table);
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            code INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            isin TEXT,
+            isin_dr TEXT,
+            name TEXT NOT NULL,
+            nav REAL NOT NULL,
+            amc TEXT NOT NULL,
+            category TEXT NOT NULL,
+            scheme TEXT,
+            maturity TEXT,
+            plan TEXT NOT NULL,
+            option TEXT,
+            PRIMARY KEY (code, date)
+        )",
+        table
+    ))?;
+
+    let tx = conn.unchecked_transaction()?;
+    let mut stats = SqliteStats::default();
+    {
+        let mut insert = tx.prepare(&format!(
+            "INSERT INTO {} (code, date, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT (code, date) DO NOTHING",
+            table,
+            COLUMNS.join(", ")
+        ))?;
+        let assignments: Vec<String> = COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ?{}", column, i + 3))
+            .collect();
+        let mut update = tx.prepare(&format!(
+            "UPDATE {} SET {} WHERE code = ?1 AND date = ?2",
+            table,
+            assignments.join(", ")
+        ))?;
+        for record in records {
+            let date = record.date.to_string();
+            let maturity = record.maturity.map(|maturity| maturity.to_string());
+            let plan = record.plan.to_string();
+            let values = params![
+                record.code.get(),
+                date,
+                record.isin.as_ref().map(|isin| isin.as_str()),
+                record.isin_dr.as_ref().map(|isin| isin.as_str()),
+                record.name,
+                record.nav,
+                record.amc,
+                record.category,
+                record.scheme,
+                maturity,
+                plan,
+                record.option,
+            ];
+            if insert.execute(values)? == 1 {
+                stats.inserted += 1;
+            } else {
+                update.execute(values)?;
+                stats.updated += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(stats)
+}
+
+/// Quotes `name` as an SQL identifier
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+
+    #[test]
+    fn fixture_upsert_is_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        let stats = write_sqlite(fixture_records("NAVOpen.txt"), &conn, "nav").unwrap();
+        assert_eq!(
+            stats,
+            SqliteStats {
+                inserted: 30,
+                updated: 0
+            }
+        );
+
+        let mut records = fixture_records("NAVOpen.txt");
+        records[0].nav = 62.0;
+        let stats = write_sqlite(records, &conn, "nav").unwrap();
+        assert_eq!(
+            stats,
+            SqliteStats {
+                inserted: 0,
+                updated: 30
+            }
+        );
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM nav", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 30);
+        let (nav, isin_dr, plan): (f64, Option<String>, String) = conn
+            .query_row(
+                "SELECT nav, isin_dr, plan FROM nav WHERE code = 120465 AND date = '2024-06-21'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((nav, isin_dr, plan.as_str()), (62.0, None, "Direct"));
+    }
+
+    #[test]
+    fn table_name_is_quoted() {
+        let conn = Connection::open_in_memory().unwrap();
+        let stats = write_sqlite(fixture_records("NAVOpen.txt"), &conn, "nav \"daily\"").unwrap();
+        assert_eq!(stats.inserted, 30);
+    }
+}
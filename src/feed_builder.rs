@@ -0,0 +1,329 @@
+//! NAVAll feeds built from records specified in code, for testing consumers of feeds
+
+use crate::normalize::NormalizeOptions;
+use crate::plan::name_plan;
+use crate::writer::feed_order;
+use crate::{
+    parse_scheme_header, FofKind, FundMaturity, FundPlan, Layout, NavDate, NavRecord, NavWriter,
+    PlanSource, RawIsin, SchemeCode,
+};
+
+/// Builds a NAVAll feed of one day, AMC by AMC
+///
+/// The feed is written by [`NavWriter`](struct.NavWriter.html) as published: column header,
+/// scheme headers with the AMCs listed under each, then their record lines. Records are
+/// grouped by scheme section first, as in the published feed, whatever the order of the
+/// calls. [`records`](#method.records) are those parsing the [`feed`](#method.feed) gives.
+///
+/// ```rust
+/// use amfi::{FeedBuilder, FundMaturity::OpenEnded, NavDate};
+///
+/// let builder = FeedBuilder::new(NavDate::from_ymd(2024, 6, 21).unwrap()).amc(
+///     "Axis Mutual Fund",
+///     |amc| {
+///         amc.section(OpenEnded, "Equity Scheme - Large Cap Fund", |s| {
+///             s.record(119551, "INF846K01EW2", None, "Axis Bluechip Fund - Direct Plan - Growth", 52.31)
+///         })
+///     },
+/// );
+/// let feed = builder.feed();
+/// assert!(feed.contains("\r\nOpen Ended Schemes(Equity Scheme - Large Cap Fund)\r\n"));
+/// let parsed = amfi::nav_from_str(&feed).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(parsed, builder.records());
+/// assert_eq!(parsed[0].plan, amfi::FundPlan::Direct);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FeedBuilder {
+    date: NavDate,
+    records: Vec<NavRecord>,
+}
+
+/// Scheme sections of an AMC, see [`FeedBuilder::amc`](struct.FeedBuilder.html#method.amc)
+#[derive(Debug, Clone)]
+pub struct AmcBuilder {
+    date: NavDate,
+    amc: String,
+    records: Vec<NavRecord>,
+}
+
+/// Records of an AMC under one scheme header, see
+/// [`AmcBuilder::section`](struct.AmcBuilder.html#method.section)
+#[derive(Debug, Clone)]
+pub struct SectionBuilder {
+    /// Record of the section without code, name or price
+    template: NavRecord,
+    records: Vec<NavRecord>,
+}
+
+impl FeedBuilder {
+    /// Feed of NAVs on `date`, without AMCs
+    pub fn new(date: NavDate) -> Self {
+        FeedBuilder {
+            date,
+            records: Vec::new(),
+        }
+    }
+
+    /// Adds the sections of `amc` built by `f`
+    ///
+    /// Named again, an AMC gets more sections, or more records in the sections it has.
+    pub fn amc<F>(mut self, amc: &str, f: F) -> Self
+    where
+        F: FnOnce(AmcBuilder) -> AmcBuilder,
+    {
+        let amc = f(AmcBuilder {
+            date: self.date,
+            amc: amc.trim().to_string(),
+            records: Vec::new(),
+        });
+        self.records.extend(amc.records);
+        self
+    }
+
+    /// The feed in NAVAll format, lines ending with `\r\n`
+    pub fn feed(&self) -> String {
+        let mut writer = NavWriter::new(Vec::new());
+        writer
+            .write_records(&self.records)
+            .expect("writing to memory");
+        String::from_utf8(writer.finish().expect("writing to memory")).expect("UTF-8 records")
+    }
+
+    /// Records of the feed in feed order, as parsed from it
+    pub fn records(&self) -> Vec<NavRecord> {
+        feed_order(&self.records, Layout::Modern)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+}
+
+impl AmcBuilder {
+    /// Adds the records built by `f` under the header of `maturity` and `category`, the text
+    /// within its parentheses, e.g. `Equity Scheme - Large Cap Fund`
+    ///
+    /// Scheme and category are split off `category` as the parser does, and the kind of Fund
+    /// of Funds is given by them or the names of records.
+    ///
+    /// # Panics
+    ///
+    /// If `category` makes a malformed scheme header, e.g. with unbalanced parentheses.
+    pub fn section<F>(mut self, maturity: FundMaturity, category: &str, f: F) -> Self
+    where
+        F: FnOnce(SectionBuilder) -> SectionBuilder,
+    {
+        let header = format!("{} Schemes({})", maturity, category);
+        let header = parse_scheme_header(&header).expect("well-formed scheme header");
+        let normalize = NormalizeOptions::default();
+        let template = NavRecord {
+            date: self.date,
+            amc: self.amc.clone(),
+            fof: FofKind::of_category(&header.scheme_category()),
+            category: normalize.category(header.category),
+            scheme: header.scheme_group.map(|s| normalize.scheme(s)),
+            maturity: header.maturity,
+            ..NavRecord::fixture()
+        };
+        let section = f(SectionBuilder {
+            template,
+            records: Vec::new(),
+        });
+        self.records.extend(section.records);
+        self
+    }
+}
+
+impl SectionBuilder {
+    /// Adds the record of scheme `code`, its ISINs, `name` and `nav`
+    ///
+    /// The plan is read off the name, as the parser does.
+    ///
+    /// # Panics
+    ///
+    /// If `name` has a `;` or a line break, which would split its record line.
+    pub fn record<'a, I, R>(mut self, code: u32, isin: I, isin_dr: R, name: &str, nav: f64) -> Self
+    where
+        I: Into<Option<&'a str>>,
+        R: Into<Option<&'a str>>,
+    {
+        assert!(
+            !name.contains(&[';', '\r', '\n'][..]),
+            "scheme name {:?} splits its record line",
+            name
+        );
+        let name = name.trim();
+        let plan = name_plan(name);
+        self.records.push(NavRecord {
+            code: SchemeCode::from(code),
+            isin: isin.into().map(RawIsin::new),
+            isin_dr: isin_dr.into().map(RawIsin::new),
+            name: name.to_string(),
+            nav,
+            plan: plan.unwrap_or(FundPlan::Regular),
+            plan_source: if plan.is_some() {
+                PlanSource::NameToken
+            } else {
+                PlanSource::Default
+            },
+            fof: self.template.fof.or_else(|| FofKind::from_name(name)),
+            ..self.template.clone()
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nav_from_str, FundMaturity::*, Result};
+    use proptest::prelude::*;
+
+    fn june_21() -> NavDate {
+        NavDate::from_ymd(2024, 6, 21).unwrap()
+    }
+
+    #[test]
+    fn sections_are_grouped() {
+        let builder = FeedBuilder::new(june_21())
+            .amc("Axis Mutual Fund", |amc| {
+                amc.section(OpenEnded, "Equity Scheme - Large Cap Fund", |s| {
+                    s.record(
+                        120465,
+                        "INF846K01AB4",
+                        None,
+                        "Axis Bluechip Fund - Direct Plan - Growth",
+                        61.43,
+                    )
+                    .record(
+                        120466,
+                        "INF846K01AC2",
+                        "INF846K01AD0",
+                        "Axis Bluechip Fund - Direct Plan - IDCW",
+                        21.98,
+                    )
+                })
+                .section(CloseEnded, "Income", |s| {
+                    s.record(
+                        100001,
+                        None,
+                        None,
+                        "Axis Fixed Term Plan - Series 1 - Growth",
+                        10.5,
+                    )
+                })
+            })
+            .amc("HDFC Mutual Fund", |amc| {
+                amc.section(OpenEnded, "Equity Scheme - Large Cap Fund", |s| {
+                    s.record(
+                        118955,
+                        "INF179K01AJ3",
+                        None,
+                        "HDFC Top 100 Fund - Growth",
+                        1050.0,
+                    )
+                })
+            });
+        let feed = builder.feed();
+        let headers: Vec<_> = feed
+            .lines()
+            .filter(|line| !line.is_empty() && !line.contains(';'))
+            .collect();
+        assert_eq!(
+            headers,
+            [
+                "Open Ended Schemes(Equity Scheme - Large Cap Fund)",
+                "Axis Mutual Fund",
+                "HDFC Mutual Fund",
+                "Close Ended Schemes(Income)",
+                "Axis Mutual Fund",
+            ]
+        );
+        let records = builder.records();
+        let codes: Vec<u32> = records.iter().map(|record| record.code.into()).collect();
+        assert_eq!(codes, [120465, 120466, 118955, 100001]);
+        assert_eq!(records[2].plan_source, PlanSource::Default);
+        assert_eq!(records[3].scheme, None);
+        assert_eq!(records[3].maturity, Some(CloseEnded));
+        assert_eq!(
+            nav_from_str(&feed).collect::<Result<Vec<_>>>().unwrap(),
+            records
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "splits its record line")]
+    fn names_stay_on_their_line() {
+        FeedBuilder::new(june_21()).amc("Axis Mutual Fund", |amc| {
+            amc.section(OpenEnded, "Income", |s| {
+                s.record(1, None, None, "Fund\n120465;-;-;Other", 1.0)
+            })
+        });
+    }
+
+    /// Section of an AMC: maturity, category and records of code, ISINs, name and NAV
+    type SectionSpec = (bool, usize, Vec<(u32, bool, bool, String, u32)>);
+
+    fn sections() -> impl Strategy<Value = Vec<(usize, SectionSpec)>> {
+        let record = (
+            1_u32..999_999,
+            prop::bool::ANY,
+            prop::bool::ANY,
+            "[A-Za-z][A-Za-z0-9 ()&-]{0,40}",
+            0_u32..10_000_000,
+        );
+        let section = (
+            prop::bool::ANY,
+            0_usize..4,
+            prop::collection::vec(record, 0..6),
+        );
+        prop::collection::vec((0_usize..3, section), 0..8)
+    }
+
+    const AMCS: [&str; 3] = [
+        "Axis Mutual Fund",
+        "HDFC Mutual Fund",
+        "360 ONE Mutual Fund",
+    ];
+    const CATEGORIES: [&str; 4] = [
+        "Equity Scheme - Large Cap Fund",
+        "Income",
+        "Other Scheme - FoF Overseas",
+        "Debt Scheme - Banking and PSU Fund",
+    ];
+
+    proptest! {
+        #[test]
+        fn built_feeds_parse_back(sections in sections()) {
+            let mut builder = FeedBuilder::new(june_21());
+            let mut specified = Vec::new();
+            for (amc, (open, category, records)) in &sections {
+                let maturity = if *open { OpenEnded } else { CloseEnded };
+                builder = builder.amc(AMCS[*amc], |amc| {
+                    amc.section(maturity, CATEGORIES[*category], |mut s| {
+                        for (code, isin, isin_dr, name, nav) in records {
+                            let isin = if *isin { Some("INF846K01AB4") } else { None };
+                            let isin_dr = if *isin_dr { Some("INF846K01AD0") } else { None };
+                            let nav = f64::from(*nav) / 10_000.0;
+                            s = s.record(*code, isin, isin_dr, name, nav);
+                            specified.push((*code, name.trim().to_string(), nav));
+                        }
+                        s
+                    })
+                });
+            }
+            let parsed = nav_from_str(&builder.feed()).collect::<Result<Vec<_>>>().unwrap();
+            prop_assert_eq!(&parsed, &builder.records());
+
+            // The records specified, in feed order
+            let mut parsed: Vec<_> = parsed
+                .into_iter()
+                .map(|record| (record.code.into(), record.name, record.nav))
+                .collect();
+            prop_assert_eq!(parsed.len(), specified.len());
+            let key = |&(code, ref name, nav): &(u32, String, f64)| (code, name.clone(), nav.to_bits());
+            parsed.sort_by_key(key);
+            specified.sort_by_key(key);
+            prop_assert_eq!(parsed, specified);
+        }
+    }
+}
@@ -0,0 +1,1393 @@
+//! Downloading the feed, its HTTP metadata and cheap update checks
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::charset::Charset;
+use crate::RateLimiter;
+use crate::BASE_URL;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::{trace::FeedSpan, DecodePolicy, Error, NavRecordIterator, Result};
+#[cfg(feature = "blocking")]
+use crate::{AmcId, NavDate, NavRecord, SchemeCode};
+use reqwest::header::{self, HeaderMap};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use reqwest::StatusCode;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "blocking")]
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::io::Cursor;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::time::Instant;
+use std::time::SystemTime;
+#[cfg(feature = "blocking")]
+use std::vec::IntoIter;
+#[cfg(feature = "blocking")]
+use {reqwest::blocking::Response, std::io::Read};
+
+/// Settings of [`daily_nav_with`](fn.daily_nav_with.html)
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "blocking")]
+/// # fn main() -> Result<(), amfi::Error> {
+/// use std::time::Duration;
+///
+/// let options = amfi::FetchOptions::new()
+///     .rate_limiter(amfi::RateLimiter::new(Duration::from_secs(5 * 60)));
+/// let records = amfi::daily_nav_with(&options)?;
+/// // Waits until five minutes after the previous request
+/// let again = amfi::daily_nav_with(&options)?;
+/// # drop((records, again));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "blocking"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    url: String,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) sniff_content: bool,
+    #[cfg(feature = "async")]
+    pub(crate) concurrency: usize,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        FetchOptions {
+            url: BASE_URL.to_string(),
+            rate_limiter: None,
+            sniff_content: true,
+            #[cfg(feature = "async")]
+            concurrency: 4,
+        }
+    }
+}
+
+impl FetchOptions {
+    /// Downloads from the AMFI portal without rate limiting
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Downloads from `url` instead of the AMFI portal
+    pub fn url<T: Into<String>>(mut self, url: T) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Waits for `limiter` before each request
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Sets whether to fail on bodies that aren't a NAV feed, on by default
+    ///
+    /// See [`NavRecordIterator::sniff_content`](struct.NavRecordIterator.html#method.sniff_content).
+    pub fn sniff_content(mut self, sniff: bool) -> Self {
+        self.sniff_content = sniff;
+        self
+    }
+
+    /// Downloads at most `n` feeds at once with
+    /// [`fetch_all_async`](fn.fetch_all_async.html), 4 by default
+    #[cfg(feature = "async")]
+    pub fn concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n;
+        self
+    }
+}
+
+/// Cache validators of a feed response
+///
+/// Obtained with [`daily_nav_with_meta`](fn.daily_nav_with_meta.html) when downloading, and
+/// stored to later ask [`is_nav_updated`](fn.is_nav_updated.html) whether the feed changed.
+///
+/// Times are `SystemTime`s, which `chrono::DateTime<Utc>` converts from and to. With the
+/// `serde` feature they serialize as HTTP dates, e.g. `Fri, 21 Jun 2024 16:10:05 GMT`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FetchMeta {
+    /// `Last-Modified` header, i.e. when AMFI published the feed
+    #[cfg_attr(feature = "serde", serde(with = "http_date::option"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub last_modified: Option<SystemTime>,
+    /// `ETag` header
+    pub etag: Option<String>,
+    /// Size of the feed in bytes
+    pub content_length: Option<u64>,
+}
+
+/// What the download of a feed observed, see
+/// [`NavRecordIterator::response_meta`](struct.NavRecordIterator.html#method.response_meta)
+///
+/// Its validators are those of [`FetchMeta`](struct.FetchMeta.html), for later update
+/// checks, see [`fetch_meta`](#method.fetch_meta). Times serialize as HTTP dates, to the
+/// second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ResponseMeta {
+    /// HTTP status of the response
+    pub status: u16,
+    /// URL of the response, after redirects
+    pub url: String,
+    /// `Content-Type` header
+    pub content_type: Option<String>,
+    /// `Last-Modified` header, i.e. when AMFI published the feed
+    #[cfg_attr(feature = "serde", serde(with = "http_date::option"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub last_modified: Option<SystemTime>,
+    /// `ETag` header
+    pub etag: Option<String>,
+    /// Size of the feed in bytes
+    pub content_length: Option<u64>,
+    /// When the response was received
+    #[cfg_attr(feature = "serde", serde(with = "http_date"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub fetched_at: SystemTime,
+}
+
+impl ResponseMeta {
+    /// Metadata of a response from `url` with `status` and `headers`, received now
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn new(status: StatusCode, url: &str, headers: &HeaderMap) -> Self {
+        let FetchMeta {
+            last_modified,
+            etag,
+            content_length,
+        } = FetchMeta::from_headers(headers);
+        ResponseMeta {
+            status: status.as_u16(),
+            url: url.to_string(),
+            content_type: headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            last_modified,
+            etag,
+            content_length,
+            fetched_at: SystemTime::now(),
+        }
+    }
+
+    /// Validators of the response, to ask [`is_nav_updated`](fn.is_nav_updated.html) later
+    pub fn fetch_meta(&self) -> FetchMeta {
+        FetchMeta {
+            last_modified: self.last_modified,
+            etag: self.etag.clone(),
+            content_length: self.content_length,
+        }
+    }
+}
+
+impl From<&ResponseMeta> for FetchMeta {
+    fn from(response: &ResponseMeta) -> Self {
+        response.fetch_meta()
+    }
+}
+
+/// Outcome of comparing [`FetchMeta`](struct.FetchMeta.html)s
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdateStatus {
+    /// Feed changed
+    Updated,
+    /// Feed is the same
+    Unchanged,
+    /// The server sent no validator that can be compared with the stored ones
+    Unknown,
+}
+
+/// Result of [`is_nav_updated`](fn.is_nav_updated.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavUpdate {
+    /// Whether the feed changed
+    pub status: UpdateStatus,
+    /// Current metadata reported by the server
+    pub meta: FetchMeta,
+}
+
+impl FetchMeta {
+    /// Reads validators from response headers, ignoring malformed values
+    ///
+    /// For partial responses the size is taken from `Content-Range`.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let text = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        let total_size = text(header::CONTENT_RANGE)
+            .and_then(|range| range.rsplit('/').next())
+            .and_then(|total| total.trim().parse().ok());
+        FetchMeta {
+            last_modified: text(header::LAST_MODIFIED)
+                .and_then(|date| httpdate::parse_http_date(date).ok()),
+            etag: text(header::ETAG).map(str::to_string),
+            content_length: total_size
+                .or_else(|| text(header::CONTENT_LENGTH).and_then(|len| len.trim().parse().ok())),
+        }
+    }
+
+    /// Whether no validator is known
+    pub fn is_empty(&self) -> bool {
+        self.last_modified.is_none() && self.etag.is_none() && self.content_length.is_none()
+    }
+
+    /// Compares with previously stored metadata
+    ///
+    /// The strongest validator present on both sides decides: `ETag`, then `Last-Modified`,
+    /// then size. Without a common validator the status is `Unknown`.
+    pub fn status_since(&self, since: &FetchMeta) -> UpdateStatus {
+        let changed = match (self, since) {
+            (
+                FetchMeta {
+                    etag: Some(etag), ..
+                },
+                FetchMeta {
+                    etag: Some(since), ..
+                },
+            ) => etag != since,
+            (
+                FetchMeta {
+                    last_modified: Some(modified),
+                    ..
+                },
+                FetchMeta {
+                    last_modified: Some(since),
+                    ..
+                },
+            ) => modified != since,
+            (
+                FetchMeta {
+                    content_length: Some(len),
+                    ..
+                },
+                FetchMeta {
+                    content_length: Some(since),
+                    ..
+                },
+            ) => len != since,
+            _ => return UpdateStatus::Unknown,
+        };
+        if changed {
+            UpdateStatus::Updated
+        } else {
+            UpdateStatus::Unchanged
+        }
+    }
+}
+
+/// Parses NAV data from [AMFI](https://www.amfiindia.com) portal, returning its metadata too
+#[cfg(feature = "blocking")]
+pub fn daily_nav_with_meta() -> Result<(NavRecordIterator<Response>, FetchMeta)> {
+    nav_from_url_with_meta(BASE_URL)
+}
+
+/// Parses NAV data from provided `url`, returning its metadata too
+#[cfg(feature = "blocking")]
+pub fn nav_from_url_with_meta<T: AsRef<str>>(
+    url: T,
+) -> Result<(NavRecordIterator<Response>, FetchMeta)> {
+    let records = get_records(url.as_ref(), None, true)?;
+    let meta = records
+        .response_meta()
+        .map(FetchMeta::from)
+        .unwrap_or_default();
+    Ok((records, meta))
+}
+
+/// Parses NAV data downloaded with `options`
+#[cfg(feature = "blocking")]
+pub fn daily_nav_with(options: &FetchOptions) -> Result<NavRecordIterator<Response>> {
+    get_records(
+        &options.url,
+        options.rate_limiter.as_ref(),
+        options.sniff_content,
+    )
+}
+
+/// Parses NAV data downloaded with `options` without blocking
+///
+/// The feed is downloaded completely before parsing starts.
+#[cfg(feature = "async")]
+pub async fn daily_nav_with_async(
+    options: &FetchOptions,
+) -> Result<NavRecordIterator<Cursor<Vec<u8>>>> {
+    get_records_async(
+        &options.url,
+        options.rate_limiter.as_ref(),
+        options.sniff_content,
+    )
+    .await
+}
+
+/// Requests the feed at `url` once `limiter` allows, failing on unsuccessful statuses and,
+/// if `sniff`, on bodies that aren't a feed
+#[cfg(feature = "blocking")]
+pub(crate) fn get_records(
+    url: &str,
+    limiter: Option<&RateLimiter>,
+    sniff: bool,
+) -> Result<NavRecordIterator<Response>> {
+    if let Some(limiter) = limiter {
+        limiter.wait();
+    }
+    let span = FeedSpan::url(url);
+    let response = {
+        let _entered = span.enter();
+        trace_event!(info, "fetching feed");
+        let response = reqwest::blocking::get(url)?;
+        trace_event!(
+            info,
+            status = response.status().as_u16(),
+            bytes = ?response.content_length(),
+            "fetched feed"
+        );
+        response
+    };
+    if !response.status().is_success() {
+        return Err(response_error(response, url));
+    }
+    let meta = ResponseMeta::new(
+        response.status(),
+        response.url().as_str(),
+        response.headers(),
+    );
+    let charset = charset(response.headers());
+    let mut records = NavRecordIterator::with_charset(response, charset)
+        .decode(DecodePolicy::Lossy)
+        .in_span(span)
+        .with_response(meta)
+        .sniff_content(sniff);
+    records.check_content(url)?;
+    Ok(records)
+}
+
+/// Downloads the feed at `url` once `limiter` allows, failing on unsuccessful statuses and,
+/// if `sniff`, on bodies that aren't a feed
+#[cfg(feature = "async")]
+pub(crate) async fn get_records_async(
+    url: &str,
+    limiter: Option<&RateLimiter>,
+    sniff: bool,
+) -> Result<NavRecordIterator<Cursor<Vec<u8>>>> {
+    if let Some(limiter) = limiter {
+        limiter.wait_async().await;
+    }
+    let span = FeedSpan::url(url);
+    {
+        let _entered = span.enter();
+        trace_event!(info, "fetching feed");
+    }
+    let started = Instant::now();
+    let response = reqwest::get(url).await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.bytes().await.ok();
+        return Err(http_error(status, url, body.as_deref()));
+    }
+    let meta = ResponseMeta::new(status, response.url().as_str(), response.headers());
+    let charset = charset(response.headers());
+    let body = response.bytes().await?;
+    {
+        let _entered = span.enter();
+        trace_event!(
+            info,
+            status = status.as_u16(),
+            bytes = body.len(),
+            "fetched feed"
+        );
+    }
+    let body = Cursor::new(body.to_vec());
+    let mut records = NavRecordIterator::with_charset(body, charset)
+        .decode(DecodePolicy::Lossy)
+        .in_span(span)
+        .with_response(meta)
+        .fetched_in(started.elapsed())
+        .sniff_content(sniff);
+    records.check_content(url)?;
+    Ok(records)
+}
+
+/// NAV history report of the portal, queried by AMC and date range, or for all AMCs by date
+#[cfg(feature = "blocking")]
+pub(crate) const HISTORY_URL: &str =
+    "https://portal.amfiindia.com/DownloadNAVHistoryReport_Po.aspx";
+
+/// Longest date range, in days, the history report serves at once
+#[cfg(feature = "blocking")]
+const HISTORY_MAX_DAYS: i64 = 90;
+
+/// Page of the portal listing fund houses and their ids
+#[cfg(feature = "blocking")]
+const AMC_IDS_URL: &str = "https://www.amfiindia.com/nav-history-download";
+
+/// Downloads the fund houses currently listed by the portal, with their ids
+///
+/// Maintenance path of the table bundled with the crate, see
+/// [`amc_ids`](fn.amc_ids.html); the `amc_ids` example regenerates it.
+#[cfg(feature = "blocking")]
+pub fn fetch_amc_ids() -> Result<Vec<(AmcId, String)>> {
+    let mut response = reqwest::blocking::get(AMC_IDS_URL)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(http_error(status, AMC_IDS_URL, None));
+    }
+    let mut html = Vec::new();
+    response.read_to_end(&mut html)?;
+    let amcs = amc_options(&String::from_utf8_lossy(&html));
+    if amcs.is_empty() {
+        return Err(Error::UnexpectedContent {
+            first_line: "no fund house options".to_string(),
+            url: Some(AMC_IDS_URL.to_string()),
+        });
+    }
+    Ok(amcs)
+}
+
+/// Fund houses of the `<option value="53">Axis Mutual Fund</option>` elements of `html`
+#[cfg(feature = "blocking")]
+fn amc_options(html: &str) -> Vec<(AmcId, String)> {
+    html.split("<option")
+        .skip(1)
+        .filter_map(|option| {
+            let (attributes, rest) = option.split_once('>')?;
+            let value = attributes.split("value=").nth(1)?;
+            let value = value.trim_start_matches(['"', '\'']);
+            let digits = value.find(|ch: char| !ch.is_ascii_digit())?;
+            let id = value[..digits].parse().ok()?;
+            let name = rest.split('<').next()?.replace("&amp;", "&");
+            let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                None
+            } else {
+                Some((AmcId(id), name))
+            }
+        })
+        .collect()
+}
+
+/// Records of a NAV history download, see [`nav_history_for_amc`](fn.nav_history_for_amc.html)
+///
+/// Downloads the next chunk of the range when the records of the current one run out.
+#[cfg(feature = "blocking")]
+pub struct HistoryRecords {
+    base: String,
+    amc: AmcId,
+    chunks: IntoIter<(NavDate, NavDate)>,
+    current: Option<NavRecordIterator<Cursor<Vec<u8>>>>,
+    rate_limiter: Option<RateLimiter>,
+}
+
+#[cfg(feature = "blocking")]
+impl HistoryRecords {
+    /// Downloads the next chunk with records, `None` once none is left
+    fn next_chunk(&mut self) -> Option<Result<NavRecordIterator<Cursor<Vec<u8>>>>> {
+        for (start, end) in self.chunks.by_ref() {
+            let url = history_url(&self.base, self.amc, start, end);
+            match get_history(&url, self.rate_limiter.as_ref()) {
+                Ok(Some(records)) => return Some(Ok(records)),
+                Ok(None) => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Iterator for HistoryRecords {
+    type Item = Result<NavRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(record);
+            }
+            match self.next_chunk()? {
+                Ok(records) => self.current = Some(records),
+                Err(error) => {
+                    self.current = None;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// Parses the NAV history of the fund house `amc` from `from` to `to`, both included
+///
+/// Ranges longer than the portal serves at once, 90 days, are downloaded in consecutive
+/// chunks, each when the records of the one before run out, and their records chained in
+/// date order. Chunks without records are skipped; if none up to the first with records has
+/// any, fails with [`Error::NoRecords`](enum.Error.html#variant.NoRecords). A later chunk
+/// failing to download is yielded as an error, and iteration goes on with the next one.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// let from = amfi::NavDate::from_ymd(2024, 1, 1).unwrap();
+/// let to = amfi::NavDate::from_ymd(2024, 6, 30).unwrap();
+/// for record in amfi::nav_history_for_amc(amfi::AmcId(53), from, to)? {
+///     let record = record?;
+///     println!("{} {} {}", record.date, record.code, record.nav);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn nav_history_for_amc(amc: AmcId, from: NavDate, to: NavDate) -> Result<HistoryRecords> {
+    history_records(HISTORY_URL, amc, from, to, None)
+}
+
+/// Parses the NAV history of the fund house `amc` from `from` to `to` as
+/// [`nav_history_for_amc`](fn.nav_history_for_amc.html) does, each chunk waiting for the rate
+/// limiter of `options`
+///
+/// The history report has a URL of its own, the one of `options` is not used.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// use std::time::Duration;
+///
+/// let options = amfi::FetchOptions::new()
+///     .rate_limiter(amfi::RateLimiter::new(Duration::from_secs(10)));
+/// let from = amfi::NavDate::from_ymd(2023, 1, 1).unwrap();
+/// let to = amfi::NavDate::from_ymd(2024, 6, 30).unwrap();
+/// for record in amfi::nav_history_for_amc_with(amfi::AmcId(53), from, to, &options)? {
+///     let record = record?;
+///     println!("{} {} {}", record.date, record.code, record.nav);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn nav_history_for_amc_with(
+    amc: AmcId,
+    from: NavDate,
+    to: NavDate,
+    options: &FetchOptions,
+) -> Result<HistoryRecords> {
+    history_records(HISTORY_URL, amc, from, to, options.rate_limiter.clone())
+}
+
+/// History of `amc` from the report at `base`, with the first chunk with records downloaded
+#[cfg(feature = "blocking")]
+fn history_records(
+    base: &str,
+    amc: AmcId,
+    from: NavDate,
+    to: NavDate,
+    rate_limiter: Option<RateLimiter>,
+) -> Result<HistoryRecords> {
+    let mut records = HistoryRecords {
+        base: base.to_string(),
+        amc,
+        chunks: history_chunks(from, to).into_iter(),
+        current: None,
+        rate_limiter,
+    };
+    match records.next_chunk() {
+        Some(first) => records.current = Some(first?),
+        None => return Err(Error::NoRecords { amc, from, to }),
+    }
+    Ok(records)
+}
+
+/// Consecutive ranges of at most [`HISTORY_MAX_DAYS`] covering `from` to `to`
+#[cfg(feature = "blocking")]
+fn history_chunks(from: NavDate, to: NavDate) -> Vec<(NavDate, NavDate)> {
+    let mut chunks = Vec::new();
+    let mut start = from.epoch_days();
+    while start <= to.epoch_days() {
+        let end = (start + HISTORY_MAX_DAYS - 1).min(to.epoch_days());
+        // Both within the range of `from` and `to`
+        let day = |days| NavDate::from_epoch_days(days).unwrap();
+        chunks.push((day(start), day(end)));
+        start = end + 1;
+    }
+    chunks
+}
+
+/// History report at `base` of `amc` from `from` to `to`
+#[cfg(feature = "blocking")]
+fn history_url(base: &str, amc: AmcId, from: NavDate, to: NavDate) -> String {
+    format!(
+        "{}?mf={}&frmdt={}&todt={}",
+        base,
+        amc,
+        from.feed_format(),
+        to.feed_format()
+    )
+}
+
+/// Downloads a history report once `limiter` allows, `None` if the portal has no records
+/// for it
+#[cfg(feature = "blocking")]
+fn get_history(
+    url: &str,
+    limiter: Option<&RateLimiter>,
+) -> Result<Option<NavRecordIterator<Cursor<Vec<u8>>>>> {
+    if let Some(limiter) = limiter {
+        limiter.wait();
+    }
+    let span = FeedSpan::url(url);
+    let started = Instant::now();
+    let body = {
+        let _entered = span.enter();
+        get_history_body(url)?
+    };
+    let (body, charset) = match body {
+        Some(body) => body,
+        None => return Ok(None),
+    };
+    let mut records = NavRecordIterator::with_charset(Cursor::new(body), charset)
+        .decode(DecodePolicy::Lossy)
+        .in_span(span)
+        .fetched_in(started.elapsed());
+    records.check_content(url)?;
+    Ok(Some(records))
+}
+
+/// Downloads the body of a history report with its charset, `None` if the portal has no
+/// records for it
+#[cfg(feature = "blocking")]
+pub(crate) fn get_history_body(url: &str) -> Result<Option<(Vec<u8>, Charset)>> {
+    trace_event!(info, "fetching history");
+    let mut response = reqwest::blocking::get(url)?;
+    let status = response.status();
+    let charset = charset(response.headers());
+    let mut body = Vec::new();
+    if !status.is_success() {
+        let body = response
+            .take(SNIPPET_LEN as u64)
+            .read_to_end(&mut body)
+            .map(|_| body);
+        return Err(http_error(status, url, body.ok().as_deref()));
+    }
+    response.read_to_end(&mut body)?;
+    if is_no_records(&body) {
+        return Ok(None);
+    }
+    Ok(Some((body, charset)))
+}
+
+/// Whether a history report is the portal's text for an empty result, e.g. `No Record Found`
+#[cfg(feature = "blocking")]
+fn is_no_records(body: &[u8]) -> bool {
+    let start = String::from_utf8_lossy(&body[..body.len().min(256)]).to_lowercase();
+    match start.lines().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) => line.starts_with("no record"),
+        None => true,
+    }
+}
+
+/// Checks whether the [AMFI](https://www.amfiindia.com) feed changed since `since` was stored
+///
+/// Issues a `HEAD` request, falling back to a `GET` of the first kilobyte if the portal
+/// rejects `HEAD`. The body is not downloaded.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// let (records, meta) = amfi::daily_nav_with_meta()?;
+/// // ... consume records, store meta ...
+/// # drop(records);
+/// let update = amfi::is_nav_updated(&meta)?;
+/// if update.status == amfi::UpdateStatus::Updated {
+///     println!("published at {:?}", update.meta.last_modified);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn is_nav_updated(since: &FetchMeta) -> Result<NavUpdate> {
+    is_nav_updated_at(BASE_URL, since)
+}
+
+/// Checks whether the feed at `url` changed since `since` was stored
+///
+/// See [`is_nav_updated`](fn.is_nav_updated.html).
+#[cfg(feature = "blocking")]
+pub fn is_nav_updated_at<T: AsRef<str>>(url: T, since: &FetchMeta) -> Result<NavUpdate> {
+    let client = reqwest::blocking::Client::new();
+    let url = url.as_ref();
+    let mut response = client.head(url).send()?;
+    if rejects_head(response.status()) {
+        response = client.get(url).header(header::RANGE, FIRST_KB).send()?;
+    }
+    update_since(url, response.status(), response.headers(), since)
+}
+
+/// Checks without blocking whether the [AMFI](https://www.amfiindia.com) feed changed since
+/// `since` was stored
+///
+/// See [`is_nav_updated`](fn.is_nav_updated.html).
+#[cfg(feature = "async")]
+pub async fn is_nav_updated_async(since: &FetchMeta) -> Result<NavUpdate> {
+    is_nav_updated_at_async(BASE_URL, since).await
+}
+
+/// Checks without blocking whether the feed at `url` changed since `since` was stored
+///
+/// See [`is_nav_updated`](fn.is_nav_updated.html).
+#[cfg(feature = "async")]
+pub async fn is_nav_updated_at_async<T: AsRef<str>>(
+    url: T,
+    since: &FetchMeta,
+) -> Result<NavUpdate> {
+    let client = reqwest::Client::new();
+    let url = url.as_ref();
+    let mut response = client.head(url).send().await?;
+    if rejects_head(response.status()) {
+        response = client
+            .get(url)
+            .header(header::RANGE, FIRST_KB)
+            .send()
+            .await?;
+    }
+    update_since(url, response.status(), response.headers(), since)
+}
+
+/// NAV date of the first record of the [AMFI](https://www.amfiindia.com) feed, downloading
+/// only its first kilobytes
+///
+/// See [`peek_feed_date_at`](fn.peek_feed_date_at.html).
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// let last_run = amfi::NavDate::from_ymd(2024, 6, 20);
+/// if amfi::peek_daily_feed_date()? > last_run {
+///     let records = amfi::daily_nav()?;
+///     // ... run the pipeline ...
+/// #   drop(records);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn peek_daily_feed_date() -> Result<Option<NavDate>> {
+    peek_feed_date_at(BASE_URL)
+}
+
+/// NAV date of the first record of the feed at `url`, downloading only its first kilobytes
+///
+/// Requests the first [`PEEK_LEN`] bytes with a `Range` header and reads the date off them as
+/// [`peek_feed_date`](fn.peek_feed_date.html) does, leaving out a last line cut short. If
+/// they hold no record, or the server ignores `Range` and sends the whole feed, the feed is
+/// read up to its first record and the connection dropped.
+#[cfg(feature = "blocking")]
+pub fn peek_feed_date_at<T: AsRef<str>>(url: T) -> Result<Option<NavDate>> {
+    let client = reqwest::blocking::Client::new();
+    let url = url.as_ref();
+    let range = format!("bytes=0-{}", PEEK_LEN - 1);
+    let response = client.get(url).header(header::RANGE, range).send()?;
+    let response = match response.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            let total = FetchMeta::from_headers(response.headers()).content_length;
+            let charset = charset(response.headers());
+            let mut body = Vec::new();
+            response.take(PEEK_LEN).read_to_end(&mut body)?;
+            let complete = total.is_some_and(|total| total <= body.len() as u64);
+            match first_date_in(body, complete, charset)? {
+                None if !complete => client.get(url).send()?,
+                date => return Ok(date),
+            }
+        }
+        // Range of an empty feed
+        StatusCode::RANGE_NOT_SATISFIABLE => client.get(url).send()?,
+        _ => response,
+    };
+    if !response.status().is_success() {
+        return Err(response_error(response, url));
+    }
+    let charset = charset(response.headers());
+    crate::peek::first_date(
+        NavRecordIterator::with_charset(response, charset).decode(DecodePolicy::Lossy),
+    )
+}
+
+/// NAV date of the first record in `body`, the start of a feed or all of it if `complete`
+///
+/// The last line of an incomplete body is left out, as it may be cut short.
+#[cfg(feature = "blocking")]
+fn first_date_in(mut body: Vec<u8>, complete: bool, charset: Charset) -> Result<Option<NavDate>> {
+    if !complete {
+        let end = body
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |at| at + 1);
+        body.truncate(end);
+    }
+    crate::peek::first_date(
+        NavRecordIterator::with_charset(Cursor::new(body), charset).decode(DecodePolicy::Lossy),
+    )
+}
+
+/// Record of scheme `code` in the [AMFI](https://www.amfiindia.com) feed, `None` if it has
+/// none
+///
+/// See [`nav_for_codes_at`](fn.nav_for_codes_at.html).
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// if let Some(record) = amfi::nav_for_code(119_551)? {
+///     println!("{} on {}: {}", record.name, record.date, record.nav);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn nav_for_code<C: Into<SchemeCode>>(code: C) -> Result<Option<NavRecord>> {
+    let code = code.into();
+    Ok(nav_for_codes(Some(code))?.remove(&code))
+}
+
+/// Records of schemes `codes` in the [AMFI](https://www.amfiindia.com) feed, by code
+///
+/// See [`nav_for_codes_at`](fn.nav_for_codes_at.html).
+#[cfg(feature = "blocking")]
+pub fn nav_for_codes<I, C>(codes: I) -> Result<BTreeMap<SchemeCode, NavRecord>>
+where
+    I: IntoIterator<Item = C>,
+    C: Into<SchemeCode>,
+{
+    nav_for_codes_at(BASE_URL, codes)
+}
+
+/// Records of schemes `codes` in the feed at `url`, by code; codes without a record are
+/// left out
+///
+/// The feed is parsed as it downloads, and the connection dropped once every code is found,
+/// so the rest of the feed is not downloaded. A code published twice gets its first record.
+/// Lines failing to parse are logged and passed over; other errors, e.g. unsuccessful
+/// statuses or content that is no feed, fail the lookup.
+///
+/// The feed is ordered by category and AMC rather than by code, so a code may be anywhere
+/// in it: looking up one downloads half of the feed on average, and looking up several
+/// stops at the last one found. Codes not in the feed download all of it.
+#[cfg(feature = "blocking")]
+pub fn nav_for_codes_at<T, I, C>(url: T, codes: I) -> Result<BTreeMap<SchemeCode, NavRecord>>
+where
+    T: AsRef<str>,
+    I: IntoIterator<Item = C>,
+    C: Into<SchemeCode>,
+{
+    let codes: BTreeSet<SchemeCode> = codes.into_iter().map(Into::into).collect();
+    if codes.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    crate::lookup::find_codes(get_records(url.as_ref(), None, true)?, codes)
+}
+
+/// Charset declared by the `Content-Type` of a response
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn charset(headers: &HeaderMap) -> Charset {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(Charset::Utf8, Charset::of_content_type)
+}
+
+/// Longest body kept in [`Error::HttpError`](../enum.Error.html#variant.HttpError), in bytes
+#[cfg(any(feature = "blocking", feature = "async"))]
+const SNIPPET_LEN: usize = 512;
+
+/// Error of an unsuccessful response from `url` starting with `body`
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn http_error(status: StatusCode, url: &str, body: Option<&[u8]>) -> Error {
+    Error::HttpError {
+        status,
+        url: url.to_string(),
+        body_snippet: body.and_then(snippet),
+    }
+}
+
+/// Error of an unsuccessful `response` from `url`, with the start of its body
+#[cfg(feature = "blocking")]
+pub(crate) fn response_error(response: Response, url: &str) -> Error {
+    let status = response.status();
+    let mut body = Vec::new();
+    let body = response
+        .take(SNIPPET_LEN as u64)
+        .read_to_end(&mut body)
+        .map(|_| body);
+    http_error(status, url, body.ok().as_deref())
+}
+
+/// Start of a response body as text, `None` if blank
+///
+/// At most [`SNIPPET_LEN`] bytes, without a character cut short at the end; invalid UTF-8 is
+/// replaced.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn snippet(body: &[u8]) -> Option<String> {
+    let mut body = &body[..body.len().min(SNIPPET_LEN)];
+    if let Err(error) = std::str::from_utf8(body) {
+        if error.error_len().is_none() {
+            body = &body[..error.valid_up_to()];
+        }
+    }
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+/// Bytes requested by [`peek_feed_date_at`](fn.peek_feed_date_at.html), enough for the
+/// headers and first records of a feed
+#[cfg(feature = "blocking")]
+pub const PEEK_LEN: u64 = 8192;
+
+/// `Range` of the `GET` used when `HEAD` is rejected
+#[cfg(any(feature = "blocking", feature = "async"))]
+const FIRST_KB: &str = "bytes=0-1023";
+
+/// Whether the server does not support `HEAD` requests
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn rejects_head(status: StatusCode) -> bool {
+    status == StatusCode::METHOD_NOT_ALLOWED || status == StatusCode::NOT_IMPLEMENTED
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn update_since(
+    url: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+    since: &FetchMeta,
+) -> Result<NavUpdate> {
+    if !status.is_success() {
+        return Err(http_error(status, url, None));
+    }
+    let meta = FetchMeta::from_headers(headers);
+    Ok(NavUpdate {
+        status: meta.status_since(since),
+        meta,
+    })
+}
+
+/// Serialization of times as HTTP dates, as in `Last-Modified` headers
+#[cfg(feature = "serde")]
+mod http_date {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::time::SystemTime;
+
+    pub(super) fn serialize<S: Serializer>(
+        time: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&httpdate::HttpDate::from(*time))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SystemTime, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        httpdate::parse_http_date(&text).map_err(de::Error::custom)
+    }
+
+    pub(super) mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use std::time::SystemTime;
+
+        pub(in super::super) fn serialize<S: Serializer>(
+            time: &Option<SystemTime>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match time {
+                Some(time) => {
+                    serializer.serialize_some(&httpdate::HttpDate::from(*time).to_string())
+                }
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub(in super::super) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<SystemTime>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|text| httpdate::parse_http_date(&text).map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+    use std::time::Duration;
+
+    fn headers(pairs: &[(HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn meta_from_headers() {
+        let meta = FetchMeta::from_headers(&headers(&[
+            (header::LAST_MODIFIED, "Fri, 21 Jun 2024 16:10:05 GMT"),
+            (header::ETAG, "\"5f-61b\""),
+            (header::CONTENT_LENGTH, "1024"),
+            (header::CONTENT_RANGE, "bytes 0-1023/2302599"),
+        ]));
+        assert_eq!(
+            meta.last_modified,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_718_986_205))
+        );
+        assert_eq!(meta.etag.as_deref(), Some("\"5f-61b\""));
+        assert_eq!(meta.content_length, Some(2_302_599));
+
+        let meta = FetchMeta::from_headers(&headers(&[(header::LAST_MODIFIED, "yesterday")]));
+        assert!(meta.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn meta_serializes_http_dates() {
+        let meta = FetchMeta::from_headers(&headers(&[
+            (header::LAST_MODIFIED, "Fri, 21 Jun 2024 16:10:05 GMT"),
+            (header::ETAG, "\"5f-61b\""),
+        ]));
+        let json = serde_json::to_value(&meta).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "last_modified": "Fri, 21 Jun 2024 16:10:05 GMT",
+                "etag": "\"5f-61b\"",
+                "content_length": null,
+            })
+        );
+        assert_eq!(serde_json::from_value::<FetchMeta>(json).unwrap(), meta);
+        let unset: FetchMeta =
+            serde_json::from_str(r#"{"last_modified":null,"etag":null,"content_length":null}"#)
+                .unwrap();
+        assert!(unset.is_empty());
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    #[test]
+    fn response_meta() {
+        let headers = headers(&[
+            (header::CONTENT_TYPE, "text/plain; charset=windows-1252"),
+            (header::LAST_MODIFIED, "Fri, 21 Jun 2024 16:10:05 GMT"),
+            (header::ETAG, "\"5f-61b\""),
+            (header::CONTENT_LENGTH, "2302599"),
+        ]);
+        let before = SystemTime::now();
+        let response = ResponseMeta::new(
+            StatusCode::OK,
+            "https://portal.amfiindia.com/spages/NAVAll.txt",
+            &headers,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.content_type.as_deref(),
+            Some("text/plain; charset=windows-1252")
+        );
+        assert!(response.fetched_at >= before);
+        // Validators compose with update checks
+        let meta = FetchMeta::from(&response);
+        assert_eq!(meta, FetchMeta::from_headers(&headers));
+        assert_eq!(
+            meta.status_since(&response.fetch_meta()),
+            UpdateStatus::Unchanged
+        );
+    }
+
+    #[test]
+    fn status_since() {
+        let stored = FetchMeta::from_headers(&headers(&[
+            (header::LAST_MODIFIED, "Fri, 21 Jun 2024 16:10:05 GMT"),
+            (header::ETAG, "\"a\""),
+            (header::CONTENT_LENGTH, "100"),
+        ]));
+        assert_eq!(stored.status_since(&stored), UpdateStatus::Unchanged);
+
+        let etag = FetchMeta {
+            etag: Some("\"b\"".to_string()),
+            ..stored.clone()
+        };
+        assert_eq!(etag.status_since(&stored), UpdateStatus::Updated);
+
+        let modified = FetchMeta {
+            etag: None,
+            last_modified: FetchMeta::from_headers(&headers(&[(
+                header::LAST_MODIFIED,
+                "Mon, 24 Jun 2024 16:00:00 GMT",
+            )]))
+            .last_modified,
+            ..stored.clone()
+        };
+        assert_eq!(modified.status_since(&stored), UpdateStatus::Updated);
+
+        let size_only = FetchMeta {
+            content_length: Some(100),
+            ..FetchMeta::default()
+        };
+        assert_eq!(size_only.status_since(&stored), UpdateStatus::Unchanged);
+        assert_eq!(
+            FetchMeta::default().status_since(&stored),
+            UpdateStatus::Unknown
+        );
+        assert_eq!(
+            stored.status_since(&FetchMeta::default()),
+            UpdateStatus::Unknown
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn requests_wait_for_the_limiter() {
+        use crate::Clock;
+        use std::sync::{Arc, Mutex};
+        use std::time::Instant;
+
+        /// Clock standing still, recording sleeps
+        struct Frozen(Instant, Mutex<Vec<Duration>>);
+
+        impl Clock for Frozen {
+            fn now(&self) -> Instant {
+                self.0
+            }
+
+            fn sleep(&self, duration: Duration) {
+                self.1.lock().unwrap().push(duration);
+            }
+        }
+
+        let clock = Arc::new(Frozen(Instant::now(), Mutex::new(Vec::new())));
+        let interval = Duration::from_secs(60);
+        let options = FetchOptions::new()
+            .url("not a url")
+            .rate_limiter(RateLimiter::new(interval).clock(clock.clone()));
+        // Rejected before connecting, but through the limiter
+        assert!(daily_nav_with(&options).is_err());
+        assert!(daily_nav_with(&options).is_err());
+        assert_eq!(*clock.1.lock().unwrap(), [interval]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn history_chunks_download_as_records_run_out() {
+        use crate::Clock;
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::sync::{Arc, Mutex};
+        use std::thread;
+
+        /// Clock standing still, counting sleeps
+        struct Frozen(Instant, Mutex<usize>);
+
+        impl Clock for Frozen {
+            fn now(&self) -> Instant {
+                self.0
+            }
+
+            fn sleep(&self, _: Duration) {
+                *self.1.lock().unwrap() += 1;
+            }
+        }
+
+        const FEED: &str = include_str!("../fixtures/NAVOpen.txt");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}/history", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                let start = request.split("frmdt=").nth(1).unwrap()[..11].to_string();
+                let (status, body) = match start.as_str() {
+                    "01-Jan-2024" => ("200 OK", "No Record Found\r\n"),
+                    "29-Jun-2024" => ("503 Service Unavailable", "Try again"),
+                    _ => ("200 OK", FEED),
+                };
+                log.lock().unwrap().push(start);
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        let clock = Arc::new(Frozen(Instant::now(), Mutex::new(0)));
+        let limiter = RateLimiter::new(Duration::from_secs(60)).clock(clock.clone());
+        let date = |month, day| NavDate::from_ymd(2024, month, day).unwrap();
+        let mut records =
+            history_records(&base, AmcId(53), date(1, 1), date(12, 31), Some(limiter)).unwrap();
+        let requested = || requests.lock().unwrap().clone();
+        // Up to the first chunk with records
+        assert_eq!(requested(), ["01-Jan-2024", "31-Mar-2024"]);
+        assert_eq!(records.by_ref().take(30).filter(Result::is_ok).count(), 30);
+        assert_eq!(requested().len(), 2);
+        // A failed chunk doesn't end the others
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().unwrap().is_ok());
+        assert_eq!(
+            requested(),
+            ["01-Jan-2024", "31-Mar-2024", "29-Jun-2024", "27-Sep-2024"]
+        );
+        assert_eq!(records.filter(Result::is_ok).count(), 29 + 30);
+        assert_eq!(requested().len(), 5);
+        assert_eq!(*clock.1.lock().unwrap(), 4);
+
+        let empty = history_records(&base, AmcId(53), date(1, 1), date(1, 2), None);
+        assert!(matches!(empty, Err(Error::NoRecords { .. })));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn history_chunks_and_urls() {
+        let date = |month, day| NavDate::from_ymd(2024, month, day).unwrap();
+        assert_eq!(
+            history_chunks(date(1, 1), date(6, 30)),
+            [
+                (date(1, 1), date(3, 30)),
+                (date(3, 31), date(6, 28)),
+                (date(6, 29), date(6, 30)),
+            ]
+        );
+        assert_eq!(
+            history_chunks(date(1, 1), date(3, 30)),
+            [(date(1, 1), date(3, 30))]
+        );
+        assert_eq!(
+            history_chunks(date(6, 21), date(6, 21)),
+            [(date(6, 21), date(6, 21))]
+        );
+        assert!(history_chunks(date(6, 21), date(6, 20)).is_empty());
+
+        assert_eq!(
+            history_url(HISTORY_URL, AmcId(53), date(1, 1), date(3, 30)),
+            "https://portal.amfiindia.com/DownloadNAVHistoryReport_Po.aspx?mf=53&frmdt=01-Jan-2024&todt=30-Mar-2024"
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn amc_options_of_page() {
+        let html = r#"<select id="NavDownMFName">
+            <option value="">Select Mutual Fund</option>
+            <option value="53">Axis Mutual Fund</option>
+            <option value='4' selected>Baroda BNP Paribas
+                Mutual Fund</option>
+            <option value="56">L&amp;T Mutual Fund</option>
+            <option value="all">All</option>
+        </select>"#;
+        assert_eq!(
+            amc_options(html),
+            [
+                (AmcId(53), "Axis Mutual Fund".to_string()),
+                (AmcId(4), "Baroda BNP Paribas Mutual Fund".to_string()),
+                (AmcId(56), "L&T Mutual Fund".to_string()),
+            ]
+        );
+        assert!(amc_options("<html></html>").is_empty());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn no_records_sentinel() {
+        assert!(is_no_records(b"\r\nNo Record Found\r\n"));
+        assert!(is_no_records(b"no records found for the selected criteria"));
+        assert!(is_no_records(b" \n"));
+        assert!(!is_no_records(
+            b"Scheme Code;Scheme Name;ISIN Div Payout/ISIN Growth;ISIN Div Reinvestment;Net Asset Value;Repurchase Price;Sale Price;Date\r\n"
+        ));
+
+        let error = Error::NoRecords {
+            amc: AmcId(53),
+            from: NavDate::from_ymd(2024, 1, 1).unwrap(),
+            to: NavDate::from_ymd(2024, 6, 30).unwrap(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "No records: the portal has no NAV history for AMC 53 from 01-Jan-2024 to 30-Jun-2024"
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn http_error_snippet() {
+        let status = StatusCode::from_u16(403).unwrap();
+        let url = "https://mirror.example/NAVAll.txt";
+        let body = b"<html>\r\n  <h1>Request blocked</h1>\n by WAF</html>\n";
+        let error = http_error(status, url, Some(body));
+        let text = error.to_string();
+        assert!(text.starts_with("Http error: 403"));
+        assert!(text.contains(url));
+        assert!(text.ends_with(": <html> <h1>Request blocked</h1> by WAF</html>"));
+        assert!(!text.contains('\n'));
+
+        assert_eq!(snippet(b" \r\n"), None);
+        assert_eq!(snippet(b"bad \xff byte").unwrap(), "bad \u{FFFD} byte");
+        // A character cut at the limit is dropped rather than replaced
+        let mut long = vec![b'a'; SNIPPET_LEN - 1];
+        long.extend_from_slice("\u{2013}".as_bytes());
+        assert_eq!(snippet(&long).unwrap(), "a".repeat(SNIPPET_LEN - 1));
+
+        match http_error(status, url, None) {
+            Error::HttpError { body_snippet, .. } => assert_eq!(body_snippet, None),
+            error => panic!("unexpected {}", error),
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn content_checked_before_parsing() {
+        let url = "https://mirror.example/NAVAll.txt";
+        let check = |body: &[u8], sniff: bool| {
+            NavRecordIterator::with_charset(body, Charset::Utf8)
+                .sniff_content(sniff)
+                .check_content(url)
+        };
+        match check(b"\r\n<!DOCTYPE html><html><body>Captive portal", true) {
+            Err(Error::UnexpectedContent {
+                first_line,
+                url: Some(from),
+            }) => {
+                assert!(first_line.starts_with("<!DOCTYPE html>"));
+                assert_eq!(from, url);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(check(b"<html>", false).is_ok());
+        assert!(check(b"Scheme Code;ISIN Div Payout/ ISIN Growth\r\n", true).is_ok());
+        // An incomplete line is left to parsing
+        assert!(check(b"Axis Mut", true).is_ok());
+        assert!(check(b"", true).is_ok());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn peeks_start_of_feed() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let fixture = std::fs::read(path).unwrap();
+        let june_21 = NavDate::from_ymd(2024, 6, 21);
+        let peek = |body: &[u8], complete| first_date_in(body.to_vec(), complete, Charset::Utf8);
+        assert_eq!(peek(&fixture, true).unwrap(), june_21);
+
+        // Cut in the date of the first record
+        let first = fixture.windows(4).position(|w| w == b"2024").unwrap() + 2;
+        assert_eq!(peek(&fixture[..first], false).unwrap(), None);
+        let second = first + 1 + fixture[first..].iter().position(|&b| b == b'\n').unwrap();
+        assert_eq!(peek(&fixture[..second + 10], false).unwrap(), june_21);
+        // A whole feed without a newline at the end
+        let trimmed = fixture.strip_suffix(b"\n").unwrap_or(&fixture);
+        assert_eq!(peek(trimmed, true).unwrap(), june_21);
+        assert_eq!(peek(b"", true).unwrap(), None);
+    }
+}
@@ -0,0 +1,69 @@
+//! Fields of record lines, to tell where a line failed to parse
+
+use std::fmt;
+
+/// Field of a record line, see [`Error::ParseFailure`](enum.Error.html#variant.ParseFailure)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// Scheme code, the first field
+    Code,
+    /// ISIN of the payout or growth option
+    Isin,
+    /// ISIN of the reinvestment option
+    IsinDr,
+    /// Scheme name
+    Name,
+    /// Net asset value
+    Nav,
+    /// Repurchase price of the legacy layout
+    RepurchasePrice,
+    /// Sale price of the legacy layout
+    SalePrice,
+    /// NAV date, the last field
+    Date,
+}
+
+impl Field {
+    /// Name of the matching `NavRecord` field, e.g. `isin_dr`
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Code => "code",
+            Field::Isin => "isin",
+            Field::IsinDr => "isin_dr",
+            Field::Name => "name",
+            Field::Nav => "nav",
+            Field::RepurchasePrice => "repurchase_price",
+            Field::SalePrice => "sale_price",
+            Field::Date => "date",
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Longest line echoed with a caret under the failure point, in chars
+const SNIPPET_CHARS: usize = 160;
+
+/// Writes `line` on its own line with a `^` under byte `at` below it, unless too long
+pub(crate) fn write_caret(f: &mut fmt::Formatter, line: &str, at: usize) -> fmt::Result {
+    let before = match line.get(..at) {
+        Some(before) if line.chars().count() <= SNIPPET_CHARS => before,
+        _ => return Ok(()),
+    };
+    // Tabs as spaces, so the caret lines up however tabs are shown
+    let shown: String = line
+        .chars()
+        .map(|ch| if ch == '\t' { ' ' } else { ch })
+        .collect();
+    write!(
+        f,
+        "\n    {}\n    {:width$}^",
+        shown,
+        "",
+        width = before.chars().count()
+    )
+}
@@ -0,0 +1,298 @@
+//! Records of several feed files read one after another
+
+use crate::{
+    nav_from_file, Error, NavRecord, NavRecordIterator, RecordSource, Result, SourceId,
+    SourceRegistry, Sourced,
+};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Handling of files that can't be opened or read by [`nav_from_files`](fn.nav_from_files.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnreadableFilePolicy {
+    /// Yield one error for the file and go on with the next
+    #[default]
+    Continue,
+    /// Yield one error for the file and end the iteration
+    Abort,
+}
+
+/// Iterator over the records of several feed files, see [`nav_from_files`](fn.nav_from_files.html)
+pub struct NavFiles<I> {
+    paths: I,
+    current: Option<(PathBuf, NavRecordIterator<File>)>,
+    unreadable: UnreadableFilePolicy,
+    done: bool,
+}
+
+/// Parses the feed files at `paths` in order, as a single iterator
+///
+/// Each file is parsed on its own, as with [`nav_from_file`](fn.nav_from_file.html): no
+/// section or column header carries over from one file to the next, and `source_line`s are
+/// lines of the record's file. Errors are wrapped in
+/// [`Error::InFile`](enum.Error.html#variant.InFile) with the path of their file.
+///
+/// A file that can't be opened or read yields a single error, then its records so far are
+/// all there is of it; see [`on_unreadable`](struct.NavFiles.html#method.on_unreadable) to
+/// end the iteration there instead.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use std::path::PathBuf;
+///
+/// let paths = vec![
+///     PathBuf::from("fixtures/NAVOpen.txt"),
+///     PathBuf::from("fixtures/NAVMissing.txt"),
+///     PathBuf::from("fixtures/NAVLegacy.txt"),
+/// ];
+/// for record in amfi::nav_from_files(paths) {
+///     match record {
+///         Ok(record) => println!("{} {}", record.code, record.nav),
+///         Err(amfi::Error::InFile { path, error }) => eprintln!("{}: {}", path.display(), error),
+///         Err(error) => return Err(error),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn nav_from_files<I>(paths: I) -> NavFiles<I::IntoIter>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    NavFiles {
+        paths: paths.into_iter(),
+        current: None,
+        unreadable: UnreadableFilePolicy::default(),
+        done: false,
+    }
+}
+
+impl<I> NavFiles<I> {
+    /// Sets what follows a file that can't be opened or read,
+    /// [`Continue`](enum.UnreadableFilePolicy.html#variant.Continue) by default
+    pub fn on_unreadable(mut self, policy: UnreadableFilePolicy) -> Self {
+        self.unreadable = policy;
+        self
+    }
+
+    /// Path of the file being read, `None` before the first and after the last
+    pub fn path(&self) -> Option<&Path> {
+        self.current.as_ref().map(|(path, _)| path.as_path())
+    }
+
+    /// Pairs each record with the id of its file, registered in
+    /// [`registry`](struct.SourcedFiles.html#method.registry)
+    ///
+    /// ```rust
+    /// use std::path::PathBuf;
+    ///
+    /// let paths = vec![
+    ///     PathBuf::from("fixtures/NAVOpen.txt"),
+    ///     PathBuf::from("fixtures/NAVLegacy.txt"),
+    /// ];
+    /// let mut files = amfi::nav_from_files(paths).sourced();
+    /// let first = files.next().unwrap().unwrap();
+    /// let source = files.registry().get(first.source_id).unwrap();
+    /// assert_eq!(source.to_string(), "fixtures/NAVOpen.txt");
+    /// ```
+    pub fn sourced(self) -> SourcedFiles<I> {
+        SourcedFiles {
+            files: self,
+            registry: SourceRegistry::new(),
+            current: None,
+        }
+    }
+
+    /// Error of the file at `path`, ending it or all files if unreadable
+    fn in_file(&mut self, path: PathBuf, error: Error) -> Error {
+        if let Error::IoError(_) = error {
+            self.current = None;
+            self.done = self.unreadable == UnreadableFilePolicy::Abort;
+        }
+        Error::InFile {
+            path,
+            error: Box::new(error),
+        }
+    }
+}
+
+impl<I: Iterator<Item = PathBuf>> Iterator for NavFiles<I> {
+    type Item = Result<NavRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match &mut self.current {
+                Some((path, records)) => match records.next() {
+                    Some(Ok(record)) => return Some(Ok(record)),
+                    Some(Err(error)) => {
+                        let path = path.clone();
+                        return Some(Err(self.in_file(path, error)));
+                    }
+                    None => self.current = None,
+                },
+                None => {
+                    let path = self.paths.next()?;
+                    match nav_from_file(&path) {
+                        Ok(records) => self.current = Some((path, records)),
+                        Err(error) => return Some(Err(self.in_file(path, error))),
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Records of several feed files with the id of their file, see
+/// [`NavFiles::sourced`](struct.NavFiles.html#method.sourced)
+pub struct SourcedFiles<I> {
+    files: NavFiles<I>,
+    registry: SourceRegistry,
+    current: Option<SourceId>,
+}
+
+impl<I> SourcedFiles<I> {
+    /// Files records were read from so far
+    pub fn registry(&self) -> &SourceRegistry {
+        &self.registry
+    }
+
+    /// Registry of the files read, once done with them
+    pub fn into_registry(self) -> SourceRegistry {
+        self.registry
+    }
+}
+
+impl<I: Iterator<Item = PathBuf>> Iterator for SourcedFiles<I> {
+    type Item = Result<Sourced<NavRecord>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.files.next()? {
+            Ok(record) => record,
+            Err(error) => return Some(Err(error)),
+        };
+        let path = self.files.path().expect("records are read from a file");
+        let registry = &self.registry;
+        let known = self.current.filter(
+            |&id| matches!(registry.get(id), Some(RecordSource::File(known)) if known == path),
+        );
+        let source_id = match known {
+            Some(id) => id,
+            None => self.registry.register_file(path),
+        };
+        self.current = Some(source_id);
+        Some(Ok(Sourced { source_id, record }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_path, fixture_records};
+
+    #[test]
+    fn reads_files_in_order() {
+        let mut files = nav_from_files(vec![
+            fixture_path("NAVOpen.txt"),
+            fixture_path("NAVLegacy.txt"),
+        ]);
+        assert_eq!(files.path(), None);
+        let first = files.next().unwrap().unwrap();
+        assert_eq!(files.path(), Some(fixture_path("NAVOpen.txt").as_path()));
+        let mut read = vec![first];
+        read.extend(files.by_ref().map(Result::unwrap));
+        assert_eq!(files.path(), None);
+
+        let mut expected = fixture_records("NAVOpen.txt");
+        let legacy = fixture_records("NAVLegacy.txt");
+        assert_eq!(read[expected.len()].source_line, legacy[0].source_line);
+        expected.extend(legacy);
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn sections_reset_between_files() {
+        // A file whose records come before any AMC header
+        let dir = std::env::temp_dir().join(format!("amfi-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let stray = dir.join("stray.txt");
+        std::fs::write(&stray, "120466;-;-;Stray Fund;21.98;21-Jun-2024\n").unwrap();
+
+        let results: Vec<_> =
+            nav_from_files(vec![fixture_path("NAVOpen.txt"), stray.clone()]).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        match results.last() {
+            Some(Err(Error::InFile { path, error })) => {
+                assert_eq!(path, &stray);
+                assert!(matches!(**error, Error::BuilderError { line: Some(1), .. }));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(results.len(), fixture_records("NAVOpen.txt").len() + 1);
+    }
+
+    #[test]
+    fn unreadable_files() {
+        let paths = || {
+            vec![
+                fixture_path("NAVOpen.txt"),
+                fixture_path("NAVMissing.txt"),
+                // Opened but not read
+                fixture_path(""),
+                fixture_path("NAVLegacy.txt"),
+            ]
+        };
+        let open = fixture_records("NAVOpen.txt").len();
+        let legacy = fixture_records("NAVLegacy.txt").len();
+
+        let results: Vec<_> = nav_from_files(paths()).collect();
+        assert_eq!(results.len(), open + 2 + legacy);
+        let errors: Vec<&Error> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+        match errors[..] {
+            [Error::InFile { path, error }, Error::InFile { error: read, .. }] => {
+                assert_eq!(path, &fixture_path("NAVMissing.txt"));
+                assert!(matches!(**error, Error::IoError(_)));
+                assert!(matches!(**read, Error::IoError(_)));
+            }
+            ref other => panic!("{:?}", other),
+        }
+        let error = results[open].as_ref().unwrap_err().to_string();
+        assert!(error.starts_with(&fixture_path("NAVMissing.txt").display().to_string()));
+
+        let mut files = nav_from_files(paths()).on_unreadable(UnreadableFilePolicy::Abort);
+        assert_eq!(
+            files.by_ref().take(open).filter(Result::is_ok).count(),
+            open
+        );
+        assert!(files.next().unwrap().is_err());
+        assert!(files.next().is_none());
+    }
+
+    #[test]
+    fn records_know_their_file() {
+        let mut files = nav_from_files(vec![
+            fixture_path("NAVOpen.txt"),
+            fixture_path("NAVMissing.txt"),
+            fixture_path("NAVLegacy.txt"),
+            fixture_path("NAVOpen.txt"),
+        ])
+        .sourced();
+        let read: Vec<_> = files.by_ref().filter_map(Result::ok).collect();
+        let open = fixture_records("NAVOpen.txt").len();
+        let legacy = fixture_records("NAVLegacy.txt").len();
+        assert_eq!(read.len(), 2 * open + legacy);
+        let ids: Vec<usize> = read.iter().map(|r| r.source_id.index()).collect();
+        assert_eq!(ids[open - 1..open + 1], [0, 1]);
+        assert_eq!(ids[open + legacy], 0);
+
+        let registry = files.into_registry();
+        let paths: Vec<_> = registry.iter().map(|(_, source)| source.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                RecordSource::File(fixture_path("NAVOpen.txt")),
+                RecordSource::File(fixture_path("NAVLegacy.txt")),
+            ]
+        );
+    }
+}
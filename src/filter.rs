@@ -0,0 +1,68 @@
+//! Section and record filters applied by `NavRecordIterator`
+
+use crate::{FundMaturity, FundPlan, NavRecordRef, VehicleKind};
+
+/// Case-insensitive text matcher
+#[derive(Debug, Clone)]
+pub(crate) enum TextFilter {
+    /// Text contains the (lowercase) pattern
+    Contains(String),
+    /// Trimmed text equals the (lowercase, trimmed) pattern
+    Exact(String),
+}
+
+impl TextFilter {
+    pub(crate) fn contains(pattern: &str) -> Self {
+        TextFilter::Contains(pattern.to_lowercase())
+    }
+
+    pub(crate) fn exact(pattern: &str) -> Self {
+        TextFilter::Exact(pattern.trim().to_lowercase())
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        match *self {
+            TextFilter::Contains(ref pattern) => text.contains(pattern.as_str()),
+            TextFilter::Exact(ref pattern) => text.trim() == pattern,
+        }
+    }
+}
+
+/// Filters of a `NavRecordIterator`, all of which must match
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Filters {
+    pub(crate) amc: Vec<TextFilter>,
+    pub(crate) category: Vec<TextFilter>,
+    pub(crate) maturity: Option<FundMaturity>,
+    pub(crate) plan: Option<FundPlan>,
+    pub(crate) vehicle: Option<VehicleKind>,
+    pub(crate) excluded_vehicles: Vec<VehicleKind>,
+}
+
+impl Filters {
+    /// Whether records of the section can match, decided before they are parsed
+    pub(crate) fn section_matches(
+        &self,
+        amc: &str,
+        category: &str,
+        maturity: Option<FundMaturity>,
+    ) -> bool {
+        self.amc.iter().all(|filter| filter.matches(amc))
+            && self.category.iter().all(|filter| filter.matches(category))
+            && (self.maturity.is_none() || self.maturity == maturity)
+    }
+
+    /// Whether a parsed record of a matching section matches
+    pub(crate) fn record_matches(&self, record: &NavRecordRef<'_>) -> bool {
+        if self.plan.is_some() && self.plan != Some(record.plan) {
+            return false;
+        }
+        if self.vehicle.is_none() && self.excluded_vehicles.is_empty() {
+            return true;
+        }
+        let vehicle = record.vehicle();
+        (self.vehicle.is_none() || self.vehicle == Some(vehicle))
+            && !self.excluded_vehicles.contains(&vehicle)
+    }
+}
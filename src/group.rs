@@ -0,0 +1,318 @@
+//! Grouping records
+
+use crate::{AmcAliases, Error, NavRecord, Result};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+/// Records bucketed by a string key, ordered by key
+pub type Groups = BTreeMap<String, Vec<NavRecord>>;
+
+/// Buckets parse results by AMC
+///
+/// AMCs are ordered by name and records keep their input order within an AMC. Parse errors
+/// are collected in input order and returned alongside. A key is allocated once per AMC, not
+/// per record.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let (amcs, errors) = amfi::group_by_amc(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+/// assert!(errors.is_empty());
+/// for (amc, records) in &amcs {
+///     println!("{}: {} schemes", amc, records.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn group_by_amc<I>(results: I) -> (Groups, Vec<Error>)
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut groups = Groups::new();
+    let errors = collect(results, |record| {
+        insert(&mut groups, record, |record| Cow::Borrowed(&record.amc));
+    });
+    (groups, errors)
+}
+
+/// Buckets parse results by AMC, keyed by [`AmcAliases::key`](struct.AmcAliases.html#method.key)
+///
+/// Records of a fund house under its former and current names share a bucket, e.g. those of
+/// feeds from before and after a rename. Records keep their `amc` as published. Otherwise
+/// behaves like [`group_by_amc`](fn.group_by_amc.html).
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let aliases = amfi::AmcAliases::new().suffix(amfi::AmcSuffixPolicy::Strip);
+/// let (amcs, _) = amfi::group_by_amc_with(amfi::nav_from_file("fixtures/NAVOpen.txt")?, &aliases);
+/// assert!(amcs["Axis"].iter().all(|record| record.amc == "Axis Mutual Fund"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn group_by_amc_with<I>(results: I, aliases: &AmcAliases) -> (Groups, Vec<Error>)
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut groups = Groups::new();
+    let mut keys = AmcKeys::new(aliases.clone());
+    let errors = collect(results, |record| {
+        let key = keys.of(&record.amc);
+        match groups.get_mut(key) {
+            Some(group) => group.push(record),
+            None => {
+                groups.insert(key.to_string(), vec![record]);
+            }
+        }
+    });
+    (groups, errors)
+}
+
+/// Buckets parse results by category
+///
+/// Keys are the `category` strings trimmed with inner whitespace collapsed, as the feed is
+/// not consistent about spacing: `"Large Cap Fund "` and `"Large Cap Fund"` share a bucket.
+/// Otherwise behaves like [`group_by_amc`](fn.group_by_amc.html).
+pub fn group_by_category<I>(results: I) -> (Groups, Vec<Error>)
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut groups = Groups::new();
+    let errors = collect(results, |record| {
+        insert(&mut groups, record, category_key);
+    });
+    (groups, errors)
+}
+
+/// Buckets parse results by AMC, then category within each AMC
+///
+/// Keys are formed as in [`group_by_amc`](fn.group_by_amc.html) and
+/// [`group_by_category`](fn.group_by_category.html).
+pub fn group_by_amc_and_category<I>(results: I) -> (BTreeMap<String, Groups>, Vec<Error>)
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut groups: BTreeMap<String, Groups> = BTreeMap::new();
+    let errors = collect(results, |record| {
+        if !groups.contains_key(&record.amc) {
+            groups.insert(record.amc.clone(), Groups::new());
+        }
+        if let Some(categories) = groups.get_mut(&record.amc) {
+            insert(categories, record, category_key);
+        }
+    });
+    (groups, errors)
+}
+
+/// Buckets parse results by AMC, keyed as in [`group_by_amc_with`](fn.group_by_amc_with.html),
+/// then category within each AMC
+pub fn group_by_amc_and_category_with<I>(
+    results: I,
+    aliases: &AmcAliases,
+) -> (BTreeMap<String, Groups>, Vec<Error>)
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut groups: BTreeMap<String, Groups> = BTreeMap::new();
+    let mut keys = AmcKeys::new(aliases.clone());
+    let errors = collect(results, |record| {
+        let key = keys.of(&record.amc);
+        if !groups.contains_key(key) {
+            groups.insert(key.to_string(), Groups::new());
+        }
+        if let Some(categories) = groups.get_mut(key) {
+            insert(categories, record, category_key);
+        }
+    });
+    (groups, errors)
+}
+
+/// Keys of the AMC headers met, each formed once
+#[derive(Debug, Clone)]
+pub(crate) struct AmcKeys {
+    aliases: AmcAliases,
+    keys: HashMap<String, String>,
+}
+
+impl AmcKeys {
+    pub(crate) fn new(aliases: AmcAliases) -> Self {
+        AmcKeys {
+            aliases,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Key of the AMC header `amc`
+    pub(crate) fn of(&mut self, amc: &str) -> &str {
+        if !self.keys.contains_key(amc) {
+            let key = self.aliases.key(amc).key;
+            self.keys.insert(amc.to_string(), key);
+        }
+        &self.keys[amc]
+    }
+
+    /// Key of the AMC header `amc`, formed again unless met before
+    pub(crate) fn get(&self, amc: &str) -> String {
+        match self.keys.get(amc) {
+            Some(key) => key.clone(),
+            None => self.aliases.key(amc).key,
+        }
+    }
+}
+
+/// Feeds records to `f`, returning the errors
+fn collect<I, F>(results: I, mut f: F) -> Vec<Error>
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+    F: FnMut(NavRecord),
+{
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(record) => f(record),
+            Err(error) => errors.push(error),
+        }
+    }
+    errors
+}
+
+/// Appends `record` to its group, allocating the key only for a new group
+fn insert<K>(groups: &mut Groups, record: NavRecord, key: K)
+where
+    K: Fn(&NavRecord) -> Cow<'_, str>,
+{
+    let key = key(&record);
+    match groups.get_mut(key.as_ref()) {
+        Some(group) => group.push(record),
+        None => {
+            let key = key.into_owned();
+            groups.insert(key, vec![record]);
+        }
+    }
+}
+
+fn category_key(record: &NavRecord) -> Cow<'_, str> {
+    collapse_whitespace(&record.category)
+}
+
+/// Trimmed text with inner whitespace collapsed, borrowed when already clean
+pub(crate) fn collapse_whitespace(text: &str) -> Cow<'_, str> {
+    let collapsed = if text.is_ascii() {
+        // Spaces only, one between words
+        let mut prev = b' ';
+        text.bytes().all(|byte| {
+            let single = byte != b' ' || prev != b' ';
+            prev = byte;
+            single && !(b'\t'..=b'\r').contains(&byte)
+        }) && prev != b' '
+    } else {
+        text.split_whitespace().eq(text.split(' '))
+    };
+    if collapsed {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+    use crate::NavRecordIterator;
+
+    #[test]
+    fn fixture_groups() {
+        let (groups, errors) = group_by_amc(fixture_records("NAVOpen.txt").into_iter().map(Ok));
+        assert!(errors.is_empty());
+        assert_eq!(groups.values().map(Vec::len).sum::<usize>(), 30);
+
+        let amcs: Vec<&str> = groups.keys().map(String::as_str).collect();
+        let mut sorted = amcs.clone();
+        sorted.sort();
+        assert_eq!(amcs, sorted);
+
+        let axis: Vec<&str> = groups["Axis Mutual Fund"]
+            .iter()
+            .map(|record| record.category.as_str())
+            .collect();
+        assert_eq!(axis.first(), Some(&"Large Cap Fund"));
+        assert!(groups["Axis Mutual Fund"]
+            .iter()
+            .all(|record| record.amc == "Axis Mutual Fund"));
+    }
+
+    #[test]
+    fn errors_are_returned() {
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n";
+        let (groups, errors) = group_by_amc(NavRecordIterator::new(feed.as_bytes()));
+        assert_eq!(groups["Axis Mutual Fund"].len(), 1);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn fixture_categories() {
+        let (groups, errors) =
+            group_by_category(fixture_records("NAVOpen.txt").into_iter().map(Ok));
+        assert!(errors.is_empty());
+        let large_cap: Vec<&str> = groups["Large Cap Fund"]
+            .iter()
+            .map(|record| record.amc.as_str())
+            .collect();
+        assert!(large_cap.contains(&"Axis Mutual Fund"));
+        assert!(large_cap.contains(&"ICICI Prudential Mutual Fund"));
+
+        let (nested, _) =
+            group_by_amc_and_category(fixture_records("NAVOpen.txt").into_iter().map(Ok));
+        assert_eq!(nested["Axis Mutual Fund"].len(), 3);
+        assert_eq!(
+            nested
+                .values()
+                .flat_map(|c| c.values())
+                .map(Vec::len)
+                .sum::<usize>(),
+            30
+        );
+    }
+
+    #[test]
+    fn renamed_amcs_share_a_group() {
+        let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Reliance Mutual Fund\n\
+                    118632;INF204K01XZ3;-;Reliance Large Cap Fund - Direct Plan - Growth;35.12;21-Jun-2019\n\
+                    Nippon India Mutual Fund\n\
+                    118632;INF204K01XZ3;-;Nippon India Large Cap Fund - Direct Plan - Growth;80.21;21-Jun-2024\n";
+        let aliases = AmcAliases::new();
+        let (groups, errors) = group_by_amc_with(NavRecordIterator::new(feed.as_bytes()), &aliases);
+        assert!(errors.is_empty());
+        assert_eq!(groups.len(), 1);
+        let amcs: Vec<&str> = groups["Nippon India Mutual Fund"]
+            .iter()
+            .map(|record| record.amc.as_str())
+            .collect();
+        assert_eq!(amcs, ["Reliance Mutual Fund", "Nippon India Mutual Fund"]);
+
+        let (nested, _) =
+            group_by_amc_and_category_with(NavRecordIterator::new(feed.as_bytes()), &aliases);
+        assert_eq!(
+            nested["Nippon India Mutual Fund"]["Large Cap Fund"].len(),
+            2
+        );
+        let (groups, _) = group_by_amc(NavRecordIterator::new(feed.as_bytes()));
+        assert_eq!(groups.len(), 2);
+    }
+
+    #[test]
+    fn category_spacing_is_normalized() {
+        let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    Open Ended Schemes(Equity Scheme - Large  Cap Fund )\n\
+                    ICICI Prudential Mutual Fund\n\
+                    120586;INF109K01AF8;-;ICICI Prudential Bluechip Fund - Direct Plan - Growth;110.25;21-Jun-2024\n";
+        let (groups, errors) = group_by_category(NavRecordIterator::new(feed.as_bytes()));
+        assert!(errors.is_empty());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups["Large Cap Fund"].len(), 2);
+    }
+}
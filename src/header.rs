@@ -0,0 +1,255 @@
+//! Column header of a feed
+
+use crate::{trim_trailing_separators, Error, Layout};
+
+/// Column named by a feed header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Column {
+    Code,
+    Isin,
+    IsinDr,
+    Name,
+    Nav,
+    RepurchasePrice,
+    SalePrice,
+    Date,
+}
+
+/// Columns of the modern layout
+const MODERN: &[Column] = &[
+    Column::Code,
+    Column::Isin,
+    Column::IsinDr,
+    Column::Name,
+    Column::Nav,
+    Column::Date,
+];
+
+/// Columns of the legacy layout
+const LEGACY: &[Column] = &[
+    Column::Code,
+    Column::Isin,
+    Column::IsinDr,
+    Column::Name,
+    Column::Nav,
+    Column::RepurchasePrice,
+    Column::SalePrice,
+    Column::Date,
+];
+
+/// Columns of the history layout
+const HISTORY: &[Column] = &[
+    Column::Code,
+    Column::Name,
+    Column::Isin,
+    Column::IsinDr,
+    Column::Nav,
+    Column::RepurchasePrice,
+    Column::SalePrice,
+    Column::Date,
+];
+
+impl Column {
+    /// Columns of `layout`, in order
+    #[cfg_attr(not(feature = "html"), allow(dead_code))]
+    pub(crate) fn of_layout(layout: Layout) -> &'static [Column] {
+        match layout {
+            Layout::Modern => MODERN,
+            Layout::Legacy => LEGACY,
+            Layout::History => HISTORY,
+        }
+    }
+
+    /// Name of the column in current feeds
+    #[cfg_attr(not(feature = "html"), allow(dead_code))]
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Column::Code => "Scheme Code",
+            Column::Isin => "ISIN Div Payout/ ISIN Growth",
+            Column::IsinDr => "ISIN Div Reinvestment",
+            Column::Name => "Scheme Name",
+            Column::Nav => "Net Asset Value",
+            Column::RepurchasePrice => "Repurchase Price",
+            Column::SalePrice => "Sale Price",
+            Column::Date => "Date",
+        }
+    }
+
+    /// Column called `name`, ignoring case, spaces and punctuation
+    pub(crate) fn of(name: &str) -> Option<Column> {
+        // Asked of every field of the first lines, so keys of the usual length stay on the stack
+        let mut buf = [0; 48];
+        let mut len = 0;
+        for byte in name.bytes().filter(u8::is_ascii_alphanumeric) {
+            match buf.get_mut(len) {
+                Some(slot) => *slot = byte.to_ascii_lowercase(),
+                None => return Column::of_key(&name_key(name)),
+            }
+            len += 1;
+        }
+        Column::of_key(std::str::from_utf8(&buf[..len]).unwrap_or_default())
+    }
+
+    /// Column of the `name_key` of a name
+    fn of_key(key: &str) -> Option<Column> {
+        match key {
+            "schemecode" => Some(Column::Code),
+            "schemename" => Some(Column::Name),
+            "netassetvalue" | "nav" => Some(Column::Nav),
+            "repurchaseprice" => Some(Column::RepurchasePrice),
+            "saleprice" => Some(Column::SalePrice),
+            "date" | "navdate" => Some(Column::Date),
+            // `ISIN Div Payout/ ISIN Growth`, `ISIN Div Reinvestment` and their spellings over
+            // the years
+            key if key.starts_with("isin") && key.contains("reinvest") => Some(Column::IsinDr),
+            key if key.starts_with("isin")
+                && (key.contains("payout") || key.contains("growth")) =>
+            {
+                Some(Column::Isin)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Lowercased ASCII letters and digits of `name`, e.g. `schemecode`
+fn name_key(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect()
+}
+
+/// Column header of a feed, see
+/// [`NavRecordIterator::header`](struct.NavRecordIterator.html#method.header)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedHeader {
+    /// Column names as published, trimmed
+    pub columns: Vec<String>,
+    /// Layout of the records below
+    pub layout: Layout,
+}
+
+impl FeedHeader {
+    /// Whether `line`, which contains a `;`, names at least two known columns
+    pub(crate) fn is_header(line: &str) -> bool {
+        line.split(';')
+            .filter(|name| Column::of(name).is_some())
+            .nth(1)
+            .is_some()
+    }
+
+    /// Parses a header `line`, failing with
+    /// [`Error::UnknownHeader`](enum.Error.html#variant.UnknownHeader) unless its columns are
+    /// those of a known layout
+    pub(crate) fn parse(line: &str) -> Result<FeedHeader, Error> {
+        let line = trim_trailing_separators(line.trim_start_matches('\u{feff}').trim());
+        let columns: Vec<String> = line
+            .split(';')
+            .map(|name| name.trim().to_string())
+            .collect();
+        let known: Vec<Option<Column>> = columns.iter().map(|name| Column::of(name)).collect();
+        let layout = if known.iter().copied().eq(MODERN.iter().copied().map(Some)) {
+            Layout::Modern
+        } else if known.iter().copied().eq(LEGACY.iter().copied().map(Some)) {
+            Layout::Legacy
+        } else if known.iter().copied().eq(HISTORY.iter().copied().map(Some)) {
+            Layout::History
+        } else {
+            let unexpected = columns
+                .iter()
+                .zip(&known)
+                .filter(|(_, column)| column.is_none())
+                .map(|(name, _)| name.clone())
+                .collect();
+            return Err(Error::UnknownHeader {
+                columns,
+                unexpected,
+            });
+        };
+        Ok(FeedHeader { columns, layout })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nav_from_str, LineType, NavRecord, Result};
+
+    #[test]
+    fn layouts() {
+        let modern = FeedHeader::parse(crate::FEED_HEADER).unwrap();
+        assert_eq!(modern.layout, Layout::Modern);
+        assert_eq!(modern.columns.len(), 6);
+        assert_eq!(modern.columns[1], "ISIN Div Payout/ ISIN Growth");
+
+        for line in &[
+            "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date",
+            "\u{feff}scheme code ; ISIN Div Payout/ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date;;\r\n",
+        ] {
+            assert_eq!(FeedHeader::parse(line).unwrap().layout, Layout::Legacy);
+        }
+
+        // The history download of all AMCs
+        let history = "Scheme Code;Scheme Name;ISIN Div Payout/ISIN Growth;ISIN Div Reinvestment;Net Asset Value;Repurchase Price;Sale Price;Date";
+        assert_eq!(FeedHeader::parse(history).unwrap().layout, Layout::History);
+    }
+
+    #[test]
+    fn unknown_layouts() {
+        let error = FeedHeader::parse(
+            "Scheme Code;ISIN;ISIN Div Reinvestment;Scheme Name;NAV (Rs);Net Asset Value;Date",
+        )
+        .unwrap_err();
+        match error {
+            Error::UnknownHeader { ref unexpected, .. } => {
+                assert_eq!(unexpected, &["ISIN", "NAV (Rs)"]);
+            }
+            ref other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            error.to_string(),
+            "Unknown header layout, unexpected columns: `ISIN`, `NAV (Rs)`"
+        );
+
+        // Known columns in an order records aren't parsed in
+        let shuffled = "Scheme Code;Scheme Name;ISIN Div Payout/ISIN Growth;ISIN Div Reinvestment;Date;Net Asset Value";
+        let error = FeedHeader::parse(shuffled).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Unknown header layout: `{}`", shuffled)
+        );
+        // Ending parsing rather than misreading the records
+        let feed = format!(
+            "{}\nAxis Mutual Fund\n120465;Axis Bluechip Fund - Direct Plan - Growth;INF846K01AB4;-;21-Jun-2024;61.43\n",
+            shuffled
+        );
+        let results: Vec<_> = nav_from_str(&feed).collect();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(Error::UnknownHeader { .. })));
+    }
+
+    #[test]
+    fn header_lines() {
+        assert_eq!(LineType::of(crate::FEED_HEADER), LineType::Header);
+        assert_eq!(LineType::of("Scheme Code;Scheme Name\n"), LineType::Header);
+        // Not swallowed as headers
+        assert_eq!(LineType::of("Schemeworks Mutual Fund\n"), LineType::Amc);
+        assert_eq!(
+            LineType::of("120465;INF846K01AB4;-;Scheme Name Fund;61.43;21-Jun-2024"),
+            LineType::Record
+        );
+
+        let feed = "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date\n\
+                    Schemeworks Mutual Fund\n\
+                    120465;INF846K01AB4;-;Schemeworks Bluechip Fund - Direct Plan - Growth;61.43;61.43;61.43;21-Jun-2024\n";
+        let mut records = nav_from_str(feed);
+        assert_eq!(records.header(), None);
+        let parsed: Vec<NavRecord> = records.by_ref().collect::<Result<_>>().unwrap();
+        assert_eq!(parsed[0].amc, "Schemeworks Mutual Fund");
+        assert_eq!(parsed[0].sale_price, Some(61.43));
+        let header = records.header().unwrap();
+        assert_eq!(header.layout, Layout::Legacy);
+        assert_eq!(header.columns[5], "Repurchase Price");
+    }
+}
@@ -0,0 +1,724 @@
+//! Per-scheme NAV time series assembled from several feeds
+
+use crate::business_days::business_days_between;
+use crate::group::AmcKeys;
+use crate::{AmcAliases, Error, NavDate, NavRecord, Result, SchemeCode};
+use std::collections::{HashMap, HashSet};
+
+/// NAV series of one scheme, sorted by date
+pub type Series = Vec<(NavDate, f64)>;
+
+/// Collects NAVs of several daily feeds into per-scheme series
+///
+/// Each scheme keeps a single flat `Vec` of `(date, nav)` points sorted by date. When a date
+/// is added twice for a scheme the later value wins and the pair is reported by
+/// [`duplicates`](#method.duplicates).
+///
+/// Returns are price returns: the feed has no IDCW payouts, so returns of IDCW options leave
+/// out the amounts paid and understate the total return; growth options are unaffected.
+/// [`cagr`](#method.cagr), [`point_to_point_return`](#method.point_to_point_return) and
+/// [`rolling_returns`](#method.rolling_returns) take the NAVs of dates without one as told
+/// by [`nav_near`](#method.nav_near).
+///
+/// The AMC of each scheme is kept under its key of [`AmcAliases`](struct.AmcAliases.html),
+/// the built-in renames unless set with [`amc_aliases`](#method.amc_aliases), so a series
+/// spanning the rename of its fund house stays with one AMC.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let mut history = amfi::NavHistory::new();
+/// for path in &["fixtures/NAVOpen.txt"] {
+///     let errors = history.add(amfi::nav_from_file(path)?);
+///     assert!(errors.is_empty());
+/// }
+/// let (date, nav) = history.latest(120_465).unwrap();
+/// println!("{} {}", date, nav);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NavHistory {
+    series: HashMap<SchemeCode, Series>,
+    duplicates: Vec<(SchemeCode, NavDate)>,
+    snap_days: u32,
+    amcs: HashMap<SchemeCode, SchemeAmc>,
+    amc_keys: AmcKeys,
+}
+
+impl Default for NavHistory {
+    fn default() -> Self {
+        NavHistory {
+            series: HashMap::new(),
+            duplicates: Vec::new(),
+            snap_days: 3,
+            amcs: HashMap::new(),
+            amc_keys: AmcKeys::new(AmcAliases::new()),
+        }
+    }
+}
+
+/// AMC of a scheme across the feeds of a history, see
+/// [`NavHistory::amc`](struct.NavHistory.html#method.amc)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeAmc {
+    /// [Key](struct.AmcAliases.html#method.key) of the AMC of the scheme's latest record
+    pub key: String,
+    /// AMC headers of the scheme's records as published, in the order first added
+    pub published: Vec<String>,
+    /// Date of the record giving `key`
+    date: NavDate,
+}
+
+/// Largest fall of a NAV series from a peak, see
+/// [`NavHistory::max_drawdown`](struct.NavHistory.html#method.max_drawdown)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drawdown {
+    /// Date of the peak
+    pub peak: NavDate,
+    /// NAV at the peak
+    pub peak_nav: f64,
+    /// Date of the lowest NAV after the peak, before it was exceeded
+    pub trough: NavDate,
+    /// NAV at the trough
+    pub trough_nav: f64,
+    /// Fall from peak to trough as a fraction of the peak, e.g. `0.2` for 20%; `0.0` for
+    /// series that never fall
+    pub depth: f64,
+}
+
+impl NavHistory {
+    /// Empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many business days away from a date its NAV may be taken, 3 by default, see
+    /// [`nav_near`](#method.nav_near)
+    pub fn snap_tolerance(mut self, business_days: u32) -> Self {
+        self.snap_days = business_days;
+        self
+    }
+
+    /// Keys AMCs with `aliases`, to be set before records are added
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use amfi::{AmcAliases, AmcSuffixPolicy};
+    ///
+    /// let aliases = AmcAliases::new().suffix(AmcSuffixPolicy::Strip);
+    /// let mut history = amfi::NavHistory::new().amc_aliases(aliases);
+    /// history.add(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+    /// assert_eq!(history.amc(120_465).unwrap().key, "Axis");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn amc_aliases(mut self, aliases: AmcAliases) -> Self {
+        self.amc_keys = AmcKeys::new(aliases);
+        self
+    }
+
+    /// Adds the records of one feed, returning its parse errors
+    pub fn add<I>(&mut self, results: I) -> Vec<Error>
+    where
+        I: IntoIterator<Item = Result<NavRecord>>,
+    {
+        let mut errors = Vec::new();
+        let mut touched = HashSet::new();
+        for result in results {
+            match result {
+                Ok(record) => {
+                    if !record.amc.is_empty() {
+                        let key = self.amc_keys.of(&record.amc);
+                        add_amc(&mut self.amcs, &record, key);
+                    }
+                    self.series
+                        .entry(record.code)
+                        .or_default()
+                        .push((record.date, record.nav));
+                    touched.insert(record.code);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        for code in touched {
+            if let Some(series) = self.series.get_mut(&code) {
+                normalize(code, series, &mut self.duplicates);
+            }
+        }
+        errors
+    }
+
+    /// Series of scheme `code`, sorted by date
+    pub fn series<C: Into<SchemeCode>>(&self, code: C) -> Option<&[(NavDate, f64)]> {
+        self.series.get(&code.into()).map(Vec::as_slice)
+    }
+
+    /// Most recent point of scheme `code`
+    pub fn latest<C: Into<SchemeCode>>(&self, code: C) -> Option<(NavDate, f64)> {
+        self.series(code).and_then(|series| series.last().cloned())
+    }
+
+    /// Simple return of scheme `code` from `from` to `to`, e.g. `0.05` for 5%
+    ///
+    /// Uses the NAV on or last before each date, so weekends and holidays resolve to the
+    /// previous business day. `None` when there is no NAV on or before `from`, or it is zero;
+    /// see [`point_to_point_return`](#method.point_to_point_return) for errors telling why.
+    pub fn returns<C: Into<SchemeCode>>(&self, code: C, from: NavDate, to: NavDate) -> Option<f64> {
+        let series = self.series(code)?;
+        let start = nav_as_of(series, from)?;
+        let end = nav_as_of(series, to)?;
+        if start == 0.0 {
+            return None;
+        }
+        Some(end / start - 1.0)
+    }
+
+    /// NAV of scheme `code` standing for `date`, with the date of the point taken
+    ///
+    /// The point of `date` itself if there is one, else the last point before it, else the
+    /// first point after it, as long as it is within the [snap
+    /// tolerance](#method.snap_tolerance). Points count as within `n` business days of `date`
+    /// when at most `n` [business days](fn.is_business_day.html) lie between them, counting
+    /// the later of the two but not the earlier: the Friday before a Saturday, Sunday or
+    /// Monday holiday is 0 business days away, the Friday before an ordinary Monday 1.
+    ///
+    /// Fails with [`Error::MissingNav`](enum.Error.html#variant.MissingNav) if no point is
+    /// close enough or the scheme is unknown.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use amfi::NavDate;
+    ///
+    /// let mut history = amfi::NavHistory::new();
+    /// history.add(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+    /// // Saturday and Sunday take Friday's NAV
+    /// let sunday = NavDate::from_ymd(2024, 6, 23).unwrap();
+    /// let (date, nav) = history.nav_near(120_465, sunday)?;
+    /// assert_eq!(date, NavDate::from_ymd(2024, 6, 21).unwrap());
+    /// assert_eq!(nav, 61.43);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn nav_near<C: Into<SchemeCode>>(&self, code: C, date: NavDate) -> Result<(NavDate, f64)> {
+        let code = code.into();
+        let missing = || Error::MissingNav {
+            code,
+            date: Some(date),
+            tolerance: self.snap_days,
+        };
+        let series = self.series(code).ok_or_else(missing)?;
+        let after = series.partition_point(|&(point, _)| point <= date);
+        let before = after.checked_sub(1).map(|index| series[index]);
+        before
+            .into_iter()
+            .chain(series.get(after).cloned())
+            .find(|&(point, _)| business_days_between(point, date) <= i64::from(self.snap_days))
+            .ok_or_else(missing)
+    }
+
+    /// Simple return of scheme `code` from `from` to `to`, e.g. `0.05` for 5%
+    ///
+    /// NAVs are taken by [`nav_near`](#method.nav_near), whose errors are returned. Fails with
+    /// [`Error::UndefinedReturn`](enum.Error.html#variant.UndefinedReturn) if `to` is before
+    /// `from` or the NAV taken for `from` is zero.
+    pub fn point_to_point_return<C: Into<SchemeCode>>(
+        &self,
+        code: C,
+        from: NavDate,
+        to: NavDate,
+    ) -> Result<f64> {
+        let ((_, start), (_, end)) = self.endpoints(code.into(), from, to)?;
+        Ok(end / start - 1.0)
+    }
+
+    /// Compound annual growth rate of scheme `code` from `from` to `to`, e.g. `0.12` for 12%
+    /// a year
+    ///
+    /// Compounded over the calendar days between the dates of the NAVs taken, in years of 365
+    /// days: `(end / start)^(365 / days) - 1`. Fails as
+    /// [`point_to_point_return`](#method.point_to_point_return) does, and with
+    /// [`Error::UndefinedReturn`](enum.Error.html#variant.UndefinedReturn) if both dates take
+    /// the same NAV.
+    ///
+    /// ```rust
+    /// use amfi::{NavDate, NavHistory, NavRecord};
+    ///
+    /// let start = NavDate::from_ymd(2021, 6, 21).unwrap();
+    /// let end = NavDate::from_ymd(2024, 6, 21).unwrap();
+    /// let mut history = NavHistory::new();
+    /// history.add(vec![
+    ///     Ok(NavRecord::fixture().with_date(start).with_nav(40.0)),
+    ///     Ok(NavRecord::fixture().with_date(end).with_nav(61.43)),
+    /// ]);
+    /// let cagr = history.cagr(120_465, start, end)?;
+    /// assert!((cagr - 0.1536).abs() < 1e-4);
+    /// # Ok::<(), amfi::Error>(())
+    /// ```
+    pub fn cagr<C: Into<SchemeCode>>(&self, code: C, from: NavDate, to: NavDate) -> Result<f64> {
+        let code = code.into();
+        let ((start_date, start), (end_date, end)) = self.endpoints(code, from, to)?;
+        let days = end_date.epoch_days() - start_date.epoch_days();
+        if days == 0 {
+            return Err(Error::UndefinedReturn {
+                code,
+                reason: "both dates take the same NAV",
+            });
+        }
+        Ok((end / start).powf(365.0 / days as f64) - 1.0)
+    }
+
+    /// Simple returns of scheme `code` over every window of `window_days` calendar days
+    /// ending at a point of its series, by end date
+    ///
+    /// Each window starts `window_days` before its end, with the NAV taken for that date by
+    /// [`nav_near`](#method.nav_near); windows whose start has no NAV close enough, or a zero
+    /// one, are left out, so the first windows of a series are. Fails with
+    /// [`Error::MissingNav`](enum.Error.html#variant.MissingNav) for an unknown scheme and
+    /// [`Error::UndefinedReturn`](enum.Error.html#variant.UndefinedReturn) for windows of
+    /// no days.
+    pub fn rolling_returns<C: Into<SchemeCode>>(
+        &self,
+        code: C,
+        window_days: u32,
+    ) -> Result<Vec<(NavDate, f64)>> {
+        let code = code.into();
+        if window_days == 0 {
+            return Err(Error::UndefinedReturn {
+                code,
+                reason: "window of no days",
+            });
+        }
+        let series = self.known(code)?;
+        Ok(series
+            .iter()
+            .filter_map(|&(end_date, end)| {
+                let start_date =
+                    NavDate::from_epoch_days(end_date.epoch_days() - i64::from(window_days))?;
+                match self.nav_near(code, start_date) {
+                    Ok((_, start)) if start != 0.0 => Some((end_date, end / start - 1.0)),
+                    _ => None,
+                }
+            })
+            .collect())
+    }
+
+    /// Largest fall of the NAV of scheme `code` from a peak to a later low
+    ///
+    /// Of the whole series; the first of equally deep falls. Fails with
+    /// [`Error::MissingNav`](enum.Error.html#variant.MissingNav) for an unknown scheme.
+    pub fn max_drawdown<C: Into<SchemeCode>>(&self, code: C) -> Result<Drawdown> {
+        let series = self.known(code.into())?;
+        let (first_date, first) = series[0];
+        let mut worst = Drawdown {
+            peak: first_date,
+            peak_nav: first,
+            trough: first_date,
+            trough_nav: first,
+            depth: 0.0,
+        };
+        let mut peak = series[0];
+        for &(date, nav) in &series[1..] {
+            if nav > peak.1 {
+                peak = (date, nav);
+            } else if peak.1 > 0.0 {
+                let depth = 1.0 - nav / peak.1;
+                if depth > worst.depth {
+                    worst = Drawdown {
+                        peak: peak.0,
+                        peak_nav: peak.1,
+                        trough: date,
+                        trough_nav: nav,
+                        depth,
+                    };
+                }
+            }
+        }
+        Ok(worst)
+    }
+
+    /// Series of scheme `code`, failing for unknown schemes
+    fn known(&self, code: SchemeCode) -> Result<&[(NavDate, f64)]> {
+        match self.series(code) {
+            Some(series) if !series.is_empty() => Ok(series),
+            _ => Err(Error::MissingNav {
+                code,
+                date: None,
+                tolerance: self.snap_days,
+            }),
+        }
+    }
+
+    /// Points taken for `from` and `to`, failing if they make no return
+    fn endpoints(
+        &self,
+        code: SchemeCode,
+        from: NavDate,
+        to: NavDate,
+    ) -> Result<((NavDate, f64), (NavDate, f64))> {
+        let undefined = |reason| Error::UndefinedReturn { code, reason };
+        if to < from {
+            return Err(undefined("period ends before it starts"));
+        }
+        let start = self.nav_near(code, from)?;
+        let end = self.nav_near(code, to)?;
+        if start.1 == 0.0 {
+            return Err(undefined("starting NAV is zero"));
+        }
+        Ok((start, end))
+    }
+
+    /// AMC of scheme `code`, `None` for schemes only added without AMC
+    pub fn amc<C: Into<SchemeCode>>(&self, code: C) -> Option<&SchemeAmc> {
+        self.amcs.get(&code.into())
+    }
+
+    /// Schemes whose AMC has the key of `amc`, sorted
+    ///
+    /// `amc` is keyed like the records were, so any name of a renamed fund house lists the
+    /// schemes of all.
+    pub fn schemes_of_amc(&self, amc: &str) -> Vec<SchemeCode> {
+        let key = self.amc_keys.get(amc);
+        let mut codes: Vec<SchemeCode> = self
+            .amcs
+            .iter()
+            .filter(|(_, scheme)| scheme.key == key)
+            .map(|(&code, _)| code)
+            .collect();
+        codes.sort_unstable();
+        codes
+    }
+
+    /// Scheme and date pairs added more than once, in the order found
+    pub fn duplicates(&self) -> &[(SchemeCode, NavDate)] {
+        &self.duplicates
+    }
+
+    /// Number of schemes
+    pub fn len(&self) -> usize {
+        self.series.len()
+    }
+
+    /// Whether no scheme has been added
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// Scheme codes, in arbitrary order
+    pub fn codes(&self) -> impl Iterator<Item = SchemeCode> + '_ {
+        self.series.keys().cloned()
+    }
+
+    /// Series of all schemes
+    pub fn into_inner(self) -> HashMap<SchemeCode, Series> {
+        self.series
+    }
+}
+
+/// Attributes the scheme of `record` to the AMC of `key`, kept if its record is the latest
+fn add_amc(amcs: &mut HashMap<SchemeCode, SchemeAmc>, record: &NavRecord, key: &str) {
+    match amcs.get_mut(&record.code) {
+        Some(scheme) => {
+            if !scheme.published.contains(&record.amc) {
+                scheme.published.push(record.amc.clone());
+            }
+            if record.date >= scheme.date && scheme.key != key {
+                scheme.key = key.to_string();
+            }
+            scheme.date = scheme.date.max(record.date);
+        }
+        None => {
+            amcs.insert(
+                record.code,
+                SchemeAmc {
+                    key: key.to_string(),
+                    published: vec![record.amc.clone()],
+                    date: record.date,
+                },
+            );
+        }
+    }
+}
+
+/// Sorts `series` by date, keeping the last added point of each date
+fn normalize(code: SchemeCode, series: &mut Series, duplicates: &mut Vec<(SchemeCode, NavDate)>) {
+    // Stable sort keeps points of one date in the order they were added
+    series.sort_by_key(|&(date, _)| date);
+    let mut deduped: Series = Vec::with_capacity(series.len());
+    for &(date, nav) in series.iter() {
+        match deduped.last_mut() {
+            Some(last) if last.0 == date => {
+                last.1 = nav;
+                duplicates.push((code, date));
+            }
+            _ => deduped.push((date, nav)),
+        }
+    }
+    *series = deduped;
+}
+
+fn nav_as_of(series: &[(NavDate, f64)], date: NavDate) -> Option<f64> {
+    let after = series.partition_point(|&(point, _)| point <= date);
+    after.checked_sub(1).map(|index| series[index].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FundMaturity::OpenEnded;
+
+    fn feed(date: &str, axis_nav: f64) -> String {
+        format!(
+            "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+             Axis Mutual Fund\n\
+             120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;{};{}\n\
+             112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;{}\n",
+            axis_nav, date, date
+        )
+    }
+
+    fn add(history: &mut NavHistory, feed: &str) {
+        let errors = history.add(crate::NavRecordIterator::new(feed.as_bytes()));
+        assert!(errors.is_empty());
+    }
+
+    fn date(day: u32) -> NavDate {
+        NavDate::from_ymd(2024, 6, day).unwrap()
+    }
+
+    /// Feed of `amc` on `date` with schemes 118632 and 118650
+    fn snapshot(amc: &str, date: NavDate, nav: f64) -> String {
+        let house = amc.trim_end_matches(" Mutual Fund");
+        crate::FeedBuilder::new(date)
+            .amc(amc, |amc| {
+                amc.section(OpenEnded, "Equity Scheme - Large Cap Fund", |s| {
+                    s.record(
+                        118632,
+                        "INF204K01XZ3",
+                        None,
+                        &format!("{} Large Cap Fund - Direct Plan - Growth", house),
+                        nav,
+                    )
+                    .record(
+                        118650,
+                        "INF204K01YA4",
+                        None,
+                        &format!("{} Large Cap Fund - Regular Plan - Growth", house),
+                        nav - 1.0,
+                    )
+                })
+            })
+            .feed()
+    }
+
+    #[test]
+    fn series_span_amc_renames() {
+        let before = NavDate::from_ymd(2019, 6, 21).unwrap();
+        let after = NavDate::from_ymd(2020, 6, 19).unwrap();
+        let mut history = NavHistory::new();
+        add(
+            &mut history,
+            &snapshot("Nippon India Mutual Fund", after, 30.5),
+        );
+        add(
+            &mut history,
+            &snapshot("Reliance Mutual Fund", before, 35.1),
+        );
+
+        assert_eq!(history.series(118_632).unwrap().len(), 2);
+        let amc = history.amc(118_632).unwrap();
+        assert_eq!(amc.key, "Nippon India Mutual Fund");
+        assert_eq!(
+            amc.published,
+            ["Nippon India Mutual Fund", "Reliance Mutual Fund"]
+        );
+        let codes = [SchemeCode::from(118_632), SchemeCode::from(118_650)];
+        assert_eq!(history.schemes_of_amc("Reliance Mutual Fund"), codes);
+        assert_eq!(history.schemes_of_amc("Nippon India Mutual Fund"), codes);
+        assert_eq!(history.amc(120_465), None);
+
+        // Without aliases the latest name wins, the former one is still listed
+        let mut history = NavHistory::new().amc_aliases(AmcAliases::empty());
+        add(
+            &mut history,
+            &snapshot("Reliance Mutual Fund", before, 35.1),
+        );
+        add(
+            &mut history,
+            &snapshot("Nippon India Mutual Fund", after, 30.5),
+        );
+        assert_eq!(
+            history.amc(118_632).unwrap().key,
+            "Nippon India Mutual Fund"
+        );
+        assert!(history.schemes_of_amc("Reliance Mutual Fund").is_empty());
+    }
+
+    #[test]
+    fn series_sorted_and_deduplicated() {
+        let mut history = NavHistory::new();
+        add(&mut history, &feed("21-Jun-2024", 62.0));
+        add(&mut history, &feed("19-Jun-2024", 60.0));
+        add(&mut history, &feed("20-Jun-2024", 61.0));
+        add(&mut history, &feed("20-Jun-2024", 61.5));
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history.series(120_465).unwrap(),
+            &[(date(19), 60.0), (date(20), 61.5), (date(21), 62.0)][..]
+        );
+        assert_eq!(history.latest(120_465), Some((date(21), 62.0)));
+        assert_eq!(history.series(1), None);
+
+        let mut duplicates = history.duplicates().to_vec();
+        duplicates.sort();
+        assert_eq!(
+            duplicates,
+            [(112_277.into(), date(20)), (120_465.into(), date(20))]
+        );
+    }
+
+    #[test]
+    fn point_to_point_returns() {
+        let mut history = NavHistory::new();
+        add(&mut history, &feed("19-Jun-2024", 60.0));
+        add(&mut history, &feed("21-Jun-2024", 63.0));
+
+        let returns = history.returns(120_465, date(19), date(21)).unwrap();
+        assert!((returns - 0.05).abs() < 1e-12);
+        assert_eq!(history.returns(120_465, date(20), date(23)), Some(returns));
+        assert_eq!(history.returns(120_465, date(18), date(21)), None);
+        assert_eq!(history.returns(112_277, date(19), date(21)), Some(0.0));
+    }
+
+    /// History of the fixture scheme with `points` of June 2024
+    fn june(points: &[(u32, f64)]) -> NavHistory {
+        let mut history = NavHistory::new();
+        let records = points
+            .iter()
+            .map(|&(day, nav)| Ok(NavRecord::fixture().with_date(date(day)).with_nav(nav)));
+        assert!(history.add(records).is_empty());
+        history
+    }
+
+    #[test]
+    fn snaps_dates_by_business_days() {
+        // Friday 21st and Tuesday 25th, without Monday 24th
+        let history = june(&[(21, 61.0), (25, 62.0)]);
+        let near = |history: &NavHistory, day| history.nav_near(120_465, date(day)).map(|p| p.0);
+        let cases = [
+            (21, Some(21)),
+            // Weekend and missing Monday before the next NAV take Friday's
+            (22, Some(21)),
+            (23, Some(21)),
+            (24, Some(21)),
+            // Before the first NAV the next is taken, up to 3 business days ahead
+            (20, Some(21)),
+            (18, Some(21)),
+            (17, None),
+            // After the last NAV the previous is taken
+            (28, Some(25)),
+        ];
+        for &(day, expected) in &cases {
+            assert_eq!(near(&history, day).ok(), expected.map(date), "{}", day);
+        }
+        let july_1 = NavDate::from_ymd(2024, 7, 1).unwrap();
+        match history.nav_near(120_465, july_1) {
+            Err(Error::MissingNav {
+                date: Some(date),
+                tolerance: 3,
+                ..
+            }) => assert_eq!(date, july_1),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let exact = history.clone().snap_tolerance(0);
+        assert_eq!(near(&exact, 23).ok(), Some(date(21)));
+        assert!(near(&exact, 24).is_err());
+        assert!(near(&exact, 20).is_err());
+
+        // Bakri Id on Monday the 17th takes Friday's NAV as the weekend does
+        let history = june(&[(14, 60.0), (18, 61.0)]).snap_tolerance(0);
+        assert_eq!(near(&history, 17).ok(), Some(date(14)));
+    }
+
+    #[test]
+    fn point_to_point_and_cagr() {
+        let history = june(&[(3, 50.0), (21, 60.0), (28, 0.0)]);
+        let ptp = history
+            .point_to_point_return(120_465, date(3), date(23))
+            .unwrap();
+        assert!((ptp - 0.2).abs() < 1e-12);
+        // 18 days between the NAVs taken, not the 20 asked for
+        let cagr = history.cagr(120_465, date(1), date(23)).unwrap();
+        assert!((cagr - (1.2_f64.powf(365.0 / 18.0) - 1.0)).abs() < 1e-9);
+
+        let undefined = |result: Result<f64>| match result {
+            Err(Error::UndefinedReturn { reason, .. }) => reason,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(
+            undefined(history.cagr(120_465, date(21), date(3))),
+            "period ends before it starts"
+        );
+        assert_eq!(
+            undefined(history.cagr(120_465, date(21), date(22))),
+            "both dates take the same NAV"
+        );
+        assert_eq!(
+            undefined(history.point_to_point_return(120_465, date(28), date(28))),
+            "starting NAV is zero"
+        );
+        let error = history.cagr(120_465, date(10), date(21)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Missing NAV: scheme 120465 has no NAV within 3 business days of 2024-06-10"
+        );
+        let error = history.cagr(1, date(3), date(21)).unwrap_err();
+        assert!(matches!(error, Error::MissingNav { date: Some(_), .. }));
+    }
+
+    #[test]
+    fn rolling_returns_by_end_date() {
+        // Weekdays of 3rd to 14th June
+        let days = [3, 4, 5, 6, 7, 10, 11, 12, 13, 14];
+        let points: Vec<(u32, f64)> = days.iter().map(|&day| (day, f64::from(day))).collect();
+        let history = june(&points).snap_tolerance(0);
+        let rolling = history.rolling_returns(120_465, 7).unwrap();
+        let expected: Vec<(NavDate, f64)> = days[5..]
+            .iter()
+            .map(|&day| (date(day), f64::from(day) / f64::from(day - 7) - 1.0))
+            .collect();
+        assert_eq!(rolling, expected);
+
+        // Windows starting on the weekend take Friday's NAV
+        let rolling = history.rolling_returns(120_465, 2).unwrap();
+        assert_eq!(rolling[3], (date(10), 10.0 / 7.0 - 1.0));
+        assert!(history.rolling_returns(120_465, 0).is_err());
+        assert!(matches!(
+            history.rolling_returns(1, 7),
+            Err(Error::MissingNav { date: None, .. })
+        ));
+    }
+
+    #[test]
+    fn max_drawdown_from_peak() {
+        let history = june(&[
+            (3, 10.0),
+            (4, 12.0),
+            (5, 9.0),
+            (6, 11.0),
+            (7, 13.0),
+            (10, 10.4),
+        ]);
+        let drawdown = history.max_drawdown(120_465).unwrap();
+        assert_eq!((drawdown.peak, drawdown.peak_nav), (date(4), 12.0));
+        assert_eq!((drawdown.trough, drawdown.trough_nav), (date(5), 9.0));
+        assert!((drawdown.depth - 0.25).abs() < 1e-12);
+
+        let rising = june(&[(3, 10.0), (4, 10.0), (5, 11.0)]);
+        let drawdown = rising.max_drawdown(120_465).unwrap();
+        assert_eq!(drawdown.depth, 0.0);
+        assert_eq!(drawdown.peak, date(3));
+        assert!(rising.max_drawdown(1).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! NAV table of the portal's HTML pages, an alternate source to the text feed
+
+use crate::group::collapse_whitespace;
+use crate::header::Column;
+use crate::{Error, Layout, NavRecordIterator, Result};
+use scraper::{ElementRef, Html, Selector};
+use std::io::{Cursor, Read};
+
+/// Columns a table must have to be read as the NAV table
+const REQUIRED: &[Column] = &[Column::Code, Column::Name, Column::Nav, Column::Date];
+
+/// Parses the NAV table of an HTML page of the portal, as saved or served when the text feed
+/// is unavailable
+///
+/// The NAV table is the first table with `Scheme Code`, `Scheme Name`, `Net Asset Value` and
+/// `Date` columns, in any order and spelled as in feed headers; other columns are ignored.
+/// Rows of a single cell, such as `Open Ended Schemes(Equity Scheme - Large Cap Fund)` or
+/// `Axis Mutual Fund`, are scheme and AMC headers as in the text feed, and repeated column
+/// headers are skipped. Records are then parsed as those of a text feed below a column
+/// header, with the same options; their `source_line` is one more than their 1-based row of
+/// the table.
+///
+/// Pages without such a table, e.g. error or maintenance pages, fail with
+/// [`Error::InvalidHtml`](enum.Error.html#variant.InvalidHtml).
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let file = std::fs::File::open("fixtures/NAVPage.html")?;
+/// for record in amfi::nav_from_html(file)? {
+///     let record = record?;
+///     println!("{} {} {}", record.code, record.nav, record.amc);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn nav_from_html<R: Read>(mut reader: R) -> Result<NavRecordIterator<Cursor<Vec<u8>>>> {
+    let mut page = Vec::new();
+    reader.read_to_end(&mut page)?;
+    let page = Html::parse_document(&String::from_utf8_lossy(&page));
+    let feed = feed_of_page(&page)?;
+    Ok(NavRecordIterator::new(Cursor::new(feed.into_bytes())))
+}
+
+/// Text feed of the NAV table of `page`, a column header then one line per row
+fn feed_of_page(page: &Html) -> Result<String> {
+    let tables = Selector::parse("table").expect("valid selector");
+    let mut count = 0;
+    for table in page.select(&tables) {
+        count += 1;
+        let rows: Vec<Vec<String>> = rows(table).into_iter().map(cells).collect();
+        if let Some((at, columns)) = rows
+            .iter()
+            .enumerate()
+            .find_map(|(at, row)| header_columns(row).map(|columns| (at, columns)))
+        {
+            return Ok(feed_of_rows(&rows, at, &columns));
+        }
+    }
+    Err(Error::InvalidHtml(format!(
+        "no table with Scheme Code, Scheme Name, Net Asset Value and Date columns among {} tables",
+        count
+    )))
+}
+
+/// Rows of `table`, without those of tables nested in it
+fn rows(table: ElementRef<'_>) -> Vec<ElementRef<'_>> {
+    let selector = Selector::parse("tr").expect("valid selector");
+    table
+        .select(&selector)
+        .filter(|row| {
+            row.ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|parent| parent.value().name() == "table")
+                .map(|parent| parent.id())
+                == Some(table.id())
+        })
+        .collect()
+}
+
+/// Texts of the cells of `row`, trimmed and with whitespace collapsed
+fn cells(row: ElementRef<'_>) -> Vec<String> {
+    row.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+        .map(|cell| collapse_whitespace(&cell.text().collect::<String>()).into_owned())
+        .collect()
+}
+
+/// Column of each cell of `row` if it is a column header naming the required columns
+fn header_columns(row: &[String]) -> Option<Vec<Option<Column>>> {
+    let columns: Vec<Option<Column>> = row.iter().map(|name| Column::of(name)).collect();
+    if REQUIRED
+        .iter()
+        .all(|required| columns.contains(&Some(*required)))
+    {
+        Some(columns)
+    } else {
+        None
+    }
+}
+
+/// Text feed of table `rows` whose column header, with `columns`, is row `at`
+///
+/// The column header comes first rather than in place of row `at`, as a header below
+/// section headers would start another feed.
+fn feed_of_rows(rows: &[Vec<String>], at: usize, columns: &[Option<Column>]) -> String {
+    let has = |column| columns.contains(&Some(column));
+    let layout = if has(Column::RepurchasePrice) && has(Column::SalePrice) {
+        Layout::Legacy
+    } else {
+        Layout::Modern
+    };
+    let order = Column::of_layout(layout);
+
+    let names: Vec<&str> = order.iter().map(|column| column.name()).collect();
+    let mut feed = names.join(";");
+    feed.push('\n');
+    for (index, row) in rows.iter().enumerate() {
+        let filled: Vec<&String> = row.iter().filter(|text| !text.is_empty()).collect();
+        if filled.is_empty() || header_columns(row).is_some() {
+            // Empty and header rows are blank lines
+        } else if filled.len() == 1 && row.len() < REQUIRED.len() {
+            feed.push_str(filled[0]);
+        } else if index > at {
+            let fields: Vec<&str> = order
+                .iter()
+                .map(|column| {
+                    let text = columns
+                        .iter()
+                        .position(|known| *known == Some(*column))
+                        .and_then(|at| row.get(at))
+                        .map_or("", String::as_str);
+                    match column {
+                        Column::Isin | Column::IsinDr if text.is_empty() => "-",
+                        _ => text,
+                    }
+                })
+                .collect();
+            feed.push_str(&fields.join(";"));
+        }
+        feed.push('\n');
+    }
+    feed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_bytes;
+    use crate::{nav_from_reader, NavRecord, NormalizeOptions};
+
+    fn collapsed() -> NormalizeOptions {
+        NormalizeOptions {
+            collapse_whitespace: true,
+            ..NormalizeOptions::default()
+        }
+    }
+
+    #[test]
+    fn agrees_with_feed() {
+        let page = fixture_bytes("NAVPage.html");
+        let records: Vec<NavRecord> = nav_from_html(&page[..])
+            .unwrap()
+            .normalize(collapsed())
+            .collect::<Result<_>>()
+            .unwrap();
+        let feed = fixture_bytes("NAVOpen.txt");
+        let expected: Vec<NavRecord> = nav_from_reader(&feed[..])
+            .normalize(collapsed())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), expected.len());
+        for (record, expected) in records.into_iter().zip(expected) {
+            assert!(record.source_line.is_some());
+            assert_eq!(
+                NavRecord {
+                    source_line: None,
+                    ..record
+                },
+                NavRecord {
+                    source_line: None,
+                    ..expected
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn maps_columns() {
+        let page = "<table>\
+            <tr><td colspan=\"4\">Open Ended Schemes(Equity Scheme - Large Cap Fund)</td></tr>\
+            <tr><th>NAV Date</th><th>NAV</th><th>Change</th><th>Scheme Name</th><th>Scheme Code</th></tr>\
+            <tr><td colspan=\"5\">Axis Mutual Fund</td></tr>\
+            <tr><td>21-Jun-2024</td><td>61.43</td><td>+0.2%</td><td>Axis Bluechip Fund &amp; Co - Direct Plan</td><td>120465</td></tr>\
+            <tr><td>21-Jun-2024</td><td>oops</td><td></td><td>Axis Bluechip Fund - Regular Plan</td><td>112277</td></tr>\
+            </table>";
+        let results: Vec<_> = nav_from_html(page.as_bytes()).unwrap().collect();
+        assert_eq!(results.len(), 2);
+        let record = results[0].as_ref().unwrap();
+        assert_eq!(record.code.to_string(), "120465");
+        assert_eq!(record.name, "Axis Bluechip Fund & Co - Direct Plan");
+        assert_eq!(record.nav, 61.43);
+        assert_eq!(record.amc, "Axis Mutual Fund");
+        assert_eq!(record.category, "Large Cap Fund");
+        assert_eq!(record.isin, None);
+        assert_eq!(record.source_line, Some(5));
+        assert!(results[1].is_err());
+
+        let legacy = "<table>\
+            <tr><th>Scheme Code</th><th>Scheme Name</th><th>Net Asset Value</th>\
+            <th>Repurchase Price</th><th>Sale Price</th><th>Date</th></tr>\
+            <tr><th colspan=\"6\">Axis Mutual Fund</th></tr>\
+            <tr><td>120465</td><td>Axis Bluechip Fund</td><td>61.43</td><td>60.82</td><td></td><td>21-Jun-2024</td></tr>\
+            </table>";
+        let records: Vec<NavRecord> = nav_from_html(legacy.as_bytes())
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(records[0].repurchase_price, Some(60.82));
+        assert_eq!(records[0].sale_price, None);
+    }
+
+    #[test]
+    fn rejects_other_pages() {
+        for page in &[
+            "<html><body><h1>Service Unavailable</h1></body></html>",
+            "",
+            // Tables without the NAV columns
+            "<table><tr><th>Date</th><th>Notice</th></tr><tr><td>21-Jun-2024</td><td>NAV</td></tr></table>",
+            "<table><tr><th>Scheme Code</th><th>Scheme Name</th><th>Date</th></tr></table>",
+        ] {
+            match nav_from_html(page.as_bytes()) {
+                Err(Error::InvalidHtml(reason)) => {
+                    assert!(reason.starts_with("no table with"), "{}", reason)
+                }
+                Err(other) => panic!("{:?}", other),
+                Ok(_) => panic!("{}", page),
+            }
+        }
+    }
+}
@@ -0,0 +1,861 @@
+//! In-memory lookup of records by scheme code and ISIN
+
+use crate::{Error, NavRecord, NavRecordIterator, SchemeCode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{btree_map, BTreeMap, HashMap};
+use std::io::Read;
+use std::iter::FromIterator;
+#[cfg(feature = "bincode")]
+use std::path::Path;
+use std::sync::Arc;
+
+/// Records indexed by scheme code and ISIN
+///
+/// Both `isin` and `isin_dr` of a record resolve to it. A scheme code seen again is not
+/// overwritten: the first record is kept and later ones are recorded as
+/// [`duplicates`](#method.duplicates), with line numbers when added through
+/// [`add_feed`](#method.add_feed). Which record lookups return for a duplicated code is set
+/// by the [`DuplicatePolicy`](enum.DuplicatePolicy.html), and ISIN and name lookups only find
+/// that record by its own ISINs and name. An ISIN shared by several codes resolves to the
+/// first.
+///
+/// With the `serde` feature the index serializes as its records, line numbers, duplicates
+/// and policy, and is rebuilt on deserialization.
+///
+/// Cloning is cheap: clones share their records until one of them is changed, which then
+/// copies them once. A server can hand out clones of its index to readers while it
+/// [`apply`](#method.apply)s corrections to its own, then swap it in.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let index: amfi::NavIndex = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+///     .filter_map(Result::ok)
+///     .collect();
+/// let record = index.get_by_isin("INF846K01AB4").unwrap();
+/// assert_eq!(index.get_by_code(record.code), Some(record));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NavIndex {
+    entries: Arc<Entries>,
+    policy: DuplicatePolicy,
+}
+
+/// Contents of a [`NavIndex`](struct.NavIndex.html), shared by its clones
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Entries {
+    records: BTreeMap<SchemeCode, NavRecord>,
+    /// Feed line numbers of `records`, where known
+    lines: BTreeMap<SchemeCode, usize>,
+    isins: HashMap<String, SchemeCode>,
+    duplicates: Vec<DuplicateEntry>,
+    /// Positions in `duplicates` of the later records of each duplicated code
+    duplicated: BTreeMap<SchemeCode, Vec<usize>>,
+    /// Lowercase name tokens per record, for `search_name`
+    tokens: BTreeMap<SchemeCode, Vec<String>>,
+    /// Lowercase name tokens of `duplicates`, by position
+    duplicate_tokens: Vec<Vec<String>>,
+}
+
+/// Record of a code chosen by the policy
+#[derive(Debug, Clone, Copy)]
+enum Kept {
+    First,
+    /// Position in `duplicates`
+    Duplicate(usize),
+}
+
+/// Record returned by [`NavIndex`](struct.NavIndex.html) lookups for a duplicated code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DuplicatePolicy {
+    /// The record seen first (default)
+    #[default]
+    KeepFirst,
+    /// The record seen last
+    KeepLast,
+    /// None: duplicated codes are not found, neither by code, ISIN nor iteration, and
+    /// [`NavIndex::try_get_by_code`](struct.NavIndex.html#method.try_get_by_code) fails with
+    /// [`Error::DuplicateCode`](enum.Error.html#variant.DuplicateCode)
+    Error,
+}
+
+/// A scheme code seen again after it was indexed
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DuplicateEntry {
+    /// Record indexed first
+    pub first: NavRecord,
+    /// 1-based feed line of `first`, if known
+    pub first_line: Option<usize>,
+    /// Later record with the same code
+    pub duplicate: NavRecord,
+    /// 1-based feed line of `duplicate`, if known
+    pub line: Option<usize>,
+}
+
+/// Scheme name match returned by [`NavIndex::search_name`](struct.NavIndex.html#method.search_name)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit<'a> {
+    /// Matched record
+    pub record: &'a NavRecord,
+    /// Relevance, higher is better: each query token scores 3 for an exact token match, 2 for
+    /// a token prefix and 1 for a substring of a token
+    pub score: u32,
+}
+
+/// Records counted by [`NavIndex::apply`](struct.NavIndex.html#method.apply)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ApplySummary {
+    /// Records of codes not indexed before
+    pub inserted: usize,
+    /// Records replacing one of the same code and an earlier or the same date
+    pub updated: usize,
+    /// Records older than the one indexed for their code, left out
+    pub ignored: usize,
+}
+
+impl NavIndex {
+    /// Empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty index resolving duplicated codes by `policy`
+    pub fn with_policy(policy: DuplicatePolicy) -> Self {
+        NavIndex {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    /// Policy for duplicated codes
+    pub fn policy(&self) -> DuplicatePolicy {
+        self.policy
+    }
+
+    /// Changes the policy for duplicated codes; no records are dropped either way
+    pub fn set_policy(&mut self, policy: DuplicatePolicy) {
+        self.policy = policy;
+    }
+
+    /// Adds the records of a feed, keeping their line numbers, and returns its parse errors
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut index = amfi::NavIndex::new();
+    /// let errors = index.add_feed(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+    /// assert!(errors.is_empty() && index.duplicates().is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_feed<T: Read>(&mut self, mut records: NavRecordIterator<T>) -> Vec<Error> {
+        let mut errors = Vec::new();
+        while let Some(result) = records.next() {
+            match result {
+                Ok(record) => {
+                    self.insert_at(record, Some(records.line()));
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        errors
+    }
+
+    /// Adds `record`, returning `false` if its code was already indexed
+    pub fn insert(&mut self, record: NavRecord) -> bool {
+        self.insert_at(record, None)
+    }
+
+    fn insert_at(&mut self, record: NavRecord, line: Option<usize>) -> bool {
+        Arc::make_mut(&mut self.entries).insert_at(record, line)
+    }
+
+    /// Upserts `records`: codes not indexed yet are added, and indexed ones replaced by
+    /// records as recent or more recent than theirs
+    ///
+    /// Meant for corrections republished after a feed was indexed. Records dated before the
+    /// one indexed for their code are ignored, so of equally dated records the last wins. A
+    /// replaced code loses its feed line number and its
+    /// [`duplicates`](#method.duplicates), the new record being the one lookups return
+    /// whatever the policy.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let index: amfi::NavIndex = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .filter_map(Result::ok)
+    ///     .collect();
+    /// let mut corrected = index.get_by_code(120_465).unwrap().clone();
+    /// corrected.nav = 61.52;
+    ///
+    /// // Readers keep the index as it was until they pick up the new one
+    /// let mut next = index.clone();
+    /// let summary = next.apply(vec![corrected]);
+    /// assert_eq!((summary.inserted, summary.updated, summary.ignored), (0, 1, 0));
+    /// assert_eq!(next.get_by_code(120_465).unwrap().nav, 61.52);
+    /// assert_eq!(index.get_by_code(120_465).unwrap().nav, 61.43);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply<I: IntoIterator<Item = NavRecord>>(&mut self, records: I) -> ApplySummary {
+        let mut summary = ApplySummary::default();
+        let mut records = records.into_iter().peekable();
+        if records.peek().is_none() {
+            return summary;
+        }
+        let entries = Arc::make_mut(&mut self.entries);
+        for record in records {
+            let indexed = entries
+                .records
+                .get(&record.code)
+                .map(|indexed| indexed.date);
+            match indexed {
+                None => {
+                    entries.insert_at(record, None);
+                    summary.inserted += 1;
+                }
+                Some(date) if record.date >= date => {
+                    entries.replace(record);
+                    summary.updated += 1;
+                }
+                Some(_) => summary.ignored += 1,
+            }
+        }
+        summary
+    }
+
+    /// Record with scheme code `code`, chosen by the policy if the code is duplicated
+    ///
+    /// Under [`DuplicatePolicy::Error`](enum.DuplicatePolicy.html#variant.Error) a duplicated
+    /// code is `None` as a missing one is; [`try_get_by_code`](#method.try_get_by_code) tells
+    /// them apart.
+    pub fn get_by_code<C: Into<SchemeCode>>(&self, code: C) -> Option<&NavRecord> {
+        self.try_get_by_code(code).unwrap_or(None)
+    }
+
+    /// Record with scheme code `code`, chosen by the policy if the code is duplicated, or
+    /// [`Error::DuplicateCode`](enum.Error.html#variant.DuplicateCode) if it is under
+    /// [`DuplicatePolicy::Error`](enum.DuplicatePolicy.html#variant.Error)
+    ///
+    /// ```rust
+    /// use amfi::{DuplicatePolicy, Error, NavIndex, NavRecord};
+    ///
+    /// let mut index = NavIndex::with_policy(DuplicatePolicy::Error);
+    /// index.insert(NavRecord::fixture());
+    /// index.insert(NavRecord::fixture().with_nav(62.0));
+    /// assert!(matches!(
+    ///     index.try_get_by_code(120_465),
+    ///     Err(Error::DuplicateCode { count: 2, .. })
+    /// ));
+    /// assert!(matches!(index.try_get_by_code(1), Ok(None)));
+    /// ```
+    pub fn try_get_by_code<C: Into<SchemeCode>>(
+        &self,
+        code: C,
+    ) -> crate::Result<Option<&NavRecord>> {
+        let code = code.into();
+        Ok(self.kept(code)?.map(|kept| self.record(code, kept)))
+    }
+
+    /// Which record of `code` lookups return
+    fn kept(&self, code: SchemeCode) -> crate::Result<Option<Kept>> {
+        if !self.entries.records.contains_key(&code) {
+            return Ok(None);
+        }
+        let later = match self.entries.duplicated.get(&code) {
+            Some(later) => later,
+            None => return Ok(Some(Kept::First)),
+        };
+        match self.policy {
+            DuplicatePolicy::KeepFirst => Ok(Some(Kept::First)),
+            DuplicatePolicy::KeepLast => Ok(Some(
+                later.last().map_or(Kept::First, |&at| Kept::Duplicate(at)),
+            )),
+            DuplicatePolicy::Error => Err(Error::DuplicateCode {
+                code,
+                count: later.len() + 1,
+            }),
+        }
+    }
+
+    fn record(&self, code: SchemeCode, kept: Kept) -> &NavRecord {
+        match kept {
+            Kept::First => &self.entries.records[&code],
+            Kept::Duplicate(at) => &self.entries.duplicates[at].duplicate,
+        }
+    }
+
+    fn tokens(&self, code: SchemeCode, kept: Kept) -> &[String] {
+        match kept {
+            Kept::First => &self.entries.tokens[&code],
+            Kept::Duplicate(at) => &self.entries.duplicate_tokens[at],
+        }
+    }
+
+    /// Record with either ISIN equal to `isin`, resolved like [`get_by_code`](#method.get_by_code)
+    pub fn get_by_isin(&self, isin: &str) -> Option<&NavRecord> {
+        self.try_get_by_isin(isin).unwrap_or(None)
+    }
+
+    /// Record with either ISIN equal to `isin`, resolved like
+    /// [`try_get_by_code`](#method.try_get_by_code)
+    ///
+    /// A record hidden by the policy is not found by its ISINs, nor is the record kept in its
+    /// place by the hidden one's.
+    pub fn try_get_by_isin(&self, isin: &str) -> crate::Result<Option<&NavRecord>> {
+        let record = match self.entries.isins.get(isin) {
+            Some(&code) => self.try_get_by_code(code)?,
+            None => None,
+        };
+        Ok(record.filter(|record| {
+            record.isin.as_ref().map(|own| own.as_str()) == Some(isin)
+                || record.isin_dr.as_ref().map(|own| own.as_str()) == Some(isin)
+        }))
+    }
+
+    /// Number of distinct scheme codes
+    pub fn len(&self) -> usize {
+        self.entries.records.len()
+    }
+
+    /// Whether the index has no records
+    pub fn is_empty(&self) -> bool {
+        self.entries.records.is_empty()
+    }
+
+    /// Records in scheme code order, resolved like [`get_by_code`](#method.get_by_code)
+    pub fn iter(&self) -> NavIndexIter<'_> {
+        NavIndexIter {
+            index: self,
+            codes: self.entries.records.keys(),
+        }
+    }
+
+    /// Finds up to `limit` records whose name matches every token of `query`
+    ///
+    /// Query and names are split into lowercase alphanumeric tokens. Each query token must
+    /// match some name token exactly, as a prefix or as a substring, in any order, so
+    /// `"axis bluechip dir growth"` finds `Axis Bluechip Fund - Direct Plan - Growth`. Hits are
+    /// ordered by descending [`score`](struct.SearchHit.html#structfield.score), then shorter
+    /// names, then scheme code. An empty query matches nothing.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let index: amfi::NavIndex = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .filter_map(Result::ok)
+    ///     .collect();
+    /// let hits = index.search_name("bluechip axis dir growth", 5);
+    /// assert_eq!(hits[0].record.code, 120_465);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_name(&self, query: &str, limit: usize) -> Vec<SearchHit<'_>> {
+        let query = tokenize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: Vec<(SearchHit, usize)> = self
+            .entries
+            .tokens
+            .keys()
+            .filter_map(|&code| {
+                let kept = self.kept(code).ok()??;
+                let tokens = self.tokens(code, kept);
+                let score = query.iter().try_fold(0, |score, query_token| {
+                    tokens
+                        .iter()
+                        .map(|token| token_score(query_token, token))
+                        .max()
+                        .filter(|&best| best > 0)
+                        .map(|best| score + best)
+                })?;
+                let record = self.record(code, kept);
+                Some((SearchHit { record, score }, tokens.len()))
+            })
+            .collect();
+        hits.sort_by(|(a, a_len), (b, b_len)| {
+            b.score
+                .cmp(&a.score)
+                .then(a_len.cmp(b_len))
+                .then(a.record.code.cmp(&b.record.code))
+        });
+        hits.into_iter().take(limit).map(|(hit, _)| hit).collect()
+    }
+
+    /// Records whose scheme code was already indexed, in insertion order
+    pub fn duplicates(&self) -> &[DuplicateEntry] {
+        &self.entries.duplicates
+    }
+
+    /// Writes the records lookups return to `path` as a snapshot file
+    ///
+    /// The file is the one [`NavSnapshot::save`](struct.NavSnapshot.html#method.save) writes,
+    /// of a snapshot held in memory and dated now. Line numbers, duplicates and the policy are
+    /// not saved.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let index: amfi::NavIndex = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .filter_map(Result::ok)
+    ///     .collect();
+    /// let path = std::env::temp_dir().join("amfi-doc.index");
+    /// index.save(&path)?;
+    /// let loaded = amfi::NavIndex::load(&path)?;
+    /// assert!(loaded.iter().eq(index.iter()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "bincode")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let snapshot: crate::NavSnapshot = self.iter().cloned().map(Ok).collect();
+        snapshot.save(path)
+    }
+
+    /// Indexes the records of a snapshot file, written by [`save`](#method.save) or
+    /// [`NavSnapshot::save`](struct.NavSnapshot.html#method.save)
+    #[cfg(feature = "bincode")]
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        Ok(crate::NavSnapshot::load(path)?
+            .records
+            .into_iter()
+            .collect())
+    }
+}
+
+impl Entries {
+    fn insert_at(&mut self, record: NavRecord, line: Option<usize>) -> bool {
+        if let Some(first) = self.records.get(&record.code) {
+            self.duplicated
+                .entry(record.code)
+                .or_default()
+                .push(self.duplicates.len());
+            self.duplicate_tokens.push(tokenize(&record.name));
+            self.duplicates.push(DuplicateEntry {
+                first: first.clone(),
+                first_line: self.lines.get(&record.code).cloned(),
+                duplicate: record,
+                line,
+            });
+            let duplicate = self.duplicates.len() - 1;
+            add_isins(&mut self.isins, &self.duplicates[duplicate].duplicate);
+            return false;
+        }
+        if let Some(line) = line {
+            self.lines.insert(record.code, line);
+        }
+        add_isins(&mut self.isins, &record);
+        self.tokens.insert(record.code, tokenize(&record.name));
+        self.records.insert(record.code, record);
+        true
+    }
+
+    /// Puts `record` in place of the one indexed with its code
+    fn replace(&mut self, record: NavRecord) {
+        let code = record.code;
+        let old = self.records.remove(&code);
+        let later = self.duplicated.remove(&code).unwrap_or_default();
+        let duplicates = &self.duplicates;
+        let replaced = old
+            .iter()
+            .chain(later.iter().map(|&at| &duplicates[at].duplicate));
+        for isin in replaced.flat_map(|old| old.isin.iter().chain(old.isin_dr.iter())) {
+            if self.isins.get(isin.as_str()) == Some(&code) {
+                self.isins.remove(isin.as_str());
+            }
+        }
+        self.lines.remove(&code);
+        if !later.is_empty() {
+            self.duplicates.retain(|entry| entry.duplicate.code != code);
+            self.index_duplicates();
+        }
+        add_isins(&mut self.isins, &record);
+        self.tokens.insert(code, tokenize(&record.name));
+        self.records.insert(code, record);
+    }
+
+    /// Rebuilds `duplicated` and `duplicate_tokens` from `duplicates`, and indexes their ISINs
+    fn index_duplicates(&mut self) {
+        self.duplicated.clear();
+        self.duplicate_tokens.clear();
+        for (at, entry) in self.duplicates.iter().enumerate() {
+            self.duplicated
+                .entry(entry.duplicate.code)
+                .or_default()
+                .push(at);
+            self.duplicate_tokens.push(tokenize(&entry.duplicate.name));
+        }
+        for entry in &self.duplicates {
+            add_isins(&mut self.isins, &entry.duplicate);
+        }
+    }
+}
+
+fn add_isins(isins: &mut HashMap<String, SchemeCode>, record: &NavRecord) {
+    for isin in record.isin.iter().chain(record.isin_dr.iter()) {
+        isins
+            .entry(isin.as_str().to_string())
+            .or_insert(record.code);
+    }
+}
+
+fn tokenize(name: &str) -> Vec<String> {
+    name.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn token_score(query: &str, token: &str) -> u32 {
+    if token == query {
+        3
+    } else if token.starts_with(query) {
+        2
+    } else if token.contains(query) {
+        1
+    } else {
+        0
+    }
+}
+
+impl FromIterator<NavRecord> for NavIndex {
+    fn from_iter<I: IntoIterator<Item = NavRecord>>(iter: I) -> Self {
+        let mut index = NavIndex::new();
+        index.extend(iter);
+        index
+    }
+}
+
+impl Extend<NavRecord> for NavIndex {
+    fn extend<I: IntoIterator<Item = NavRecord>>(&mut self, iter: I) {
+        for record in iter {
+            self.insert(record);
+        }
+    }
+}
+
+/// Iterator over [`NavIndex`](struct.NavIndex.html) records in scheme code order
+pub struct NavIndexIter<'a> {
+    index: &'a NavIndex,
+    codes: btree_map::Keys<'a, SchemeCode, NavRecord>,
+}
+
+impl<'a> Iterator for NavIndexIter<'a> {
+    type Item = &'a NavRecord;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index;
+        self.codes.find_map(|&code| index.get_by_code(code))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.codes.size_hint().1)
+    }
+}
+
+impl<'a> IntoIterator for &'a NavIndex {
+    type Item = &'a NavRecord;
+    type IntoIter = NavIndexIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(rename = "NavIndex")]
+struct IndexRef<'a> {
+    records: Vec<&'a NavRecord>,
+    lines: &'a BTreeMap<SchemeCode, usize>,
+    duplicates: &'a [DuplicateEntry],
+    policy: DuplicatePolicy,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "NavIndex")]
+struct Index {
+    records: Vec<NavRecord>,
+    lines: BTreeMap<SchemeCode, usize>,
+    duplicates: Vec<DuplicateEntry>,
+    policy: DuplicatePolicy,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for NavIndex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        IndexRef {
+            records: self.entries.records.values().collect(),
+            lines: &self.entries.lines,
+            duplicates: &self.entries.duplicates,
+            policy: self.policy,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for NavIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Index {
+            records,
+            lines,
+            duplicates,
+            policy,
+        } = Index::deserialize(deserializer)?;
+        let mut index = NavIndex::with_policy(policy);
+        for record in records {
+            let line = lines.get(&record.code).cloned();
+            index.insert_at(record, line);
+        }
+        let entries = Arc::make_mut(&mut index.entries);
+        entries.duplicates = duplicates;
+        entries.index_duplicates();
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_path, fixture_records};
+    use crate::NavDate;
+
+    #[test]
+    fn lookup_by_code_and_isin() {
+        let index: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        assert_eq!(index.len(), 30);
+        assert!(index.duplicates().is_empty());
+
+        let record = index.get_by_code(120_466).unwrap();
+        assert_eq!(index.get_by_isin("INF846K01AC2"), Some(record));
+        assert_eq!(index.get_by_isin("INF846K01AD0"), Some(record));
+        assert_eq!(index.get_by_isin("-"), None);
+        assert_eq!(index.get_by_code(1), None);
+
+        let codes: Vec<u32> = index.iter().map(|record| record.code.get()).collect();
+        let mut sorted = codes.clone();
+        sorted.sort();
+        assert_eq!(codes, sorted);
+    }
+
+    #[test]
+    fn duplicates_are_kept_aside() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut second = records[0].clone();
+        second.nav = 1.0;
+        let index: NavIndex = records
+            .iter()
+            .cloned()
+            .chain(Some(second.clone()))
+            .collect();
+        assert_eq!(index.len(), 30);
+        assert_eq!(index.get_by_code(records[0].code), Some(&records[0]));
+        assert_eq!(index.duplicates().len(), 1);
+        assert_eq!(index.duplicates()[0].first, records[0]);
+        assert_eq!(index.duplicates()[0].duplicate, second);
+        assert_eq!(index.duplicates()[0].line, None);
+    }
+
+    #[test]
+    fn duplicated_feed_line() {
+        let path = fixture_path("NAVOpen.txt");
+        let mut feed = std::fs::read_to_string(&path).unwrap();
+        let original = feed
+            .lines()
+            .position(|line| line.starts_with("120465;"))
+            .unwrap()
+            + 1;
+        feed.push_str("HDFC Mutual Fund\r\n\r\n");
+        feed.push_str("120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;62.0000;21-Jun-2024\r\n");
+        let duplicate = feed.lines().count();
+
+        let mut index = NavIndex::new();
+        let errors = index.add_feed(NavRecordIterator::new(feed.as_bytes()));
+        assert!(errors.is_empty());
+        assert_eq!(index.len(), 30);
+        let entry = &index.duplicates()[0];
+        assert_eq!(
+            (entry.first_line, entry.line),
+            (Some(original), Some(duplicate))
+        );
+        assert_eq!(entry.first.amc, "Axis Mutual Fund");
+        assert_eq!(entry.duplicate.amc, "HDFC Mutual Fund");
+
+        assert_eq!(index.get_by_code(120_465).unwrap().nav, 61.43);
+        index.set_policy(DuplicatePolicy::KeepLast);
+        assert_eq!(index.get_by_code(120_465).unwrap().nav, 62.0);
+        assert_eq!(index.get_by_isin("INF846K01AB4").unwrap().nav, 62.0);
+        index.set_policy(DuplicatePolicy::Error);
+        assert_eq!(index.get_by_code(120_465), None);
+        assert_eq!(index.get_by_isin("INF846K01AB4"), None);
+        assert_eq!(index.iter().count(), 29);
+        match index.try_get_by_isin("INF846K01AB4") {
+            Err(Error::DuplicateCode { code, count }) => {
+                assert_eq!(code, 120_465);
+                assert_eq!(count, 2);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(index.try_get_by_code(1).unwrap(), None);
+        assert!(index.try_get_by_code(120_466).unwrap().is_some());
+    }
+
+    #[test]
+    fn duplicate_with_other_isin_and_name() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut duplicate = records[0].clone().with_nav(62.0);
+        duplicate.isin = Some("INF846K01ZZ9".into());
+        duplicate.name = "Axis Large Cap Fund - Direct Plan - Growth".to_string();
+        let mut index: NavIndex = records
+            .iter()
+            .cloned()
+            .chain(Some(duplicate.clone()))
+            .collect();
+
+        assert_eq!(index.get_by_isin("INF846K01AB4"), Some(&records[0]));
+        assert_eq!(index.get_by_isin("INF846K01ZZ9"), None);
+        assert!(index.search_name("axis large cap direct", 5).is_empty());
+
+        index.set_policy(DuplicatePolicy::KeepLast);
+        assert_eq!(index.get_by_isin("INF846K01AB4"), None);
+        assert_eq!(index.get_by_isin("INF846K01ZZ9"), Some(&duplicate));
+        assert_eq!(
+            index.search_name("axis large cap direct", 5)[0].record,
+            &duplicate
+        );
+        assert!(index
+            .search_name("axis bluechip direct", 5)
+            .iter()
+            .all(|hit| hit.record.code != duplicate.code));
+
+        assert_eq!(index.apply(Some(records[0].clone())).updated, 1);
+        assert_eq!(index.get_by_isin("INF846K01AB4"), Some(&records[0]));
+        assert_eq!(index.get_by_isin("INF846K01ZZ9"), None);
+    }
+
+    #[test]
+    fn search_abbreviated_and_reordered() {
+        let index: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        let hits = index.search_name("axis bluechip dir growth", 3);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].record.code, 120_465);
+        assert_eq!(hits[0].score, 3 + 3 + 2 + 3);
+
+        let hits = index.search_name("GROWTH Dir bluech", 10);
+        let codes: Vec<u32> = hits.iter().map(|hit| hit.record.code.get()).collect();
+        assert_eq!(codes, [120_465, 120_586]);
+
+        let hits = index.search_name("liquid", 10);
+        assert_eq!(hits.len(), 3);
+        assert!(hits.iter().all(|hit| hit.score == 3));
+        assert_eq!(index.search_name("liquid", 1).len(), 1);
+    }
+
+    #[test]
+    fn search_ranks_exact_over_prefix_over_substring() {
+        let index: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        let hits = index.search_name("cap", 30);
+        assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert_eq!(index.search_name("flexi", 1)[0].score, 3);
+        assert_eq!(index.search_name("flex", 1)[0].score, 2);
+        assert_eq!(index.search_name("lexi", 1)[0].score, 1);
+    }
+
+    #[test]
+    fn search_without_match() {
+        let index: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        assert!(index.search_name("axis quantum", 10).is_empty());
+        assert!(index.search_name(" - ", 10).is_empty());
+    }
+
+    #[test]
+    fn apply_upserts_by_date() {
+        let mut index: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        let reader = index.clone();
+        let today = index.get_by_code(120_466).unwrap().clone();
+        let mut renamed = today.clone().with_nav(22.0);
+        renamed.name = "Axis Bluechip Fund - Direct Plan - Income Distribution".to_string();
+        renamed.isin_dr = None;
+        let older = index
+            .get_by_code(120_465)
+            .unwrap()
+            .clone()
+            .with_date(NavDate::from_ymd(2024, 6, 20).unwrap())
+            .with_nav(1.0);
+        let new = NavRecord::fixture().with_code(1);
+
+        let summary = index.apply(vec![renamed.clone(), older, new.clone()]);
+        assert_eq!(
+            summary,
+            ApplySummary {
+                inserted: 1,
+                updated: 1,
+                ignored: 1,
+            }
+        );
+        assert_eq!(index.len(), 31);
+        assert_eq!(index.get_by_code(120_466), Some(&renamed));
+        assert_eq!(index.get_by_isin("INF846K01AC2"), Some(&renamed));
+        assert_eq!(index.get_by_isin("INF846K01AD0"), None);
+        assert_eq!(index.get_by_code(120_465).unwrap().nav, 61.43);
+        assert_eq!(
+            index.search_name("income distribution", 5)[0].record,
+            &renamed
+        );
+        assert!(index.search_name("axis direct idcw", 5).is_empty());
+        assert_eq!(index.get_by_code(1), Some(&new));
+
+        // The clone taken before is untouched
+        assert_eq!(reader.len(), 30);
+        assert_eq!(reader.get_by_code(120_466), Some(&today));
+        assert_eq!(reader.get_by_isin("INF846K01AD0"), Some(&today));
+    }
+
+    #[test]
+    fn apply_drops_duplicates_of_replaced_codes() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut index = NavIndex::with_policy(DuplicatePolicy::Error);
+        index.extend(records.iter().cloned().chain(Some(records[0].clone())));
+        assert_eq!(index.get_by_code(records[0].code), None);
+        let corrected = records[0].clone().with_nav(1.0);
+        assert_eq!(index.apply(Some(corrected.clone())).updated, 1);
+        assert!(index.duplicates().is_empty());
+        assert_eq!(index.get_by_code(records[0].code), Some(&corrected));
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn save_and_load() {
+        let mut index: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        index.apply(Some(NavRecord::fixture().with_code(1)));
+        let path = std::env::temp_dir().join(format!("amfi-index-{}", std::process::id()));
+        index.save(&path).unwrap();
+        let loaded = NavIndex::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), 31);
+        assert!(loaded.iter().eq(index.iter()));
+        assert_eq!(
+            loaded.get_by_isin("INF846K01AD0"),
+            index.get_by_isin("INF846K01AD0")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut index: NavIndex = fixture_records("NAVOpen.txt").into_iter().collect();
+        let duplicate = index.iter().next().unwrap().clone();
+        index.insert(duplicate);
+        let json = serde_json::to_string(&index).unwrap();
+        let back: NavIndex = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, index);
+    }
+}
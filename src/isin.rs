@@ -0,0 +1,407 @@
+//! International Securities Identification Number (ISIN)
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::fmt;
+use std::str::FromStr;
+
+/// Validated ISIN
+///
+/// Twelve characters: two letter country code, nine alphanumeric characters and an
+/// ISO 6166 (Luhn) check digit. Serializes as a plain string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Isin(String);
+
+/// Reason an ISIN failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsinError {
+    /// Not exactly 12 characters long
+    Length(usize),
+    /// Country code is not two upper case letters
+    Country(String),
+    /// Character outside `A-Z`/`0-9` (or a non-digit check character) at 0-based position
+    Character(usize, char),
+    /// Check digit does not match
+    CheckDigit {
+        /// Check digit computed from the first eleven characters
+        expected: char,
+        /// Check digit present in the input
+        found: char,
+    },
+}
+
+impl fmt::Display for IsinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IsinError::Length(len) => write!(f, "expected 12 characters, found {}", len),
+            IsinError::Country(ref cc) => write!(f, "invalid country code `{}`", cc),
+            IsinError::Character(pos, ch) => {
+                write!(f, "invalid character `{}` at position {}", ch, pos)
+            }
+            IsinError::CheckDigit { expected, found } => write!(
+                f,
+                "check digit mismatch: expected `{}`, found `{}`",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IsinError {}
+
+impl Isin {
+    /// Explains what is wrong with `isin`, if anything
+    pub fn validate(isin: &str) -> Result<(), IsinError> {
+        // Validated for every record of a feed, so kept free of allocations
+        let len = if isin.is_ascii() {
+            isin.len()
+        } else {
+            isin.chars().count()
+        };
+        if len != 12 {
+            return Err(IsinError::Length(len));
+        }
+        let mut chars = ['\0'; 12];
+        for (slot, ch) in chars.iter_mut().zip(isin.chars()) {
+            *slot = ch;
+        }
+        if !chars[..2].iter().all(|ch| ch.is_ascii_uppercase()) {
+            return Err(IsinError::Country(chars[..2].iter().collect()));
+        }
+        for (pos, &ch) in chars.iter().enumerate().skip(2) {
+            let valid = if pos == 11 {
+                ch.is_ascii_digit()
+            } else {
+                ch.is_ascii_digit() || ch.is_ascii_uppercase()
+            };
+            if !valid {
+                return Err(IsinError::Character(pos, ch));
+            }
+        }
+        let expected = check_digit(&chars[..11]);
+        if expected != chars[11] {
+            return Err(IsinError::CheckDigit {
+                expected,
+                found: chars[11],
+            });
+        }
+        Ok(())
+    }
+
+    /// Two letter country code, e.g. `IN`
+    pub fn country(&self) -> &str {
+        &self.0[..2]
+    }
+
+    /// Whether the ISIN is of an Indian mutual fund, i.e. starts with `INF`
+    ///
+    /// Every mutual fund ISIN of the feed should; others usually come from columns shifted
+    /// upstream.
+    pub fn is_indian_mf(&self) -> bool {
+        is_indian_mf(&self.0)
+    }
+
+    /// ISIN as string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn is_indian_mf(isin: &str) -> bool {
+    isin.starts_with("INF")
+}
+
+/// ISO 6166 check digit: letters expand to two digits (A = 10), then Luhn
+fn check_digit(body: &[char]) -> char {
+    // Digits of the expansion from the right, e.g. `A` gives 0 then 1, every other one doubled
+    let (mut sum, mut pos) = (0, 0);
+    let mut add = |digit: u32| {
+        sum += match (pos % 2, digit * 2) {
+            (0, doubled) if doubled > 9 => doubled - 9,
+            (0, doubled) => doubled,
+            _ => digit,
+        };
+        pos += 1;
+    };
+    for ch in body.iter().rev() {
+        let value = ch.to_digit(36).unwrap_or(0);
+        add(value % 10);
+        if value > 9 {
+            add(value / 10);
+        }
+    }
+    std::char::from_digit((10 - sum % 10) % 10, 10).unwrap_or('0')
+}
+
+impl FromStr for Isin {
+    type Err = IsinError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Isin::validate(s).map(|_| Isin(s.to_string()))
+    }
+}
+
+impl fmt::Display for Isin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Isin {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Isin {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+/// ISIN as published in the feed
+///
+/// Values failing validation are kept as `Invalid` so they can be reported instead of being
+/// silently dropped. Serializes as a plain string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RawIsin {
+    /// Valid ISIN
+    Valid(Isin),
+    /// Raw text which failed validation
+    Invalid(String),
+}
+
+impl RawIsin {
+    /// Validates `raw`, keeping it as `Invalid` on failure
+    pub fn new<S: AsRef<str>>(raw: S) -> Self {
+        let raw = raw.as_ref();
+        match raw.parse() {
+            Ok(isin) => RawIsin::Valid(isin),
+            Err(_) => RawIsin::Invalid(raw.to_string()),
+        }
+    }
+
+    /// Rebuilds an ISIN validated before it was stored, skipping the check digit
+    #[cfg(feature = "bincode")]
+    pub(crate) fn trusted(raw: String, valid: bool) -> Self {
+        if valid {
+            RawIsin::Valid(Isin(raw))
+        } else {
+            RawIsin::Invalid(raw)
+        }
+    }
+
+    /// Raw text
+    pub fn as_str(&self) -> &str {
+        match *self {
+            RawIsin::Valid(ref isin) => isin.as_str(),
+            RawIsin::Invalid(ref raw) => raw,
+        }
+    }
+
+    /// Validated ISIN, if valid
+    pub fn valid(&self) -> Option<&Isin> {
+        match *self {
+            RawIsin::Valid(ref isin) => Some(isin),
+            RawIsin::Invalid(..) => None,
+        }
+    }
+
+    /// Whether the ISIN passed validation
+    pub fn is_valid(&self) -> bool {
+        self.valid().is_some()
+    }
+
+    /// Explains what is wrong with the ISIN, if anything
+    pub fn validate(&self) -> Result<(), IsinError> {
+        Isin::validate(self.as_str())
+    }
+
+    /// Whether the text starts with `INF`, valid or not, see
+    /// [`Isin::is_indian_mf`](struct.Isin.html#method.is_indian_mf)
+    pub fn is_indian_mf(&self) -> bool {
+        is_indian_mf(self.as_str())
+    }
+}
+
+/// Handling of ISINs failing validation or not of an Indian mutual fund, see
+/// [`Isin::is_indian_mf`](struct.Isin.html#method.is_indian_mf)
+///
+/// Set for parsing with
+/// [`NavRecordIterator::isin_strictness`](struct.NavRecordIterator.html#method.isin_strictness)
+/// and for validation in [`ValidationRules`](struct.ValidationRules.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsinStrictness {
+    /// Keep ISINs as published
+    #[default]
+    Lenient,
+    /// Keep ISINs as published, validation reports those not starting with `INF`
+    Warn,
+    /// Parse invalid ISINs and those not starting with `INF` as `None`; validation reports
+    /// the latter
+    Strict,
+}
+
+impl IsinStrictness {
+    /// Whether a parsed `isin` is kept
+    pub(crate) fn keeps(self, isin: &str) -> bool {
+        self != IsinStrictness::Strict || (Isin::validate(isin).is_ok() && is_indian_mf(isin))
+    }
+}
+
+impl From<Isin> for RawIsin {
+    fn from(isin: Isin) -> Self {
+        RawIsin::Valid(isin)
+    }
+}
+
+impl<'a> From<&'a str> for RawIsin {
+    fn from(raw: &'a str) -> Self {
+        RawIsin::new(raw)
+    }
+}
+
+impl fmt::Display for RawIsin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Isin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Isin {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for RawIsin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for RawIsin {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(RawIsin::new)
+    }
+}
+
+/// Twelve letters and digits, as serialized
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Isin {
+    fn schema_name() -> String {
+        "Isin".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            string: Some(Box::new(schemars::schema::StringValidation {
+                pattern: Some("^[A-Z]{2}[A-Z0-9]{9}[0-9]$".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Any string, as invalid ISINs are kept
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for RawIsin {
+    fn schema_name() -> String {
+        "RawIsin".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_isins() {
+        for raw in &[
+            "INF846K01DP8",
+            "INF209K01YN0",
+            "US0378331005",
+            "INE009A01021",
+        ] {
+            assert_eq!(Isin::validate(raw), Ok(()), "{}", raw);
+        }
+        let isin: Isin = "INF179K01BE2".parse().unwrap();
+        assert_eq!(isin.country(), "IN");
+        assert_eq!(isin.to_string(), "INF179K01BE2");
+    }
+
+    #[test]
+    fn invalid_isins() {
+        assert_eq!(Isin::validate("INF846K01DP"), Err(IsinError::Length(11)));
+        assert_eq!(
+            Isin::validate("1NF846K01DP8"),
+            Err(IsinError::Country("1N".to_string()))
+        );
+        assert_eq!(
+            Isin::validate("INF846K0-DP8"),
+            Err(IsinError::Character(8, '-'))
+        );
+        assert_eq!(
+            Isin::validate("INF846K01DP9"),
+            Err(IsinError::CheckDigit {
+                expected: '8',
+                found: '9'
+            })
+        );
+    }
+
+    #[test]
+    fn raw_isin_keeps_invalid_values() {
+        assert!(RawIsin::new("INF846K01DP8").is_valid());
+        let raw = RawIsin::new("INF846K01DPX");
+        assert_eq!(raw, RawIsin::Invalid("INF846K01DPX".to_string()));
+        assert_eq!(raw.as_str(), "INF846K01DPX");
+        assert_eq!(raw.validate(), Err(IsinError::Character(11, 'X')));
+    }
+
+    #[test]
+    fn strictness() {
+        let share: Isin = "INE009A01021".parse().unwrap();
+        assert!(!share.is_indian_mf());
+        assert!(RawIsin::new("INF846K01DPX").is_indian_mf());
+        let cases = [
+            (RawIsin::new("INF846K01DP8"), [true, true, true]),
+            (RawIsin::new("INF846K01DPX"), [true, true, false]),
+            (RawIsin::from(share), [true, true, false]),
+        ];
+        for (isin, kept) in cases.iter() {
+            let strictness = [
+                IsinStrictness::Lenient,
+                IsinStrictness::Warn,
+                IsinStrictness::Strict,
+            ];
+            for (&strictness, &kept) in strictness.iter().zip(kept) {
+                assert_eq!(
+                    strictness.keeps(isin.as_str()),
+                    kept,
+                    "{:?} {:?}",
+                    strictness,
+                    isin
+                );
+            }
+        }
+    }
+}
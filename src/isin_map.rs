@@ -0,0 +1,283 @@
+//! Records keyed by ISIN
+
+use crate::payout::split_plan;
+use crate::{Isin, NavRecord};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Records keyed by ISIN, see [`isin_map`](fn.isin_map.html)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IsinMap {
+    /// Records by valid ISIN; `isin` and `isin_dr` of a record share one `Arc`
+    pub by_isin: HashMap<Isin, Arc<NavRecord>>,
+    /// Records with neither a valid `isin` nor `isin_dr`, in input order
+    pub without_isin: Vec<Arc<NavRecord>>,
+    /// ISINs found on records of different scheme codes, in input order
+    pub conflicts: Vec<IsinConflict>,
+}
+
+/// An ISIN already mapped to a record of another scheme code
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsinConflict {
+    /// Shared ISIN
+    pub isin: Isin,
+    /// Record the ISIN maps to, the first seen
+    pub kept: Arc<NavRecord>,
+    /// Later record of another scheme code with the same ISIN, not mapped by it
+    pub conflicting: Arc<NavRecord>,
+}
+
+/// Maps both ISINs of every record to one shared copy of it
+///
+/// A scheme is published under its payout or growth ISIN and, for some plans, its
+/// reinvestment ISIN: both keys resolve to the same `Arc`, so no record is cloned. Invalid
+/// ISINs are not keys. An ISIN seen again for the same scheme code keeps the first record; for
+/// another scheme code it is reported in `conflicts` rather than overwritten.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?.filter_map(Result::ok);
+/// let map = amfi::isin_map(records);
+/// let payout = &map.by_isin["INF846K01AC2"];
+/// let reinvestment = &map.by_isin["INF846K01AD0"];
+/// assert!(std::sync::Arc::ptr_eq(payout, reinvestment));
+/// assert!(map.conflicts.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub fn isin_map<I>(records: I) -> IsinMap
+where
+    I: IntoIterator<Item = NavRecord>,
+{
+    let mut map = IsinMap::default();
+    for record in records {
+        let record = Arc::new(record);
+        let isins = record.isin.iter().chain(record.isin_dr.iter());
+        let mut keyed = false;
+        for isin in isins.filter_map(|isin| isin.valid()) {
+            keyed = true;
+            match map.by_isin.get(isin) {
+                Some(kept) if kept.code != record.code => map.conflicts.push(IsinConflict {
+                    isin: isin.clone(),
+                    kept: kept.clone(),
+                    conflicting: record.clone(),
+                }),
+                Some(_) => {}
+                None => {
+                    map.by_isin.insert(isin.clone(), record.clone());
+                }
+            }
+        }
+        if !keyed {
+            map.without_isin.push(record);
+        }
+    }
+    map
+}
+
+/// ISIN claimed by records of several scheme codes, see
+/// [`isin_conflicts`](fn.isin_conflicts.html)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DuplicateIsin {
+    /// Shared ISIN
+    pub isin: Isin,
+    /// First record of each scheme code with the ISIN, in input order
+    pub records: Vec<NavRecord>,
+    /// What the names of the records suggest
+    pub kind: DuplicateIsinKind,
+}
+
+/// Likely cause of a [`DuplicateIsin`](struct.DuplicateIsin.html), told by the names of the
+/// records sharing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum DuplicateIsinKind {
+    /// Names differ only in their plan tokens, e.g. `Direct` and `Regular`: plans of one
+    /// scheme, one of them given the ISIN of the other upstream
+    LikelyPlanMismatch,
+    /// Names differ otherwise, or not at all
+    Distinct,
+}
+
+/// ISINs of records of more than one scheme code, in order of the first record with each
+///
+/// An ISIN identifies one plan and option of a scheme, so one on two scheme codes is a data
+/// error, most often a direct and a regular plan mapped to the same ISIN upstream. Both
+/// `isin` and `isin_dr` of records count, invalid ISINs don't. Records of a code seen again,
+/// e.g. of other dates in a history, are listed once.
+///
+/// Takes the records of a [`NavSnapshot`](struct.NavSnapshot.html), or any slice of them.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::DuplicateIsinKind;
+///
+/// let records: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?.collect::<Result<_, _>>()?;
+/// for conflict in amfi::isin_conflicts(&records) {
+///     if conflict.kind == DuplicateIsinKind::LikelyPlanMismatch {
+///         println!("{} on {} plans", conflict.isin, conflict.records.len());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn isin_conflicts(records: &[NavRecord]) -> Vec<DuplicateIsin> {
+    let mut claims: HashMap<&Isin, Vec<&NavRecord>> = HashMap::new();
+    let mut order = Vec::new();
+    for record in records {
+        let isins = record.isin.iter().chain(record.isin_dr.iter());
+        for isin in isins.filter_map(|isin| isin.valid()) {
+            let claim = claims.entry(isin).or_insert_with(|| {
+                order.push(isin);
+                Vec::new()
+            });
+            if claim.iter().all(|claimed| claimed.code != record.code) {
+                claim.push(record);
+            }
+        }
+    }
+    order
+        .into_iter()
+        .filter_map(|isin| {
+            let claim = &claims[isin];
+            if claim.len() < 2 {
+                return None;
+            }
+            Some(DuplicateIsin {
+                isin: isin.clone(),
+                records: claim.iter().map(|&record| record.clone()).collect(),
+                kind: DuplicateIsinKind::of(claim),
+            })
+        })
+        .collect()
+}
+
+impl DuplicateIsinKind {
+    /// Kind of an ISIN shared by `records`, from their names split by `split_plan`
+    fn of(records: &[&NavRecord]) -> Self {
+        let names: Vec<_> = records
+            .iter()
+            .map(|record| split_plan(&record.name))
+            .collect();
+        let (rest, plan) = &names[0];
+        let same_rest = names.iter().all(|(other, _)| other == rest);
+        let same_plan = names.iter().all(|(_, other)| other == plan);
+        if same_rest && !same_plan {
+            DuplicateIsinKind::LikelyPlanMismatch
+        } else {
+            DuplicateIsinKind::Distinct
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+
+    #[test]
+    fn both_isins_share_a_record() {
+        let records = fixture_records("NAVOpen.txt");
+        let both = records
+            .iter()
+            .filter(|record| record.isin.is_some() && record.isin_dr.is_some())
+            .count();
+        let map = isin_map(records.clone());
+        // One fixture record has no ISIN at all
+        assert_eq!(map.by_isin.len(), records.len() - 1 + both);
+        assert_eq!(map.without_isin.len(), 1);
+        assert_eq!(map.without_isin[0].code.get(), 149_003);
+        assert!(map.conflicts.is_empty());
+
+        let payout = &map.by_isin["INF846K01AC2"];
+        assert_eq!(payout.code.get(), 120_466);
+        assert!(Arc::ptr_eq(payout, &map.by_isin["INF846K01AD0"]));
+        // Its two keys hold the only references
+        assert_eq!(Arc::strong_count(payout), 2);
+    }
+
+    #[test]
+    fn conflicts_and_records_without_isin() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut other = records[1].clone();
+        other.code = 999_999.into();
+        let mut unlisted = records[0].clone();
+        unlisted.code = 999_998.into();
+        unlisted.isin = None;
+        unlisted.isin_dr = Some("garbage".into());
+        // Same scheme seen again is not a conflict
+        let again = records[1].clone();
+
+        let map = isin_map(records.iter().cloned().chain(vec![other, unlisted, again]));
+        assert_eq!(map.conflicts.len(), 2);
+        for (conflict, isin) in map.conflicts.iter().zip(&["INF846K01AC2", "INF846K01AD0"]) {
+            assert_eq!(conflict.isin.as_str(), *isin);
+            assert_eq!(conflict.kept.code.get(), 120_466);
+            assert_eq!(conflict.conflicting.code.get(), 999_999);
+            assert_eq!(map.by_isin[*isin].code.get(), 120_466);
+        }
+        let without: Vec<u32> = map.without_isin.iter().map(|r| r.code.get()).collect();
+        assert_eq!(without, [149_003, 999_998]);
+    }
+
+    #[test]
+    fn duplicate_isins_by_kind() {
+        let records = fixture_records("NAVOpen.txt");
+        let direct = records[0].clone();
+        assert_eq!(direct.name, "Axis Bluechip Fund - Direct Plan - Growth");
+        // The regular plan given the ISIN of the direct one, listed on two dates
+        let mut regular = direct.clone();
+        regular.code = 999_999.into();
+        regular.name = "Axis Bluechip Fund - Regular Plan - Growth".to_string();
+        let mut later = regular.clone();
+        later.date = crate::NavDate::from_ymd(2024, 6, 24).unwrap();
+        // Another fund with the reinvestment ISIN of the second record
+        let mut other = records[1].clone();
+        other.code = 999_998.into();
+        other.name = "Axis Midcap Fund - Direct Plan - IDCW".to_string();
+        other.isin = None;
+
+        let conflicts = isin_conflicts(&[
+            direct.clone(),
+            regular.clone(),
+            records[1].clone(),
+            other,
+            later,
+        ]);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].isin.as_str(), "INF846K01AB4");
+        assert_eq!(conflicts[0].records, [direct, regular]);
+        assert_eq!(conflicts[0].kind, DuplicateIsinKind::LikelyPlanMismatch);
+        assert_eq!(conflicts[1].isin.as_str(), "INF846K01AD0");
+        let codes: Vec<u32> = conflicts[1].records.iter().map(|r| r.code.get()).collect();
+        assert_eq!(codes, [120_466, 999_998]);
+        assert_eq!(conflicts[1].kind, DuplicateIsinKind::Distinct);
+
+        assert!(isin_conflicts(&records).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn duplicate_isins_serialize() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut regular = records[0].clone();
+        regular.code = 999_999.into();
+        regular.name = "Axis Bluechip Fund - Regular Plan - Growth".to_string();
+        let conflicts = isin_conflicts(&[records[0].clone(), regular]);
+        let json = serde_json::to_value(&conflicts).unwrap();
+        assert_eq!(json[0]["isin"], "INF846K01AB4");
+        assert_eq!(json[0]["kind"], "likely_plan_mismatch");
+        assert_eq!(json[0]["records"][1]["code"], 999_999);
+        let back: Vec<DuplicateIsin> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, conflicts);
+    }
+}
@@ -0,0 +1,327 @@
+//! Identity of a scheme across feeds
+
+use crate::payout::{payout, Payout};
+use crate::{FundPlan, Isin, NavRecord, SchemeCode};
+use std::collections::HashMap;
+
+/// Identity of a scheme for joining feeds of different days, see
+/// [`NavRecord::scheme_key`](struct.NavRecord.html#method.scheme_key)
+///
+/// Components are compared in turn, the first being the most reliable:
+///
+/// 1. `code`, the AMFI scheme code, which rarely changes;
+/// 2. `isin`, the growth/payout ISIN, or else the reinvestment ISIN, when valid;
+/// 3. `name`, written `<base name>|<plan>|<option>`. The base name is the scheme name up to
+///    its first ` - `, lowercased with runs of other than letters and digits replaced by one
+///    space. Plan is `direct`, `regular` or empty if unspecified, option `growth`, `idcw` or
+///    empty if unknown, e.g.
+///    `axis bluechip fund|direct|growth`. Names are only compared between keys that both
+///    have an ISIN or both lack one: a scheme without one, e.g. a segregated portfolio named
+///    after its fund, is not the fund.
+///
+/// This format is stable and only changes with a breaking release.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchemeKey {
+    /// Scheme code
+    pub code: SchemeCode,
+    /// Valid ISIN
+    pub isin: Option<Isin>,
+    /// Normalized base name, plan and option
+    pub name: String,
+}
+
+/// Component of a [`SchemeKey`](struct.SchemeKey.html) two records agree on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyKind {
+    /// Same scheme code
+    Code,
+    /// Same ISIN, different codes
+    Isin,
+    /// Same name key, different codes and ISINs, both or neither with an ISIN
+    Name,
+}
+
+const KINDS: [KeyKind; 3] = [KeyKind::Code, KeyKind::Isin, KeyKind::Name];
+
+/// Value of one key component
+#[derive(PartialEq, Eq, Hash)]
+enum Part<'a> {
+    Code(SchemeCode),
+    Isin(&'a Isin),
+    /// Name key, and whether the key has an ISIN
+    Name(&'a str, bool),
+}
+
+impl SchemeKey {
+    pub(crate) fn of(record: &NavRecord) -> Self {
+        let isin = record
+            .isin
+            .iter()
+            .chain(record.isin_dr.iter())
+            .find_map(|isin| isin.valid())
+            .cloned();
+        SchemeKey {
+            code: record.code,
+            isin,
+            name: name_key(record),
+        }
+    }
+
+    /// First component in cascade order both keys have and agree on, `None` if none does
+    pub fn matches(&self, other: &SchemeKey) -> Option<KeyKind> {
+        KINDS.iter().cloned().find(|&kind| {
+            let part = self.part(kind);
+            part.is_some() && part == other.part(kind)
+        })
+    }
+
+    fn part(&self, kind: KeyKind) -> Option<Part<'_>> {
+        match kind {
+            KeyKind::Code => Some(Part::Code(self.code)),
+            KeyKind::Isin => self.isin.as_ref().map(Part::Isin),
+            KeyKind::Name => Some(Part::Name(&self.name, self.isin.is_some())),
+        }
+    }
+}
+
+fn name_key(record: &NavRecord) -> String {
+    let base = match record.name.find(" - ") {
+        Some(pos) => &record.name[..pos],
+        None => &record.name,
+    };
+    let mut key = String::with_capacity(base.len() + 16);
+    for word in base
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !key.is_empty() {
+            key.push(' ');
+        }
+        key.extend(word.chars().flat_map(char::to_lowercase));
+    }
+    key.push('|');
+    key.push_str(match record.plan {
+        FundPlan::Direct => "direct",
+        FundPlan::Regular => "regular",
+        FundPlan::Unspecified => "",
+    });
+    key.push('|');
+    key.push_str(match payout(&record.name, record.option.as_deref()) {
+        Some(Payout::Growth) => "growth",
+        Some(Payout::Idcw) => "idcw",
+        None => "",
+    });
+    key
+}
+
+/// Aligns the records of two snapshots by their [`SchemeKey`](struct.SchemeKey.html)s
+///
+/// Records are matched by code first, the remaining ones by ISIN, then by name key. A
+/// component only matches when it is unique among the records still unmatched on both sides,
+/// so ambiguous names are left unmatched rather than guessed. Pairs come in `old` order, then
+/// records only in `new` in `new` order.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let old: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?.collect::<Result<_, _>>()?;
+/// let mut new = old.clone();
+/// new[0].code = 999_999.into();
+/// let pairs = amfi::match_records(&old, &new);
+/// assert_eq!(pairs.len(), old.len());
+/// let (before, after) = pairs[0];
+/// let kind = before.unwrap().scheme_key().matches(&after.unwrap().scheme_key());
+/// assert_eq!(kind, Some(amfi::KeyKind::Isin));
+/// # Ok(())
+/// # }
+/// ```
+pub fn match_records<'a>(
+    old: &'a [NavRecord],
+    new: &'a [NavRecord],
+) -> Vec<(Option<&'a NavRecord>, Option<&'a NavRecord>)> {
+    let old_keys: Vec<SchemeKey> = old.iter().map(SchemeKey::of).collect();
+    let new_keys: Vec<SchemeKey> = new.iter().map(SchemeKey::of).collect();
+    let mut partners: Vec<Option<usize>> = vec![None; old.len()];
+    let mut taken = vec![false; new.len()];
+    for &kind in &KINDS {
+        let unmatched_old = unique(&old_keys, kind, |i| partners[i].is_none());
+        let unmatched_new = unique(&new_keys, kind, |j| !taken[j]);
+        for (part, i) in unmatched_old {
+            if let (Some(i), Some(&Some(j))) = (i, unmatched_new.get(&part)) {
+                partners[i] = Some(j);
+                taken[j] = true;
+            }
+        }
+    }
+
+    let mut pairs: Vec<_> = old
+        .iter()
+        .zip(&partners)
+        .map(|(record, partner)| (Some(record), partner.map(|j| &new[j])))
+        .collect();
+    pairs.extend(
+        new.iter()
+            .zip(&taken)
+            .filter(|&(_, &taken)| !taken)
+            .map(|(record, _)| (None, Some(record))),
+    );
+    pairs
+}
+
+/// Index of each component value among the keys passing `include`, `None` when not unique
+fn unique<'a, F>(
+    keys: &'a [SchemeKey],
+    kind: KeyKind,
+    include: F,
+) -> HashMap<Part<'a>, Option<usize>>
+where
+    F: Fn(usize) -> bool,
+{
+    let mut parts = HashMap::new();
+    for (i, key) in keys.iter().enumerate().filter(|&(i, _)| include(i)) {
+        if let Some(part) = key.part(kind) {
+            parts
+                .entry(part)
+                .and_modify(|index| *index = None)
+                .or_insert(Some(i));
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+
+    fn kinds(pairs: &[(Option<&NavRecord>, Option<&NavRecord>)]) -> Vec<Option<KeyKind>> {
+        pairs
+            .iter()
+            .map(|pair| match *pair {
+                (Some(old), Some(new)) => old.scheme_key().matches(&new.scheme_key()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn key_components() {
+        let records = fixture_records("NAVOpen.txt");
+        let key = records[0].scheme_key();
+        assert_eq!(key.code.get(), 120_465);
+        assert_eq!(key.isin.as_ref().map(Isin::as_str), Some("INF846K01AB4"));
+        assert_eq!(key.name, "axis bluechip fund|direct|growth");
+        let banking = records.iter().find(|r| r.code.get() == 119_551).unwrap();
+        assert_eq!(
+            banking.scheme_key().name,
+            "aditya birla sun life banking psu debt fund|direct|idcw"
+        );
+        // Neither ISIN valid
+        let unlisted = records.iter().find(|r| r.code.get() == 149_003).unwrap();
+        assert_eq!(unlisted.scheme_key().isin, None);
+    }
+
+    #[test]
+    fn same_feed_matches_by_code() {
+        let records = fixture_records("NAVOpen.txt");
+        let pairs = match_records(&records, &records);
+        assert_eq!(pairs.len(), records.len());
+        assert!(kinds(&pairs)
+            .iter()
+            .all(|&kind| kind == Some(KeyKind::Code)));
+    }
+
+    #[test]
+    fn renamed_and_recoded_schemes() {
+        let old = fixture_records("NAVOpen.txt");
+        let mut new = old.clone();
+        // Renamed, same code
+        new[0].name = "Axis Large Cap Fund - Direct Plan - Growth".to_string();
+        // New code, same ISIN
+        new[1].code = 900_001.into();
+        // New code and ISIN, name reworded
+        new[2].code = 900_002.into();
+        new[2].isin = Some("INF846K01ZZ0".into());
+        new[2].name = "AXIS BLUECHIP FUND - REGULAR - GROWTH OPTION".to_string();
+        // New code and ISIN, unrelated name: removed and added
+        new[3].code = 900_003.into();
+        new[3].isin = Some("INF109K01ZZ3".into());
+        new[3].name = "ICICI Prudential New Fund - Direct Plan - Growth".to_string();
+        new.push(new[3].clone());
+        new.remove(3);
+
+        let pairs = match_records(&old, &new);
+        assert_eq!(pairs.len(), old.len() + 1);
+        let kinds = kinds(&pairs);
+        assert_eq!(
+            kinds[..4],
+            [
+                Some(KeyKind::Code),
+                Some(KeyKind::Isin),
+                Some(KeyKind::Name),
+                None
+            ]
+        );
+        assert_eq!(pairs[2].1.unwrap().code.get(), 900_002);
+        assert_eq!(pairs[3], (Some(&old[3]), None));
+        assert_eq!(pairs[old.len()], (None, Some(&new[new.len() - 1])));
+        assert!(kinds[4..old.len()]
+            .iter()
+            .all(|&kind| kind == Some(KeyKind::Code)));
+    }
+
+    #[test]
+    fn isin_less_schemes_match_by_name_among_themselves() {
+        let old = fixture_records("NAVOpen.txt");
+        let portfolio = old.iter().find(|r| r.code.get() == 149_003).unwrap();
+        assert!(!portfolio.has_isin());
+        // The fund the portfolio was segregated from, of the same name key
+        let fund = NavRecord {
+            code: 119_556.into(),
+            isin: Some("INF209K01UU3".into()),
+            name: "Aditya Birla Sun Life Credit Risk Fund - Direct Plan - Growth".to_string(),
+            ..portfolio.clone()
+        };
+        assert!(fund.has_isin());
+        assert_eq!(fund.scheme_key().name, portfolio.scheme_key().name);
+        assert_eq!(fund.scheme_key().matches(&portfolio.scheme_key()), None);
+
+        let mut new: Vec<NavRecord> = old
+            .iter()
+            .filter(|r| r.code != portfolio.code)
+            .cloned()
+            .collect();
+        new.push(fund);
+        let pairs = match_records(&old, &new);
+        assert!(pairs.contains(&(Some(portfolio), None)));
+
+        // Recoded, still without ISIN
+        let recoded = NavRecord {
+            code: 900_001.into(),
+            ..portfolio.clone()
+        };
+        new.push(recoded.clone());
+        let pairs = match_records(&old, &new);
+        assert!(pairs.contains(&(Some(portfolio), Some(&recoded))));
+        assert_eq!(
+            portfolio.scheme_key().matches(&recoded.scheme_key()),
+            Some(KeyKind::Name)
+        );
+    }
+
+    #[test]
+    fn ambiguous_names_stay_unmatched() {
+        let old = fixture_records("NAVOpen.txt");
+        let mut new = old.clone();
+        // Two schemes recoded without ISINs and with the same name key
+        for (i, code) in &[(0, 900_001), (2, 900_002)] {
+            new[*i].code = (*code).into();
+            new[*i].isin = None;
+            new[*i].name = "Axis Bluechip Fund - Direct Plan - Growth".to_string();
+            new[*i].plan = FundPlan::Direct;
+        }
+        let pairs = match_records(&old, &new);
+        assert_eq!(pairs[0].1, None);
+        assert_eq!(pairs[2].1, None);
+        assert_eq!(pairs.len(), old.len() + 2);
+    }
+}
@@ -0,0 +1,189 @@
+//! Latest record of each scheme across many feeds
+
+use crate::{Error, NavDate, NavRecord, Result, SchemeCode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Counts of a compaction to the latest record per scheme, see
+/// [`LatestNavCollector::stats`](struct.LatestNavCollector.html#method.stats)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CompactionStats {
+    /// Records ingested
+    pub records: usize,
+    /// Records dropped for one of the same scheme as new or newer, ties included
+    pub superseded: usize,
+    /// Records replaced by a later one of the same scheme and date
+    pub ties: usize,
+    /// Earliest NAV date ingested
+    pub first_date: Option<NavDate>,
+    /// Latest NAV date ingested
+    pub last_date: Option<NavDate>,
+}
+
+/// Latest record of each scheme among records fed from any number of feeds, keeping one
+/// record per scheme in memory
+///
+/// The record of a scheme with the newest NAV date wins; of records of the same date the one
+/// ingested last does, as when replaying daily files where a later file corrects an earlier
+/// one.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let mut latest = amfi::LatestNavCollector::new();
+/// for path in &["fixtures/NAVHistory.txt", "fixtures/NAVOpen.txt"] {
+///     for error in latest.ingest(amfi::nav_from_file(path)?) {
+///         eprintln!("{}: {}", path, error);
+///     }
+/// }
+/// let stats = latest.stats();
+/// assert_eq!(latest.len(), stats.records - stats.superseded);
+/// for record in latest.into_records() {
+///     println!("{} {} {}", record.code, record.date, record.nav);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LatestNavCollector {
+    latest: HashMap<SchemeCode, NavRecord>,
+    stats: CompactionStats,
+}
+
+impl LatestNavCollector {
+    /// Collector of no records
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps `record` if its scheme has none as new
+    pub fn push(&mut self, record: NavRecord) {
+        let stats = &mut self.stats;
+        stats.records += 1;
+        stats.first_date = Some(stats.first_date.map_or(record.date, |d| d.min(record.date)));
+        stats.last_date = Some(stats.last_date.map_or(record.date, |d| d.max(record.date)));
+        match self.latest.get_mut(&record.code) {
+            Some(kept) => {
+                stats.superseded += 1;
+                if record.date == kept.date {
+                    stats.ties += 1;
+                }
+                if record.date >= kept.date {
+                    *kept = record;
+                }
+            }
+            None => {
+                self.latest.insert(record.code, record);
+            }
+        }
+    }
+
+    /// Keeps the latest records of `results`, returning their errors
+    pub fn ingest<I>(&mut self, results: I) -> Vec<Error>
+    where
+        I: IntoIterator<Item = Result<NavRecord>>,
+    {
+        let mut errors = Vec::new();
+        for result in results {
+            match result {
+                Ok(record) => self.push(record),
+                Err(error) => errors.push(error),
+            }
+        }
+        errors
+    }
+
+    /// Counts of the records ingested so far
+    pub fn stats(&self) -> CompactionStats {
+        self.stats
+    }
+
+    /// Number of schemes
+    pub fn len(&self) -> usize {
+        self.latest.len()
+    }
+
+    /// Whether no record was ingested
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_empty()
+    }
+
+    /// Latest record of the scheme with `code`
+    pub fn get<C: Into<SchemeCode>>(&self, code: C) -> Option<&NavRecord> {
+        self.latest.get(&code.into())
+    }
+
+    /// Latest record of each scheme, by scheme code
+    pub fn into_records(self) -> Vec<NavRecord> {
+        let mut records: Vec<NavRecord> = self.latest.into_values().collect();
+        records.sort_by_key(|record| record.code);
+        records
+    }
+}
+
+impl Extend<NavRecord> for LatestNavCollector {
+    fn extend<I: IntoIterator<Item = NavRecord>>(&mut self, iter: I) {
+        for record in iter {
+            self.push(record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav_from_str;
+    use crate::testing::fixture_text;
+
+    /// Fixture feed dated `day` of June 2024
+    fn feed_on(day: u32) -> String {
+        fixture_text("NAVOpen.txt").replace("21-Jun-2024", &format!("{}-Jun-2024", day))
+    }
+
+    #[test]
+    fn newest_date_wins_whatever_the_order() {
+        let mut latest = LatestNavCollector::new();
+        for &day in &[19, 21, 20] {
+            assert!(latest.ingest(nav_from_str(&feed_on(day))).is_empty());
+        }
+        assert_eq!(
+            latest.stats(),
+            CompactionStats {
+                records: 90,
+                superseded: 60,
+                ties: 0,
+                first_date: NavDate::from_ymd(2024, 6, 19),
+                last_date: NavDate::from_ymd(2024, 6, 21),
+            }
+        );
+        assert_eq!(latest.len(), 30);
+        let records = latest.into_records();
+        assert!(records
+            .iter()
+            .all(|record| Some(record.date) == NavDate::from_ymd(2024, 6, 21)));
+        assert!(records.windows(2).all(|pair| pair[0].code < pair[1].code));
+    }
+
+    #[test]
+    fn ties_go_to_the_later() {
+        let mut latest = LatestNavCollector::new();
+        let feed = feed_on(21);
+        latest.ingest(nav_from_str(&feed));
+        let revised = nav_from_str(&feed).map(|result| {
+            result.map(|mut record| {
+                record.nav += 1.0;
+                record
+            })
+        });
+        latest.ingest(revised);
+        latest.extend(nav_from_str(&feed_on(20)).map(Result::unwrap));
+        let stats = latest.stats();
+        assert_eq!((stats.superseded, stats.ties), (60, 30));
+
+        let first = nav_from_str(&feed).next().unwrap().unwrap();
+        assert_eq!(latest.get(first.code).unwrap().nav, first.nav + 1.0);
+        assert_eq!(latest.get(1u32), None);
+    }
+}
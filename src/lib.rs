@@ -4,6 +4,8 @@
 //!
 //! This library can also parse data mirrors and local file copies.
 //! See [nav_from_url](fn.nav_from_url.html) and [nav_from_file](fn.nav_from_file.html).
+//! Feeds already in memory are parsed with [nav_from_reader](fn.nav_from_reader.html) and
+//! [nav_from_str](fn.nav_from_str.html).
 //!
 //! ## Basic Usage
 //!
@@ -18,79 +20,1328 @@
 //! ```
 //!
 //! ## Cargo features
-//! Enable [serde](https://crates.io/crates/serde) feature for serialization/deserialization support.
+//! The default `blocking` feature provides downloading from the AMFI portal or mirrors with a
+//! blocking HTTP client, see [daily_nav](fn.daily_nav.html). The `async` feature provides
+//! the same without blocking, see [daily_nav_async](fn.daily_nav_async.html); it does not
+//! pull in the blocking client. Disable default features to build only the parser, e.g. for
+//! `wasm32-unknown-unknown`.
+//!
+//! NAV dates are [NavDate](struct.NavDate.html)s, which need no date library. The default
+//! `chrono` feature converts them to and from `chrono::NaiveDate`, and the `time` feature to and
+//! from `time::Date`. The HTTP features take response times as `SystemTime`s; snapshots, the
+//! watcher, archives and [fetch_all_async](fn.fetch_all_async.html) still need `chrono`.
+//!
+//! Enable [serde](https://crates.io/crates/serde) feature for serialization/deserialization support
+//! and JSON Lines export, see [write_jsonl](fn.write_jsonl.html). Whole feeds stream into any
+//! serializer without being collected, see [serialize_records](fn.serialize_records.html). Dates
+//! serialize as ISO 8601 strings; [amfi::serde](serde/index.html) has helpers for other
+//! representations.
+//!
+//! Enable `schemars` feature for [JSON Schemas](https://json-schema.org) of the serialized
+//! records, summaries and metadata, e.g. `schemars::schema_for!(amfi::NavRecord)`.
+//!
+//! Enable `bincode` feature to save parsed feeds in a compact binary file and load them much
+//! faster than parsing again, see [NavSnapshot::save](struct.NavSnapshot.html#method.save).
+//! Files saved by earlier releases keep loading in later ones. With the default `blocking`
+//! feature too, past days are downloaded to such files, see [backfill](fn.backfill.html).
+//!
+//! Enable `archive` feature to keep downloaded feeds with a manifest of their SHA-256 and
+//! record count, see [download_nav_with_manifest](fn.download_nav_with_manifest.html), and to
+//! verify them later with [verify_archive](fn.verify_archive.html).
+//!
+//! Enable `csv` feature for CSV export, see [write_csv](fn.write_csv.html), and for adding
+//! launch dates, SEBI categories and other details of the AMFI scheme master to records, see
+//! [enrich](fn.enrich.html).
+//!
+//! Enable `arrow` feature for conversion to Arrow record batches, see
+//! [to_record_batch](fn.to_record_batch.html), and `parquet` feature for Parquet export, see
+//! [write_parquet](fn.write_parquet.html).
+//!
+//! Enable `sqlite` feature for loading records into SQLite, see
+//! [write_sqlite](fn.write_sqlite.html).
+//!
+//! Enable `stream` feature for parsing from asynchronous readers, see
+//! [NavRecordStream](struct.NavRecordStream.html). With the `async` feature too, new feed
+//! publications are watched as a stream of snapshots, see
+//! [watch_daily_nav_stream](fn.watch_daily_nav_stream.html).
+//!
+//! Enable `encoding` feature to decode downloaded feeds served in a charset other than UTF-8,
+//! e.g. `windows-1252`, as declared by their `Content-Type`.
+//!
+//! Enable `tracing` feature for [tracing](https://crates.io/crates/tracing) events: section
+//! headers at `DEBUG`, unparsable lines at `WARN`, downloads and one summary per feed at `INFO`,
+//! within an `amfi_feed` span. Without it no instrumentation is compiled in.
+//!
+//! Enable `rayon` feature for parsing a feed held in memory on all cores, see
+//! [parse_parallel](fn.parse_parallel.html).
+//!
+//! Enable `html` feature for parsing the NAV table of the portal's HTML pages, as an alternate
+//! source when the text feed is unavailable, see [nav_from_html](fn.nav_from_html.html).
+//!
+//! Enable `test-corpus` feature for [amfi::corpus](corpus/index.html), sample feed lines with
+//! the records they parse into, to test wrappers of the parser against.
+//!
+//! Enable `cli` feature for the `amfi` command line tool converting, filtering and diffing
+//! feeds; run `amfi --help` for usage.
 //!
 //! [AMFI]: https://www.amfiindia.com
 
-use chrono::NaiveDate;
-use derive_builder::Builder;
+use crate::charset::Charset;
+use crate::filter::{Filters, TextFilter};
+use crate::order::CodeOrder;
+use crate::payout::{payout, Payout};
+use crate::progress::AmcTracker;
+use crate::source::Source;
+use crate::tee::Tee;
+use crate::throughput::Meter;
+use crate::trace::{FeedSpan, FeedTrace};
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-use std::convert::AsRef;
+use ::serde::{Deserialize, Serialize};
+use derive_builder::Builder;
+use log::warn;
+use std::borrow::Cow;
+use std::convert::{AsRef, TryFrom};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
-use std::path::Path;
-use synom::{
-    alt, call, do_parse, map, named, option, tag, take_until, terminated, tuple, tuple_parser,
-    IResult,
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use synom::{alt, call, tag, IResult};
+
+#[macro_use]
+mod trace;
+
+mod amc;
+mod amc_ids;
+#[cfg(feature = "chrono")]
+mod anomaly;
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(all(feature = "blocking", feature = "bincode"))]
+mod backfill;
+#[cfg(all(feature = "async", feature = "chrono"))]
+mod batch;
+mod business_days;
+mod category;
+mod changes;
+mod charset;
+mod chunks;
+mod code;
+#[cfg(any(test, feature = "test-corpus"))]
+pub mod corpus;
+mod date;
+mod diff;
+#[cfg(feature = "blocking")]
+mod download;
+#[cfg(feature = "csv")]
+mod enrich;
+mod export;
+mod feed_builder;
+#[cfg(feature = "fetch")]
+mod fetch;
+mod field;
+mod files;
+mod filter;
+mod group;
+mod header;
+mod history;
+#[cfg(feature = "html")]
+mod html;
+mod index;
+mod isin;
+mod isin_map;
+mod key;
+mod latest;
+#[cfg(feature = "fetch")]
+mod limit;
+mod lines;
+#[cfg(feature = "blocking")]
+mod lookup;
+mod nested;
+mod normalize;
+mod order;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod partial;
+mod payout;
+mod peek;
+mod plan;
+mod progress;
+mod provenance;
+mod record_ref;
+mod records;
+mod recovery;
+mod scheme;
+mod section_iter;
+mod sections;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "chrono")]
+mod snapshot;
+mod source;
+mod stats;
+#[cfg(feature = "bincode")]
+mod stored;
+#[cfg(feature = "stream")]
+mod stream;
+mod summary;
+mod table;
+mod tee;
+#[cfg(test)]
+mod testing;
+mod throughput;
+#[cfg(feature = "chrono")]
+mod universe;
+mod validate;
+#[cfg(all(
+    feature = "chrono",
+    any(feature = "blocking", all(feature = "async", feature = "stream"))
+))]
+mod watch;
+mod writer;
+
+pub use crate::amc::{
+    amc_ids, amc_list, normalize_amc, AmcAliases, AmcEntry, AmcId, AmcKey, AmcNormalizer,
+    AmcSuffixPolicy,
+};
+#[cfg(feature = "chrono")]
+pub use crate::anomaly::{detect_anomalies, Anomaly, AnomalyConfig};
+#[cfg(feature = "archive")]
+pub use crate::archive::{
+    download_nav_with_manifest, download_nav_with_manifest_at, verify_archive, ArchiveManifest,
+    VerifyReport,
+};
+#[cfg(all(feature = "blocking", feature = "bincode"))]
+pub use crate::backfill::{backfill, BackfillOptions, BackfillOutcome, BackfillReport};
+#[cfg(all(feature = "async", feature = "chrono"))]
+pub use crate::batch::fetch_all_async;
+pub use crate::business_days::{
+    expected_feed_date, is_business_day, previous_business_day, FEED_PUBLISHED_BY_HOUR,
+};
+pub use crate::category::{
+    AssetClass, FofKind, SchemeCategory, SchemeGroup, SubCategory, VehicleKind,
+};
+pub use crate::changes::{changes_between, ChangeSet, NavTolerance};
+pub use crate::charset::DecodePolicy;
+pub use crate::chunks::{Batch, NavChunks};
+pub use crate::code::SchemeCode;
+pub use crate::date::{NavDate, NavDateError};
+pub use crate::diff::{diff_navs, NavDelta, NavDiff};
+#[cfg(feature = "blocking")]
+pub use crate::download::{download_nav_at, download_nav_to};
+#[cfg(feature = "csv")]
+pub use crate::enrich::{enrich, EnrichedRecord, Enrichment, MasterEntry, MatchedBy, SchemeMaster};
+#[cfg(feature = "arrow")]
+pub use crate::export::{arrow_schema, to_record_batch};
+#[cfg(feature = "serde")]
+pub use crate::export::{
+    serialize_records, to_json_string, to_json_writer, write_nested_json, ParseErrorPolicy,
+};
+pub use crate::export::{shard_by_amc, RecordSink, ShardStats, WriteStats};
+#[cfg(feature = "csv")]
+pub use crate::export::{write_csv, write_csv_stream, CsvSink};
+#[cfg(feature = "serde")]
+pub use crate::export::{write_jsonl, JsonLinesSink};
+#[cfg(feature = "parquet")]
+pub use crate::export::{write_parquet, ROW_GROUP_SIZE};
+#[cfg(feature = "sqlite")]
+pub use crate::export::{write_sqlite, SqliteStats};
+pub use crate::feed_builder::{AmcBuilder, FeedBuilder, SectionBuilder};
+#[cfg(feature = "blocking")]
+pub use crate::fetch::{
+    daily_nav_with, daily_nav_with_meta, fetch_amc_ids, is_nav_updated, is_nav_updated_at,
+    nav_for_code, nav_for_codes, nav_for_codes_at, nav_from_url_with_meta, nav_history_for_amc,
+    nav_history_for_amc_with, peek_daily_feed_date, peek_feed_date_at, HistoryRecords, PEEK_LEN,
+};
+#[cfg(feature = "async")]
+pub use crate::fetch::{daily_nav_with_async, is_nav_updated_async, is_nav_updated_at_async};
+#[cfg(feature = "fetch")]
+pub use crate::fetch::{FetchMeta, FetchOptions, NavUpdate, ResponseMeta, UpdateStatus};
+pub use crate::field::Field;
+pub use crate::files::{nav_from_files, NavFiles, SourcedFiles, UnreadableFilePolicy};
+pub use crate::group::{
+    group_by_amc, group_by_amc_and_category, group_by_amc_and_category_with, group_by_amc_with,
+    group_by_category, Groups,
+};
+pub use crate::header::FeedHeader;
+pub use crate::history::{Drawdown, NavHistory, SchemeAmc, Series};
+#[cfg(feature = "html")]
+pub use crate::html::nav_from_html;
+pub use crate::index::{
+    ApplySummary, DuplicateEntry, DuplicatePolicy, NavIndex, NavIndexIter, SearchHit,
+};
+pub use crate::isin::{Isin, IsinError, IsinStrictness, RawIsin};
+pub use crate::isin_map::{
+    isin_conflicts, isin_map, DuplicateIsin, DuplicateIsinKind, IsinConflict, IsinMap,
 };
+pub use crate::key::{match_records, KeyKind, SchemeKey};
+pub use crate::latest::{CompactionStats, LatestNavCollector};
+#[cfg(feature = "fetch")]
+pub use crate::limit::{Clock, RateLimiter, SystemClock};
+pub use crate::lines::{feed_lines, FeedLine, FeedLines};
+pub use crate::nested::{to_nested, AmcSection, LeanRecord, NestedFeed, SchemeSection};
+pub use crate::normalize::{CaseFolding, NormalizeOptions};
+#[cfg(feature = "rayon")]
+pub use crate::parallel::{parse_parallel, ParsedFeed};
+pub use crate::partial::{parse_record_line, PartialRecord, SectionContext};
+pub use crate::peek::peek_feed_date;
+pub use crate::plan::{PlanMap, PlanResolver, PlanSource};
+pub use crate::progress::{AmcProgress, AmcProgressRecords};
+pub use crate::provenance::{
+    Provenance, RecordSource, SourceConflict, SourceId, SourceRegistry, Sourced,
+};
+pub use crate::record_ref::{for_each_record, NavRecordRef};
+pub use crate::records::NavRecords;
+pub use crate::recovery::RecoveryPolicy;
+pub use crate::scheme::{parse_scheme_header, SchemeHeader};
+pub use crate::section_iter::{for_each_section, sections, NavSections, Section, SectionRecords};
+pub use crate::sections::{check_sections, FeedSection, SectionFinding, SectionReport};
+#[cfg(feature = "chrono")]
+pub use crate::snapshot::{NavSnapshot, SnapshotSource};
+pub use crate::stats::{category_stats, CategoryStats};
+#[cfg(feature = "stream")]
+pub use crate::stream::NavRecordStream;
+pub use crate::summary::{summarize, DateSkewReport, FeedSummary, StaleReport};
+pub use crate::table::{render_table, render_table_with, Column, TABLE_TEXT_WIDTH};
+pub use crate::throughput::{ParseStats, Throughput};
+#[cfg(feature = "chrono")]
+pub use crate::universe::{compare_universe, FieldChange, SchemeChange, UniverseDiff};
+pub use crate::validate::{
+    validate_feed, validate_feed_with, FeedIssue, ValidationIssue, ValidationRules,
+};
+#[cfg(all(feature = "blocking", feature = "chrono"))]
+pub use crate::watch::watch_daily_nav;
+#[cfg(all(feature = "async", feature = "stream", feature = "chrono"))]
+pub use crate::watch::{watch_daily_nav_stream, WatchStream};
+#[cfg(all(
+    feature = "chrono",
+    any(feature = "blocking", all(feature = "async", feature = "stream"))
+))]
+pub use crate::watch::{WatchConfig, WatchHandle};
+pub use crate::writer::{NavWriter, FEED_HEADER, HISTORY_FEED_HEADER, LEGACY_FEED_HEADER};
 
+#[cfg(feature = "fetch")]
 const BASE_URL: &str = "http://portal.amfiindia.com/spages/NAVAll.txt";
 
-#[derive(Debug, Builder)]
-#[builder(setter(into), private)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(into), build_fn(private, name = "build_fields"))]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 /// Net Asset Value Record
+///
+/// Records are usually produced by [`NavRecordIterator`](struct.NavRecordIterator.html).
+/// To construct one by hand use [`NavRecordBuilder`](struct.NavRecordBuilder.html): `code`,
+/// `name`, `nav`, `date`, `amc`, `category` and `plan` are required, while `isin`, `isin_dr`,
+/// `scheme`, `maturity`, `option` and the prices default to `None`.
+///
+/// ```rust
+/// use amfi::{FundPlan, NavDate, NavRecordBuilder};
+///
+/// let record = NavRecordBuilder::default()
+///     .code(120_465)
+///     .name("Axis Bluechip Fund - Direct Plan - Growth")
+///     .nav(61.43)
+///     .date(NavDate::from_ymd(2024, 6, 21).unwrap())
+///     .amc("Axis Mutual Fund")
+///     .category("Large Cap Fund")
+///     .plan(FundPlan::Direct)
+///     .build()
+///     .unwrap();
+/// assert_eq!(record.isin, None);
+/// ```
+///
+/// With the `serde` feature records serialize as a flat map of the fields below, under the
+/// same names, e.g. in JSON:
+///
+/// ```json
+/// {
+///   "code": 120465,
+///   "isin": "INF846K01AB4",
+///   "isin_dr": null,
+///   "name": "Axis Bluechip Fund - Direct Plan - Growth",
+///   "nav": 61.43,
+///   "date": "2024-06-21",
+///   "amc": "Axis Mutual Fund",
+///   "category": "Large Cap Fund",
+///   "scheme": "Equity Scheme",
+///   "maturity": "open_ended",
+///   "plan": "direct",
+///   "option": null,
+///   "source_line": 7
+/// }
+/// ```
+///
+/// `plan_source` is left out when `name_token`, `fof` when `None`, `raw` unless captured,
+/// see [`capture_raw`](struct.NavRecordIterator.html#method.capture_raw),
+/// `repurchase_price` and `sale_price` unless given by the feed, `source_line` for records
+/// not read from a feed, `segment` unless stamped and `recovered` unless true. `isin`,
+/// `isin_dr`, `scheme`, `maturity` and `option` are `null` when `None`. Formats that are
+/// not human readable, like bincode, always carry every field.
+/// ISINs are plain strings (also when invalid), `maturity` is `open_ended`
+/// or `close_ended` and `plan` is `regular` or `direct`. Field names and these
+/// representations are part of the public API and only change with a breaking release.
+/// Deserialization also accepts the `OpenEnded`/`Direct` style enum names of amfi 0.1.
+///
+/// Records compare equal when all fields but `source_line`, `segment` and `recovered` are
+/// equal, so the same record read from concatenated or shifted lines of feeds is equal. The
+/// captured `raw` text and the prices are compared too, so records read from feeds of
+/// different layouts, or captured from differently laid out lines, may differ. `nav` being an
+/// `f64`, records don't implement `Eq`/`Hash`; use [`key`](#method.key) to identify a record
+/// in maps and sets.
 pub struct NavRecord {
     /// Scheme Code
-    pub code: u32,
+    pub code: SchemeCode,
     /// ISIN Growth/Divdend Payout
-    pub isin: Option<String>,
+    #[builder(default)]
+    pub isin: Option<RawIsin>,
     /// ISIN Divdend Reinvestment
-    pub isin_dr: Option<String>,
+    #[builder(default)]
+    pub isin_dr: Option<RawIsin>,
     /// Scheme Name
     pub name: String,
     /// Net Asset Value (NAV)
     pub nav: f64,
-    /// NAV Date
-    pub date: NaiveDate,
+    /// NAV Date, serialized as ISO 8601 string; see [`serde`](serde/index.html) for other formats
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::date_iso"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "NavDate"))]
+    pub date: NavDate,
     /// Asset Management Company (AMC)
+    ///
+    /// Empty only for records read before the first AMC header of a feed, which are errors
+    /// unless [`HeaderlessPolicy::Keep`](enum.HeaderlessPolicy.html#variant.Keep) is set.
     pub amc: String,
     /// Category
+    ///
+    /// Empty for records read before the first scheme header of a feed, as of fragments of
+    /// one listing a single AMC, or after a header naming none, e.g. `Close Ended Schemes` of
+    /// some older feeds.
     pub category: String,
     /// Scheme
+    #[builder(default)]
     pub scheme: Option<String>,
     /// Fund Maturity (Open/Close Ended)
+    #[builder(default)]
     pub maturity: Option<FundMaturity>,
     /// Plan (Regular/Direct)
     pub plan: FundPlan,
+    /// How `plan` was decided, see
+    /// [`plan_resolver`](struct.NavRecordIterator.html#method.plan_resolver)
+    ///
+    /// Not kept by snapshot files, so loaded records, like those built by hand unless set,
+    /// only tell `NameToken` from `Default`.
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub plan_source: PlanSource,
     /// Option (Growth/Monthly Dividend Payout etc.)
+    #[builder(default)]
     pub option: Option<String>,
+    /// Kind of Fund of Funds, `None` for other schemes
+    ///
+    /// Taken from the `FoF Domestic` and `FoF Overseas` category headers, else guessed from
+    /// the name, see [`FofKind::from_name`](enum.FofKind.html#method.from_name). Derived by
+    /// `NavRecordBuilder::build` unless set.
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fof: Option<FofKind>,
+    /// Repurchase Price, only in the legacy and history layouts and often blank there
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub repurchase_price: Option<f64>,
+    /// Sale Price, only in the legacy and history layouts and often blank there
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sale_price: Option<f64>,
+    /// Text as published, only with
+    /// [`capture_raw`](struct.NavRecordIterator.html#method.capture_raw)
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub raw: Option<RawFields>,
+    /// 1-based number of the feed line the record was read from, the line of its parse
+    /// errors; `None` for records built by hand or parsed with
+    /// [`parse_line`](#method.parse_line)
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source_line: Option<u32>,
+    /// 1-based number of the feed the record was read from in a concatenation of feeds, only
+    /// with [`stamp_segments`](struct.NavRecordIterator.html#method.stamp_segments)
+    ///
+    /// Not kept by snapshot files.
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub segment: Option<u32>,
+    /// Whether the record was read from a line with shifted columns, see
+    /// [`RecoveryPolicy::Lenient`](enum.RecoveryPolicy.html#variant.Lenient)
+    ///
+    /// Not kept by snapshot files.
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recovered: bool,
+}
+
+impl PartialEq for NavRecord {
+    fn eq(&self, other: &NavRecord) -> bool {
+        // Destructured so that new fields aren't forgotten
+        let NavRecord {
+            code,
+            isin,
+            isin_dr,
+            name,
+            nav,
+            date,
+            amc,
+            category,
+            scheme,
+            maturity,
+            plan,
+            plan_source,
+            option,
+            fof,
+            repurchase_price,
+            sale_price,
+            raw,
+            source_line: _,
+            segment: _,
+            recovered: _,
+        } = self;
+        *code == other.code
+            && *isin == other.isin
+            && *isin_dr == other.isin_dr
+            && *name == other.name
+            && *nav == other.nav
+            && *date == other.date
+            && *amc == other.amc
+            && *category == other.category
+            && *scheme == other.scheme
+            && *maturity == other.maturity
+            && *plan == other.plan
+            && *plan_source == other.plan_source
+            && *option == other.option
+            && *fof == other.fof
+            && *repurchase_price == other.repurchase_price
+            && *sale_price == other.sale_price
+            && *raw == other.raw
+    }
+}
+
+/// Text of a record as published in the feed, see
+/// [`NavRecordIterator::capture_raw`](struct.NavRecordIterator.html#method.capture_raw)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RawFields {
+    /// Record line, without its line terminator
+    pub line: String,
+    /// Scheme name before trimming
+    pub name: String,
+    /// NAV before conversion to `f64`
+    pub nav: String,
+    /// NAV date before parsing
+    pub date: String,
+}
+
+impl NavRecordBuilder {
+    /// Builds a new `NavRecord`
+    ///
+    /// Fails with [`Error::BuilderError`](enum.Error.html#variant.BuilderError) listing the
+    /// required fields that were not set, or naming `nav` if it is `NaN` or infinite.
+    ///
+    /// ```rust
+    /// let error = amfi::NavRecordBuilder::default().code(120_465).build().unwrap_err();
+    /// match error {
+    ///     amfi::Error::BuilderError { fields, .. } => {
+    ///         assert_eq!(fields, ["name", "nav", "date", "amc", "category", "plan"])
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn build(&self) -> Result<NavRecord> {
+        let missing = self.unset_required();
+        if !missing.is_empty() {
+            return Err(Error::BuilderError {
+                fields: missing,
+                reason: "required fields not set".to_string(),
+                line: None,
+                raw_line: None,
+            });
+        }
+        if self.nav.is_some_and(|nav| !nav.is_finite()) {
+            return Err(Error::BuilderError {
+                fields: vec!["nav".to_string()],
+                reason: "NAV not finite".to_string(),
+                line: None,
+                raw_line: None,
+            });
+        }
+        // Only fails for required fields missing from `unset_required`
+        let mut record = self.build_fields().map_err(|reason| Error::BuilderError {
+            fields: Vec::new(),
+            reason,
+            line: None,
+            raw_line: None,
+        })?;
+        if self.fof.is_none() {
+            record.fof =
+                FofKind::of_scheme(record.scheme.as_deref(), &record.category, &record.name);
+        }
+        if self.plan_source.is_none() {
+            record.plan_source = PlanSource::of_name(&record.name);
+        }
+        Ok(record)
+    }
+
+    /// Names of the fields without `#[builder(default)]` that are not set, in declaration order
+    fn unset_required(&self) -> Vec<String> {
+        macro_rules! unset {
+            ($($field:ident),*) => {{
+                let mut unset = Vec::new();
+                $(
+                    if self.$field.is_none() {
+                        unset.push(stringify!($field).to_string());
+                    }
+                )*
+                unset
+            }};
+        }
+        unset!(code, name, nav, date, amc, category, plan)
+    }
+
+    /// Builder set to the fields of [`NavRecord::fixture`](struct.NavRecord.html#method.fixture),
+    /// a direct plan growth option, for tests to override some fields of
+    ///
+    /// ```rust
+    /// let record = amfi::NavRecordBuilder::example_direct_growth()
+    ///     .nav(62.0)
+    ///     .build()?;
+    /// assert_eq!(record.name, "Axis Bluechip Fund - Direct Plan - Growth");
+    /// # Ok::<(), amfi::Error>(())
+    /// ```
+    pub fn example_direct_growth() -> NavRecordBuilder {
+        let record = NavRecord::fixture();
+        let mut builder = NavRecordBuilder::default();
+        builder
+            .code(record.code)
+            .isin(record.isin)
+            .isin_dr(record.isin_dr)
+            .name(record.name)
+            .nav(record.nav)
+            .date(record.date)
+            .amc(record.amc)
+            .category(record.category)
+            .scheme(record.scheme)
+            .maturity(record.maturity)
+            .plan(record.plan)
+            .option(record.option);
+        builder
+    }
+}
+
+impl NavRecord {
+    /// Realistic record for tests: Axis Bluechip Fund, direct plan, growth option, as read
+    /// from the feed of 21 June 2024 but for `source_line`
+    ///
+    /// Fields are set as by the feed iterator, so `option`, `raw` and the prices are `None`.
+    /// Vary it with [`with_code`](#method.with_code), [`with_nav`](#method.with_nav) and
+    /// [`with_date`](#method.with_date), or start from
+    /// [`NavRecordBuilder::example_direct_growth`](struct.NavRecordBuilder.html#method.example_direct_growth)
+    /// to set other fields.
+    ///
+    /// ```rust
+    /// use amfi::{NavDate, NavRecord};
+    ///
+    /// let record = NavRecord::fixture()
+    ///     .with_nav(62.0)
+    ///     .with_date(NavDate::from_ymd(2024, 6, 24).unwrap());
+    /// assert_eq!(record.code, NavRecord::fixture().code);
+    /// ```
+    pub fn fixture() -> NavRecord {
+        NavRecord {
+            code: SchemeCode::from(120_465),
+            isin: Some(RawIsin::new("INF846K01AB4")),
+            isin_dr: None,
+            name: "Axis Bluechip Fund - Direct Plan - Growth".to_string(),
+            nav: 61.43,
+            date: NavDate::from_ymd(2024, 6, 21).expect("valid date"),
+            amc: "Axis Mutual Fund".to_string(),
+            category: "Large Cap Fund".to_string(),
+            scheme: Some("Equity Scheme".to_string()),
+            maturity: Some(FundMaturity::OpenEnded),
+            plan: FundPlan::Direct,
+            plan_source: PlanSource::NameToken,
+            option: None,
+            fof: None,
+            repurchase_price: None,
+            sale_price: None,
+            raw: None,
+            source_line: None,
+            segment: None,
+            recovered: false,
+        }
+    }
+
+    /// The record with scheme code `code`
+    pub fn with_code<C: Into<SchemeCode>>(mut self, code: C) -> NavRecord {
+        self.code = code.into();
+        self
+    }
+
+    /// The record with NAV `nav`
+    pub fn with_nav(mut self, nav: f64) -> NavRecord {
+        self.nav = nav;
+        self
+    }
+
+    /// The record with NAV date `date`
+    pub fn with_date(mut self, date: NavDate) -> NavRecord {
+        self.date = date;
+        self
+    }
+
+    /// Identity of the record: scheme code and NAV date
+    ///
+    /// A scheme has at most one NAV per date, so this key is unique across a feed and across
+    /// feeds for different days.
+    pub fn key(&self) -> (SchemeCode, NavDate) {
+        (self.code, self.date)
+    }
+
+    /// Identity of the scheme for joining feeds of different days: code, ISIN and a
+    /// normalized name, see [`SchemeKey`](struct.SchemeKey.html) and
+    /// [`match_records`](fn.match_records.html)
+    pub fn scheme_key(&self) -> SchemeKey {
+        SchemeKey::of(self)
+    }
+
+    /// Whether either ISIN is valid
+    ///
+    /// Thousands of schemes, mostly close-ended series and segregated portfolios, are
+    /// published with `-` for both. Such records are keyed by code and name only, see
+    /// [`SchemeKey`](struct.SchemeKey.html), and not by [`isin_map`](fn.isin_map.html).
+    pub fn has_isin(&self) -> bool {
+        self.isin
+            .iter()
+            .chain(self.isin_dr.iter())
+            .any(RawIsin::is_valid)
+    }
+
+    /// NAV date as days since 1970-01-01, as in Arrow `Date32` columns
+    pub fn date_epoch_days(&self) -> i32 {
+        // Years 1 to 9999 are within ±3 million days
+        self.date.epoch_days() as i32
+    }
+
+    /// Unix timestamp of midnight IST starting the NAV date, see
+    /// [`NavDate::unix_midnight_ist`](struct.NavDate.html#method.unix_midnight_ist)
+    pub fn date_unix_midnight_ist(&self) -> i64 {
+        self.date.unix_midnight_ist()
+    }
+
+    /// Typed scheme group derived from the `scheme` field, `None` if it is
+    ///
+    /// The raw `scheme` string remains available as is.
+    pub fn scheme_group(&self) -> Option<SchemeGroup> {
+        self.scheme.as_deref().map(SchemeGroup::new)
+    }
+
+    /// Typed category derived from the `scheme` and `category` fields, see
+    /// [`SchemeCategory::of_group`](struct.SchemeCategory.html#method.of_group)
+    ///
+    /// The raw `category` string remains available as is.
+    pub fn category_typed(&self) -> SchemeCategory {
+        SchemeCategory::new(self.scheme.as_deref(), &self.category)
+    }
+
+    /// Whether the scheme is a Fund of Funds, see [`fof`](#structfield.fof)
+    pub fn is_fof(&self) -> bool {
+        self.fof.is_some()
+    }
+
+    /// Whether the scheme is an active fund, index fund, ETF or FoF
+    ///
+    /// Told by the category where it does, i.e. for index fund, ETF and FoF sections and
+    /// other current SEBI categories, where active funds are FoFs only by
+    /// [`fof`](#structfield.fof). Unrecognized and legacy categories fall back to
+    /// [`VehicleKind::from_name`](enum.VehicleKind.html#method.from_name).
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use amfi::VehicleKind;
+    ///
+    /// let etfs = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .filter_map(Result::ok)
+    ///     .filter(|record| record.vehicle() == VehicleKind::Etf)
+    ///     .count();
+    /// assert_eq!(etfs, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn vehicle(&self) -> VehicleKind {
+        VehicleKind::of_record(
+            self.scheme.as_deref(),
+            &self.category,
+            self.is_fof(),
+            &self.name,
+        )
+    }
+
+    /// Parses a single record line out of its feed context
+    ///
+    /// Contextual fields which normally come from the surrounding section headers are left
+    /// empty: `amc` and `category` are empty strings, `scheme` and `maturity` are `None`.
+    /// Lines of the legacy layout, see [`Layout`](enum.Layout.html), are told apart by their
+    /// column count. Also available through `FromStr`, i.e. `line.parse::<NavRecord>()`.
+    pub fn parse_line(line: &str) -> Result<NavRecord> {
+        parse_record_line(line, Layout::of_record(line))
+            .map(|partial| partial.into_record(&SectionContext::default()))
+    }
+
+    /// Portal id of the record's AMC, see [`AmcId::find`](struct.AmcId.html#method.find)
+    ///
+    /// `None` for AMCs missing from the bundled table and records without AMC.
+    pub fn amc_id(&self) -> Option<AmcId> {
+        AmcId::find(&self.amc)
+    }
+
+    /// Canonical short AMC name, see [normalize_amc](fn.normalize_amc.html)
+    pub fn amc_normalized(&self) -> String {
+        normalize_amc(&self.amc)
+    }
+
+    /// Whether `nav` is a price: neither zero nor `NaN`/infinite
+    ///
+    /// See also [`ZeroNavPolicy`](enum.ZeroNavPolicy.html).
+    pub fn has_valid_nav(&self) -> bool {
+        self.nav != 0.0 && self.nav.is_finite()
+    }
+
+    /// Whether this is a direct plan
+    pub fn is_direct(&self) -> bool {
+        self.plan == FundPlan::Direct
+    }
+
+    /// Whether this is a regular plan
+    pub fn is_regular(&self) -> bool {
+        self.plan == FundPlan::Regular
+    }
+
+    /// Whether this is the growth option
+    ///
+    /// Read from `option` if set, otherwise from the words of `name` after the fund name:
+    /// `Axis Growth Opportunities Fund - Direct Plan - IDCW` is not growth, and names with
+    /// both options such as `... - Growth Plus Dividend` are neither growth nor IDCW.
+    ///
+    /// ```rust
+    /// let record: amfi::NavRecord =
+    ///     "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024"
+    ///         .parse()?;
+    /// assert!(record.is_growth());
+    /// assert!(!record.is_idcw());
+    /// # Ok::<(), amfi::Error>(())
+    /// ```
+    pub fn is_growth(&self) -> bool {
+        payout(&self.name, self.option.as_deref()) == Some(Payout::Growth)
+    }
+
+    /// Whether this is an IDCW (Income Distribution cum Capital Withdrawal) option, named
+    /// dividend before 2021; see [`is_growth`](#method.is_growth)
+    pub fn is_idcw(&self) -> bool {
+        payout(&self.name, self.option.as_deref()) == Some(Payout::Idcw)
+    }
+
+    /// Whether the scheme is open ended
+    pub fn is_open_ended(&self) -> bool {
+        self.maturity == Some(FundMaturity::OpenEnded)
+    }
+
+    /// Whether the scheme is close ended
+    pub fn is_close_ended(&self) -> bool {
+        self.maturity == Some(FundMaturity::CloseEnded)
+    }
+
+    /// Whether `category` equals the record's category, ignoring case and whitespace
+    ///
+    /// `"large cap fund"` and `"Sectoral/Thematic"` match the feed's `Large Cap Fund` and
+    /// `Sectoral/ Thematic`.
+    pub fn matches_category(&self, category: &str) -> bool {
+        fn folded(text: &str) -> impl Iterator<Item = char> + '_ {
+            text.chars()
+                .filter(|ch| !ch.is_whitespace())
+                .flat_map(char::to_lowercase)
+        }
+        folded(&self.category).eq(folded(category))
+    }
+}
+
+impl std::fmt::Display for NavRecord {
+    /// Renders the record line as published in the feed, e.g.
+    /// `120466;INF846K01AC2;INF846K01AD0;Axis Bluechip Fund - Direct Plan - IDCW;21.98;21-Jun-2024`
+    ///
+    /// The alternate form (`{:#}`) renders a multi-line human readable view including the
+    /// section context.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let isin = |isin: &Option<RawIsin>| isin.as_ref().map_or("-", RawIsin::as_str).to_string();
+        if f.alternate() {
+            writeln!(f, "{}", self.name)?;
+            writeln!(f, "  Code:     {}", self.code)?;
+            writeln!(
+                f,
+                "  ISIN:     {} / {}",
+                isin(&self.isin),
+                isin(&self.isin_dr)
+            )?;
+            writeln!(f, "  NAV:      {} ({})", self.nav, self.date.feed_format())?;
+            writeln!(f, "  AMC:      {}", self.amc)?;
+            write!(f, "  Category: ")?;
+            if let Some(maturity) = self.maturity {
+                write!(f, "{} / ", maturity)?;
+            }
+            if let Some(ref scheme) = self.scheme {
+                write!(f, "{} / ", scheme.trim())?;
+            }
+            writeln!(f, "{}", self.category.trim())?;
+            write!(f, "  Plan:     {}", self.plan)?;
+            if let Some(ref option) = self.option {
+                write!(f, " / {}", option)?;
+            }
+            Ok(())
+        } else {
+            write!(
+                f,
+                "{};{};{};{};{};{}",
+                self.code,
+                isin(&self.isin),
+                isin(&self.isin_dr),
+                self.name,
+                self.nav,
+                self.date.feed_format()
+            )
+        }
+    }
+}
+
+impl FromStr for NavRecord {
+    type Err = Error;
+
+    /// See [`NavRecord::parse_line`](struct.NavRecord.html#method.parse_line)
+    fn from_str(s: &str) -> Result<Self> {
+        NavRecord::parse_line(s)
+    }
 }
 
 /// Error type
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Error from IO operation
     IoError(io::Error),
     /// Error from reqwest library
+    #[cfg(feature = "fetch")]
     ReqwestError(reqwest::Error),
-    /// Error from Builder parser
-    BuilderError(String),
-    /// Error from Synom parser combinator
+    /// Record could not be built, by hand or from a feed line
+    BuilderError {
+        /// Fields missing or invalid, e.g. `["amc"]`
+        fields: Vec<String>,
+        /// What is wrong with them
+        reason: String,
+        /// 1-based number of the feed line, `None` for records built by hand
+        line: Option<usize>,
+        /// Text of the feed line, `None` for records built by hand
+        raw_line: Option<String>,
+    },
+    /// Scheme header that could not be parsed, see
+    /// [`parse_scheme_header`](fn.parse_scheme_header.html)
     SynomError(String),
-    /// HTTP Error from server
-    HttpError(reqwest::StatusCode),
+    /// Record published with a NAV of zero, yielded in its place under
+    /// [`ZeroNavPolicy::TreatAsMissing`](enum.ZeroNavPolicy.html#variant.TreatAsMissing)
+    ZeroNav {
+        /// Code of the record
+        code: SchemeCode,
+        /// 1-based number of the line of the record
+        at_line: usize,
+    },
+    /// Record line that could not be parsed, with the field it failed at
+    ///
+    /// Displayed with the line and a `^` under the failure point, unless the line is long.
+    ParseFailure {
+        /// Field that could not be parsed
+        field: Field,
+        /// Byte offset in `raw_line` where parsing stopped
+        at_byte: usize,
+        /// Text of the field from `at_byte`, up to the next `;`
+        found: String,
+        /// What the field should be, e.g. `decimal number`
+        expected: &'static str,
+        /// 1-based number of the feed line, `None` for lines parsed on their own
+        line: Option<usize>,
+        /// Text of the line, trimmed and without control characters unless kept
+        raw_line: String,
+    },
+    /// Unsuccessful HTTP response from server
+    #[cfg(feature = "fetch")]
+    HttpError {
+        /// Status of the response
+        status: reqwest::StatusCode,
+        /// URL requested
+        url: String,
+        /// Start of the response body, up to 512 bytes decoded lossily; `None` if blank or
+        /// not read, e.g. for `HEAD` requests
+        body_snippet: Option<String>,
+    },
+    /// Download by [`download_nav_at`](fn.download_nav_at.html) ending short of the size the
+    /// server announced, its part kept to resume
+    #[cfg(feature = "blocking")]
+    IncompleteDownload {
+        /// URL of the feed
+        url: String,
+        /// Bytes of the feed downloaded
+        received: u64,
+        /// Size of the feed announced
+        expected: u64,
+    },
+    /// Content that is not a NAV feed, e.g. an HTML error page served with status 200
+    ///
+    /// See [`NavRecordIterator::sniff_content`](struct.NavRecordIterator.html#method.sniff_content).
+    UnexpectedContent {
+        /// First non-blank line, up to 200 characters
+        first_line: String,
+        /// URL the content was downloaded from, `None` for other sources
+        url: Option<String>,
+    },
+    /// The portal has no NAV history for the AMC in the requested range
+    #[cfg(feature = "fetch")]
+    NoRecords {
+        /// Fund house requested
+        amc: AmcId,
+        /// First date requested
+        from: NavDate,
+        /// Last date requested
+        to: NavDate,
+    },
+    /// Column header of an unknown layout, ending parsing
+    ///
+    /// See [`NavRecordIterator::header`](struct.NavRecordIterator.html#method.header).
+    UnknownHeader {
+        /// Column names as published, trimmed
+        columns: Vec<String>,
+        /// Names of no known column, empty if the columns are known but not in the order or
+        /// number of a layout
+        unexpected: Vec<String>,
+    },
+    /// Parse errors exceeded the limit set with
+    /// [`NavRecordIterator::max_errors`](struct.NavRecordIterator.html#method.max_errors) or
+    /// [`max_error_ratio`](struct.NavRecordIterator.html#method.max_error_ratio)
+    TooManyErrors {
+        /// Lines that failed to parse
+        errors: usize,
+        /// Lines read, of any kind
+        lines_read: usize,
+    },
+    /// Line longer than the limit set with
+    /// [`NavRecordIterator::max_line_len`](struct.NavRecordIterator.html#method.max_line_len),
+    /// skipped without being kept
+    LineTooLong {
+        /// Longest line read, in bytes
+        limit: usize,
+        /// 1-based number of the line
+        at_line: usize,
+    },
+    /// Line not valid UTF-8, decoded with
+    /// [`DecodePolicy::Strict`](enum.DecodePolicy.html#variant.Strict) and skipped
+    InvalidUtf8 {
+        /// 1-based number of the line
+        at_line: usize,
+    },
+    /// Record with a lower scheme code than the one before it in its section, see
+    /// [`NavRecordIterator::assert_sorted_within_sections`](struct.NavRecordIterator.html#method.assert_sorted_within_sections)
+    OrderingViolation {
+        /// Section of the record
+        section: SectionContext,
+        /// Code of the record before it
+        prev_code: SchemeCode,
+        /// Code of the record
+        code: SchemeCode,
+        /// 1-based number of the line of the record
+        at_line: usize,
+    },
+    /// Too many lines in a row were neither records nor section or column headers, ending
+    /// parsing; see
+    /// [`NavRecordIterator::max_unclassified_lines`](struct.NavRecordIterator.html#method.max_unclassified_lines)
+    TooManyUnclassifiedLines {
+        /// Most unclassified lines in a row tolerated
+        limit: usize,
+        /// 1-based number of the line exceeding it
+        at_line: usize,
+    },
+    /// Error of a feed downloaded by [`fetch_all_async`](fn.fetch_all_async.html)
+    #[cfg(feature = "fetch")]
+    InUrl {
+        /// URL of the feed
+        url: String,
+        /// Error met downloading or parsing it
+        error: Box<Error>,
+    },
+    /// Error of a feed file read by [`nav_from_files`](fn.nav_from_files.html)
+    InFile {
+        /// Path of the file
+        path: PathBuf,
+        /// Error met parsing or reading it
+        error: Box<Error>,
+    },
+    /// Error of a shard written by [`shard_by_amc`](fn.shard_by_amc.html)
+    InShard {
+        /// Normalized name of the AMC of the shard
+        amc: String,
+        /// Error met opening, writing or finishing it
+        error: Box<Error>,
+    },
+    /// No NAV of a scheme close enough to a date, see
+    /// [`NavHistory::nav_near`](struct.NavHistory.html#method.nav_near)
+    MissingNav {
+        /// Scheme asked for
+        code: SchemeCode,
+        /// Date asked for, `None` if the scheme has no NAV at all
+        date: Option<NavDate>,
+        /// Business days the NAV could be away from `date`
+        tolerance: u32,
+    },
+    /// Return that can't be computed from the NAVs at hand, see
+    /// [`NavHistory::point_to_point_return`](struct.NavHistory.html#method.point_to_point_return)
+    UndefinedReturn {
+        /// Scheme asked for
+        code: SchemeCode,
+        /// Why, e.g. `starting NAV is zero`
+        reason: &'static str,
+    },
+    /// Scheme code indexed more than once, see
+    /// [`NavIndex::try_get_by_code`](struct.NavIndex.html#method.try_get_by_code)
+    DuplicateCode {
+        /// Duplicated code
+        code: SchemeCode,
+        /// Records indexed with it
+        count: usize,
+    },
+    /// Error from csv library
+    #[cfg(feature = "csv")]
+    CsvError(csv::Error),
+    /// File is not a scheme master this build can read, see
+    /// [`SchemeMaster::from_csv`](struct.SchemeMaster.html#method.from_csv)
+    #[cfg(feature = "csv")]
+    InvalidMaster(String),
+    /// Error from arrow library
+    #[cfg(feature = "arrow")]
+    ArrowError(arrow::error::ArrowError),
+    /// Error from parquet library
+    #[cfg(feature = "parquet")]
+    ParquetError(parquet::errors::ParquetError),
+    /// Error from rusqlite library
+    #[cfg(feature = "sqlite")]
+    SqliteError(rusqlite::Error),
+    /// Error from bincode library
+    #[cfg(feature = "bincode")]
+    BincodeError(bincode::Error),
+    /// File is not a snapshot this build can load, see
+    /// [`NavSnapshot::load`](struct.NavSnapshot.html#method.load)
+    #[cfg(feature = "bincode")]
+    InvalidSnapshot(String),
+    /// Page has no NAV table, see [`nav_from_html`](fn.nav_from_html.html)
+    #[cfg(feature = "html")]
+    InvalidHtml(String),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match *self {
             Error::IoError(ref err) => write!(f, "IO error: {}", err),
+            #[cfg(feature = "fetch")]
             Error::ReqwestError(ref err) => write!(f, "Reqwest error: {}", err),
-            Error::BuilderError(ref err) => write!(f, "Builder error: {}", err),
+            Error::BuilderError {
+                ref fields,
+                ref reason,
+                line,
+                ref raw_line,
+            } => {
+                write!(f, "Builder error: {}", reason)?;
+                if !fields.is_empty() {
+                    write!(f, " ({})", fields.join(", "))?;
+                }
+                if let Some(line) = line {
+                    write!(f, " at line {}", line)?;
+                }
+                if let Some(ref raw_line) = raw_line {
+                    write!(f, ": `{}`", raw_line)?;
+                }
+                Ok(())
+            }
             Error::SynomError(ref err) => write!(f, "Synom error: Error parsing line `{}`", err),
-            Error::HttpError(ref err) => write!(f, "Http error: {}.", err.as_str()),
+            Error::ZeroNav { code, at_line } => write!(
+                f,
+                "Missing NAV: scheme {} has a NAV of zero at line {}",
+                code, at_line
+            ),
+            Error::ParseFailure {
+                field,
+                at_byte,
+                ref found,
+                expected,
+                line,
+                ref raw_line,
+            } => {
+                write!(
+                    f,
+                    "Parse error: expected {} for {}, found ",
+                    expected, field
+                )?;
+                if found.is_empty() {
+                    write!(f, "nothing")?;
+                } else {
+                    write!(f, "`{}`", found)?;
+                }
+                if let Some(line) = line {
+                    write!(f, " at line {}", line)?;
+                }
+                write!(f, ", byte {}", at_byte)?;
+                field::write_caret(f, raw_line, at_byte)
+            }
+            #[cfg(feature = "fetch")]
+            Error::HttpError {
+                ref status,
+                ref url,
+                ref body_snippet,
+            } => {
+                write!(f, "Http error: {} from {}", status, url)?;
+                if let Some(ref snippet) = body_snippet {
+                    // One line, for alerts
+                    write!(f, ": ")?;
+                    for (i, word) in snippet.split_whitespace().enumerate() {
+                        if i > 0 {
+                            write!(f, " ")?;
+                        }
+                        write!(f, "{}", word)?;
+                    }
+                }
+                Ok(())
+            }
+            #[cfg(feature = "blocking")]
+            Error::IncompleteDownload {
+                ref url,
+                received,
+                expected,
+            } => write!(
+                f,
+                "Incomplete download: {} bytes of {} from {}",
+                received, expected, url
+            ),
+            Error::UnexpectedContent {
+                ref first_line,
+                ref url,
+            } => {
+                write!(f, "Unexpected content")?;
+                if let Some(ref url) = url {
+                    write!(f, " from {}", url)?;
+                }
+                write!(f, ", not a NAV feed: `{}`", first_line)
+            }
+            Error::UnknownHeader {
+                ref columns,
+                ref unexpected,
+            } => {
+                if unexpected.is_empty() {
+                    write!(f, "Unknown header layout: `{}`", columns.join(";"))
+                } else {
+                    let names: Vec<String> = unexpected
+                        .iter()
+                        .map(|name| format!("`{}`", name))
+                        .collect();
+                    write!(
+                        f,
+                        "Unknown header layout, unexpected columns: {}",
+                        names.join(", ")
+                    )
+                }
+            }
+            #[cfg(feature = "fetch")]
+            Error::NoRecords { amc, from, to } => write!(
+                f,
+                "No records: the portal has no NAV history for AMC {} from {} to {}",
+                amc,
+                from.feed_format(),
+                to.feed_format()
+            ),
+            Error::TooManyErrors { errors, lines_read } => write!(
+                f,
+                "Too many errors: {} lines failed to parse in {} lines read",
+                errors, lines_read
+            ),
+            Error::LineTooLong { limit, at_line } => write!(
+                f,
+                "Line too long: line {} is longer than {} bytes",
+                at_line, limit
+            ),
+            Error::InvalidUtf8 { at_line } => {
+                write!(
+                    f,
+                    "Invalid UTF-8: line {} has bytes that are not UTF-8",
+                    at_line
+                )
+            }
+            Error::OrderingViolation {
+                ref section,
+                prev_code,
+                code,
+                at_line,
+            } => write!(
+                f,
+                "Out of order: scheme {} follows scheme {} at line {}, in {} ({})",
+                code, prev_code, at_line, section.amc, section.category
+            ),
+            Error::TooManyUnclassifiedLines { limit, at_line } => write!(
+                f,
+                "Too many unclassified lines: more than {} lines in a row up to line {} are \
+                 neither records nor headers",
+                limit, at_line
+            ),
+            #[cfg(feature = "fetch")]
+            Error::InUrl { ref url, ref error } => write!(f, "{}: {}", url, error),
+            Error::InFile {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {}", path.display(), error),
+            Error::InShard { ref amc, ref error } => write!(f, "Shard {}: {}", amc, error),
+            Error::MissingNav {
+                code,
+                date: Some(date),
+                tolerance,
+            } => write!(
+                f,
+                "Missing NAV: scheme {} has no NAV within {} business days of {}",
+                code, tolerance, date
+            ),
+            Error::MissingNav {
+                code, date: None, ..
+            } => write!(f, "Missing NAV: scheme {} has no NAVs", code),
+            Error::UndefinedReturn { code, reason } => {
+                write!(f, "Undefined return of scheme {}: {}", code, reason)
+            }
+            Error::DuplicateCode { code, count } => write!(
+                f,
+                "Duplicate scheme code: {} records of scheme {} are indexed",
+                count, code
+            ),
+            #[cfg(feature = "csv")]
+            Error::CsvError(ref err) => write!(f, "CSV error: {}", err),
+            #[cfg(feature = "csv")]
+            Error::InvalidMaster(ref reason) => write!(f, "Invalid scheme master: {}", reason),
+            #[cfg(feature = "arrow")]
+            Error::ArrowError(ref err) => write!(f, "Arrow error: {}", err),
+            #[cfg(feature = "parquet")]
+            Error::ParquetError(ref err) => write!(f, "Parquet error: {}", err),
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(ref err) => write!(f, "SQLite error: {}", err),
+            #[cfg(feature = "bincode")]
+            Error::BincodeError(ref err) => write!(f, "Bincode error: {}", err),
+            #[cfg(feature = "bincode")]
+            Error::InvalidSnapshot(ref reason) => write!(f, "Invalid snapshot: {}", reason),
+            #[cfg(feature = "html")]
+            Error::InvalidHtml(ref reason) => write!(f, "Invalid HTML NAV page: {}", reason),
         }
     }
 }
@@ -98,19 +1349,95 @@ impl std::fmt::Display for Error {
 impl std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
-            Error::IoError(ref err) => err.description(),
-            Error::ReqwestError(ref err) => err.description(),
-            Error::BuilderError(ref err) => err.as_str(),
+            Error::IoError(..) => "I/O error",
+            #[cfg(feature = "fetch")]
+            Error::ReqwestError(..) => "HTTP request error",
+            Error::BuilderError { ref reason, .. } => reason.as_str(),
             Error::SynomError(ref err) => err.as_str(),
-            Error::HttpError(ref err) => err.as_str(),
+            Error::ZeroNav { .. } => "zero NAV",
+            Error::ParseFailure { expected, .. } => expected,
+            #[cfg(feature = "fetch")]
+            Error::HttpError { ref status, .. } => status.as_str(),
+            #[cfg(feature = "blocking")]
+            Error::IncompleteDownload { .. } => "incomplete download",
+            Error::UnexpectedContent { .. } => "unexpected content",
+            Error::UnknownHeader { .. } => "unknown header layout",
+            #[cfg(feature = "fetch")]
+            Error::NoRecords { .. } => "no records",
+            Error::TooManyErrors { .. } => "too many errors",
+            Error::LineTooLong { .. } => "line too long",
+            Error::InvalidUtf8 { .. } => "invalid UTF-8",
+            Error::OrderingViolation { .. } => "records out of order",
+            Error::TooManyUnclassifiedLines { .. } => "too many unclassified lines",
+            #[cfg(feature = "fetch")]
+            Error::InUrl { .. } => "error in feed at URL",
+            Error::InFile { .. } => "error in feed file",
+            Error::InShard { .. } => "error in shard",
+            Error::MissingNav { .. } => "missing NAV",
+            Error::UndefinedReturn { reason, .. } => reason,
+            Error::DuplicateCode { .. } => "duplicate scheme code",
+            #[cfg(feature = "csv")]
+            Error::CsvError(..) => "CSV error",
+            #[cfg(feature = "csv")]
+            Error::InvalidMaster(ref reason) => reason.as_str(),
+            #[cfg(feature = "arrow")]
+            Error::ArrowError(..) => "Arrow error",
+            #[cfg(feature = "parquet")]
+            Error::ParquetError(..) => "Parquet error",
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(..) => "SQLite error",
+            #[cfg(feature = "bincode")]
+            Error::BincodeError(..) => "bincode error",
+            #[cfg(feature = "bincode")]
+            Error::InvalidSnapshot(ref reason) => reason.as_str(),
+            #[cfg(feature = "html")]
+            Error::InvalidHtml(ref reason) => reason.as_str(),
         }
     }
 
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match *self {
             Error::IoError(ref err) => Some(err),
+            #[cfg(feature = "fetch")]
             Error::ReqwestError(ref err) => Some(err),
-            Error::HttpError(..) | Error::BuilderError(..) | Error::SynomError(..) => None,
+            #[cfg(feature = "fetch")]
+            Error::InUrl { ref error, .. } => Some(error.as_ref()),
+            Error::InFile { ref error, .. } => Some(error.as_ref()),
+            Error::InShard { ref error, .. } => Some(error.as_ref()),
+            #[cfg(feature = "csv")]
+            Error::CsvError(ref err) => Some(err),
+            #[cfg(feature = "arrow")]
+            Error::ArrowError(ref err) => Some(err),
+            #[cfg(feature = "parquet")]
+            Error::ParquetError(ref err) => Some(err),
+            #[cfg(feature = "sqlite")]
+            Error::SqliteError(ref err) => Some(err),
+            #[cfg(feature = "bincode")]
+            Error::BincodeError(ref err) => Some(err),
+            #[cfg(feature = "bincode")]
+            Error::InvalidSnapshot(..) => None,
+            #[cfg(feature = "csv")]
+            Error::InvalidMaster(..) => None,
+            #[cfg(feature = "html")]
+            Error::InvalidHtml(..) => None,
+            #[cfg(feature = "fetch")]
+            Error::HttpError { .. } | Error::NoRecords { .. } => None,
+            #[cfg(feature = "blocking")]
+            Error::IncompleteDownload { .. } => None,
+            Error::BuilderError { .. }
+            | Error::SynomError(..)
+            | Error::ZeroNav { .. }
+            | Error::ParseFailure { .. }
+            | Error::UnexpectedContent { .. }
+            | Error::UnknownHeader { .. }
+            | Error::TooManyErrors { .. }
+            | Error::LineTooLong { .. }
+            | Error::InvalidUtf8 { .. }
+            | Error::OrderingViolation { .. }
+            | Error::TooManyUnclassifiedLines { .. }
+            | Error::MissingNav { .. }
+            | Error::UndefinedReturn { .. }
+            | Error::DuplicateCode { .. } => None,
         }
     }
 }
@@ -121,327 +1448,4112 @@ impl From<io::Error> for Error {
     }
 }
 
+#[cfg(feature = "fetch")]
 impl From<reqwest::Error> for Error {
     fn from(e: reqwest::Error) -> Self {
         Error::ReqwestError(e)
     }
 }
 
-type Result<T> = std::result::Result<T, Error>;
-
-named!(
-    parse_isin -> Option<String>,
-    map!(
-        alt!( alphanumeric | tag!("---") | tag!("-") ),
-        |isin: &str| {
-            match isin {
-                "-" | "---" => None,
-                s => Some(s.to_string())
-            }
-        }
-    )
-);
-
-named!(
-    parse_name -> (String, FundPlan, Option<String>),
-    map!(
-        take_until!(";"),
-        |s: &str| {
-            let name = s.trim().to_string();
-            let plan = if name.to_uppercase().find("DIRECT").is_some() {
-                FundPlan::Direct
-            } else {
-                FundPlan::Regular
-            };
-            let option = None;
-            (name, plan, option)
-        }
-    )
-);
+#[cfg(feature = "csv")]
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::CsvError(e)
+    }
+}
 
-fn custom_seperator(input: &str) -> IResult<&str, ()> {
-    let mut pos = 0;
-    for ch in input.chars() {
-        if ch.is_whitespace() || ch == ';' {
-            pos += 1;
-        } else {
-            break;
-        }
+#[cfg(feature = "arrow")]
+impl From<arrow::error::ArrowError> for Error {
+    fn from(e: arrow::error::ArrowError) -> Self {
+        Error::ArrowError(e)
     }
-    IResult::Done(&input[pos..], ())
 }
 
-fn alphanumeric(input: &str) -> IResult<&str, &str> {
-    let mut pos = 0;
-    for ch in input.chars() {
-        if ch.is_alphanumeric() {
-            pos += 1;
-        } else {
-            break;
-        }
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        Error::ParquetError(e)
     }
+}
 
-    if pos > 0 {
-        IResult::Done(&input[pos..], &input[..pos])
-    } else {
-        IResult::Error
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::SqliteError(e)
     }
 }
 
-fn digit(input: &str) -> IResult<&str, u32> {
-    let mut pos = 0;
-    for ch in input.chars() {
-        if ch.is_digit(10) {
-            pos += 1;
-        } else {
-            break;
-        }
+#[cfg(feature = "bincode")]
+impl From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Error::BincodeError(e)
     }
+}
 
-    if pos > 0 {
-        IResult::Done(&input[pos..], input[..pos].parse::<u32>().unwrap())
-    } else {
-        IResult::Error
+type Result<T> = std::result::Result<T, Error>;
+
+/// ISIN, `None` for `-` or `---`
+// Linted in the expansion of nom's `tag!`
+#[allow(clippy::manual_strip)]
+fn parse_isin(input: &str) -> IResult<&str, Option<&str>> {
+    match alt!(input, alphanumeric | tag!("---") | tag!("-")) {
+        IResult::Done(rest, "-") | IResult::Done(rest, "---") => IResult::Done(rest, None),
+        IResult::Done(rest, isin) => IResult::Done(rest, Some(isin)),
+        IResult::Error => IResult::Error,
     }
 }
 
-fn double(input: &str) -> IResult<&str, f64> {
-    let mut pos = 0;
-    let mut seen_decimal_sign = false;
-    for ch in input.chars() {
-        if ch.is_digit(10) {
-            pos += 1;
-        } else if !seen_decimal_sign && ch == '.' {
-            seen_decimal_sign = true;
-            pos += 1;
-        } else {
-            break;
+/// `line` without separators after its last field, which some mirrors append
+pub(crate) fn trim_trailing_separators(line: &str) -> &str {
+    line.trim_end_matches(|ch: char| ch == ';' || ch.is_whitespace())
+}
+
+/// Name up to the separator before the trailing fields, semicolons included, and its plan
+///
+/// NAV, prices and date never contain `;`, so the name is everything before the last two
+/// fields, four in the legacy layout or six, ISINs included, in the history layout. Names of
+/// old archives contain semicolons, e.g. `XYZ Fund; Series 2 - Growth`. Empty columns between
+/// the fields are extra separators, except for the blank prices of the legacy and history
+/// layouts.
+fn parse_name(input: &str, layout: Layout) -> IResult<&str, (&str, Option<FundPlan>)> {
+    let (trailing, collapse) = match layout {
+        Layout::Modern => (2, true),
+        Layout::Legacy => (4, false),
+        Layout::History => (6, false),
+    };
+    let fields = trim_trailing_separators(input).as_bytes();
+    let mut end = fields.len();
+    for _ in 0..trailing {
+        match fields[..end].iter().rposition(|&byte| byte == b';') {
+            Some(pos) if collapse => end = separator_start(fields, pos),
+            Some(pos) => end = pos,
+            None => return IResult::Error,
         }
     }
-
-    if pos > 0 {
-        IResult::Done(&input[pos..], input[..pos].parse::<f64>().unwrap())
-    } else {
+    let end = separator_start(fields, end);
+    let name = &input[..end];
+    if name.trim().is_empty() {
         IResult::Error
+    } else {
+        IResult::Done(&input[end..], (name, plan::name_plan(name)))
     }
 }
 
-fn date(input: &str) -> IResult<&str, chrono::NaiveDate> {
-    if let Some(slice) = input.get(..11) {
-        if let Ok(dt) = chrono::NaiveDate::parse_from_str(slice, "%d-%b-%Y") {
-            IResult::Done(&input[11..], dt)
-        } else {
-            IResult::Error
+/// Start of the separator ending at the `;` at `pos`, taking in empty columns before it
+fn separator_start(fields: &[u8], pos: usize) -> usize {
+    let mut start = pos;
+    loop {
+        let blank = fields[..start]
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map_or(0, |last| last + 1);
+        match blank.checked_sub(1) {
+            Some(before) if fields[before] == b';' => start = before,
+            _ => return start,
         }
-    } else {
-        IResult::Error
     }
 }
 
-named!(
-    parse_record -> NavRecordBuilder,
-    do_parse!(
-        code: digit >>
-        custom_seperator >>
-        isin: parse_isin >>
-        custom_seperator >>
-        isin_dr: parse_isin >>
-        custom_seperator >>
-        name_plan: parse_name >>
-        custom_seperator >>
-        nav: double >>
-        custom_seperator >>
-        date: date >>
-        ({
-            let mut rb = NavRecordBuilder::default();
-            let (name, plan, option) = name_plan;
-            rb.code(code)
-                .isin(isin)
-                .isin_dr(isin_dr)
-                .name(name)
-                .plan(plan)
-                .option(option)
-                .nav(nav)
-                .date(date);
-            rb
-        })
-    )
-);
-
-named!(
-    parse_scheme -> (Option<FundMaturity>, Option<String>, String),
-    do_parse!(
-        maturity: take_until!("(") >>
-        tag!("(") >>
-        scheme: option!( terminated!( take_until!(" - "), tag!(" - ") ) ) >>
-        category: take_until!(")") >>
-        ({
-            let muc = maturity.trim().to_uppercase();
-            let maturity = if muc.starts_with("CLOSE") {
-                Some(FundMaturity::CloseEnded)
-            } else if muc.starts_with("OPEN") {
-                Some(FundMaturity::OpenEnded)
-            } else {
-                None
-            };
-            (
-                maturity,
-                scheme.map(|s: &str| s.to_string()),
-                category.to_string()
-            )
-        })
-    )
-);
+/// Fewest delimiters of a record line, or column header, of a feed delimited by other than `;`
+const MIN_RECORD_DELIMITERS: usize = 3;
 
-/// Parses NAV data from [AMFI](https://www.amfiindia.com) portal
-///
-/// Primary access method for latest data. See [example](index.html#basic-usage)
-pub fn daily_nav() -> Result<NavRecordIterator<reqwest::Response>> {
-    nav_from_url(BASE_URL)
+/// Field delimiter of a feed, `b';'` by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Delimiter(u8);
+
+impl Default for Delimiter {
+    fn default() -> Self {
+        Delimiter(b';')
+    }
 }
 
-/// Parses NAV data from provided `url`
+/// `line` of a feed delimited by `delimiter`, with `;` in its place if it is a record line
+/// or column header, i.e. has at least [`MIN_RECORD_DELIMITERS`]
 ///
-/// Parse NAV data from any mirror site providing same data format.
-pub fn nav_from_url<T: AsRef<str>>(url: T) -> Result<NavRecordIterator<reqwest::Response>> {
-    let response = reqwest::get(url.as_ref())?;
-    if response.status().is_success() {
-        Ok(NavRecordIterator::new(response))
+/// Names may contain `;` once replaced, see [`parse_name`].
+fn with_semicolons(line: &str, delimiter: Delimiter) -> Cow<'_, str> {
+    let Delimiter(delimiter) = delimiter;
+    if delimiter == b';'
+        || line.bytes().filter(|&byte| byte == delimiter).count() < MIN_RECORD_DELIMITERS
+    {
+        return Cow::Borrowed(line);
+    }
+    Cow::Owned(line.replace(char::from(delimiter), ";"))
+}
+
+/// `line` without C0 control characters other than tabs, e.g. stray vertical tabs of archives
+pub(crate) fn strip_controls(line: &str) -> Cow<'_, str> {
+    let stray = |ch: char| ch < ' ' && ch != '\t';
+    // Control characters are single bytes, never part of another character in UTF-8
+    if line.bytes().any(|byte| stray(char::from(byte))) {
+        Cow::Owned(line.chars().filter(|&ch| !stray(ch)).collect())
     } else {
-        Err(Error::HttpError(response.status()))
+        Cow::Borrowed(line)
     }
 }
 
-/// Parses NAV data from local file
-///
-/// Parse NAV data from local copy in same data format.
-pub fn nav_from_file<P: AsRef<Path>>(path: P) -> Result<NavRecordIterator<File>> {
-    let file = File::open(path)?;
-    Ok(NavRecordIterator::new(file))
+/// Case-insensitive `contains` for an ASCII `needle`, without allocating
+pub(crate) fn contains_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    let (haystack, needle) = (haystack.as_bytes(), needle.as_bytes());
+    let first = needle[0].to_ascii_lowercase();
+    // Searched in every record name, so compared whole only where the first byte matches
+    haystack.len() >= needle.len()
+        && haystack[..=haystack.len() - needle.len()]
+            .iter()
+            .enumerate()
+            .any(|(at, byte)| {
+                byte.to_ascii_lowercase() == first
+                    && haystack[at..at + needle.len()].eq_ignore_ascii_case(needle)
+            })
 }
 
-enum LineType {
-    Record,
-    Amc,
-    Scheme,
-    Blank,
-    Header,
+/// Case-insensitive `starts_with` for an ASCII `prefix`, without allocating
+pub(crate) fn starts_with_ignore_ascii_case(text: &str, prefix: &str) -> bool {
+    text.as_bytes()
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix.as_bytes()))
 }
 
-/// Open/Closed Funds
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum FundMaturity {
-    /// Open Ended Funds
-    OpenEnded,
-    /// Close Ended Funds
-    CloseEnded,
+/// Byte length of the longest prefix of `input` whose chars satisfy `pred`
+///
+/// Parsers slice `input` by this length, so it must count bytes rather than chars to stay
+/// on a char boundary.
+fn prefix_len<F: FnMut(char) -> bool>(input: &str, mut pred: F) -> usize {
+    input
+        .char_indices()
+        .find(|&(_, ch)| !pred(ch))
+        .map_or(input.len(), |(pos, _)| pos)
 }
 
-/// Fund Plans are identified on best effort basis. By default plans are Regular.
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum FundPlan {
-    /// Regular Plan
-    Regular,
-    /// Direct Plan
-    Direct,
+/// Field separator: `;`, and the spaces and tabs around it
+///
+/// Other control characters are stripped from record lines before parsing, see
+/// [`strip_controls`].
+fn custom_seperator(input: &str) -> IResult<&str, ()> {
+    let pos = prefix_len(input, |ch| {
+        ch == ';' || ch == '\t' || (ch.is_whitespace() && !ch.is_control())
+    });
+    IResult::Done(&input[pos..], ())
 }
 
-/// Iterator over [`NavRecord`](NavRecord)
-pub struct NavRecordIterator<T> {
-    reader: BufReader<T>,
-    amc: String,
-    category: String,
-    scheme: Option<String>,
-    maturity: Option<FundMaturity>,
-    buf: String,
-    bailout: bool,
+fn alphanumeric(input: &str) -> IResult<&str, &str> {
+    let pos = prefix_len(input, char::is_alphanumeric);
+
+    if pos > 0 {
+        IResult::Done(&input[pos..], &input[..pos])
+    } else {
+        IResult::Error
+    }
 }
 
-impl<T: Read> NavRecordIterator<T> {
-    fn new(response: T) -> Self {
-        NavRecordIterator {
-            reader: BufReader::new(response),
-            amc: String::new(),
-            category: String::new(),
-            scheme: None,
-            buf: String::new(),
-            bailout: false,
-            maturity: None,
-        }
+fn digit(input: &str) -> IResult<&str, u32> {
+    let pos = prefix_len(input, |ch| ch.is_ascii_digit());
+
+    // Codes too large for u32 are errors, not panics
+    match input[..pos].parse::<u32>() {
+        Ok(code) => IResult::Done(&input[pos..], code),
+        Err(_) => IResult::Error,
     }
-    fn line_type(&self) -> LineType {
-        let mut lt = LineType::Blank;
-        if self.buf.starts_with("Scheme") {
-            lt = LineType::Header;
-        } else if self.buf.find(";").is_some() {
-            lt = LineType::Record;
-        } else if self.buf.find("Ended Scheme").is_some() {
-            lt = LineType::Scheme;
-        } else if !self.buf.trim().is_empty() {
-            lt = LineType::Amc;
+}
+
+/// Number and its text
+fn double(input: &str) -> IResult<&str, (f64, &str)> {
+    let mut seen_decimal_sign = false;
+    let pos = prefix_len(input, |ch| {
+        if ch == '.' && !seen_decimal_sign {
+            seen_decimal_sign = true;
+            true
+        } else {
+            ch.is_ascii_digit()
         }
-        lt
+    });
+
+    // A lone `.` is not a number, nor hundreds of digits overflowing to infinity
+    match input[..pos].parse::<f64>() {
+        Ok(nav) if nav.is_finite() => IResult::Done(&input[pos..], (nav, &input[..pos])),
+        _ => IResult::Error,
     }
 }
 
-impl<T: Read> Iterator for NavRecordIterator<T> {
-    type Item = Result<NavRecord>;
-
+/// Date and its text
+fn date(input: &str) -> IResult<&str, (NavDate, &str)> {
+    // `get` rather than indexing, byte 11 may fall inside a multi-byte char
+    match (input.get(..11), input.get(11..)) {
+        (Some(slice), Some(rest)) => match NavDate::parse_feed(slice) {
+            Ok(dt) => IResult::Done(rest, (dt, slice)),
+            Err(_) => IResult::Error,
+        },
+        _ => IResult::Error,
+    }
+}
+
+/// Fields of a record line, borrowing from the line
+#[derive(Debug)]
+struct RecordFields<'a> {
+    code: u32,
+    isin: Option<&'a str>,
+    isin_dr: Option<&'a str>,
+    plan: Option<FundPlan>,
+    nav: f64,
+    repurchase_price: Option<f64>,
+    sale_price: Option<f64>,
+    date: NavDate,
+    raw_name: &'a str,
+    raw_nav: &'a str,
+    raw_date: &'a str,
+}
+
+impl RecordFields<'_> {
+    /// Plan told by the name, or the default plan
+    fn plan(&self) -> (FundPlan, PlanSource) {
+        match self.plan {
+            Some(plan) => (plan, PlanSource::NameToken),
+            None => (FundPlan::Regular, PlanSource::Default),
+        }
+    }
+}
+
+/// Where and why a record line failed to parse
+#[derive(Debug)]
+struct Failure<'a> {
+    field: Field,
+    /// Text of the line from the failure point, up to the next `;`
+    found: &'a str,
+    expected: &'static str,
+}
+
+impl<'a> Failure<'a> {
+    /// Failure of `field` at the start of `rest`
+    fn at(field: Field, rest: &'a str, expected: &'static str) -> Self {
+        let end = rest.find(';').unwrap_or(rest.len());
+        Failure {
+            field,
+            found: rest[..end].trim_end(),
+            expected,
+        }
+    }
+
+    /// Error of the record `line` in which this failure is, numbered `number` if known
+    fn into_error(self, line: &str, number: Option<usize>) -> Error {
+        Error::ParseFailure {
+            field: self.field,
+            // `found` borrows from `line`
+            at_byte: self.found.as_ptr() as usize - line.as_ptr() as usize,
+            found: self.found.to_string(),
+            expected: self.expected,
+            line: number,
+            raw_line: line.to_string(),
+        }
+    }
+}
+
+/// Output of `parser` at the start of `rest`, which is advanced past it; a failure of
+/// `field` there if it fails
+fn field<'a, O, P>(
+    rest: &mut &'a str,
+    field: Field,
+    expected: &'static str,
+    parser: P,
+) -> std::result::Result<O, Failure<'a>>
+where
+    P: FnOnce(&'a str) -> IResult<&'a str, O>,
+{
+    match parser(rest) {
+        IResult::Done(after, output) => {
+            *rest = after;
+            Ok(output)
+        }
+        IResult::Error => Err(Failure::at(field, rest, expected)),
+    }
+}
+
+/// Skips the field separator at the start of `rest`
+fn separator(rest: &mut &str) {
+    if let IResult::Done(after, ()) = custom_seperator(rest) {
+        *rest = after;
+    }
+}
+
+/// Repurchase and sale prices between NAV and date, and the separators around them
+fn prices<'a>(
+    rest: &mut &'a str,
+    layout: Layout,
+) -> std::result::Result<(Option<f64>, Option<f64>), Failure<'a>> {
+    if layout == Layout::Modern {
+        separator(rest);
+        return Ok((None, None));
+    }
+    // Blank prices leave adjacent separators, which `custom_seperator` would merge
+    let input = rest.trim_start();
+    let expected = "price, or blank, `-` or `N.A.`";
+    let mut fields = input.splitn(4, ';');
+    match (fields.next(), fields.next(), fields.next(), fields.next()) {
+        (Some(""), Some(repurchase), Some(sale), Some(after)) => {
+            let repurchase = price(repurchase).ok_or_else(|| {
+                Failure::at(Field::RepurchasePrice, repurchase.trim_start(), expected)
+            })?;
+            let sale = price(sale)
+                .ok_or_else(|| Failure::at(Field::SalePrice, sale.trim_start(), expected))?;
+            *rest = after.trim_start();
+            Ok((repurchase, sale))
+        }
+        (Some(""), ..) => Err(Failure::at(
+            Field::SalePrice,
+            &input[input.len()..],
+            "prices and date after the NAV",
+        )),
+        _ => Err(Failure::at(Field::Nav, input, "`;` after the NAV")),
+    }
+}
+
+/// ISINs between name and NAV in the history layout, up to the separator before the NAV
+///
+/// Either may be blank for none, so their separators are taken one by one.
+fn history_isins<'a>(
+    rest: &mut &'a str,
+) -> std::result::Result<(Option<&'a str>, Option<&'a str>), Failure<'a>> {
+    let input = *rest;
+    let mut fields = input.splitn(4, ';');
+    match (fields.next(), fields.next(), fields.next(), fields.next()) {
+        (Some(""), Some(isin), Some(isin_dr), Some(after)) => {
+            let isin = history_isin(isin)
+                .ok_or_else(|| Failure::at(Field::Isin, isin.trim_start(), "ISIN, `-` or blank"))?;
+            let isin_dr = history_isin(isin_dr).ok_or_else(|| {
+                Failure::at(Field::IsinDr, isin_dr.trim_start(), "ISIN, `-` or blank")
+            })?;
+            // At the `;` before `after`
+            *rest = &input[input.len() - after.len() - 1..];
+            Ok((isin, isin_dr))
+        }
+        _ => Err(Failure::at(
+            Field::Isin,
+            input,
+            "`;` and ISINs after the name",
+        )),
+    }
+}
+
+/// ISIN of the history layout, `None` for blank, `-` or `---`
+fn history_isin(text: &str) -> Option<Option<&str>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(None);
+    }
+    match parse_isin(text) {
+        IResult::Done("", isin) => Some(isin),
+        _ => None,
+    }
+}
+
+/// Optional price of the legacy layout: a number, or blank, `N.A.` or `-` for none
+fn price(text: &str) -> Option<Option<f64>> {
+    let text = text.trim();
+    if text.is_empty()
+        || text == "-"
+        || text.eq_ignore_ascii_case("N.A.")
+        || text.eq_ignore_ascii_case("NA")
+    {
+        return Some(None);
+    }
+    match double(text) {
+        IResult::Done("", (price, _)) => Some(Some(price)),
+        _ => None,
+    }
+}
+
+/// Parses a record line of `layout`, failing with the field where parsing stopped
+fn parse_record<'a>(
+    input: &'a str,
+    layout: Layout,
+) -> std::result::Result<RecordFields<'a>, Failure<'a>> {
+    let rest = &mut &*input;
+    let code = field(rest, Field::Code, "scheme code", digit)?;
+    separator(rest);
+    let name = |rest: &mut &'a str| {
+        let expected = match layout {
+            Layout::Modern => "scheme name followed by NAV and date",
+            Layout::Legacy => "scheme name followed by NAV, prices and date",
+            Layout::History => "scheme name followed by ISINs, NAV, prices and date",
+        };
+        field(rest, Field::Name, expected, |input| {
+            parse_name(input, layout)
+        })
+    };
+    let (isin, isin_dr, (raw_name, plan)) = if layout == Layout::History {
+        let name = name(rest)?;
+        let (isin, isin_dr) = history_isins(rest)?;
+        (isin, isin_dr, name)
+    } else {
+        let isin = field(rest, Field::Isin, "ISIN or `-`", parse_isin)?;
+        separator(rest);
+        let isin_dr = field(rest, Field::IsinDr, "ISIN or `-`", parse_isin)?;
+        separator(rest);
+        (isin, isin_dr, name(rest)?)
+    };
+    separator(rest);
+    let start = *rest;
+    let (nav, raw_nav) = field(rest, Field::Nav, "decimal number", double)?;
+    // A number ending in other text is not the NAV, e.g. `12.5%`
+    if rest.starts_with(|ch: char| ch != ';' && !ch.is_whitespace()) {
+        return Err(Failure::at(Field::Nav, start, "decimal number"));
+    }
+    let (repurchase_price, sale_price) = prices(rest, layout)?;
+    let (date, raw_date) = field(rest, Field::Date, "date like `21-Jun-2024`", date)?;
+    Ok(RecordFields {
+        code,
+        isin,
+        isin_dr,
+        plan,
+        nav,
+        repurchase_price,
+        sale_price,
+        date,
+        raw_name,
+        raw_nav,
+        raw_date,
+    })
+}
+
+/// Parses NAV data from [AMFI](https://www.amfiindia.com) portal
+///
+/// Primary access method for latest data. See [example](index.html#basic-usage)
+#[cfg(feature = "blocking")]
+pub fn daily_nav() -> Result<NavRecordIterator<reqwest::blocking::Response>> {
+    nav_from_url(BASE_URL)
+}
+
+/// Parses NAV data from provided `url`
+///
+/// Parse NAV data from any mirror site providing same data format.
+#[cfg(feature = "blocking")]
+pub fn nav_from_url<T: AsRef<str>>(
+    url: T,
+) -> Result<NavRecordIterator<reqwest::blocking::Response>> {
+    fetch::get_records(url.as_ref(), None, true)
+}
+
+/// Parses NAV data from [AMFI](https://www.amfiindia.com) portal without blocking
+///
+/// The feed is downloaded completely before parsing starts.
+#[cfg(feature = "async")]
+pub async fn daily_nav_async() -> Result<NavRecordIterator<io::Cursor<Vec<u8>>>> {
+    nav_from_url_async(BASE_URL).await
+}
+
+/// Parses NAV data from provided `url` without blocking
+///
+/// See [`daily_nav_async`](fn.daily_nav_async.html).
+#[cfg(feature = "async")]
+pub async fn nav_from_url_async<T: AsRef<str>>(
+    url: T,
+) -> Result<NavRecordIterator<io::Cursor<Vec<u8>>>> {
+    fetch::get_records_async(url.as_ref(), None, true).await
+}
+
+/// Parses NAV data from local file
+///
+/// Parse NAV data from local copy in same data format.
+pub fn nav_from_file<P: AsRef<Path>>(path: P) -> Result<NavRecordIterator<File>> {
+    let file = File::open(path)?;
+    Ok(NavRecordIterator::new(file))
+}
+
+/// Parses a NAV history download of all AMCs from local file
+///
+/// The portal's history report queried by date alone gives the records of every AMC for
+/// each day of the range, under the usual scheme and AMC headers, in the
+/// [`History`](enum.Layout.html#variant.History) layout with repurchase and sale prices.
+/// Those files run to hundreds of MB, so records are read line by line as yielded, like
+/// [`nav_from_file`](fn.nav_from_file.html), never the whole file at once.
+///
+/// Fails with [`Error::UnexpectedContent`](enum.Error.html#variant.UnexpectedContent) unless
+/// the file starts with the column header of that layout; `nav_from_file` reads any feed.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let records = amfi::nav_history_from_file("fixtures/NAVHistoryAll.txt")?;
+/// for record in records {
+///     let record = record?;
+///     println!("{} {} {} {:?}", record.date, record.amc, record.nav, record.repurchase_price);
+/// }
+/// assert!(amfi::nav_history_from_file("fixtures/NAVOpen.txt").is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn nav_history_from_file<P: AsRef<Path>>(path: P) -> Result<NavRecordIterator<File>> {
+    let mut file = File::open(path)?;
+    let mut start = Vec::new();
+    (&mut file)
+        .take(HISTORY_SNIFF_LEN)
+        .read_to_end(&mut start)?;
+    file.seek(SeekFrom::Start(0))?;
+    let start = String::from_utf8_lossy(&start);
+    let first = start
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if !FeedHeader::is_header(first) || FeedHeader::parse(first)?.layout != Layout::History {
+        return Err(Error::UnexpectedContent {
+            first_line: first.chars().take(SNIFFED_LINE_LEN).collect(),
+            url: None,
+        });
+    }
+    Ok(NavRecordIterator::new(file))
+}
+
+/// Bytes of a file `nav_history_from_file` looks for the column header in
+const HISTORY_SNIFF_LEN: u64 = 4096;
+
+/// Parses NAV data from any reader, e.g. an uploaded file
+///
+/// Available without the `fetch` feature.
+pub fn nav_from_reader<R: Read>(reader: R) -> NavRecordIterator<R> {
+    NavRecordIterator::new(reader)
+}
+
+/// Parses NAV data held in memory
+///
+/// ```rust
+/// let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+///             Axis Mutual Fund\n\
+///             120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n";
+/// let records: Vec<_> = amfi::nav_from_str(feed).collect();
+/// assert_eq!(records.len(), 1);
+/// ```
+pub fn nav_from_str(feed: &str) -> NavRecordIterator<&[u8]> {
+    NavRecordIterator::new(feed.as_bytes())
+}
+
+/// Kind of a feed line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineType {
+    Record,
+    Amc,
+    Scheme,
+    Blank,
+    Header,
+}
+
+impl LineType {
+    /// Classifies a feed line, shared by all readers of the feed
+    ///
+    /// Lines with a `;` are records unless they name known columns, see
+    /// [`FeedHeader`](struct.FeedHeader.html); records start with their scheme code.
+    pub(crate) fn of(line: &str) -> Self {
+        if line.contains(';') {
+            let code_first = line
+                .trim_start()
+                .starts_with(|ch: char| ch.is_ascii_digit());
+            if !code_first && FeedHeader::is_header(line) {
+                LineType::Header
+            } else {
+                LineType::Record
+            }
+        } else if line.trim().is_empty() {
+            LineType::Blank
+        } else if names_schemes(line) {
+            LineType::Scheme
+        } else {
+            LineType::Amc
+        }
+    }
+}
+
+/// Whether `line` has `Ended Scheme` or `Interval Fund Scheme`, as scheme headers do
+///
+/// Looked for in every header line, so found by the `S` of `Scheme`.
+fn names_schemes(line: &str) -> bool {
+    line.match_indices('S').any(|(at, _)| {
+        line[at..].starts_with("Scheme")
+            && (line[..at].ends_with("Ended ") || line[..at].ends_with("Interval Fund "))
+    })
+}
+
+/// Longest first line kept in [`Error::UnexpectedContent`]
+const SNIFFED_LINE_LEN: usize = 200;
+
+/// Error if the first non-blank `line` of a feed doesn't start one
+///
+/// Feeds start with the column header, a scheme or AMC header, or a record. HTML, e.g. of a
+/// captive portal or maintenance page, and other text are rejected.
+pub(crate) fn unexpected_content(line: &str, url: Option<&str>) -> Option<Error> {
+    let line = line.trim_start_matches('\u{feff}').trim();
+    let feed_like = !line.starts_with('<')
+        && match LineType::of(line) {
+            LineType::Header | LineType::Scheme | LineType::Blank => true,
+            LineType::Record => line.starts_with(|c: char| c.is_ascii_digit()),
+            LineType::Amc => contains_ignore_ascii_case(line, "Mutual Fund"),
+        };
+    if feed_like {
+        None
+    } else {
+        Some(Error::UnexpectedContent {
+            first_line: line.chars().take(SNIFFED_LINE_LEN).collect(),
+            url: url.map(str::to_string),
+        })
+    }
+}
+
+/// Column layout of a feed
+///
+/// Current feeds have the columns `Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div
+/// Reinvestment;Scheme Name;Net Asset Value;Date`. Older archives and the history download
+/// of one AMC have `Repurchase Price;Sale Price` between NAV and date, see
+/// [`NavRecord::repurchase_price`](struct.NavRecord.html#structfield.repurchase_price). The
+/// history download of all AMCs, see [`nav_history_from_file`](fn.nav_history_from_file.html),
+/// also has the name right after the code.
+/// Readers tell the layout from the header line, see [`FeedHeader`](struct.FeedHeader.html), or
+/// without one from the columns of the first record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// Six columns, without prices
+    #[default]
+    Modern,
+    /// Eight columns, with repurchase and sale prices
+    Legacy,
+    /// Eight columns of the bulk history download: code, name, ISINs, NAV, repurchase and
+    /// sale prices, date
+    History,
+}
+
+impl Layout {
+    /// Layout of a record line by its column count, and NAV column for names with semicolons
+    ///
+    /// Lines of eight columns are of the history layout when their second column can't be an
+    /// ISIN, e.g. has spaces.
+    fn of_record(line: &str) -> Self {
+        let line = trim_trailing_separators(line);
+        let nav = line.rsplit(';').nth(3).unwrap_or_default();
+        if line.bytes().filter(|&byte| byte == b';').count() >= 7
+            && matches!(double(nav.trim()), IResult::Done("", _))
+        {
+            let second = line.split(';').nth(1).unwrap_or_default().trim();
+            if second
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+            {
+                Layout::Legacy
+            } else {
+                Layout::History
+            }
+        } else {
+            Layout::Modern
+        }
+    }
+}
+
+/// Open/Closed Funds, open ended by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FundMaturity {
+    /// Open Ended Funds
+    #[default]
+    #[cfg_attr(feature = "serde", serde(alias = "OpenEnded"))]
+    OpenEnded,
+    /// Close Ended Funds
+    #[cfg_attr(feature = "serde", serde(alias = "CloseEnded"))]
+    CloseEnded,
+}
+
+/// Fund Plans are identified on best effort basis. By default plans are Regular.
+///
+/// Names naming no plan, as in feeds from before Direct plans were introduced in 2013, are
+/// Regular unless resolved or set `Unspecified` by
+/// [`NavRecordIterator::missing_plan`](struct.NavRecordIterator.html#method.missing_plan).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FundPlan {
+    /// Regular Plan
+    #[default]
+    #[cfg_attr(feature = "serde", serde(alias = "Regular"))]
+    Regular,
+    /// Direct Plan
+    #[cfg_attr(feature = "serde", serde(alias = "Direct"))]
+    Direct,
+    /// Plan not named, see [`MissingPlanPolicy`](enum.MissingPlanPolicy.html)
+    #[cfg_attr(feature = "serde", serde(alias = "Unspecified"))]
+    Unspecified,
+}
+
+/// Error parsing [`FundPlan`](enum.FundPlan.html) or [`FundMaturity`](enum.FundMaturity.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVariantError {
+    kind: &'static str,
+    found: String,
+}
+
+impl std::fmt::Display for ParseVariantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "unrecognized {} `{}`", self.kind, self.found)
+    }
+}
+
+impl std::error::Error for ParseVariantError {}
+
+/// Lowercase words, ignoring punctuation such as `-` and `_`
+fn variant_key(s: &str) -> String {
+    s.to_lowercase()
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl std::fmt::Display for FundMaturity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match *self {
+            FundMaturity::OpenEnded => "Open Ended",
+            FundMaturity::CloseEnded => "Close Ended",
+        })
+    }
+}
+
+impl FromStr for FundMaturity {
+    type Err = ParseVariantError;
+
+    /// Accepts `Open Ended`, `open-ended`, `Close Ended Schemes`, `closed ended` and similar
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match variant_key(s).as_str() {
+            "open" | "open ended" | "openended" | "open ended scheme" | "open ended schemes" => {
+                Ok(FundMaturity::OpenEnded)
+            }
+            "close"
+            | "closed"
+            | "close ended"
+            | "closed ended"
+            | "closeended"
+            | "close ended scheme"
+            | "close ended schemes" => Ok(FundMaturity::CloseEnded),
+            _ => Err(ParseVariantError {
+                kind: "fund maturity",
+                found: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for FundPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match *self {
+            FundPlan::Regular => "Regular",
+            FundPlan::Direct => "Direct",
+            FundPlan::Unspecified => "Unspecified",
+        })
+    }
+}
+
+impl FromStr for FundPlan {
+    type Err = ParseVariantError;
+
+    /// Accepts `Direct`, `direct plan`, `REGULAR`, `Regular Plan`, `unspecified` and similar
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match variant_key(s).as_str() {
+            "direct" | "direct plan" => Ok(FundPlan::Direct),
+            "regular" | "regular plan" => Ok(FundPlan::Regular),
+            "unspecified" => Ok(FundPlan::Unspecified),
+            _ => Err(ParseVariantError {
+                kind: "fund plan",
+                found: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Handling of records published with a NAV of zero, see
+/// [`NavRecordIterator::zero_nav_policy`](struct.NavRecordIterator.html#method.zero_nav_policy)
+///
+/// Zero NAVs (`0.0000`) usually belong to schemes winding up or awaiting their first NAV and
+/// are not prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZeroNavPolicy {
+    /// Yield the record as published
+    #[default]
+    Keep,
+    /// Leave the record out
+    Skip,
+    /// Yield [`Error::ZeroNav`](enum.Error.html#variant.ZeroNav) in place of the record, so a
+    /// NAV of zero is neither taken for a price nor left out unnoticed
+    TreatAsMissing,
+}
+
+/// Handling of record lines before the first AMC header of a feed, see
+/// [`NavRecordIterator::headerless`](struct.NavRecordIterator.html#method.headerless)
+///
+/// Such lines are usually of a truncated feed, or of a fragment of one copied without its
+/// headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderlessPolicy {
+    /// Yield an [`Error::BuilderError`](enum.Error.html#variant.BuilderError) for the line
+    #[default]
+    Error,
+    /// Yield the record with an empty [`amc`](struct.NavRecord.html#structfield.amc), counted
+    /// apart by [`FeedSummary::without_amc`](struct.FeedSummary.html#structfield.without_amc)
+    Keep,
+}
+
+/// Order of the dates of a feed, see
+/// [`NavRecordIterator::stop_after_date`](struct.NavRecordIterator.html#method.stop_after_date)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateOrder {
+    /// Oldest first, as in history downloads and concatenated daily feeds
+    #[default]
+    Ascending,
+    /// Newest first
+    Descending,
+}
+
+/// Plan of records whose name names none and no
+/// [`PlanResolver`](trait.PlanResolver.html) knew, see
+/// [`NavRecordIterator::missing_plan`](struct.NavRecordIterator.html#method.missing_plan)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingPlanPolicy {
+    /// [`FundPlan::Regular`](enum.FundPlan.html#variant.Regular), as in earlier releases
+    #[default]
+    Regular,
+    /// [`FundPlan::Unspecified`](enum.FundPlan.html#variant.Unspecified), telling apart
+    /// schemes of feeds from before Direct plans were introduced
+    Unspecified,
+}
+
+/// Iterator over [`NavRecord`](NavRecord)
+///
+/// ## Filtering
+///
+/// The `filter_*` adapters narrow the records yielded and can be chained; a record must match
+/// all of them:
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::{FundMaturity, FundPlan};
+///
+/// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+///     .filter_amc("axis")
+///     .filter_plan(FundPlan::Direct)
+///     .filter_maturity(FundMaturity::OpenEnded);
+/// for record in records {
+///     assert!(record?.amc.starts_with("Axis"));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// AMC, category and maturity are known from the section headers, so record lines of
+/// sections that don't match are skipped without being parsed. Consequently malformed lines
+/// in skipped sections produce no errors. The plan is derived from the scheme name, so with
+/// [`filter_plan`](#method.filter_plan) alone every record line is parsed and malformed lines
+/// are still reported. I/O errors and malformed section headers are always reported.
+pub struct NavRecordIterator<T> {
+    reader: BufReader<Source<T>>,
+    buf: String,
+    bytes: Vec<u8>,
+    charset: Charset,
+    lossy_lines: usize,
+    span: FeedSpan,
+    tee: Option<Tee>,
+    map: Option<RecordHook>,
+    #[cfg(feature = "fetch")]
+    response: Option<ResponseMeta>,
+    state: FeedState,
+    /// Summary of the items yielded, see [`NavRecordIterator::summary_so_far`]
+    summary: FeedSummary,
+}
+
+impl<T: Read> NavRecordIterator<T> {
+    fn new(response: T) -> Self {
+        Self::with_charset(response, Charset::Utf8)
+    }
+
+    pub(crate) fn with_charset(response: T, charset: Charset) -> Self {
+        NavRecordIterator {
+            reader: BufReader::new(Source::new(response)),
+            buf: String::new(),
+            bytes: Vec::new(),
+            charset,
+            lossy_lines: 0,
+            span: FeedSpan::new(),
+            tee: None,
+            map: None,
+            #[cfg(feature = "fetch")]
+            response: None,
+            state: FeedState::default(),
+            summary: FeedSummary::default(),
+        }
+    }
+
+    /// Reads the whole rest of the feed from the inner reader into memory, after the bytes
+    /// already buffered
+    fn read_rest(&mut self) -> io::Result<()> {
+        let mut rest = Vec::new();
+        let read = self.reader.get_mut().inner_mut().read_to_end(&mut rest);
+        // Bytes read before an error are kept, the error is met again reading the rest
+        self.reader.get_mut().read_ahead(rest);
+        read.map(drop)
+    }
+
+    /// Parses in `span`, that of the download of the feed
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn in_span(mut self, span: FeedSpan) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Parses the body of the `response` described
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn with_response(mut self, response: ResponseMeta) -> Self {
+        self.response = Some(response);
+        self
+    }
+
+    /// What the download of the feed observed, `None` for feeds not downloaded
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "blocking")]
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let records = amfi::daily_nav()?;
+    /// let response = records.response_meta().unwrap();
+    /// println!("{} {} {:?}", response.status, response.url, response.last_modified);
+    /// // Validators to ask `is_nav_updated` later
+    /// let since = response.fetch_meta();
+    /// # drop(since);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "blocking"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "fetch")]
+    pub fn response_meta(&self) -> Option<&ResponseMeta> {
+        self.response.as_ref()
+    }
+
+    /// Bytes, records and errors of the parse so far, and its timing once the feed was read
+    /// to its end
+    ///
+    /// Timing takes two timestamps per feed, and a download taken before parsing, as
+    /// [`daily_nav_async`](fn.daily_nav_async.html) does, is timed apart. See
+    /// [`ParseStats::throughput`](struct.ParseStats.html#method.throughput).
+    pub fn stats(&self) -> ParseStats {
+        self.state.stats()
+    }
+
+    /// Summary of the records and errors yielded so far, as
+    /// [`summarize`](fn.summarize.html) would count them
+    ///
+    /// Kept up to date as items are yielded, so feeds streamed elsewhere without being
+    /// collected are summarized all the same; see [`finish`](#method.finish) for that of the
+    /// whole feed.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut records = amfi::nav_from_file("fixtures/NAVOpen.txt")?;
+    /// for record in records.by_ref().take(10) {
+    ///     let _ = record?;
+    /// }
+    /// assert_eq!(records.summary_so_far().records, 10);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summary_so_far(&self) -> &FeedSummary {
+        &self.summary
+    }
+
+    /// Summary of the whole feed, reading and counting the items not yielded yet
+    ///
+    /// The summary is that [`summarize`](fn.summarize.html) makes of all the items of the
+    /// feed, those yielded before included. Use
+    /// [`summary_so_far`](#method.summary_so_far) to leave the rest of the feed unread.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut records = amfi::nav_from_file("fixtures/NAVOpen.txt")?;
+    /// for record in records.by_ref() {
+    ///     // Sent on without being kept
+    ///     drop(record?);
+    /// }
+    /// let summary = records.finish();
+    /// assert_eq!((summary.records, summary.errors), (30, 0));
+    /// println!("{:?}", summary.feed_date());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn finish(mut self) -> FeedSummary {
+        for _ in self.by_ref() {}
+        self.summary
+    }
+
+    /// Sets the time taken to download the feed before it is parsed
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn fetched_in(mut self, duration: std::time::Duration) -> Self {
+        self.state.meter.fetched_in(duration);
+        self
+    }
+
+    /// Number of lines read so far whose invalid bytes were replaced with `U+FFFD`
+    ///
+    /// Downloaded feeds are decoded with the charset of their `Content-Type`, and lossily
+    /// rather than failing on invalid bytes, see [`decode`](#method.decode); the first such
+    /// line is logged as a warning. Other feeds are strict UTF-8 by default, so this is zero
+    /// for them.
+    pub fn lossy_lines(&self) -> usize {
+        self.lossy_lines
+    }
+
+    /// Sets what decoding does with bytes that are not valid UTF-8, lossy for downloaded
+    /// feeds and strict for others by default
+    ///
+    /// Strictly, each line with invalid bytes is an
+    /// [`Error::InvalidUtf8`](enum.Error.html#variant.InvalidUtf8) and parsing goes on with
+    /// the next line. Lossily, invalid bytes are replaced with `U+FFFD`, counted in
+    /// [`ParseStats::replaced_chars`](struct.ParseStats.html#structfield.replaced_chars).
+    /// Feeds declaring another charset, with the `encoding` feature, are decoded without
+    /// error either way.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use amfi::DecodePolicy;
+    ///
+    /// let mut records = amfi::nav_from_file("fixtures/NAVStrayByte.txt")?;
+    /// assert!(records.any(|record| record.is_err()));
+    ///
+    /// let mut records = amfi::nav_from_file("fixtures/NAVStrayByte.txt")?.decode(DecodePolicy::Lossy);
+    /// assert!(records.by_ref().all(|record| record.is_ok()));
+    /// assert_eq!(records.stats().replaced_chars, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decode(mut self, policy: DecodePolicy) -> Self {
+        self.state.decode = policy;
+        self
+    }
+
+    /// Reads the next line into `buf` through the charset, copying its bytes to the tee
+    ///
+    /// Bytes of a line past the length limit are read through without being kept, so a line
+    /// without end takes no more memory than the limit.
+    fn read_line(&mut self) -> io::Result<LineRead> {
+        self.bytes.clear();
+        let limit = self.state.lines.max_len;
+        // Room for the newline, or for one byte too many
+        let mut read = (&mut self.reader)
+            .take(limit.saturating_add(1) as u64)
+            .read_until(b'\n', &mut self.bytes)?;
+        let too_long = read > limit && self.bytes.last() != Some(&b'\n');
+        copy_to_tee(&mut self.tee, &mut self.state, &self.bytes)?;
+        if too_long {
+            loop {
+                let available = match self.reader.fill_buf() {
+                    Ok(available) => available,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                let (used, complete) = match available.iter().position(|&byte| byte == b'\n') {
+                    Some(newline) => (newline + 1, true),
+                    None => (available.len(), available.is_empty()),
+                };
+                copy_to_tee(&mut self.tee, &mut self.state, &available[..used])?;
+                self.reader.consume(used);
+                read += used;
+                if complete {
+                    return Ok(LineRead::TooLong(read));
+                }
+            }
+        }
+        if self.charset == Charset::Utf8 && self.buf.is_empty() {
+            // Valid lines are moved into the buffer rather than copied
+            match String::from_utf8(std::mem::take(&mut self.bytes)) {
+                Ok(line) => {
+                    self.bytes = std::mem::replace(&mut self.buf, line).into_bytes();
+                    return Ok(LineRead::Line(read));
+                }
+                Err(e) => self.bytes = e.into_bytes(),
+            }
+        }
+        match self.state.decode(self.charset, &self.bytes, &mut self.buf) {
+            Some(0) => {}
+            Some(_) => {
+                if self.lossy_lines == 0 {
+                    warn!(
+                        "line {} is not valid in its charset, replacing invalid bytes",
+                        self.state.line() + 1
+                    );
+                }
+                self.lossy_lines += 1;
+            }
+            None => return Ok(LineRead::Invalid(read)),
+        }
+        Ok(LineRead::Line(read))
+    }
+
+    /// 1-based number of the last line read, i.e. the line of the last record yielded
+    pub fn line(&self) -> usize {
+        self.state.line()
+    }
+
+    /// Column header read so far, `None` before it or for feeds without one
+    ///
+    /// Its [`layout`](struct.FeedHeader.html#structfield.layout) is the layout records are
+    /// parsed in. Headers of an unknown layout end parsing with
+    /// [`Error::UnknownHeader`](enum.Error.html#variant.UnknownHeader).
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut records = amfi::nav_from_file("fixtures/NAVLegacy.txt")?;
+    /// records.next();
+    /// assert_eq!(records.header().unwrap().layout, amfi::Layout::Legacy);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn header(&self) -> Option<&FeedHeader> {
+        self.state.header.as_ref()
+    }
+
+    /// 1-based number of the feed being read in a concatenation of feeds, 1 for a single feed
+    ///
+    /// A column header following section headers or records, or another column header,
+    /// starts the next feed: its records belong to none of the sections before.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let day = std::fs::read_to_string("fixtures/NAVOpen.txt")?;
+    /// let days = format!("{}{}", day, day);
+    /// let mut records = amfi::nav_from_str(&days).stamp_segments(true);
+    /// assert_eq!(records.next().unwrap()?.segment, Some(1));
+    /// assert_eq!(records.segment(), 1);
+    /// assert_eq!(records.last().unwrap()?.segment, Some(2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn segment(&self) -> usize {
+        self.state.segment()
+    }
+
+    /// AMC of the section the next record would belong to, empty before the first AMC header
+    ///
+    /// Like the other `current_*` accessors this reflects the lines consumed so far; records
+    /// are yielded as soon as their line is read, so after `next` returns a record this is
+    /// the record's section.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut records = amfi::nav_from_file("fixtures/NAVOpen.txt")?;
+    /// while let Some(record) = records.next() {
+    ///     eprintln!("processing {}: {}", records.current_amc(), record?.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn current_amc(&self) -> &str {
+        &self.state.section.amc
+    }
+
+    /// Category of the current section, see [`current_amc`](#method.current_amc)
+    pub fn current_category(&self) -> &str {
+        &self.state.section.category
+    }
+
+    /// Scheme type of the current section, see [`current_amc`](#method.current_amc)
+    pub fn current_scheme(&self) -> Option<&str> {
+        self.state.section.scheme.as_deref()
+    }
+
+    /// Maturity of the current section, see [`current_amc`](#method.current_amc)
+    pub fn current_maturity(&self) -> Option<FundMaturity> {
+        self.state.section.maturity
+    }
+
+    /// Current section, see [`current_amc`](#method.current_amc), for records of
+    /// [`parse_record_line`](fn.parse_record_line.html)
+    pub fn current_section(&self) -> &SectionContext {
+        &self.state.section
+    }
+
+    /// Skips the rest of the current section
+    ///
+    /// Record lines up to the next AMC or scheme header are read without being parsed, so
+    /// they yield neither records nor errors. Nothing is read until the next call to
+    /// `next`.
+    pub fn skip_current_section(&mut self) {
+        self.state.skip_section = true;
+    }
+
+    /// Yields only records whose AMC contains `pattern`, ignoring case
+    pub fn filter_amc(mut self, pattern: &str) -> Self {
+        self.state.filters.amc.push(TextFilter::contains(pattern));
+        self.state.update_section();
+        self
+    }
+
+    /// Yields only records whose AMC is `name`, ignoring case and surrounding whitespace
+    pub fn filter_amc_exact(mut self, name: &str) -> Self {
+        self.state.filters.amc.push(TextFilter::exact(name));
+        self.state.update_section();
+        self
+    }
+
+    /// Yields only records whose category contains `pattern`, ignoring case
+    pub fn filter_category(mut self, pattern: &str) -> Self {
+        self.state
+            .filters
+            .category
+            .push(TextFilter::contains(pattern));
+        self.state.update_section();
+        self
+    }
+
+    /// Yields only records whose category is `name`, ignoring case and surrounding whitespace
+    pub fn filter_category_exact(mut self, name: &str) -> Self {
+        self.state.filters.category.push(TextFilter::exact(name));
+        self.state.update_section();
+        self
+    }
+
+    /// Yields only records of `maturity` sections
+    pub fn filter_maturity(mut self, maturity: FundMaturity) -> Self {
+        self.state.filters.maturity = Some(maturity);
+        self.state.update_section();
+        self
+    }
+
+    /// Yields only records of `plan`
+    pub fn filter_plan(mut self, plan: FundPlan) -> Self {
+        self.state.filters.plan = Some(plan);
+        self
+    }
+
+    /// Consults `resolver` for the plan of schemes whose name contains neither `Direct` nor
+    /// `Regular`, which are otherwise Regular, see
+    /// [`NavRecord::plan_source`](struct.NavRecord.html#structfield.plan_source)
+    ///
+    /// Resolved plans are those seen by [`filter_plan`](#method.filter_plan).
+    pub fn plan_resolver(mut self, resolver: Arc<dyn PlanResolver>) -> Self {
+        self.state.plan_resolver = Some(resolver);
+        self
+    }
+
+    /// Sets the plan of records whose name names none and the
+    /// [`plan_resolver`](#method.plan_resolver) doesn't know,
+    /// [`Regular`](enum.MissingPlanPolicy.html#variant.Regular) by default
+    ///
+    /// Their [`plan_source`](struct.NavRecord.html#structfield.plan_source) is `Default`
+    /// either way.
+    ///
+    /// ```rust
+    /// use amfi::{FundPlan, MissingPlanPolicy};
+    ///
+    /// let feed = "Axis Mutual Fund\n120465;INF846K01AB4;-;Axis Equity Fund - Growth;41.30;02-Jan-2012\n";
+    /// let record = amfi::nav_from_str(feed)
+    ///     .missing_plan(MissingPlanPolicy::Unspecified)
+    ///     .next()
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(record.plan, FundPlan::Unspecified);
+    /// ```
+    pub fn missing_plan(mut self, policy: MissingPlanPolicy) -> Self {
+        self.state.missing_plan = policy;
+        self
+    }
+
+    /// Cleans up names, AMCs and categories as set by `options`, see
+    /// [`NormalizeOptions`](struct.NormalizeOptions.html)
+    pub fn normalize(mut self, options: NormalizeOptions) -> Self {
+        self.state.normalize = options;
+        self
+    }
+
+    /// Yields only records of `kind`, see [`NavRecord::vehicle`](struct.NavRecord.html#method.vehicle)
+    pub fn filter_vehicle(mut self, kind: VehicleKind) -> Self {
+        self.state.filters.vehicle = Some(kind);
+        self
+    }
+
+    /// Skips records of `kind`, e.g. ETFs whose NAV differs from their market price, see
+    /// [`NavRecord::vehicle`](struct.NavRecord.html#method.vehicle)
+    pub fn exclude_vehicle(mut self, kind: VehicleKind) -> Self {
+        self.state.filters.excluded_vehicles.push(kind);
+        self
+    }
+
+    /// Passes each record through `f` before yielding it, dropping it if `f` returns `None`
+    ///
+    /// For corrections applied to every record, e.g. overriding the category of a misfiled
+    /// scheme or rounding NAVs. `f` sees records that parsed and passed the filters, never
+    /// those of skipped sections or errors. Dropped records are counted by
+    /// [`filtered_records`](#method.filtered_records), not as errors, and not by
+    /// [`take_records`](#method.take_records). Called again, the new `f` is applied to what
+    /// the previous ones return. Records handed to
+    /// [`for_each_ref`](#method.for_each_ref) are not passed through it.
+    ///
+    /// A panic in `f` propagates out of `next`; the iterator stays usable, having read the
+    /// line of the record.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let records: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .map_records(|mut record| {
+    ///         record.nav = (record.nav * 100.0).round() / 100.0;
+    ///         Some(record).filter(|record| record.nav > 0.0)
+    ///     })
+    ///     .collect::<Result<_, _>>()?;
+    /// assert_eq!(records[0].nav, 61.43);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn map_records<F>(mut self, mut f: F) -> Self
+    where
+        F: FnMut(NavRecord) -> Option<NavRecord> + Send + 'static,
+    {
+        self.map = Some(match self.map.take() {
+            Some(mut previous) => Box::new(move |record| previous(record).and_then(&mut f)),
+            None => Box::new(f),
+        });
+        self
+    }
+
+    /// Number of records parsed so far but not yielded, failing a record filter such as
+    /// [`filter_plan`](#method.filter_plan) or dropped by
+    /// [`map_records`](#method.map_records)
+    ///
+    /// Record lines of sections skipped by filters aren't parsed and don't count.
+    pub fn filtered_records(&self) -> usize {
+        self.state.filtered
+    }
+
+    /// Keeps the published text of each record in [`NavRecord::raw`](struct.NavRecord.html#structfield.raw)
+    ///
+    /// Off by default, it costs four more strings per record.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let feed = "Axis Mutual Fund\n120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth ;61.4300;21-Jun-2024\r\n";
+    /// let record = amfi::nav_from_str(feed).capture_raw(true).next().unwrap()?;
+    /// let raw = record.raw.unwrap();
+    /// assert_eq!(raw.name, "Axis Bluechip Fund - Direct Plan - Growth ");
+    /// assert_eq!(raw.nav, "61.4300");
+    /// assert_eq!(raw.date, "21-Jun-2024");
+    /// assert!(raw.line.ends_with("21-Jun-2024"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn capture_raw(mut self, capture: bool) -> Self {
+        self.state.capture_raw = capture;
+        self
+    }
+
+    /// Sets [`NavRecord::segment`](struct.NavRecord.html#structfield.segment) of each record
+    /// to the number of the feed it was read from, see [`segment`](#method.segment)
+    ///
+    /// Off by default, for feeds read one at a time.
+    pub fn stamp_segments(mut self, stamp: bool) -> Self {
+        self.state.stamp_segments = stamp;
+        self
+    }
+
+    /// Sets how records with a NAV of zero are yielded, [`Keep`](enum.ZeroNavPolicy.html#variant.Keep) by default
+    ///
+    /// ```rust
+    /// use amfi::ZeroNavPolicy;
+    ///
+    /// let feed = "Axis Mutual Fund\n120465;INF846K01AB4;-;Axis Bluechip Fund - Growth;0.0000;21-Jun-2024\n";
+    /// assert_eq!(amfi::nav_from_str(feed).count(), 1);
+    /// assert_eq!(amfi::nav_from_str(feed).zero_nav_policy(ZeroNavPolicy::Skip).count(), 0);
+    /// ```
+    pub fn zero_nav_policy(mut self, policy: ZeroNavPolicy) -> Self {
+        self.state.zero_nav = policy;
+        self
+    }
+
+    /// Sets how record lines failing to parse are read,
+    /// [`Strict`](enum.RecoveryPolicy.html#variant.Strict) by default
+    ///
+    /// ```rust
+    /// use amfi::RecoveryPolicy;
+    ///
+    /// // No `-` for the missing reinvestment ISIN
+    /// let feed = "Axis Mutual Fund\n120465;INF846K01AB4;Axis Bluechip Fund - Growth;61.43;21-Jun-2024\n";
+    /// let record = amfi::nav_from_str(feed)
+    ///     .recovery(RecoveryPolicy::Lenient)
+    ///     .next()
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert!(record.recovered);
+    /// assert_eq!(record.name, "Axis Bluechip Fund - Growth");
+    /// assert_eq!(record.isin_dr, None);
+    /// ```
+    pub fn recovery(mut self, policy: RecoveryPolicy) -> Self {
+        self.state.recovery = policy;
+        self
+    }
+
+    /// Checks records of each section are sorted by scheme code, as lookups by binary search
+    /// assume; off by default
+    ///
+    /// A record with a lower code than the one before it in its section is an
+    /// [`Error::OrderingViolation`](enum.Error.html#variant.OrderingViolation) instead, or
+    /// under [`RecoveryPolicy::Lenient`](enum.RecoveryPolicy.html#variant.Lenient) yielded
+    /// and counted in [`ParseStats::out_of_order`](struct.ParseStats.html#structfield.out_of_order).
+    /// Records left out by filters are checked, those of sections skipped are not.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use amfi::{Error, RecoveryPolicy};
+    ///
+    /// // 112277 follows 120466 among the Axis large caps
+    /// let errors: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .assert_sorted_within_sections(true)
+    ///     .filter_map(Result::err)
+    ///     .collect();
+    /// assert!(matches!(errors[0], Error::OrderingViolation { at_line: 9, .. }));
+    ///
+    /// let mut records = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .assert_sorted_within_sections(true)
+    ///     .recovery(RecoveryPolicy::Lenient);
+    /// assert_eq!(records.by_ref().filter(Result::is_ok).count(), 30);
+    /// assert_eq!(records.stats().out_of_order, errors.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn assert_sorted_within_sections(mut self, check: bool) -> Self {
+        self.state.order.check(check);
+        self
+    }
+
+    /// Sets the field delimiter of the feed, `b';'` by default, for mirrors delimiting fields
+    /// by `|` or tabs instead
+    ///
+    /// Lines with at least three delimiters are records, or the column header; others are
+    /// told apart as usual, so section headers are read the same whatever the delimiter.
+    ///
+    /// # Panics
+    ///
+    /// If `delimiter` is not an ASCII punctuation character or tab, or is one of `-`, `.` and
+    /// `%`, which fields contain.
+    ///
+    /// ```rust
+    /// let feed = "Axis Mutual Fund\n120465|INF846K01AB4|-|Axis Bluechip Fund - Direct Plan - Growth|61.43|21-Jun-2024\n";
+    /// let record = amfi::nav_from_str(feed).delimiter(b'|').next().unwrap().unwrap();
+    /// assert_eq!(record.name, "Axis Bluechip Fund - Direct Plan - Growth");
+    /// ```
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        assert!(
+            (delimiter.is_ascii_punctuation() || delimiter == b'\t')
+                && !b"-.%".contains(&delimiter),
+            "invalid delimiter {:?}",
+            char::from(delimiter)
+        );
+        self.state.delimiter = Delimiter(delimiter);
+        self
+    }
+
+    /// Sets how record lines before the first AMC header are read,
+    /// [`Error`](enum.HeaderlessPolicy.html#variant.Error) by default
+    ///
+    /// ```rust
+    /// use amfi::HeaderlessPolicy;
+    ///
+    /// let fragment = "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n";
+    /// let error = amfi::nav_from_str(fragment).next().unwrap().unwrap_err();
+    /// assert!(error.to_string().contains("before the first AMC header"));
+    /// let record = amfi::nav_from_str(fragment)
+    ///     .headerless(HeaderlessPolicy::Keep)
+    ///     .next()
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!((record.amc.as_str(), record.category.as_str()), ("", ""));
+    /// ```
+    pub fn headerless(mut self, policy: HeaderlessPolicy) -> Self {
+        self.state.headerless = policy;
+        self
+    }
+
+    /// Copies every byte read from the feed to `writer` before parsing it, e.g. to archive a
+    /// download while parsing it
+    ///
+    /// The copy is identical to the feed, headers and lines failing to parse included. When
+    /// parsing ends, say at [`Error::UnexpectedContent`](enum.Error.html#variant.UnexpectedContent),
+    /// the rest of the feed is copied too and `writer` flushed; iterators dropped before then
+    /// only flush it. Errors writing to `writer` are yielded as
+    /// [`Error::IoError`](enum.Error.html#variant.IoError) and end parsing.
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let archive = std::fs::File::create("NAVAll-2024-06-21.txt")?;
+    /// for record in amfi::nav_from_file("NAVAll.txt")?.tee(std::io::BufWriter::new(archive)) {
+    ///     println!("{}", record?.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tee<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.tee = Some(Tee::new(writer));
+        self
+    }
+
+    /// Sets how ISINs failing validation or not of an Indian mutual fund are parsed,
+    /// [`Lenient`](enum.IsinStrictness.html#variant.Lenient) by default
+    ///
+    /// ```rust
+    /// use amfi::IsinStrictness;
+    ///
+    /// // ISIN of a share, from a column shifted upstream
+    /// let feed = "Axis Mutual Fund\n120465;INE009A01021;-;Axis Bluechip Fund - Growth;61.43;21-Jun-2024\n";
+    /// let record = amfi::nav_from_str(feed).next().unwrap().unwrap();
+    /// assert!(record.isin.is_some());
+    /// let record = amfi::nav_from_str(feed)
+    ///     .isin_strictness(IsinStrictness::Strict)
+    ///     .next()
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(record.isin, None);
+    /// ```
+    pub fn isin_strictness(mut self, strictness: IsinStrictness) -> Self {
+        self.state.isin_strictness = strictness;
+        self
+    }
+
+    /// Skips the first `n` record lines without parsing them, e.g. to resume a parse that
+    /// stopped after `n` records
+    ///
+    /// Section headers are still read, so the records after the skipped lines have their AMC
+    /// and category. Lines of sections left out by [`filter_amc`](#method.filter_amc),
+    /// [`filter_category`](#method.filter_category) and
+    /// [`filter_maturity`](#method.filter_maturity) don't count. Other filters apply to the
+    /// records after the skipped lines, so without them every line skipped is a record or
+    /// error not yielded. Lines skipped aren't parsed: their errors are neither yielded nor
+    /// counted by [`max_errors`](#method.max_errors), [`max_error_ratio`](#method.max_error_ratio)
+    /// or [`summarize`](fn.summarize.html). [`line`](#method.line) and
+    /// [`NavRecord::source_line`](struct.NavRecord.html#structfield.source_line) count all lines.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut records = amfi::nav_from_file("fixtures/NAVOpen.txt")?.skip_records(3);
+    /// let record = records.next().unwrap()?;
+    /// assert_eq!(record.amc, "ICICI Prudential Mutual Fund");
+    /// assert_eq!(record.source_line, Some(13));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn skip_records(mut self, n: usize) -> Self {
+        self.state.slice.skip = n;
+        self
+    }
+
+    /// Ends the iteration after `m` records, errors not counting
+    ///
+    /// Lines after the `m`th record are not read, so neither are their errors. Applies after
+    /// [`skip_records`](#method.skip_records) and the filters.
+    pub fn take_records(mut self, m: usize) -> Self {
+        self.state.slice.take = Some(m);
+        self
+    }
+
+    /// Ends the iteration at the first record past `date`: newer than it for feeds in
+    /// `Ascending` [`DateOrder`](enum.DateOrder.html), older for `Descending`
+    ///
+    /// That record isn't yielded and the lines after it aren't read. Dates are those of parsed
+    /// records, whether the record filters keep them or not; records of sections left out by
+    /// the section filters and those skipped by [`skip_records`](#method.skip_records) aren't
+    /// parsed and can't end the iteration.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use amfi::{DateOrder, NavDate};
+    ///
+    /// let bound = NavDate::parse_feed("20-Jun-2024").unwrap();
+    /// let records = amfi::nav_from_file("fixtures/NAVHistory.txt")?
+    ///     .stop_after_date(bound, DateOrder::Ascending)
+    ///     .filter_map(Result::ok);
+    /// assert!(records.map(|record| record.date).all(|date| date <= bound));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stop_after_date(mut self, date: NavDate, order: DateOrder) -> Self {
+        self.state.slice.stop = Some((date, order));
+        self
+    }
+
+    /// Ends the iteration once more than `max` lines failed to parse, unlimited by default
+    ///
+    /// A line or two failing is normal, most of them failing means the format changed. The
+    /// error of the line exceeding the limit is followed by a final
+    /// [`Error::TooManyErrors`](enum.Error.html#variant.TooManyErrors), after which the
+    /// iterator yields nothing.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let results: Vec<_> = amfi::nav_from_file("fixtures/NAVHalfGarbage.txt")?
+    ///     .max_errors(2)
+    ///     .collect();
+    /// // Records and errors up to the third error, then the verdict
+    /// assert_eq!(results.len(), 7);
+    /// assert!(matches!(
+    ///     results[6],
+    ///     Err(amfi::Error::TooManyErrors { errors: 3, lines_read: 15 })
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_errors(mut self, max: usize) -> Self {
+        self.state.limits.max_errors = Some(max);
+        self
+    }
+
+    /// Ends the iteration once more than `ratio` of the record lines parsed failed, checked
+    /// from `min_lines` record lines on; unlimited by default
+    ///
+    /// See [`max_errors`](#method.max_errors). Record lines of sections skipped by filters
+    /// aren't parsed and don't count.
+    pub fn max_error_ratio(mut self, ratio: f64, min_lines: usize) -> Self {
+        self.state.limits.max_ratio = Some((ratio, min_lines));
+        self
+    }
+
+    /// Skips lines longer than `bytes`, line ending excluded, with an
+    /// [`Error::LineTooLong`](enum.Error.html#variant.LineTooLong) each;
+    /// [`MAX_LINE_LEN`](constant.MAX_LINE_LEN.html) by default
+    ///
+    /// Feed lines are a few hundred bytes at most. Without newlines, e.g. in a binary file or
+    /// a minified page, the whole input would otherwise be held in memory as one line. The
+    /// rest of a longer line is read through without being kept, and parsing goes on with the
+    /// next line.
+    ///
+    /// ```rust
+    /// let feed = format!("{}\nAxis Mutual Fund\n", "x".repeat(100));
+    /// let mut results = amfi::nav_from_str(&feed).sniff_content(false).max_line_len(64);
+    /// assert!(matches!(
+    ///     results.next(),
+    ///     Some(Err(amfi::Error::LineTooLong { limit: 64, at_line: 1 }))
+    /// ));
+    /// assert!(results.next().is_none());
+    /// ```
+    pub fn max_line_len(mut self, bytes: usize) -> Self {
+        self.state.lines.max_len = bytes;
+        self
+    }
+
+    /// Ends the iteration with
+    /// [`Error::TooManyUnclassifiedLines`](enum.Error.html#variant.TooManyUnclassifiedLines)
+    /// once more than `max` lines in a row are neither records nor section or column headers;
+    /// [`MAX_UNCLASSIFIED_LINES`](constant.MAX_UNCLASSIFIED_LINES.html) by default
+    ///
+    /// Lines that are no record, scheme or column header are taken for AMC headers, of which
+    /// a feed has one per section; blank lines don't count or break a run. A long run of them
+    /// means the input is no feed, e.g. a text file, and is not read to its end.
+    pub fn max_unclassified_lines(mut self, max: usize) -> Self {
+        self.state.lines.max_unclassified = max;
+        self
+    }
+
+    /// Sets whether the first non-blank line is checked to start a NAV feed, on by default
+    ///
+    /// Error pages, e.g. of captive portals or AMFI maintenance, are often served with
+    /// status 200. Instead of an error per line of HTML, such content yields a single
+    /// [`Error::UnexpectedContent`](enum.Error.html#variant.UnexpectedContent) and ends
+    /// the iteration; downloads fail with it before returning the iterator. Turn it off for
+    /// mirrors starting their feed with something else, e.g. an unusual AMC name.
+    ///
+    /// ```rust
+    /// let page = "<!DOCTYPE html>\n<html><body>Under maintenance</body></html>\n";
+    /// let results: Vec<_> = amfi::nav_from_str(page).collect();
+    /// assert_eq!(results.len(), 1);
+    /// assert!(matches!(results[0], Err(amfi::Error::UnexpectedContent { .. })));
+    /// assert_eq!(amfi::nav_from_str(page).sniff_content(false).count(), 0);
+    /// ```
+    pub fn sniff_content(mut self, sniff: bool) -> Self {
+        self.state.trust_content = !sniff;
+        self
+    }
+
+    /// Sets the capacity of the read buffer to `bytes`, at least 1; 8 KiB by default
+    ///
+    /// Larger buffers take fewer reads, hence system calls, over a whole feed, smaller ones
+    /// less memory. Bytes already buffered, e.g. by a download checking the start of its body,
+    /// are kept. The records and errors yielded are the same whatever the capacity.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?.with_buffer_capacity(64 * 1024);
+    /// assert_eq!(records.count(), 30);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_buffer_capacity(self, bytes: usize) -> Self {
+        let buffered = self.reader.buffer().to_vec();
+        let mut source = self.reader.into_inner();
+        source.unread(&buffered);
+        NavRecordIterator {
+            reader: BufReader::with_capacity(bytes.max(1), source),
+            ..self
+        }
+    }
+
+    /// Batches of up to `size` records, each with the errors met while parsing them
+    ///
+    /// Sections carry over from one batch to the next as they do between records, so records
+    /// of every batch have the AMC and category of their place in the feed. Only the last batch
+    /// may have fewer records, or none but errors.
+    ///
+    /// # Panics
+    ///
+    /// If `size` is 0.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// for batch in amfi::nav_from_file("fixtures/NAVHalfGarbage.txt")?.chunks(500) {
+    ///     println!("{} records, {} errors", batch.records.len(), batch.errors.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chunks(self, size: usize) -> NavChunks<T> {
+        NavChunks::new(self, size)
+    }
+
+    /// Calls `f` as the section of each AMC ends, for progress of long parses
+    ///
+    /// A section ends at the header of another AMC or the end of the feed, having yielded
+    /// [`AmcProgress::records`](struct.AmcProgress.html#structfield.records); a header
+    /// repeating the AMC of the section, as at the start of the next of concatenated feeds,
+    /// continues it. Feeds listing an AMC under several scheme headers report it for each run
+    /// of its sections, with the same [`index`](struct.AmcProgress.html#structfield.index).
+    /// `f` is called from `next`, before the first record of the next section is returned.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let total = amfi::amc_list(std::fs::File::open("fixtures/NAVOpen.txt")?)?.len();
+    /// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .on_amc_done(|done| {
+    ///         println!("finished {}, {} of {} AMCs done", done.amc, done.index, total)
+    ///     })
+    ///     .amc_total(total);
+    /// for record in records {
+    ///     record?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_amc_done<F: FnMut(&AmcProgress)>(self, f: F) -> AmcProgressRecords<T, F> {
+        AmcProgressRecords::new(self, f)
+    }
+
+    /// Fails if the start of the body downloaded from `url` is not a NAV feed
+    ///
+    /// Judges the first non-blank line if it is already buffered and complete, or starts
+    /// markup; otherwise leaves it to parsing.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn check_content(&mut self, url: &str) -> Result<()> {
+        if self.state.trust_content {
+            return Ok(());
+        }
+        let start = String::from_utf8_lossy(self.reader.fill_buf()?);
+        let first = start
+            .split_inclusive('\n')
+            .find(|line| !line.trim().is_empty());
+        match first {
+            Some(line) if line.ends_with('\n') || line.trim_start().starts_with('<') => {
+                unexpected_content(line, Some(url)).map_or(Ok(()), Err)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Longest line read by default, see
+/// [`NavRecordIterator::max_line_len`](struct.NavRecordIterator.html#method.max_line_len), 4 MiB
+pub const MAX_LINE_LEN: usize = 4 * 1024 * 1024;
+
+/// Most unclassified lines in a row read by default, see
+/// [`NavRecordIterator::max_unclassified_lines`](struct.NavRecordIterator.html#method.max_unclassified_lines)
+pub const MAX_UNCLASSIFIED_LINES: usize = 1000;
+
+/// Largest file read whole by [`NavRecordIterator::read_ahead`](struct.NavRecordIterator.html#method.read_ahead),
+/// 64 MiB
+pub const READ_AHEAD_LIMIT: u64 = 64 * 1024 * 1024;
+
+impl NavRecordIterator<File> {
+    /// With `true`, reads the whole file into memory now if it is at most
+    /// [`READ_AHEAD_LIMIT`](constant.READ_AHEAD_LIMIT.html) bytes, parsing from memory
+    ///
+    /// A few large reads instead of one per buffer help on network filesystems. Larger files,
+    /// and files whose size is unknown, are read through the buffer as by default, with
+    /// `false`. Reading ahead can't be undone. An I/O error reading ahead is not reported
+    /// here: the bytes read before it are parsed, then the rest of the file is read, and an
+    /// error again is yielded as usual. The records and errors yielded are the same either
+    /// way.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let records = amfi::nav_from_file("fixtures/NAVOpen.txt")?.read_ahead(true);
+    /// assert_eq!(records.count(), 30);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_ahead(mut self, ahead: bool) -> Self {
+        let fits = |file: &File| file.metadata().is_ok_and(|m| m.len() <= READ_AHEAD_LIMIT);
+        if ahead && fits(self.reader.get_ref().inner()) {
+            let _ = self.read_rest();
+        }
+        self
+    }
+}
+
+impl<T: Read> Iterator for NavRecordIterator<T> {
+    type Item = Result<NavRecord>;
+
     fn next(&mut self) -> Option<Self::Item> {
-        let mut item = None;
+        let span = self.span.clone();
+        let _entered = span.enter();
+        let item = loop {
+            if let Some(error) = self.state.too_many_errors() {
+                break Err(error);
+            }
+            let item = self.next_with(FeedState::parse_line)?;
+            match (item, &mut self.map) {
+                (Ok(record), Some(map)) => match map(record) {
+                    Some(record) => break Ok(record),
+                    None => self.state.drop_record(),
+                },
+                (item, _) => break item,
+            }
+        };
+        match item {
+            Ok(ref record) => self.summary.add(record),
+            Err(_) => self.summary.errors += 1,
+        }
+        Some(item)
+    }
+}
+
+/// Hook of [`NavRecordIterator::map_records`]
+type RecordHook = Box<dyn FnMut(NavRecord) -> Option<NavRecord> + Send>;
+
+impl<T: Read> NavRecordIterator<T> {
+    /// Reads lines until `parse` makes an item of one
+    pub(crate) fn next_with<I, F>(&mut self, mut parse: F) -> Option<Result<I>>
+    where
+        F: FnMut(&mut FeedState, &str) -> Option<Result<I>>,
+    {
+        self.state.start();
+        while !self.state.is_done() {
+            self.buf.clear();
+            match self.read_line() {
+                Ok(LineRead::Line(0)) => break,
+                Err(e) => {
+                    trace_event!(warn, line = self.state.line() + 1, error = %e, "read failed");
+                    return Some(Err(e.into()));
+                }
+                Ok(LineRead::Line(read)) => {
+                    self.state.read(read);
+                    if let Some(item) = parse(&mut self.state, &self.buf) {
+                        return Some(item);
+                    }
+                }
+                Ok(LineRead::TooLong(read)) => {
+                    self.state.read(read);
+                    return Some(Err(self.state.line_too_long()));
+                }
+                Ok(LineRead::Invalid(read)) => {
+                    self.state.read(read);
+                    return Some(Err(self.state.invalid_utf8()));
+                }
+            }
+        }
+        if let Some(tee) = self.tee.take() {
+            if let Err(e) = tee.finish(&mut self.reader) {
+                return Some(Err(e.into()));
+            }
+        }
+        self.state.finish();
+        None
+    }
+}
+
+/// Copies `bytes` read from the feed to `tee`, if any, ending parsing if that fails
+fn copy_to_tee(tee: &mut Option<Tee>, state: &mut FeedState, bytes: &[u8]) -> io::Result<()> {
+    if let Some(copy) = tee {
+        if let Err(e) = copy.write(bytes) {
+            // The copy is no longer that of the feed
+            *tee = None;
+            state.bailout = true;
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Line read by [`NavRecordIterator::read_line`], with the number of bytes read
+enum LineRead {
+    /// Line read into the buffer, the end of the feed if empty
+    Line(usize),
+    /// Line longer than the limit, read through
+    TooLong(usize),
+    /// Line not valid UTF-8, decoded strictly
+    Invalid(usize),
+}
+
+/// Section context carried from line to line of a feed
+///
+/// Shared by the readers of a feed, which only split it into lines.
+#[derive(Debug, Default)]
+pub(crate) struct FeedState {
+    section: SectionContext,
+    bailout: bool,
+    filters: Filters,
+    skip_section: bool,
+    capture_raw: bool,
+    stamp_segments: bool,
+    zero_nav: ZeroNavPolicy,
+    isin_strictness: IsinStrictness,
+    plan_resolver: Option<Arc<dyn PlanResolver>>,
+    missing_plan: MissingPlanPolicy,
+    headerless: HeaderlessPolicy,
+    /// Field delimiter of the feed, `b';'` unless set
+    delimiter: Delimiter,
+    decode: DecodePolicy,
+    recovery: RecoveryPolicy,
+    normalize: NormalizeOptions,
+    header: Option<FeedHeader>,
+    /// Feeds read before the current one, in a concatenation of feeds
+    segments_before: usize,
+    layout: Option<Layout>,
+    line: usize,
+    sniffed: bool,
+    trust_content: bool,
+    limits: ErrorLimits,
+    lines: LineLimits,
+    meter: Meter,
+    /// AMC sections ended, tracked while their progress is reported
+    amcs: Option<AmcTracker>,
+    order: CodeOrder,
+    /// Unclassified lines in a row, up to the current line
+    unclassified: usize,
+    slice: RecordSlice,
+    record_lines: usize,
+    /// Records parsed but not yielded, see [`NavRecordIterator::filtered_records`]
+    filtered: usize,
+    errors: usize,
+    tripped: bool,
+    trace: FeedTrace,
+}
+
+/// Parse errors tolerated before giving up on a feed, unlimited by default
+#[derive(Debug, Default)]
+pub(crate) struct ErrorLimits {
+    max_errors: Option<usize>,
+    /// Largest ratio of errors to record lines, and record lines parsed before checking it
+    max_ratio: Option<(f64, usize)>,
+}
+
+impl ErrorLimits {
+    fn exceeded(&self, errors: usize, record_lines: usize) -> bool {
+        self.max_errors.is_some_and(|max| errors > max)
+            || self.max_ratio.is_some_and(|(ratio, min_lines)| {
+                record_lines >= min_lines.max(1) && errors as f64 > ratio * record_lines as f64
+            })
+    }
+}
+
+/// Bounds on the lines of an input that may be no feed
+#[derive(Debug)]
+pub(crate) struct LineLimits {
+    /// Longest line read, in bytes
+    max_len: usize,
+    /// Most unclassified lines in a row
+    max_unclassified: usize,
+}
+
+impl Default for LineLimits {
+    fn default() -> Self {
+        LineLimits {
+            max_len: MAX_LINE_LEN,
+            max_unclassified: MAX_UNCLASSIFIED_LINES,
+        }
+    }
+}
+
+/// Records of a feed yielded, all of them by default
+#[derive(Debug, Default)]
+pub(crate) struct RecordSlice {
+    /// Record lines left to skip unparsed
+    skip: usize,
+    /// Records left to yield
+    take: Option<usize>,
+    /// Date past which parsing ends, and the order of the dates
+    stop: Option<(NavDate, DateOrder)>,
+}
+
+impl RecordSlice {
+    /// Whether a record of `date` is past the date bound
+    fn past_bound(&self, date: NavDate) -> bool {
+        match self.stop {
+            Some((bound, DateOrder::Ascending)) => date > bound,
+            Some((bound, DateOrder::Descending)) => date < bound,
+            None => false,
+        }
+    }
+}
+
+impl FeedState {
+    /// Plan of a `record` whose name names none, as known to the resolver, else as set by the
+    /// missing plan policy
+    fn resolve_plan(&self, record: &mut NavRecordRef<'_>) {
+        if record.plan_source != PlanSource::Default {
+            return;
+        }
+        if let Some(resolver) = &self.plan_resolver {
+            if let Some(plan) = resolver.resolve(record.isin.or(record.isin_dr), record.code) {
+                record.plan = plan;
+                record.plan_source = PlanSource::Resolver;
+                return;
+            }
+        }
+        if self.missing_plan == MissingPlanPolicy::Unspecified {
+            record.plan = FundPlan::Unspecified;
+        }
+    }
+}
+
+/// What a line means to the parser, see [`FeedState::advance`]
+pub(crate) enum Step {
+    /// Record line to parse in the current section
+    Record,
+    /// Record line of a section left out by the filters
+    Skip,
+    /// Column header
+    Header,
+    /// Blank line
+    Blank,
+    /// AMC header, now the AMC of the section
+    Amc,
+    /// Scheme header, now the maturity, scheme and category of the section
+    Scheme,
+    /// Record line that can't be parsed where it is
+    Invalid(Error),
+    /// Malformed section header, ending parsing
+    Bailout(Error),
+}
+
+impl FeedState {
+    /// Whether a malformed section header ended parsing
+    pub(crate) fn is_done(&self) -> bool {
+        self.bailout || self.slice.take == Some(0)
+    }
+
+    /// 1-based number of the last line parsed
+    pub(crate) fn line(&self) -> usize {
+        self.line
+    }
+
+    /// 1-based number of the feed being read in a concatenation of feeds
+    pub(crate) fn segment(&self) -> usize {
+        self.segments_before + 1
+    }
+
+    /// Section the next record line belongs to
+    #[cfg(feature = "rayon")]
+    pub(crate) fn section(&self) -> &SectionContext {
+        &self.section
+    }
+
+    /// Column layout of the feed, known from its header or first record line
+    pub(crate) fn layout(&self) -> Layout {
+        self.layout.unwrap_or_default()
+    }
+
+    /// Skips checking the first line starts a feed, see
+    /// [`NavRecordIterator::sniff_content`]
+    #[cfg(feature = "stream")]
+    pub(crate) fn trust_content(&mut self, trust: bool) {
+        self.trust_content = trust;
+    }
+
+    /// Starts timing the parse, when reading the first line
+    #[inline]
+    pub(crate) fn start(&mut self) {
+        self.meter.start();
+    }
+
+    /// Counts `bytes` of the feed read
+    pub(crate) fn read(&mut self, bytes: usize) {
+        self.meter.read(bytes);
+        self.trace.read(bytes);
+    }
+
+    /// Ends the feed, emitting the summary of the `tracing` feature
+    pub(crate) fn finish(&mut self) {
+        self.meter.finish();
+        if let Some(amcs) = &mut self.amcs {
+            amcs.end(self.meter.records());
+        }
+        self.trace.finish(self.line);
+    }
+
+    /// Counts and timing of the parse so far
+    pub(crate) fn stats(&self) -> ParseStats {
+        self.meter.stats(self.errors)
+    }
+
+    /// Tracks the AMC sections ended, to report them with [`FeedState::amcs_done`]
+    pub(crate) fn track_amcs(&mut self) {
+        self.amcs.get_or_insert_with(AmcTracker::default);
+    }
+
+    /// AMC sections ended since the last call, none unless tracked
+    pub(crate) fn amcs_done(&mut self) -> Vec<AmcProgress> {
+        self.amcs.as_mut().map_or_else(Vec::new, AmcTracker::take)
+    }
+
+    /// Parses the next `line` of the feed, newline included, returning its record or error
+    pub(crate) fn parse_line(&mut self, line: &str) -> Option<Result<NavRecord>> {
+        let capture_raw = self.capture_raw;
+        self.parse_line_with(line, |result| {
+            result.map(|record| record.into_record(capture_raw))
+        })
+    }
+
+    /// Parses the next `line` of the feed, newline included, handing its borrowed record or
+    /// error to `f`
+    pub(crate) fn parse_line_with<O, F>(&mut self, line: &str, f: F) -> Option<O>
+    where
+        F: FnOnce(Result<NavRecordRef<'_>>) -> O,
+    {
+        let delimited = with_semicolons(line, self.delimiter);
+        let step = self.advance(&delimited);
+        self.parse_step(step, line, &delimited, f)
+    }
+
+    /// Parses `line`, newline included, read by [`advance`](#method.advance) as `step` from
+    /// `delimited`, handing its borrowed record or error to `f`
+    pub(crate) fn parse_step<O, F>(
+        &mut self,
+        step: Step,
+        line: &str,
+        delimited: &str,
+        f: F,
+    ) -> Option<O>
+    where
+        F: FnOnce(Result<NavRecordRef<'_>>) -> O,
+    {
+        if let Step::Record | Step::Invalid(_) = step {
+            if self.slice.skip > 0 {
+                self.slice.skip -= 1;
+                return None;
+            }
+            self.record_lines += 1;
+        }
+        let stripped;
+        let mut reread = String::new();
+        let item = match step {
+            Step::Record => {
+                stripped = self.normalize.record_line(delimited);
+                let layout = self.layout();
+                let recovered = self.recovery == RecoveryPolicy::Lenient
+                    && parse_record(&stripped, layout).map_or(true, |f| recovery::suspect(&f))
+                    && match recovery::recover(&stripped, layout) {
+                        Some(reading) => {
+                            reread = reading;
+                            true
+                        }
+                        None => false,
+                    };
+                let text = if recovered { &reread } else { &*stripped };
+                match self
+                    .section
+                    .record_ref(line, text, Some(self.line), layout, &self.normalize)
+                {
+                    Ok(record) if self.slice.past_bound(record.date) => {
+                        trace_event!(debug, line = self.line, date = %record.date, "past date bound");
+                        self.bailout = true;
+                        None
+                    }
+                    Ok(mut record) => {
+                        record.recovered = recovered;
+                        let strictness = self.isin_strictness;
+                        record.isin = record.isin.filter(|isin| strictness.keeps(isin));
+                        record.isin_dr = record.isin_dr.filter(|isin| strictness.keeps(isin));
+                        self.resolve_plan(&mut record);
+                        if self.stamp_segments {
+                            record.segment = u32::try_from(self.segment()).ok();
+                        }
+                        let follows = self.order.follows(record.code);
+                        if follows.is_some() && self.recovery == RecoveryPolicy::Lenient {
+                            self.meter.out_of_order();
+                        }
+                        if let Some(prev_code) =
+                            follows.filter(|_| self.recovery != RecoveryPolicy::Lenient)
+                        {
+                            Some(Err(Error::OrderingViolation {
+                                section: self.section.clone(),
+                                prev_code,
+                                code: record.code,
+                                at_line: self.line,
+                            }))
+                        } else if !self.filters.record_matches(&record) {
+                            self.filtered += 1;
+                            None
+                        } else if record.nav == 0.0 {
+                            match self.zero_nav {
+                                ZeroNavPolicy::Keep => Some(Ok(record)),
+                                ZeroNavPolicy::Skip => None,
+                                ZeroNavPolicy::TreatAsMissing => Some(Err(Error::ZeroNav {
+                                    code: record.code,
+                                    at_line: self.line,
+                                })),
+                            }
+                        } else {
+                            Some(Ok(record))
+                        }
+                    }
+                    Err(error) => Some(Err(error)),
+                }
+            }
+            Step::Skip | Step::Header | Step::Blank | Step::Amc | Step::Scheme => None,
+            Step::Invalid(error) | Step::Bailout(error) => Some(Err(error)),
+        };
+        self.trace.parsed(&item, self.line);
+        match item {
+            Some(Ok(_)) => {
+                self.meter.record(true);
+                if let Some(take) = &mut self.slice.take {
+                    *take -= 1;
+                }
+            }
+            Some(Err(_)) => {
+                self.errors += 1;
+                self.tripped = self.limits.exceeded(self.errors, self.record_lines);
+            }
+            None => {}
+        }
+        item.map(f)
+    }
+
+    /// Counts a record parsed but dropped by the iterator rather than yielded
+    pub(crate) fn drop_record(&mut self) {
+        self.meter.record(false);
+        self.filtered += 1;
+        if let Some(take) = &mut self.slice.take {
+            *take += 1;
+        }
+    }
+
+    /// Error ending parsing once the error limits were exceeded, returned once
+    pub(crate) fn too_many_errors(&mut self) -> Option<Error> {
+        if self.tripped && !self.bailout {
+            self.bailout = true;
+            trace_event!(
+                warn,
+                errors = self.errors,
+                line = self.line,
+                "too many errors"
+            );
+            Some(Error::TooManyErrors {
+                errors: self.errors,
+                lines_read: self.line,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Longest line read, in bytes
+    #[cfg(feature = "stream")]
+    pub(crate) fn max_line_len(&self) -> usize {
+        self.lines.max_len
+    }
+
+    /// Counts the next line of the feed, longer than the limit and not read, returning its
+    /// error
+    pub(crate) fn line_too_long(&mut self) -> Error {
+        self.line += 1;
+        trace_event!(warn, line = self.line, "line too long");
+        self.errors += 1;
+        self.tripped = self.limits.exceeded(self.errors, self.record_lines);
+        Error::LineTooLong {
+            limit: self.lines.max_len,
+            at_line: self.line,
+        }
+    }
+
+    /// Appends the next line of the feed, `bytes` in `charset`, to `line`, returning the
+    /// number of invalid bytes replaced, `None` if the line can't be decoded
+    pub(crate) fn decode(
+        &mut self,
+        charset: Charset,
+        bytes: &[u8],
+        line: &mut String,
+    ) -> Option<usize> {
+        let replaced = charset.decode(bytes, line, self.decode)?;
+        self.meter.replaced(replaced);
+        Some(replaced)
+    }
+
+    /// Sets what decoding does with bytes that are not valid UTF-8
+    #[cfg(feature = "stream")]
+    pub(crate) fn decode_policy(&mut self, policy: DecodePolicy) {
+        self.decode = policy;
+    }
+
+    /// Counts the next line of the feed, not valid UTF-8 and not parsed, returning its error
+    pub(crate) fn invalid_utf8(&mut self) -> Error {
+        self.line += 1;
+        trace_event!(warn, line = self.line, "invalid UTF-8");
+        self.errors += 1;
+        self.tripped = self.limits.exceeded(self.errors, self.record_lines);
+        Error::InvalidUtf8 { at_line: self.line }
+    }
+
+    /// Reads the next `line` of the feed, updating the section context from headers
+    pub(crate) fn advance(&mut self, line: &str) -> Step {
+        self.line += 1;
+        if !self.sniffed && !line.trim().is_empty() {
+            self.sniffed = true;
+            if !self.trust_content {
+                if let Some(error) = unexpected_content(line, None) {
+                    self.bailout = true;
+                    return Step::Bailout(error);
+                }
+            }
+        }
+        let line_type = LineType::of(line);
+        match line_type {
+            LineType::Amc => self.unclassified += 1,
+            LineType::Blank => {}
+            _ => self.unclassified = 0,
+        }
+        if self.unclassified > self.lines.max_unclassified {
+            trace_event!(warn, line = self.line, "too many unclassified lines");
+            self.bailout = true;
+            return Step::Bailout(Error::TooManyUnclassifiedLines {
+                limit: self.lines.max_unclassified,
+                at_line: self.line,
+            });
+        }
+        match line_type {
+            LineType::Record if self.layout.is_none() => {
+                self.layout = Some(Layout::of_record(line));
+                self.advance_record(line)
+            }
+            LineType::Record => self.advance_record(line),
+            LineType::Scheme => match parse_scheme_header(line.trim()) {
+                Ok(header) => {
+                    trace_event!(
+                        debug,
+                        line = self.line,
+                        category = header.category.as_str(),
+                        "scheme header"
+                    );
+                    let normalize = &self.normalize;
+                    self.section.maturity = header.maturity;
+                    self.section.fof = FofKind::of_header(&header);
+                    self.section.scheme = header.scheme_group.map(|s| normalize.scheme(s));
+                    self.section.category = normalize.category(header.category);
+                    self.update_section();
+                    Step::Scheme
+                }
+                Err(error) => {
+                    self.bailout = true;
+                    Step::Bailout(error)
+                }
+            },
+            LineType::Amc => {
+                self.section.amc = self.normalize.amc(line);
+                self.update_section();
+                if let Some(amcs) = &mut self.amcs {
+                    amcs.amc(&self.section.amc, self.meter.records());
+                }
+                trace_event!(debug, line = self.line, amc = %self.section.amc, "AMC header");
+                Step::Amc
+            }
+            LineType::Header => match FeedHeader::parse(line) {
+                Ok(header) => {
+                    if self.header.is_some() || self.section != SectionContext::default() {
+                        // Another feed starts, sections don't carry over from the last one
+                        self.segments_before += 1;
+                        self.section = SectionContext::default();
+                        self.skip_section = false;
+                        trace_event!(
+                            debug,
+                            line = self.line,
+                            segment = self.segment(),
+                            "feed segment"
+                        );
+                    }
+                    self.layout = Some(header.layout);
+                    self.header = Some(header);
+                    trace_event!(debug, line = self.line, layout = ?self.layout, "column header");
+                    Step::Header
+                }
+                Err(error) => {
+                    self.bailout = true;
+                    Step::Bailout(error)
+                }
+            },
+            LineType::Blank => Step::Blank,
+        }
+    }
+
+    fn advance_record(&self, line: &str) -> Step {
+        if self.skip_section {
+            Step::Skip
+        } else if self.section.amc.is_empty() && self.headerless == HeaderlessPolicy::Error {
+            // Without an AMC the line is likely not where it belongs, e.g. of a truncated feed
+            Step::Invalid(Error::BuilderError {
+                fields: vec!["amc".to_string()],
+                reason: "record before the first AMC header".to_string(),
+                line: Some(self.line),
+                raw_line: Some(line.trim().to_string()),
+            })
+        } else {
+            Step::Record
+        }
+    }
+
+    fn update_section(&mut self) {
+        let SectionContext {
+            amc,
+            category,
+            maturity,
+            ..
+        } = &self.section;
+        self.skip_section = !self.filters.section_matches(amc, category, *maturity);
+        self.order.section();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{case, fixture_records, fixture_text, record_line};
+
+    #[test]
+    fn it_works() {
+        assert_eq!(2 + 2, 4);
+    }
+
+    #[test]
+    fn record_from_str() {
+        let line = "120466;INF846K01AC2;INF846K01AD0;Axis Bluechip Fund - Direct Plan - IDCW;21.9800;21-Jun-2024";
+        let record: NavRecord = line.parse().unwrap();
+        let expected = NavRecordBuilder::default()
+            .code(120_466)
+            .isin(RawIsin::new("INF846K01AC2"))
+            .isin_dr(RawIsin::new("INF846K01AD0"))
+            .name("Axis Bluechip Fund - Direct Plan - IDCW")
+            .plan(FundPlan::Direct)
+            .nav(21.98)
+            .date(NavDate::from_ymd(2024, 6, 21).unwrap())
+            .amc("")
+            .category("")
+            .build()
+            .unwrap();
+        assert_eq!(record, expected);
+        assert_eq!(record.key(), (SchemeCode::from(120_466), expected.date));
+
+        assert!("Axis Mutual Fund".parse::<NavRecord>().is_err());
+    }
+
+    #[test]
+    fn fixture_record() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let read = nav_from_file(path).unwrap().next().unwrap().unwrap();
+        assert_eq!(NavRecord::fixture(), read);
+        assert_eq!(
+            NavRecordBuilder::example_direct_growth().build().unwrap(),
+            read
+        );
+
+        let date = NavDate::from_ymd(2024, 6, 24).unwrap();
+        let record = NavRecord::fixture()
+            .with_code(120_466)
+            .with_nav(62.0)
+            .with_date(date);
+        assert_eq!(record.key(), (SchemeCode::from(120_466), date));
+        assert_eq!(record.nav, 62.0);
+        assert_eq!(FundPlan::default(), FundPlan::Regular);
+        assert_eq!(FundMaturity::default(), FundMaturity::OpenEnded);
+    }
+
+    #[test]
+    fn parse_failure_fields() {
+        let cases: &[(&str, Field, &str)] = &[
+            (
+                "ABC;-;-;Axis Bluechip Fund;61.43;21-Jun-2024",
+                Field::Code,
+                "ABC",
+            ),
+            (
+                "120465;@INF;-;Axis Bluechip Fund;61.43;21-Jun-2024",
+                Field::Isin,
+                "@INF",
+            ),
+            (
+                "120465;-;#;Axis Bluechip Fund;61.43;21-Jun-2024",
+                Field::IsinDr,
+                "#",
+            ),
+            (
+                "120465;-;-;Axis Bluechip Fund;61.43",
+                Field::Name,
+                "Axis Bluechip Fund",
+            ),
+            (
+                "120465;-;-;Axis Bluechip Fund;61.43%;21-Jun-2024",
+                Field::Nav,
+                "61.43%",
+            ),
+            (
+                "120465;-;-;Axis Bluechip Fund;61.43;2024-06-21",
+                Field::Date,
+                "2024-06-21",
+            ),
+            (
+                "120465;-;-;Axis Bluechip Fund;61.43;21-Jun",
+                Field::Date,
+                "21-Jun",
+            ),
+            (
+                "120465;-;-;Axis Bluechip Fund;61.43;abc;60.82;21-Jun-2024",
+                Field::RepurchasePrice,
+                "abc",
+            ),
+            (
+                "120465;-;-;Axis Bluechip Fund;61.43;60.82;1,000;21-Jun-2024",
+                Field::SalePrice,
+                "1,000",
+            ),
+        ];
+        for &(line, expected_field, expected_found) in cases {
+            match NavRecord::parse_line(line) {
+                Err(Error::ParseFailure {
+                    field,
+                    at_byte,
+                    found,
+                    line: None,
+                    raw_line,
+                    ..
+                }) => {
+                    assert_eq!(field, expected_field, "{}", line);
+                    assert_eq!(found, expected_found, "{}", line);
+                    assert_eq!(raw_line, line);
+                    assert!(raw_line[at_byte..].starts_with(&found), "{}", line);
+                }
+                other => panic!("{}: {:?}", line, other),
+            }
+        }
 
-        while !self.bailout && item.is_none() {
-            self.buf.clear();
-            match self.reader.read_line(&mut self.buf) {
-                Ok(0) => {
-                    break;
+        // The failing lines of the corpus, e.g. `N.A.` NAVs
+        for case in corpus::cases() {
+            let expected_field = match case.expected {
+                Err(field) => field,
+                Ok(_) => continue,
+            };
+            let line = record_line(case);
+            match NavRecord::parse_line(line) {
+                Err(Error::ParseFailure {
+                    field,
+                    at_byte,
+                    found,
+                    raw_line,
+                    ..
+                }) => {
+                    assert_eq!(field, expected_field, "{}", case.name);
+                    assert_eq!(raw_line, line, "{}", case.name);
+                    assert!(raw_line[at_byte..].starts_with(&found), "{}", case.name);
                 }
-                Err(e) => {
-                    item = Some(Err(e.into()));
-                    break;
+                other => panic!("{}: {:?}", case.name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_failure_display() {
+        let na = case("na_nav");
+        let line = record_line(na);
+        let at = line.find("N.A.").unwrap();
+        let error = nav_from_str(na.input).next().unwrap().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Parse error: expected decimal number for nav, found `N.A.` at line 3, byte {}\n    \
+                 {}\n    {}^",
+                at,
+                line,
+                " ".repeat(at)
+            )
+        );
+
+        let long = format!(
+            "120465;-;-;{};61.43;21-Jun",
+            "Axis Bluechip Fund ".repeat(10)
+        );
+        let error = NavRecord::parse_line(&long).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Parse error: expected date like `21-Jun-2024` for date, found `21-Jun`, byte {}",
+                long.len() - 6
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_infinite_navs() {
+        // Parses as `inf`
+        let huge = format!("1{}.5", "0".repeat(400));
+        assert_eq!(huge.parse::<f64>().unwrap(), f64::INFINITY);
+        let legacy = "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;\
+                      Net Asset Value;Repurchase Price;Sale Price;Date\n";
+        let cases = [
+            ("", format!("{};21-Jun-2024", huge), Field::Nav),
+            (
+                legacy,
+                format!("61.43;{};61.43;21-Jun-2024", huge),
+                Field::RepurchasePrice,
+            ),
+        ];
+        for (header, rest, field) in &cases {
+            let feed = format!(
+                "{}Axis Mutual Fund\n120465;INF846K01AB4;-;Axis Bluechip Fund - Growth;{}\n",
+                header, rest
+            );
+            match nav_from_str(&feed).next().unwrap() {
+                Err(Error::ParseFailure {
+                    field: failed,
+                    found,
+                    ..
+                }) => {
+                    assert_eq!(failed, *field);
+                    assert_eq!(found, huge);
                 }
-                _ => match self.line_type() {
-                    LineType::Record => {
-                        item = Some(match parse_record(&self.buf.trim()) {
-                            IResult::Done(_rem, mut rb) => rb
-                                .maturity(self.maturity.clone())
-                                .amc(self.amc.clone())
-                                .scheme(self.scheme.clone())
-                                .category(self.category.clone())
-                                .build()
-                                .map_err(Error::BuilderError),
-                            IResult::Error => Err(Error::SynomError(self.buf.trim().to_string())),
-                        })
-                    }
-                    LineType::Scheme => {
-                        match parse_scheme(&self.buf.trim()) {
-                            IResult::Done(_, (maturity, scheme, category)) => {
-                                self.maturity = maturity;
-                                self.scheme = scheme;
-                                self.category = category;
-                            }
-                            IResult::Error => {
-                                self.bailout = true;
-                                item = Some(Err(Error::SynomError(self.buf.clone())));
-                            }
-                        };
-                    }
-                    LineType::Amc => {
-                        self.amc = self.buf.trim().to_string();
-                    }
-                    LineType::Blank | LineType::Header => (),
-                },
+                other => panic!("{:?}: {:?}", field, other),
+            }
+        }
+    }
+
+    #[test]
+    fn record_display_round_trip() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        for record in nav_from_file(path).unwrap() {
+            let record = record.unwrap();
+            let mut reparsed: NavRecord = record.to_string().parse().unwrap();
+            reparsed.amc = record.amc.clone();
+            reparsed.category = record.category.clone();
+            reparsed.scheme = record.scheme.clone();
+            reparsed.maturity = record.maturity;
+            reparsed.fof = record.fof;
+            assert_eq!(reparsed, record);
+        }
+    }
+
+    #[test]
+    fn fof_schemes() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let records: Vec<NavRecord> = nav_from_file(path).unwrap().collect::<Result<_>>().unwrap();
+        let fofs: Vec<_> = records
+            .iter()
+            .filter(|record| record.is_fof())
+            .map(|record| (record.code.get(), record.fof.unwrap()))
+            .collect();
+        // The gold fund is a FoF by its header only
+        assert_eq!(
+            fofs,
+            [(120_638, FofKind::Overseas), (147_953, FofKind::Domestic)]
+        );
+
+        // By name in other sections
+        let feed = "Open Ended Schemes(Other Scheme - Index Funds)\n\
+                    Motilal Oswal Mutual Fund\n\
+                    145552;INF247L01AP3;-;Motilal Oswal Nasdaq 100 Fund of Fund- Direct Plan Growth;34.4152;21-Jun-2024\n\
+                    147622;INF247L01BE6;-;Motilal Oswal Nifty 50 Index Fund - Direct plan;14.9128;21-Jun-2024\n\
+                    Open Ended Schemes(Hybrid Scheme - Multi Asset Allocation)\n\
+                    ICICI Prudential Mutual Fund\n\
+                    120334;INF109K01VQ1;-;ICICI Prudential Asset Allocator Fund (FOF) - Direct Plan - Growth;110.7900;21-Jun-2024";
+        let fofs: Vec<_> = nav_from_str(feed)
+            .map(|record| record.unwrap().fof)
+            .collect();
+        assert_eq!(
+            fofs,
+            [Some(FofKind::Overseas), None, Some(FofKind::Domestic)]
+        );
+
+        // Derived by the builder unless set
+        let mut builder = NavRecordBuilder::default();
+        builder
+            .code(120_638)
+            .name("Fund")
+            .nav(16.44)
+            .date(NavDate::from_ymd(2024, 6, 21).unwrap())
+            .amc("ICICI Prudential Mutual Fund")
+            .category("FoF Overseas")
+            .plan(FundPlan::Direct);
+        assert_eq!(builder.build().unwrap().fof, Some(FofKind::Overseas));
+        assert_eq!(builder.fof(None).build().unwrap().fof, None);
+    }
+
+    #[test]
+    fn isin_less_rows() {
+        // Published with a dash for both ISINs, as segregated portfolios are
+        let mut isin_less = 0;
+        for case in corpus::cases() {
+            let expected = match case.expected {
+                Ok(expected) => expected,
+                Err(_) => continue,
+            };
+            let record = nav_from_str(case.input).last().unwrap().unwrap();
+            if (expected.isin, expected.isin_dr) == (None, None) {
+                assert!(!record.has_isin(), "{}", case.name);
+                assert_eq!(SchemeKey::of(&record).isin, None, "{}", case.name);
+                isin_less += 1;
+            }
+        }
+        assert!(isin_less >= 2);
+    }
+
+    #[test]
+    fn vehicle_kinds() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let records: Vec<NavRecord> = nav_from_file(path).unwrap().collect::<Result<_>>().unwrap();
+        let vehicle = |code: u32| {
+            records
+                .iter()
+                .find(|record| record.code.get() == code)
+                .unwrap()
+                .vehicle()
+        };
+        // Gold ETF, other ETFs, index funds and FoFs by their headers
+        assert_eq!(vehicle(111_954), VehicleKind::Etf);
+        assert_eq!(vehicle(135_781), VehicleKind::Etf);
+        assert_eq!(vehicle(118_741), VehicleKind::IndexFund);
+        assert_eq!(vehicle(147_953), VehicleKind::Fof);
+        assert_eq!(vehicle(120_465), VehicleKind::ActiveFund);
+
+        let feed = "Open Ended Schemes(Other Scheme - Other  ETFs)\n\
+                    Edelweiss Mutual Fund\n\
+                    148926;INF754K01LE1;-;BHARAT Bond ETF - April 2030;1349.5474;21-Jun-2024\n\
+                    Open Ended Schemes(Other Scheme - Index Funds)\n\
+                    UTI Mutual Fund\n\
+                    143341;INF789F1AUS7;-;UTI Nifty Next 50 Fund - Direct Plan - Growth;21.6325;21-Jun-2024\n\
+                    Motilal Oswal Mutual Fund\n\
+                    145552;INF247L01AP3;-;Motilal Oswal Nasdaq 100 Fund of Fund- Direct Plan Growth;34.4152;21-Jun-2024\n\
+                    147796;INF247L01BR8;-;Motilal Oswal S&P 500 Fund - Direct Plan - Growth;15.3920;21-Jun-2024\n\
+                    Open Ended Schemes(Other Scheme - Gold ETF)\n\
+                    SBI Mutual Fund\n\
+                    115127;INF200KA16D8;-;SBI Gold Exchange Traded Scheme;60.1226;21-Jun-2024\n\
+                    Open Ended Schemes(Growth)\n\
+                    Franchise Mutual Fund\n\
+                    100001;-;-;Franchise Sensex Plan;14.00;21-Jun-2024\n\
+                    100002;-;-;Franchise Liquid BeES;1000.00;21-Jun-2024\n\
+                    100003;-;-;Franchise Growth Fund;22.00;21-Jun-2024";
+        let kinds: Vec<_> = nav_from_str(feed)
+            .map(|record| record.unwrap().vehicle())
+            .collect();
+        // The category wins over the names, which only tell for legacy sections
+        assert_eq!(
+            kinds,
+            [
+                VehicleKind::Etf,
+                VehicleKind::IndexFund,
+                VehicleKind::IndexFund,
+                VehicleKind::IndexFund,
+                VehicleKind::Etf,
+                VehicleKind::IndexFund,
+                VehicleKind::Etf,
+                VehicleKind::ActiveFund,
+            ]
+        );
+
+        let codes = |records: NavRecordIterator<&[u8]>| -> Vec<u32> {
+            records.map(|record| record.unwrap().code.get()).collect()
+        };
+        assert_eq!(
+            codes(nav_from_str(feed).filter_vehicle(VehicleKind::Etf)),
+            [148_926, 115_127, 100_002]
+        );
+        assert!(codes(
+            nav_from_str(feed)
+                .filter_plan(FundPlan::Direct)
+                .exclude_vehicle(VehicleKind::IndexFund)
+        )
+        .is_empty());
+        assert_eq!(
+            codes(nav_from_str(feed).exclude_vehicle(VehicleKind::IndexFund)).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn record_display_alternate() {
+        let record: NavRecord =
+            "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            format!("{:#}", record),
+            concat!(
+                "Axis Bluechip Fund - Direct Plan - Growth\n",
+                "  Code:     120465\n",
+                "  ISIN:     INF846K01AB4 / -\n",
+                "  NAV:      61.43 (21-Jun-2024)\n",
+                "  AMC:      \n",
+                "  Category: \n",
+                "  Plan:     Direct",
+            )
+        );
+    }
+
+    #[test]
+    fn fund_enum_strings() {
+        assert_eq!(FundPlan::Direct.to_string(), "Direct");
+        assert_eq!(FundMaturity::CloseEnded.to_string(), "Close Ended");
+        for variant in &[FundPlan::Direct, FundPlan::Regular] {
+            assert_eq!(variant.to_string().parse::<FundPlan>(), Ok(*variant));
+        }
+        for variant in &[FundMaturity::OpenEnded, FundMaturity::CloseEnded] {
+            assert_eq!(variant.to_string().parse::<FundMaturity>(), Ok(*variant));
+        }
+        assert_eq!("direct plan".parse::<FundPlan>(), Ok(FundPlan::Direct));
+        assert_eq!(" REGULAR ".parse::<FundPlan>(), Ok(FundPlan::Regular));
+        assert_eq!(
+            "open-ended".parse::<FundMaturity>(),
+            Ok(FundMaturity::OpenEnded)
+        );
+        assert_eq!(
+            "Closed Ended".parse::<FundMaturity>(),
+            Ok(FundMaturity::CloseEnded)
+        );
+
+        let err = "interval".parse::<FundMaturity>().unwrap_err();
+        assert_eq!(err.to_string(), "unrecognized fund maturity `interval`");
+    }
+
+    #[test]
+    fn filters() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let codes = |records: NavRecordIterator<File>| -> Vec<u32> {
+            records.map(|record| record.unwrap().code.get()).collect()
+        };
+        let all = || nav_from_file(path).unwrap();
+
+        assert_eq!(
+            codes(all().filter_amc("AXIS").filter_plan(FundPlan::Direct)),
+            [120_465, 120_466, 120_389, 147_953]
+        );
+        assert_eq!(codes(all().filter_amc("axis")).len(), 5);
+        assert!(codes(all().filter_amc_exact("Axis")).is_empty());
+        assert_eq!(codes(all().filter_amc_exact(" axis mutual fund")).len(), 5);
+        assert_eq!(
+            codes(all().filter_category("liquid").filter_amc("hdfc")),
+            [119_091, 119_092]
+        );
+        assert_eq!(codes(all().filter_category_exact("Gold ETF")), [111_954]);
+        assert_eq!(
+            codes(all().filter_maturity(FundMaturity::OpenEnded)).len(),
+            30
+        );
+        assert!(codes(all().filter_maturity(FundMaturity::CloseEnded)).is_empty());
+    }
+
+    #[test]
+    fn predicates() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let records: Vec<NavRecord> = nav_from_file(path).unwrap().map(Result::unwrap).collect();
+        let count = |pred: fn(&NavRecord) -> bool| records.iter().filter(|r| pred(r)).count();
+        assert_eq!(
+            count(NavRecord::is_direct) + count(NavRecord::is_regular),
+            records.len()
+        );
+        assert_eq!(count(NavRecord::is_open_ended), records.len());
+        assert_eq!(count(NavRecord::is_close_ended), 0);
+        assert!(records
+            .iter()
+            .all(|record| !(record.is_growth() && record.is_idcw())));
+
+        let by_code = |code: u32| records.iter().find(|r| r.code.get() == code).unwrap();
+        let idcw = records
+            .iter()
+            .find(|r| r.name == "HDFC Flexi Cap Fund - IDCW Option - Regular Plan")
+            .unwrap();
+        assert!(idcw.is_idcw() && idcw.is_regular() && !idcw.is_growth());
+        let large_cap = by_code(120_465);
+        assert!(large_cap.is_growth() && large_cap.is_direct());
+        assert!(large_cap.matches_category("large cap fund"));
+        assert!(large_cap.matches_category("  LargeCap\tFund "));
+        assert!(!large_cap.matches_category("Large Cap"));
+        assert!(records
+            .iter()
+            .any(|record| record.matches_category("Sectoral/Thematic")));
+    }
+
+    #[test]
+    fn filters_skip_sections_unparsed() {
+        let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n\
+                    ICICI Prudential Mutual Fund\n\
+                    108466;garbage;\n";
+        let results: Vec<_> = NavRecordIterator::new(feed.as_bytes())
+            .filter_amc("icici")
+            .collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+
+        let results: Vec<_> = NavRecordIterator::new(feed.as_bytes())
+            .filter_plan(FundPlan::Regular)
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_err));
+    }
+
+    #[test]
+    fn current_section_and_skip() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let mut records = nav_from_file(path).unwrap();
+        assert_eq!(records.current_amc(), "");
+        assert_eq!(records.current_maturity(), None);
+
+        let first = records.next().unwrap().unwrap();
+        assert_eq!(first.code.get(), 120_465);
+        assert_eq!(records.current_amc(), "Axis Mutual Fund");
+        assert_eq!(records.current_category(), "Large Cap Fund");
+        assert_eq!(records.current_scheme(), Some("Equity Scheme"));
+        assert_eq!(records.current_maturity(), Some(FundMaturity::OpenEnded));
+
+        // Rest of Axis skipped, ICICI follows in the same category
+        records.skip_current_section();
+        assert_eq!(records.current_amc(), "Axis Mutual Fund");
+        let next = records.next().unwrap().unwrap();
+        assert_eq!(next.code.get(), 120_586);
+        assert_eq!(records.current_amc(), "ICICI Prudential Mutual Fund");
+
+        // Skipping every section after its first record leaves one record per section
+        let mut records = nav_from_file(path).unwrap();
+        let mut firsts = Vec::new();
+        while let Some(record) = records.next() {
+            firsts.push(record.unwrap().amc);
+            records.skip_current_section();
+        }
+        let sections = nav_from_file(path)
+            .unwrap()
+            .map(|record| {
+                let record = record.unwrap();
+                (record.amc, record.category)
+            })
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(firsts.len(), sections.len());
+
+        // Malformed lines of a skipped section are not parsed
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n\
+                    ICICI Prudential Mutual Fund\n\
+                    108466;garbage;\n";
+        let mut records = nav_from_str(feed);
+        assert!(records.next().unwrap().is_ok());
+        records.skip_current_section();
+        assert!(records.next().unwrap().is_err());
+        assert_eq!(records.line(), 5);
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn zero_navs() {
+        let feed = "Close Ended Schemes(Debt Scheme - Fixed Maturity Plan)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Fixed Term Plan - Series 1 - Growth;0.0000;21-Jun-2024\n\
+                    120466;INF846K01AC2;-;Axis Fixed Term Plan - Series 1 - IDCW;0.00;21-Jun-2024\n\
+                    120467;INF846K01AD0;-;Axis Fixed Term Plan - Series 2 - Growth;10.4500;21-Jun-2024\n\
+                    120468;garbage;\n";
+        let navs = |policy| -> Vec<f64> {
+            nav_from_str(feed)
+                .zero_nav_policy(policy)
+                .filter_map(|record| record.ok().map(|record| record.nav))
+                .collect()
+        };
+        assert_eq!(navs(ZeroNavPolicy::default()), [0.0, 0.0, 10.45]);
+        assert_eq!(navs(ZeroNavPolicy::Skip), [10.45]);
+        assert_eq!(navs(ZeroNavPolicy::TreatAsMissing), [10.45]);
+        let missing: Vec<String> = nav_from_str(feed)
+            .zero_nav_policy(ZeroNavPolicy::TreatAsMissing)
+            .filter_map(|record| match record {
+                Err(error @ Error::ZeroNav { .. }) => Some(error.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                "Missing NAV: scheme 120465 has a NAV of zero at line 3",
+                "Missing NAV: scheme 120466 has a NAV of zero at line 4"
+            ]
+        );
+
+        // Parse errors are reported under every policy
+        let errors = nav_from_str(feed)
+            .zero_nav_policy(ZeroNavPolicy::Skip)
+            .filter(Result::is_err)
+            .count();
+        assert_eq!(errors, 1);
+
+        let records: Vec<NavRecord> = nav_from_str(feed).filter_map(Result::ok).collect();
+        let valid: Vec<bool> = records.iter().map(NavRecord::has_valid_nav).collect();
+        assert_eq!(valid, [false, false, true]);
+        let mut record = records[2].clone();
+        record.nav = f64::INFINITY;
+        assert!(!record.has_valid_nav());
+    }
+
+    #[test]
+    fn summaries_while_streaming() {
+        for path in &[
+            "fixtures/NAVOpen.txt",
+            "fixtures/NAVHistory.txt",
+            "fixtures/NAVMessy.txt",
+            "fixtures/NAVLegacy.txt",
+        ] {
+            let collected: Vec<_> = nav_from_file(path).unwrap().collect();
+            let expected = summarize(collected);
+
+            let mut records = nav_from_file(path).unwrap();
+            assert_eq!(*records.summary_so_far(), FeedSummary::default());
+            let mut checkpoint = summarize(records.by_ref().take(7).collect::<Vec<_>>());
+            assert_eq!(*records.summary_so_far(), checkpoint, "{}", path);
+            for result in records.by_ref().take(3) {
+                match result {
+                    Ok(ref record) => checkpoint.add(record),
+                    Err(_) => checkpoint.errors += 1,
+                }
+            }
+            assert_eq!(*records.summary_so_far(), checkpoint, "{}", path);
+            assert_eq!(records.finish(), expected, "{}", path);
+        }
+
+        // Errors ending the feed and records left out are counted as yielded
+        let limited = || {
+            nav_from_file("fixtures/NAVHistory.txt")
+                .unwrap()
+                .max_errors(0)
+        };
+        let collected: Vec<_> = limited().collect();
+        assert!(collected.last().unwrap().is_err());
+        let expected = summarize(collected);
+        assert_eq!(limited().finish(), expected);
+        let mapped = || {
+            nav_from_file("fixtures/NAVOpen.txt")
+                .unwrap()
+                .map_records(|record| Some(record).filter(|record| record.plan == FundPlan::Direct))
+        };
+        let summary = mapped().finish();
+        assert_eq!(summary, summarize(mapped()));
+        assert_eq!(summary.records, summary.direct);
+    }
+
+    #[test]
+    fn record_slices() {
+        // Three daily feeds of five records, the second with an unparsable line
+        let history = || nav_from_file("fixtures/NAVHistory.txt").unwrap();
+        let lines = |results: Vec<Result<NavRecord>>| -> Vec<Option<u32>> {
+            results
+                .into_iter()
+                .map(|result| result.ok().map(|record| record.source_line.unwrap()))
+                .collect()
+        };
+        assert_eq!(history().count(), 16);
+
+        let skipped: Vec<_> = history().skip_records(7).take(3).collect();
+        assert_eq!(lines(skipped), [Some(29), None, Some(34)]);
+        // Skipped lines aren't parsed, so their errors aren't counted
+        let results: Vec<_> = history().skip_records(9).max_errors(0).collect();
+        assert_eq!(results.len(), 7);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(summarize(history().skip_records(9)).errors, 0);
+
+        assert_eq!(history().take_records(0).count(), 0);
+        // Errors don't count, lines after the last record aren't read
+        let taken: Vec<_> = history().take_records(8).collect();
+        assert_eq!(taken.len(), 8);
+        let taken: Vec<_> = history().take_records(9).collect();
+        assert_eq!(taken.len(), 10);
+        assert!(taken[8].is_err() && taken[9].is_ok());
+        let day: Vec<_> = history().skip_records(5).take_records(5).collect();
+        assert_eq!(
+            lines(day),
+            [Some(27), Some(28), Some(29), None, Some(34), Some(40)]
+        );
+
+        let bound = NavDate::parse_feed("20-Jun-2024").unwrap();
+        let mut records = history().stop_after_date(bound, DateOrder::Ascending);
+        let results: Vec<_> = records.by_ref().collect();
+        assert_eq!(results.len(), 11);
+        assert!(results.iter().flatten().all(|record| record.date <= bound));
+        // Read up to the first record of the 21st
+        assert_eq!(records.line(), 48);
+        assert!(records.next().is_none());
+
+        let descending = history().stop_after_date(bound, DateOrder::Descending);
+        assert_eq!(descending.count(), 0);
+
+        // Records of sections left out aren't parsed
+        let mut records = history()
+            .filter_amc("HDFC")
+            .stop_after_date(bound, DateOrder::Ascending);
+        assert_eq!(records.by_ref().count(), 2);
+        assert_eq!(records.line(), 60);
+    }
+
+    #[test]
+    fn concatenated_feeds() {
+        let day = std::fs::read_to_string("fixtures/NAVOpen.txt").unwrap();
+        let single: Vec<NavRecord> = nav_from_str(&day).collect::<Result<_>>().unwrap();
+        let days = format!("{}{}", day, day);
+        let mut records = nav_from_str(&days).stamp_segments(true);
+        let both: Vec<NavRecord> = records.by_ref().collect::<Result<_>>().unwrap();
+        assert_eq!(records.segment(), 2);
+        let (first, second) = both.split_at(single.len());
+        assert_eq!(first, &single[..]);
+        assert_eq!(second, &single[..]);
+        assert!(first.iter().all(|record| record.segment == Some(1)));
+        assert!(second.iter().all(|record| record.segment == Some(2)));
+        assert_eq!(
+            second[0].source_line.unwrap(),
+            first[0].source_line.unwrap() + day.lines().count() as u32
+        );
+        assert_eq!(nav_from_str(&day).next().unwrap().unwrap().segment, None);
+
+        // Records of a feed without an AMC header don't take that of the feed before
+        let feed = format!(
+            "{}\n{}\n120466;-;-;Stray Fund;21.98;21-Jun-2024\n",
+            day.trim_end(),
+            FEED_HEADER.trim_end()
+        );
+        let results: Vec<_> = nav_from_str(&feed).collect();
+        assert_eq!(results.len(), single.len() + 1);
+        match results.last() {
+            Some(Err(Error::BuilderError { reason, .. })) => {
+                assert_eq!(reason, "record before the first AMC header")
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // A leading header starts the first feed, as do blank lines before it
+        let page = format!("\n{}", day);
+        let mut lines = feed_lines(page.as_bytes());
+        assert!(matches!(lines.next(), Some(Ok(FeedLine::Blank))));
+        assert!(matches!(lines.next(), Some(Ok(FeedLine::Header))));
+        assert_eq!(lines.segment(), 1);
+    }
+
+    #[test]
+    fn legacy_layout() {
+        let modern = fixture_records("NAVOpen.txt");
+        let legacy_feed = fixture_text("NAVLegacy.txt");
+        let legacy = fixture_records("NAVLegacy.txt");
+        assert!(modern
+            .iter()
+            .all(|record| (record.repurchase_price, record.sale_price) == (None, None)));
+
+        // Same core fields, prices where given
+        let prices: Vec<_> = legacy
+            .iter()
+            .map(|record| (record.repurchase_price, record.sale_price))
+            .collect();
+        assert_eq!(
+            prices[..5],
+            [
+                (Some(61.43), Some(61.43)),
+                (None, None),
+                (None, None),
+                (Some(110.25), Some(110.25)),
+                (Some(31.26), None),
+            ]
+        );
+        let stripped: Vec<NavRecord> = legacy
+            .iter()
+            .cloned()
+            .map(|mut record| {
+                record.repurchase_price = None;
+                record.sale_price = None;
+                record
+            })
+            .collect();
+        assert_eq!(stripped, modern);
+
+        // Without a header the first record tells the layout
+        let headless = &legacy_feed[legacy_feed.find('\n').unwrap() + 1..];
+        let reparsed: Vec<NavRecord> = nav_from_str(headless).collect::<Result<_>>().unwrap();
+        assert_eq!(reparsed, legacy);
+        for case in corpus::cases() {
+            let (first, rest) = case.input.split_once('\n').unwrap();
+            if FeedHeader::is_header(first) {
+                let result = nav_from_str(rest).last().unwrap();
+                if let Err(mismatch) = case.check(&result) {
+                    panic!("{} without header: {}", case.name, mismatch);
+                }
+            }
+        }
+
+        // Records of the other layout don't parse under a header
+        let header = legacy_feed.lines().next().unwrap();
+        let feed = format!("{}\n{}", header, record_line(case("modern")));
+        assert!(nav_from_str(&feed).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn history_layout() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVHistoryAll.txt");
+        let mut records = nav_history_from_file(path).unwrap();
+        let history: Vec<NavRecord> = records.by_ref().collect::<Result<_>>().unwrap();
+        for other in &["NAVOpen.txt", "NAVLegacy.txt"] {
+            let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), other);
+            match nav_history_from_file(path) {
+                Err(Error::UnexpectedContent { first_line, .. }) => {
+                    assert!(first_line.starts_with("Scheme Code;ISIN"))
+                }
+                other => panic!("unexpected {:?}", other.map(|_| ())),
+            }
+        }
+        assert_eq!(records.header().unwrap().layout, Layout::History);
+        assert_eq!(history.len(), 12);
+        let dates: std::collections::HashSet<_> =
+            history.iter().map(|record| record.date).collect();
+        assert_eq!(dates.len(), 3);
+
+        // AMC and section carry over the days of a section
+        let liquid: Vec<_> = history
+            .iter()
+            .filter(|record| record.category == "Liquid Fund")
+            .collect();
+        assert_eq!(liquid.len(), 3);
+        assert!(liquid.iter().all(|record| record.amc == "Axis Mutual Fund"));
+        assert_eq!(
+            liquid
+                .iter()
+                .map(|record| (record.repurchase_price, record.sale_price))
+                .collect::<Vec<_>>(),
+            [
+                (Some(2736.2183), None),
+                (Some(2736.4802), None),
+                (Some(2736.7421), None)
+            ]
+        );
+
+        // Records of the last day are those of the daily feed, prices and blank ISINs aside
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let daily: std::collections::HashMap<SchemeCode, NavRecord> = nav_from_file(path)
+            .unwrap()
+            .map(|record| record.map(|record| (record.code, record)))
+            .collect::<Result<_>>()
+            .unwrap();
+        let last_day = NavDate::from_ymd(2024, 6, 21).unwrap();
+        for record in history.iter().filter(|record| record.date == last_day) {
+            let mut record = record.clone();
+            record.repurchase_price = None;
+            record.sale_price = None;
+            assert_eq!(record, daily[&record.code]);
+        }
+
+        // Without a header the first record tells the layout
+        let feed = std::fs::read_to_string(path.replace("NAVOpen", "NAVHistoryAll")).unwrap();
+        let headless = &feed[feed.find('\n').unwrap() + 1..];
+        assert_eq!(
+            nav_from_str(headless).collect::<Result<Vec<_>>>().unwrap(),
+            history
+        );
+        let line = feed.lines().nth(6).unwrap();
+        let record = NavRecord::parse_line(line).unwrap();
+        assert_eq!(record.name, "Axis Bluechip Fund - Direct Plan - Growth");
+        assert_eq!(record.isin_dr, None);
+
+        // ISINs are checked in their columns
+        let error = parse_record_line(
+            "120465;Axis Bluechip Fund - Direct Plan - Growth;INF846K01AB4;IN F8;61.43;;;21-Jun-2024",
+            Layout::History,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::ParseFailure {
+                field: Field::IsinDr,
+                ..
+            }
+        ));
+
+        // And written back in the same layout, by date within each AMC
+        let mut writer = NavWriter::new(Vec::new()).layout(Layout::History);
+        writer.write_records(&history).unwrap();
+        let written = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(written.starts_with(HISTORY_FEED_HEADER));
+        let sorted = |records: &[NavRecord]| {
+            let mut records: Vec<NavRecord> = records
+                .iter()
+                .cloned()
+                .map(|mut record| {
+                    record.source_line = None;
+                    record
+                })
+                .collect();
+            records.sort_by_key(|record| (record.code, record.date));
+            records
+        };
+        let reread: Vec<NavRecord> = nav_from_str(&written).collect::<Result<_>>().unwrap();
+        assert_eq!(sorted(&reread), sorted(&history));
+    }
+
+    #[test]
+    fn error_limits() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVHalfGarbage.txt");
+        let errors = |records: NavRecordIterator<File>| -> Vec<usize> {
+            records
+                .filter_map(|result| match result {
+                    Err(Error::TooManyErrors { errors, .. }) => Some(100 + errors),
+                    Err(_) => Some(0),
+                    Ok(_) => None,
+                })
+                .collect()
+        };
+        // Unlimited by default
+        assert_eq!(errors(nav_from_file(path).unwrap()), [0; 5]);
+        assert_eq!(errors(nav_from_file(path).unwrap().max_errors(5)), [0; 5]);
+        assert_eq!(errors(nav_from_file(path).unwrap().max_errors(0)), [0, 101]);
+        // Half the lines fail
+        assert_eq!(
+            errors(nav_from_file(path).unwrap().max_error_ratio(0.5, 1)),
+            [0; 5]
+        );
+        assert_eq!(
+            errors(nav_from_file(path).unwrap().max_error_ratio(0.4, 10)),
+            [0, 0, 0, 0, 0, 105]
+        );
+
+        // Fused after the verdict
+        let mut records = nav_from_file(path).unwrap().max_errors(1);
+        assert_eq!(records.by_ref().count(), 5);
+        assert!(records.next().is_none());
+        assert_eq!(records.line(), 10);
+    }
+
+    #[test]
+    fn extra_separators() {
+        let parse = |line: &str| {
+            nav_from_str(&format!("Axis Mutual Fund\n{}", line))
+                .next()
+                .unwrap()
+        };
+        let line =
+            "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024";
+        let expected = parse(line).unwrap();
+        for variant in &[
+            format!("{};", line),
+            format!("{};;", line),
+            format!("{} \t", line),
+            format!("{}; \r", line),
+            line.replacen(";INF846K01AB4;", ";INF846K01AB4;;", 1),
+            line.replacen("120465;", "120465;;", 1),
+            line.replacen("Growth;", "Growth;;", 1),
+            line.replacen(";21-Jun", ";;21-Jun", 1),
+            line.replace(';', ";;"),
+        ] {
+            assert_eq!(parse(variant).unwrap(), expected, "{}", variant);
+        }
+
+        // Legacy blank prices are columns, not extra separators
+        let legacy = "120466;INF846K01AC2;INF846K01AD0;Axis Bluechip Fund - Direct Plan - IDCW;21.98;;;21-Jun-2024";
+        let expected = parse(legacy).unwrap();
+        assert_eq!(expected.nav, 21.98);
+        assert_eq!(parse(&format!("{};", legacy)).unwrap(), expected);
+
+        // Required fields can't be empty
+        for bad in &[
+            line.replacen("Axis Bluechip Fund - Direct Plan - Growth", " ", 1),
+            line.replacen("61.43", "", 1),
+            line.replacen("21-Jun-2024", "", 1),
+            line.replacen("120465", "", 1),
+            ";;;;;".to_string(),
+        ] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn control_characters() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVControl.txt");
+        let records: Vec<NavRecord> = nav_from_file(path).unwrap().collect::<Result<_>>().unwrap();
+        let fields: Vec<_> = records
+            .iter()
+            .map(|record| (record.name.as_str(), record.nav))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                // Tab inside the name
+                ("Axis Bluechip Fund - Direct Plan - Growth", 61.43),
+                // Vertical tab after the date
+                ("Axis Bluechip Fund - Direct Plan - IDCW", 21.98),
+                // Other control characters anywhere
+                ("Axis Bluechip Fund - Regular Plan - Growth", 54.12),
+                // Tabs around separators
+                ("Axis Bluechip Fund - Regular Plan - IDCW", 18.44),
+            ]
+        );
+        let date = NavDate::from_ymd(2024, 6, 21).unwrap();
+        assert!(records.iter().all(|record| record.date == date));
+        assert_eq!(records[3].isin_dr, None);
+    }
+
+    #[test]
+    fn semicolons_in_names() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVArchive.txt");
+        let records: Vec<NavRecord> = nav_from_file(path).unwrap().collect::<Result<_>>().unwrap();
+        let fields: Vec<_> = records
+            .iter()
+            .map(|record| (record.name.as_str(), record.nav, record.sale_price))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("Kotak FMP; Series 2 - Growth", 10.4521, Some(10.4521)),
+                ("Kotak FMP; Series 2 - Dividend", 10.021, None),
+                ("Kotak FMP Series 3 - Growth", 10.3114, None),
+            ]
+        );
+
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;XYZ Fund; Series 2; Plan B - Direct Plan - Growth;61.43;21-Jun-2024;\n\
+                    120466;INF846K01AC2;INF846K01AD0;XYZ Fund; Series 2 - IDCW;21.98;21-Jun-2024\n";
+        let records: Vec<NavRecord> = nav_from_str(feed).collect::<Result<_>>().unwrap();
+        assert_eq!(
+            records[0].name,
+            "XYZ Fund; Series 2; Plan B - Direct Plan - Growth"
+        );
+        assert_eq!(records[0].plan, FundPlan::Direct);
+        assert_eq!(records[0].repurchase_price, None);
+        assert_eq!(records[1].name, "XYZ Fund; Series 2 - IDCW");
+        assert_eq!(records[1].nav, 21.98);
+    }
+
+    #[test]
+    fn source_lines() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let lines: Vec<String> = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        let mut records = nav_from_file(path).unwrap().capture_raw(true);
+        while let Some(record) = records.next() {
+            let record = record.unwrap();
+            let number = record.source_line.unwrap();
+            assert_eq!(number as usize, records.line());
+            assert_eq!(lines[number as usize - 1], record.raw.unwrap().line);
+        }
+
+        // Not part of the content
+        let line = &lines[6];
+        let parsed = NavRecord::parse_line(line).unwrap();
+        assert_eq!(parsed.source_line, None);
+        let mut read = nav_from_str(&format!("{}\n{}", lines[4], line))
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.source_line, Some(2));
+        read.amc.clear();
+        read.category.clear();
+        read.scheme = None;
+        read.maturity = None;
+        assert_eq!(read, parsed);
+    }
+
+    #[test]
+    fn builder_errors() {
+        match NavRecordBuilder::default().code(120_465).nav(61.43).build() {
+            Err(Error::BuilderError {
+                fields,
+                line: None,
+                raw_line: None,
+                ..
+            }) => assert_eq!(fields, ["name", "date", "amc", "category", "plan"]),
+            other => panic!("unexpected {:?}", other),
+        }
+        // Fields left out of `unset_required` would fail here
+        let fixture = NavRecord::fixture();
+        let record = NavRecordBuilder::default()
+            .code(fixture.code)
+            .name(fixture.name.as_str())
+            .nav(fixture.nav)
+            .date(fixture.date)
+            .amc(fixture.amc.as_str())
+            .category(fixture.category.as_str())
+            .plan(fixture.plan)
+            .build()
+            .unwrap();
+        assert_eq!(record.code, fixture.code);
+        for nav in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            match NavRecordBuilder::example_direct_growth().nav(*nav).build() {
+                Err(Error::BuilderError { fields, .. }) => assert_eq!(fields, ["nav"]),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+
+        // Records before the first AMC header are errors, the rest still parse
+        let record =
+            "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024";
+        let feed = format!(
+            "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\n{}\nAxis Mutual Fund\n{}\n",
+            record, record
+        );
+        let results: Vec<_> = nav_from_str(&feed).collect();
+        assert_eq!(results.len(), 2);
+        let error = results[0].as_ref().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Builder error: record before the first AMC header (amc) at line 3: `{}`",
+                record
+            )
+        );
+        match error {
+            Error::BuilderError {
+                fields,
+                line,
+                raw_line,
+                ..
+            } => {
+                assert_eq!(fields, &["amc"]);
+                assert_eq!(*line, Some(3));
+                assert_eq!(raw_line.as_deref(), Some(record));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(results[1].as_ref().unwrap().source_line, Some(5));
+        assert!(NavRecord::parse_line(record).is_ok());
+    }
+
+    #[test]
+    fn stray_byte() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVStrayByte.txt");
+        let mut records = nav_from_file(path).unwrap();
+        let results: Vec<_> = records.by_ref().collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[1], Err(Error::InvalidUtf8 { at_line: 8 })));
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            "Invalid UTF-8: line 8 has bytes that are not UTF-8"
+        );
+        assert_eq!(results[2].as_ref().unwrap().code, SchemeCode::from(112_277));
+        assert_eq!(records.stats().errors, 1);
+
+        let mut records = nav_from_file(path).unwrap().decode(DecodePolicy::Lossy);
+        let names: Vec<String> = records
+            .by_ref()
+            .map(|record| record.unwrap().name)
+            .collect();
+        assert_eq!(names[1], "Axis Bluechip Fund\u{FFFD} - Direct Plan - IDCW");
+        assert_eq!(records.lossy_lines(), 1);
+        let stats = records.stats();
+        assert_eq!(
+            (stats.records, stats.errors, stats.replaced_chars),
+            (3, 0, 1)
+        );
+    }
+
+    #[test]
+    fn sorted_within_sections() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVMirror.txt");
+        let results: Vec<_> = nav_from_file(path)
+            .unwrap()
+            .assert_sorted_within_sections(true)
+            .collect();
+        assert_eq!(results.len(), 3);
+        match &results[2] {
+            Err(Error::OrderingViolation {
+                section,
+                prev_code,
+                code,
+                at_line,
+            }) => {
+                assert_eq!(section.amc, "Axis Mutual Fund");
+                assert_eq!(
+                    (prev_code.get(), code.get(), *at_line),
+                    (120_466, 112_277, 9)
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "Out of order: scheme 112277 follows scheme 120466 at line 9, in Axis Mutual Fund \
+             (Large Cap Fund)"
+        );
+        assert!(nav_from_file(path).unwrap().all(|record| record.is_ok()));
+
+        // Each AMC section starts over
+        let feed = "Axis Mutual Fund\n\
+                    120466;INF846K01AC2;-;Axis Bluechip Fund - Direct Plan - IDCW;21.98;21-Jun-2024\n\
+                    HDFC Mutual Fund\n\
+                    118955;INF179K01AJ3;-;HDFC Flexi Cap Fund - Growth;1890.12;21-Jun-2024\n\
+                    118955;INF179K01AJ3;-;HDFC Flexi Cap Fund - Growth;1890.12;21-Jun-2024\n\
+                    101762;INF179K01AK1;-;HDFC Flexi Cap Fund - IDCW;88.43;21-Jun-2024\n";
+        let mut records = nav_from_str(feed)
+            .assert_sorted_within_sections(true)
+            .recovery(RecoveryPolicy::Lenient);
+        assert_eq!(records.by_ref().filter(Result::is_ok).count(), 4);
+        let stats = records.stats();
+        assert_eq!((stats.errors, stats.out_of_order), (0, 1));
+    }
+
+    #[test]
+    fn alternate_delimiters() {
+        let read = |name: &str, delimiter: u8| -> Vec<NavRecord> {
+            let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+            nav_from_file(path)
+                .unwrap()
+                .capture_raw(true)
+                .delimiter(delimiter)
+                .collect::<Result<_>>()
+                .unwrap()
+        };
+        let without_raw = |records: Vec<NavRecord>| -> Vec<NavRecord> {
+            records
+                .into_iter()
+                .map(|record| NavRecord {
+                    raw: None,
+                    ..record
+                })
+                .collect()
+        };
+        let semicolons = read("NAVMirror.txt", b';');
+        assert_eq!(semicolons.len(), 3);
+        assert_eq!(semicolons[0].amc, "Axis Mutual Fund");
+        assert_eq!(semicolons[0].category, "Large Cap Fund");
+        for &(name, delimiter) in &[("NAVMirror.pipe.txt", b'|'), ("NAVMirror.tab.txt", b'\t')] {
+            let records = read(name, delimiter);
+            // Raw lines are as read
+            let raw = records[1].raw.as_ref().unwrap();
+            assert!(raw.line.contains(char::from(delimiter)), "{}", raw.line);
+            assert_eq!(
+                without_raw(records),
+                without_raw(semicolons.clone()),
+                "{}",
+                name
+            );
+        }
+
+        // Read with the default delimiter, no line is a record
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVMirror.pipe.txt");
+        let results: Vec<_> = nav_from_file(path).unwrap().collect();
+        assert!(matches!(
+            results[..],
+            [Err(Error::UnexpectedContent { .. })]
+        ));
+    }
+
+    #[test]
+    fn headerless_fragment() {
+        let fragment = "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                        120466;INF846K01AC2;INF846K01AD0;Axis Bluechip Fund - Direct Plan - IDCW;21.98;21-Jun-2024\n\
+                        Open Ended Schemes(Debt Scheme - Liquid Fund)\n\
+                        120389;INF846K01AU4;-;Axis Liquid Fund - Direct Plan - Growth Option;2736.7421;21-Jun-2024\n";
+        let lines: Vec<_> = nav_from_str(fragment)
+            .map(|result| match result {
+                Err(Error::BuilderError { line, .. }) => line,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(lines, [Some(1), Some(2), Some(4)]);
+
+        let records: Vec<NavRecord> = nav_from_str(fragment)
+            .headerless(HeaderlessPolicy::Keep)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|record| record.amc.is_empty()));
+        assert_eq!(records[0].category, "");
+        assert_eq!(records[2].category, "Liquid Fund");
+
+        let summary = summarize(records.iter().cloned().map(Ok));
+        assert_eq!((summary.without_amc, summary.without_category), (3, 2));
+        assert!(summary.per_amc.is_empty());
+        assert_eq!(
+            summary.per_category.keys().collect::<Vec<_>>(),
+            ["Liquid Fund"]
+        );
+
+        // Headers later in the feed apply as usual
+        let feed = format!(
+            "{}Axis Mutual Fund\n{}",
+            fragment,
+            fragment.lines().next().unwrap()
+        );
+        let last = nav_from_str(&feed)
+            .headerless(HeaderlessPolicy::Keep)
+            .last()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (last.amc.as_str(), last.category.as_str()),
+            ("Axis Mutual Fund", "Liquid Fund")
+        );
+    }
+
+    #[test]
+    fn bare_scheme_headers() {
+        // As in some feeds of 2008, the category of close ended schemes not published
+        let feed = "Open Ended Schemes(Income)\n\
+                    Axis Mutual Fund\n\
+                    100027;INF846K01AE8;-;Axis Income Fund - Growth;14.2100;02-Jan-2008\n\
+                    Close Ended Schemes\n\
+                    Axis Mutual Fund\n\
+                    100001;INF846K01ZZ0;-;Axis Fixed Term Plan Series 1 - Growth;10.0100;02-Jan-2008\n\
+                    100002;INF846K01ZY3;-;Axis Fixed Term Plan Series 2 - Growth;10.0200;02-Jan-2008\n\
+                    Interval Fund Schemes(Income)\n\
+                    Axis Mutual Fund\n\
+                    118544;INF846K01QW6;-;Axis Interval Fund - Growth;13.2050;02-Jan-2008\n";
+        let records: Vec<NavRecord> = nav_from_str(feed).collect::<Result<_>>().unwrap();
+        let sections: Vec<_> = records
+            .iter()
+            .map(|r| (r.maturity, r.scheme.as_deref(), r.category.as_str()))
+            .collect();
+        let close = Some(FundMaturity::CloseEnded);
+        assert_eq!(
+            sections,
+            [
+                (Some(FundMaturity::OpenEnded), None, "Income"),
+                (close, None, ""),
+                (close, None, ""),
+                (None, None, "Income"),
+            ]
+        );
+        let summary = summarize(records.iter().cloned().map(Ok));
+        assert_eq!((summary.close_ended, summary.without_category), (2, 2));
+    }
+
+    #[test]
+    fn sniff_content() {
+        // The only item, if an `UnexpectedContent` error
+        let first_error = |feed: &str| -> Option<Error> {
+            let mut results: Vec<_> = nav_from_str(feed).collect();
+            match results.pop() {
+                Some(Err(error @ Error::UnexpectedContent { .. })) if results.is_empty() => {
+                    Some(error)
+                }
+                _ => None,
             }
+        };
+        let record = "Axis Mutual Fund\n\
+                      120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n";
+        for start in &[
+            "\u{feff}Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Date\n",
+            "\r\n \nOpen Ended Schemes(Equity Scheme - Large Cap Fund)\n",
+            "Axis Mutual Fund\n",
+            "",
+        ] {
+            let feed = format!("{}{}", start, record);
+            assert_eq!(nav_from_str(&feed).filter(Result::is_ok).count(), 1);
         }
-        item
+
+        let page = "\n<!doctype html>\n<html>\n<p>Site under maintenance; back soon</p>\n</html>\n";
+        match first_error(page) {
+            Some(Error::UnexpectedContent { first_line, url }) => {
+                assert_eq!(first_line, "<!doctype html>");
+                assert_eq!(url, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(first_error("<HTML><HEAD><TITLE>Access Denied</TITLE></HEAD></HTML>").is_some());
+        assert!(first_error("Access Denied\nReference #18.5c1d\n").is_some());
+        assert!(first_error("{\"error\": \"throttled\"}\n").is_some());
+        let long = "x".repeat(1000);
+        assert_eq!(
+            first_error(&long).unwrap().to_string(),
+            format!("Unexpected content, not a NAV feed: `{}`", &long[..200])
+        );
+
+        // Escape hatch for mirrors starting otherwise
+        let feed = format!("Axis AMC\n{}", record);
+        assert!(first_error(&feed).is_some());
+        assert_eq!(
+            nav_from_str(&feed)
+                .sniff_content(false)
+                .filter(Result::is_ok)
+                .count(),
+            1
+        );
+        assert_eq!(nav_from_str(page).sniff_content(false).count(), 1);
+    }
+
+    #[test]
+    fn buffer_choices() {
+        let results = |records: NavRecordIterator<File>| -> Vec<String> {
+            records.map(|result| format!("{:?}", result)).collect()
+        };
+        for name in &[
+            "NAVOpen.txt",
+            "NAVHalfGarbage.txt",
+            "NAVLegacy.txt",
+            "NAVMessy.txt",
+        ] {
+            let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+            let open = || nav_from_file(&path).unwrap();
+            let expected = results(open());
+            assert!(!expected.is_empty());
+            for &capacity in &[0, 1, 7, 64 * 1024] {
+                let buffered = results(open().with_buffer_capacity(capacity));
+                assert_eq!(buffered, expected, "{} with {} bytes", name, capacity);
+            }
+            assert_eq!(results(open().read_ahead(true)), expected, "{}", name);
+            assert_eq!(results(open().read_ahead(false)), expected, "{}", name);
+
+            // Changed midway, keeping what was buffered
+            let mut records = open();
+            let first = format!("{:?}", records.next().unwrap());
+            let rest = records.with_buffer_capacity(3).read_ahead(true);
+            let mut changed = vec![first];
+            changed.extend(results(rest.with_buffer_capacity(1 << 20)));
+            assert_eq!(changed, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn skips_lines_too_long() {
+        let feed = "\nAxis Mutual Fund\n\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n";
+        // 100 MB without a newline, generated as read
+        let line = || io::repeat(b'x').take(100 << 20);
+        for &limit in &[None, Some(1024)] {
+            let records = NavRecordIterator::new(line().chain(feed.as_bytes()));
+            let mut records = match limit {
+                Some(limit) => records.max_line_len(limit),
+                None => records,
+            };
+            let limit = limit.unwrap_or(MAX_LINE_LEN);
+            match records.next() {
+                Some(Err(Error::LineTooLong { limit: l, at_line })) => {
+                    assert_eq!((l, at_line), (limit, 1));
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+            // Memory bounded by the limit, not the line
+            assert!(records.bytes.capacity() <= 2 * limit);
+            let record = records.next().unwrap().unwrap();
+            assert_eq!(record.code, 120465);
+            assert_eq!(record.source_line, Some(4));
+            assert!(records.next().is_none());
+        }
+
+        // Up to the limit, line ending excluded
+        let amc = "Axis Mutual Fund\r\n";
+        assert_eq!(nav_from_str(amc).max_line_len(17).count(), 0);
+        let results: Vec<_> = nav_from_str(amc).max_line_len(16).collect();
+        assert!(matches!(
+            results[..],
+            [Err(Error::LineTooLong { at_line: 1, .. })]
+        ));
+    }
+
+    #[test]
+    fn maps_records() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let mut records = nav_from_file(path)
+            .unwrap()
+            .filter_amc("axis")
+            .filter_plan(FundPlan::Direct)
+            .map_records(move |record| {
+                log.lock().unwrap().push(record.amc.clone());
+                Some(record).filter(|record| record.code != 120_465)
+            })
+            .map_records(|mut record| {
+                record.category = "Misfiled".to_string();
+                Some(record)
+            })
+            .take_records(2);
+        let kept: Vec<NavRecord> = records.by_ref().collect::<Result<_>>().unwrap();
+        assert_eq!(kept.len(), 2);
+        assert!(kept.iter().all(|record| record.category == "Misfiled"));
+        assert!(kept.iter().all(|record| record.code != 120_465));
+        // Regular plans failed the filter, the first record was dropped
+        assert_eq!(records.filtered_records(), 2);
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 3);
+        assert!(seen.iter().all(|amc| amc == "Axis Mutual Fund"));
+    }
+
+    #[test]
+    fn panics_in_record_hooks_propagate() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let mut records = nav_from_file(path).unwrap().map_records(|record| {
+            assert_ne!(record.code, 120_466, "misfiled");
+            Some(record)
+        });
+        assert_eq!(records.next().unwrap().unwrap().code, 120_465);
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| records.next()));
+        assert!(panic.is_err());
+        // Parsing goes on after the record
+        let rest: Vec<NavRecord> = records.collect::<Result<_>>().unwrap();
+        assert_eq!(rest.len(), 28);
+    }
+
+    #[test]
+    fn stops_at_unclassified_lines() {
+        let text = "Lorem ipsum\ndolor sit amet\n\nconsectetur\nadipiscing elit\n";
+        let results: Vec<_> = nav_from_str(text)
+            .sniff_content(false)
+            .max_unclassified_lines(3)
+            .collect();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            results[0],
+            Err(Error::TooManyUnclassifiedLines {
+                limit: 3,
+                at_line: 5
+            })
+        ));
+
+        // Records and headers end a run
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    HDFC Mutual Fund\n\
+                    119062;INF179K01XQ0;-;HDFC Top 100 Fund - Direct Plan - Growth;1046.73;21-Jun-2024\n";
+        let records = nav_from_str(feed).max_unclassified_lines(1);
+        assert_eq!(records.filter(Result::is_ok).count(), 2);
     }
 }
+
+/// Compile-time checks of the API provided by each HTTP feature combination
 #[cfg(test)]
-mod tests {
+mod feature_api {
+    use super::*;
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn parser_always_available() {
+        let records: Vec<_> = nav_from_str("Axis Mutual Fund\n").collect();
+        assert!(records.is_empty());
+        let records: Vec<_> = nav_from_reader(io::empty()).collect();
+        assert!(records.is_empty());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn blocking() {
+        let _: fn() -> Result<NavRecordIterator<reqwest::blocking::Response>> = daily_nav;
+        let _ = nav_from_url::<&str>;
+        let _ = nav_from_url_with_meta::<&str>;
+        let _ = is_nav_updated_at::<&str>;
+        let _: fn(AmcId, NavDate, NavDate) -> Result<HistoryRecords> = nav_history_for_amc;
+        let _: fn(AmcId, NavDate, NavDate, &FetchOptions) -> Result<HistoryRecords> =
+            nav_history_for_amc_with;
+        let _: fn() -> Result<Vec<(AmcId, String)>> = fetch_amc_ids;
+        #[cfg(feature = "chrono")]
+        let _ = watch_daily_nav::<fn(NavSnapshot)>;
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn non_blocking() {
+        // Futures are only created, never polled
+        fn future<F: std::future::Future>(_: F) {}
+        let since = FetchMeta::default();
+        future(daily_nav_async());
+        future(nav_from_url_async("http://localhost/NAVAll.txt"));
+        future(is_nav_updated_async(&since));
+        future(is_nav_updated_at_async(
+            "http://localhost/NAVAll.txt",
+            &since,
+        ));
+    }
+
+    #[cfg(all(feature = "async", feature = "stream", feature = "chrono"))]
+    #[test]
+    fn non_blocking_stream() {
+        fn stream<S: futures_core::Stream<Item = NavSnapshot> + Send>(_: S) {}
+        stream(watch_daily_nav_stream(WatchConfig::new()));
+    }
+
+    #[cfg(not(feature = "fetch"))]
+    #[test]
+    fn without_http() {
+        // Variants of HTTP errors only exist with a fetch feature
+        match Error::SynomError(String::new()) {
+            Error::IoError(_) | Error::BuilderError { .. } | Error::SynomError(_) => {}
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Arbitrary input must give errors, never panics
+///
+/// The `fuzz` directory has `cargo fuzz` targets exploring the same parsers in depth, e.g.
+/// `cargo +nightly fuzz run feed`.
+#[cfg(test)]
+mod no_panic {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Record-like lines: fields of arbitrary text, digits and separators
+    fn record_line() -> impl Strategy<Value = String> {
+        let field = prop_oneof![
+            "\\PC*",
+            "[0-9.]{0,24}",
+            "[0-9]{1,2}-[A-Za-z]{3}-[0-9]{4}\\PC?"
+        ];
+        prop::collection::vec(field, 0..8).prop_map(|fields| fields.join(";"))
+    }
+
+    proptest! {
+        #[test]
+        fn parse_record_never_panics(line in record_line()) {
+            let _ = NavRecord::parse_line(&line);
+            let _ = parse_record(&line, Layout::Modern);
+            let _ = parse_record(&line, Layout::Legacy);
+            let _ = parse_record(&line, Layout::History);
+            let _ = recovery::recover(&line, Layout::Modern);
+            let _ = recovery::recover(&line, Layout::Legacy);
+            let _ = recovery::recover(&line, Layout::History);
+        }
+
+        #[test]
+        fn parse_scheme_never_panics(line in "\\PC*") {
+            let _ = parse_scheme_header(&line);
+            let _ = parse_scheme_header(&format!("Open Ended Schemes({}", line));
+        }
+
+        #[test]
+        fn feed_never_panics(lines in prop::collection::vec(
+            prop_oneof![record_line(), "Open Ended Schemes\\PC*", "\\PC*"],
+            0..16,
+        )) {
+            let feed = lines.join("\n");
+            let _ = nav_from_str(&feed).count();
+        }
+
+        #[test]
+        fn bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            let _ = nav_from_reader(&bytes[..]).count();
+        }
+    }
+
+    #[test]
+    fn regressions() {
+        // Multi-byte whitespace and alphanumerics around separators
+        assert!(NavRecord::parse_line("1;\u{3000}INF846K01AB4;-;Fund;1.0;21-Jun-2024").is_ok());
+        assert!(NavRecord::parse_line("1;ÉINF846K01AB4;-;Fund;1.0;21-Jun-2024").is_ok());
+        // Numbers which do not fit, or are only a decimal point
+        assert!(NavRecord::parse_line("99999999999;-;-;Fund;1.0;21-Jun-2024").is_err());
+        assert!(NavRecord::parse_line("1;-;-;Fund;.;21-Jun-2024").is_err());
+        // Date cut inside a multi-byte character
+        assert!(NavRecord::parse_line("1;-;-;Fund;1.0;21-Jun-202€").is_err());
+        assert!(parse_scheme_header("Open Ended Schemes(").is_err());
     }
 }
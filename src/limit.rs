@@ -0,0 +1,246 @@
+//! Spacing of requests to the AMFI portal
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time source of a [`RateLimiter`](struct.RateLimiter.html), replaceable to test without
+/// sleeping
+pub trait Clock: Send + Sync {
+    /// Current instant
+    fn now(&self) -> Instant;
+
+    /// Blocks the current thread for `duration`
+    fn sleep(&self, duration: Duration);
+}
+
+/// [`Clock`](trait.Clock.html) of the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Minimum interval between requests, shared by its clones
+///
+/// Each request through the limiter reserves the earliest slot at least `interval`, plus a
+/// random jitter, after the previous one and waits for it. Clones share the slots, so one
+/// limiter attached to [`FetchOptions`](struct.FetchOptions.html) and
+/// [`WatchConfig`](struct.WatchConfig.html) spaces all their requests, from any thread.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// let limiter = amfi::RateLimiter::new(Duration::from_secs(60)).jitter(Duration::from_secs(5));
+/// let options = amfi::FetchOptions::new().rate_limiter(limiter.clone());
+/// // The first request goes out at once
+/// assert_eq!(limiter.reserve(), Duration::from_secs(0));
+/// assert!(limiter.reserve() >= Duration::from_secs(60));
+/// # drop(options);
+/// ```
+#[derive(Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    jitter: Duration,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<State>>,
+}
+
+/// Slots of a limiter and its clones
+struct State {
+    /// Earliest instant of the next request, `None` before the first
+    next: Option<Instant>,
+    /// xorshift state for jitter
+    random: u64,
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("interval", &self.interval)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+impl RateLimiter {
+    /// Spaces requests at least `interval` apart
+    pub fn new(interval: Duration) -> Self {
+        // Randomly keyed, so limiters of different processes don't jitter alike
+        let seed = RandomState::new().build_hasher().finish();
+        RateLimiter {
+            interval,
+            jitter: Duration::from_secs(0),
+            clock: Arc::new(SystemClock),
+            state: Arc::new(Mutex::new(State {
+                next: None,
+                random: seed | 1,
+            })),
+        }
+    }
+
+    /// Adds a random delay of up to `jitter` to every interval
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Reads time from and sleeps with `clock` instead of the system clock
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Reserves the slot of the next request, returning how long to wait before making it
+    ///
+    /// For callers sleeping by other means than [`wait`](#method.wait); the slot is taken
+    /// whether or not they wait.
+    pub fn reserve(&self) -> Duration {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let start = state.next.map_or(now, |next| next.max(now));
+        let jitter = self.jitter.mul_f64(state.fraction());
+        state.next = Some(start + self.interval + jitter);
+        start - now
+    }
+
+    /// Blocks until the next request may be made
+    pub fn wait(&self) {
+        let wait = self.reserve();
+        if wait > Duration::from_secs(0) {
+            self.clock.sleep(wait);
+        }
+    }
+
+    /// Waits without blocking until the next request may be made
+    ///
+    /// Sleeps on the [tokio](https://crates.io/crates/tokio) timer, whatever the clock.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(&self) {
+        let wait = self.reserve();
+        if wait > Duration::from_secs(0) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl State {
+    /// Pseudo-random number in `[0, 1)`
+    fn fraction(&mut self) -> f64 {
+        let mut x = self.random;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.random = x;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock advanced only by sleeping
+    struct MockClock {
+        now: Mutex<Instant>,
+        slept: Mutex<Vec<Duration>>,
+    }
+
+    impl MockClock {
+        fn new() -> Arc<Self> {
+            Arc::new(MockClock {
+                now: Mutex::new(Instant::now()),
+                slept: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+        }
+
+        fn slept(&self) -> Vec<Duration> {
+            self.slept.lock().unwrap().clone()
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.slept.lock().unwrap().push(duration);
+            self.advance(duration);
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn waits_for_interval_since_last_request() {
+        let clock = MockClock::new();
+        let limiter = RateLimiter::new(secs(10)).clock(clock.clone());
+        limiter.wait();
+        limiter.wait();
+        clock.advance(secs(4));
+        limiter.wait();
+        clock.advance(secs(25));
+        limiter.wait();
+        assert_eq!(clock.slept(), [secs(10), secs(6)]);
+    }
+
+    #[test]
+    fn clones_share_slots() {
+        let clock = MockClock::new();
+        let limiter = RateLimiter::new(secs(10)).clock(clock.clone());
+        let other = limiter.clone();
+        assert_eq!(limiter.reserve(), secs(0));
+        assert_eq!(other.reserve(), secs(10));
+        assert_eq!(limiter.reserve(), secs(20));
+
+        // Concurrent requests get distinct slots
+        let limiter = RateLimiter::new(secs(1)).clock(clock);
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let limiter = limiter.clone();
+                thread::spawn(move || limiter.reserve())
+            })
+            .collect();
+        let mut waits: Vec<u64> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap().as_secs())
+            .collect();
+        waits.sort_unstable();
+        assert_eq!(waits, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn jitter_is_bounded() {
+        let clock = MockClock::new();
+        let limiter = RateLimiter::new(secs(10))
+            .jitter(secs(2))
+            .clock(clock.clone());
+        limiter.wait();
+        for _ in 0..100 {
+            limiter.wait();
+        }
+        let slept = clock.slept();
+        assert_eq!(slept.len(), 100);
+        assert!(slept
+            .iter()
+            .all(|&wait| wait >= secs(10) && wait <= secs(12)));
+        assert!(slept.iter().any(|&wait| wait != slept[0]));
+    }
+}
@@ -0,0 +1,243 @@
+//! Lines of a feed with their place in its structure
+
+use crate::{
+    Error, FeedHeader, FeedState, FundMaturity, NavRecord, NavRecordIterator, Result, Step,
+};
+use std::io::Read;
+
+/// Line of a feed, see [`feed_lines`](fn.feed_lines.html)
+// Lines are yielded one at a time, so records aren't boxed to keep headers small
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum FeedLine {
+    /// Column header, `Scheme Code;ISIN Div Payout/ ISIN Growth;...`
+    Header,
+    /// Blank line
+    Blank,
+    /// AMC header starting the records of an AMC, trimmed
+    Amc(String),
+    /// Scheme header starting the AMCs of a category, e.g. `Open Ended Schemes(Equity Scheme -
+    /// Large Cap Fund)`
+    SchemeHeader {
+        /// Open or close ended, `None` if the header says neither
+        maturity: Option<FundMaturity>,
+        /// Scheme, `None` for headers naming a category only
+        scheme: Option<String>,
+        /// Category
+        category: String,
+    },
+    /// Record of the current AMC and category
+    Record(NavRecord),
+    /// Record line that couldn't be parsed, where
+    /// [`NavRecordIterator`](struct.NavRecordIterator.html) yields `error`
+    Unparsed {
+        /// Line, trimmed
+        line: String,
+        /// Why it couldn't be parsed
+        error: Error,
+    },
+}
+
+/// Iterator over the lines of a feed, see [`feed_lines`](fn.feed_lines.html)
+pub struct FeedLines<R> {
+    records: NavRecordIterator<R>,
+}
+
+/// Parses every line of a feed, keeping its structure of AMC and scheme headers
+///
+/// Lines are classified and parsed by the same code as for
+/// [`NavRecordIterator`](struct.NavRecordIterator.html), whose records and errors are those of
+/// the `Record` and `Unparsed` lines. Errors end the iteration: I/O errors, a first line not
+/// starting a feed and malformed scheme headers.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::FeedLine;
+///
+/// let file = std::fs::File::open("fixtures/NAVOpen.txt")?;
+/// for line in amfi::feed_lines(file) {
+///     match line? {
+///         FeedLine::SchemeHeader { category, .. } => println!("{}", category),
+///         FeedLine::Amc(amc) => println!("  {}", amc),
+///         FeedLine::Record(record) => println!("    {}", record.name),
+///         FeedLine::Unparsed { line, error } => println!("    {}: {}", line, error),
+///         FeedLine::Header | FeedLine::Blank => {}
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn feed_lines<R: Read>(reader: R) -> FeedLines<R> {
+    FeedLines {
+        records: NavRecordIterator::new(reader),
+    }
+}
+
+impl<R: Read> FeedLines<R> {
+    /// 1-based number of the last line read
+    pub fn line(&self) -> usize {
+        self.records.line()
+    }
+
+    /// 1-based number of the feed being read in a concatenation of feeds, see
+    /// [`NavRecordIterator::segment`](struct.NavRecordIterator.html#method.segment)
+    pub fn segment(&self) -> usize {
+        self.records.segment()
+    }
+
+    /// Column header read so far, see
+    /// [`NavRecordIterator::header`](struct.NavRecordIterator.html#method.header)
+    pub fn header(&self) -> Option<&FeedHeader> {
+        self.records.header()
+    }
+}
+
+impl<R: Read> Iterator for FeedLines<R> {
+    type Item = Result<FeedLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records
+            .next_with(|state, line| Some(state.feed_line(line)))
+    }
+}
+
+impl FeedState {
+    /// Parses the next `line` of the feed, newline included, into its kind and contents
+    fn feed_line(&mut self, line: &str) -> Result<FeedLine> {
+        let unparsed = |error| FeedLine::Unparsed {
+            line: line.trim().to_string(),
+            error,
+        };
+        Ok(match self.advance(line) {
+            // Without filters no section is skipped
+            Step::Record | Step::Skip => match self.section.parse_record(
+                line,
+                Some(self.line),
+                self.layout(),
+                self.capture_raw,
+                &self.normalize,
+            ) {
+                Ok(record) => FeedLine::Record(record),
+                Err(error) => unparsed(error),
+            },
+            Step::Header => FeedLine::Header,
+            Step::Blank => FeedLine::Blank,
+            Step::Amc => FeedLine::Amc(self.section.amc.clone()),
+            Step::Scheme => FeedLine::SchemeHeader {
+                maturity: self.section.maturity,
+                scheme: self.section.scheme.clone(),
+                category: self.section.category.clone(),
+            },
+            Step::Invalid(error) => unparsed(error),
+            Step::Bailout(error) => return Err(error),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nav_from_reader, FundPlan};
+
+    const FEED: &str = "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Date\n\
+                        \n\
+                        120466;INF846K01AC2;INF846K01AD0;Stray Fund;21.98;21-Jun-2024\n\
+                        Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                        \n\
+                        Axis Mutual Fund \n\
+                        120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                        120467;garbage;\n\
+                        Close Ended Schemes(Income)\n";
+
+    /// Records and errors of the `Record` and `Unparsed` lines
+    fn records(lines: Vec<Result<FeedLine>>) -> Vec<Result<NavRecord>> {
+        lines
+            .into_iter()
+            .filter_map(|line| match line {
+                Ok(FeedLine::Record(record)) => Some(Ok(record)),
+                Ok(FeedLine::Unparsed { error, .. }) => Some(Err(error)),
+                Ok(_) => None,
+                Err(error) => Some(Err(error)),
+            })
+            .collect()
+    }
+
+    fn same(left: Vec<Result<NavRecord>>, right: Vec<Result<NavRecord>>) {
+        assert_eq!(left.len(), right.len());
+        for (left, right) in left.into_iter().zip(right) {
+            match (left, right) {
+                (Ok(left), Ok(right)) => assert_eq!(left, right),
+                (Err(left), Err(right)) => assert_eq!(left.to_string(), right.to_string()),
+                (left, right) => panic!("{:?} != {:?}", left, right),
+            }
+        }
+    }
+
+    #[test]
+    fn structure() {
+        let lines: Vec<_> = feed_lines(FEED.as_bytes()).collect();
+        assert_eq!(lines.len(), 9);
+        assert!(matches!(lines[0], Ok(FeedLine::Header)));
+        assert!(matches!(lines[1], Ok(FeedLine::Blank)));
+        match &lines[2] {
+            Ok(FeedLine::Unparsed { line, .. }) => assert!(line.starts_with("120466;")),
+            other => panic!("{:?}", other),
+        }
+        match &lines[3] {
+            Ok(FeedLine::SchemeHeader {
+                maturity,
+                scheme,
+                category,
+            }) => {
+                assert_eq!(*maturity, Some(FundMaturity::OpenEnded));
+                assert_eq!(scheme.as_deref(), Some("Equity Scheme"));
+                assert_eq!(category, "Large Cap Fund");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(&lines[5], Ok(FeedLine::Amc(amc)) if amc == "Axis Mutual Fund"));
+        match &lines[6] {
+            Ok(FeedLine::Record(record)) => {
+                assert_eq!(record.amc, "Axis Mutual Fund");
+                assert_eq!(record.plan, FundPlan::Direct);
+                assert_eq!(record.source_line, Some(7));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(lines[7], Ok(FeedLine::Unparsed { .. })));
+        assert!(matches!(
+            lines[8],
+            Ok(FeedLine::SchemeHeader {
+                maturity: Some(FundMaturity::CloseEnded),
+                scheme: None,
+                ..
+            })
+        ));
+
+        same(records(lines), nav_from_reader(FEED.as_bytes()).collect());
+    }
+
+    #[test]
+    fn agrees_with_records() {
+        for fixture in &["NAVOpen.txt", "NAVLegacy.txt", "NAVHalfGarbage.txt"] {
+            let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+            let body = std::fs::read(&path).unwrap();
+            let lines: Vec<_> = feed_lines(&body[..]).collect();
+            same(records(lines), nav_from_reader(&body[..]).collect());
+        }
+    }
+
+    #[test]
+    fn errors_end_lines() {
+        let lines: Vec<_> = feed_lines("<html>".as_bytes()).collect();
+        assert_eq!(lines.len(), 1);
+        assert!(matches!(lines[0], Err(Error::UnexpectedContent { .. })));
+
+        let feed = "Axis Mutual Fund\nOpen Ended Schemes(Large Cap Fund\nBlank\n";
+        let mut lines = feed_lines(feed.as_bytes());
+        assert!(matches!(lines.next(), Some(Ok(FeedLine::Amc(_)))));
+        assert!(matches!(lines.next(), Some(Err(Error::SynomError(_)))));
+        assert!(lines.next().is_none());
+        assert_eq!(lines.line(), 2);
+    }
+}
@@ -0,0 +1,137 @@
+//! Records of a few schemes read off a feed, without reading the rest of it
+
+use crate::{Error, NavRecord, NavRecordIterator, Result, SchemeCode};
+use log::warn;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+
+/// First record of each of `codes` read by `records`, reading no further than the last one
+///
+/// Lines failing to parse are logged and passed over; other errors, e.g. of I/O or content
+/// that is no feed, fail the lookup.
+pub(crate) fn find_codes<R: Read>(
+    records: NavRecordIterator<R>,
+    codes: BTreeSet<SchemeCode>,
+) -> Result<BTreeMap<SchemeCode, NavRecord>> {
+    let mut found = BTreeMap::new();
+    if codes.is_empty() {
+        return Ok(found);
+    }
+    for result in records {
+        match result {
+            Ok(record) => {
+                if codes.contains(&record.code) && !found.contains_key(&record.code) {
+                    found.insert(record.code, record);
+                    if found.len() == codes.len() {
+                        break;
+                    }
+                }
+            }
+            Err(error) if is_line_error(&error) => warn!("{}", error),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(found)
+}
+
+/// Whether `error` is of one line, parsing going on after it
+fn is_line_error(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::ParseFailure { .. }
+            | Error::BuilderError { .. }
+            | Error::LineTooLong { .. }
+            | Error::InvalidUtf8 { .. }
+            | Error::OrderingViolation { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_bytes;
+    use std::io;
+
+    /// Reader counting the bytes read through it
+    struct Counting<'a> {
+        inner: &'a [u8],
+        read: &'a mut usize,
+    }
+
+    impl Read for Counting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            *self.read += read;
+            Ok(read)
+        }
+    }
+
+    /// Records of `codes` found in `feed`, and the bytes read to find them
+    fn find(feed: &[u8], codes: &[u32]) -> (Result<BTreeMap<SchemeCode, NavRecord>>, usize) {
+        let mut read = 0;
+        let reader = Counting {
+            inner: feed,
+            read: &mut read,
+        };
+        let records = crate::nav_from_reader(reader).with_buffer_capacity(64);
+        let codes = codes.iter().map(|&code| SchemeCode::from(code)).collect();
+        let found = find_codes(records, codes);
+        (found, read)
+    }
+
+    #[test]
+    fn stops_at_the_last_code_found() {
+        let feed = fixture_bytes("NAVOpen.txt");
+        let (found, read) = find(&feed, &[120_465]);
+        let found = found.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[&SchemeCode::from(120_465)].nav, 61.43);
+        assert!(read < feed.len() / 10, "read {} bytes", read);
+
+        let (found, read) = find(&feed, &[119_551, 112_277, 999_999]);
+        let found = found.unwrap();
+        assert_eq!(
+            found.keys().map(|code| code.get()).collect::<Vec<_>>(),
+            [112_277, 119_551]
+        );
+        // Not found: the whole feed was read
+        assert_eq!(read, feed.len());
+
+        let (found, read) = find(&feed, &[]);
+        assert!(found.unwrap().is_empty());
+        assert_eq!(read, 0);
+    }
+
+    #[test]
+    fn reads_half_the_feed_for_a_code() {
+        // The feed is ordered by category and AMC, not by code, so a code is anywhere in it
+        let feed = fixture_bytes("NAVOpen.txt");
+        let codes: Vec<u32> = crate::nav_from_reader(&feed[..])
+            .map(|record| record.unwrap().code.get())
+            .collect();
+        let share: f64 = codes
+            .iter()
+            .map(|&code| {
+                let (found, read) = find(&feed, &[code]);
+                assert_eq!(found.unwrap().len(), 1);
+                read as f64 / feed.len() as f64
+            })
+            .sum::<f64>()
+            / codes.len() as f64;
+        assert!((0.4..0.6).contains(&share), "{}", share);
+    }
+
+    #[test]
+    fn skips_bad_lines_but_not_bad_feeds() {
+        let feed = "Axis Mutual Fund\n\
+                    120466;garbage;\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n";
+        let (found, _) = find(feed.as_bytes(), &[120_465]);
+        assert_eq!(found.unwrap().len(), 1);
+        let (found, _) = find(
+            b"<html><body>Service Unavailable</body></html>\n",
+            &[120_465],
+        );
+        assert!(matches!(found, Err(Error::UnexpectedContent { .. })));
+    }
+}
@@ -0,0 +1,335 @@
+//! Records nested by AMC and scheme section, as laid out in the feed
+
+use crate::{
+    Error, FofKind, FundMaturity, FundPlan, NavDate, NavRecord, PlanSource, RawFields, RawIsin,
+    Result, SchemeCode,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Records of a feed nested by AMC, then scheme section, see [`to_nested`](fn.to_nested.html)
+///
+/// Serializes as an array of AMCs.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct NestedFeed {
+    /// AMCs in the order of their first record
+    pub amcs: Vec<AmcSection>,
+}
+
+/// Records of one AMC, by scheme section
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AmcSection {
+    /// Name of the AMC, as in its records
+    pub name: String,
+    /// Scheme sections in the order of their first record of the AMC
+    pub sections: Vec<SchemeSection>,
+}
+
+/// Records of one AMC under one scheme header
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SchemeSection {
+    /// Maturity of the section's records
+    pub maturity: Option<FundMaturity>,
+    /// Scheme of the section's records
+    pub scheme: Option<String>,
+    /// Category of the section's records
+    pub category: String,
+    /// Records in feed order
+    pub records: Vec<LeanRecord>,
+}
+
+/// Record without the fields given by its AMC and scheme section
+///
+/// Fields are those of [`NavRecord`](struct.NavRecord.html) but `amc`, `category`, `scheme`
+/// and `maturity`, serialized the same way.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LeanRecord {
+    /// Scheme Code
+    pub code: SchemeCode,
+    /// ISIN Growth/Divdend Payout
+    pub isin: Option<RawIsin>,
+    /// ISIN Divdend Reinvestment
+    pub isin_dr: Option<RawIsin>,
+    /// Scheme Name
+    pub name: String,
+    /// Net Asset Value (NAV)
+    pub nav: f64,
+    /// NAV Date, serialized as ISO 8601 string
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::date_iso"))]
+    pub date: NavDate,
+    /// Plan (Regular/Direct)
+    pub plan: FundPlan,
+    /// How `plan` was decided
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub plan_source: PlanSource,
+    /// Option (Growth/Monthly Dividend Payout etc.)
+    pub option: Option<String>,
+    /// Kind of Fund of Funds, `None` for other schemes
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fof: Option<FofKind>,
+    /// Repurchase Price, only in the legacy and history layouts
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub repurchase_price: Option<f64>,
+    /// Sale Price, only in the legacy and history layouts
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub sale_price: Option<f64>,
+    /// Text as published, only if captured
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub raw: Option<RawFields>,
+    /// 1-based number of the feed line the record was read from
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub source_line: Option<u32>,
+    /// 1-based number of the feed the record was read from, only if stamped
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub segment: Option<u32>,
+    /// Whether the record was read from a line with shifted columns
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub recovered: bool,
+}
+
+/// Nests parse results by AMC, then scheme section, returning the errors
+///
+/// Feeds list AMCs under each scheme header; here scheme sections are listed under each AMC
+/// instead. Sections are told apart by maturity, scheme and category, as they are in the
+/// records; AMCs by name.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let (feed, errors) = amfi::to_nested(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+/// assert!(errors.is_empty());
+/// for amc in &feed.amcs {
+///     for section in &amc.sections {
+///         println!("{} / {}: {}", amc.name, section.category, section.records.len());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_nested<I>(results: I) -> (NestedFeed, Vec<Error>)
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut feed = NestedFeed::default();
+    let mut errors = Vec::new();
+    let mut amcs: HashMap<String, usize> = HashMap::new();
+    let mut sections: HashMap<(usize, Option<FundMaturity>, Option<String>, String), usize> =
+        HashMap::new();
+    for result in results {
+        let record = match result {
+            Ok(record) => record,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        let (lean, amc, maturity, scheme, category) = LeanRecord::split(record);
+        let amc_at = *amcs.entry(amc).or_insert_with_key(|name| {
+            feed.amcs.push(AmcSection {
+                name: name.clone(),
+                sections: Vec::new(),
+            });
+            feed.amcs.len() - 1
+        });
+        let amc = &mut feed.amcs[amc_at];
+        let section_at = *sections
+            .entry((amc_at, maturity, scheme, category))
+            .or_insert_with_key(|(_, maturity, scheme, category)| {
+                amc.sections.push(SchemeSection {
+                    maturity: *maturity,
+                    scheme: scheme.clone(),
+                    category: category.clone(),
+                    records: Vec::new(),
+                });
+                amc.sections.len() - 1
+            });
+        amc.sections[section_at].records.push(lean);
+    }
+    (feed, errors)
+}
+
+impl NestedFeed {
+    /// Number of records of all AMCs
+    pub fn len(&self) -> usize {
+        self.amcs
+            .iter()
+            .flat_map(|amc| &amc.sections)
+            .map(|section| section.records.len())
+            .sum()
+    }
+
+    /// Whether there are no records
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Flat records, by AMC then section, with the fields given by their AMC and section
+    pub fn into_records(self) -> Vec<NavRecord> {
+        let mut records = Vec::with_capacity(self.len());
+        for amc in self.amcs {
+            for mut section in amc.sections {
+                for lean in std::mem::take(&mut section.records) {
+                    records.push(lean.into_record(&amc.name, &section));
+                }
+            }
+        }
+        records
+    }
+}
+
+impl LeanRecord {
+    /// Lean record of `record`, and its AMC, maturity, scheme and category
+    fn split(record: NavRecord) -> (Self, String, Option<FundMaturity>, Option<String>, String) {
+        let NavRecord {
+            code,
+            isin,
+            isin_dr,
+            name,
+            nav,
+            date,
+            amc,
+            category,
+            scheme,
+            maturity,
+            plan,
+            plan_source,
+            option,
+            fof,
+            repurchase_price,
+            sale_price,
+            raw,
+            source_line,
+            segment,
+            recovered,
+        } = record;
+        let lean = LeanRecord {
+            code,
+            isin,
+            isin_dr,
+            name,
+            nav,
+            date,
+            plan,
+            plan_source,
+            option,
+            fof,
+            repurchase_price,
+            sale_price,
+            raw,
+            source_line,
+            segment,
+            recovered,
+        };
+        (lean, amc, maturity, scheme, category)
+    }
+
+    /// Record of AMC `amc` in `section`
+    pub fn into_record(self, amc: &str, section: &SchemeSection) -> NavRecord {
+        NavRecord {
+            code: self.code,
+            isin: self.isin,
+            isin_dr: self.isin_dr,
+            name: self.name,
+            nav: self.nav,
+            date: self.date,
+            amc: amc.to_string(),
+            category: section.category.clone(),
+            scheme: section.scheme.clone(),
+            maturity: section.maturity,
+            plan: self.plan,
+            plan_source: self.plan_source,
+            option: self.option,
+            fof: self.fof,
+            repurchase_price: self.repurchase_price,
+            sale_price: self.sale_price,
+            raw: self.raw,
+            source_line: self.source_line,
+            segment: self.segment,
+            recovered: self.recovered,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+    use std::collections::HashSet;
+
+    #[test]
+    fn nests_fixture() {
+        let records = fixture_records("NAVOpen.txt");
+        let (feed, errors) = to_nested(records.iter().cloned().map(Ok));
+        assert!(errors.is_empty());
+        assert_eq!(feed.len(), records.len());
+
+        let amcs: HashSet<&str> = records.iter().map(|r| r.amc.as_str()).collect();
+        assert_eq!(feed.amcs.len(), amcs.len());
+        assert_eq!(feed.amcs[0].name, records[0].amc);
+        for amc in &feed.amcs {
+            let sections: HashSet<(Option<FundMaturity>, Option<&str>, &str)> = records
+                .iter()
+                .filter(|r| r.amc == amc.name)
+                .map(|r| (r.maturity, r.scheme.as_deref(), r.category.as_str()))
+                .collect();
+            assert_eq!(amc.sections.len(), sections.len());
+            assert!(amc.sections.iter().all(|s| !s.records.is_empty()));
+        }
+
+        // By AMC then section, each in feed order
+        let mut expected = records.clone();
+        let section_of = |record: &NavRecord| {
+            let amc = feed.amcs.iter().position(|a| a.name == record.amc).unwrap();
+            let section = feed.amcs[amc]
+                .sections
+                .iter()
+                .position(|s| s.category == record.category && s.scheme == record.scheme)
+                .unwrap();
+            (amc, section)
+        };
+        expected.sort_by_key(section_of);
+        let flat = feed.into_records();
+        assert_eq!(flat, expected);
+        for (record, expected) in flat.iter().zip(&expected) {
+            assert_eq!(record.source_line, expected.source_line);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_lean_records() {
+        let (feed, _) = to_nested(fixture_records("NAVOpen.txt").into_iter().map(Ok));
+        let json = serde_json::to_value(&feed).unwrap();
+        let amcs = json.as_array().unwrap();
+        assert_eq!(amcs.len(), feed.amcs.len());
+        let record = &amcs[0]["sections"][0]["records"][0];
+        assert!(record.get("amc").is_none() && record.get("category").is_none());
+        assert!(record.get("repurchase_price").is_none());
+        assert_eq!(record["date"], "2024-06-21");
+
+        let parsed: NestedFeed = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, feed);
+    }
+}
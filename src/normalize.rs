@@ -0,0 +1,266 @@
+//! Cleanup of the texts of parsed records
+
+use std::borrow::Cow;
+
+/// Case of a text after normalization, see [`NormalizeOptions`](struct.NormalizeOptions.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseFolding {
+    /// Case as published
+    #[default]
+    Keep,
+    /// Uppercase
+    Upper,
+    /// Lowercase
+    Lower,
+}
+
+impl CaseFolding {
+    fn apply(self, text: Cow<'_, str>) -> Cow<'_, str> {
+        match self {
+            CaseFolding::Keep => text,
+            CaseFolding::Upper => Cow::Owned(text.to_uppercase()),
+            CaseFolding::Lower => Cow::Owned(text.to_lowercase()),
+        }
+    }
+}
+
+/// Cleanup of scheme names, AMCs, categories and schemes, see
+/// [`NavRecordIterator::normalize`](struct.NavRecordIterator.html#method.normalize)
+///
+/// The defaults give the texts of earlier releases: names and AMCs trimmed, names without
+/// control characters and categories as published between the delimiters of their header.
+/// Filters of the iterator see the normalized texts.
+/// [`raw`](#method.raw) keeps every text as published.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::{CaseFolding, NormalizeOptions};
+///
+/// let options = NormalizeOptions {
+///     collapse_whitespace: true,
+///     name_case: CaseFolding::Upper,
+///     strip_amc_suffix: true,
+///     ..NormalizeOptions::default()
+/// };
+/// let record = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+///     .normalize(options)
+///     .next()
+///     .unwrap()?;
+/// assert_eq!(record.name, "AXIS BLUECHIP FUND - DIRECT PLAN - GROWTH");
+/// assert_eq!(record.amc, "Axis");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Whether leading and trailing whitespace of names and AMCs is removed, `true` by default
+    pub trim: bool,
+    /// Whether runs of whitespace become a single space and leading and trailing whitespace
+    /// is removed, in all texts, `false` by default
+    pub collapse_whitespace: bool,
+    /// Whether control characters other than tabs are removed from record lines before
+    /// parsing, tabs in names becoming spaces, `true` by default
+    ///
+    /// Without it a control character outside the name makes its record line unparsable.
+    pub strip_controls: bool,
+    /// Case of scheme names, as published by default
+    pub name_case: CaseFolding,
+    /// Case of AMCs, as published by default
+    pub amc_case: CaseFolding,
+    /// Case of categories, as published by default
+    pub category_case: CaseFolding,
+    /// Whether a trailing `Mutual Fund` is removed from AMCs, e.g. `Axis Mutual Fund` becoming
+    /// `Axis`, `false` by default
+    pub strip_amc_suffix: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            trim: true,
+            collapse_whitespace: false,
+            strip_controls: true,
+            name_case: CaseFolding::Keep,
+            amc_case: CaseFolding::Keep,
+            category_case: CaseFolding::Keep,
+            strip_amc_suffix: false,
+        }
+    }
+}
+
+impl NormalizeOptions {
+    /// Options keeping texts as published, but for the line terminators of AMC headers
+    pub fn raw() -> Self {
+        NormalizeOptions {
+            trim: false,
+            strip_controls: false,
+            ..NormalizeOptions::default()
+        }
+    }
+
+    /// Record `line` trimmed, without control characters unless kept
+    pub(crate) fn record_line<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let trimmed = line.trim();
+        if self.strip_controls {
+            crate::strip_controls(trimmed)
+        } else {
+            Cow::Borrowed(trimmed)
+        }
+    }
+
+    /// Scheme name from its `raw` field, borrowed unless changed
+    pub(crate) fn name<'a>(&self, raw: &'a str) -> Cow<'a, str> {
+        let mut name = Cow::Borrowed(raw);
+        if self.strip_controls && name.contains('\t') {
+            name = Cow::Owned(name.replace('\t', " "));
+        }
+        self.name_case.apply(self.clean(name))
+    }
+
+    /// AMC from its header `line`
+    pub(crate) fn amc(&self, line: &str) -> String {
+        let line = line.trim_end_matches(&['\r', '\n'][..]);
+        let mut amc = self.clean(Cow::Borrowed(line));
+        if self.strip_amc_suffix {
+            if let Some(stripped) = strip_suffix_ignore_ascii_case(amc.trim_end(), "Mutual Fund") {
+                amc = Cow::Owned(stripped.trim_end().to_string());
+            }
+        }
+        self.amc_case.apply(amc).into_owned()
+    }
+
+    /// Category from its `raw` text in the scheme header
+    pub(crate) fn category(&self, raw: String) -> String {
+        self.category_case
+            .apply(self.collapse(Cow::Owned(raw)))
+            .into_owned()
+    }
+
+    /// Scheme from its `raw` text in the scheme header
+    pub(crate) fn scheme(&self, raw: String) -> String {
+        self.collapse(Cow::Owned(raw)).into_owned()
+    }
+
+    /// `text` trimmed and collapsed as configured
+    fn clean<'a>(&self, mut text: Cow<'a, str>) -> Cow<'a, str> {
+        if self.trim {
+            text = match text {
+                Cow::Borrowed(text) => Cow::Borrowed(text.trim()),
+                Cow::Owned(text) => Cow::Owned(text.trim().to_string()),
+            };
+        }
+        self.collapse(text)
+    }
+
+    fn collapse<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        if !self.collapse_whitespace {
+            return text;
+        }
+        match crate::group::collapse_whitespace(&text) {
+            Cow::Borrowed(_) => text,
+            Cow::Owned(collapsed) => Cow::Owned(collapsed),
+        }
+    }
+}
+
+/// `text` without a case-insensitive ASCII `suffix`
+fn strip_suffix_ignore_ascii_case<'a>(text: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = text.len().checked_sub(suffix.len())?;
+    let tail = text.as_bytes().get(start..)?;
+    if tail.eq_ignore_ascii_case(suffix.as_bytes()) && text.is_char_boundary(start) {
+        Some(&text[..start])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nav_from_file, NavRecord, Result};
+
+    const MESSY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVMessy.txt");
+
+    fn records(options: Option<NormalizeOptions>) -> Vec<NavRecord> {
+        let mut records = nav_from_file(MESSY).unwrap();
+        if let Some(options) = options {
+            records = records.normalize(options);
+        }
+        records.collect::<Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn defaults_keep_output() {
+        let plain = records(None);
+        assert_eq!(plain, records(Some(NormalizeOptions::default())));
+        assert_eq!(plain.len(), 4);
+        assert_eq!(plain[0].name, "Axis  Bluechip Fund - Direct Plan -  Growth");
+        assert_eq!(plain[0].amc, "Axis  Mutual Fund");
+        assert_eq!(plain[0].category, "Large  Cap Fund ");
+        assert_eq!(plain[1].name, "Axis Bluechip Fund - Regular Plan - Growth");
+        assert_eq!(plain[2].amc, "HDFC Mutual Fund");
+        assert_eq!(plain[3].category, "Other  ETFs");
+    }
+
+    #[test]
+    fn raw_keeps_text() {
+        let raw = records(Some(NormalizeOptions::raw()));
+        let plain = records(None);
+        assert_eq!(raw.len(), plain.len());
+        assert_eq!(raw[0].amc, "  Axis  Mutual Fund \t");
+        assert_eq!(raw[0].category, plain[0].category);
+        assert_eq!(raw[1].name, "Axis Bluechip Fund - Regular Plan - Growth\t");
+        assert_eq!(raw[3].name, "SBI\tNifty 50 ETF");
+        for (raw, plain) in raw.iter().zip(&plain) {
+            assert_eq!(raw.name.trim().replace('\t', " "), plain.name);
+            assert_eq!(
+                (raw.code, raw.nav, raw.plan),
+                (plain.code, plain.nav, plain.plan)
+            );
+        }
+    }
+
+    #[test]
+    fn collapse_fold_and_strip() {
+        let options = NormalizeOptions {
+            collapse_whitespace: true,
+            name_case: CaseFolding::Upper,
+            amc_case: CaseFolding::Lower,
+            category_case: CaseFolding::Upper,
+            strip_amc_suffix: true,
+            ..NormalizeOptions::default()
+        };
+        let records = records(Some(options));
+        assert_eq!(records[0].name, "AXIS BLUECHIP FUND - DIRECT PLAN - GROWTH");
+        assert_eq!(records[0].amc, "axis");
+        assert_eq!(records[0].category, "LARGE CAP FUND");
+        assert_eq!(records[0].scheme.as_deref(), Some("Equity Scheme"));
+        assert_eq!(records[2].amc, "hdfc");
+        assert_eq!(records[3].name, "SBI NIFTY 50 ETF");
+        assert_eq!(records[3].category, "OTHER ETFS");
+        // Categories are still recognized
+        assert!(records.iter().all(|record| record.fof.is_none()));
+        assert_eq!(records[3].vehicle(), crate::VehicleKind::Etf);
+
+        // Filters see normalized texts
+        let count = nav_from_file(MESSY)
+            .unwrap()
+            .normalize(options)
+            .filter_amc_exact("AXIS")
+            .count();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn suffix() {
+        assert_eq!(
+            strip_suffix_ignore_ascii_case("Axis MUTUAL fund", "Mutual Fund"),
+            Some("Axis ")
+        );
+        assert_eq!(strip_suffix_ignore_ascii_case("Fund", "Mutual Fund"), None);
+        assert_eq!(
+            strip_suffix_ignore_ascii_case("Sundaram Fund", "Mutual Fund"),
+            None
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Order of records by scheme code within their sections, see
+//! [`NavRecordIterator::assert_sorted_within_sections`](struct.NavRecordIterator.html#method.assert_sorted_within_sections)
+
+#[cfg(any(test, feature = "chrono"))]
+use crate::NavRecord;
+use crate::SchemeCode;
+
+/// Code of the last record of the current section, kept by the feed state while checked
+#[derive(Debug, Default)]
+pub(crate) struct CodeOrder {
+    checked: bool,
+    last: Option<SchemeCode>,
+}
+
+impl CodeOrder {
+    /// Checks records are sorted from now on, if `checked`
+    pub(crate) fn check(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+
+    /// Starts a section, whose first record follows none
+    pub(crate) fn section(&mut self) {
+        self.last = None;
+    }
+
+    /// Meets a record of `code`, returning the code it follows if lower, when checked
+    pub(crate) fn follows(&mut self, code: SchemeCode) -> Option<SchemeCode> {
+        if !self.checked {
+            return None;
+        }
+        self.last.replace(code).filter(|&last| code < last)
+    }
+}
+
+/// Whether `a` and `b` are of the same section of a feed
+#[cfg(any(test, feature = "chrono"))]
+fn same_section(a: &NavRecord, b: &NavRecord) -> bool {
+    a.amc == b.amc && a.category == b.category && a.scheme == b.scheme && a.maturity == b.maturity
+}
+
+/// Whether consecutive `records` of a section are sorted by code, equal codes allowed
+#[cfg(any(test, feature = "chrono"))]
+pub(crate) fn is_sorted_within_sections(records: &[NavRecord]) -> bool {
+    records
+        .windows(2)
+        .all(|pair| !same_section(&pair[0], &pair[1]) || pair[0].code <= pair[1].code)
+}
+
+/// Sorts the records of each run of a section by code, keeping the runs in place
+#[cfg(any(test, feature = "chrono"))]
+pub(crate) fn sort_within_sections(records: &mut [NavRecord]) {
+    let mut start = 0;
+    while start < records.len() {
+        let end = start
+            + records[start..]
+                .iter()
+                .take_while(|record| same_section(&records[start], record))
+                .count();
+        records[start..end].sort_by_key(|record| record.code);
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(amc: &str, code: u32) -> NavRecord {
+        NavRecord {
+            amc: amc.to_string(),
+            code: SchemeCode::from(code),
+            ..NavRecord::fixture()
+        }
+    }
+
+    #[test]
+    fn sections_sort_apart() {
+        let mut records = vec![
+            record("Axis Mutual Fund", 120466),
+            record("Axis Mutual Fund", 112277),
+            record("HDFC Mutual Fund", 100001),
+            record("HDFC Mutual Fund", 100001),
+            record("Axis Mutual Fund", 100002),
+        ];
+        assert!(!is_sorted_within_sections(&records));
+        sort_within_sections(&mut records);
+        assert!(is_sorted_within_sections(&records));
+        let codes: Vec<u32> = records.iter().map(|record| record.code.get()).collect();
+        assert_eq!(codes, [112277, 120466, 100001, 100001, 100002]);
+    }
+
+    #[test]
+    fn unchecked_order_follows_nothing() {
+        let mut order = CodeOrder::default();
+        assert_eq!(order.follows(SchemeCode::from(2)), None);
+        assert_eq!(order.follows(SchemeCode::from(1)), None);
+        order.check(true);
+        order.section();
+        assert_eq!(order.follows(SchemeCode::from(2)), None);
+        assert_eq!(order.follows(SchemeCode::from(2)), None);
+        assert_eq!(
+            order.follows(SchemeCode::from(1)),
+            Some(SchemeCode::from(2))
+        );
+        assert_eq!(order.follows(SchemeCode::from(3)), None);
+        order.section();
+        assert_eq!(order.follows(SchemeCode::from(1)), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Parallel parsing of a feed held in memory
+
+use crate::{Error, FeedState, NavRecord, NormalizeOptions, SectionContext, Step};
+use rayon::prelude::*;
+
+/// Records and errors of a feed, see [`parse_parallel`](fn.parse_parallel.html)
+#[derive(Debug, Default)]
+pub struct ParsedFeed {
+    /// Records in feed order
+    pub records: Vec<NavRecord>,
+    /// Errors with their 1-based line numbers, in feed order
+    pub errors: Vec<(usize, Error)>,
+}
+
+/// Parses `body` on all cores of the [rayon](https://crates.io/crates/rayon) thread pool
+///
+/// A quick sequential pass tracks the section and column headers, then record lines are
+/// parsed in parallel, each with the section and layout it is in. Records and errors are the
+/// same, and in the same order, as with [`nav_from_str`](fn.nav_from_str.html), whose
+/// [`line`](struct.NavRecordIterator.html#method.line) gives the error line numbers.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let body = std::fs::read_to_string("fixtures/NAVOpen.txt")?;
+/// let feed = amfi::parse_parallel(&body);
+/// assert_eq!(feed.records.len(), 30);
+/// assert!(feed.errors.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_parallel(body: &str) -> ParsedFeed {
+    let mut state = FeedState::default();
+    let mut sections: Vec<SectionContext> = Vec::new();
+    let mut lines = Vec::new();
+    let mut invalid = Vec::new();
+    let mut bailout = None;
+    for line in body.split_inclusive('\n') {
+        match state.advance(line) {
+            Step::Record => {
+                if sections.last() != Some(state.section()) {
+                    sections.push(state.section().clone());
+                }
+                lines.push((state.line(), sections.len() - 1, state.layout(), line));
+            }
+            Step::Skip | Step::Header | Step::Blank | Step::Amc | Step::Scheme => {}
+            Step::Invalid(error) => invalid.push((state.line(), error)),
+            Step::Bailout(error) => {
+                bailout = Some((state.line(), error));
+                break;
+            }
+        }
+    }
+
+    let normalize = NormalizeOptions::default();
+    let results: Vec<_> = lines
+        .par_iter()
+        .map(|&(number, section, layout, line)| {
+            let record =
+                sections[section].parse_record(line, Some(number), layout, false, &normalize);
+            (number, record)
+        })
+        .collect();
+    let mut feed = ParsedFeed::default();
+    for (number, result) in results {
+        match result {
+            Ok(record) => feed.records.push(record),
+            Err(error) => feed.errors.push((number, error)),
+        }
+    }
+    if !invalid.is_empty() {
+        feed.errors.extend(invalid);
+        feed.errors.sort_by_key(|&(number, _)| number);
+    }
+    feed.errors.extend(bailout);
+    feed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_text;
+
+    fn sequential(body: &str) -> ParsedFeed {
+        let mut iter = crate::nav_from_str(body);
+        let mut feed = ParsedFeed::default();
+        while let Some(result) = iter.next() {
+            match result {
+                Ok(record) => feed.records.push(record),
+                Err(error) => feed.errors.push((iter.line(), error)),
+            }
+        }
+        feed
+    }
+
+    fn assert_same(body: &str) {
+        let parallel = parse_parallel(body);
+        let sequential = sequential(body);
+        assert_eq!(parallel.records, sequential.records);
+        let errors = |feed: &ParsedFeed| -> Vec<(usize, String)> {
+            feed.errors
+                .iter()
+                .map(|(line, error)| (*line, error.to_string()))
+                .collect()
+        };
+        assert_eq!(errors(&parallel), errors(&sequential));
+    }
+
+    #[test]
+    fn fixture_matches_iterator() {
+        let body = fixture_text("NAVOpen.txt");
+        assert_eq!(parse_parallel(&body).records.len(), 30);
+        assert_same(&body);
+        assert_same(&body.repeat(50));
+    }
+
+    #[test]
+    fn errors_and_bailout_match_iterator() {
+        let body = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n\
+                    Open Ended Schemes(Equity Scheme\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024";
+        let feed = parse_parallel(body);
+        assert_eq!(feed.records.len(), 1);
+        assert_eq!(
+            feed.errors
+                .iter()
+                .map(|(line, _)| *line)
+                .collect::<Vec<_>>(),
+            [4, 5]
+        );
+        assert_same(body);
+        assert_same("");
+    }
+
+    #[test]
+    fn concatenated_layouts_match_iterator() {
+        let legacy = fixture_text("NAVLegacy.txt");
+        let modern = fixture_text("NAVOpen.txt");
+        let body = format!("{}{}{}", legacy, modern, legacy);
+        let feed = parse_parallel(&body);
+        assert!(feed.errors.is_empty());
+        assert!(feed
+            .records
+            .iter()
+            .any(|record| record.sale_price.is_some()));
+        assert!(feed
+            .records
+            .iter()
+            .any(|record| record.sale_price.is_none()));
+        assert_same(&body);
+        assert_same(&format!("{}{}", modern, legacy));
+    }
+}
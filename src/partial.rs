@@ -0,0 +1,297 @@
+//! Record lines parsed apart from their section, the low-level parsing API
+//!
+//! [`parse_record_line`](fn.parse_record_line.html) reads the fields a record line holds
+//! itself; [`PartialRecord::into_record`](struct.PartialRecord.html#method.into_record)
+//! adds those its section headers give. [`NavRecordIterator`](struct.NavRecordIterator.html)
+//! parses record lines in the same two steps, so wrappers keeping their own track of
+//! sections, e.g. in streaming frameworks, get the records it yields.
+
+use crate::normalize::NormalizeOptions;
+use crate::record_ref::{NavRecordRef, RawRef};
+use crate::{
+    parse_record, FofKind, FundMaturity, FundPlan, Layout, NavDate, NavRecord, PlanSource, RawIsin,
+    Result, SchemeCode,
+};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+/// Fields of a record line, without the AMC, category, scheme and maturity of its section
+///
+/// See [`parse_record_line`](fn.parse_record_line.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialRecord {
+    /// Scheme Code
+    pub code: SchemeCode,
+    /// ISIN Growth/Divdend Payout as published, unvalidated
+    pub isin: Option<RawIsin>,
+    /// ISIN Divdend Reinvestment as published, unvalidated
+    pub isin_dr: Option<RawIsin>,
+    /// Scheme Name
+    pub name: String,
+    /// Plan (Regular/Direct)
+    pub plan: FundPlan,
+    /// How `plan` was decided
+    pub plan_source: PlanSource,
+    /// Option (Growth/Monthly Dividend Payout etc.), not told by record lines
+    pub option: Option<String>,
+    /// Net Asset Value (NAV)
+    pub nav: f64,
+    /// NAV Date
+    pub date: NavDate,
+    /// Repurchase Price, only in the legacy and history layouts and often blank there
+    pub repurchase_price: Option<f64>,
+    /// Sale Price, only in the legacy and history layouts and often blank there
+    pub sale_price: Option<f64>,
+}
+
+/// Context of a record line given by the preceding section headers, see
+/// [`PartialRecord::into_record`](struct.PartialRecord.html#method.into_record)
+///
+/// Empty by default, as for record lines before the first header of a feed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SectionContext {
+    /// AMC, as its header normalized
+    pub amc: String,
+    /// Category of the scheme header
+    pub category: String,
+    /// Scheme group of the scheme header, `None` for headers naming a category only
+    pub scheme: Option<String>,
+    /// Maturity of the scheme header, `None` if it says neither
+    pub maturity: Option<FundMaturity>,
+    /// Kind of Fund of Funds given by the category header, see
+    /// [`FofKind::of_category`](enum.FofKind.html#method.of_category)
+    pub fof: Option<FofKind>,
+}
+
+/// Parses a record `line` of `layout` apart from its section
+///
+/// This is the stable low-level API of the parser: the line is cleaned up and parsed as
+/// [`NavRecordIterator`](struct.NavRecordIterator.html) does with default options, which
+/// yields the same record once given the section with
+/// [`PartialRecord::into_record`](struct.PartialRecord.html#method.into_record). Errors are
+/// those of the iterator, without line number.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::{FundMaturity, FundPlan, Layout, SectionContext};
+///
+/// let line = "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.4300;21-Jun-2024";
+/// let partial = amfi::parse_record_line(line, Layout::Modern)?;
+/// assert_eq!(partial.plan, FundPlan::Direct);
+/// let record = partial.into_record(&SectionContext {
+///     amc: "Axis Mutual Fund".to_string(),
+///     category: "Large Cap Fund".to_string(),
+///     scheme: Some("Equity Scheme".to_string()),
+///     maturity: Some(FundMaturity::OpenEnded),
+///     fof: None,
+/// });
+/// assert_eq!(record.amc, "Axis Mutual Fund");
+/// assert_eq!(record.nav, 61.43);
+///
+/// assert!(amfi::parse_record_line("Axis Mutual Fund", Layout::Modern).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_record_line(line: &str, layout: Layout) -> Result<PartialRecord> {
+    let normalize = NormalizeOptions::default();
+    let stripped = normalize.record_line(line);
+    Ok(PartialRef::parse(&stripped, None, layout, &normalize)?.into_owned())
+}
+
+/// [`PartialRecord`] borrowing the texts of its line, which it also keeps as published
+///
+/// The fields of a record line are read here only: [`parse_record_line`] owns them,
+/// [`SectionContext::record_ref`] places them in their section.
+struct PartialRef<'a> {
+    code: SchemeCode,
+    isin: Option<&'a str>,
+    isin_dr: Option<&'a str>,
+    name: Cow<'a, str>,
+    plan: FundPlan,
+    plan_source: PlanSource,
+    nav: f64,
+    date: NavDate,
+    repurchase_price: Option<f64>,
+    sale_price: Option<f64>,
+    raw_name: &'a str,
+    raw_nav: &'a str,
+    raw_date: &'a str,
+}
+
+impl<'a> PartialRef<'a> {
+    /// Parses a record line of `layout`, `stripped` of its control characters, its `number`
+    /// in the feed if known, its name cleaned up by `normalize`
+    fn parse(
+        stripped: &'a str,
+        number: Option<usize>,
+        layout: Layout,
+        normalize: &NormalizeOptions,
+    ) -> Result<Self> {
+        let fields = parse_record(stripped, layout).map_err(|f| f.into_error(stripped, number))?;
+        let (plan, plan_source) = fields.plan();
+        Ok(PartialRef {
+            code: SchemeCode::from(fields.code),
+            isin: fields.isin,
+            isin_dr: fields.isin_dr,
+            name: normalize.name(fields.raw_name),
+            plan,
+            plan_source,
+            nav: fields.nav,
+            date: fields.date,
+            repurchase_price: fields.repurchase_price,
+            sale_price: fields.sale_price,
+            raw_name: fields.raw_name,
+            raw_nav: fields.raw_nav,
+            raw_date: fields.raw_date,
+        })
+    }
+
+    fn into_owned(self) -> PartialRecord {
+        PartialRecord {
+            code: self.code,
+            isin: self.isin.map(RawIsin::new),
+            isin_dr: self.isin_dr.map(RawIsin::new),
+            name: self.name.into_owned(),
+            plan: self.plan,
+            plan_source: self.plan_source,
+            option: None,
+            nav: self.nav,
+            date: self.date,
+            repurchase_price: self.repurchase_price,
+            sale_price: self.sale_price,
+        }
+    }
+}
+
+impl PartialRecord {
+    /// Record of this line in the section of `context`
+    ///
+    /// The kind of Fund of Funds is the section's, else guessed from the name.
+    pub fn into_record(self, context: &SectionContext) -> NavRecord {
+        NavRecord {
+            fof: context.fof_of(&self.name),
+            code: self.code,
+            isin: self.isin,
+            isin_dr: self.isin_dr,
+            name: self.name,
+            nav: self.nav,
+            date: self.date,
+            amc: context.amc.clone(),
+            category: context.category.clone(),
+            scheme: context.scheme.clone(),
+            maturity: context.maturity,
+            plan: self.plan,
+            plan_source: self.plan_source,
+            option: self.option,
+            repurchase_price: self.repurchase_price,
+            sale_price: self.sale_price,
+            raw: None,
+            source_line: None,
+            segment: None,
+            recovered: false,
+        }
+    }
+}
+
+impl SectionContext {
+    /// Kind of Fund of Funds of the scheme `name`d in this section
+    fn fof_of(&self, name: &str) -> Option<FofKind> {
+        self.fof.or_else(|| FofKind::from_name(name))
+    }
+
+    /// Parses a record `line` of this section in `layout`, keeping its text if `capture_raw`
+    /// and its `number` in the feed if known, its name cleaned up by `normalize`
+    pub(crate) fn parse_record(
+        &self,
+        line: &str,
+        number: Option<usize>,
+        layout: Layout,
+        capture_raw: bool,
+        normalize: &NormalizeOptions,
+    ) -> Result<NavRecord> {
+        let stripped = normalize.record_line(line);
+        self.record_ref(line, &stripped, number, layout, normalize)
+            .map(|record| record.into_record(capture_raw))
+    }
+
+    /// Parses a record `line` of this section, `stripped` of its control characters, into a
+    /// record borrowing its texts
+    ///
+    /// Fields are separated by `;` only, with optional spaces or tabs around it; names may
+    /// contain both, tabs becoming spaces. Other C0 control characters are dropped, by
+    /// default. The fields are those [`parse_record_line`] reads, their section that of
+    /// [`PartialRecord::into_record`].
+    ///
+    /// Builds the record directly rather than through `NavRecordBuilder`, whose `build`
+    /// clones every field.
+    pub(crate) fn record_ref<'a>(
+        &'a self,
+        line: &'a str,
+        stripped: &'a str,
+        number: Option<usize>,
+        layout: Layout,
+        normalize: &NormalizeOptions,
+    ) -> Result<NavRecordRef<'a>> {
+        let partial = PartialRef::parse(stripped, number, layout, normalize)?;
+        Ok(NavRecordRef {
+            code: partial.code,
+            isin: partial.isin,
+            isin_dr: partial.isin_dr,
+            fof: self.fof_of(&partial.name),
+            name: partial.name,
+            nav: partial.nav,
+            date: partial.date,
+            amc: &self.amc,
+            category: &self.category,
+            scheme: self.scheme.as_deref(),
+            maturity: self.maturity,
+            plan: partial.plan,
+            plan_source: partial.plan_source,
+            repurchase_price: partial.repurchase_price,
+            sale_price: partial.sale_price,
+            source_line: number.and_then(|number| u32::try_from(number).ok()),
+            segment: None,
+            recovered: false,
+            raw: RawRef {
+                line: line.trim_end_matches(&['\r', '\n'][..]),
+                name: partial.raw_name,
+                nav: partial.raw_nav,
+                date: partial.raw_date,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav_from_str;
+
+    #[test]
+    fn legacy_prices() {
+        let line = "100027;-;-;Grindlays Super Saver Income Fund-GSSIF-Half Yearly Dividend;10.7959;;10.7959;01-Apr-2008";
+        let partial = parse_record_line(line, Layout::Legacy).unwrap();
+        assert_eq!(partial.repurchase_price, None);
+        assert_eq!(partial.sale_price, Some(10.7959));
+        assert_eq!(partial.plan_source, PlanSource::Default);
+    }
+
+    #[test]
+    fn fof_of_the_section_or_name() {
+        let feed = "Open Ended Schemes(Other Scheme - FoF Overseas)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Global Innovation FoF - Direct Plan - Growth;13.2;21-Jun-2024\n";
+        let mut records = nav_from_str(feed);
+        let mut parsed = records.next().unwrap().unwrap();
+        let context = records.current_section().clone();
+        assert_eq!(context.fof, Some(FofKind::Overseas));
+
+        let line = feed.lines().last().unwrap();
+        let partial = parse_record_line(line, Layout::Modern).unwrap();
+        parsed.source_line = None;
+        assert_eq!(partial.clone().into_record(&context), parsed);
+        let record = partial.into_record(&SectionContext::default());
+        assert_eq!(record.fof, FofKind::from_name(&record.name));
+        assert_eq!(record.amc, "");
+    }
+}
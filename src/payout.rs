@@ -0,0 +1,243 @@
+//! Growth/IDCW option of a scheme, read from its name
+
+/// Payout option of a scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Payout {
+    /// Growth (cumulative) option
+    Growth,
+    /// Income Distribution cum Capital Withdrawal, formerly dividend
+    Idcw,
+}
+
+/// Tokens naming the growth option
+const GROWTH: &[&str] = &["growth", "cumulative"];
+/// Tokens naming the IDCW option, including its pre-2021 dividend names
+const IDCW: &[&str] = &[
+    "idcw",
+    "dividend",
+    "div",
+    "payout",
+    "reinvestment",
+    "reinvest",
+    "distribution",
+];
+/// Tokens naming a plan
+const PLAN: &[&str] = &["direct", "regular"];
+/// Tokens qualifying a plan or option, e.g. how often IDCW is paid
+const QUALIFIERS: &[&str] = &[
+    "plan",
+    "option",
+    "daily",
+    "weekly",
+    "fortnightly",
+    "monthly",
+    "quarterly",
+    "half",
+    "yearly",
+    "annual",
+];
+/// Tokens ending the fund name when a name has no ` - ` separated parts
+const NAME_END: &[&str] = &["fund", "scheme", "etf", "fof"];
+
+/// Option of a scheme from its `option` field, or else from the parts of its `name`
+///
+/// The fund name itself is ignored, so `Axis Growth Opportunities Fund - Direct Plan -
+/// IDCW` is IDCW. Names mixing both options, e.g. `Growth Plus Dividend`, have neither.
+pub(crate) fn payout(name: &str, option: Option<&str>) -> Option<Payout> {
+    let text = match option {
+        Some(option) => option,
+        None => option_part(name),
+    };
+    let (mut growth, mut idcw) = (false, false);
+    for token in tokens(text) {
+        growth |= GROWTH.iter().any(|word| token.eq_ignore_ascii_case(word));
+        idcw |= IDCW.iter().any(|word| token.eq_ignore_ascii_case(word));
+    }
+    match (growth, idcw) {
+        (true, false) => Some(Payout::Growth),
+        (false, true) => Some(Payout::Idcw),
+        _ => None,
+    }
+}
+
+/// Part of `name` after the fund name
+fn option_part(name: &str) -> &str {
+    if let Some(pos) = name.find(" - ") {
+        return &name[pos + 3..];
+    }
+    // `ICICI Prudential Bluechip Fund IDCW`: after the last word ending a fund name
+    let mut end = 0;
+    let mut start = None;
+    for (pos, ch) in name.char_indices().chain(Some((name.len(), ' '))) {
+        if ch.is_alphanumeric() {
+            start.get_or_insert(pos);
+        } else if let Some(start) = start.take() {
+            let token = &name[start..pos];
+            if NAME_END.iter().any(|word| token.eq_ignore_ascii_case(word)) {
+                end = pos;
+            }
+        }
+    }
+    &name[end..]
+}
+
+/// ` - ` separated parts of `name` after the fund name naming no plan or option
+///
+/// Whatever AMFI starts appending to names, as IDCW was in 2021, shows up here until taught
+/// to `payout` and the plan tokens.
+pub(crate) fn unknown_suffixes(name: &str) -> impl Iterator<Item = &str> {
+    let known = |token: &str| {
+        [GROWTH, IDCW, PLAN, QUALIFIERS]
+            .iter()
+            .any(|words| words.iter().any(|word| token.eq_ignore_ascii_case(word)))
+    };
+    dash_parts(name)
+        .skip(1)
+        .map(str::trim)
+        .filter(move |part| !part.is_empty() && !tokens(part).any(known))
+}
+
+/// Parts of `name` split at ` - `, as by `split(" - ")`
+///
+/// Counted for every record, so found by their `-` rather than by a substring search.
+fn dash_parts(name: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(name);
+    std::iter::from_fn(move || {
+        let text = rest?;
+        let bytes = text.as_bytes();
+        let mut from = 0;
+        while let Some(at) = text[from..].find('-').map(|at| from + at) {
+            if at > 0 && bytes[at - 1] == b' ' && bytes.get(at + 1) == Some(&b' ') {
+                rest = Some(&text[at + 2..]);
+                return Some(&text[..at - 1]);
+            }
+            from = at + 1;
+        }
+        rest = None;
+        Some(text)
+    })
+}
+
+/// Lowercased tokens of `name` but those naming a plan, e.g. `axis bluechip fund growth`, and
+/// the plan tokens, e.g. `direct`
+pub(crate) fn split_plan(name: &str) -> (String, String) {
+    let (mut rest, mut plan) = (Vec::new(), Vec::new());
+    for token in tokens(name).map(str::to_lowercase) {
+        if PLAN.contains(&token.as_str()) {
+            plan.push(token);
+        } else if token != "plan" {
+            rest.push(token);
+        }
+    }
+    (rest.join(" "), plan.join(" "))
+}
+
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unknown(name: &str) -> Vec<&str> {
+        unknown_suffixes(name).collect()
+    }
+
+    fn name(name: &str) -> Option<Payout> {
+        payout(name, None)
+    }
+
+    #[test]
+    fn fixture_names() {
+        use Payout::*;
+        assert_eq!(
+            name("Axis Bluechip Fund - Direct Plan - Growth"),
+            Some(Growth)
+        );
+        assert_eq!(name("Axis Bluechip Fund - Direct Plan - IDCW"), Some(Idcw));
+        assert_eq!(
+            name("HDFC Flexi Cap Fund - Growth Option - Direct Plan"),
+            Some(Growth)
+        );
+        assert_eq!(
+            name("HDFC Flexi Cap Fund - IDCW Option - Regular Plan"),
+            Some(Idcw)
+        );
+        assert_eq!(
+            name("Nippon India Overnight Fund - Direct Plan - Daily IDCW Option"),
+            Some(Idcw)
+        );
+        assert_eq!(name("ICICI Prudential Bluechip Fund - IDCW"), Some(Idcw));
+    }
+
+    #[test]
+    fn awkward_names() {
+        use Payout::*;
+        // Option words in the fund name itself
+        assert_eq!(
+            name("Axis Growth Opportunities Fund - Direct Plan - IDCW"),
+            Some(Idcw)
+        );
+        assert_eq!(name("Quant Dividend Yield Fund - Growth"), Some(Growth));
+        assert_eq!(name("Mirae Asset Growth Fund"), None);
+        // Both options in one name
+        assert_eq!(name("Kotak Equity Fund - Growth Plus Dividend"), None);
+        // Older spellings and no separators
+        assert_eq!(
+            name("Franklin India Bluechip Fund - Dividend Payout"),
+            Some(Idcw)
+        );
+        assert_eq!(name("UTI Liquid Fund - Cumulative Option"), Some(Growth));
+        assert_eq!(name("ICICI Prudential Bluechip Fund IDCW"), Some(Idcw));
+        assert_eq!(name("Nippon India ETF Nifty BeES"), None);
+        // Substrings do not count
+        assert_eq!(name("DSP Fund - Regular Plan - Growthx"), None);
+    }
+
+    #[test]
+    fn option_field_wins() {
+        let fund = "Axis Bluechip Fund - Direct Plan - Growth";
+        assert_eq!(payout(fund, Some("IDCW Payout")), Some(Payout::Idcw));
+        assert_eq!(payout(fund, Some("")), None);
+    }
+
+    #[test]
+    fn dash_parts_split_as_split() {
+        for name in [
+            "Axis Bluechip Fund - Direct Plan - Growth",
+            "Axis Bluechip Fund",
+            "Multi-Asset Fund -Growth - IDCW",
+            "a - - b -  - c - ",
+            " - ",
+            "",
+        ] {
+            assert_eq!(
+                dash_parts(name).collect::<Vec<_>>(),
+                name.split(" - ").collect::<Vec<_>>(),
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_suffixes_of_names() {
+        assert!(unknown("Axis Bluechip Fund - Direct Plan - Growth").is_empty());
+        assert!(
+            unknown("Nippon India Overnight Fund - Direct Plan - Daily IDCW Option").is_empty()
+        );
+        assert!(unknown("Axis Liquid Fund - Regular Plan - Monthly - IDCW").is_empty());
+        // No separated parts
+        assert!(unknown("ICICI Prudential Bluechip Fund IDCW").is_empty());
+        assert_eq!(
+            unknown("Axis Bluechip Fund - Direct Plan - Loyalty Units -  - Growth "),
+            ["Loyalty Units"]
+        );
+        assert_eq!(
+            unknown("Franklin India Debt Fund - Segregated Portfolio 1 - Bonus"),
+            ["Segregated Portfolio 1", "Bonus"]
+        );
+    }
+}
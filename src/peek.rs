@@ -0,0 +1,79 @@
+//! NAV date of a feed read off its first record
+
+use crate::{NavDate, NavRecordIterator, Result, Step};
+use std::io::Read;
+
+/// NAV date of the first record of a feed, `None` if it has no record with a date
+///
+/// Reads lines up to the first record line and parses only its date, the last column, so
+/// the rest of the feed is neither read nor parsed. Record lines whose date doesn't parse
+/// are passed over. Fails on I/O errors and, as
+/// [`NavRecordIterator`](struct.NavRecordIterator.html), on a first line not starting a feed
+/// or a malformed scheme header before the first record.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let file = std::fs::File::open("fixtures/NAVOpen.txt")?;
+/// assert_eq!(amfi::peek_feed_date(file)?, amfi::NavDate::from_ymd(2024, 6, 21));
+/// # Ok(())
+/// # }
+/// ```
+pub fn peek_feed_date<R: Read>(reader: R) -> Result<Option<NavDate>> {
+    first_date(NavRecordIterator::new(reader))
+}
+
+/// NAV date of the first record read by `records`
+pub(crate) fn first_date<R: Read>(mut records: NavRecordIterator<R>) -> Result<Option<NavDate>> {
+    records
+        .next_with(|state, line| match state.advance(line) {
+            // A record out of place still tells the date of the feed
+            Step::Record | Step::Skip | Step::Invalid(_) => record_date(line).map(Ok),
+            Step::Bailout(error) => Some(Err(error)),
+            Step::Header | Step::Blank | Step::Amc | Step::Scheme => None,
+        })
+        .transpose()
+}
+
+/// Date in the last column of record `line`
+fn record_date(line: &str) -> Option<NavDate> {
+    let (_, date) = line.trim_end().rsplit_once(';')?;
+    NavDate::parse_feed(date.trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_bytes;
+    use crate::Error;
+
+    #[test]
+    fn peeks_fixtures() {
+        let june_21 = NavDate::from_ymd(2024, 6, 21);
+        assert_eq!(
+            peek_feed_date(&fixture_bytes("NAVOpen.txt")[..]).unwrap(),
+            june_21
+        );
+        assert_eq!(
+            peek_feed_date(&fixture_bytes("NAVLegacy.txt")[..]).unwrap(),
+            june_21
+        );
+
+        // Stops at the first record, the garbage after it is never read
+        let mut feed = fixture_bytes("NAVOpen.txt");
+        feed.extend_from_slice(b"\n\xff\xfe not a feed");
+        assert_eq!(peek_feed_date(&feed[..]).unwrap(), june_21);
+    }
+
+    #[test]
+    fn peeks_without_records() {
+        let feed = "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;\
+                    Net Asset Value;Date\n\nOpen Ended Schemes(Equity Scheme - Large Cap Fund)\n\n\
+                    Axis Mutual Fund\n\n120465;INF846K01AB4;-;Axis Bluechip Fund;61.4300;N.A.\n";
+        assert_eq!(peek_feed_date(feed.as_bytes()).unwrap(), None);
+        assert_eq!(peek_feed_date(&b""[..]).unwrap(), None);
+        assert!(matches!(
+            peek_feed_date(&b"<html><body>Service Unavailable</body></html>"[..]),
+            Err(Error::UnexpectedContent { .. })
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+//! How the plan of a record is decided
+
+use crate::{FundPlan, SchemeCode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How the [`plan`](struct.NavRecord.html#structfield.plan) of a record was decided,
+/// `NameToken` by default as for most records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum PlanSource {
+    /// The name contains `Direct` or `Regular`
+    #[default]
+    NameToken,
+    /// The name contains neither and a [`PlanResolver`](trait.PlanResolver.html) knew the
+    /// scheme
+    Resolver,
+    /// The name contains neither and the plan defaulted to Regular, or Unspecified as set by
+    /// [`NavRecordIterator::missing_plan`](struct.NavRecordIterator.html#method.missing_plan)
+    Default,
+}
+
+impl PlanSource {
+    /// Source of `plan` for a record named `name` when nothing else is known, i.e. for records
+    /// built by hand or read from files that don't keep it
+    pub(crate) fn of_name(name: &str) -> PlanSource {
+        if name_plan(name).is_some() {
+            PlanSource::NameToken
+        } else {
+            PlanSource::Default
+        }
+    }
+}
+
+/// Plan named by `name`, `Direct` winning over `Regular`
+pub(crate) fn name_plan(name: &str) -> Option<FundPlan> {
+    // Read for every record, so in one pass over the name
+    let bytes = name.as_bytes();
+    let at = |at: usize, word: &str| {
+        bytes
+            .get(at..at + word.len())
+            .is_some_and(|part| part.eq_ignore_ascii_case(word.as_bytes()))
+    };
+    let mut plan = None;
+    for (pos, byte) in bytes.iter().enumerate() {
+        match byte.to_ascii_lowercase() {
+            b'd' if at(pos, "DIRECT") => return Some(FundPlan::Direct),
+            b'r' if plan.is_none() && at(pos, "REGULAR") => plan = Some(FundPlan::Regular),
+            _ => {}
+        }
+    }
+    plan
+}
+
+/// Plan of schemes whose name names none, consulted by
+/// [`NavRecordIterator::plan_resolver`](struct.NavRecordIterator.html#method.plan_resolver)
+/// before defaulting to Regular
+pub trait PlanResolver: Send + Sync {
+    /// Plan of the scheme with `code` and ISIN `isin` (growth or payout, else reinvestment),
+    /// `None` if unknown
+    fn resolve(&self, isin: Option<&str>, code: SchemeCode) -> Option<FundPlan>;
+}
+
+impl fmt::Debug for dyn PlanResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PlanResolver")
+    }
+}
+
+/// [`PlanResolver`](trait.PlanResolver.html) looking plans up by ISIN, then by scheme code
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use std::sync::Arc;
+/// use amfi::{FundPlan, PlanMap, PlanSource};
+///
+/// let mut plans = PlanMap::new();
+/// plans.insert_isin("INF846K01AB4", FundPlan::Direct);
+/// let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+///             Axis Mutual Fund\n\
+///             120465;INF846K01AB4;-;Axis Bluechip Fund - Growth;61.43;21-Jun-2024\n";
+/// let record = amfi::nav_from_str(feed)
+///     .plan_resolver(Arc::new(plans))
+///     .next()
+///     .unwrap()?;
+/// assert_eq!(record.plan, FundPlan::Direct);
+/// assert_eq!(record.plan_source, PlanSource::Resolver);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PlanMap {
+    isins: HashMap<String, FundPlan>,
+    codes: HashMap<SchemeCode, FundPlan>,
+}
+
+impl PlanMap {
+    /// Empty map
+    pub fn new() -> Self {
+        PlanMap::default()
+    }
+
+    /// Sets the plan of schemes with ISIN `isin`, compared ignoring case and surrounding
+    /// whitespace
+    pub fn insert_isin(&mut self, isin: &str, plan: FundPlan) -> Option<FundPlan> {
+        self.isins.insert(isin.trim().to_ascii_uppercase(), plan)
+    }
+
+    /// Sets the plan of the scheme with `code`
+    pub fn insert_code<C: Into<SchemeCode>>(
+        &mut self,
+        code: C,
+        plan: FundPlan,
+    ) -> Option<FundPlan> {
+        self.codes.insert(code.into(), plan)
+    }
+
+    /// Number of ISINs and codes
+    pub fn len(&self) -> usize {
+        self.isins.len() + self.codes.len()
+    }
+
+    /// Whether no ISIN or code is set
+    pub fn is_empty(&self) -> bool {
+        self.isins.is_empty() && self.codes.is_empty()
+    }
+}
+
+impl PlanResolver for PlanMap {
+    fn resolve(&self, isin: Option<&str>, code: SchemeCode) -> Option<FundPlan> {
+        isin.and_then(|isin| self.isins.get(&isin.trim().to_ascii_uppercase()))
+            .or_else(|| self.codes.get(&code))
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nav_from_str, MissingPlanPolicy, NavRecord, Result};
+    use std::sync::Arc;
+
+    const FEED: &str = "Open Ended Schemes(Debt Scheme - Liquid Fund)\n\
+                        HDFC Mutual Fund\n\
+                        119091;INF179KB1HK0;-;HDFC Liquid Fund - Direct Plan - Growth Option;4750.3291;21-Jun-2024\n\
+                        100868;INF179KB1HP9;-;HDFC Liquid Fund - Regular Plan - Growth;4717.9041;21-Jun-2024\n\
+                        119092;INF179KB1HL8;-;HDFC Liquid Fund - Growth Option;4750.3291;21-Jun-2024\n\
+                        119093;-;INF179KB1HM6;HDFC Liquid Fund - IDCW Daily;1020.6100;21-Jun-2024\n\
+                        119094;-;-;HDFC Liquid Fund - IDCW Weekly;1021.4200;21-Jun-2024\n";
+
+    fn plans(resolver: Option<PlanMap>) -> Vec<(FundPlan, PlanSource)> {
+        let mut records = nav_from_str(FEED);
+        if let Some(resolver) = resolver {
+            records = records.plan_resolver(Arc::new(resolver));
+        }
+        records
+            .collect::<Result<Vec<NavRecord>>>()
+            .unwrap()
+            .into_iter()
+            .map(|record| (record.plan, record.plan_source))
+            .collect()
+    }
+
+    #[test]
+    fn resolves_names_without_plan() {
+        use FundPlan::{Direct, Regular};
+        use PlanSource::{Default, NameToken, Resolver};
+
+        assert_eq!(
+            plans(None),
+            [
+                (Direct, NameToken),
+                (Regular, NameToken),
+                (Regular, Default),
+                (Regular, Default),
+                (Regular, Default),
+            ]
+        );
+
+        let mut map = PlanMap::new();
+        // Not consulted for names with a plan
+        map.insert_isin("INF179KB1HP9", Direct);
+        map.insert_isin(" inf179kb1hl8", Direct);
+        // By the reinvestment ISIN when there is no other
+        map.insert_isin("INF179KB1HM6", Regular);
+        assert_eq!(map.len(), 3);
+        assert_eq!(
+            plans(Some(map.clone())),
+            [
+                (Direct, NameToken),
+                (Regular, NameToken),
+                (Direct, Resolver),
+                (Regular, Resolver),
+                (Regular, Default),
+            ]
+        );
+
+        map.insert_code(119_094, Direct);
+        assert_eq!(plans(Some(map))[4], (Direct, Resolver));
+    }
+
+    #[test]
+    fn filters_see_resolved_plans() {
+        let mut map = PlanMap::new();
+        map.insert_code(119_092, FundPlan::Direct);
+        let codes: Vec<u32> = nav_from_str(FEED)
+            .plan_resolver(Arc::new(map))
+            .filter_plan(FundPlan::Direct)
+            .map(|record| record.unwrap().code.get())
+            .collect();
+        assert_eq!(codes, [119_091, 119_092]);
+    }
+
+    #[test]
+    fn missing_plans() {
+        use FundPlan::{Direct, Regular, Unspecified};
+
+        let mut map = PlanMap::new();
+        map.insert_code(119_092, Direct);
+        let records: Vec<NavRecord> = nav_from_str(FEED)
+            .plan_resolver(Arc::new(map))
+            .missing_plan(MissingPlanPolicy::Unspecified)
+            .collect::<Result<_>>()
+            .unwrap();
+        let plans: Vec<_> = records
+            .iter()
+            .map(|record| (record.plan, record.plan_source))
+            .collect();
+        assert_eq!(
+            plans,
+            [
+                (Direct, PlanSource::NameToken),
+                (Regular, PlanSource::NameToken),
+                (Direct, PlanSource::Resolver),
+                (Unspecified, PlanSource::Default),
+                (Unspecified, PlanSource::Default),
+            ]
+        );
+        assert!(!records[3].is_direct() && !records[3].is_regular());
+
+        let codes: Vec<u32> = nav_from_str(FEED)
+            .missing_plan(MissingPlanPolicy::Unspecified)
+            .filter_plan(Unspecified)
+            .map(|record| record.unwrap().code.get())
+            .collect();
+        assert_eq!(codes, [119_092, 119_093, 119_094]);
+        assert_eq!("Unspecified".parse::<FundPlan>(), Ok(Unspecified));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_unless_named() {
+        let mut map = PlanMap::new();
+        map.insert_code(119_092, FundPlan::Direct);
+        let records: Vec<NavRecord> = nav_from_str(FEED)
+            .plan_resolver(Arc::new(map))
+            .collect::<Result<_>>()
+            .unwrap();
+        let json = serde_json::to_value(&records).unwrap();
+        assert!(json[0].get("plan_source").is_none());
+        assert_eq!(json[2]["plan_source"], "resolver");
+        assert_eq!(json[3]["plan_source"], "default");
+        let back: Vec<NavRecord> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, records);
+
+        let records: Vec<NavRecord> = nav_from_str(FEED)
+            .missing_plan(MissingPlanPolicy::Unspecified)
+            .collect::<Result<_>>()
+            .unwrap();
+        let json = serde_json::to_value(&records).unwrap();
+        assert_eq!(json[4]["plan"], "unspecified");
+        let back: Vec<NavRecord> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, records);
+    }
+}
@@ -0,0 +1,185 @@
+//! Progress of a parse reported as the sections of each AMC end, see
+//! [`NavRecordIterator::on_amc_done`](struct.NavRecordIterator.html#method.on_amc_done)
+
+use crate::{NavRecord, NavRecordIterator, Result};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// AMC section whose records were all read, handed to the callback of
+/// [`NavRecordIterator::on_amc_done`](struct.NavRecordIterator.html#method.on_amc_done)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmcProgress {
+    /// AMC of the section, normalized as the records' AMC
+    pub amc: String,
+    /// Records yielded in the section
+    pub records: usize,
+    /// Records yielded since the start of the feed, the section's included
+    pub total_records: usize,
+    /// 1-based position of the AMC in order of first appearance, as listed by
+    /// [`amc_list`](fn.amc_list.html)
+    pub index: usize,
+    /// AMCs in the feed, if set with
+    /// [`AmcProgressRecords::amc_total`](struct.AmcProgressRecords.html#method.amc_total)
+    pub total: Option<usize>,
+}
+
+/// AMC sections ended so far, kept by the feed state while progress is reported
+#[derive(Debug, Default)]
+pub(crate) struct AmcTracker {
+    /// AMC of the current section and the records yielded before it started
+    current: Option<(String, usize)>,
+    /// Position of each AMC met, in order of first appearance
+    indexes: HashMap<String, usize>,
+    /// Sections ended but not yet reported
+    done: Vec<AmcProgress>,
+}
+
+impl AmcTracker {
+    /// Meets the header of `amc` with `records` yielded so far, ending the current section
+    /// unless it is of the same AMC
+    pub(crate) fn amc(&mut self, amc: &str, records: usize) {
+        if amc.is_empty() || matches!(&self.current, Some((current, _)) if current == amc) {
+            return;
+        }
+        self.end(records);
+        let next = self.indexes.len() + 1;
+        self.indexes.entry(amc.to_string()).or_insert(next);
+        self.current = Some((amc.to_string(), records));
+    }
+
+    /// Ends the current section, if any, with `records` yielded so far
+    pub(crate) fn end(&mut self, records: usize) {
+        if let Some((amc, start)) = self.current.take() {
+            self.done.push(AmcProgress {
+                index: self.indexes[&amc],
+                amc,
+                records: records - start,
+                total_records: records,
+                total: None,
+            });
+        }
+    }
+
+    /// Sections ended since the last call
+    pub(crate) fn take(&mut self) -> Vec<AmcProgress> {
+        std::mem::take(&mut self.done)
+    }
+}
+
+/// Records of a feed, calling back as the section of each AMC ends, see
+/// [`NavRecordIterator::on_amc_done`](struct.NavRecordIterator.html#method.on_amc_done)
+pub struct AmcProgressRecords<T: Read, F> {
+    records: NavRecordIterator<T>,
+    total: Option<usize>,
+    f: F,
+}
+
+impl<T: Read, F: FnMut(&AmcProgress)> AmcProgressRecords<T, F> {
+    pub(crate) fn new(mut records: NavRecordIterator<T>, f: F) -> Self {
+        records.state.track_amcs();
+        AmcProgressRecords {
+            records,
+            total: None,
+            f,
+        }
+    }
+
+    /// Reports `total` AMCs in the feed, e.g. the length of a prior
+    /// [`amc_list`](fn.amc_list.html) of it
+    pub fn amc_total(mut self, total: usize) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// The iterator parsing the feed, e.g. for its [`stats`](struct.NavRecordIterator.html#method.stats)
+    pub fn get_ref(&self) -> &NavRecordIterator<T> {
+        &self.records
+    }
+
+    /// The iterator parsing the feed, no longer reporting progress
+    pub fn into_inner(self) -> NavRecordIterator<T> {
+        self.records
+    }
+}
+
+impl<T: Read, F: FnMut(&AmcProgress)> Iterator for AmcProgressRecords<T, F> {
+    type Item = Result<NavRecord>;
+
+    fn next(&mut self) -> Option<Result<NavRecord>> {
+        let item = self.records.next();
+        for mut done in self.records.state.amcs_done() {
+            done.total = self.total;
+            (self.f)(&done);
+        }
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::fixture_path;
+    use crate::{amc_list, nav_from_file, nav_from_str, AmcProgress, Result};
+    use std::fs::File;
+
+    #[test]
+    fn reports_each_amc_once() {
+        let total = amc_list(File::open(fixture_path("NAVOpen.txt")).unwrap())
+            .unwrap()
+            .len();
+        let mut done = Vec::new();
+        let records: Vec<_> = nav_from_file(fixture_path("NAVOpen.txt"))
+            .unwrap()
+            .on_amc_done(|progress| done.push(progress.clone()))
+            .amc_total(total)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(!done.is_empty());
+        let last = done.last().unwrap();
+        assert_eq!(last.total_records, records.len());
+        assert_eq!(
+            done.iter().map(|progress| progress.records).sum::<usize>(),
+            records.len()
+        );
+        assert!(done.iter().all(|progress| progress.total == Some(total)));
+        assert_eq!(
+            done.iter().map(|progress| progress.index).max(),
+            Some(total)
+        );
+        for progress in &done {
+            let section = records
+                .iter()
+                .filter(|record| record.amc == progress.amc)
+                .count();
+            assert!(progress.records <= section);
+        }
+    }
+
+    #[test]
+    fn repeated_headers_continue_sections() {
+        let feed = "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;\
+                    Net Asset Value;Date\n\nOpen Ended Schemes(Equity Scheme - Large Cap Fund)\n\nAxis Mutual Fund\n\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    Axis Mutual Fund\n\
+                    120466;INF846K01AC2;-;Axis Bluechip Fund - Direct Plan - IDCW;18.21;21-Jun-2024\n\
+                    HDFC Mutual Fund\n\
+                    118955;INF179K01AJ3;-;HDFC Flexi Cap Fund - Direct Plan - Growth;1890.12;21-Jun-2024\n";
+        // A second feed starting with the AMC the first ended with
+        let concatenated = format!("{}{}", feed, feed.replace("Axis", "HDFC"));
+        let mut done: Vec<AmcProgress> = Vec::new();
+        let count = nav_from_str(&concatenated)
+            .on_amc_done(|progress| done.push(progress.clone()))
+            .filter(|record| record.is_ok())
+            .count();
+        assert_eq!(count, 6);
+        let reported: Vec<_> = done
+            .iter()
+            .map(|progress| (progress.amc.as_str(), progress.records, progress.index))
+            .collect();
+        assert_eq!(
+            reported,
+            [("Axis Mutual Fund", 2, 1), ("HDFC Mutual Fund", 4, 2)]
+        );
+        assert_eq!(done[1].total_records, 6);
+        assert_eq!(done[1].total, None);
+    }
+}
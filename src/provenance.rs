@@ -0,0 +1,212 @@
+//! Where records merged from several feeds came from
+//!
+//! Records keep no trace of their feed, so APIs reading several sources pair each with the
+//! [`SourceId`](struct.SourceId.html) of its source, interned in a
+//! [`SourceRegistry`](struct.SourceRegistry.html) mapping ids to URLs and paths.
+
+use crate::NavRecord;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Id of a source in its [`SourceRegistry`](struct.SourceRegistry.html), in order of
+/// registration from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SourceId(u32);
+
+impl SourceId {
+    /// Position of the source in its registry
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Feed records were read from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum RecordSource {
+    /// Downloaded from a URL
+    Url(String),
+    /// Read from a local file
+    File(PathBuf),
+}
+
+impl fmt::Display for RecordSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordSource::Url(url) => f.write_str(url),
+            RecordSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Sources of records, each registered once under its [`SourceId`](struct.SourceId.html)
+///
+/// ```rust
+/// use amfi::{RecordSource, SourceRegistry};
+///
+/// let mut registry = SourceRegistry::new();
+/// let open = registry.register(RecordSource::Url("https://a/NAVOpen.txt".to_string()));
+/// let close = registry.register(RecordSource::Url("https://a/NAVClose.txt".to_string()));
+/// assert_eq!(registry.register(RecordSource::Url("https://a/NAVOpen.txt".to_string())), open);
+/// assert_eq!(registry.get(close).unwrap().to_string(), "https://a/NAVClose.txt");
+/// assert_eq!(registry.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SourceRegistry {
+    sources: Vec<RecordSource>,
+}
+
+impl SourceRegistry {
+    /// Registry without sources
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Id of `source`, registered unless it was already
+    pub fn register(&mut self, source: RecordSource) -> SourceId {
+        match self.sources.iter().position(|known| *known == source) {
+            Some(index) => SourceId(index as u32),
+            None => {
+                self.sources.push(source);
+                SourceId(self.sources.len() as u32 - 1)
+            }
+        }
+    }
+
+    /// Id of the file at `path`, registered unless it was already
+    pub(crate) fn register_file(&mut self, path: &Path) -> SourceId {
+        match self
+            .sources
+            .iter()
+            .position(|known| matches!(known, RecordSource::File(known) if known.as_path() == path))
+        {
+            Some(index) => SourceId(index as u32),
+            None => self.register(RecordSource::File(path.to_path_buf())),
+        }
+    }
+
+    /// Source registered as `id`, `None` if of another registry
+    pub fn get(&self, id: SourceId) -> Option<&RecordSource> {
+        self.sources.get(id.index())
+    }
+
+    /// Sources with their ids, in order of registration
+    pub fn iter(&self) -> impl Iterator<Item = (SourceId, &RecordSource)> {
+        self.sources
+            .iter()
+            .enumerate()
+            .map(|(index, source)| (SourceId(index as u32), source))
+    }
+
+    /// Number of sources
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Whether no source is registered
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
+/// Record, or other value, with the id of the source it was read from
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Sourced<T> {
+    /// Source in the registry of the API that read it
+    pub source_id: SourceId,
+    /// Value read
+    pub record: T,
+}
+
+/// Records of the same scheme and date from two sources, whose NAVs differ; the first was
+/// kept and the other dropped
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SourceConflict {
+    /// Record kept, of the source listed first
+    pub kept: Sourced<NavRecord>,
+    /// Record dropped
+    pub dropped: Sourced<NavRecord>,
+}
+
+#[cfg(any(test, all(feature = "async", feature = "chrono")))]
+impl SourceConflict {
+    /// Conflict of `dropped` with `kept`, if their NAVs differ
+    pub(crate) fn of(kept: &Sourced<NavRecord>, dropped: &Sourced<NavRecord>) -> Option<Self> {
+        let (a, b) = (kept.record.nav, dropped.record.nav);
+        if a == b || (a.is_nan() && b.is_nan()) {
+            None
+        } else {
+            Some(SourceConflict {
+                kept: kept.clone(),
+                dropped: dropped.clone(),
+            })
+        }
+    }
+}
+
+/// Sources of the records of a snapshot merged from several, see
+/// [`NavSnapshot::provenance`](struct.NavSnapshot.html#structfield.provenance)
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Provenance {
+    /// Sources read
+    pub registry: SourceRegistry,
+    /// Source of each record of the snapshot, in the order of the records
+    pub sources: Vec<SourceId>,
+    /// Records dropped as duplicates that disagreed with those kept
+    pub conflicts: Vec<SourceConflict>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_interned() {
+        let mut registry = SourceRegistry::new();
+        let url = registry.register(RecordSource::Url("https://a/NAVOpen.txt".to_string()));
+        let file = registry.register_file(Path::new("fixtures/NAVOpen.txt"));
+        assert_eq!(
+            registry.register(RecordSource::File(PathBuf::from("fixtures/NAVOpen.txt"))),
+            file
+        );
+        assert_eq!((url.index(), file.index()), (0, 1));
+        let listed: Vec<_> = registry.iter().map(|(id, _)| id).collect();
+        assert_eq!(listed, [url, file]);
+        assert_eq!(SourceRegistry::new().get(url), None);
+    }
+
+    #[test]
+    fn conflicts_are_of_navs() {
+        let sourced = |id, nav| Sourced {
+            source_id: SourceId(id),
+            record: NavRecord {
+                nav,
+                ..NavRecord::fixture()
+            },
+        };
+        assert_eq!(SourceConflict::of(&sourced(0, 1.0), &sourced(1, 1.0)), None);
+        assert_eq!(
+            SourceConflict::of(&sourced(0, f64::NAN), &sourced(1, f64::NAN)),
+            None
+        );
+        let conflict = SourceConflict::of(&sourced(0, 1.0), &sourced(1, 2.0)).unwrap();
+        assert_eq!(conflict.dropped.source_id, SourceId(1));
+    }
+}
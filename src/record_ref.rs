@@ -0,0 +1,272 @@
+//! Records borrowing their texts from the feed line and its section
+
+use crate::{
+    FofKind, FundMaturity, FundPlan, NavDate, NavRecord, NavRecordIterator, PlanSource, RawFields,
+    RawIsin, Result, SchemeCode, VehicleKind,
+};
+use std::borrow::Cow;
+use std::io::Read;
+
+/// [`NavRecord`](struct.NavRecord.html) borrowing its texts, see
+/// [`for_each_record`](fn.for_each_record.html)
+///
+/// Texts borrow from the line being parsed and its section headers, so a record lives only
+/// as long as the callback it is handed to; [`to_owned`](#method.to_owned) keeps it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavRecordRef<'a> {
+    /// Scheme Code
+    pub code: SchemeCode,
+    /// ISIN Growth/Divdend Payout as published, unvalidated
+    pub isin: Option<&'a str>,
+    /// ISIN Divdend Reinvestment as published, unvalidated
+    pub isin_dr: Option<&'a str>,
+    /// Scheme Name, borrowed unless changed by
+    /// [`normalize`](struct.NavRecordIterator.html#method.normalize), e.g. for its tabs
+    pub name: Cow<'a, str>,
+    /// Net Asset Value (NAV)
+    pub nav: f64,
+    /// NAV Date
+    pub date: NavDate,
+    /// Asset Management Company (AMC)
+    pub amc: &'a str,
+    /// Category
+    pub category: &'a str,
+    /// Scheme
+    pub scheme: Option<&'a str>,
+    /// Fund Maturity (Open/Close Ended)
+    pub maturity: Option<FundMaturity>,
+    /// Plan (Regular/Direct)
+    pub plan: FundPlan,
+    /// How `plan` was decided
+    pub plan_source: PlanSource,
+    /// Kind of Fund of Funds, `None` for other schemes
+    pub fof: Option<FofKind>,
+    /// Repurchase Price, only in the legacy and history layouts and often blank there
+    pub repurchase_price: Option<f64>,
+    /// Sale Price, only in the legacy and history layouts and often blank there
+    pub sale_price: Option<f64>,
+    /// 1-based number of the feed line the record was read from
+    pub source_line: Option<u32>,
+    /// 1-based number of the feed read in a concatenation of feeds, only with
+    /// [`stamp_segments`](struct.NavRecordIterator.html#method.stamp_segments)
+    pub segment: Option<u32>,
+    /// Whether the record was read from a line with shifted columns, see
+    /// [`RecoveryPolicy::Lenient`](enum.RecoveryPolicy.html#variant.Lenient)
+    pub recovered: bool,
+    pub(crate) raw: RawRef<'a>,
+}
+
+/// Texts of a record as published, kept by records with
+/// [`capture_raw`](struct.NavRecordIterator.html#method.capture_raw)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RawRef<'a> {
+    pub(crate) line: &'a str,
+    pub(crate) name: &'a str,
+    pub(crate) nav: &'a str,
+    pub(crate) date: &'a str,
+}
+
+impl NavRecordRef<'_> {
+    /// Owned record, as yielded by [`NavRecordIterator`](struct.NavRecordIterator.html) but
+    /// for `raw`
+    pub fn to_owned(&self) -> NavRecord {
+        self.clone().into_record(false)
+    }
+
+    /// Owned record, with its text as published if `capture_raw`
+    pub(crate) fn into_record(self, capture_raw: bool) -> NavRecord {
+        NavRecord {
+            code: self.code,
+            isin: self.isin.map(RawIsin::new),
+            isin_dr: self.isin_dr.map(RawIsin::new),
+            name: self.name.into_owned(),
+            nav: self.nav,
+            date: self.date,
+            amc: self.amc.to_string(),
+            category: self.category.to_string(),
+            scheme: self.scheme.map(str::to_string),
+            maturity: self.maturity,
+            plan: self.plan,
+            plan_source: self.plan_source,
+            option: None,
+            fof: self.fof,
+            repurchase_price: self.repurchase_price,
+            sale_price: self.sale_price,
+            raw: if capture_raw {
+                Some(RawFields {
+                    line: self.raw.line.to_string(),
+                    name: self.raw.name.to_string(),
+                    nav: self.raw.nav.to_string(),
+                    date: self.raw.date.to_string(),
+                })
+            } else {
+                None
+            },
+            source_line: self.source_line,
+            segment: self.segment,
+            recovered: self.recovered,
+        }
+    }
+
+    /// Whether this is a direct plan
+    pub fn is_direct(&self) -> bool {
+        self.plan == FundPlan::Direct
+    }
+
+    /// Whether this is a regular plan
+    pub fn is_regular(&self) -> bool {
+        self.plan == FundPlan::Regular
+    }
+
+    /// Whether the scheme is an active fund, index fund, ETF or FoF, see
+    /// [`NavRecord::vehicle`](struct.NavRecord.html#method.vehicle)
+    pub fn vehicle(&self) -> VehicleKind {
+        VehicleKind::of_record(self.scheme, self.category, self.fof.is_some(), &self.name)
+    }
+}
+
+/// Hands every record and error of a feed to `f`, without allocating texts per record
+///
+/// Lower-level than [`nav_from_reader`](fn.nav_from_reader.html), whose iterator is built on
+/// the same parser; [`NavRecordIterator::for_each_ref`](struct.NavRecordIterator.html#method.for_each_ref)
+/// does the same with its filters and options.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use std::collections::HashMap;
+///
+/// let mut direct = 0;
+/// let mut navs: HashMap<String, f64> = HashMap::new();
+/// let file = std::fs::File::open("fixtures/NAVOpen.txt")?;
+/// amfi::for_each_record(file, |record| {
+///     if let Ok(record) = record {
+///         direct += record.is_direct() as usize;
+///         if let Some(sum) = navs.get_mut(record.amc) {
+///             *sum += record.nav;
+///         } else {
+///             navs.insert(record.amc.to_string(), record.nav);
+///         }
+///     }
+/// });
+/// assert_eq!(direct, 23);
+/// # Ok(())
+/// # }
+/// ```
+pub fn for_each_record<R, F>(reader: R, f: F)
+where
+    R: Read,
+    F: FnMut(Result<NavRecordRef<'_>>),
+{
+    NavRecordIterator::new(reader).for_each_ref(f)
+}
+
+impl<T: Read> NavRecordIterator<T> {
+    /// Hands every remaining record and error to `f`, borrowing the texts of records rather
+    /// than yielding owned ones, see [`for_each_record`](fn.for_each_record.html)
+    ///
+    /// Records and errors are those the iterator would yield, without
+    /// [`map_records`](#method.map_records) applied.
+    pub fn for_each_ref<F>(mut self, mut f: F)
+    where
+        F: FnMut(Result<NavRecordRef<'_>>),
+    {
+        let span = self.span.clone();
+        let _entered = span.enter();
+        loop {
+            if let Some(error) = self.state.too_many_errors() {
+                f(Err(error));
+                continue;
+            }
+            match self.next_with(|state, line| state.parse_line_with(line, &mut f).map(Ok)) {
+                Some(Ok(())) => {}
+                Some(Err(error)) => f(Err(error)),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nav_from_reader, CaseFolding, NormalizeOptions, ZeroNavPolicy};
+
+    fn owned<T: Read>(records: NavRecordIterator<T>) -> Vec<Result<NavRecord>> {
+        let mut owned = Vec::new();
+        records.for_each_ref(|result| owned.push(result.map(|record| record.to_owned())));
+        owned
+    }
+
+    fn same(left: Vec<Result<NavRecord>>, right: Vec<Result<NavRecord>>) {
+        assert_eq!(left.len(), right.len());
+        for (left, right) in left.into_iter().zip(right) {
+            match (left, right) {
+                (Ok(left), Ok(right)) => assert_eq!(left, right),
+                (Err(left), Err(right)) => assert_eq!(left.to_string(), right.to_string()),
+                (left, right) => panic!("{:?} != {:?}", left, right),
+            }
+        }
+    }
+
+    fn configure<T: Read>(records: NavRecordIterator<T>) -> NavRecordIterator<T> {
+        records
+            .filter_plan(FundPlan::Direct)
+            .zero_nav_policy(ZeroNavPolicy::Skip)
+            .normalize(NormalizeOptions {
+                name_case: CaseFolding::Upper,
+                ..NormalizeOptions::default()
+            })
+            .max_errors(2)
+    }
+
+    #[test]
+    fn agrees_with_iterator() {
+        for fixture in &[
+            "NAVOpen.txt",
+            "NAVLegacy.txt",
+            "NAVHalfGarbage.txt",
+            "NAVMessy.txt",
+        ] {
+            let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+            let body = std::fs::read(&path).unwrap();
+            same(
+                owned(nav_from_reader(&body[..])),
+                nav_from_reader(&body[..]).collect(),
+            );
+
+            same(
+                owned(configure(nav_from_reader(&body[..]))),
+                configure(nav_from_reader(&body[..])).collect(),
+            );
+        }
+    }
+
+    #[test]
+    fn borrows_texts() {
+        let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;-;-;Axis\tBluechip Fund - Regular Plan - Growth;41.43;21-Jun-2024\n";
+        let mut records = Vec::new();
+        for_each_record(feed.as_bytes(), |record| {
+            let record = record.unwrap();
+            assert_eq!(record.amc, "Axis Mutual Fund");
+            assert_eq!(record.category, "Large Cap Fund");
+            assert_eq!(record.scheme, Some("Equity Scheme"));
+            assert_eq!(record.vehicle(), VehicleKind::ActiveFund);
+            records.push((
+                record.isin.map(str::to_string),
+                matches!(record.name, Cow::Borrowed(_)),
+                record.is_direct(),
+            ));
+        });
+        assert_eq!(
+            records,
+            [
+                (Some("INF846K01AB4".to_string()), true, true),
+                // Tabs in names become spaces
+                (None, false, false),
+            ]
+        );
+    }
+}
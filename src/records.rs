@@ -0,0 +1,237 @@
+//! Collected records with sorting helpers
+
+use crate::NavRecord;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+
+/// Records collected from a feed, in feed order until sorted
+///
+/// Dereferences to a slice of records. Sorts are stable and NAVs that are NaN sort after all
+/// others in either direction.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let mut records: amfi::NavRecords = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+///     .filter_map(Result::ok)
+///     .collect();
+/// let top = records.top_n_by_nav(3);
+/// assert!(top[0].nav >= top[1].nav && top[1].nav >= top[2].nav);
+/// records.sort_by_name();
+/// assert!(records[0].name <= records[1].name);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct NavRecords {
+    records: Vec<NavRecord>,
+}
+
+impl NavRecords {
+    /// No records
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Underlying records
+    pub fn into_vec(self) -> Vec<NavRecord> {
+        self.records
+    }
+
+    /// Sorts by scheme code
+    pub fn sort_by_code(&mut self) {
+        self.records.sort_by_key(|record| record.code);
+    }
+
+    /// Sorts by scheme name, byte-wise
+    pub fn sort_by_name(&mut self) {
+        self.records.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Sorts by NAV, smallest first, NaN last
+    pub fn sort_by_nav(&mut self) {
+        self.records.sort_by(|a, b| nav_ascending(a.nav, b.nav));
+    }
+
+    /// Sorts by NAV, largest first, NaN last
+    pub fn sort_by_nav_descending(&mut self) {
+        self.records.sort_by(|a, b| nav_descending(a.nav, b.nav));
+    }
+
+    /// Sorts by `key`, keeping the order of records with equal keys
+    pub fn sort_by_key<K: Ord, F: FnMut(&NavRecord) -> K>(&mut self, key: F) {
+        self.records.sort_by_key(key);
+    }
+
+    /// The `n` records with the largest NAV, largest first, in feed order on ties
+    ///
+    /// Selects them without sorting all records. NaN NAVs rank below all others.
+    pub fn top_n_by_nav(&self, n: usize) -> Vec<&NavRecord> {
+        let order = |a: &(usize, &NavRecord), b: &(usize, &NavRecord)| {
+            nav_descending(a.1.nav, b.1.nav).then(a.0.cmp(&b.0))
+        };
+        let mut top: Vec<(usize, &NavRecord)> = self.records.iter().enumerate().collect();
+        if n < top.len() {
+            top.select_nth_unstable_by(n, order);
+            top.truncate(n);
+        }
+        top.sort_unstable_by(order);
+        top.into_iter().map(|(_, record)| record).collect()
+    }
+}
+
+/// Orders NAVs ascending, NaN after all numbers
+fn nav_ascending(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (nan_a, nan_b) => nan_a.cmp(&nan_b),
+    }
+}
+
+/// Orders NAVs descending, NaN after all numbers
+fn nav_descending(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => nav_ascending(b, a),
+        (nan_a, nan_b) => nan_a.cmp(&nan_b),
+    }
+}
+
+impl Deref for NavRecords {
+    type Target = [NavRecord];
+
+    fn deref(&self) -> &Self::Target {
+        &self.records
+    }
+}
+
+impl DerefMut for NavRecords {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.records
+    }
+}
+
+impl From<Vec<NavRecord>> for NavRecords {
+    fn from(records: Vec<NavRecord>) -> Self {
+        NavRecords { records }
+    }
+}
+
+impl From<NavRecords> for Vec<NavRecord> {
+    fn from(records: NavRecords) -> Self {
+        records.records
+    }
+}
+
+impl FromIterator<NavRecord> for NavRecords {
+    fn from_iter<I: IntoIterator<Item = NavRecord>>(iter: I) -> Self {
+        NavRecords {
+            records: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<NavRecord> for NavRecords {
+    fn extend<I: IntoIterator<Item = NavRecord>>(&mut self, iter: I) {
+        self.records.extend(iter);
+    }
+}
+
+impl IntoIterator for NavRecords {
+    type Item = NavRecord;
+    type IntoIter = std::vec::IntoIter<NavRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a NavRecords {
+    type Item = &'a NavRecord;
+    type IntoIter = std::slice::Iter<'a, NavRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+
+    /// Fixture with NaN NAVs mixed in and a duplicated NAV
+    fn with_nans() -> NavRecords {
+        let mut records: NavRecords = fixture_records("NAVOpen.txt").into_iter().collect();
+        records[3].nav = f64::NAN;
+        records[17].nav = f64::NAN;
+        records[20].nav = records[5].nav;
+        records
+    }
+
+    fn navs(records: &[NavRecord]) -> Vec<f64> {
+        records.iter().map(|record| record.nav).collect()
+    }
+
+    #[test]
+    fn sorts() {
+        let mut records = with_nans();
+        records.sort_by_nav();
+        let sorted = navs(&records);
+        assert!(sorted[..28].windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(sorted[28].is_nan() && sorted[29].is_nan());
+
+        records.sort_by_nav_descending();
+        let sorted = navs(&records);
+        assert!(sorted[..28].windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(sorted[28].is_nan() && sorted[29].is_nan());
+
+        records.sort_by_code();
+        assert!(records.windows(2).all(|pair| pair[0].code < pair[1].code));
+        records.sort_by_name();
+        assert!(records.windows(2).all(|pair| pair[0].name <= pair[1].name));
+    }
+
+    #[test]
+    fn sorts_are_stable() {
+        let original = with_nans();
+        let mut records = original.clone();
+        records.sort_by_key(|record| record.amc.clone());
+        for pair in records.windows(2).filter(|pair| pair[0].amc == pair[1].amc) {
+            let position = |code| original.iter().position(|r| r.code == code);
+            assert!(position(pair[0].code) < position(pair[1].code));
+        }
+
+        // Equal NAVs keep feed order in both directions
+        let tied = original[5].code;
+        let duplicate = original[20].code;
+        for descending in &[false, true] {
+            let mut records = original.clone();
+            if *descending {
+                records.sort_by_nav_descending();
+            } else {
+                records.sort_by_nav();
+            }
+            let position = |code| records.iter().position(|r| r.code == code).unwrap();
+            assert_eq!(position(tied) + 1, position(duplicate));
+        }
+    }
+
+    #[test]
+    fn top_n_matches_full_sort() {
+        let records = with_nans();
+        let mut sorted = records.clone();
+        sorted.sort_by_nav_descending();
+        for n in 0..=32 {
+            let top: Vec<NavRecord> = records.top_n_by_nav(n).into_iter().cloned().collect();
+            assert_eq!(top.len(), n.min(30));
+            assert_eq!(
+                top.iter().map(|r| r.code).collect::<Vec<_>>(),
+                sorted.iter().take(n).map(|r| r.code).collect::<Vec<_>>()
+            );
+        }
+        assert!(NavRecords::new().top_n_by_nav(20).is_empty());
+    }
+}
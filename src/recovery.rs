@@ -0,0 +1,337 @@
+//! Recovery of record lines whose columns are shifted by a missing or extra separator
+
+use crate::{parse_record, trim_trailing_separators, Isin, Layout, NavDate, RecordFields};
+
+/// Handling of record lines failing to parse, see
+/// [`NavRecordIterator::recovery`](struct.NavRecordIterator.html#method.recovery)
+///
+/// The usual corruption is a missing column, mostly an absent ISIN without its `-`
+/// placeholder, shifting every column after it. Such lines fail to parse, or parse into a
+/// record whose ISIN is a word of the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecoveryPolicy {
+    /// Yield lines as parsed, an error for those failing to parse
+    #[default]
+    Strict,
+    /// Read lines failing to parse, or parsed with an invalid ISIN, again with their columns
+    /// shifted back; yield the record of the only valid reading, tagged
+    /// [`recovered`](struct.NavRecord.html#structfield.recovered), else the line as parsed
+    ///
+    /// A reading assumes one fault in the line:
+    ///
+    /// - the reinvestment ISIN missing, as `-` would give, or both ISINs missing;
+    /// - a separator missing, a column split in two at its first or last space;
+    /// - a separator too many, two adjacent columns joined.
+    ///
+    /// A reading is valid if its code is a number, its ISINs pass validation or are `-`, or
+    /// blank in the history layout, no `;`-separated part of its name lacks letters or looks
+    /// like an ISIN, its NAV and prices are positive and its date is a whole column. Readings
+    /// giving the same record count as one.
+    Lenient,
+}
+
+/// Record a valid reading gives, to tell readings apart
+#[derive(Debug, PartialEq)]
+struct Reading {
+    code: u32,
+    isin: Option<String>,
+    isin_dr: Option<String>,
+    name: String,
+    nav: f64,
+    repurchase_price: Option<f64>,
+    sale_price: Option<f64>,
+    date: NavDate,
+}
+
+/// Whether a record of `fields`, as parsed by the strict parser, is to be read again
+pub(crate) fn suspect(fields: &RecordFields<'_>) -> bool {
+    let invalid = |isin: Option<&str>| isin.is_some_and(|isin| Isin::validate(isin).is_err());
+    invalid(fields.isin) || invalid(fields.isin_dr)
+}
+
+/// Line of the only valid reading of the record line `stripped` in `layout`, see
+/// [`RecoveryPolicy::Lenient`]
+pub(crate) fn recover(stripped: &str, layout: Layout) -> Option<String> {
+    let mut columns: Vec<&str> = trim_trailing_separators(stripped.trim())
+        .split(';')
+        .map(str::trim)
+        .collect();
+    if layout == Layout::Modern {
+        // Empty columns are extra separators, which the parser skips
+        columns.retain(|column| !column.is_empty());
+    }
+    let mut found: Option<(String, Reading)> = None;
+    for line in readings(&columns, layout) {
+        if let Some(reading) = reading(&line, layout) {
+            match &found {
+                None => found = Some((line, reading)),
+                Some((_, first)) if *first == reading => {}
+                Some(_) => return None,
+            }
+        }
+    }
+    found.map(|(line, _)| line)
+}
+
+/// Column of the first ISIN in `layout`, the reinvestment ISIN being the next one
+fn isin_column(layout: Layout) -> usize {
+    match layout {
+        Layout::Modern | Layout::Legacy => 1,
+        Layout::History => 2,
+    }
+}
+
+/// Lines of `columns` in `layout` with one fault undone
+fn readings(columns: &[&str], layout: Layout) -> Vec<String> {
+    let mut readings = Vec::new();
+    let mut with = |columns: Vec<&str>| readings.push(columns.join(";"));
+    let isin = isin_column(layout);
+    if columns.len() > isin + 1 {
+        let mut one = columns.to_vec();
+        one.insert(isin + 1, "-");
+        with(one);
+        let mut both = columns.to_vec();
+        both.splice(isin..isin, ["-", "-"]);
+        with(both);
+    }
+    for (i, column) in columns.iter().enumerate() {
+        let first = column.find(char::is_whitespace);
+        let last = column.rfind(char::is_whitespace);
+        let mut splits = vec![first];
+        if last != first {
+            splits.push(last);
+        }
+        for at in splits.into_iter().flatten() {
+            let mut split = columns.to_vec();
+            split.splice(i..=i, [column[..at].trim_end(), column[at..].trim_start()]);
+            with(split);
+        }
+    }
+    for i in 1..columns.len() {
+        let joined = format!("{}{}", columns[i - 1], columns[i]);
+        let mut join = columns.to_vec();
+        join.splice(i - 1..=i, [joined.as_str()]);
+        with(join);
+    }
+    readings
+}
+
+/// Record of a reading `line` in `layout`, if valid
+fn reading(line: &str, layout: Layout) -> Option<Reading> {
+    let columns: Vec<&str> = line.split(';').collect();
+    // ISINs of the history layout may also be blank
+    let isin_or_blank = |column: &str| {
+        matches!(column, "-" | "---")
+            || (column.is_empty() && layout == Layout::History)
+            || Isin::validate(column).is_ok()
+    };
+    let code = columns.first()?;
+    let isin = isin_column(layout);
+    if code.is_empty()
+        || !code.bytes().all(|byte| byte.is_ascii_digit())
+        || !isin_or_blank(columns.get(isin)?)
+        || !isin_or_blank(columns.get(isin + 1)?)
+        || NavDate::parse_feed(columns.last()?).is_err()
+    {
+        return None;
+    }
+    let fields = parse_record(line, layout).ok()?;
+    let positive = |value: f64| value.is_finite() && value > 0.0;
+    let name_valid = fields.raw_name.split(';').all(|part| {
+        let part = part.trim();
+        part.contains(char::is_alphabetic) && !looks_like_isin(part)
+    });
+    let prices_valid = [fields.repurchase_price, fields.sale_price]
+        .iter()
+        .flatten()
+        .all(|&price| positive(price));
+    if !(name_valid && prices_valid && positive(fields.nav)) {
+        return None;
+    }
+    Some(Reading {
+        code: fields.code,
+        isin: fields.isin.map(str::to_string),
+        isin_dr: fields.isin_dr.map(str::to_string),
+        name: fields.raw_name.trim().to_string(),
+        nav: fields.nav,
+        repurchase_price: fields.repurchase_price,
+        sale_price: fields.sale_price,
+        date: fields.date,
+    })
+}
+
+/// Whether `text` has the shape of an ISIN, valid or not
+fn looks_like_isin(text: &str) -> bool {
+    text.len() == 12
+        && text.bytes().all(|byte| byte.is_ascii_alphanumeric())
+        && text.bytes().any(|byte| byte.is_ascii_digit())
+        && text.bytes().take(2).all(|byte| byte.is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nav_from_str, Error, NavRecord};
+    use proptest::prelude::*;
+
+    const AMC: &str = "Axis Mutual Fund\n";
+    const LEGACY: &str = "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;\
+                          Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date\n";
+    const HISTORY: &str = "Scheme Code;Scheme Name;ISIN Div Payout/ISIN Growth;\
+                           ISIN Div Reinvestment;Net Asset Value;Repurchase Price;Sale Price;Date\n";
+
+    fn parse(line: &str, policy: RecoveryPolicy) -> crate::Result<NavRecord> {
+        parse_in("", line, policy)
+    }
+
+    /// Record of `line` in a feed starting with `header`
+    fn parse_in(header: &str, line: &str, policy: RecoveryPolicy) -> crate::Result<NavRecord> {
+        nav_from_str(&format!("{}{}{}\n", header, AMC, line))
+            .recovery(policy)
+            .next()
+            .unwrap()
+    }
+
+    #[test]
+    fn recovers_shifted_lines() {
+        let line =
+            "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.4300;21-Jun-2024";
+        let legacy = "120466;INF846K01AC2;-;Axis Bluechip Fund - Direct Plan - IDCW;21.98;;22.10;21-Jun-2024";
+        let history = "120466;Axis Bluechip Fund - Direct Plan - IDCW;INF846K01AC2;-;21.98;;22.10;21-Jun-2024";
+        let cases = [
+            // Reinvestment ISIN without its placeholder
+            ("", line.replacen(";-;", ";", 1), line),
+            (LEGACY, legacy.replacen(";-;", ";", 1), legacy),
+            (HISTORY, history.replacen(";-;", ";", 1), history),
+            // Neither ISIN
+            (
+                "",
+                line.replacen(";INF846K01AB4;-;", ";", 1),
+                "120465;-;-;Axis Bluechip Fund - Direct Plan - Growth;61.4300;21-Jun-2024",
+            ),
+            // Separators missing
+            ("", line.replacen("Growth;", "Growth ", 1), line),
+            ("", line.replacen("4300;", "4300 ", 1), line),
+            // A separator too many
+            ("", line.replacen("Jun-", "Jun;-", 1), line),
+        ];
+        for (header, shifted, line) in &cases {
+            // Failing or mangled
+            let strict = parse_in(header, shifted, RecoveryPolicy::Strict);
+            assert!(strict.map_or(true, |record| record.isin_dr.is_some()));
+            let recovered = parse_in(header, shifted, RecoveryPolicy::Lenient).unwrap();
+            let expected = parse_in(header, line, RecoveryPolicy::Strict).unwrap();
+            assert!(recovered.recovered, "{}", shifted);
+            assert_eq!(recovered, expected, "{}", shifted);
+        }
+
+        let raw = nav_from_str(&format!("{}{}\n", AMC, cases[0].1))
+            .recovery(RecoveryPolicy::Lenient)
+            .capture_raw(true)
+            .next()
+            .unwrap()
+            .unwrap()
+            .raw
+            .unwrap();
+        assert_eq!(raw.line, cases[0].1);
+        assert_eq!(raw.name, "Axis Bluechip Fund - Direct Plan - Growth");
+    }
+
+    #[test]
+    fn leaves_unrecoverable_lines() {
+        for line in &[
+            // No name
+            "120465;INF846K01AB4;61.4300;21-Jun-2024",
+            // Invalid ISIN, not taken for a name
+            "120465;INF846K01AB5;Axis Bluechip Fund - Growth;61.4300;21-Jun-2024",
+            // Two faults
+            "120465;INF846K01AB4;Axis Bluechip Fund - Growth 61.4300;21-Jun-2024",
+            // Zero NAV
+            "120465;INF846K01AB4;Axis Bluechip Fund - Growth;0.0000;21-Jun-2024",
+        ] {
+            let strict = format!("{:?}", parse(line, RecoveryPolicy::Strict));
+            let lenient = format!("{:?}", parse(line, RecoveryPolicy::Lenient));
+            assert_eq!(lenient, strict, "{}", line);
+        }
+        assert!(matches!(
+            parse(
+                "120465;INF846K01AB4;61.4300;21-Jun-2024",
+                RecoveryPolicy::Lenient
+            ),
+            Err(Error::ParseFailure { .. })
+        ));
+    }
+
+    /// Well-formed record lines, with valid ISINs, and their layout
+    fn record_line() -> impl Strategy<Value = (String, Layout)> {
+        let isin = prop_oneof![
+            Just("-"),
+            Just("INF846K01AB4"),
+            Just("INF846K01AC2"),
+            Just("INF846K01AD0"),
+        ];
+        (
+            "[1-9][0-9]{0,5}",
+            isin.clone(),
+            isin,
+            "[A-Z][a-z]{1,8}( [A-Za-z0-9&()-]{1,8}){0,5}",
+            "[0-9]{1,4}\\.[0-9]{1,4}",
+            prop_oneof![
+                Just(Layout::Modern),
+                Just(Layout::Legacy),
+                Just(Layout::History)
+            ],
+        )
+            .prop_map(|(code, isin, isin_dr, name, nav, layout)| {
+                let line = match layout {
+                    Layout::Modern => {
+                        format!("{};{};{};{};{};21-Jun-2024", code, isin, isin_dr, name, nav)
+                    }
+                    Layout::Legacy => format!(
+                        "{};{};{};{};{};;;21-Jun-2024",
+                        code, isin, isin_dr, name, nav
+                    ),
+                    Layout::History => format!(
+                        "{};{};{};{};{};;;21-Jun-2024",
+                        code, name, isin, isin_dr, nav
+                    ),
+                };
+                (line, layout)
+            })
+    }
+
+    /// Column header of a feed of `layout`
+    fn header(layout: Layout) -> &'static str {
+        match layout {
+            Layout::Modern => "",
+            Layout::Legacy => LEGACY,
+            Layout::History => HISTORY,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn never_changes_well_formed_lines((line, layout) in record_line()) {
+            let header = header(layout);
+            let strict = parse_in(header, &line, RecoveryPolicy::Strict);
+            let lenient = parse_in(header, &line, RecoveryPolicy::Lenient);
+            prop_assert_eq!(format!("{:?}", lenient), format!("{:?}", strict));
+        }
+
+        #[test]
+        fn recovers_dropped_placeholders((line, layout) in record_line()) {
+            let header = header(layout);
+            let expected = parse_in(header, &line, RecoveryPolicy::Strict).unwrap();
+            prop_assume!(expected.nav > 0.0 && expected.isin_dr.is_none());
+            // Names ending in ` -` of the history layout also read as a name and a placeholder
+            prop_assume!(layout != Layout::History || !expected.name.ends_with(" -"));
+            let columns: Vec<&str> = line.split(';').collect();
+            let isin_dr = isin_column(layout) + 1;
+            let shifted = [&columns[..isin_dr], &columns[isin_dr + 1..]].concat().join(";");
+            let recovered = parse_in(header, &shifted, RecoveryPolicy::Lenient).unwrap();
+            prop_assert_eq!(recover(&shifted, layout), Some(line.clone()));
+            prop_assert!(recovered.recovered);
+            prop_assert_eq!(recovered, expected);
+        }
+    }
+}
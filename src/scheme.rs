@@ -0,0 +1,236 @@
+//! Scheme section headers, e.g. `Open Ended Schemes(Equity Scheme - Large Cap Fund)`
+
+use crate::{
+    contains_ignore_ascii_case, starts_with_ignore_ascii_case, Error, FundMaturity, Result,
+    SchemeCategory, SchemeGroup,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Scheme section header, as read by [`parse_scheme_header`](fn.parse_scheme_header.html)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SchemeHeader {
+    /// `Open` or `Close Ended Schemes`, `None` for other prefixes
+    pub maturity: Option<FundMaturity>,
+    /// Text before the first ` - ` within the parentheses, e.g. `Equity Scheme`; `None` for
+    /// headers of a category only, e.g. `Close Ended Schemes(Income)`
+    pub scheme_group: Option<String>,
+    /// Rest of the text within the parentheses, e.g. `Large Cap Fund`; empty for headers
+    /// without parentheses, e.g. `Close Ended Schemes`
+    pub category: String,
+}
+
+/// Parses a scheme section header of the feed, or of other AMFI files using them
+///
+/// The maturity is told by the text before the opening parenthesis, the scheme group and
+/// category by the text up to the matching closing parenthesis, so parentheses may nest in
+/// the category, e.g. `Other Scheme - FoF (Overseas)`. The group ends at the first ` - `,
+/// or ` – `; further dashes belong to the category. Both are the text between these
+/// delimiters as published, spaces included, as the `scheme` and `category` of records.
+///
+/// Headers of some older feeds have no parenthesized part, e.g. `Close Ended Schemes`: their
+/// maturity is told by the whole line and they have no scheme group and an empty category,
+/// as records read before any scheme header.
+///
+/// Fails with [`Error::SynomError`](enum.Error.html#variant.SynomError) holding `line` if its
+/// parenthesis isn't closed, or if it has none and doesn't name schemes.
+///
+/// ```rust
+/// use amfi::{FundMaturity, SchemeHeader};
+///
+/// let header = amfi::parse_scheme_header("Open Ended Schemes(Equity Scheme - Large Cap Fund)")?;
+/// assert_eq!(header.maturity, Some(FundMaturity::OpenEnded));
+/// assert_eq!(header.scheme_group.as_deref(), Some("Equity Scheme"));
+/// assert_eq!(header.category, "Large Cap Fund");
+///
+/// let header = amfi::parse_scheme_header("Close Ended Schemes ( Income )")?;
+/// assert_eq!(header.scheme_group, None);
+/// assert_eq!(header.to_string(), "Close Ended Schemes(Income)");
+///
+/// let header = amfi::parse_scheme_header("Close Ended Schemes")?;
+/// assert_eq!(header.maturity, Some(FundMaturity::CloseEnded));
+/// assert_eq!(header.category, "");
+/// # Ok::<(), amfi::Error>(())
+/// ```
+pub fn parse_scheme_header(line: &str) -> Result<SchemeHeader> {
+    let invalid = || Error::SynomError(line.to_string());
+    let (prefix, body) = match line.find('(') {
+        Some(open) => {
+            let body = &line[open + 1..];
+            let close = matching_close(body).ok_or_else(invalid)?;
+            (line[..open].trim_start(), &body[..close])
+        }
+        None if contains_ignore_ascii_case(line, "SCHEME") => (line.trim_start(), ""),
+        None => return Err(invalid()),
+    };
+
+    let maturity = if starts_with_ignore_ascii_case(prefix, "CLOSE") {
+        Some(FundMaturity::CloseEnded)
+    } else if starts_with_ignore_ascii_case(prefix, "OPEN") {
+        Some(FundMaturity::OpenEnded)
+    } else {
+        None
+    };
+    // The first ` - ` or ` – `, found by its dash
+    let dash = body
+        .char_indices()
+        .find(|&(at, ch)| {
+            (ch == '-' || ch == '–')
+                && body[..at].ends_with(' ')
+                && body[at + ch.len_utf8()..].starts_with(' ')
+        })
+        .map(|(at, ch)| (at - 1, ch.len_utf8() + 2));
+    let (scheme_group, category) = match dash {
+        Some((at, len)) => (Some(&body[..at]), &body[at + len..]),
+        None => (None, body),
+    };
+    Ok(SchemeHeader {
+        maturity,
+        scheme_group: scheme_group.map(str::to_string),
+        category: category.to_string(),
+    })
+}
+
+/// Position of the `)` closing the parenthesis opened just before `body`
+///
+/// The last `)` if parentheses don't balance, as in `Income (Debt`.
+fn matching_close(body: &str) -> Option<usize> {
+    let mut depth = 0_usize;
+    for (at, ch) in body.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(at),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    body.rfind(')')
+}
+
+impl SchemeHeader {
+    /// Typed scheme group of the header, see [`SchemeGroup::new`](enum.SchemeGroup.html#method.new)
+    pub fn group(&self) -> Option<SchemeGroup> {
+        self.scheme_group.as_deref().map(SchemeGroup::new)
+    }
+
+    /// Typed category of the header, see
+    /// [`SchemeCategory::of_group`](struct.SchemeCategory.html#method.of_group)
+    pub fn scheme_category(&self) -> SchemeCategory {
+        SchemeCategory::of_group(self.group().as_ref(), &self.category)
+    }
+}
+
+/// Header as in current feeds, e.g. `Open Ended Schemes(Equity Scheme - Large Cap Fund)`
+///
+/// Scheme group and category are trimmed. Headers of other maturities have no prefix, e.g.
+/// `(Income)`, which parses back without maturity. Headers of a maturity only have no
+/// parentheses, e.g. `Close Ended Schemes`.
+impl fmt::Display for SchemeHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.maturity {
+            Some(FundMaturity::OpenEnded) => f.write_str("Open Ended Schemes")?,
+            Some(FundMaturity::CloseEnded) => f.write_str("Close Ended Schemes")?,
+            None => {}
+        }
+        let bare = self.scheme_group.is_none() && self.category.trim().is_empty();
+        if bare && self.maturity.is_some() {
+            return Ok(());
+        }
+        f.write_str("(")?;
+        if let Some(ref group) = self.scheme_group {
+            write!(f, "{} - ", group.trim())?;
+        }
+        write!(f, "{})", self.category.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(maturity: Option<FundMaturity>, group: Option<&str>, category: &str) -> SchemeHeader {
+        SchemeHeader {
+            maturity,
+            scheme_group: group.map(str::to_string),
+            category: category.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_headers() {
+        let open = Some(FundMaturity::OpenEnded);
+        let cases = [
+            (
+                "Open Ended Schemes(Equity Scheme - Large Cap Fund)",
+                header(open, Some("Equity Scheme"), "Large Cap Fund"),
+            ),
+            (
+                "Open Ended Schemes ( Equity Scheme - Large Cap Fund )",
+                header(open, Some(" Equity Scheme"), "Large Cap Fund "),
+            ),
+            (
+                "Close Ended Schemes(Income)",
+                header(Some(FundMaturity::CloseEnded), None, "Income"),
+            ),
+            (
+                "Open Ended Schemes(Other Scheme - FoF (Overseas)) ",
+                header(open, Some("Other Scheme"), "FoF (Overseas)"),
+            ),
+            (
+                "Open Ended Schemes(Solution Oriented Scheme - Retirement Fund - Hybrid)",
+                header(
+                    open,
+                    Some("Solution Oriented Scheme"),
+                    "Retirement Fund - Hybrid",
+                ),
+            ),
+            (
+                "Open Ended Schemes(Debt Scheme – Liquid Fund)",
+                header(open, Some("Debt Scheme"), "Liquid Fund"),
+            ),
+            (
+                "Interval Fund Schemes(Income (Debt)",
+                header(None, None, "Income (Debt"),
+            ),
+            // No parentheses, as in some older feeds
+            (
+                "Close Ended Schemes",
+                header(Some(FundMaturity::CloseEnded), None, ""),
+            ),
+            (" Open Ended Schemes ", header(open, None, "")),
+            ("Interval Fund Schemes", header(None, None, "")),
+        ];
+        for (line, expected) in cases.iter() {
+            assert_eq!(&parse_scheme_header(line).unwrap(), expected, "{}", line);
+        }
+
+        for line in &["Open Ended Schemes(Income", "Axis Mutual Fund", ""] {
+            match parse_scheme_header(line) {
+                Err(Error::SynomError(text)) => assert_eq!(&text, line),
+                other => panic!("{}: {:?}", line, other),
+            }
+        }
+    }
+
+    #[test]
+    fn displays_canonical_header() {
+        for line in &[
+            "Open Ended Schemes(Equity Scheme - Large Cap Fund)",
+            "Close Ended Schemes(Income)",
+            "Open Ended Schemes(Other Scheme - FoF (Overseas))",
+            "(Income)",
+            "Close Ended Schemes",
+        ] {
+            let header = parse_scheme_header(line).unwrap();
+            assert_eq!(&header.to_string(), line);
+            assert_eq!(parse_scheme_header(&header.to_string()).unwrap(), header);
+        }
+        let header = parse_scheme_header("open ended schemes ( Debt Scheme -  Liquid Fund )");
+        assert_eq!(
+            header.unwrap().to_string(),
+            "Open Ended Schemes(Debt Scheme - Liquid Fund)"
+        );
+    }
+}
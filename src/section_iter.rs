@@ -0,0 +1,359 @@
+//! Sections of a feed, their records parsed on demand
+
+use crate::{with_semicolons, NavRecord, NavRecordIterator, Result, SectionContext, Step};
+use std::io::Read;
+
+/// Reader of the sections of a feed one after another, see [`sections`](fn.sections.html)
+pub struct NavSections<R> {
+    records: NavRecordIterator<R>,
+    /// Whether a header was read since the last record line, so the next starts a section
+    boundary: bool,
+}
+
+/// Records under one AMC and scheme header, borrowing the feed being read, see
+/// [`NavSections::next_section`](struct.NavSections.html#method.next_section)
+///
+/// Dropped before its records are all read, the rest of them are skipped unparsed.
+pub struct Section<'a, R> {
+    sections: &'a mut NavSections<R>,
+    context: SectionContext,
+    line: usize,
+    /// First record line and what it was read as, parsed with the first record asked for
+    first: Option<(String, Step)>,
+    done: bool,
+}
+
+/// Iterator over the records of a [`Section`](struct.Section.html), see
+/// [`Section::records`](struct.Section.html#method.records)
+pub struct SectionRecords<'s, 'a, R> {
+    section: &'s mut Section<'a, R>,
+}
+
+/// What reading the next line of the feed found
+// Read one at a time, so records aren't boxed
+#[allow(clippy::large_enum_variant)]
+enum Next {
+    /// A record line, or the error of a record line
+    Record(Result<NavRecord>),
+    /// The first record line of a section, not parsed
+    Start(String, Step),
+    /// A header ending the section
+    Boundary,
+}
+
+/// Reads the feed in `reader` section by section, each section a run of record lines under
+/// the same AMC and scheme header
+///
+/// A [`Section`](struct.Section.html) tells its AMC, maturity, scheme group and category as
+/// soon as its first record line is read, and parses its records only as they are asked for;
+/// sections dropped unread cost no parsing at all. Sections borrow the reader, so they are
+/// read by [`next_section`](struct.NavSections.html#method.next_section) in a `while let`
+/// loop rather than by an iterator, or passed to a closure by
+/// [`for_each_section`](fn.for_each_section.html). Records are parsed as by
+/// [`NavRecordIterator`](struct.NavRecordIterator.html), which
+/// [`NavRecordIterator::sections`](struct.NavRecordIterator.html#method.sections) reads with
+/// its settings.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let file = std::fs::File::open("fixtures/NAVOpen.txt")?;
+/// let mut sections = amfi::sections(file);
+/// while let Some(section) = sections.next_section() {
+///     let mut section = section?;
+///     if section.context().category != "Liquid Fund" {
+///         continue;
+///     }
+///     for record in section.records() {
+///         let record = record?;
+///         println!("{} {}", record.amc, record.nav);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn sections<R: Read>(reader: R) -> NavSections<R> {
+    NavRecordIterator::new(reader).sections()
+}
+
+/// Calls `f` with each section of the feed in `reader`, see [`sections`](fn.sections.html)
+///
+/// Stops at the first error of `f`, or of the feed other than of a record, and returns it.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let file = std::fs::File::open("fixtures/NAVOpen.txt")?;
+/// let mut liquid = 0;
+/// amfi::for_each_section(file, |mut section| {
+///     if section.context().category == "Liquid Fund" {
+///         liquid += section.records().filter(Result::is_ok).count();
+///     }
+///     Ok(())
+/// })?;
+/// assert_eq!(liquid, 3);
+/// # Ok(())
+/// # }
+/// ```
+pub fn for_each_section<R, F>(reader: R, mut f: F) -> Result<()>
+where
+    R: Read,
+    F: FnMut(Section<'_, R>) -> Result<()>,
+{
+    let mut sections = sections(reader);
+    while let Some(section) = sections.next_section() {
+        f(section?)?;
+    }
+    Ok(())
+}
+
+impl<R: Read> NavRecordIterator<R> {
+    /// Reads the feed section by section, see [`sections`](fn.sections.html)
+    pub fn sections(self) -> NavSections<R> {
+        NavSections {
+            records: self,
+            boundary: true,
+        }
+    }
+}
+
+impl<R: Read> NavSections<R> {
+    /// Next section, `None` at the end of the feed
+    ///
+    /// Record lines of the last section left unread are skipped without being parsed. Fails
+    /// with errors ending the feed, such as I/O errors or a malformed scheme header.
+    pub fn next_section(&mut self) -> Option<Result<Section<'_, R>>> {
+        loop {
+            match self.read()? {
+                Ok(Next::Start(line, step)) => {
+                    return Some(Ok(Section {
+                        context: self.records.state.section.clone(),
+                        line: self.records.state.line(),
+                        first: Some((line, step)),
+                        sections: self,
+                        done: false,
+                    }))
+                }
+                // Records of a section left unread, past its first line
+                Ok(Next::Record(_)) | Ok(Next::Boundary) => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+
+    /// 1-based number of the last line read
+    pub fn line(&self) -> usize {
+        self.records.line()
+    }
+
+    /// Reads lines up to the next record, record line starting a section or header ending one
+    fn read(&mut self) -> Option<Result<Next>> {
+        if let Some(error) = self.records.state.too_many_errors() {
+            return Some(Err(error));
+        }
+        let boundary = &mut self.boundary;
+        self.records.next_with(|state, line| {
+            let delimited = with_semicolons(line, state.delimiter);
+            let capture_raw = state.capture_raw;
+            match state.advance(&delimited) {
+                Step::Amc | Step::Scheme | Step::Header => {
+                    *boundary = true;
+                    Some(Ok(Next::Boundary))
+                }
+                Step::Blank | Step::Skip => None,
+                Step::Bailout(error) => Some(Err(error)),
+                step if *boundary => {
+                    *boundary = false;
+                    Some(Ok(Next::Start(line.to_string(), step)))
+                }
+                step => state
+                    .parse_step(step, line, &delimited, |result| {
+                        result.map(|record| record.into_record(capture_raw))
+                    })
+                    .map(|result| Ok(Next::Record(result))),
+            }
+        })
+    }
+}
+
+impl<'a, R: Read> Section<'a, R> {
+    /// AMC, maturity, scheme group and category of the section
+    pub fn context(&self) -> &SectionContext {
+        &self.context
+    }
+
+    /// 1-based line of the first record line of the section
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Records of the section, parsed as they are read
+    pub fn records(&mut self) -> SectionRecords<'_, 'a, R> {
+        SectionRecords { section: self }
+    }
+
+    fn next_record(&mut self) -> Option<Result<NavRecord>> {
+        if let Some((line, step)) = self.first.take() {
+            let state = &mut self.sections.records.state;
+            let delimited = with_semicolons(&line, state.delimiter);
+            let capture_raw = state.capture_raw;
+            let record = state.parse_step(step, &line, &delimited, |result| {
+                result.map(|record| record.into_record(capture_raw))
+            });
+            if record.is_some() {
+                return record;
+            }
+        }
+        while !self.done {
+            match self.sections.read() {
+                Some(Ok(Next::Record(record))) => return Some(record),
+                Some(Ok(Next::Start(..))) => unreachable!("sections start after a header"),
+                Some(Ok(Next::Boundary)) | None => self.done = true,
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<R> Drop for Section<'_, R> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.sections.records.state.skip_section = true;
+        }
+    }
+}
+
+impl<R: Read> Iterator for SectionRecords<'_, '_, R> {
+    type Item = Result<NavRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.section.next_record()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_text;
+    use crate::{nav_from_str, Error, FundMaturity};
+
+    const FEED: &str = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                        \n\
+                        Axis Mutual Fund\n\
+                        120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                        120466;garbage;\n\
+                        112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n\
+                        HDFC Mutual Fund\n\
+                        119018;INF179K01YV8;-;HDFC Top 100 Fund - Direct Plan - Growth;1120.35;21-Jun-2024\n\
+                        Open Ended Schemes(Debt Scheme - Liquid Fund)\n\
+                        Axis Mutual Fund\n\
+                        120389;INF846K01AU4;-;Axis Liquid Fund - Direct Plan - Growth Option;2736.7421;21-Jun-2024\n";
+
+    /// Results of every section in turn
+    fn flattened(feed: &str) -> Vec<Result<NavRecord>> {
+        let mut results = Vec::new();
+        for_each_section(feed.as_bytes(), |mut section| {
+            results.extend(section.records());
+            Ok(())
+        })
+        .unwrap();
+        results
+    }
+
+    #[test]
+    fn same_records_as_the_flat_iterator() {
+        for feed in &[FEED.to_string(), fixture_text("NAVOpen.txt")] {
+            let flat: Vec<_> = nav_from_str(feed).collect();
+            let sectioned = flattened(feed);
+            assert_eq!(sectioned.len(), flat.len());
+            for (left, right) in sectioned.iter().zip(&flat) {
+                match (left, right) {
+                    (Ok(left), Ok(right)) => assert_eq!(left, right),
+                    (Err(left), Err(right)) => assert_eq!(left.to_string(), right.to_string()),
+                    other => panic!("{:?}", other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn headers_before_records() {
+        let mut sections = sections(FEED.as_bytes());
+        let mut seen = Vec::new();
+        while let Some(section) = sections.next_section() {
+            let section = section.unwrap();
+            let context = section.context();
+            assert_eq!(context.maturity, Some(FundMaturity::OpenEnded));
+            seen.push((
+                section.line(),
+                context.amc.clone(),
+                context.scheme.clone().unwrap(),
+                context.category.clone(),
+            ));
+        }
+        let seen: Vec<_> = seen
+            .iter()
+            .map(|(line, amc, scheme, category)| {
+                (*line, amc.as_str(), scheme.as_str(), category.as_str())
+            })
+            .collect();
+        assert_eq!(
+            seen,
+            [
+                (4, "Axis Mutual Fund", "Equity Scheme", "Large Cap Fund"),
+                (8, "HDFC Mutual Fund", "Equity Scheme", "Large Cap Fund"),
+                (11, "Axis Mutual Fund", "Debt Scheme", "Liquid Fund"),
+            ]
+        );
+    }
+
+    #[test]
+    fn dropped_sections_are_not_parsed() {
+        let feed = fixture_text("NAVOpen.txt");
+        let mut sections = sections(feed.as_bytes());
+        let mut liquid = Vec::new();
+        let mut first_of_others = 0;
+        while let Some(section) = sections.next_section() {
+            let mut section = section.unwrap();
+            if section.context().category == "Liquid Fund" {
+                liquid.extend(section.records().map(Result::unwrap));
+            } else if section.line() % 2 == 0 {
+                // Left after its first record
+                first_of_others += section.records().take(1).count();
+            }
+        }
+        assert_eq!(liquid.len(), 3);
+        assert!(first_of_others > 0);
+        let parsed = sections.records.state.record_lines;
+        assert_eq!(parsed, liquid.len() + first_of_others);
+
+        let expected: Vec<_> = nav_from_str(&feed)
+            .map(Result::unwrap)
+            .filter(|record| record.category == "Liquid Fund")
+            .collect();
+        assert_eq!(liquid, expected);
+    }
+
+    #[test]
+    fn errors_of_the_feed_end_it() {
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    Open Ended Schemes(Large Cap Fund\n\
+                    120466;INF846K01AC2;-;Axis Bluechip Fund - Direct Plan - IDCW;21.98;21-Jun-2024\n";
+        let mut sections = sections(feed.as_bytes());
+        let mut section = sections.next_section().unwrap().unwrap();
+        assert!(section.records().next().unwrap().is_ok());
+        assert!(matches!(
+            section.records().next(),
+            Some(Err(Error::SynomError(_)))
+        ));
+        assert!(section.records().next().is_none());
+        drop(section);
+        assert!(sections.next_section().is_none());
+
+        let result = for_each_section(feed.as_bytes(), |_| Ok(()));
+        assert!(matches!(result, Err(Error::SynomError(_))));
+    }
+}
@@ -0,0 +1,405 @@
+//! Record counts of the sections of a feed and checks of their order
+
+use crate::{FeedLine, FeedLines, FundMaturity, Result, SchemeCategory, SchemeCode, SubCategory};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+
+/// Records of one AMC under one scheme header, see [`check_sections`](fn.check_sections.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedSection {
+    /// 1-based line of the AMC header, or of the scheme header for a section without AMC
+    pub line: usize,
+    /// Maturity of the scheme header
+    pub maturity: Option<FundMaturity>,
+    /// Category of the scheme header
+    pub category: String,
+    /// AMC, `None` for a scheme header directly followed by another or the end of the feed
+    pub amc: Option<String>,
+    /// Records parsed
+    pub records: usize,
+    /// Record lines that failed to parse
+    pub errors: usize,
+}
+
+/// Sign of a dropped or misplaced section header, see
+/// [`check_sections`](fn.check_sections.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SectionFinding {
+    /// Section without records or unparsed record lines
+    Empty {
+        /// 1-based line of its header
+        line: usize,
+        /// AMC, `None` if the scheme header has no AMC below it
+        amc: Option<String>,
+        /// Category of the scheme header
+        category: String,
+    },
+    /// AMC listed under a maturity after being listed under a later one, e.g. open ended
+    /// schemes after close ended ones
+    OutOfOrder {
+        /// 1-based line of the AMC header
+        line: usize,
+        /// AMC
+        amc: String,
+        /// Maturity of the section
+        maturity: Option<FundMaturity>,
+        /// Latest maturity of the AMC's earlier sections
+        after: Option<FundMaturity>,
+    },
+    /// Record whose category doesn't exist for its maturity, e.g. a liquid fund under close
+    /// ended schemes
+    ImplausibleCategory {
+        /// 1-based line of the record
+        line: usize,
+        /// Scheme code of the record
+        code: SchemeCode,
+        /// Maturity of its section
+        maturity: FundMaturity,
+        /// Category of its section
+        category: String,
+    },
+}
+
+impl SectionFinding {
+    /// 1-based line of the feed to look at
+    pub fn line(&self) -> usize {
+        match *self {
+            SectionFinding::Empty { line, .. }
+            | SectionFinding::OutOfOrder { line, .. }
+            | SectionFinding::ImplausibleCategory { line, .. } => line,
+        }
+    }
+}
+
+impl fmt::Display for SectionFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SectionFinding::Empty {
+                line,
+                amc: Some(amc),
+                category,
+            } => write!(f, "line {}: no records of {} in {}", line, amc, category),
+            SectionFinding::Empty {
+                line,
+                amc: None,
+                category,
+            } => write!(f, "line {}: no AMC in {}", line, category),
+            SectionFinding::OutOfOrder {
+                line,
+                amc,
+                maturity,
+                after,
+            } => write!(
+                f,
+                "line {}: {} schemes of {} after {} ones",
+                line,
+                maturity_name(*maturity),
+                amc,
+                maturity_name(*after)
+            ),
+            SectionFinding::ImplausibleCategory {
+                line,
+                code,
+                maturity,
+                category,
+            } => write!(
+                f,
+                "line {}: scheme {} is {} but a {}",
+                line,
+                code,
+                maturity_name(Some(*maturity)),
+                category.trim()
+            ),
+        }
+    }
+}
+
+/// Sections of a feed and the findings of checking them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SectionReport {
+    /// Sections in feed order
+    pub sections: Vec<FeedSection>,
+    /// Findings in feed order
+    pub findings: Vec<SectionFinding>,
+}
+
+/// Counts the records of each section of a feed and checks the sections
+///
+/// Feeds list all open ended schemes, then close ended, then interval schemes, each by
+/// category with the AMCs having schemes in it. A scheme header lost upstream lets the
+/// records below it inherit the section before, which shows as:
+///
+/// - an AMC listed under open ended schemes after close ended ones,
+/// - sections without records,
+/// - records of categories that only exist for the other maturity, e.g. liquid funds or
+///   ETFs under close ended schemes, fixed maturity plans under open ended ones.
+///
+/// Sections of a concatenation of feeds are checked feed by feed, see
+/// [`FeedLines::segment`](struct.FeedLines.html#method.segment). Fails with the error ending
+/// `lines`, e.g. an I/O error.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let file = std::fs::File::open("fixtures/NAVOpen.txt")?;
+/// let report = amfi::check_sections(amfi::feed_lines(file))?;
+/// for finding in &report.findings {
+///     eprintln!("{}", finding);
+/// }
+/// assert_eq!(report.sections.iter().map(|s| s.records).sum::<usize>(), 30);
+/// # Ok(())
+/// # }
+/// ```
+pub fn check_sections<R: Read>(mut lines: FeedLines<R>) -> Result<SectionReport> {
+    let mut report = SectionReport::default();
+    let mut latest: HashMap<String, Option<FundMaturity>> = HashMap::new();
+    let mut segment = lines.segment();
+    while let Some(line) = lines.next() {
+        let number = lines.line();
+        if lines.segment() != segment {
+            segment = lines.segment();
+            latest.clear();
+        }
+        match line? {
+            FeedLine::SchemeHeader {
+                maturity, category, ..
+            } => report.sections.push(FeedSection {
+                line: number,
+                maturity,
+                category,
+                amc: None,
+                records: 0,
+                errors: 0,
+            }),
+            FeedLine::Amc(amc) => {
+                let (maturity, category) = match report.sections.last() {
+                    Some(section) => (section.maturity, section.category.clone()),
+                    None => (None, String::new()),
+                };
+                if let Some(&after) = latest.get(&amc) {
+                    if rank(maturity) < rank(after) {
+                        report.findings.push(SectionFinding::OutOfOrder {
+                            line: number,
+                            amc: amc.clone(),
+                            maturity,
+                            after,
+                        });
+                    }
+                }
+                let after = latest.entry(amc.clone()).or_insert(maturity);
+                if rank(maturity) > rank(*after) {
+                    *after = maturity;
+                }
+                let section = FeedSection {
+                    line: number,
+                    maturity,
+                    category,
+                    amc: Some(amc),
+                    records: 0,
+                    errors: 0,
+                };
+                match report.sections.last_mut() {
+                    Some(last) if last.amc.is_none() && last.records + last.errors == 0 => {
+                        *last = section
+                    }
+                    _ => report.sections.push(section),
+                }
+            }
+            FeedLine::Record(record) => {
+                if let Some(section) = report.sections.last_mut() {
+                    section.records += 1;
+                }
+                if let Some(maturity) = record.maturity {
+                    if implausible(maturity, record.scheme.as_deref(), &record.category) {
+                        report.findings.push(SectionFinding::ImplausibleCategory {
+                            line: number,
+                            code: record.code,
+                            maturity,
+                            category: record.category,
+                        });
+                    }
+                }
+            }
+            FeedLine::Unparsed { .. } => {
+                if let Some(section) = report.sections.last_mut() {
+                    section.errors += 1;
+                }
+            }
+            FeedLine::Header | FeedLine::Blank => {}
+        }
+    }
+    let empty = report
+        .sections
+        .iter()
+        .filter(|section| section.records + section.errors == 0)
+        .map(|section| SectionFinding::Empty {
+            line: section.line,
+            amc: section.amc.clone(),
+            category: section.category.clone(),
+        });
+    report.findings.extend(empty);
+    report.findings.sort_by_key(SectionFinding::line);
+    Ok(report)
+}
+
+/// Place of `maturity` in the order of a feed
+fn rank(maturity: Option<FundMaturity>) -> u8 {
+    match maturity {
+        Some(FundMaturity::OpenEnded) => 0,
+        Some(FundMaturity::CloseEnded) => 1,
+        None => 2,
+    }
+}
+
+fn maturity_name(maturity: Option<FundMaturity>) -> &'static str {
+    match maturity {
+        Some(FundMaturity::OpenEnded) => "open ended",
+        Some(FundMaturity::CloseEnded) => "close ended",
+        None => "other",
+    }
+}
+
+/// Whether no scheme of `maturity` can be of `category`
+fn implausible(maturity: FundMaturity, scheme: Option<&str>, category: &str) -> bool {
+    match maturity {
+        FundMaturity::CloseEnded => matches!(
+            SchemeCategory::new(scheme, category).sub,
+            SubCategory::Overnight
+                | SubCategory::Liquid
+                | SubCategory::UltraShortDuration
+                | SubCategory::LowDuration
+                | SubCategory::MoneyMarket
+                | SubCategory::Arbitrage
+                | SubCategory::IndexFund
+                | SubCategory::GoldEtf
+                | SubCategory::Etf
+        ),
+        FundMaturity::OpenEnded => {
+            let category = category.to_ascii_lowercase();
+            ["fixed maturity", "fmp", "capital protection", "interval"]
+                .iter()
+                .any(|closed| category.contains(closed))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{feed_lines, Error};
+
+    fn report(feed: &str) -> SectionReport {
+        check_sections(feed_lines(feed.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn fixture_is_consistent() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let report = check_sections(feed_lines(std::fs::File::open(path).unwrap())).unwrap();
+        assert_eq!(report.findings, []);
+        assert_eq!(report.sections.iter().map(|s| s.records).sum::<usize>(), 30);
+        assert!(report.sections.iter().all(|s| s.amc.is_some()));
+        let first = &report.sections[0];
+        assert_eq!(first.maturity, Some(FundMaturity::OpenEnded));
+        assert_eq!(first.category, "Large Cap Fund");
+    }
+
+    #[test]
+    fn dropped_headers() {
+        // The close ended header of Axis' FMP was lost, then that of the liquid funds
+        let feed = "Open Ended Schemes(Debt Scheme - Liquid Fund)\n\
+                    Axis Mutual Fund\n\
+                    100001;-;-;Axis Liquid Fund - Growth;2700.1;21-Jun-2024\n\
+                    HDFC Mutual Fund\n\
+                    100002;-;-;HDFC Liquid Fund - Growth;4800.2;21-Jun-2024\n\
+                    Close Ended Schemes(Debt Scheme - Fixed Maturity Plan)\n\
+                    Axis Mutual Fund\n\
+                    100003;-;-;Axis Fixed Term Plan Series 112;11.3;21-Jun-2024\n\
+                    HDFC Mutual Fund\n\
+                    100004;-;-;HDFC Overnight Fund - Growth;3600.4;21-Jun-2024\n\
+                    Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    100005;-;-;Axis Bluechip Fund - Growth;61.43;21-Jun-2024\n\
+                    SBI Mutual Fund\n\
+                    Open Ended Schemes(Equity Scheme - Mid Cap Fund)\n";
+        let report = report(feed);
+        assert_eq!(
+            report
+                .sections
+                .iter()
+                .map(|s| (s.line, s.amc.as_deref(), s.records))
+                .collect::<Vec<_>>(),
+            [
+                (2, Some("Axis Mutual Fund"), 1),
+                (4, Some("HDFC Mutual Fund"), 1),
+                (7, Some("Axis Mutual Fund"), 1),
+                (9, Some("HDFC Mutual Fund"), 1),
+                (12, Some("Axis Mutual Fund"), 1),
+                (14, Some("SBI Mutual Fund"), 0),
+                (15, None, 0),
+            ]
+        );
+        assert_eq!(
+            report.findings,
+            [
+                SectionFinding::OutOfOrder {
+                    line: 12,
+                    amc: "Axis Mutual Fund".to_string(),
+                    maturity: Some(FundMaturity::OpenEnded),
+                    after: Some(FundMaturity::CloseEnded),
+                },
+                SectionFinding::Empty {
+                    line: 14,
+                    amc: Some("SBI Mutual Fund".to_string()),
+                    category: "Large Cap Fund".to_string(),
+                },
+                SectionFinding::Empty {
+                    line: 15,
+                    amc: None,
+                    category: "Mid Cap Fund".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            report.findings[0].to_string(),
+            "line 12: open ended schemes of Axis Mutual Fund after close ended ones"
+        );
+    }
+
+    fn report_findings(feed: &str) -> Vec<String> {
+        report(feed)
+            .findings
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn implausible_categories() {
+        let feed = "Close Ended Schemes(Debt Scheme - Liquid Fund)\n\
+                    Axis Mutual Fund\n\
+                    100001;-;-;Axis Liquid Fund - Growth;2700.1;21-Jun-2024\n\
+                    Open Ended Schemes(Debt Scheme - Fixed Maturity Plan)\n\
+                    Axis Mutual Fund\n\
+                    100003;-;-;Axis Fixed Term Plan Series 112;11.3;21-Jun-2024\n";
+        let findings = report_findings(feed);
+        assert_eq!(
+            findings,
+            [
+                "line 3: scheme 100001 is close ended but a Liquid Fund",
+                "line 5: open ended schemes of Axis Mutual Fund after close ended ones",
+                "line 6: scheme 100003 is open ended but a Fixed Maturity Plan",
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_fail_the_check() {
+        let feed = "Axis Mutual Fund\nOpen Ended Schemes(Large Cap Fund\n";
+        assert!(matches!(
+            check_sections(feed_lines(feed.as_bytes())),
+            Err(Error::SynomError(_))
+        ));
+    }
+}
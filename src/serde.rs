@@ -0,0 +1,763 @@
+//! Serde helpers for alternative date representations
+//!
+//! [`NavRecord::date`](../struct.NavRecord.html#structfield.date) serializes as an ISO 8601
+//! string (`2024-06-21`). The modules here serialize a date differently and can be used on
+//! your own types with `#[serde(with = "...")]`. Besides [`NavDate`](../struct.NavDate.html)
+//! they serialize `chrono::NaiveDate` with the `chrono` feature and `time::Date` with the
+//! `time` feature, the same way:
+//!
+//! ```rust
+//! use amfi::NavDate;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Price {
+//!     #[serde(with = "amfi::serde::date_ddmmmyyyy")]
+//!     date: NavDate,
+//!     nav: f64,
+//! }
+//!
+//! let price = Price { date: NavDate::from_ymd(2024, 6, 21).unwrap(), nav: 61.43 };
+//! let json = serde_json::to_string(&price).unwrap();
+//! assert_eq!(json, r#"{"date":"21-Jun-2024","nav":61.43}"#);
+//! ```
+//!
+//! For integer dates use [`epoch_days`](epoch_days/index.html), days since 1970-01-01, or
+//! [`date_unix_midnight_ist`](date_unix_midnight_ist/index.html), a Unix timestamp. NAV dates
+//! are Indian calendar days, so the timestamp is of midnight in Indian Standard Time: UTC
+//! midnight read back in a timezone west of UTC would be the previous day.
+//!
+//! To serialize whole records with another date format wrap them in a view:
+//! [`NavRecordIsoDate`](struct.NavRecordIsoDate.html),
+//! [`NavRecordFeedDate`](struct.NavRecordFeedDate.html) or
+//! [`NavRecordEpochDays`](struct.NavRecordEpochDays.html). Views have the same fields as
+//! `NavRecord` and deserialize what they serialize.
+
+use crate::{
+    FofKind, FundMaturity, FundPlan, NavDate, NavRecord, PlanSource, RawFields, RawIsin, SchemeCode,
+};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+/// Converts a date of any supported type for serialization
+fn nav_date<T, E>(date: &T) -> Result<NavDate, E>
+where
+    T: Copy + TryInto<NavDate>,
+    T::Error: std::fmt::Display,
+    E: serde::ser::Error,
+{
+    (*date).try_into().map_err(E::custom)
+}
+
+/// Date as ISO 8601 string, e.g. `2024-06-21`
+pub mod date_iso {
+    use crate::NavDate;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::convert::TryInto;
+    use std::fmt::Display;
+
+    /// Serializes `date` as `YYYY-MM-DD`
+    pub fn serialize<T, S>(date: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Copy + TryInto<NavDate>,
+        T::Error: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(&super::nav_date::<_, S::Error>(date)?)
+    }
+
+    /// Deserializes a `YYYY-MM-DD` string
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<NavDate>,
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse::<NavDate>()
+            .map(T::from)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Date as string in feed format, e.g. `21-Jun-2024`
+pub mod date_ddmmmyyyy {
+    use crate::NavDate;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::convert::TryInto;
+    use std::fmt::Display;
+
+    /// Serializes `date` as `DD-MMM-YYYY`
+    pub fn serialize<T, S>(date: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Copy + TryInto<NavDate>,
+        T::Error: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(&super::nav_date::<_, S::Error>(date)?.feed_format())
+    }
+
+    /// Deserializes a `DD-MMM-YYYY` string, month name in any case
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<NavDate>,
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        NavDate::parse_feed(&raw)
+            .map(T::from)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Date as signed number of days since 1970-01-01
+pub mod date_epoch_days {
+    use crate::NavDate;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::convert::TryInto;
+    use std::fmt::Display;
+
+    /// Serializes `date` as days since the Unix epoch
+    pub fn serialize<T, S>(date: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Copy + TryInto<NavDate>,
+        T::Error: Display,
+        S: Serializer,
+    {
+        serializer.serialize_i64(super::nav_date::<_, S::Error>(date)?.epoch_days())
+    }
+
+    /// Deserializes days since the Unix epoch
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<NavDate>,
+        D: Deserializer<'de>,
+    {
+        let days = i64::deserialize(deserializer)?;
+        NavDate::from_epoch_days(days)
+            .map(T::from)
+            .ok_or_else(|| de::Error::custom(format!("{} days is out of date range", days)))
+    }
+}
+
+/// Alias of [`date_epoch_days`](date_epoch_days/index.html)
+pub use self::date_epoch_days as epoch_days;
+
+/// Date as Unix timestamp in seconds of midnight in Indian Standard Time
+///
+/// NAV dates are Indian business days: `2024-07-01` serializes as `1719772200`,
+/// 2024-06-30T18:30:00Z, see
+/// [`NavDate::unix_midnight_ist`](../struct.NavDate.html#method.unix_midnight_ist).
+/// Any timestamp deserializes to its calendar date in IST.
+pub mod date_unix_midnight_ist {
+    use crate::NavDate;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::convert::TryInto;
+    use std::fmt::Display;
+
+    /// Serializes `date` as the Unix timestamp of its midnight in IST
+    pub fn serialize<T, S>(date: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Copy + TryInto<NavDate>,
+        T::Error: Display,
+        S: Serializer,
+    {
+        serializer.serialize_i64(super::nav_date::<_, S::Error>(date)?.unix_midnight_ist())
+    }
+
+    /// Deserializes a Unix timestamp to its date in IST
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<NavDate>,
+        D: Deserializer<'de>,
+    {
+        let seconds = i64::deserialize(deserializer)?;
+        NavDate::from_unix_ist(seconds)
+            .map(T::from)
+            .ok_or_else(|| de::Error::custom(format!("timestamp {} is out of date range", seconds)))
+    }
+}
+
+/// Date representation used by a view
+trait DateFormat {
+    fn serialize<S: Serializer>(date: &NavDate, serializer: S) -> Result<S::Ok, S::Error>;
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NavDate, D::Error>;
+}
+
+macro_rules! date_format {
+    ($format:ident, $module:ident) => {
+        struct $format;
+
+        impl DateFormat for $format {
+            fn serialize<S: Serializer>(date: &NavDate, serializer: S) -> Result<S::Ok, S::Error> {
+                $module::serialize(date, serializer)
+            }
+
+            fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<NavDate, D::Error> {
+                $module::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+date_format!(Iso, date_iso);
+date_format!(Feed, date_ddmmmyyyy);
+date_format!(EpochDays, date_epoch_days);
+
+struct Date<F>(NavDate, PhantomData<F>);
+
+impl<F: DateFormat> Serialize for Date<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        F::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, F: DateFormat> Deserialize<'de> for Date<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        F::deserialize(deserializer).map(|date| Date(date, PhantomData))
+    }
+}
+
+/// Serialized form of a borrowed `NavRecord`, fields in `NavRecord` order
+struct RecordRef<'a, F: DateFormat> {
+    code: SchemeCode,
+    isin: &'a Option<RawIsin>,
+    isin_dr: &'a Option<RawIsin>,
+    name: &'a str,
+    nav: f64,
+    date: Date<F>,
+    amc: &'a str,
+    category: &'a str,
+    scheme: &'a Option<String>,
+    maturity: Option<FundMaturity>,
+    plan: FundPlan,
+    plan_source: PlanSource,
+    option: &'a Option<String>,
+    fof: Option<FofKind>,
+    repurchase_price: Option<f64>,
+    sale_price: Option<f64>,
+    raw: &'a Option<RawFields>,
+    source_line: Option<u32>,
+    segment: Option<u32>,
+    recovered: bool,
+}
+
+impl<'a, F: DateFormat> RecordRef<'a, F> {
+    fn new(record: &'a NavRecord) -> Self {
+        RecordRef {
+            code: record.code,
+            isin: &record.isin,
+            isin_dr: &record.isin_dr,
+            name: &record.name,
+            nav: record.nav,
+            date: Date(record.date, PhantomData),
+            amc: &record.amc,
+            category: &record.category,
+            scheme: &record.scheme,
+            maturity: record.maturity,
+            plan: record.plan,
+            plan_source: record.plan_source,
+            option: &record.option,
+            fof: record.fof,
+            repurchase_price: record.repurchase_price,
+            sale_price: record.sale_price,
+            raw: &record.raw,
+            source_line: record.source_line,
+            segment: record.segment,
+            recovered: record.recovered,
+        }
+    }
+}
+
+/// Written by hand to leave out a `name_token` plan source, `None` FoF kind, prices, `raw`,
+/// `source_line`, `segment` and a false `recovered`, in human readable formats only: formats
+/// like bincode read fields by position and need them present.
+impl<'a, F: DateFormat> Serialize for RecordRef<'a, F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        let mut state = serializer.serialize_struct("NavRecord", 20)?;
+        state.serialize_field("code", &self.code)?;
+        state.serialize_field("isin", self.isin)?;
+        state.serialize_field("isin_dr", self.isin_dr)?;
+        state.serialize_field("name", self.name)?;
+        state.serialize_field("nav", &self.nav)?;
+        state.serialize_field("date", &self.date)?;
+        state.serialize_field("amc", self.amc)?;
+        state.serialize_field("category", self.category)?;
+        state.serialize_field("scheme", self.scheme)?;
+        state.serialize_field("maturity", &self.maturity)?;
+        state.serialize_field("plan", &self.plan)?;
+        if self.plan_source == PlanSource::NameToken && human_readable {
+            state.skip_field("plan_source")?;
+        } else {
+            state.serialize_field("plan_source", &self.plan_source)?;
+        }
+        state.serialize_field("option", self.option)?;
+        if self.fof.is_none() && human_readable {
+            state.skip_field("fof")?;
+        } else {
+            state.serialize_field("fof", &self.fof)?;
+        }
+        for &(key, price) in &[
+            ("repurchase_price", self.repurchase_price),
+            ("sale_price", self.sale_price),
+        ] {
+            if price.is_none() && human_readable {
+                state.skip_field(key)?;
+            } else {
+                state.serialize_field(key, &price)?;
+            }
+        }
+        if self.raw.is_none() && human_readable {
+            state.skip_field("raw")?;
+        } else {
+            state.serialize_field("raw", self.raw)?;
+        }
+        if self.source_line.is_none() && human_readable {
+            state.skip_field("source_line")?;
+        } else {
+            state.serialize_field("source_line", &self.source_line)?;
+        }
+        if self.segment.is_none() && human_readable {
+            state.skip_field("segment")?;
+        } else {
+            state.serialize_field("segment", &self.segment)?;
+        }
+        if !self.recovered && human_readable {
+            state.skip_field("recovered")?;
+        } else {
+            state.serialize_field("recovered", &self.recovered)?;
+        }
+        state.end()
+    }
+}
+
+impl Serialize for NavRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RecordRef::<Iso>::new(self).serialize(serializer)
+    }
+}
+
+/// Deserialized counterpart of `RecordRef`
+#[derive(Deserialize)]
+#[serde(rename = "NavRecord", bound = "")]
+struct Record<F: DateFormat> {
+    code: SchemeCode,
+    isin: Option<RawIsin>,
+    isin_dr: Option<RawIsin>,
+    name: String,
+    nav: f64,
+    date: Date<F>,
+    amc: String,
+    category: String,
+    scheme: Option<String>,
+    maturity: Option<FundMaturity>,
+    plan: FundPlan,
+    #[serde(default, deserialize_with = "present")]
+    plan_source: Option<PlanSource>,
+    option: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    fof: Option<Option<FofKind>>,
+    #[serde(default)]
+    repurchase_price: Option<f64>,
+    #[serde(default)]
+    sale_price: Option<f64>,
+    #[serde(default)]
+    raw: Option<RawFields>,
+    #[serde(default)]
+    source_line: Option<u32>,
+    #[serde(default)]
+    segment: Option<u32>,
+    #[serde(default)]
+    recovered: bool,
+}
+
+/// Value of a field, `None` being left for a missing field
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl<F: DateFormat> From<Record<F>> for NavRecord {
+    fn from(record: Record<F>) -> Self {
+        // Left out when `None`, and by releases before it was added
+        let fof = record.fof.unwrap_or_else(|| {
+            FofKind::of_scheme(record.scheme.as_deref(), &record.category, &record.name)
+        });
+        // Left out for names with a plan, and by releases before it was added
+        let plan_source = record
+            .plan_source
+            .unwrap_or_else(|| PlanSource::of_name(&record.name));
+        NavRecord {
+            code: record.code,
+            isin: record.isin,
+            isin_dr: record.isin_dr,
+            name: record.name,
+            nav: record.nav,
+            date: record.date.0,
+            amc: record.amc,
+            category: record.category,
+            scheme: record.scheme,
+            maturity: record.maturity,
+            plan: record.plan,
+            plan_source,
+            option: record.option,
+            fof,
+            repurchase_price: record.repurchase_price,
+            sale_price: record.sale_price,
+            raw: record.raw,
+            source_line: record.source_line,
+            segment: record.segment,
+            recovered: record.recovered,
+        }
+    }
+}
+
+macro_rules! record_view {
+    ($(#[$attr:meta])* $view:ident, $format:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $view(pub NavRecord);
+
+        impl From<NavRecord> for $view {
+            fn from(record: NavRecord) -> Self {
+                $view(record)
+            }
+        }
+
+        impl From<$view> for NavRecord {
+            fn from(view: $view) -> Self {
+                view.0
+            }
+        }
+
+        impl Serialize for $view {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                RecordRef::<$format>::new(&self.0).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $view {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Record::<$format>::deserialize(deserializer).map(|record| $view(record.into()))
+            }
+        }
+    };
+}
+
+record_view!(
+    /// `NavRecord` serializing `date` as ISO 8601 string (`2024-06-21`), same as `NavRecord`
+    NavRecordIsoDate,
+    Iso
+);
+record_view!(
+    /// `NavRecord` serializing `date` in feed format (`21-Jun-2024`)
+    NavRecordFeedDate,
+    Feed
+);
+record_view!(
+    /// `NavRecord` serializing `date` as days since 1970-01-01 (`19895`)
+    NavRecordEpochDays,
+    EpochDays
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_path, fixture_records};
+    use serde_json::{json, Value};
+
+    fn round_trip<V>(expected: Value) -> Vec<NavRecord>
+    where
+        V: Serialize + for<'de> Deserialize<'de> + From<NavRecord> + Into<NavRecord>,
+    {
+        let records = fixture_records("NAVOpen.txt");
+        let views: Vec<V> = records.iter().cloned().map(V::from).collect();
+        let json = serde_json::to_value(&views).unwrap();
+        assert_eq!(json[0]["date"], expected);
+        let back: Vec<V> = serde_json::from_value(json).unwrap();
+        let back: Vec<NavRecord> = back.into_iter().map(Into::into).collect();
+        assert_eq!(back, records);
+        back
+    }
+
+    #[test]
+    fn record_date_is_iso() {
+        let records = fixture_records("NAVOpen.txt");
+        let json = serde_json::to_value(&records[0]).unwrap();
+        assert_eq!(json["date"], json!("2024-06-21"));
+        let back: NavRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(back, records[0]);
+    }
+
+    #[test]
+    fn views_round_trip() {
+        round_trip::<NavRecordIsoDate>(json!("2024-06-21"));
+        round_trip::<NavRecordFeedDate>(json!("21-Jun-2024"));
+        round_trip::<NavRecordEpochDays>(json!(19_895));
+    }
+
+    #[test]
+    fn view_matches_record_shape() {
+        let record = fixture_records("NAVOpen.txt").remove(0);
+        let mut plain = serde_json::to_value(&record).unwrap();
+        let view = serde_json::to_value(NavRecordFeedDate(record)).unwrap();
+        plain["date"] = json!("21-Jun-2024");
+        assert_eq!(view, plain);
+    }
+
+    #[test]
+    fn json_shape_is_stable() {
+        let record = fixture_records("NAVOpen.txt").remove(0);
+        assert_eq!(
+            serde_json::to_value(&record).unwrap(),
+            json!({
+                "code": 120_465,
+                "isin": "INF846K01AB4",
+                "isin_dr": null,
+                "name": "Axis Bluechip Fund - Direct Plan - Growth",
+                "nav": 61.43,
+                "date": "2024-06-21",
+                "amc": "Axis Mutual Fund",
+                "category": "Large Cap Fund",
+                "scheme": "Equity Scheme",
+                "maturity": "open_ended",
+                "plan": "direct",
+                "option": null,
+                "source_line": 7,
+            })
+        );
+    }
+
+    #[test]
+    fn json_shape_of_optional_fields() {
+        let mut record = fixture_records("NAVOpen.txt").remove(0);
+        record.plan_source = PlanSource::Resolver;
+        record.fof = Some(FofKind::Overseas);
+        record.repurchase_price = Some(60.82);
+        record.sale_price = Some(62.05);
+        record.source_line = None;
+        record.segment = Some(2);
+        record.recovered = true;
+        assert_eq!(
+            serde_json::to_value(&record).unwrap(),
+            json!({
+                "code": 120_465,
+                "isin": "INF846K01AB4",
+                "isin_dr": null,
+                "name": "Axis Bluechip Fund - Direct Plan - Growth",
+                "nav": 61.43,
+                "date": "2024-06-21",
+                "amc": "Axis Mutual Fund",
+                "category": "Large Cap Fund",
+                "scheme": "Equity Scheme",
+                "maturity": "open_ended",
+                "plan": "direct",
+                "plan_source": "resolver",
+                "option": null,
+                "fof": "overseas",
+                "repurchase_price": 60.82,
+                "sale_price": 62.05,
+                "segment": 2,
+                "recovered": true,
+            })
+        );
+    }
+
+    #[test]
+    fn enums_accept_old_names() {
+        let plans: Vec<FundPlan> =
+            serde_json::from_str(r#"["Direct", "direct", "Regular"]"#).unwrap();
+        assert_eq!(
+            plans,
+            [FundPlan::Direct, FundPlan::Direct, FundPlan::Regular]
+        );
+        let maturity: FundMaturity = serde_json::from_str(r#""CloseEnded""#).unwrap();
+        assert_eq!(maturity, FundMaturity::CloseEnded);
+    }
+
+    #[test]
+    fn fixture_round_trips_json() {
+        let records = fixture_records("NAVOpen.txt");
+        let json = serde_json::to_string(&records).unwrap();
+        let back: Vec<NavRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, records);
+    }
+
+    #[test]
+    fn fixture_round_trips_bincode() {
+        let records = fixture_records("NAVOpen.txt");
+        let bytes = bincode::serialize(&records).unwrap();
+        let back: Vec<NavRecord> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(back, records);
+
+        let views: Vec<NavRecordEpochDays> = records.iter().cloned().map(From::from).collect();
+        let bytes = bincode::serialize(&views).unwrap();
+        let back: Vec<NavRecordEpochDays> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(back, views);
+    }
+
+    #[test]
+    fn raw_fields_only_when_captured() {
+        let path = fixture_path("NAVOpen.txt");
+        let records: Vec<NavRecord> = crate::nav_from_file(&path)
+            .unwrap()
+            .capture_raw(true)
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        let json = serde_json::to_value(&records[0]).unwrap();
+        assert_eq!(json["raw"]["nav"], json!("61.4300"));
+        let back: Vec<NavRecord> =
+            serde_json::from_str(&serde_json::to_string(&records).unwrap()).unwrap();
+        assert_eq!(back, records);
+        let back: Vec<NavRecord> =
+            bincode::deserialize(&bincode::serialize(&records).unwrap()).unwrap();
+        assert_eq!(back, records);
+
+        let plain = serde_json::to_value(&fixture_records("NAVOpen.txt")[0]).unwrap();
+        assert!(plain.get("raw").is_none());
+    }
+
+    #[test]
+    fn integer_dates_across_month_boundary() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Mirror {
+            #[serde(with = "crate::serde::epoch_days")]
+            days: NavDate,
+            #[serde(with = "crate::serde::date_unix_midnight_ist")]
+            timestamp: NavDate,
+        }
+
+        let mirror = |date: NavDate| Mirror {
+            days: date,
+            timestamp: date,
+        };
+        let june_30 = NavDate::from_ymd(2024, 6, 30).unwrap();
+        let july_1 = june_30.succ().unwrap();
+        assert_eq!(
+            serde_json::to_value(mirror(june_30)).unwrap(),
+            json!({"days": 19_904, "timestamp": 1_719_685_800})
+        );
+        let json = json!({"days": 19_905, "timestamp": 1_719_772_200});
+        assert_eq!(serde_json::to_value(mirror(july_1)).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<Mirror>(json).unwrap(),
+            mirror(july_1)
+        );
+
+        // Any time of the day in IST, UTC midnight included, is the same date
+        let utc_midnight = json!({"days": 19_905, "timestamp": 1_719_792_000});
+        assert_eq!(
+            serde_json::from_value::<Mirror>(utc_midnight).unwrap(),
+            mirror(july_1)
+        );
+        let before = json!({"days": 19_904, "timestamp": 1_719_772_199});
+        assert_eq!(
+            serde_json::from_value::<Mirror>(before).unwrap(),
+            mirror(june_30)
+        );
+
+        let record = fixture_records("NAVOpen.txt").remove(0);
+        assert_eq!(record.date_epoch_days(), 19_895);
+        assert_eq!(record.date_unix_midnight_ist(), 19_895 * 86_400 - 19_800);
+    }
+
+    #[test]
+    fn legacy_prices_only_when_present() {
+        let records = fixture_records("NAVLegacy.txt");
+        let json = serde_json::to_value(&records[0]).unwrap();
+        assert_eq!(json["repurchase_price"], json!(61.43));
+        assert!(serde_json::to_value(&records[1])
+            .unwrap()
+            .get("sale_price")
+            .is_none());
+        let back: Vec<NavRecord> =
+            serde_json::from_str(&serde_json::to_string(&records).unwrap()).unwrap();
+        assert_eq!(back, records);
+        let back: Vec<NavRecord> =
+            bincode::deserialize(&bincode::serialize(&records).unwrap()).unwrap();
+        assert_eq!(back, records);
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn schema_matches_serialization() {
+        use std::collections::BTreeSet;
+
+        fn validator<T: schemars::JsonSchema>() -> jsonschema::JSONSchema {
+            let schema = serde_json::to_value(schemars::schema_for!(T)).unwrap();
+            jsonschema::JSONSchema::compile(&schema).unwrap()
+        }
+        let keys = |json: &Value| -> BTreeSet<String> {
+            json.as_object().unwrap().keys().cloned().collect()
+        };
+
+        let path = fixture_path("NAVLegacy.txt");
+        let mut records: Vec<NavRecord> = crate::nav_from_file(&path)
+            .unwrap()
+            .capture_raw(true)
+            .stamp_segments(true)
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        records.extend(fixture_records("NAVOpen.txt"));
+        let minimal = NavRecord::parse_line(
+            "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024",
+        )
+        .unwrap();
+        records.push(minimal.clone());
+        let shifted =
+            "Axis Mutual Fund\n120465;INF846K01AB4;Axis Bluechip Fund - Growth;61.43;21-Jun-2024\n";
+        records.extend(
+            crate::nav_from_str(shifted)
+                .recovery(crate::RecoveryPolicy::Lenient)
+                .map(Result::unwrap),
+        );
+        let records = serde_json::to_value(&records).unwrap();
+
+        let schema = validator::<NavRecord>();
+        for record in records.as_array().unwrap() {
+            assert!(schema.is_valid(record), "{}", record);
+        }
+        let mut wrong = records[0].clone();
+        wrong["plan"] = json!("Direct Plan");
+        assert!(!schema.is_valid(&wrong));
+        wrong["plan"] = json!("direct");
+        wrong["date"] = json!(19_895);
+        assert!(!schema.is_valid(&wrong));
+
+        // Same fields; those always present aren't `Option`s, which are all optional
+        let schema = serde_json::to_value(schemars::schema_for!(NavRecord)).unwrap();
+        let present = records.as_array().unwrap().iter().flat_map(&keys).collect();
+        assert_eq!(keys(&schema["properties"]), present);
+        let required: BTreeSet<String> =
+            serde_json::from_value(schema["required"].clone()).unwrap();
+        assert!(required.is_subset(&keys(&serde_json::to_value(&minimal).unwrap())));
+        assert_eq!(
+            required,
+            ["amc", "category", "code", "date", "name", "nav", "plan"]
+                .iter()
+                .map(|key| key.to_string())
+                .collect()
+        );
+
+        let summary = crate::summarize(crate::nav_from_file(&path).unwrap());
+        let summary = serde_json::to_value(&summary).unwrap();
+        assert!(validator::<crate::FeedSummary>().is_valid(&summary));
+    }
+
+    #[test]
+    fn epoch_days_out_of_range() {
+        let result: Result<NavRecordEpochDays, _> = serde_json::from_value(json!({
+            "code": 1, "isin": null, "isin_dr": null, "name": "", "nav": 1.0,
+            "date": i64::MAX, "amc": "", "category": "", "scheme": null,
+            "maturity": null, "plan": "direct", "option": null,
+        }));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,525 @@
+//! Parsed feeds kept with their origin
+
+#[cfg(feature = "fetch")]
+use crate::fetch::{FetchMeta, ResponseMeta};
+#[cfg(feature = "bincode")]
+use crate::stored;
+use crate::{
+    category_stats, changes_between, diff_navs, group_by_amc, group_by_category, nav_from_file,
+    order, CategoryStats, ChangeSet, CompactionStats, Error, FeedSummary, Groups,
+    LatestNavCollector, NavDate, NavDiff, NavIndex, NavRecord, NavTolerance, Provenance,
+    RecordSource, Result,
+};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+#[cfg(feature = "bincode")]
+use std::fs::File;
+#[cfg(feature = "bincode")]
+use std::io::{BufWriter, Write};
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Where the records of a [`NavSnapshot`](struct.NavSnapshot.html) were read from
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub enum SnapshotSource {
+    /// Downloaded from `url`
+    Url {
+        /// URL requested
+        url: String,
+        /// HTTP metadata of the response
+        #[cfg(feature = "fetch")]
+        #[cfg_attr(feature = "serde", serde(default))]
+        meta: FetchMeta,
+        /// What the download observed, see
+        /// [`NavRecordIterator::response_meta`](struct.NavRecordIterator.html#method.response_meta);
+        /// not kept by snapshot files
+        #[cfg(feature = "fetch")]
+        #[cfg_attr(feature = "serde", serde(default))]
+        response: Option<ResponseMeta>,
+    },
+    /// Read from a local file
+    File(PathBuf),
+    /// Merged from the feeds downloaded from `urls`, see
+    /// [`fetch_all_async`](fn.fetch_all_async.html)
+    Urls(Vec<String>),
+    /// Collected from records at hand, see `FromIterator`
+    Memory,
+}
+
+/// Records of a feed with where and when they were obtained
+///
+/// Owns the records, which it dereferences to, and hosts the grouping, indexing and
+/// diffing helpers. With the `serde` feature a snapshot serializes whole, records, origin
+/// and statistics, making a self-describing archive; parse errors are not serialized.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let snapshot = amfi::NavSnapshot::from_file("fixtures/NAVOpen.txt")?;
+/// assert_eq!(snapshot.len(), 30);
+/// assert_eq!(snapshot.feed_date, amfi::NavDate::from_ymd(2024, 6, 21));
+/// for (amc, records) in snapshot.group_by_amc() {
+///     println!("{}: {} schemes", amc, records.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NavSnapshot {
+    /// Parsed records, in feed order
+    pub records: Vec<NavRecord>,
+    /// When the feed was downloaded, or last modified for files
+    pub fetched_at: DateTime<Utc>,
+    /// Where the feed was read from
+    pub source: SnapshotSource,
+    /// NAV date of most records, see
+    /// [`FeedSummary::feed_date`](struct.FeedSummary.html#method.feed_date)
+    pub feed_date: Option<NavDate>,
+    /// Counts of records and errors
+    pub stats: FeedSummary,
+    /// Parse errors, in feed order
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    pub errors: Vec<Error>,
+    /// Source of each record, for snapshots merged from several, see
+    /// [`source_of`](#method.source_of); not kept by snapshot files
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub provenance: Option<Provenance>,
+}
+
+impl NavSnapshot {
+    /// Collects parse results read from `source` at `fetched_at`
+    pub fn from_results<I>(results: I, source: SnapshotSource, fetched_at: DateTime<Utc>) -> Self
+    where
+        I: IntoIterator<Item = Result<NavRecord>>,
+    {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        for result in results {
+            match result {
+                Ok(record) => records.push(record),
+                Err(error) => errors.push(error),
+            }
+        }
+        let mut stats = FeedSummary::default();
+        for record in &records {
+            stats.add(record);
+        }
+        stats.errors = errors.len();
+        NavSnapshot {
+            feed_date: stats.feed_date(),
+            records,
+            fetched_at,
+            source,
+            stats,
+            errors,
+            provenance: None,
+        }
+    }
+
+    /// Parses the feed in the file at `path`, dated by its modification time
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let modified = path
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or_else(|_| SystemTime::now());
+        Ok(Self::from_results(
+            nav_from_file(path)?,
+            SnapshotSource::File(path.to_path_buf()),
+            modified.into(),
+        ))
+    }
+
+    /// Downloads and parses the feed of the [AMFI](https://www.amfiindia.com) portal
+    #[cfg(feature = "blocking")]
+    pub fn fetch() -> Result<Self> {
+        Self::fetch_from(crate::BASE_URL)
+    }
+
+    /// Downloads and parses the feed at `url`
+    #[cfg(feature = "blocking")]
+    pub fn fetch_from<T: AsRef<str>>(url: T) -> Result<Self> {
+        let url = url.as_ref();
+        let fetched_at = SystemTime::now().into();
+        let results = crate::fetch::get_records(url, None, true)?;
+        let response = results.response_meta().cloned();
+        Ok(Self::from_results(
+            results,
+            SnapshotSource::Url {
+                url: url.to_string(),
+                meta: response.as_ref().map(FetchMeta::from).unwrap_or_default(),
+                response,
+            },
+            fetched_at,
+        ))
+    }
+
+    /// HTTP metadata of a downloaded snapshot
+    #[cfg(feature = "fetch")]
+    pub fn meta(&self) -> Option<&FetchMeta> {
+        match self.source {
+            SnapshotSource::Url { ref meta, .. } => Some(meta),
+            _ => None,
+        }
+    }
+
+    /// What the download of a downloaded snapshot observed, `None` for others and those
+    /// loaded from snapshot files
+    #[cfg(feature = "fetch")]
+    pub fn response_meta(&self) -> Option<&ResponseMeta> {
+        match self.source {
+            SnapshotSource::Url { ref response, .. } => response.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Source of the record at `index`, if the snapshot has a
+    /// [`provenance`](#structfield.provenance) covering it
+    pub fn source_of(&self, index: usize) -> Option<&RecordSource> {
+        let provenance = self.provenance.as_ref()?;
+        provenance.registry.get(*provenance.sources.get(index)?)
+    }
+
+    /// Records, see [`Deref`](#impl-Deref-for-NavSnapshot)
+    pub fn records(&self) -> &[NavRecord] {
+        &self.records
+    }
+
+    /// Records with neither ISIN valid, in feed order, see
+    /// [`NavRecord::has_isin`](struct.NavRecord.html#method.has_isin)
+    pub fn without_isin(&self) -> Vec<&NavRecord> {
+        self.records.iter().filter(|r| !r.has_isin()).collect()
+    }
+
+    /// Whether the records of each section are sorted by scheme code, see
+    /// [`NavRecordIterator::assert_sorted_within_sections`](struct.NavRecordIterator.html#method.assert_sorted_within_sections)
+    ///
+    /// Records of the same AMC, category, scheme and maturity in a row are of a section.
+    pub fn is_sorted_by_code(&self) -> bool {
+        order::is_sorted_within_sections(&self.records)
+    }
+
+    /// Records with those of each section sorted by scheme code, borrowed if they are already
+    /// and sorted in a copy otherwise
+    ///
+    /// Sections stay in feed order, see [`is_sorted_by_code`](#method.is_sorted_by_code).
+    pub fn sorted_by_code(&self) -> Cow<'_, [NavRecord]> {
+        if self.is_sorted_by_code() {
+            Cow::Borrowed(&self.records)
+        } else {
+            let mut records = self.records.clone();
+            order::sort_within_sections(&mut records);
+            Cow::Owned(records)
+        }
+    }
+
+    /// Keeps only the latest record of each scheme, sorted by scheme code, see
+    /// [`LatestNavCollector`](struct.LatestNavCollector.html)
+    ///
+    /// For snapshots of several days, e.g. a month of daily feeds replayed into one, this
+    /// leaves the latest known NAV of every scheme. Statistics are recounted and parse errors
+    /// kept; the [`provenance`](#structfield.provenance), whose sources no longer line up
+    /// with the records, is dropped.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut results = Vec::new();
+    /// for path in &["fixtures/NAVOpen.txt", "fixtures/NAVHistory.txt"] {
+    ///     results.extend(amfi::nav_from_file(path)?);
+    /// }
+    /// let mut snapshot: amfi::NavSnapshot = results.into_iter().collect();
+    /// let stats = snapshot.compact_latest();
+    /// assert!(stats.superseded > 0);
+    /// assert_eq!(snapshot.len(), stats.records - stats.superseded);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compact_latest(&mut self) -> CompactionStats {
+        let mut latest = LatestNavCollector::new();
+        latest.extend(self.records.drain(..));
+        let stats = latest.stats();
+        let results = latest
+            .into_records()
+            .into_iter()
+            .map(Ok)
+            .chain(self.errors.drain(..).map(Err));
+        let source = std::mem::replace(&mut self.source, SnapshotSource::Memory);
+        *self = NavSnapshot::from_results(results, source, self.fetched_at);
+        stats
+    }
+
+    /// Records by AMC, see [`group_by_amc`](fn.group_by_amc.html)
+    pub fn group_by_amc(&self) -> Groups {
+        group_by_amc(self.records.iter().cloned().map(Ok)).0
+    }
+
+    /// Records by category, see [`group_by_category`](fn.group_by_category.html)
+    pub fn group_by_category(&self) -> Groups {
+        group_by_category(self.records.iter().cloned().map(Ok)).0
+    }
+
+    /// NAV statistics by category, see [`category_stats`](fn.category_stats.html)
+    pub fn category_stats(&self) -> BTreeMap<String, CategoryStats> {
+        category_stats(&self.records)
+    }
+
+    /// Index of the records by scheme code and ISIN
+    pub fn index(&self) -> NavIndex {
+        self.records.iter().cloned().collect()
+    }
+
+    /// Changes from this snapshot to `newer`, see [`diff_navs`](fn.diff_navs.html)
+    pub fn diff(&self, newer: &NavSnapshot) -> NavDiff {
+        diff_navs(&self.index(), &newer.index())
+    }
+
+    /// Writes syncing a table of the `previous` snapshot's records to this one's, NAVs and
+    /// prices compared exactly, see [`changes_between`](fn.changes_between.html)
+    pub fn changes_since(&self, previous: &NavSnapshot) -> ChangeSet {
+        self.changes_since_with(previous, NavTolerance::Exact)
+    }
+
+    /// Writes syncing a table of the `previous` snapshot's records to this one's, NAVs and
+    /// prices compared with `tolerance`
+    pub fn changes_since_with(&self, previous: &NavSnapshot, tolerance: NavTolerance) -> ChangeSet {
+        changes_between(&previous.records, &self.records, tolerance)
+    }
+
+    /// Writes the snapshot to `path` in a compact binary format, see [`load`](#method.load)
+    ///
+    /// The file starts with a magic header and a format version. Parse errors are not saved.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let snapshot = amfi::NavSnapshot::from_file("fixtures/NAVOpen.txt")?;
+    /// let path = std::env::temp_dir().join("amfi-doc.snapshot");
+    /// snapshot.save(&path)?;
+    /// let loaded = amfi::NavSnapshot::load(&path)?;
+    /// assert_eq!(loaded.records, snapshot.records);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "bincode")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        stored::write(self, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [`save`](#method.save)
+    ///
+    /// Files written by any earlier release keep loading, whichever features it was built
+    /// with: fields the records or statistics gained since are left at their defaults.
+    /// Fails with [`Error::InvalidSnapshot`](enum.Error.html#variant.InvalidSnapshot) for
+    /// files that aren't snapshots or were written by a newer release in a format this one
+    /// doesn't know.
+    #[cfg(feature = "bincode")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        stored::read(&std::fs::read(path)?)
+    }
+}
+
+/// Collects parse results held in memory, dated now
+impl FromIterator<Result<NavRecord>> for NavSnapshot {
+    fn from_iter<I: IntoIterator<Item = Result<NavRecord>>>(results: I) -> Self {
+        Self::from_results(results, SnapshotSource::Memory, SystemTime::now().into())
+    }
+}
+
+impl Deref for NavSnapshot {
+    type Target = [NavRecord];
+
+    fn deref(&self) -> &[NavRecord] {
+        &self.records
+    }
+}
+
+impl IntoIterator for NavSnapshot {
+    type Item = NavRecord;
+    type IntoIter = std::vec::IntoIter<NavRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a NavSnapshot {
+    type Item = &'a NavRecord;
+    type IntoIter = std::slice::Iter<'a, NavRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+
+    #[test]
+    fn fixture_snapshot() {
+        let snapshot = NavSnapshot::from_file(FIXTURE).unwrap();
+        assert_eq!(snapshot.len(), 30);
+        assert_eq!(snapshot.stats.records, 30);
+        assert_eq!(snapshot.feed_date, NavDate::from_ymd(2024, 6, 21));
+        assert_eq!(
+            snapshot.source,
+            SnapshotSource::File(PathBuf::from(FIXTURE))
+        );
+        assert_eq!(snapshot[0].code, 120_465);
+        assert_eq!((&snapshot).into_iter().count(), 30);
+        assert_eq!(snapshot.group_by_amc().len(), 6);
+        assert_eq!(snapshot.index().len(), 30);
+        let without_isin = snapshot.without_isin();
+        assert_eq!(without_isin.len(), 1);
+        assert_eq!(without_isin[0].code, 149_003);
+        assert_eq!(snapshot.stats.without_isin, 1);
+        let diff = snapshot.diff(&snapshot);
+        assert_eq!(diff.deltas.len(), 30);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n";
+        let snapshot: NavSnapshot = crate::nav_from_str(feed).collect();
+        assert_eq!(snapshot.source, SnapshotSource::Memory);
+        assert_eq!(snapshot.errors.len(), 1);
+        assert_eq!(snapshot.stats.errors, 1);
+        let records: Vec<NavRecord> = snapshot.into_iter().collect();
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn sorted_lazily() {
+        let snapshot = NavSnapshot::from_file(FIXTURE).unwrap();
+        assert!(!snapshot.is_sorted_by_code());
+        let sorted = snapshot.sorted_by_code();
+        assert!(matches!(sorted, Cow::Owned(_)));
+        assert_eq!(sorted.len(), snapshot.len());
+        assert_eq!(sorted[0].code, 112_277);
+        assert_eq!(sorted[2].code, 120_466);
+
+        let sorted = NavSnapshot::from_results(
+            sorted.iter().cloned().map(Ok),
+            SnapshotSource::Memory,
+            snapshot.fetched_at,
+        );
+        assert!(sorted.is_sorted_by_code());
+        assert!(matches!(sorted.sorted_by_code(), Cow::Borrowed(_)));
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn binary_round_trip_without_amc() {
+        let fragment =
+            "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n";
+        let snapshot: NavSnapshot = crate::nav_from_str(fragment)
+            .headerless(crate::HeaderlessPolicy::Keep)
+            .collect();
+        assert_eq!(snapshot.stats.without_amc, 1);
+        let mut bytes = Vec::new();
+        stored::write(&snapshot, &mut bytes).unwrap();
+        assert_eq!(stored::read(&bytes).unwrap().stats, snapshot.stats);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn binary_round_trip() {
+        let mut snapshot = NavSnapshot::from_file(FIXTURE).unwrap();
+        snapshot.records[1].isin = Some(crate::RawIsin::new("INF846K01AB5"));
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("amfi-{}.snapshot", std::process::id()));
+        snapshot.save(&path).unwrap();
+        let loaded = NavSnapshot::load(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Field for field, `source_line` included
+        assert_eq!(loaded.records, snapshot.records);
+        assert!(!loaded.records[1].isin.as_ref().unwrap().is_valid());
+        assert!(loaded.records[0].isin.as_ref().unwrap().is_valid());
+        for (loaded, record) in loaded.records.iter().zip(&snapshot.records) {
+            assert_eq!(loaded.source_line, record.source_line);
+        }
+        assert_eq!(loaded.fetched_at, snapshot.fetched_at);
+        assert_eq!(loaded.source, snapshot.source);
+        assert_eq!(loaded.feed_date, snapshot.feed_date);
+        assert_eq!(loaded.stats, snapshot.stats);
+        assert!(loaded.errors.is_empty());
+
+        let reason = |header: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[..header.len()].copy_from_slice(header);
+            match stored::read(&bytes) {
+                Err(Error::InvalidSnapshot(reason)) => reason,
+                other => panic!("unexpected {:?}", other.map(|snapshot| snapshot.len())),
+            }
+        };
+        assert_eq!(reason(b"Scheme Code"), "not an amfi snapshot");
+        assert_eq!(
+            reason(b"AMFISNAP\x02"),
+            format!(
+                "format version 2 is newer than the supported {}",
+                stored::FORMAT_VERSION
+            )
+        );
+        assert_eq!(
+            reason(b"AMFISNAP\x01\x01"),
+            "format version 1 with flags 0x01"
+        );
+        assert!(matches!(
+            stored::read(b"AMFISNAP"),
+            Err(Error::InvalidSnapshot(_))
+        ));
+
+        // Stored the same with and without the `fetch` feature
+        snapshot.source = SnapshotSource::Url {
+            url: "https://example.com/NAVAll.txt".to_string(),
+            #[cfg(feature = "fetch")]
+            meta: FetchMeta {
+                etag: Some("\"5f3a\"".to_string()),
+                ..FetchMeta::default()
+            },
+            #[cfg(feature = "fetch")]
+            response: None,
+        };
+        let mut bytes = Vec::new();
+        stored::write(&snapshot, &mut bytes).unwrap();
+        assert_eq!(&bytes[8..10], &[stored::FORMAT_VERSION, 0]);
+        assert_eq!(stored::read(&bytes).unwrap().source, snapshot.source);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_whole() {
+        let snapshot = NavSnapshot::from_file(FIXTURE).unwrap();
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["source"]["file"], FIXTURE);
+        assert_eq!(json["feed_date"], "2024-06-21");
+        assert_eq!(json["stats"]["records"], 30);
+        assert!(json.get("errors").is_none());
+
+        let back: NavSnapshot = serde_json::from_value(json).unwrap();
+        assert_eq!(back.records, snapshot.records);
+        assert_eq!(back.fetched_at, snapshot.fetched_at);
+        assert_eq!(back.source, snapshot.source);
+        assert_eq!(back.stats, snapshot.stats);
+    }
+}
@@ -0,0 +1,61 @@
+//! Reader of a feed behind the buffer of
+//! [`NavRecordIterator`](struct.NavRecordIterator.html), with bytes read ahead of it
+
+use std::io::{self, Cursor, Read};
+
+/// Bytes read ahead of the `inner` reader, then the rest of it
+pub(crate) struct Source<T> {
+    ahead: Cursor<Vec<u8>>,
+    inner: T,
+}
+
+impl<T> Source<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Source {
+            ahead: Cursor::new(Vec::new()),
+            inner,
+        }
+    }
+
+    pub(crate) fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Puts `bytes`, read from this source before the bytes still ahead, back in front of them
+    pub(crate) fn unread(&mut self, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            self.ahead = Cursor::new([bytes, self.rest()].concat());
+        }
+    }
+
+    /// Appends `bytes`, read from the inner reader, to the bytes ahead
+    pub(crate) fn read_ahead(&mut self, bytes: Vec<u8>) {
+        self.ahead = Cursor::new(match self.rest() {
+            [] => bytes,
+            rest => [rest, &bytes].concat(),
+        });
+    }
+
+    /// Bytes ahead not read yet
+    fn rest(&self) -> &[u8] {
+        &self.ahead.get_ref()[self.ahead.position() as usize..]
+    }
+}
+
+impl<T: Read> Read for Source<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.ahead.get_ref().is_empty() {
+            let read = self.ahead.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            // Drained, e.g. a whole file: let it go
+            self.ahead = Cursor::new(Vec::new());
+        }
+        self.inner.read(buf)
+    }
+}
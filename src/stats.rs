@@ -0,0 +1,143 @@
+//! NAV statistics per category
+
+use crate::group::collapse_whitespace;
+use crate::NavRecord;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// NAV statistics of a category, see [`category_stats`](fn.category_stats.html)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CategoryStats {
+    /// Records with a valid NAV
+    pub count: usize,
+    /// Arithmetic mean NAV
+    pub mean_nav: f64,
+    /// Median NAV, the mean of the middle two for an even count
+    pub median_nav: f64,
+    /// Lowest NAV
+    pub min: f64,
+    /// Highest NAV
+    pub max: f64,
+    /// Fraction of the records that are direct plans, from 0 to 1
+    pub direct_share: f64,
+}
+
+/// Aggregates NAVs per category
+///
+/// Keys are the categories normalized as in
+/// [`group_by_category`](fn.group_by_category.html), so spacing differences don't split
+/// buckets. Records whose NAV is zero or `NaN`, see
+/// [`has_valid_nav`](struct.NavRecord.html#method.has_valid_nav), are left out, and a
+/// category having only such records is absent.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let records: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?.collect::<Result<_, _>>()?;
+/// let stats = amfi::category_stats(&records);
+/// let large_cap = &stats["Large Cap Fund"];
+/// assert!(large_cap.min <= large_cap.median_nav && large_cap.median_nav <= large_cap.max);
+/// # Ok(())
+/// # }
+/// ```
+pub fn category_stats<'a, I>(records: I) -> BTreeMap<String, CategoryStats>
+where
+    I: IntoIterator<Item = &'a NavRecord>,
+{
+    let mut navs: BTreeMap<String, (Vec<f64>, usize)> = BTreeMap::new();
+    for record in records {
+        if !record.has_valid_nav() {
+            continue;
+        }
+        let key = collapse_whitespace(&record.category);
+        let (category, direct) = match navs.get_mut(key.as_ref()) {
+            Some(category) => category,
+            None => navs.entry(key.into_owned()).or_default(),
+        };
+        category.push(record.nav);
+        if record.is_direct() {
+            *direct += 1;
+        }
+    }
+    navs.into_iter()
+        .map(|(category, (navs, direct))| (category, CategoryStats::of(navs, direct)))
+        .collect()
+}
+
+impl CategoryStats {
+    /// Statistics of `navs`, none of them `NaN`, of which `direct` are direct plans
+    fn of(mut navs: Vec<f64>, direct: usize) -> Self {
+        navs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let count = navs.len();
+        CategoryStats {
+            count,
+            mean_nav: navs.iter().sum::<f64>() / count as f64,
+            // The middle one twice for an odd count
+            median_nav: (navs[(count - 1) / 2] + navs[count / 2]) / 2.0,
+            min: navs[0],
+            max: navs[count - 1],
+            direct_share: direct as f64 / count as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav_from_str;
+
+    #[test]
+    fn stats_per_category() {
+        let feed = "Open Ended Schemes(Debt Scheme - Liquid Fund)\n\
+                    Axis Mutual Fund\n\
+                    120389;INF846K01CR6;-;Axis Liquid Fund - Direct Plan - Growth;2750.10;21-Jun-2024\n\
+                    112210;INF846K01CP0;-;Axis Liquid Fund - Regular Plan - Growth;2720.50;21-Jun-2024\n\
+                    112211;INF846K01CQ8;-;Axis Liquid Fund - Regular Plan - Daily IDCW;1000.00;21-Jun-2024\n\
+                    112212;INF846K01CS4;-;Axis Liquid Fund - Regular Plan - Weekly IDCW;0.0000;21-Jun-2024\n\
+                    Open Ended Schemes(Debt Scheme -  Liquid  Fund )\n\
+                    HDFC Mutual Fund\n\
+                    119091;INF179KB1HP9;-;HDFC Liquid Fund - Direct Plan - Growth;4800.25;21-Jun-2024\n\
+                    Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    Open Ended Schemes(Equity Scheme - Small Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    125354;INF846K01K35;-;Axis Small Cap Fund - Direct Plan - Growth;0.0000;21-Jun-2024\n";
+        let records: Vec<NavRecord> = nav_from_str(feed).map(Result::unwrap).collect();
+        let stats = category_stats(&records);
+        assert_eq!(
+            stats.keys().collect::<Vec<_>>(),
+            ["Large Cap Fund", "Liquid Fund"]
+        );
+
+        let liquid = stats["Liquid Fund"];
+        assert_eq!(liquid.count, 4);
+        assert_eq!(liquid.median_nav, (2720.50 + 2750.10) / 2.0);
+        assert_eq!(
+            liquid.mean_nav,
+            (2750.10 + 2720.50 + 1000.00 + 4800.25) / 4.0
+        );
+        assert_eq!(liquid.min, 1000.00);
+        assert_eq!(liquid.max, 4800.25);
+        assert_eq!(liquid.direct_share, 0.5);
+
+        // A single scheme
+        assert_eq!(
+            stats["Large Cap Fund"],
+            CategoryStats {
+                count: 1,
+                mean_nav: 61.43,
+                median_nav: 61.43,
+                min: 61.43,
+                max: 61.43,
+                direct_share: 1.0,
+            }
+        );
+
+        let mut missing = records[0].clone();
+        missing.nav = f64::NAN;
+        assert!(category_stats(&[missing]).is_empty());
+    }
+}
@@ -0,0 +1,482 @@
+//! Compact encoding of snapshot files
+//!
+//! Records are stored with their repeated texts, AMC, category, scheme and option, in a
+//! table and their ISINs flagged as validated, so loading neither parses dates nor re-runs
+//! check digits.
+//!
+//! Files are positional, so what is stored is frozen in the `Stored*` types rather than
+//! taken from the public ones: adding a field to `NavRecord` or `FeedSummary` leaves the
+//! encoding alone, and loaded values get the field's default. Changing the encoding means
+//! bumping `FORMAT_VERSION`, keeping the previous types for reading older files and
+//! upgrading what they decode to the current types.
+//! Enums are stored by variant index, so their new variants must be appended. Fields derived
+//! from stored ones, like `fof`, are derived again when loading instead, as far as they can be:
+//! `plan_source` is told from the name only.
+
+#[cfg(feature = "fetch")]
+use crate::FetchMeta;
+use crate::{
+    Error, FeedSummary, FofKind, FundMaturity, FundPlan, NavDate, NavRecord, NavSnapshot,
+    PlanSource, RawFields, RawIsin, SchemeCategory, SnapshotSource,
+};
+use bincode::Options;
+use chrono::{DateTime, Utc};
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+#[cfg(feature = "fetch")]
+use std::time::SystemTime;
+
+/// Start of snapshot files
+const MAGIC: &[u8; 8] = b"AMFISNAP";
+
+/// Length of the header: magic, format version and a byte of flags
+const HEADER_LEN: usize = 10;
+
+/// Version of the snapshot file format, bumped whenever the encoding of a snapshot changes
+///
+/// 1. Initial format. Sources are stored the same with and without the `fetch` feature;
+///    flags are zero.
+pub(crate) const FORMAT_VERSION: u8 = 1;
+
+/// Writes the header and the encoding of `snapshot`
+pub(crate) fn write<W: Write>(snapshot: &NavSnapshot, mut writer: W) -> crate::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[FORMAT_VERSION, 0])?;
+    bincode::serialize_into(&mut writer, &StoredSnapshot::new(snapshot))?;
+    Ok(())
+}
+
+/// Decodes a snapshot file of any format version up to `FORMAT_VERSION`
+pub(crate) fn read(bytes: &[u8]) -> crate::Result<NavSnapshot> {
+    let invalid = |reason: String| Err(Error::InvalidSnapshot(reason));
+    if bytes.len() < HEADER_LEN {
+        return invalid("file too short".to_string());
+    }
+    if &bytes[..8] != MAGIC {
+        return invalid("not an amfi snapshot".to_string());
+    }
+    let body = &bytes[HEADER_LEN..];
+    match (bytes[8], bytes[9]) {
+        (1, 0) => decode(body),
+        (version, _) if version > FORMAT_VERSION => invalid(format!(
+            "format version {} is newer than the supported {}",
+            version, FORMAT_VERSION
+        )),
+        (version, flags) => invalid(format!(
+            "format version {} with flags {:#04x}",
+            version, flags
+        )),
+    }
+}
+
+/// Decodes the body of a snapshot file
+fn decode(body: &[u8]) -> crate::Result<NavSnapshot> {
+    // The options of `bincode::serialize_into`
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes();
+    let mut deserializer = bincode::Deserializer::from_slice(body, options);
+    Ok(SnapshotSeed.deserialize(&mut deserializer)?)
+}
+
+/// Snapshot as written to a file, read back by [`SnapshotSeed`](struct.SnapshotSeed.html)
+#[derive(Serialize)]
+struct StoredSnapshot<'a> {
+    fetched_at: DateTime<Utc>,
+    source: StoredSource,
+    feed_date: Option<NavDate>,
+    stats: StoredStats,
+    strings: Vec<&'a str>,
+    records: Vec<StoredRecord<&'a str>>,
+}
+
+/// `SnapshotSource` as of format version 1
+#[derive(Serialize, Deserialize)]
+enum StoredSource {
+    Url {
+        url: String,
+        meta: Option<StoredMeta>,
+    },
+    File(PathBuf),
+    Memory,
+    Urls(Vec<String>),
+}
+
+/// `FetchMeta` as of format version 1
+#[derive(Serialize, Deserialize)]
+struct StoredMeta {
+    last_modified: Option<DateTime<Utc>>,
+    etag: Option<String>,
+    content_length: Option<u64>,
+}
+
+impl<'a> From<&'a SnapshotSource> for StoredSource {
+    fn from(source: &'a SnapshotSource) -> Self {
+        match *source {
+            #[cfg(feature = "fetch")]
+            SnapshotSource::Url {
+                ref url, ref meta, ..
+            } => StoredSource::Url {
+                url: url.clone(),
+                meta: Some(StoredMeta {
+                    last_modified: meta.last_modified.map(DateTime::from),
+                    etag: meta.etag.clone(),
+                    content_length: meta.content_length,
+                }),
+            },
+            #[cfg(not(feature = "fetch"))]
+            SnapshotSource::Url { ref url } => StoredSource::Url {
+                url: url.clone(),
+                meta: None,
+            },
+            SnapshotSource::File(ref path) => StoredSource::File(path.clone()),
+            SnapshotSource::Memory => StoredSource::Memory,
+            SnapshotSource::Urls(ref urls) => StoredSource::Urls(urls.clone()),
+        }
+    }
+}
+
+impl From<StoredSource> for SnapshotSource {
+    fn from(source: StoredSource) -> Self {
+        match source {
+            // Builds without the `fetch` feature have nowhere to keep the metadata
+            #[cfg_attr(not(feature = "fetch"), allow(unused_variables))]
+            StoredSource::Url { url, meta } => SnapshotSource::Url {
+                url,
+                #[cfg(feature = "fetch")]
+                meta: meta
+                    .map(|meta| FetchMeta {
+                        last_modified: meta.last_modified.map(SystemTime::from),
+                        etag: meta.etag,
+                        content_length: meta.content_length,
+                    })
+                    .unwrap_or_default(),
+                #[cfg(feature = "fetch")]
+                response: None,
+            },
+            StoredSource::File(path) => SnapshotSource::File(path),
+            StoredSource::Memory => SnapshotSource::Memory,
+            StoredSource::Urls(urls) => SnapshotSource::Urls(urls),
+        }
+    }
+}
+
+/// `FeedSummary` as of format version 1
+#[derive(Serialize, Deserialize)]
+struct StoredStats {
+    records: u64,
+    errors: u64,
+    per_amc: BTreeMap<String, u64>,
+    per_category: BTreeMap<String, u64>,
+    direct: u64,
+    regular: u64,
+    open_ended: u64,
+    close_ended: u64,
+    unknown_maturity: u64,
+    non_mf_isins: u64,
+    per_date: BTreeMap<NavDate, u64>,
+    /// Written empty, read and ignored
+    schemes_per_date: BTreeMap<NavDate, Vec<u32>>,
+}
+
+impl<'a> From<&'a FeedSummary> for StoredStats {
+    fn from(stats: &'a FeedSummary) -> Self {
+        // Records without an AMC or category are counted under an empty key, as they were
+        // before `FeedSummary` counted them apart
+        let counts = |map: &BTreeMap<String, usize>, without: usize| {
+            map.iter()
+                .map(|(key, &count)| (key.clone(), count as u64))
+                .chain(Some((String::new(), without as u64)).filter(|&(_, count)| count > 0))
+                .collect()
+        };
+        StoredStats {
+            records: stats.records as u64,
+            errors: stats.errors as u64,
+            per_amc: counts(&stats.per_amc, stats.without_amc),
+            per_category: counts(&stats.per_category, stats.without_category),
+            direct: stats.direct as u64,
+            regular: stats.regular as u64,
+            open_ended: stats.open_ended as u64,
+            close_ended: stats.close_ended as u64,
+            unknown_maturity: stats.unknown_maturity as u64,
+            non_mf_isins: stats.non_mf_isins as u64,
+            per_date: stats
+                .per_date
+                .iter()
+                .map(|(&date, &count)| (date, count as u64))
+                .collect(),
+            // No longer kept, see `FeedSummary::off_date_records`
+            schemes_per_date: BTreeMap::new(),
+        }
+    }
+}
+
+impl From<StoredStats> for FeedSummary {
+    fn from(mut stats: StoredStats) -> Self {
+        let counts = |map: BTreeMap<String, u64>| {
+            map.into_iter()
+                .map(|(key, count)| (key, count as usize))
+                .collect()
+        };
+        // Counted under an empty key, see `StoredStats::from`
+        let without_amc = stats.per_amc.remove("").unwrap_or(0) as usize;
+        let without_category = stats.per_category.remove("").unwrap_or(0) as usize;
+        FeedSummary {
+            records: stats.records as usize,
+            errors: stats.errors as usize,
+            per_amc: counts(stats.per_amc),
+            per_category: counts(stats.per_category),
+            without_amc,
+            without_category,
+            direct: stats.direct as usize,
+            regular: stats.regular as usize,
+            // Not stored, every record being of one plan
+            unspecified_plan: stats.records.saturating_sub(stats.direct + stats.regular) as usize,
+            open_ended: stats.open_ended as usize,
+            close_ended: stats.close_ended as usize,
+            unknown_maturity: stats.unknown_maturity as usize,
+            non_mf_isins: stats.non_mf_isins as usize,
+            // Not stored, counted from the records once loaded
+            without_isin: 0,
+            per_date: stats
+                .per_date
+                .into_iter()
+                .map(|(date, count)| (date, count as usize))
+                .collect(),
+            // Not stored, counted from the records once loaded
+            unknown_suffixes: BTreeMap::new(),
+        }
+    }
+}
+
+/// `NavRecord` as of format version 1, texts shared across records being indexes into
+/// `strings` and other texts `&str` when saving and `String` when loading
+#[derive(Serialize, Deserialize)]
+struct StoredRecord<S> {
+    code: u32,
+    isin: Option<(bool, S)>,
+    isin_dr: Option<(bool, S)>,
+    name: S,
+    nav: f64,
+    date: (u16, u8, u8),
+    amc: u32,
+    category: u32,
+    scheme: Option<u32>,
+    maturity: Option<FundMaturity>,
+    plan: FundPlan,
+    option: Option<u32>,
+    repurchase_price: Option<f64>,
+    sale_price: Option<f64>,
+    raw: Option<StoredRaw<S>>,
+    source_line: Option<u32>,
+}
+
+/// `RawFields` as of format version 1
+#[derive(Serialize, Deserialize)]
+struct StoredRaw<S> {
+    line: S,
+    name: S,
+    nav: S,
+    date: S,
+}
+
+/// Table of distinct texts
+#[derive(Default)]
+struct Strings<'a> {
+    texts: Vec<&'a str>,
+    indexes: HashMap<&'a str, u32>,
+}
+
+impl<'a> Strings<'a> {
+    fn index(&mut self, text: &'a str) -> u32 {
+        let texts = &mut self.texts;
+        *self.indexes.entry(text).or_insert_with(|| {
+            texts.push(text);
+            texts.len() as u32 - 1
+        })
+    }
+}
+
+impl<'a> StoredSnapshot<'a> {
+    fn new(snapshot: &'a NavSnapshot) -> Self {
+        let mut strings = Strings::default();
+        let isin =
+            |isin: &'a Option<RawIsin>| isin.as_ref().map(|isin| (isin.is_valid(), isin.as_str()));
+        let records = snapshot
+            .records
+            .iter()
+            .map(|record| StoredRecord {
+                code: record.code.get(),
+                isin: isin(&record.isin),
+                isin_dr: isin(&record.isin_dr),
+                name: &record.name,
+                nav: record.nav,
+                date: (
+                    record.date.year() as u16,
+                    record.date.month() as u8,
+                    record.date.day() as u8,
+                ),
+                amc: strings.index(&record.amc),
+                category: strings.index(&record.category),
+                scheme: record.scheme.as_ref().map(|scheme| strings.index(scheme)),
+                maturity: record.maturity,
+                plan: record.plan,
+                option: record.option.as_ref().map(|option| strings.index(option)),
+                repurchase_price: record.repurchase_price,
+                sale_price: record.sale_price,
+                raw: record.raw.as_ref().map(|raw| StoredRaw {
+                    line: raw.line.as_str(),
+                    name: raw.name.as_str(),
+                    nav: raw.nav.as_str(),
+                    date: raw.date.as_str(),
+                }),
+                source_line: record.source_line,
+            })
+            .collect();
+        StoredSnapshot {
+            fetched_at: snapshot.fetched_at,
+            source: (&snapshot.source).into(),
+            feed_date: snapshot.feed_date,
+            stats: (&snapshot.stats).into(),
+            strings: strings.texts,
+            records,
+        }
+    }
+}
+
+impl StoredRecord<String> {
+    /// Record with its texts looked up in `strings` and the FoF kind of its category, `None`
+    /// if an index or date is out of range
+    fn into_record(self, strings: &[String], fof: Option<FofKind>) -> Option<NavRecord> {
+        let text = |index: u32| strings.get(index as usize).cloned();
+        let isin =
+            |isin: Option<(bool, String)>| isin.map(|(valid, raw)| RawIsin::trusted(raw, valid));
+        let (year, month, day) = self.date;
+        let fof = fof.or_else(|| FofKind::from_name(&self.name));
+        let plan_source = PlanSource::of_name(&self.name);
+        Some(NavRecord {
+            code: self.code.into(),
+            isin: isin(self.isin),
+            isin_dr: isin(self.isin_dr),
+            name: self.name,
+            nav: self.nav,
+            date: NavDate::from_ymd(year.into(), month.into(), day.into())?,
+            amc: text(self.amc)?,
+            category: text(self.category)?,
+            scheme: match self.scheme {
+                Some(index) => Some(text(index)?),
+                None => None,
+            },
+            maturity: self.maturity,
+            plan: self.plan,
+            plan_source,
+            option: match self.option {
+                Some(index) => Some(text(index)?),
+                None => None,
+            },
+            fof,
+            repurchase_price: self.repurchase_price,
+            sale_price: self.sale_price,
+            raw: self.raw.map(|raw| RawFields {
+                line: raw.line,
+                name: raw.name,
+                nav: raw.nav,
+                date: raw.date,
+            }),
+            source_line: self.source_line,
+            segment: None,
+            recovered: false,
+        })
+    }
+}
+
+/// Decodes a snapshot
+///
+/// Records are rebuilt while they are decoded rather than collected as `StoredRecord`s
+/// first, which would take about as long again for large feeds.
+struct SnapshotSeed;
+
+impl<'de> DeserializeSeed<'de> for SnapshotSeed {
+    type Value = NavSnapshot;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<NavSnapshot, D::Error> {
+        deserializer.deserialize_tuple(6, self)
+    }
+}
+
+impl<'de> Visitor<'de> for SnapshotSeed {
+    type Value = NavSnapshot;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a stored snapshot")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<NavSnapshot, A::Error> {
+        let missing = |index| de::Error::invalid_length(index, &"6 fields");
+        let fetched_at = seq.next_element()?.ok_or_else(|| missing(0))?;
+        let source: StoredSource = seq.next_element()?.ok_or_else(|| missing(1))?;
+        let feed_date = seq.next_element()?.ok_or_else(|| missing(2))?;
+        let stats: StoredStats = seq.next_element()?.ok_or_else(|| missing(3))?;
+        let strings: Vec<String> = seq.next_element()?.ok_or_else(|| missing(4))?;
+        let records = seq
+            .next_element_seed(RecordsSeed(&strings))?
+            .ok_or_else(|| missing(5))?;
+        let mut stats = FeedSummary::from(stats);
+        stats.without_isin = records.iter().filter(|r| !r.has_isin()).count();
+        for record in &records {
+            stats.add_name_suffixes(&record.name);
+        }
+        Ok(NavSnapshot {
+            records,
+            fetched_at,
+            source: source.into(),
+            feed_date,
+            stats,
+            errors: Vec::new(),
+            provenance: None,
+        })
+    }
+}
+
+/// Records decoded against the table of texts
+struct RecordsSeed<'a>(&'a [String]);
+
+impl<'de, 'a> DeserializeSeed<'de> for RecordsSeed<'a> {
+    type Value = Vec<NavRecord>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for RecordsSeed<'a> {
+    type Value = Vec<NavRecord>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("stored records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // The length is read from the file, so a corrupt one mustn't allocate unbounded
+        let mut records = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1 << 16));
+        // FoF kinds by category, looked up once per category
+        let mut fof = HashMap::new();
+        while let Some(record) = seq.next_element::<StoredRecord<String>>()? {
+            let category = record.category;
+            let fof = *fof.entry(category).or_insert_with(|| {
+                self.0
+                    .get(category as usize)
+                    .and_then(|category| FofKind::of_category(&SchemeCategory::new(None, category)))
+            });
+            let record = record
+                .into_record(self.0, fof)
+                .ok_or_else(|| de::Error::custom("record out of range"))?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,238 @@
+//! Asynchronous parsing of a feed
+
+use crate::charset::Charset;
+use crate::{DecodePolicy, FeedState, NavRecord, ParseStats, Result};
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::AsyncBufRead;
+
+/// Stream of records parsed from an asynchronous reader
+///
+/// The counterpart of [`NavRecordIterator`](struct.NavRecordIterator.html) for
+/// [tokio](https://crates.io/crates/tokio) readers; it yields the same records and errors.
+/// Readers implementing `futures::io::AsyncBufRead` can be adapted with
+/// `tokio_util::compat`.
+///
+/// ```rust
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), amfi::Error> {
+/// use std::future::poll_fn;
+/// use std::pin::Pin;
+/// use futures_core::Stream;
+/// use tokio::io::BufReader;
+///
+/// let file = tokio::fs::File::open("fixtures/NAVOpen.txt").await?;
+/// let mut records = amfi::NavRecordStream::new(BufReader::new(file));
+/// while let Some(record) = poll_fn(|cx| Pin::new(&mut records).poll_next(cx)).await {
+///     println!("{}", record?.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct NavRecordStream<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// Whether the line being read is longer than the limit, its bytes no longer kept
+    too_long: bool,
+    state: FeedState,
+}
+
+impl<R: AsyncBufRead + Unpin> NavRecordStream<R> {
+    /// Parses the feed read from `reader`
+    pub fn new(reader: R) -> Self {
+        NavRecordStream {
+            reader,
+            buf: Vec::new(),
+            too_long: false,
+            state: FeedState::default(),
+        }
+    }
+
+    /// Sets whether the first non-blank line is checked to start a NAV feed, see
+    /// [`NavRecordIterator::sniff_content`](struct.NavRecordIterator.html#method.sniff_content)
+    pub fn sniff_content(mut self, sniff: bool) -> Self {
+        self.state.trust_content(!sniff);
+        self
+    }
+
+    /// Sets what decoding does with bytes that are not valid UTF-8, strict by default, see
+    /// [`NavRecordIterator::decode`](struct.NavRecordIterator.html#method.decode)
+    pub fn decode(mut self, policy: DecodePolicy) -> Self {
+        self.state.decode_policy(policy);
+        self
+    }
+
+    /// Bytes, records and errors of the parse so far, and its timing once the stream ended,
+    /// see [`NavRecordIterator::stats`](struct.NavRecordIterator.html#method.stats)
+    pub fn stats(&self) -> ParseStats {
+        self.state.stats()
+    }
+
+    /// 1-based number of the last line read, i.e. the line of the last record yielded
+    pub fn line(&self) -> usize {
+        self.state.line()
+    }
+
+    /// Underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for NavRecordStream<R> {
+    type Item = Result<NavRecord>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.state.start();
+        while !this.state.is_done() {
+            let available = match Pin::new(&mut this.reader).poll_fill_buf(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(error.into()))),
+                Poll::Ready(Ok(available)) => available,
+            };
+            if available.is_empty() && this.buf.is_empty() && !this.too_long {
+                break;
+            }
+            // Take bytes up to the next newline, or the rest of the input at its end
+            let (used, complete) = match available.iter().position(|&byte| byte == b'\n') {
+                Some(newline) => (newline + 1, true),
+                None => (available.len(), available.is_empty()),
+            };
+            if !this.too_long {
+                if this.buf.len() + used - usize::from(complete) > this.state.max_line_len() {
+                    this.too_long = true;
+                    this.buf.clear();
+                } else {
+                    this.buf.extend_from_slice(&available[..used]);
+                }
+            }
+            Pin::new(&mut this.reader).consume(used);
+            this.state.read(used);
+            if !complete {
+                continue;
+            }
+            if this.too_long {
+                this.too_long = false;
+                return Poll::Ready(Some(Err(this.state.line_too_long())));
+            }
+            let item = match std::str::from_utf8(&this.buf) {
+                Ok(line) => this.state.parse_line(line),
+                Err(_) => {
+                    let mut line = String::with_capacity(this.buf.len());
+                    match this.state.decode(Charset::Utf8, &this.buf, &mut line) {
+                        Some(_) => this.state.parse_line(&line),
+                        None => Some(Err(this.state.invalid_utf8())),
+                    }
+                }
+            };
+            this.buf.clear();
+            if item.is_some() {
+                return Poll::Ready(item);
+            }
+        }
+        this.state.finish();
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::poll_fn;
+    use tokio::io::BufReader;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+
+    async fn collect<R: AsyncBufRead + Unpin>(
+        mut stream: NavRecordStream<R>,
+    ) -> Vec<(usize, Result<NavRecord>)> {
+        let mut items = Vec::new();
+        while let Some(item) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            items.push((stream.line(), item));
+        }
+        items
+    }
+
+    fn sync(feed: &[u8]) -> Vec<(usize, Result<NavRecord>)> {
+        let mut iter = crate::nav_from_reader(feed);
+        let mut items = Vec::new();
+        while let Some(item) = iter.next() {
+            items.push((iter.line(), item));
+        }
+        items
+    }
+
+    fn same(left: &[(usize, Result<NavRecord>)], right: &[(usize, Result<NavRecord>)]) {
+        assert_eq!(left.len(), right.len());
+        for ((left_line, left), (right_line, right)) in left.iter().zip(right) {
+            assert_eq!(left_line, right_line);
+            match (left, right) {
+                (Ok(left), Ok(right)) => assert_eq!(left, right),
+                (Err(left), Err(right)) => assert_eq!(left.to_string(), right.to_string()),
+                _ => panic!("{:?} != {:?}", left, right),
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn fixture_matches_iterator() {
+        let file = tokio::fs::File::open(FIXTURE).await.unwrap();
+        let items = collect(NavRecordStream::new(BufReader::new(file))).await;
+        assert_eq!(items.len(), 30);
+        same(&items, &sync(&std::fs::read(FIXTURE).unwrap()));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn small_buffers_and_errors() {
+        let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024";
+        let reader = BufReader::with_capacity(7, feed.as_bytes());
+        let items = collect(NavRecordStream::new(reader)).await;
+        assert_eq!(items.len(), 3);
+        assert!(items[1].1.is_err());
+        same(&items, &sync(feed.as_bytes()));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn skips_lines_too_long() {
+        let line = "x".repeat(crate::MAX_LINE_LEN + 1);
+        for feed in &[
+            format!("Axis Mutual Fund\n{}\n120466;garbage;\n", line),
+            // Without end
+            format!("Axis Mutual Fund\n{}", line),
+        ] {
+            let items = collect(NavRecordStream::new(BufReader::new(feed.as_bytes()))).await;
+            assert!(matches!(
+                items[0],
+                (2, Err(crate::Error::LineTooLong { at_line: 2, .. }))
+            ));
+            same(&items, &sync(feed.as_bytes()));
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn stray_bytes() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVStrayByte.txt");
+        let feed = std::fs::read(path).unwrap();
+        let items = collect(NavRecordStream::new(&feed[..])).await;
+        assert!(matches!(
+            items[1],
+            (8, Err(crate::Error::InvalidUtf8 { at_line: 8 }))
+        ));
+        same(&items, &sync(&feed));
+
+        let mut stream = NavRecordStream::new(&feed[..]).decode(DecodePolicy::Lossy);
+        let mut names = Vec::new();
+        while let Some(item) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            names.push(item.unwrap().name);
+        }
+        assert_eq!(names[1], "Axis Bluechip Fund\u{FFFD} - Direct Plan - IDCW");
+        assert_eq!(stream.stats().replaced_chars, 1);
+    }
+}
@@ -0,0 +1,620 @@
+//! Feed health statistics
+
+use crate::group::collapse_whitespace;
+use crate::payout::unknown_suffixes;
+use crate::{expected_feed_date, FundMaturity, FundPlan, NavDate, NavRecord, Result, SchemeCode};
+use log::warn;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::SystemTime;
+
+/// Record counts of a feed, see [`summarize`](fn.summarize.html)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FeedSummary {
+    /// Records parsed
+    pub records: usize,
+    /// Parse errors
+    pub errors: usize,
+    /// Records per AMC, of those with one
+    pub per_amc: BTreeMap<String, usize>,
+    /// Records per category, trimmed with inner whitespace collapsed, of those with one
+    pub per_category: BTreeMap<String, usize>,
+    /// Records with an empty AMC, read before the first AMC header, see
+    /// [`HeaderlessPolicy`](enum.HeaderlessPolicy.html)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub without_amc: usize,
+    /// Records with an empty category, read before the first scheme header or after one
+    /// naming none
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub without_category: usize,
+    /// Direct plan records
+    pub direct: usize,
+    /// Regular plan records
+    pub regular: usize,
+    /// Records of no plan, see [`FundPlan::Unspecified`](enum.FundPlan.html#variant.Unspecified)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unspecified_plan: usize,
+    /// Open ended records
+    pub open_ended: usize,
+    /// Close ended records
+    pub close_ended: usize,
+    /// Records without maturity
+    pub unknown_maturity: usize,
+    /// ISINs of either column not starting with `INF`, usually shifted columns, see
+    /// [`Isin::is_indian_mf`](struct.Isin.html#method.is_indian_mf)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub non_mf_isins: usize,
+    /// Records with neither ISIN valid, see
+    /// [`NavRecord::has_isin`](struct.NavRecord.html#method.has_isin)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub without_isin: usize,
+    /// Records per NAV date
+    pub per_date: BTreeMap<NavDate, usize>,
+    /// ` - ` separated name parts naming no plan or option, whitespace collapsed, with the
+    /// records having them, see [`unknown_name_suffixes`](#method.unknown_name_suffixes)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unknown_suffixes: BTreeMap<String, usize>,
+}
+
+/// Counts records and errors of a feed
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let summary = amfi::summarize(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+/// assert_eq!(summary.records, 30);
+/// println!("{}", summary);
+/// # Ok(())
+/// # }
+/// ```
+pub fn summarize<I>(results: I) -> FeedSummary
+where
+    I: IntoIterator<Item = Result<NavRecord>>,
+{
+    let mut summary = FeedSummary::default();
+    for result in results {
+        match result {
+            Ok(ref record) => summary.add(record),
+            Err(_) => summary.errors += 1,
+        }
+    }
+    summary
+}
+
+impl FeedSummary {
+    /// Counts `record`
+    pub fn add(&mut self, record: &NavRecord) {
+        self.records += 1;
+        if record.amc.is_empty() {
+            self.without_amc += 1;
+        } else {
+            increment(&mut self.per_amc, &record.amc);
+        }
+        let category = collapse_whitespace(&record.category);
+        if category.is_empty() {
+            self.without_category += 1;
+        } else {
+            increment(&mut self.per_category, &category);
+        }
+        match record.plan {
+            FundPlan::Direct => self.direct += 1,
+            FundPlan::Regular => self.regular += 1,
+            FundPlan::Unspecified => self.unspecified_plan += 1,
+        }
+        match record.maturity {
+            Some(FundMaturity::OpenEnded) => self.open_ended += 1,
+            Some(FundMaturity::CloseEnded) => self.close_ended += 1,
+            None => self.unknown_maturity += 1,
+        }
+        self.non_mf_isins += [&record.isin, &record.isin_dr]
+            .iter()
+            .filter(|isin| isin.as_ref().is_some_and(|isin| !isin.is_indian_mf()))
+            .count();
+        if !record.has_isin() {
+            self.without_isin += 1;
+        }
+        *self.per_date.entry(record.date).or_insert(0) += 1;
+        self.add_name_suffixes(&record.name);
+    }
+
+    /// Counts the parts of `name` that are in [`unknown_suffixes`](#structfield.unknown_suffixes)
+    pub(crate) fn add_name_suffixes(&mut self, name: &str) {
+        for suffix in unknown_suffixes(name) {
+            increment(&mut self.unknown_suffixes, &collapse_whitespace(suffix));
+        }
+    }
+
+    /// Name parts after the fund name that are no plan or option known to the parser, the
+    /// most frequent first
+    ///
+    /// Records are parsed the same whatever their names end with; these counts only make new
+    /// vocabulary visible, as `IDCW` was when it replaced `Dividend` in 2021, before it is
+    /// misread for long. Parts of the fund name itself after a ` - `, such as the series of
+    /// close ended schemes, are counted too.
+    ///
+    /// ```rust
+    /// let feed = "Axis Mutual Fund\n\
+    ///             120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth - Bonus;61.43;21-Jun-2024\n\
+    ///             120466;INF846K01AC2;-;Axis Bluechip Fund - Direct Plan - Bonus;21.98;21-Jun-2024\n";
+    /// let summary = amfi::summarize(amfi::nav_from_str(feed));
+    /// assert_eq!(summary.unknown_name_suffixes(), [("Bonus", 2)]);
+    /// ```
+    pub fn unknown_name_suffixes(&self) -> Vec<(&str, usize)> {
+        let mut suffixes: Vec<_> = self
+            .unknown_suffixes
+            .iter()
+            .map(|(suffix, &count)| (suffix.as_str(), count))
+            .collect();
+        suffixes.sort_by_key(|&(_, count)| Reverse(count));
+        suffixes
+    }
+
+    /// Number of distinct AMCs
+    pub fn amcs(&self) -> usize {
+        self.per_amc.len()
+    }
+
+    /// Earliest NAV date
+    pub fn min_date(&self) -> Option<NavDate> {
+        self.per_date.keys().next().cloned()
+    }
+
+    /// Latest NAV date
+    pub fn max_date(&self) -> Option<NavDate> {
+        self.per_date.keys().next_back().cloned()
+    }
+
+    /// NAV date of most records, the latest one on ties
+    pub fn most_common_date(&self) -> Option<NavDate> {
+        self.per_date
+            .iter()
+            .max_by_key(|&(_, count)| count)
+            .map(|(&date, _)| date)
+    }
+
+    /// NAV date of the feed, the date of most records
+    ///
+    /// A daily feed is for one date, but a few lagging schemes may still carry an earlier
+    /// one, see [`off_date_records`](#method.off_date_records).
+    pub fn feed_date(&self) -> Option<NavDate> {
+        self.most_common_date()
+    }
+
+    /// Schemes of `records`, those summarized, whose NAV date differs from
+    /// [`feed_date`](#method.feed_date), in feed order
+    ///
+    /// The summary only counts records per date, so the records are read again to find them.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let records: Vec<amfi::NavRecord> = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+    ///     .collect::<Result<_, _>>()?;
+    /// let summary = amfi::summarize(records.iter().cloned().map(Ok));
+    /// assert!(summary.off_date_records(&records).is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn off_date_records<'a, I>(&self, records: I) -> Vec<(SchemeCode, NavDate)>
+    where
+        I: IntoIterator<Item = &'a NavRecord>,
+    {
+        let feed_date = match self.feed_date() {
+            Some(feed_date) => feed_date,
+            None => return Vec::new(),
+        };
+        records
+            .into_iter()
+            .filter(|record| record.date != feed_date)
+            .map(|record| (record.code, record.date))
+            .collect()
+    }
+
+    /// Compares NAV dates with [`feed_date`](#method.feed_date)
+    ///
+    /// The skew is the largest number of days any record is off the feed date. A skew
+    /// beyond `max_date_skew` days is logged as a warning; callers treating it as an error
+    /// check [`is_excessive`](struct.DateSkewReport.html#method.is_excessive).
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let summary = amfi::summarize(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+    /// let report = summary.check_date_skew(0);
+    /// assert!(!report.is_excessive());
+    /// assert_eq!(report.feed_date, amfi::NavDate::from_ymd(2024, 6, 21));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check_date_skew(&self, max_date_skew: u32) -> DateSkewReport {
+        let feed_date = self.feed_date();
+        let skew = match feed_date {
+            Some(feed_date) => self
+                .per_date
+                .keys()
+                .map(|date| (date.epoch_days() - feed_date.epoch_days()).unsigned_abs())
+                .max()
+                .unwrap_or(0),
+            None => 0,
+        };
+        let report = DateSkewReport {
+            feed_date,
+            max_date_skew,
+            skew: skew as u32,
+            off_date_records: self.records - feed_date.map_or(0, |date| self.per_date[&date]),
+        };
+        if report.is_excessive() {
+            warn!(
+                "{} records are up to {} days off feed date {}",
+                report.off_date_records,
+                report.skew,
+                feed_date.expect("skew without a feed date")
+            );
+        }
+        report
+    }
+
+    /// Compares NAV dates with `expected`, the business day the feed should be for
+    ///
+    /// Records dated before `expected` are stale. The feed is stale when their share of all
+    /// records exceeds `tolerance`, e.g. `0.1` allows 10% of schemes lagging behind.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let summary = amfi::summarize(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+    /// let expected = amfi::NavDate::from_ymd(2024, 6, 24).unwrap();
+    /// let report = summary.check_stale(expected, 0.1);
+    /// assert!(report.is_stale());
+    /// assert_eq!(report.stale_records, 30);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check_stale(&self, expected: NavDate, tolerance: f64) -> StaleReport {
+        let stale_records = self
+            .per_date
+            .range(..expected)
+            .map(|(_, count)| count)
+            .sum();
+        StaleReport {
+            expected,
+            tolerance,
+            records: self.per_date.values().sum(),
+            stale_records,
+            oldest: self.min_date(),
+            newest: self.max_date(),
+        }
+    }
+
+    /// Compares NAV dates with the date of the latest feed due at `now`, see
+    /// [`expected_feed_date`](fn.expected_feed_date.html) and
+    /// [`check_stale`](#method.check_stale)
+    ///
+    /// Weekends and holidays expect the previous business day's feed, so a run on Saturday
+    /// finds Friday's feed current.
+    ///
+    /// # Panics
+    ///
+    /// If `now` is outside years 1 to 9999.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let summary = amfi::summarize(amfi::nav_from_file("fixtures/NAVOpen.txt")?);
+    /// // Saturday, 22 June 2024, 10 AM IST
+    /// let saturday = UNIX_EPOCH + Duration::from_secs(1_719_030_600);
+    /// assert!(!summary.check_stale_at(saturday, 0.1).is_stale());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check_stale_at(&self, now: SystemTime, tolerance: f64) -> StaleReport {
+        let expected = expected_feed_date(now).expect("time within years 1 to 9999");
+        self.check_stale(expected, tolerance)
+    }
+}
+
+/// Result of [`FeedSummary::check_stale`](struct.FeedSummary.html#method.check_stale)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct StaleReport {
+    /// Date the feed was expected to be for
+    pub expected: NavDate,
+    /// Share of stale records tolerated
+    pub tolerance: f64,
+    /// Records checked
+    pub records: usize,
+    /// Records dated before `expected`
+    pub stale_records: usize,
+    /// Earliest NAV date
+    pub oldest: Option<NavDate>,
+    /// Latest NAV date
+    pub newest: Option<NavDate>,
+}
+
+/// Result of [`FeedSummary::check_date_skew`](struct.FeedSummary.html#method.check_date_skew)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DateSkewReport {
+    /// Date of most records
+    pub feed_date: Option<NavDate>,
+    /// Days of skew tolerated
+    pub max_date_skew: u32,
+    /// Largest number of days a record is off `feed_date`
+    pub skew: u32,
+    /// Records not dated `feed_date`
+    pub off_date_records: usize,
+}
+
+impl DateSkewReport {
+    /// Whether `skew` exceeds `max_date_skew`
+    pub fn is_excessive(&self) -> bool {
+        self.skew > self.max_date_skew
+    }
+}
+
+impl StaleReport {
+    /// Share of records dated before `expected`, `0.0` for an empty feed
+    pub fn stale_fraction(&self) -> f64 {
+        if self.records == 0 {
+            0.0
+        } else {
+            self.stale_records as f64 / self.records as f64
+        }
+    }
+
+    /// Whether more than `tolerance` of records are stale
+    pub fn is_stale(&self) -> bool {
+        self.stale_fraction() > self.tolerance
+    }
+}
+
+fn increment(counts: &mut BTreeMap<String, usize>, key: &str) {
+    match counts.get_mut(key) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(key.to_string(), 1);
+        }
+    }
+}
+
+impl fmt::Display for FeedSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let date = |date: Option<NavDate>| date.map_or("-".to_string(), |d| d.to_string());
+        writeln!(f, "{:<16}{:>8}", "Records", self.records)?;
+        writeln!(f, "{:<16}{:>8}", "Parse errors", self.errors)?;
+        writeln!(f, "{:<16}{:>8}", "AMCs", self.amcs())?;
+        if self.without_amc > 0 {
+            writeln!(f, "{:<16}{:>8}", "No AMC", self.without_amc)?;
+        }
+        if self.without_category > 0 {
+            writeln!(f, "{:<16}{:>8}", "No category", self.without_category)?;
+        }
+        writeln!(f, "{:<16}{:>8}", "Direct", self.direct)?;
+        writeln!(f, "{:<16}{:>8}", "Regular", self.regular)?;
+        if self.unspecified_plan > 0 {
+            writeln!(f, "{:<16}{:>8}", "No plan", self.unspecified_plan)?;
+        }
+        writeln!(f, "{:<16}{:>8}", "Open ended", self.open_ended)?;
+        writeln!(f, "{:<16}{:>8}", "Close ended", self.close_ended)?;
+        writeln!(f, "{:<16}{:>8}", "No maturity", self.unknown_maturity)?;
+        writeln!(f, "{:<16}{:>8}", "Non-MF ISINs", self.non_mf_isins)?;
+        writeln!(f, "{:<16}{:>8}", "No ISIN", self.without_isin)?;
+        writeln!(
+            f,
+            "{:<16}{} .. {}, mostly {}",
+            "Dates",
+            date(self.min_date()),
+            date(self.max_date()),
+            date(self.most_common_date())
+        )?;
+        for (title, counts) in &[("AMC", &self.per_amc), ("Category", &self.per_category)] {
+            let width = counts
+                .keys()
+                .map(|key| key.chars().count())
+                .max()
+                .unwrap_or(0);
+            writeln!(f)?;
+            writeln!(f, "{:<width$}  {:>8}", title, "Records", width = width)?;
+            for (key, count) in counts.iter() {
+                writeln!(f, "{:<width$}  {:>8}", key, count, width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_path;
+    use crate::NavRecordIterator;
+
+    fn fixture() -> FeedSummary {
+        let path = fixture_path("NAVOpen.txt");
+        summarize(crate::nav_from_file(&path).unwrap())
+    }
+
+    #[test]
+    fn fixture_counts() {
+        let summary = fixture();
+        assert_eq!(summary.records, 30);
+        assert_eq!(summary.errors, 0);
+        assert_eq!(summary.amcs(), 6);
+        assert!(summary.per_amc.values().all(|&count| count == 5));
+        assert_eq!(summary.per_category.len(), 18);
+        assert_eq!(summary.per_category["Large Cap Fund"], 5);
+        assert_eq!(summary.per_category["Other ETFs"], 1);
+        assert_eq!((summary.direct, summary.regular), (23, 7));
+        assert_eq!(
+            (
+                summary.open_ended,
+                summary.close_ended,
+                summary.unknown_maturity
+            ),
+            (30, 0, 0)
+        );
+        assert_eq!(summary.non_mf_isins, 0);
+        let date = NavDate::from_ymd(2024, 6, 21);
+        assert_eq!(summary.min_date(), date);
+        assert_eq!(summary.max_date(), date);
+        assert_eq!(summary.most_common_date(), date);
+    }
+
+    #[test]
+    fn errors_and_dates() {
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;20-Jun-2024\n\
+                    120466;garbage;\n\
+                    120389;INF846K01AU4;-;Axis Liquid Fund - Direct Plan - Growth Option;2736.7421;21-Jun-2024\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n";
+        let summary = summarize(NavRecordIterator::new(feed.as_bytes()));
+        assert_eq!((summary.records, summary.errors), (3, 1));
+        assert_eq!(summary.unknown_maturity, 3);
+        assert_eq!(summary.min_date(), NavDate::from_ymd(2024, 6, 20));
+        assert_eq!(summary.most_common_date(), NavDate::from_ymd(2024, 6, 21));
+    }
+
+    #[test]
+    fn non_mf_isins() {
+        // ISINs of shares, e.g. from shifted columns
+        let feed = "Axis Mutual Fund\n\
+                    120465;INE009A01021;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;INF846K01AC2;INE040A01034;Axis Bluechip Fund - Direct Plan - IDCW;21.98;21-Jun-2024\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n";
+        let summary = summarize(NavRecordIterator::new(feed.as_bytes()));
+        assert_eq!(summary.non_mf_isins, 2);
+    }
+
+    #[test]
+    fn stale_feed() {
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;19-Jun-2024\n\
+                    120389;INF846K01AU4;-;Axis Liquid Fund - Direct Plan - Growth Option;2736.7421;21-Jun-2024\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n\
+                    120466;INF846K01AC2;-;Axis Bluechip Fund - Direct Plan - IDCW;18.20;21-Jun-2024\n";
+        let summary = summarize(NavRecordIterator::new(feed.as_bytes()));
+        let date = |day| NavDate::from_ymd(2024, 6, day).unwrap();
+
+        let report = summary.check_stale(date(21), 0.3);
+        assert_eq!((report.records, report.stale_records), (4, 1));
+        assert_eq!(
+            (report.oldest, report.newest),
+            (Some(date(19)), Some(date(21)))
+        );
+        assert!(!report.is_stale());
+        assert!(summary.check_stale(date(21), 0.2).is_stale());
+        assert!(summary.check_stale(date(24), 0.9).is_stale());
+        assert_eq!(summary.check_stale(date(24), 0.9).stale_records, 4);
+        assert!(!FeedSummary::default().check_stale(date(21), 0.0).is_stale());
+
+        // Friday's feed is current until Monday evening
+        let at = |day: u32, hour: i64| {
+            let seconds = date(day).unix_midnight_ist() + hour * 3600;
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds as u64)
+        };
+        for &(day, hour) in &[(21, 23), (22, 9), (23, 20), (24, 22)] {
+            let report = summary.check_stale_at(at(day, hour), 0.3);
+            assert_eq!(report.expected, date(21));
+            assert!(!report.is_stale(), "{} {}", day, hour);
+        }
+        assert!(summary.check_stale_at(at(24, 23), 0.3).is_stale());
+    }
+
+    #[test]
+    fn off_date_records() {
+        let path = fixture_path("NAVOpen.txt");
+        let mut feed = std::fs::read_to_string(&path).unwrap();
+        for code in &["120465;", "119551;"] {
+            let start = feed.find(code).unwrap();
+            let end = start + feed[start..].find("21-Jun-2024").unwrap();
+            feed.replace_range(end..end + 11, "20-Jun-2024");
+        }
+        let records: Vec<NavRecord> = NavRecordIterator::new(feed.as_bytes())
+            .collect::<Result<_>>()
+            .unwrap();
+        let summary = summarize(records.iter().cloned().map(Ok));
+        let date = |day| NavDate::from_ymd(2024, 6, day).unwrap();
+
+        assert_eq!(summary.records, 30);
+        assert_eq!(summary.feed_date(), Some(date(21)));
+        assert_eq!(
+            summary.off_date_records(&records),
+            [
+                (SchemeCode::from(120_465), date(20)),
+                (SchemeCode::from(119_551), date(20))
+            ]
+        );
+        let report = summary.check_date_skew(0);
+        assert_eq!((report.skew, report.off_date_records), (1, 2));
+        assert!(report.is_excessive());
+        assert!(!summary.check_date_skew(1).is_excessive());
+
+        let original: Vec<NavRecord> = crate::nav_from_file(&path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(fixture().off_date_records(&original).is_empty());
+        assert!(FeedSummary::default().off_date_records(&records).is_empty());
+        assert_eq!(fixture().check_date_skew(0).skew, 0);
+        let empty = FeedSummary::default();
+        assert_eq!(empty.feed_date(), None);
+        assert!(!empty.check_date_skew(0).is_excessive());
+    }
+
+    #[test]
+    fn isin_less_records() {
+        assert_eq!(fixture().without_isin, 1);
+        // Close-ended series of 2009, published without ISINs
+        let path = fixture_path("NAVArchive.txt");
+        let summary = summarize(crate::nav_from_file(&path).unwrap());
+        assert_eq!(summary.without_isin, 3);
+        assert!(summary.to_string().contains("No ISIN                3\n"));
+    }
+
+    #[test]
+    fn unknown_name_suffixes() {
+        assert_eq!(
+            fixture().unknown_name_suffixes(),
+            [("Segregated Portfolio 1", 1)]
+        );
+        let feed = "Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth - Zeta Units;61.43;21-Jun-2024\n\
+                    120466;INF846K01AC2;-;Axis Bluechip Fund - Direct Plan - Quux;21.98;21-Jun-2024\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth - Zeta  Units;54.12;21-Jun-2024\n";
+        let records: Vec<NavRecord> = NavRecordIterator::new(feed.as_bytes())
+            .map(Result::unwrap)
+            .collect();
+        let summary = summarize(records.iter().cloned().map(Ok));
+        assert_eq!(
+            summary.unknown_name_suffixes(),
+            [("Zeta Units", 2), ("Quux", 1)]
+        );
+
+        // Observed only: the records read as those of names without the suffixes
+        let plain = feed
+            .replace(" - Zeta Units", "")
+            .replace(" - Zeta  Units", "")
+            .replace(" - Quux", "");
+        for (record, plain) in records.iter().zip(NavRecordIterator::new(plain.as_bytes())) {
+            let plain = plain.unwrap();
+            assert_eq!(
+                (record.plan, record.plan_source),
+                (plain.plan, plain.plan_source)
+            );
+            assert_eq!(record.is_growth(), plain.is_growth());
+            assert_eq!(record.is_idcw(), plain.is_idcw());
+        }
+    }
+
+    #[test]
+    fn display_table() {
+        let text = fixture().to_string();
+        assert!(text.starts_with("Records               30\nParse errors           0\n"));
+        assert!(text.contains("Dates           2024-06-21 .. 2024-06-21, mostly 2024-06-21\n"));
+        assert!(text.contains("\nAxis Mutual Fund                          5\n"));
+    }
+}
@@ -0,0 +1,234 @@
+//! Records as an aligned plain text table, for terminals
+
+use crate::{NavRecord, Result};
+use std::io::Write;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Widest text cell of [`render_table`](fn.render_table.html), in terminal columns
+pub const TABLE_TEXT_WIDTH: usize = 60;
+
+/// Column of [`render_table`](fn.render_table.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// Scheme code, right-aligned
+    Code,
+    /// Growth or payout ISIN, else reinvestment ISIN, `-` without one
+    Isin,
+    /// Scheme name
+    Name,
+    /// AMC
+    Amc,
+    /// Category
+    Category,
+    /// Plan, empty if unspecified
+    Plan,
+    /// NAV with 4 decimals, right-aligned
+    Nav,
+    /// NAV date, `YYYY-MM-DD`
+    Date,
+}
+
+impl Column {
+    /// Code, name, NAV and date
+    pub const DEFAULT: &'static [Column] = &[Column::Code, Column::Name, Column::Nav, Column::Date];
+
+    fn title(self) -> &'static str {
+        match self {
+            Column::Code => "Code",
+            Column::Isin => "ISIN",
+            Column::Name => "Name",
+            Column::Amc => "AMC",
+            Column::Category => "Category",
+            Column::Plan => "Plan",
+            Column::Nav => "NAV",
+            Column::Date => "Date",
+        }
+    }
+
+    fn right_aligned(self) -> bool {
+        matches!(self, Column::Code | Column::Nav)
+    }
+
+    fn cell(self, record: &NavRecord) -> String {
+        match self {
+            Column::Code => record.code.to_string(),
+            Column::Isin => record
+                .isin
+                .as_ref()
+                .or(record.isin_dr.as_ref())
+                .map_or_else(|| "-".to_string(), |isin| isin.as_str().to_string()),
+            Column::Name => record.name.clone(),
+            Column::Amc => record.amc.clone(),
+            Column::Category => record.category.clone(),
+            Column::Plan => match record.plan {
+                crate::FundPlan::Unspecified => String::new(),
+                plan => plan.to_string(),
+            },
+            Column::Nav => format!("{:.4}", record.nav),
+            Column::Date => record.date.to_string(),
+        }
+    }
+}
+
+/// Writes `records` to `w` as a table of `columns` under a header row, text cells cut to
+/// [`TABLE_TEXT_WIDTH`](constant.TABLE_TEXT_WIDTH.html)
+///
+/// See [`render_table_with`](fn.render_table_with.html).
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// use amfi::Column;
+///
+/// let records: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?.collect::<Result<_, _>>()?;
+/// let mut table = Vec::new();
+/// amfi::render_table(&records[..2], Column::DEFAULT, &mut table)?;
+/// assert_eq!(
+///     String::from_utf8(table).unwrap(),
+///     "  Code  Name                                           NAV  Date\n\
+///      120465  Axis Bluechip Fund - Direct Plan - Growth  61.4300  2024-06-21\n\
+///      120466  Axis Bluechip Fund - Direct Plan - IDCW    21.9800  2024-06-21\n"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn render_table<W: Write>(records: &[NavRecord], columns: &[Column], w: W) -> Result<()> {
+    render_table_with(records, columns, TABLE_TEXT_WIDTH, w)
+}
+
+/// Writes `records` to `w` as a table of `columns` under a header row, text cells cut to
+/// `max_width` terminal columns
+///
+/// Columns are as wide as their widest cell and two spaces apart, measured in terminal
+/// columns, so East Asian wide characters count twice and combining marks not at all. Longer
+/// cells end with `…`. Numbers are right-aligned, and rows have no trailing spaces.
+pub fn render_table_with<W: Write>(
+    records: &[NavRecord],
+    columns: &[Column],
+    max_width: usize,
+    mut w: W,
+) -> Result<()> {
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| {
+            columns
+                .iter()
+                .map(|column| truncate(column.cell(record), max_width))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| row[i].width())
+                .chain(Some(column.title().width()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let titles = columns.iter().map(|column| column.title().to_string());
+    let mut line = String::new();
+    for row in Some(titles.collect()).iter().chain(&rows) {
+        line.clear();
+        for (i, (column, cell)) in columns.iter().zip(row).enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            let padding = " ".repeat(widths[i] - cell.width());
+            if column.right_aligned() {
+                line.push_str(&padding);
+                line.push_str(cell);
+            } else {
+                line.push_str(cell);
+                line.push_str(&padding);
+            }
+        }
+        writeln!(w, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// `text` cut to `max_width` terminal columns, ending with `…` if cut
+fn truncate(text: String, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text;
+    }
+    let mut cut = String::new();
+    let mut width = 0;
+    for ch in text.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if width + ch_width + 1 > max_width {
+            break;
+        }
+        cut.push(ch);
+        width += ch_width;
+    }
+    let mut cut = cut.trim_end().to_string();
+    if max_width > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FundPlan, NavDate};
+
+    fn record(code: u32, name: &str, nav: f64) -> NavRecord {
+        NavRecord {
+            name: name.to_string(),
+            nav,
+            ..NavRecord::fixture().with_code(code)
+        }
+    }
+
+    fn render(records: &[NavRecord], columns: &[Column], max_width: usize) -> String {
+        let mut out = Vec::new();
+        render_table_with(records, columns, max_width, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn aligns_columns() {
+        let mut unspecified = record(5, "Kotak FMP Series 3 - Growth", 10.31);
+        unspecified.plan = FundPlan::Unspecified;
+        unspecified.isin = None;
+        unspecified.date = NavDate::from_ymd(2009, 1, 2).unwrap();
+        let records = [record(120_465, "Axis Bluechip Fund", 61.43), unspecified];
+        let columns = [
+            Column::Code,
+            Column::Isin,
+            Column::Name,
+            Column::Plan,
+            Column::Nav,
+            Column::Date,
+        ];
+        assert_eq!(
+            render(&records, &columns, TABLE_TEXT_WIDTH),
+            "  Code  ISIN          Name                         Plan        NAV  Date\n\
+             120465  INF846K01AB4  Axis Bluechip Fund           Direct  61.4300  2024-06-21\n     \
+             5  -             Kotak FMP Series 3 - Growth          10.3100  2009-01-02\n"
+        );
+        assert_eq!(render(&[], Column::DEFAULT, 10), "Code  Name  NAV  Date\n");
+    }
+
+    #[test]
+    fn truncates_by_display_width() {
+        let records = [
+            record(1, "Axis Bluechip Fund - Direct Plan - Growth", 61.43),
+            // Wide characters take two columns, combining marks none
+            record(2, "日本株式ファンド", 1.0),
+            record(3, "Re\u{301}sume\u{301} Fund", 1.0),
+        ];
+        let text = render(&records, &[Column::Name, Column::Code], 12);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[1], "Axis Bluech…     1");
+        assert_eq!(lines[2], "日本株式フ…      2");
+        assert_eq!(lines[3], "Re\u{301}sume\u{301} Fund      3");
+        for line in &lines {
+            assert_eq!(line.width(), lines[1].width(), "{}", line);
+        }
+    }
+}
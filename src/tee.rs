@@ -0,0 +1,161 @@
+//! Copy of the bytes of a feed as they are read, see
+//! [`NavRecordIterator::tee`](struct.NavRecordIterator.html#method.tee)
+
+use std::io::{self, Read, Write};
+
+/// Writer receiving the bytes of a feed, flushed when dropped
+pub(crate) struct Tee {
+    writer: Box<dyn Write + Send>,
+}
+
+impl Tee {
+    pub(crate) fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Tee {
+            writer: Box::new(writer),
+        }
+    }
+
+    /// Copies `bytes` just read
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)
+    }
+
+    /// Copies the `rest` of the feed left unread when parsing ended, and flushes
+    pub(crate) fn finish<R: Read>(mut self, rest: &mut R) -> io::Result<()> {
+        io::copy(rest, &mut self.writer)?;
+        self.writer.flush()
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        // Errors are reported by `finish`, dropping early there is no one to report them to
+        let _ = self.writer.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{nav_from_reader, DecodePolicy, Error};
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    /// Writer whose bytes outlive it
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Shared {
+        fn bytes(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Writer with room for so many bytes, failing once full
+    struct Full(usize);
+
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.0 {
+                return Err(io::Error::new(io::ErrorKind::WriteZero, "disk full"));
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn copies_every_byte() {
+        for fixture in &[
+            "NAVOpen.txt",
+            "NAVLegacy.txt",
+            "NAVHalfGarbage.txt",
+            "NAVMessy.txt",
+        ] {
+            let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+            let body = std::fs::read(&path).unwrap();
+            let copy = Shared::default();
+            let teed: Vec<_> = nav_from_reader(&body[..]).tee(copy.clone()).collect();
+            assert_eq!(copy.bytes(), body, "{}", fixture);
+
+            let parsed: Vec<_> = nav_from_reader(&body[..]).collect();
+            assert_eq!(teed.len(), parsed.len());
+            for (teed, parsed) in teed.iter().zip(&parsed) {
+                assert_eq!(teed.as_ref().ok(), parsed.as_ref().ok());
+            }
+        }
+    }
+
+    #[test]
+    fn copies_unparsed_bytes() {
+        // Invalid UTF-8 and a feed ending without a newline
+        let body: &[u8] = b"\xef\xbb\xbfAxis Mutual Fund\r\n\
+            120465;INF846K01AB4;-;Axis Bluechip Fund \x96 Growth;61.43;21-Jun-2024\r\n\
+            120466;garbage";
+        let copy = Shared::default();
+        let results: Vec<_> = nav_from_reader(body).tee(copy.clone()).collect();
+        assert!(matches!(results[0], Err(Error::InvalidUtf8 { at_line: 2 })));
+        assert_eq!(copy.bytes(), body);
+
+        let copy = Shared::default();
+        let results: Vec<_> = nav_from_reader(body)
+            .decode(DecodePolicy::Lossy)
+            .tee(copy.clone())
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(copy.bytes(), body);
+
+        // Parsing ends at content that isn't a feed, the rest is still copied
+        let html: &[u8] = b"<html>\n<body>Service Unavailable</body>\n</html>\n";
+        let copy = Shared::default();
+        let results: Vec<_> = nav_from_reader(html).tee(copy.clone()).collect();
+        assert!(matches!(
+            results[..],
+            [Err(Error::UnexpectedContent { .. })]
+        ));
+        assert_eq!(copy.bytes(), html);
+
+        // Lines too long are read through, not kept
+        let long = format!("Axis Mutual Fund\n{};\n120466;garbage\n", "x".repeat(5000));
+        let copy = Shared::default();
+        let results: Vec<_> = nav_from_reader(long.as_bytes())
+            .max_line_len(100)
+            .with_buffer_capacity(7)
+            .tee(copy.clone())
+            .collect();
+        assert!(matches!(
+            results[0],
+            Err(Error::LineTooLong { at_line: 2, .. })
+        ));
+        assert_eq!(results.len(), 2);
+        assert_eq!(copy.bytes(), long.as_bytes());
+    }
+
+    #[test]
+    fn write_errors_end_parsing() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+        let body = std::fs::read(path).unwrap();
+        let mut records = nav_from_reader(&body[..]).tee(Full(400));
+        let results: Vec<_> = records.by_ref().collect();
+        match results.last() {
+            Some(Err(Error::IoError(err))) => assert_eq!(err.kind(), io::ErrorKind::WriteZero),
+            other => panic!("{:?}", other),
+        }
+        assert!(results[..results.len() - 1].iter().all(Result::is_ok));
+        assert!(records.next().is_none());
+    }
+}
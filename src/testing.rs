@@ -0,0 +1,43 @@
+//! Fixtures and corpus cases shared by the crate's tests
+
+use crate::corpus::{self, Case};
+use crate::{nav_from_file, NavRecord, Result};
+use std::path::PathBuf;
+
+/// Path of `fixtures/<name>`
+pub(crate) fn fixture_path(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "fixtures", name]
+        .iter()
+        .collect()
+}
+
+/// Bytes of `fixtures/<name>`
+pub(crate) fn fixture_bytes(name: &str) -> Vec<u8> {
+    std::fs::read(fixture_path(name)).unwrap()
+}
+
+/// Text of `fixtures/<name>`
+pub(crate) fn fixture_text(name: &str) -> String {
+    std::fs::read_to_string(fixture_path(name)).unwrap()
+}
+
+/// Records of `fixtures/<name>`, which must parse without errors
+pub(crate) fn fixture_records(name: &str) -> Vec<NavRecord> {
+    nav_from_file(fixture_path(name))
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap()
+}
+
+/// Case of the corpus named `name`
+pub(crate) fn case(name: &str) -> &'static Case {
+    corpus::cases()
+        .iter()
+        .find(|case| case.name == name)
+        .unwrap_or_else(|| panic!("no corpus case {}", name))
+}
+
+/// Record line of `case`, the last line of its input
+pub(crate) fn record_line(case: &Case) -> &'static str {
+    case.input.lines().last().unwrap()
+}
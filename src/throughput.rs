@@ -0,0 +1,198 @@
+//! Bytes, records and timing of a parse, for capacity planning
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Counts and timing of the parse of a feed, see
+/// [`NavRecordIterator::stats`](struct.NavRecordIterator.html#method.stats)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParseStats {
+    /// Bytes of the feed read
+    pub bytes_read: u64,
+    /// Records yielded
+    pub records: usize,
+    /// Lines failing to parse
+    pub errors: usize,
+    /// `U+FFFD` put in for bytes that are not valid UTF-8, see
+    /// [`DecodePolicy::Lossy`](enum.DecodePolicy.html#variant.Lossy)
+    pub replaced_chars: usize,
+    /// Records with a lower scheme code than the one before them in their section, counted
+    /// with [`NavRecordIterator::assert_sorted_within_sections`](struct.NavRecordIterator.html#method.assert_sorted_within_sections)
+    /// under [`RecoveryPolicy::Lenient`](enum.RecoveryPolicy.html#variant.Lenient)
+    pub out_of_order: usize,
+    /// Time taken to download the feed before parsing it, for feeds downloaded whole first
+    ///
+    /// `None` for feeds not downloaded and for those parsed as they download, whose download
+    /// time is part of [`parse_duration`](#structfield.parse_duration).
+    pub fetch_duration: Option<Duration>,
+    /// Time from reading the first line to the end of the feed, `None` until the feed was
+    /// read to its end
+    pub parse_duration: Option<Duration>,
+}
+
+/// Rates of a parse, see [`ParseStats::throughput`](struct.ParseStats.html#method.throughput)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    /// Records yielded per second
+    pub records_per_sec: f64,
+    /// Megabytes, of a million bytes, read per second
+    pub mb_per_sec: f64,
+}
+
+impl ParseStats {
+    /// Records and bytes per second of parsing, `None` until the feed was read to its end
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// let mut records = amfi::nav_from_file("fixtures/NAVOpen.txt")?;
+    /// assert_eq!(records.stats().throughput(), None);
+    /// records.by_ref().for_each(drop);
+    /// let stats = records.stats();
+    /// assert_eq!(stats.records, 30);
+    /// println!("{} bytes: {}", stats.bytes_read, stats.throughput().unwrap());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn throughput(&self) -> Option<Throughput> {
+        let seconds = self.parse_duration?.as_secs_f64();
+        if seconds == 0.0 {
+            return None;
+        }
+        Some(Throughput {
+            records_per_sec: self.records as f64 / seconds,
+            mb_per_sec: self.bytes_read as f64 / 1e6 / seconds,
+        })
+    }
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} records/s, {:.2} MB/s",
+            self.records_per_sec, self.mb_per_sec
+        )
+    }
+}
+
+/// Byte and record counts and timestamps of a parse, kept by the feed state
+#[derive(Debug, Default)]
+pub(crate) struct Meter {
+    bytes: u64,
+    records: usize,
+    replaced_chars: usize,
+    out_of_order: usize,
+    fetch_duration: Option<Duration>,
+    started: Option<Instant>,
+    parse_duration: Option<Duration>,
+}
+
+impl Meter {
+    /// Starts timing the parse, unless started already
+    #[inline]
+    pub(crate) fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    /// Counts `bytes` read
+    #[inline]
+    pub(crate) fn read(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+    }
+
+    /// Counts a record yielded, or taken back if `yielded` is false
+    #[inline]
+    pub(crate) fn record(&mut self, yielded: bool) {
+        if yielded {
+            self.records += 1;
+        } else {
+            self.records -= 1;
+        }
+    }
+
+    /// Sets the time taken to download the feed before parsing it
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn fetched_in(&mut self, duration: Duration) {
+        self.fetch_duration = Some(duration);
+    }
+
+    /// Counts `U+FFFD` put in for `replaced` invalid bytes
+    #[inline]
+    pub(crate) fn replaced(&mut self, replaced: usize) {
+        self.replaced_chars += replaced;
+    }
+
+    /// Counts a record out of order in its section
+    #[inline]
+    pub(crate) fn out_of_order(&mut self) {
+        self.out_of_order += 1;
+    }
+
+    /// Records yielded so far
+    #[inline]
+    pub(crate) fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Stops timing the parse, once
+    pub(crate) fn finish(&mut self) {
+        if self.parse_duration.is_none() {
+            self.parse_duration = self.started.map(|started| started.elapsed());
+        }
+    }
+
+    /// Statistics so far, with `errors` lines failing to parse
+    pub(crate) fn stats(&self, errors: usize) -> ParseStats {
+        ParseStats {
+            bytes_read: self.bytes,
+            records: self.records,
+            errors,
+            replaced_chars: self.replaced_chars,
+            out_of_order: self.out_of_order,
+            fetch_duration: self.fetch_duration,
+            parse_duration: self.parse_duration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throughput_of_finished_parses() {
+        let mut stats = ParseStats {
+            bytes_read: 3_000_000,
+            records: 15_000,
+            ..ParseStats::default()
+        };
+        assert_eq!(stats.throughput(), None);
+        stats.parse_duration = Some(Duration::ZERO);
+        assert_eq!(stats.throughput(), None);
+        stats.parse_duration = Some(Duration::from_millis(1500));
+        let throughput = stats.throughput().unwrap();
+        assert_eq!(throughput.records_per_sec, 10_000.0);
+        assert_eq!(throughput.mb_per_sec, 2.0);
+        assert_eq!(throughput.to_string(), "10000 records/s, 2.00 MB/s");
+    }
+
+    #[test]
+    fn meter_times_once() {
+        let mut meter = Meter::default();
+        meter.finish();
+        assert_eq!(meter.stats(0).parse_duration, None);
+        meter.start();
+        meter.read(10);
+        meter.record(true);
+        meter.record(true);
+        meter.record(false);
+        meter.finish();
+        let stats = meter.stats(2);
+        assert_eq!((stats.bytes_read, stats.records, stats.errors), (10, 1, 2));
+        let duration = stats.parse_duration.unwrap();
+        meter.finish();
+        assert_eq!(meter.stats(2).parse_duration, Some(duration));
+    }
+}
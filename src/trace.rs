@@ -0,0 +1,204 @@
+//! Instrumentation with the `tracing` feature, compiled out without it
+//!
+//! Section headers are `DEBUG` events, lines failing to parse `WARN` events and downloads
+//! `INFO` events. A parse ends with one `INFO` summary, so a clean parse of a file emits
+//! nothing else at `INFO` or above. Downloading and parsing happen in one `amfi_feed` span.
+
+use crate::Result;
+
+/// Emits a `tracing` event with the `tracing` feature, nothing without it
+///
+/// Takes the level macro's name then its arguments, e.g.
+/// `trace_event!(debug, line, "AMC header")`.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::$level!($($arg)+);
+        }
+    };
+}
+
+/// Span of downloading and parsing one feed
+#[derive(Debug, Clone)]
+pub(crate) struct FeedSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+/// Guard of an entered [`FeedSpan`]
+#[cfg(feature = "tracing")]
+pub(crate) type Entered<'a> = tracing::span::Entered<'a>;
+
+/// Guard of an entered [`FeedSpan`]
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Entered;
+
+impl FeedSpan {
+    /// Span of a feed read from elsewhere than a URL
+    #[inline]
+    pub(crate) fn new() -> Self {
+        FeedSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("amfi_feed"),
+        }
+    }
+
+    /// Span of a feed downloaded from `url`
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    #[inline]
+    pub(crate) fn url(url: &str) -> Self {
+        FeedSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("amfi_feed", url),
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[inline]
+    pub(crate) fn enter(&self) -> Entered<'_> {
+        self.span.enter()
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline]
+    pub(crate) fn enter(&self) -> Entered {
+        Entered
+    }
+}
+
+/// Counts of a parse for its summary event
+#[derive(Debug, Default)]
+pub(crate) struct FeedTrace {
+    #[cfg(feature = "tracing")]
+    counts: Counts,
+}
+
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default)]
+struct Counts {
+    bytes: usize,
+    records: usize,
+    errors: usize,
+    finished: bool,
+}
+
+impl FeedTrace {
+    /// Counts `bytes` read
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    #[inline]
+    pub(crate) fn read(&mut self, bytes: usize) {
+        #[cfg(feature = "tracing")]
+        {
+            self.counts.bytes += bytes;
+        }
+    }
+
+    /// Counts what the parse of `line` yielded, warning of errors
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    #[inline]
+    pub(crate) fn parsed<T>(&mut self, item: &Option<Result<T>>, line: usize) {
+        #[cfg(feature = "tracing")]
+        match item {
+            Some(Ok(_)) => self.counts.records += 1,
+            Some(Err(error)) => {
+                self.counts.errors += 1;
+                tracing::warn!(line, %error, "unparsable line");
+            }
+            None => {}
+        }
+    }
+
+    /// Emits the summary of the parse, once
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    #[inline]
+    pub(crate) fn finish(&mut self, lines: usize) {
+        #[cfg(feature = "tracing")]
+        {
+            let counts = &mut self.counts;
+            if !counts.finished {
+                counts.finished = true;
+                tracing::info!(
+                    lines,
+                    bytes = counts.bytes,
+                    records = counts.records,
+                    errors = counts.errors,
+                    "parsed feed"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::fmt::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    /// Subscriber keeping the level and fields of every event
+    #[derive(Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<(Level, String)>>>);
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            let level = *event.metadata().level();
+            self.0.lock().unwrap().push((level, fields.0));
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if !self.0.is_empty() {
+                self.0.push(' ');
+            }
+            write!(self.0, "{}={:?}", field.name(), value).unwrap();
+        }
+    }
+
+    #[test]
+    fn events_of_a_bad_line() {
+        let feed = "Open Ended Schemes(Equity Scheme - Large Cap Fund)\n\
+                    \n\
+                    Axis Mutual Fund\n\
+                    120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024\n\
+                    120466;garbage;\n\
+                    112277;INF846K01AE8;-;Axis Bluechip Fund - Regular Plan - Growth;54.12;21-Jun-2024\n";
+        let collector = Collector::default();
+        tracing::subscriber::with_default(collector.clone(), || {
+            assert_eq!(crate::nav_from_str(feed).count(), 3);
+        });
+        let events = collector.0.lock().unwrap();
+        let levels: Vec<Level> = events.iter().map(|(level, _)| *level).collect();
+        assert_eq!(
+            levels,
+            [Level::DEBUG, Level::DEBUG, Level::WARN, Level::INFO]
+        );
+        assert!(events[0].1.starts_with("message=scheme header line=1"));
+        assert!(events[1].1.contains("line=3 amc=Axis Mutual Fund"));
+        assert!(events[2]
+            .1
+            .starts_with("message=unparsable line line=5 error="));
+        assert_eq!(
+            events[3].1,
+            "message=parsed feed lines=6 bytes=250 records=2 errors=1"
+        );
+    }
+}
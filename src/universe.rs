@@ -0,0 +1,293 @@
+//! Launches, wind-ups and changes of schemes between two snapshots
+
+use crate::group::collapse_whitespace;
+use crate::{FundPlan, NavIndex, NavRecord, NavSnapshot, RawIsin, SchemeCode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Value of a field before and after a change
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldChange<T> {
+    /// Value in the old snapshot
+    pub before: T,
+    /// Value in the new snapshot
+    pub after: T,
+}
+
+impl<T: PartialEq + Clone> FieldChange<T> {
+    /// Change from `before` to `after`, `None` if they are equal
+    fn of(before: &T, after: &T) -> Option<Self> {
+        if before == after {
+            None
+        } else {
+            Some(FieldChange {
+                before: before.clone(),
+                after: after.clone(),
+            })
+        }
+    }
+}
+
+/// Scheme present in both snapshots whose details changed
+///
+/// Fields that didn't change are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SchemeChange {
+    /// Scheme code in the old snapshot
+    pub old_code: SchemeCode,
+    /// Scheme code in the new snapshot, differing from `old_code` for renumbered schemes
+    pub new_code: SchemeCode,
+    /// Scheme name
+    pub name: Option<FieldChange<String>>,
+    /// ISIN, growth or dividend payout
+    pub isin: Option<FieldChange<Option<RawIsin>>>,
+    /// ISIN, dividend reinvestment
+    pub isin_dr: Option<FieldChange<Option<RawIsin>>>,
+    /// Plan
+    pub plan: Option<FieldChange<FundPlan>>,
+    /// Category, compared with inner whitespace collapsed
+    pub category: Option<FieldChange<String>>,
+}
+
+impl SchemeChange {
+    fn new(old: &NavRecord, new: &NavRecord) -> Self {
+        let category = if collapse_whitespace(&old.category) == collapse_whitespace(&new.category) {
+            None
+        } else {
+            FieldChange::of(&old.category, &new.category)
+        };
+        SchemeChange {
+            old_code: old.code,
+            new_code: new.code,
+            name: FieldChange::of(&old.name, &new.name),
+            isin: FieldChange::of(&old.isin, &new.isin),
+            isin_dr: FieldChange::of(&old.isin_dr, &new.isin_dr),
+            plan: FieldChange::of(&old.plan, &new.plan),
+            category,
+        }
+    }
+
+    /// Whether the scheme code changed
+    pub fn is_renumbered(&self) -> bool {
+        self.old_code != self.new_code
+    }
+
+    /// Whether any compared field changed
+    pub fn has_changes(&self) -> bool {
+        self.name.is_some()
+            || self.isin.is_some()
+            || self.isin_dr.is_some()
+            || self.plan.is_some()
+            || self.category.is_some()
+    }
+}
+
+/// Result of [`compare_universe`](fn.compare_universe.html)
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UniverseDiff {
+    /// Schemes only in the new snapshot, i.e. launches, in scheme code order
+    pub added: Vec<NavRecord>,
+    /// Schemes only in the old snapshot, i.e. wound up or merged, in scheme code order
+    pub removed: Vec<NavRecord>,
+    /// Schemes whose code changed, matched by ISIN, in old scheme code order
+    pub renamed: Vec<SchemeChange>,
+    /// Schemes keeping their code whose name, ISIN, plan or category changed, in scheme
+    /// code order
+    pub changed: Vec<SchemeChange>,
+}
+
+impl UniverseDiff {
+    /// Whether the scheme universe is the same in both snapshots
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+/// Compares the schemes of two snapshots, ignoring their NAVs
+///
+/// Schemes are matched by code. A scheme whose code is missing from the new snapshot is
+/// matched by ISIN (growth or reinvestment) instead, provided that record's code is not in
+/// the old snapshot itself, and reported as renamed rather than removed and added. See
+/// [`diff_navs`](fn.diff_navs.html) for changes of NAVs.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let old = amfi::NavSnapshot::from_file("fixtures/NAVOpen.txt")?;
+/// let mut records = old.records.clone();
+/// records[0].name.push_str(" (Renamed)");
+/// let new: amfi::NavSnapshot = records.into_iter().map(Ok).collect();
+///
+/// let diff = amfi::compare_universe(&old, &new);
+/// assert_eq!(diff.changed.len(), 1);
+/// assert!(diff.changed[0].name.is_some());
+/// # Ok(())
+/// # }
+/// ```
+pub fn compare_universe(old: &NavSnapshot, new: &NavSnapshot) -> UniverseDiff {
+    compare_indexes(&old.index(), &new.index())
+}
+
+fn compare_indexes(old: &NavIndex, new: &NavIndex) -> UniverseDiff {
+    let mut diff = UniverseDiff::default();
+    let mut matched = HashSet::new();
+    for record in old {
+        if let Some(counterpart) = new.get_by_code(record.code) {
+            matched.insert(counterpart.code);
+            let change = SchemeChange::new(record, counterpart);
+            if change.has_changes() {
+                diff.changed.push(change);
+            }
+            continue;
+        }
+        let renumbered = record
+            .isin
+            .iter()
+            .chain(record.isin_dr.iter())
+            .filter_map(|isin| new.get_by_isin(isin.as_str()))
+            .find(|found| old.get_by_code(found.code).is_none() && !matched.contains(&found.code));
+        match renumbered {
+            Some(counterpart) => {
+                matched.insert(counterpart.code);
+                diff.renamed.push(SchemeChange::new(record, counterpart));
+            }
+            None => diff.removed.push(record.clone()),
+        }
+    }
+    diff.added = new
+        .iter()
+        .filter(|record| !matched.contains(&record.code))
+        .cloned()
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NavDate, NavRecordBuilder};
+
+    const LARGE_CAP: &str = "Open Ended Schemes(Equity Scheme - Large Cap Fund)";
+
+    fn record(code: u32, isin: &str, name: &str) -> NavRecord {
+        NavRecordBuilder::default()
+            .code(code)
+            .isin(Some(RawIsin::new(isin)))
+            .name(name)
+            .nav(10.0)
+            .date(NavDate::from_ymd(2024, 6, 21).unwrap())
+            .amc("Axis Mutual Fund")
+            .category(LARGE_CAP)
+            .plan(FundPlan::Direct)
+            .build()
+            .unwrap()
+    }
+
+    fn snapshot(records: Vec<NavRecord>) -> NavSnapshot {
+        records.into_iter().map(Ok).collect()
+    }
+
+    #[test]
+    fn added_removed_renamed_and_changed() {
+        let flexi_cap = "Open Ended Schemes(Equity Scheme - Flexi Cap Fund)";
+        let old = snapshot(vec![
+            record(1, "INF846K01AB4", "Axis Bluechip Fund"),
+            NavRecord {
+                plan: FundPlan::Regular,
+                ..record(2, "INF846K01AE8", "Axis Focused 25 Fund")
+            },
+            record(3, "INF846K01AD0", "Axis Midcap Fund"),
+            record(4, "INF846K01AC2", "Axis Old Fund"),
+            record(5, "INF846K01AF5", "Axis Spaced Fund"),
+        ]);
+        let new = snapshot(vec![
+            // Unchanged but for the NAV
+            NavRecord {
+                nav: 11.0,
+                ..record(1, "INF846K01AB4", "Axis Bluechip Fund")
+            },
+            NavRecord {
+                category: flexi_cap.to_string(),
+                ..record(2, "INF846K01AE8", "Axis Focused Fund")
+            },
+            // Renumbered, matched by ISIN
+            record(30, "INF846K01AD0", "Axis Midcap Fund"),
+            NavRecord {
+                category: LARGE_CAP.replace(' ', "  "),
+                ..record(5, "INF846K01AF5", "Axis Spaced Fund")
+            },
+            record(6, "INF846K01AG3", "Axis New Fund"),
+        ]);
+
+        let diff = compare_universe(&old, &new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].code, 6);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].code, 4);
+
+        assert_eq!(diff.renamed.len(), 1);
+        let renamed = &diff.renamed[0];
+        assert_eq!((renamed.old_code, renamed.new_code), (3.into(), 30.into()));
+        assert!(renamed.is_renumbered());
+        assert!(!renamed.has_changes());
+
+        assert_eq!(diff.changed.len(), 1);
+        let changed = &diff.changed[0];
+        assert!(!changed.is_renumbered());
+        assert_eq!(
+            changed.name,
+            Some(FieldChange {
+                before: "Axis Focused 25 Fund".to_string(),
+                after: "Axis Focused Fund".to_string(),
+            })
+        );
+        assert_eq!(
+            changed.plan,
+            Some(FieldChange {
+                before: FundPlan::Regular,
+                after: FundPlan::Direct,
+            })
+        );
+        assert_eq!(changed.category.as_ref().unwrap().after, flexi_cap);
+        assert_eq!(changed.isin, None);
+
+        assert!(compare_universe(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn changed_isin() {
+        let old = snapshot(vec![record(1, "INF846K01AB4", "Fund")]);
+        let new = snapshot(vec![record(1, "INF846K01AE8", "Fund")]);
+        let diff = compare_universe(&old, &new);
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.renamed.is_empty());
+        assert_eq!(
+            diff.changed[0].isin,
+            Some(FieldChange {
+                before: Some(RawIsin::new("INF846K01AB4")),
+                after: Some(RawIsin::new("INF846K01AE8")),
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes() {
+        let old = snapshot(vec![record(1, "INF846K01AB4", "Fund")]);
+        let new = snapshot(vec![record(2, "INF846K01AB4", "Fund II")]);
+        let diff = compare_universe(&old, &new);
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["renamed"][0]["old_code"], 1);
+        assert_eq!(json["renamed"][0]["new_code"], 2);
+        assert_eq!(json["renamed"][0]["name"]["before"], "Fund");
+        assert_eq!(json["renamed"][0]["name"]["after"], "Fund II");
+        assert_eq!(json["renamed"][0]["isin"], serde_json::Value::Null);
+        let back: UniverseDiff = serde_json::from_value(json).unwrap();
+        assert_eq!(back, diff);
+    }
+}
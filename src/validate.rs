@@ -0,0 +1,382 @@
+//! Semantic sanity checks of parsed records
+
+use crate::{
+    contains_ignore_ascii_case, FundPlan, IsinError, IsinStrictness, NavDate, NavRecord, RawIsin,
+    SchemeCode,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Limits applied by [`NavRecord::validate_with`](struct.NavRecord.html#method.validate_with)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationRules {
+    /// Smallest plausible NAV, `0.0` by default
+    pub min_nav: f64,
+    /// Largest plausible NAV, `100000.0` by default
+    pub max_nav: f64,
+    /// Earliest plausible date, 1990-01-01 by default
+    pub earliest_date: NavDate,
+    /// Latest plausible date, today in India by the system clock if `None` (the default)
+    ///
+    /// Set it where there is no system clock, e.g. on `wasm32-unknown-unknown`.
+    pub latest_date: Option<NavDate>,
+    /// Longest plausible scheme name in characters, 250 by default
+    pub max_name_len: usize,
+    /// Whether ISINs not starting with `INF` are reported,
+    /// [`Lenient`](enum.IsinStrictness.html#variant.Lenient) (not) by default
+    pub isin_strictness: IsinStrictness,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        ValidationRules {
+            min_nav: 0.0,
+            max_nav: 100_000.0,
+            earliest_date: NavDate::from_ymd(1990, 1, 1).expect("valid date"),
+            latest_date: None,
+            max_name_len: 250,
+            isin_strictness: IsinStrictness::Lenient,
+        }
+    }
+}
+
+/// Problem found in a record that parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// NAV is `NaN` or infinite
+    NavNotFinite,
+    /// NAV is outside `min_nav..=max_nav`
+    NavOutOfRange(f64),
+    /// Date is after `latest_date`
+    FutureDate(NavDate),
+    /// Date is before `earliest_date`
+    DateTooOld(NavDate),
+    /// `isin` fails validation
+    InvalidIsin(IsinError),
+    /// `isin_dr` fails validation
+    InvalidIsinDr(IsinError),
+    /// `isin` doesn't start with `INF`, reported unless `isin_strictness` is lenient
+    NonMfIsin(String),
+    /// `isin_dr` doesn't start with `INF`, reported unless `isin_strictness` is lenient
+    NonMfIsinDr(String),
+    /// Name is empty or whitespace
+    EmptyName,
+    /// Name is longer than `max_name_len`, with its length in characters
+    NameTooLong(usize),
+    /// Scheme code is zero
+    ZeroCode,
+    /// Name says one plan, `plan` the other
+    PlanMismatch {
+        /// Plan the name says
+        name: FundPlan,
+        /// Plan of the record
+        plan: FundPlan,
+    },
+    /// Scheme code already seen at the index, see [`validate_feed`](fn.validate_feed.html)
+    DuplicateCode(usize),
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationIssue::NavNotFinite => write!(f, "NAV is not a number"),
+            ValidationIssue::NavOutOfRange(nav) => write!(f, "implausible NAV {}", nav),
+            ValidationIssue::FutureDate(date) => write!(f, "date {} is in the future", date),
+            ValidationIssue::DateTooOld(date) => write!(f, "implausibly old date {}", date),
+            ValidationIssue::InvalidIsin(ref err) => write!(f, "invalid ISIN: {}", err),
+            ValidationIssue::InvalidIsinDr(ref err) => {
+                write!(f, "invalid reinvestment ISIN: {}", err)
+            }
+            ValidationIssue::NonMfIsin(ref isin) => {
+                write!(f, "ISIN {} is not of an Indian mutual fund", isin)
+            }
+            ValidationIssue::NonMfIsinDr(ref isin) => write!(
+                f,
+                "reinvestment ISIN {} is not of an Indian mutual fund",
+                isin
+            ),
+            ValidationIssue::EmptyName => write!(f, "empty name"),
+            ValidationIssue::NameTooLong(len) => write!(f, "name of {} characters", len),
+            ValidationIssue::ZeroCode => write!(f, "scheme code is zero"),
+            ValidationIssue::PlanMismatch { name, plan } => {
+                write!(f, "name says {} plan but plan is {}", name, plan)
+            }
+            ValidationIssue::DuplicateCode(first) => {
+                write!(f, "scheme code already at index {}", first)
+            }
+        }
+    }
+}
+
+/// Issue of a record of a feed, see [`validate_feed`](fn.validate_feed.html)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedIssue {
+    /// 0-based index of the record
+    pub index: usize,
+    /// Scheme code of the record
+    pub code: SchemeCode,
+    /// What is wrong
+    pub issue: ValidationIssue,
+}
+
+impl NavRecord {
+    /// Checks the record is plausible with the default
+    /// [`ValidationRules`](struct.ValidationRules.html)
+    ///
+    /// Parsing only checks the syntax of a line; this checks its values, returning every
+    /// issue found, none for a sane record.
+    ///
+    /// ```rust
+    /// use amfi::ValidationIssue;
+    ///
+    /// let mut record: amfi::NavRecord =
+    ///     "120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;21-Jun-2024"
+    ///         .parse()?;
+    /// assert!(record.validate().is_empty());
+    /// record.nav = -1.0;
+    /// assert_eq!(record.validate(), [ValidationIssue::NavOutOfRange(-1.0)]);
+    /// # Ok::<(), amfi::Error>(())
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        self.validate_with(&ValidationRules::default())
+    }
+
+    /// Checks the record is plausible with `rules`, see [`validate`](#method.validate)
+    pub fn validate_with(&self, rules: &ValidationRules) -> Vec<ValidationIssue> {
+        let latest = rules.latest_date.unwrap_or_else(NavDate::today);
+        self.issues(rules, latest)
+    }
+
+    fn issues(&self, rules: &ValidationRules, latest: NavDate) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if !self.nav.is_finite() {
+            issues.push(ValidationIssue::NavNotFinite);
+        } else if self.nav < rules.min_nav || self.nav > rules.max_nav {
+            issues.push(ValidationIssue::NavOutOfRange(self.nav));
+        }
+        if self.date > latest {
+            issues.push(ValidationIssue::FutureDate(self.date));
+        } else if self.date < rules.earliest_date {
+            issues.push(ValidationIssue::DateTooOld(self.date));
+        }
+        if let Some(Err(err)) = self.isin.as_ref().map(|isin| isin.validate()) {
+            issues.push(ValidationIssue::InvalidIsin(err));
+        }
+        if let Some(Err(err)) = self.isin_dr.as_ref().map(|isin| isin.validate()) {
+            issues.push(ValidationIssue::InvalidIsinDr(err));
+        }
+        if rules.isin_strictness != IsinStrictness::Lenient {
+            if let Some(isin) = non_mf(&self.isin) {
+                issues.push(ValidationIssue::NonMfIsin(isin));
+            }
+            if let Some(isin) = non_mf(&self.isin_dr) {
+                issues.push(ValidationIssue::NonMfIsinDr(isin));
+            }
+        }
+        let name_len = self.name.chars().count();
+        if self.name.trim().is_empty() {
+            issues.push(ValidationIssue::EmptyName);
+        } else if name_len > rules.max_name_len {
+            issues.push(ValidationIssue::NameTooLong(name_len));
+        }
+        if self.code.get() == 0 {
+            issues.push(ValidationIssue::ZeroCode);
+        }
+        if let Some(name) = name_plan(&self.name) {
+            if name != self.plan {
+                issues.push(ValidationIssue::PlanMismatch {
+                    name,
+                    plan: self.plan,
+                });
+            }
+        }
+        issues
+    }
+}
+
+/// Text of `isin` if it doesn't start with `INF`
+fn non_mf(isin: &Option<RawIsin>) -> Option<String> {
+    isin.as_ref()
+        .filter(|isin| !isin.is_indian_mf())
+        .map(|isin| isin.as_str().to_string())
+}
+
+/// Plan named by `name`, `None` if it names neither or both
+fn name_plan(name: &str) -> Option<FundPlan> {
+    match (
+        contains_ignore_ascii_case(name, "DIRECT"),
+        contains_ignore_ascii_case(name, "REGULAR"),
+    ) {
+        (true, false) => Some(FundPlan::Direct),
+        (false, true) => Some(FundPlan::Regular),
+        _ => None,
+    }
+}
+
+/// Checks every record with the default [`ValidationRules`](struct.ValidationRules.html) and
+/// the records against each other
+///
+/// Besides the issues of [`NavRecord::validate`](struct.NavRecord.html#method.validate),
+/// reports scheme codes published more than once. Issues come in record order.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let mut records: Vec<_> = amfi::nav_from_file("fixtures/NAVOpen.txt")?.collect::<Result<_, _>>()?;
+/// assert!(amfi::validate_feed(&records).is_empty());
+/// records.push(records[0].clone());
+/// let issues = amfi::validate_feed(&records);
+/// assert_eq!(issues[0].index, 30);
+/// assert_eq!(issues[0].issue, amfi::ValidationIssue::DuplicateCode(0));
+/// # Ok(())
+/// # }
+/// ```
+pub fn validate_feed<'a, I>(records: I) -> Vec<FeedIssue>
+where
+    I: IntoIterator<Item = &'a NavRecord>,
+{
+    validate_feed_with(records, &ValidationRules::default())
+}
+
+/// Checks every record with `rules` and the records against each other, see
+/// [`validate_feed`](fn.validate_feed.html)
+pub fn validate_feed_with<'a, I>(records: I, rules: &ValidationRules) -> Vec<FeedIssue>
+where
+    I: IntoIterator<Item = &'a NavRecord>,
+{
+    let latest = rules.latest_date.unwrap_or_else(NavDate::today);
+    let mut first_seen: HashMap<SchemeCode, usize> = HashMap::new();
+    let mut issues = Vec::new();
+    for (index, record) in records.into_iter().enumerate() {
+        let issue = |issue| FeedIssue {
+            index,
+            code: record.code,
+            issue,
+        };
+        issues.extend(record.issues(rules, latest).into_iter().map(issue));
+        let first = *first_seen.entry(record.code).or_insert(index);
+        if first != index {
+            issues.push(issue(ValidationIssue::DuplicateCode(first)));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_records;
+
+    fn rules() -> ValidationRules {
+        ValidationRules {
+            latest_date: NavDate::from_ymd(2024, 6, 21),
+            ..ValidationRules::default()
+        }
+    }
+
+    #[test]
+    fn fixture_is_sane() {
+        let records = fixture_records("NAVOpen.txt");
+        assert!(validate_feed_with(&records, &rules()).is_empty());
+        // The fixture's dates are in the past
+        assert!(validate_feed(&records).is_empty());
+    }
+
+    #[test]
+    fn record_issues() {
+        let mut record = fixture_records("NAVOpen.txt")[1].clone();
+        record.nav = f64::NAN;
+        record.date = NavDate::from_ymd(2024, 6, 22).unwrap();
+        record.isin = Some("INF846K01AC3".into());
+        record.isin_dr = Some("garbage".into());
+        record.name = "Axis Bluechip Fund - Regular Plan - IDCW".to_string();
+        record.plan = FundPlan::Direct;
+        record.code = 0.into();
+        assert_eq!(
+            record.validate_with(&rules()),
+            [
+                ValidationIssue::NavNotFinite,
+                ValidationIssue::FutureDate(record.date),
+                ValidationIssue::InvalidIsin(IsinError::CheckDigit {
+                    expected: '2',
+                    found: '3'
+                }),
+                ValidationIssue::InvalidIsinDr(IsinError::Length(7)),
+                ValidationIssue::ZeroCode,
+                ValidationIssue::PlanMismatch {
+                    name: FundPlan::Regular,
+                    plan: FundPlan::Direct
+                },
+            ]
+        );
+
+        record = fixture_records("NAVOpen.txt")[1].clone();
+        record.nav = 100_000.5;
+        record.date = NavDate::from_ymd(1989, 12, 31).unwrap();
+        record.name = " ".to_string();
+        assert_eq!(
+            record.validate_with(&rules()),
+            [
+                ValidationIssue::NavOutOfRange(100_000.5),
+                ValidationIssue::DateTooOld(record.date),
+                ValidationIssue::EmptyName,
+            ]
+        );
+        record.name = "x".repeat(251);
+        record.nav = 0.0;
+        record.date = NavDate::from_ymd(1990, 1, 1).unwrap();
+        assert_eq!(
+            record.validate_with(&rules()),
+            [ValidationIssue::NameTooLong(251)]
+        );
+    }
+
+    #[test]
+    fn non_mf_isins() {
+        let mut record = fixture_records("NAVOpen.txt")[1].clone();
+        record.isin = Some("INE009A01021".into());
+        record.isin_dr = Some("INE009A01022".into());
+        let mut rules = rules();
+        assert_eq!(
+            record.validate_with(&rules),
+            [ValidationIssue::InvalidIsinDr(IsinError::CheckDigit {
+                expected: '1',
+                found: '2'
+            })]
+        );
+        for &strictness in &[IsinStrictness::Warn, IsinStrictness::Strict] {
+            rules.isin_strictness = strictness;
+            assert_eq!(
+                record.validate_with(&rules)[1..],
+                [
+                    ValidationIssue::NonMfIsin("INE009A01021".to_string()),
+                    ValidationIssue::NonMfIsinDr("INE009A01022".to_string()),
+                ]
+            );
+        }
+        assert!(fixture_records("NAVOpen.txt")
+            .iter()
+            .all(|record| record.validate_with(&rules).is_empty()));
+    }
+
+    #[test]
+    fn duplicate_codes() {
+        let mut records = fixture_records("NAVOpen.txt");
+        records.push(records[3].clone());
+        records.push(records[3].clone());
+        records[5].code = 0.into();
+        let issues = validate_feed_with(&records, &rules());
+        let found: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.index, issue.code.get(), issue.issue.clone()))
+            .collect();
+        let dup = records[3].code.get();
+        assert_eq!(
+            found,
+            [
+                (5, 0, ValidationIssue::ZeroCode),
+                (30, dup, ValidationIssue::DuplicateCode(3)),
+                (31, dup, ValidationIssue::DuplicateCode(3)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,648 @@
+//! Polling for new feed publications
+
+use crate::date::IST_OFFSET;
+#[cfg(feature = "blocking")]
+use crate::fetch::{get_records, is_nav_updated_at};
+#[cfg(all(feature = "async", feature = "stream"))]
+use crate::fetch::{get_records_async, is_nav_updated_at_async};
+use crate::fetch::{FetchMeta, UpdateStatus};
+use crate::snapshot::{NavSnapshot, SnapshotSource};
+use crate::{expected_feed_date, NavDate, NavRecordIterator, RateLimiter, Result, BASE_URL};
+use chrono::{DateTime, FixedOffset, NaiveTime, Utc};
+#[cfg(all(feature = "async", feature = "stream"))]
+use futures_core::Stream;
+use log::{info, warn};
+#[cfg(all(feature = "async", feature = "stream"))]
+use std::future::Future;
+use std::io::Read;
+#[cfg(all(feature = "async", feature = "stream"))]
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(all(feature = "async", feature = "stream"))]
+use std::task::{Context, Poll};
+#[cfg(feature = "blocking")]
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Longest uninterrupted sleep, bounds how long [`WatchHandle::stop`] takes to be noticed
+const SLEEP_STEP: Duration = Duration::from_millis(250);
+
+/// Settings of [`watch_daily_nav`](fn.watch_daily_nav.html) and
+/// [`watch_daily_nav_stream`](fn.watch_daily_nav_stream.html)
+///
+/// ```rust
+/// use chrono::NaiveTime;
+/// use std::time::Duration;
+///
+/// let config = amfi::WatchConfig::new()
+///     .interval(Duration::from_secs(10 * 60))
+///     .window(NaiveTime::from_hms_opt(20, 0, 0).unwrap(), NaiveTime::from_hms_opt(2, 0, 0).unwrap())
+///     .max_duration(Duration::from_secs(8 * 3600));
+/// let handle = config.handle();
+/// # drop(handle);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    interval: Duration,
+    window: Option<(NaiveTime, NaiveTime)>,
+    max_duration: Option<Duration>,
+    url: String,
+    since: Option<FetchMeta>,
+    rate_limiter: Option<RateLimiter>,
+    business_days: bool,
+    stop: Arc<AtomicBool>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            interval: Duration::from_secs(15 * 60),
+            window: None,
+            max_duration: None,
+            url: BASE_URL.to_string(),
+            since: None,
+            rate_limiter: None,
+            business_days: false,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl WatchConfig {
+    /// Polls the AMFI portal every 15 minutes, at any time, until stopped
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time between polls
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Polls only from `start` until `end`, Indian Standard Time
+    ///
+    /// The window wraps around midnight when `end` is before `start`.
+    pub fn window(mut self, start: NaiveTime, end: NaiveTime) -> Self {
+        self.window = Some((start, end));
+        self
+    }
+
+    /// Stops watching after `max_duration`
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Polls `url` instead of the AMFI portal
+    pub fn url<T: Into<String>>(mut self, url: T) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Metadata of the feed already processed, e.g. stored by a previous run
+    ///
+    /// Without it the first feed fetched is reported as new.
+    pub fn since(mut self, meta: FetchMeta) -> Self {
+        self.since = Some(meta);
+        self
+    }
+
+    /// Waits for `limiter` before each request, e.g. one shared with
+    /// [`FetchOptions`](struct.FetchOptions.html) of other downloads
+    ///
+    /// Both the `HEAD` request of a poll and the download of a changed feed wait for it.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Pauses polling while the feed reported last is dated
+    /// [`expected_feed_date`](fn.expected_feed_date.html) or later, i.e. until the next
+    /// business day's feed is due
+    ///
+    /// Saves the polls of weekends, holidays and the hours before the evening publication,
+    /// at the cost of missing corrections republished under the same NAV date.
+    pub fn business_days(mut self, business_days: bool) -> Self {
+        self.business_days = business_days;
+        self
+    }
+
+    /// Handle to stop a watch using this config from another thread
+    pub fn handle(&self) -> WatchHandle {
+        WatchHandle {
+            stop: Arc::clone(&self.stop),
+        }
+    }
+}
+
+/// Stops a running [`watch_daily_nav`](fn.watch_daily_nav.html) or
+/// [`watch_daily_nav_stream`](fn.watch_daily_nav_stream.html)
+#[derive(Debug, Clone)]
+pub struct WatchHandle {
+    stop: Arc<AtomicBool>,
+}
+
+impl WatchHandle {
+    /// Asks the watch to return; it does so within a fraction of a second, or once the
+    /// callback in progress returns
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether `stop` has been called
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+}
+
+/// Polls the feed on the current thread, calling `callback` each time it changes
+///
+/// Snapshots passed to `callback` carry the HTTP metadata of the feed, see
+/// [`NavSnapshot::meta`](struct.NavSnapshot.html#method.meta); pass it to
+/// [`WatchConfig::since`](struct.WatchConfig.html#method.since) to resume watching later.
+///
+/// Every poll is a `HEAD` request (see [`is_nav_updated`](fn.is_nav_updated.html)); the
+/// feed is downloaded and parsed only when it changed. A feed is reported once per
+/// `Last-Modified`; if the server sends no validators, once per latest NAV date. Failed
+/// polls are logged and retried at the next interval.
+///
+/// Returns when [`WatchHandle::stop`](struct.WatchHandle.html#method.stop) is called or
+/// `max_duration` elapses. With the `async` and `stream` features,
+/// [`watch_daily_nav_stream`](fn.watch_daily_nav_stream.html) watches without blocking.
+///
+/// ```rust,no_run
+/// # fn main() -> Result<(), amfi::Error> {
+/// let config = amfi::WatchConfig::new();
+/// let handle = config.handle();
+/// amfi::watch_daily_nav(config, |snapshot| {
+///     println!("{} records", snapshot.records.len());
+///     handle.stop();
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn watch_daily_nav<F>(config: WatchConfig, mut callback: F) -> Result<()>
+where
+    F: FnMut(NavSnapshot),
+{
+    let mut watch = Watch::new(config);
+    while !watch.handle.is_stopped() {
+        if watch.polls_now() {
+            match poll(&watch.config, watch.last.as_ref(), watch.last_date) {
+                Ok(Some((snapshot, meta))) => callback(watch.changed(snapshot, meta)),
+                Ok(None) => {}
+                Err(error) => warn!("polling {} failed: {}", watch.config.url, error),
+            }
+        }
+        sleep_until(watch.next_wake(), &watch.handle);
+        if watch.expired() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Polls the feed without blocking, yielding a snapshot each time it changes
+///
+/// The counterpart of [`watch_daily_nav`](fn.watch_daily_nav.html), polling alike and
+/// reporting the same snapshots. The stream ends when
+/// [`WatchHandle::stop`](struct.WatchHandle.html#method.stop) is called or `max_duration`
+/// elapses; dropping it stops watching too.
+///
+/// ```rust,no_run
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// use futures_core::Stream;
+/// use std::future::poll_fn;
+/// use std::pin::Pin;
+///
+/// let mut snapshots = amfi::watch_daily_nav_stream(amfi::WatchConfig::new());
+/// while let Some(snapshot) = poll_fn(|cx| Pin::new(&mut snapshots).poll_next(cx)).await {
+///     println!("{} records", snapshot.records.len());
+/// }
+/// # }
+/// ```
+#[cfg(all(feature = "async", feature = "stream"))]
+pub fn watch_daily_nav_stream(config: WatchConfig) -> WatchStream {
+    WatchStream {
+        next: Some(Box::pin(next_change(Watch::new(config)))),
+    }
+}
+
+/// Snapshots of a changing feed, see [`watch_daily_nav_stream`](fn.watch_daily_nav_stream.html)
+#[cfg(all(feature = "async", feature = "stream"))]
+pub struct WatchStream {
+    next: Option<NextChange>,
+}
+
+/// Future of the next change of the feed and the watch to carry on with
+#[cfg(all(feature = "async", feature = "stream"))]
+type NextChange = Pin<Box<dyn Future<Output = Option<(NavSnapshot, Watch)>> + Send>>;
+
+#[cfg(all(feature = "async", feature = "stream"))]
+impl Stream for WatchStream {
+    type Item = NavSnapshot;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<NavSnapshot>> {
+        let this = self.get_mut();
+        let next = match this.next.as_mut() {
+            Some(next) => next,
+            None => return Poll::Ready(None),
+        };
+        match next.as_mut().poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some((snapshot, watch))) => {
+                this.next = Some(Box::pin(next_change(watch)));
+                Poll::Ready(Some(snapshot))
+            }
+            Poll::Ready(None) => {
+                this.next = None;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "async", feature = "stream"))]
+impl std::fmt::Debug for WatchStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchStream")
+            .field("done", &self.next.is_none())
+            .finish()
+    }
+}
+
+/// Polls until the feed changes, or `None` once the watch is over
+#[cfg(all(feature = "async", feature = "stream"))]
+async fn next_change(mut watch: Watch) -> Option<(NavSnapshot, Watch)> {
+    loop {
+        if let Some(wake) = watch.wake.take() {
+            sleep_until_async(wake, &watch.handle).await;
+            if watch.expired() {
+                return None;
+            }
+        }
+        if watch.handle.is_stopped() {
+            return None;
+        }
+        let mut changed = None;
+        if watch.polls_now() {
+            match poll_async(&watch.config, watch.last.as_ref(), watch.last_date).await {
+                Ok(Some((snapshot, meta))) => changed = Some(watch.changed(snapshot, meta)),
+                Ok(None) => {}
+                Err(error) => warn!("polling {} failed: {}", watch.config.url, error),
+            }
+        }
+        watch.wake = Some(watch.next_wake());
+        if let Some(snapshot) = changed {
+            return Some((snapshot, watch));
+        }
+    }
+}
+
+/// Progress of a watch between polls
+struct Watch {
+    config: WatchConfig,
+    handle: WatchHandle,
+    deadline: Option<Instant>,
+    /// Metadata of the feed reported last
+    last: Option<FetchMeta>,
+    /// Latest NAV date of the feed reported last
+    last_date: Option<NavDate>,
+    /// When to poll next, `None` to poll at once
+    #[cfg_attr(not(all(feature = "async", feature = "stream")), allow(dead_code))]
+    wake: Option<Instant>,
+}
+
+impl Watch {
+    fn new(config: WatchConfig) -> Self {
+        Watch {
+            deadline: config.max_duration.map(|max| Instant::now() + max),
+            handle: config.handle(),
+            last: config.since.clone(),
+            last_date: None,
+            wake: None,
+            config,
+        }
+    }
+
+    /// Whether to poll now, as the feed is due and the window open
+    fn polls_now(&self) -> bool {
+        let ist = FixedOffset::east_opt(IST_OFFSET).unwrap();
+        let now = DateTime::<Utc>::from(SystemTime::now())
+            .with_timezone(&ist)
+            .time();
+        let due = !self.config.business_days || is_due(self.last_date, SystemTime::now());
+        due && in_window(self.config.window, now)
+    }
+
+    /// Notes the feed of `snapshot` as reported
+    fn changed(&mut self, snapshot: NavSnapshot, meta: FetchMeta) -> NavSnapshot {
+        self.last = Some(meta);
+        self.last_date = snapshot.stats.max_date();
+        snapshot
+    }
+
+    /// When to poll next, an interval from now but no later than the deadline
+    fn next_wake(&self) -> Instant {
+        let wake = Instant::now() + self.config.interval;
+        self.deadline.map_or(wake, |deadline| wake.min(deadline))
+    }
+
+    /// Whether `max_duration` has elapsed
+    fn expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Fetches the feed if it changed since `last`, returning its metadata too
+#[cfg(feature = "blocking")]
+fn poll(
+    config: &WatchConfig,
+    last: Option<&FetchMeta>,
+    last_date: Option<NavDate>,
+) -> Result<Option<(NavSnapshot, FetchMeta)>> {
+    let limiter = config.rate_limiter.as_ref();
+    if let Some(last) = last {
+        if let Some(limiter) = limiter {
+            limiter.wait();
+        }
+        if is_nav_updated_at(&config.url, last)?.status == UpdateStatus::Unchanged {
+            return Ok(None);
+        }
+    }
+    let fetched_at = SystemTime::now().into();
+    let results = get_records(&config.url, limiter, true)?;
+    Ok(snapshot_if_new(
+        config, results, fetched_at, last, last_date,
+    ))
+}
+
+/// Fetches the feed without blocking if it changed since `last`, returning its metadata too
+#[cfg(all(feature = "async", feature = "stream"))]
+async fn poll_async(
+    config: &WatchConfig,
+    last: Option<&FetchMeta>,
+    last_date: Option<NavDate>,
+) -> Result<Option<(NavSnapshot, FetchMeta)>> {
+    let limiter = config.rate_limiter.as_ref();
+    if let Some(last) = last {
+        if let Some(limiter) = limiter {
+            limiter.wait_async().await;
+        }
+        let update = is_nav_updated_at_async(&config.url, last).await?;
+        if update.status == UpdateStatus::Unchanged {
+            return Ok(None);
+        }
+    }
+    let fetched_at = SystemTime::now().into();
+    let results = get_records_async(&config.url, limiter, true).await?;
+    Ok(snapshot_if_new(
+        config, results, fetched_at, last, last_date,
+    ))
+}
+
+/// Snapshot of the feed fetched into `results`, unless it is the one reported last
+fn snapshot_if_new<R: Read>(
+    config: &WatchConfig,
+    mut results: NavRecordIterator<R>,
+    fetched_at: DateTime<Utc>,
+    last: Option<&FetchMeta>,
+    last_date: Option<NavDate>,
+) -> Option<(NavSnapshot, FetchMeta)> {
+    let response = results.response_meta().cloned();
+    let meta = response.as_ref().map(FetchMeta::from).unwrap_or_default();
+    if last.is_some_and(|last| !is_new(&meta, last)) {
+        return None;
+    }
+    let source = SnapshotSource::Url {
+        url: config.url.clone(),
+        meta: meta.clone(),
+        response,
+    };
+    let snapshot = NavSnapshot::from_results(results.by_ref(), source, fetched_at);
+    let stats = results.stats();
+    if let (Some(duration), Some(throughput)) = (stats.parse_duration, stats.throughput()) {
+        info!(
+            "read {} bytes of {} in {:?}: {}",
+            stats.bytes_read, config.url, duration, throughput
+        );
+    }
+    let unknown = last.is_some_and(|last| meta.status_since(last) == UpdateStatus::Unknown);
+    if unknown && last_date.is_some() && snapshot.stats.max_date() == last_date {
+        return None;
+    }
+    Some((snapshot, meta))
+}
+
+/// Whether `meta` describes another publication than `last`
+fn is_new(meta: &FetchMeta, last: &FetchMeta) -> bool {
+    match (meta.last_modified, last.last_modified) {
+        (Some(modified), Some(since)) if modified == since => false,
+        _ => meta.status_since(last) != UpdateStatus::Unchanged,
+    }
+}
+
+/// Whether a feed newer than the one dated `last_date` is due at `now`
+fn is_due(last_date: Option<NavDate>, now: SystemTime) -> bool {
+    last_date < expected_feed_date(now)
+}
+
+/// Whether `time` is in `window`, any time is without one
+fn in_window(window: Option<(NaiveTime, NaiveTime)>, time: NaiveTime) -> bool {
+    match window {
+        None => true,
+        Some((start, end)) if start <= end => start <= time && time < end,
+        Some((start, end)) => start <= time || time < end,
+    }
+}
+
+#[cfg(feature = "blocking")]
+fn sleep_until(wake: Instant, handle: &WatchHandle) {
+    while !handle.is_stopped() {
+        let now = Instant::now();
+        if now >= wake {
+            return;
+        }
+        thread::sleep((wake - now).min(SLEEP_STEP));
+    }
+}
+
+#[cfg(all(feature = "async", feature = "stream"))]
+async fn sleep_until_async(wake: Instant, handle: &WatchHandle) {
+    while !handle.is_stopped() {
+        let now = Instant::now();
+        if now >= wake {
+            return;
+        }
+        tokio::time::sleep((wake - now).min(SLEEP_STEP)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn window_wraps_midnight() {
+        let night = Some((time(20, 0), time(2, 0)));
+        assert!(in_window(night, time(20, 0)));
+        assert!(in_window(night, time(23, 59)));
+        assert!(in_window(night, time(1, 30)));
+        assert!(!in_window(night, time(2, 0)));
+        assert!(!in_window(night, time(12, 0)));
+
+        let day = Some((time(9, 0), time(17, 0)));
+        assert!(in_window(day, time(9, 0)));
+        assert!(!in_window(day, time(17, 0)));
+        assert!(in_window(None, time(3, 0)));
+    }
+
+    #[test]
+    fn due_after_business_day_evenings() {
+        let friday = NavDate::from_ymd(2024, 6, 21);
+        let at = |day: u32, hour: i64| {
+            let date = NavDate::from_ymd(2024, 6, day).unwrap();
+            let seconds = date.unix_midnight_ist() + hour * 3600;
+            std::time::UNIX_EPOCH + Duration::from_secs(seconds as u64)
+        };
+        assert!(is_due(None, at(22, 10)));
+        assert!(is_due(NavDate::from_ymd(2024, 6, 20), at(22, 10)));
+        // Friday's feed holds over the weekend until Monday's is published
+        assert!(!is_due(friday, at(22, 10)));
+        assert!(!is_due(friday, at(24, 22)));
+        assert!(is_due(friday, at(24, 23)));
+    }
+
+    #[test]
+    fn same_last_modified_is_not_new() {
+        let last = FetchMeta {
+            last_modified: httpdate::parse_http_date("Fri, 21 Jun 2024 16:10:05 GMT").ok(),
+            etag: Some("\"a\"".to_string()),
+            content_length: Some(100),
+        };
+        let etag_only = FetchMeta {
+            etag: Some("\"b\"".to_string()),
+            ..last.clone()
+        };
+        assert!(!is_new(&last, &last));
+        assert!(!is_new(&etag_only, &last));
+        let republished = FetchMeta {
+            last_modified: httpdate::parse_http_date("Mon, 24 Jun 2024 16:10:05 GMT").ok(),
+            ..etag_only
+        };
+        assert!(is_new(&republished, &last));
+        assert!(is_new(&FetchMeta::default(), &last));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn stops_without_polling() {
+        let config = WatchConfig::new().url("http://127.0.0.1:1/NAVAll.txt");
+        config.handle().stop();
+        let mut calls = 0;
+        watch_daily_nav(config, |_| calls += 1).unwrap();
+        assert_eq!(calls, 0);
+
+        let now = DateTime::<Utc>::from(SystemTime::now())
+            .with_timezone(&FixedOffset::east_opt(IST_OFFSET).unwrap())
+            .time();
+        let later = now + chrono::Duration::hours(1);
+        let config = WatchConfig::new()
+            .url("http://127.0.0.1:1/NAVAll.txt")
+            .window(later, later + chrono::Duration::minutes(1))
+            .interval(Duration::from_millis(10))
+            .max_duration(Duration::from_millis(50));
+        watch_daily_nav(config, |_| calls += 1).unwrap();
+        assert_eq!(calls, 0);
+    }
+
+    #[cfg(all(feature = "async", feature = "stream"))]
+    async fn collect(mut snapshots: WatchStream) -> Vec<NavSnapshot> {
+        let mut collected = Vec::new();
+        while let Some(snapshot) =
+            std::future::poll_fn(|cx| Pin::new(&mut snapshots).poll_next(cx)).await
+        {
+            collected.push(snapshot);
+        }
+        collected
+    }
+
+    /// Serves the feed, always with the same `Last-Modified`, counting `GET` requests
+    #[cfg(all(feature = "async", feature = "stream"))]
+    fn serve() -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        const FEED: &[u8] = include_bytes!("../fixtures/NAVOpen.txt");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/NAVAll.txt", listener.local_addr().unwrap());
+        let gets = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = Arc::clone(&gets);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                let mut response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\
+                     Last-Modified: Fri, 21 Jun 2024 16:10:05 GMT\r\nConnection: close\r\n\r\n",
+                    FEED.len()
+                )
+                .into_bytes();
+                if request.starts_with("GET") {
+                    counted.fetch_add(1, Ordering::SeqCst);
+                    response.extend_from_slice(FEED);
+                }
+                let _ = stream.write_all(&response);
+            }
+        });
+        (url, gets)
+    }
+
+    #[cfg(all(feature = "async", feature = "stream"))]
+    #[tokio::test]
+    async fn stream_reports_each_publication_once() {
+        let (url, gets) = serve();
+        let config = WatchConfig::new()
+            .url(url)
+            .interval(Duration::from_millis(20))
+            .max_duration(Duration::from_millis(300));
+        let snapshots = collect(watch_daily_nav_stream(config)).await;
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].records.len(), 30);
+        assert_eq!(gets.load(Ordering::SeqCst), 1);
+
+        // Resuming from the feed reported, nothing is new
+        let since = snapshots[0].meta().cloned().unwrap();
+        let (url, gets) = serve();
+        let config = WatchConfig::new()
+            .url(url)
+            .since(since)
+            .interval(Duration::from_millis(20))
+            .max_duration(Duration::from_millis(100));
+        assert!(collect(watch_daily_nav_stream(config)).await.is_empty());
+        assert_eq!(gets.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(all(feature = "async", feature = "stream"))]
+    #[tokio::test]
+    async fn stream_ends_when_stopped() {
+        let config = WatchConfig::new().url("http://127.0.0.1:1/NAVAll.txt");
+        config.handle().stop();
+        assert!(collect(watch_daily_nav_stream(config)).await.is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+//! Writing records back out in the AMFI NAVAll format
+
+use crate::{FundMaturity, Layout, NavRecord, RawIsin, Result};
+use std::io::Write;
+
+/// Column header of the NAVAll feed
+pub const FEED_HEADER: &str =
+    "Scheme Code;ISIN Div Payout/ ISIN Growth;ISIN Div Reinvestment;Scheme Name;Net Asset Value;Date";
+
+/// Column header of the NAV history report of one AMC and older archives, see
+/// [`Layout::Legacy`](enum.Layout.html#variant.Legacy)
+pub const LEGACY_FEED_HEADER: &str = "Scheme Code;ISIN Div Payout/ ISIN Growth;\
+    ISIN Div Reinvestment;Scheme Name;Net Asset Value;Repurchase Price;Sale Price;Date";
+
+/// Column header of the NAV history report of all AMCs, see
+/// [`Layout::History`](enum.Layout.html#variant.History)
+pub const HISTORY_FEED_HEADER: &str = "Scheme Code;Scheme Name;ISIN Div Payout/ISIN Growth;\
+    ISIN Div Reinvestment;Net Asset Value;Repurchase Price;Sale Price;Date";
+
+/// Scheme section of a record: maturity, scheme and category
+type Section = (Option<FundMaturity>, Option<String>, String);
+
+/// Records of a section grouped by AMC
+type AmcGroups<'a> = Vec<(&'a str, Vec<&'a NavRecord>)>;
+
+/// Writes records as a NAVAll feed
+///
+/// Output starts with the column header, followed by the scheme section headers (e.g.
+/// `Open Ended Schemes(Equity Scheme - Large Cap Fund)`) and AMC headers reconstructed from
+/// the records' `maturity`, `scheme`, `category` and `amc` fields, and the record lines in
+/// their [`Display`](struct.NavRecord.html#impl-Display) format. Lines end with `\r\n`
+/// like the published feed.
+///
+/// Records without maturity are written under `Interval Fund Schemes` headers, which the
+/// parser does not recognize as section headers.
+///
+/// In the [`Legacy`](enum.Layout.html#variant.Legacy) and
+/// [`History`](enum.Layout.html#variant.History) layouts of the NAV history report, see
+/// [`layout`](#method.layout), record lines have the repurchase and sale prices after the
+/// NAV, `N.A.` if `None`.
+///
+/// ```rust
+/// # fn main() -> Result<(), amfi::Error> {
+/// let records: Vec<amfi::NavRecord> = amfi::nav_from_file("fixtures/NAVOpen.txt")?
+///     .filter_map(Result::ok)
+///     .filter(|record| record.plan == amfi::FundPlan::Direct)
+///     .collect();
+/// let mut writer = amfi::NavWriter::new(Vec::new());
+/// writer.write_records(&records)?;
+/// let feed = writer.finish()?;
+/// # assert!(!feed.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub struct NavWriter<W: Write> {
+    writer: W,
+    header_written: bool,
+    section: Option<Section>,
+    amc: Option<String>,
+    layout: Layout,
+}
+
+impl<W: Write> NavWriter<W> {
+    /// Wraps `writer`
+    pub fn new(writer: W) -> Self {
+        NavWriter {
+            writer,
+            header_written: false,
+            section: None,
+            amc: None,
+            layout: Layout::Modern,
+        }
+    }
+
+    /// Writes columns of `layout`, [`Modern`](enum.Layout.html#variant.Modern) by default
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), amfi::Error> {
+    /// use amfi::{Layout, NavRecord};
+    ///
+    /// let mut writer = amfi::NavWriter::new(Vec::new()).layout(Layout::Legacy);
+    /// writer.write_record(&NavRecord::fixture())?;
+    /// let feed = String::from_utf8(writer.finish()?).unwrap();
+    /// assert_eq!(
+    ///     feed.lines().nth(6),
+    ///     Some("120465;INF846K01AB4;-;Axis Bluechip Fund - Direct Plan - Growth;61.43;N.A.;N.A.;21-Jun-2024")
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Writes one record, preceded by section/AMC headers when they differ from the previous
+    /// record's
+    ///
+    /// Records must be supplied grouped by section and AMC, otherwise headers are repeated.
+    /// Use [`write_records`](#method.write_records) for input in arbitrary order.
+    pub fn write_record(&mut self, record: &NavRecord) -> Result<()> {
+        self.write_header()?;
+        let section = section_of(record);
+        let new_section = self.section.as_ref() != Some(&section);
+        if new_section || self.amc.as_ref() != Some(&record.amc) {
+            if self.amc.is_some() {
+                self.write_line("")?;
+            }
+            if new_section {
+                self.write_line(&section_header(&section))?;
+                self.write_line("")?;
+                self.section = Some(section);
+            }
+            self.write_line(&record.amc)?;
+            self.write_line("")?;
+            self.amc = Some(record.amc.clone());
+        }
+        self.write_line(&record_line(record, self.layout))
+    }
+
+    /// Writes records grouped by section, then AMC
+    ///
+    /// Sections and AMCs appear in the order they are first seen; records keep their relative
+    /// order within a group. In the layouts of the NAV history report, i.e. all but
+    /// [`Modern`](enum.Layout.html#variant.Modern), records of several days are sorted by date
+    /// within their group.
+    pub fn write_records<'a, I>(&mut self, records: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a NavRecord>,
+    {
+        for record in feed_order(records, self.layout) {
+            self.write_record(record)?;
+        }
+        Ok(())
+    }
+
+    /// Terminates the last section, flushes and returns the underlying writer
+    pub fn finish(mut self) -> Result<W> {
+        self.write_header()?;
+        if self.amc.is_some() {
+            self.write_line("")?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        if !self.header_written {
+            self.header_written = true;
+            let header = match self.layout {
+                Layout::Modern => FEED_HEADER,
+                Layout::Legacy => LEGACY_FEED_HEADER,
+                Layout::History => HISTORY_FEED_HEADER,
+            };
+            self.write_line(header)?;
+            self.write_line("")?;
+        }
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        write!(self.writer, "{}\r\n", line)?;
+        Ok(())
+    }
+}
+
+/// `records` grouped by section, then AMC, as written by
+/// [`NavWriter::write_records`](struct.NavWriter.html#method.write_records)
+pub(crate) fn feed_order<'a, I>(records: I, layout: Layout) -> Vec<&'a NavRecord>
+where
+    I: IntoIterator<Item = &'a NavRecord>,
+{
+    let mut sections: Vec<(Section, AmcGroups)> = Vec::new();
+    for record in records {
+        let section = section_of(record);
+        let index = match sections.iter().position(|(s, _)| *s == section) {
+            Some(index) => index,
+            None => {
+                sections.push((section, Vec::new()));
+                sections.len() - 1
+            }
+        };
+        let amcs = &mut sections[index].1;
+        match amcs.iter_mut().find(|(amc, _)| *amc == record.amc) {
+            Some((_, group)) => group.push(record),
+            None => amcs.push((&record.amc, vec![record])),
+        }
+    }
+    let mut ordered = Vec::new();
+    for (_, amcs) in sections {
+        for (_, mut group) in amcs {
+            if layout != Layout::Modern {
+                group.sort_by_key(|record| record.date);
+            }
+            ordered.extend(group);
+        }
+    }
+    ordered
+}
+
+/// Line of `record` in `layout`
+fn record_line(record: &NavRecord, layout: Layout) -> String {
+    let price = |price: Option<f64>| price.map_or("N.A.".to_string(), |p| p.to_string());
+    let line = record.to_string();
+    match (layout, line.rsplit_once(';')) {
+        (Layout::Legacy, Some((columns, date))) => format!(
+            "{};{};{};{}",
+            columns,
+            price(record.repurchase_price),
+            price(record.sale_price),
+            date
+        ),
+        (Layout::History, _) => {
+            let isin =
+                |isin: &Option<RawIsin>| isin.as_ref().map_or("-", RawIsin::as_str).to_string();
+            format!(
+                "{};{};{};{};{};{};{};{}",
+                record.code,
+                record.name,
+                isin(&record.isin),
+                isin(&record.isin_dr),
+                record.nav,
+                price(record.repurchase_price),
+                price(record.sale_price),
+                record.date.feed_format()
+            )
+        }
+        _ => line,
+    }
+}
+
+fn section_of(record: &NavRecord) -> Section {
+    (
+        record.maturity,
+        record.scheme.clone(),
+        record.category.clone(),
+    )
+}
+
+/// Scheme header line of `section`
+///
+/// Sections of no maturity are those of the portal's interval funds, whose header the parser
+/// reads back as a scheme header of no maturity.
+fn section_header(section: &Section) -> String {
+    let (maturity, ref scheme, ref category) = *section;
+    let maturity = match maturity {
+        Some(maturity) => format!("{} Schemes", maturity),
+        None => "Interval Fund Schemes".to_string(),
+    };
+    match *scheme {
+        Some(ref scheme) => format!("{}({} - {})", maturity, scheme.trim(), category.trim()),
+        None => format!("{}({})", maturity, category.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_path, fixture_records};
+    use crate::{LineType, NavRecordIterator};
+
+    fn reparse(feed: Vec<u8>) -> Vec<NavRecord> {
+        NavRecordIterator::new(&feed[..])
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut writer = NavWriter::new(Vec::new());
+        writer.write_records(&records).unwrap();
+        let feed = writer.finish().unwrap();
+        assert!(feed.starts_with(FEED_HEADER.as_bytes()));
+        assert_eq!(reparse(feed), records);
+    }
+
+    #[test]
+    fn round_trip_without_maturity() {
+        let records = vec![
+            NavRecord::fixture(),
+            NavRecord {
+                maturity: None,
+                ..NavRecord::fixture().with_code(118_544)
+            },
+        ];
+        for record in &records {
+            let header = section_header(&section_of(record));
+            assert_eq!(LineType::of(&header), LineType::Scheme, "{}", header);
+        }
+        let mut writer = NavWriter::new(Vec::new());
+        writer.write_records(&records).unwrap();
+        assert_eq!(reparse(writer.finish().unwrap()), records);
+    }
+
+    #[test]
+    fn legacy_round_trip() {
+        let records = fixture_records("NAVLegacy.txt");
+        assert!(records.iter().any(|record| record.sale_price.is_none()));
+        let mut writer = NavWriter::new(Vec::new()).layout(Layout::Legacy);
+        writer.write_records(&records).unwrap();
+        let feed = writer.finish().unwrap();
+        assert!(feed.starts_with(LEGACY_FEED_HEADER.as_bytes()));
+        let reparsed = NavRecordIterator::new(&feed[..]);
+        let reparsed: Vec<NavRecord> = reparsed.collect::<Result<_>>().unwrap();
+        assert_eq!(reparsed, records);
+
+        // The modern layout drops the prices, as the daily feed has none
+        let mut writer = NavWriter::new(Vec::new()).layout(Layout::Modern);
+        writer.write_records(&records).unwrap();
+        let mut modern = records;
+        for record in &mut modern {
+            record.repurchase_price = None;
+            record.sale_price = None;
+        }
+        assert_eq!(reparse(writer.finish().unwrap()), modern);
+    }
+
+    #[test]
+    fn history_is_sorted_by_amc_then_date() {
+        // Feeds of three days, one after the other
+        let path = fixture_path("NAVHistory.txt");
+        let records: Vec<NavRecord> = crate::nav_from_file(&path)
+            .unwrap()
+            .filter_map(Result::ok)
+            .collect();
+        let mut writer = NavWriter::new(Vec::new()).layout(Layout::Legacy);
+        writer.write_records(records.iter().rev()).unwrap();
+        let feed = writer.finish().unwrap();
+        let text = String::from_utf8(feed.clone()).unwrap();
+        assert_eq!(text.matches("Scheme Code;").count(), 1);
+
+        let reparsed = reparse(feed);
+        // One run of records per section and AMC, across days
+        let mut groups: Vec<(&str, &str)> = reparsed
+            .iter()
+            .map(|record| (record.category.as_str(), record.amc.as_str()))
+            .collect();
+        groups.dedup();
+        let mut unique = groups.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(groups.len(), unique.len());
+        for run in reparsed.windows(2) {
+            if run[0].amc == run[1].amc && run[0].category == run[1].category {
+                assert!(run[0].date <= run[1].date);
+            }
+        }
+        let mut expected = records;
+        let mut reparsed = reparsed;
+        expected.sort_by_key(NavRecord::key);
+        reparsed.sort_by_key(NavRecord::key);
+        assert_eq!(reparsed, expected);
+    }
+
+    #[test]
+    fn out_of_order_records_are_grouped() {
+        let records = fixture_records("NAVOpen.txt");
+        let mut shuffled: Vec<&NavRecord> = records.iter().step_by(2).collect();
+        shuffled.extend(records.iter().skip(1).step_by(2));
+
+        let mut writer = NavWriter::new(Vec::new());
+        writer.write_records(shuffled.iter().cloned()).unwrap();
+        let feed = writer.finish().unwrap();
+        let text = String::from_utf8(feed.clone()).unwrap();
+        assert_eq!(text.matches("Axis Mutual Fund").count(), 3);
+
+        let mut reparsed = reparse(feed);
+        let mut expected = records;
+        reparsed.sort_by_key(NavRecord::key);
+        expected.sort_by_key(NavRecord::key);
+        assert_eq!(reparsed, expected);
+    }
+}
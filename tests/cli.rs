@@ -0,0 +1,120 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/NAVOpen.txt");
+
+fn amfi(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_amfi"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn filter_prints_matching_records() {
+    let output = amfi(&["filter", "--amc", "axis", "--plan", "direct", FIXTURE]);
+    assert_eq!(output.status.code(), Some(0));
+    let text = stdout(&output);
+    assert!(!text.is_empty());
+    assert!(text.lines().all(|line| line.contains("Axis")));
+    assert!(text.lines().all(|line| line.contains("Direct")));
+}
+
+#[test]
+fn fetch_round_trips_feed() {
+    let output = amfi(&["fetch", "--stats", FIXTURE]);
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let mut lines = stderr.lines();
+    assert_eq!(lines.next(), Some("records: 30, errors: 0"));
+    let bytes = std::fs::metadata(FIXTURE).unwrap().len();
+    let parsed = lines.next().unwrap();
+    assert!(
+        parsed.starts_with(&format!("parsed {} bytes in ", bytes)),
+        "{}",
+        parsed
+    );
+    assert!(parsed.ends_with(" MB/s"), "{}", parsed);
+    assert_eq!(lines.next(), None);
+    let records: Vec<amfi::NavRecord> = amfi::nav_from_file(FIXTURE)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let mut writer = amfi::NavWriter::new(Vec::new());
+    writer.write_records(&records).unwrap();
+    assert_eq!(output.stdout, writer.finish().unwrap());
+}
+
+#[test]
+fn progress_lists_amcs() {
+    let output = amfi(&["json", "--progress", "-o", "/dev/null", FIXTURE]);
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let total = amfi::amc_list(std::fs::File::open(FIXTURE).unwrap())
+        .unwrap()
+        .len();
+    let first = stderr.lines().next().unwrap();
+    assert!(
+        first.starts_with(&format!(
+            "finished Axis Mutual Fund (1 of {} AMCs done): ",
+            total
+        )),
+        "{}",
+        first
+    );
+    assert!(stderr.trim_end().ends_with(" 30 in all"), "{}", stderr);
+}
+
+#[test]
+fn fetch_prints_table() {
+    let output = amfi(&["fetch", "--table", "--plan", "direct", FIXTURE]);
+    assert_eq!(output.status.code(), Some(0));
+    let text = stdout(&output);
+    let mut lines = text.lines();
+    let header = lines.next().unwrap();
+    assert!(header.trim_start().starts_with("Code  Name"));
+    assert!(header.ends_with("NAV  Date"));
+    let first = lines.next().unwrap();
+    // Long names are cut with an ellipsis, and every row has the same width
+    assert!(first.starts_with("120465  Axis Bluechip Fund - Direct Plan - Growth"));
+    assert!(first.ends_with("61.4300  2024-06-21"));
+    assert!(lines.all(|line| line.chars().count() == first.chars().count()));
+}
+
+#[test]
+fn json_and_csv_have_one_line_per_record() {
+    let json = amfi(&["json", FIXTURE]);
+    assert_eq!(json.status.code(), Some(0));
+    assert_eq!(stdout(&json).lines().count(), 30);
+
+    let csv = amfi(&["csv", "--category", "Large Cap", FIXTURE]);
+    assert_eq!(csv.status.code(), Some(0));
+    assert_eq!(stdout(&csv).lines().count(), 1 + 5);
+}
+
+#[test]
+fn diff_of_same_feed_is_empty() {
+    let output = amfi(&["diff", FIXTURE, FIXTURE]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn exit_codes() {
+    assert_eq!(amfi(&[]).status.code(), Some(2));
+    assert_eq!(amfi(&["diff", FIXTURE]).status.code(), Some(2));
+    assert_eq!(amfi(&["--plan", "gold", "json"]).status.code(), Some(2));
+    assert_eq!(amfi(&["json", "no/such/file.txt"]).status.code(), Some(1));
+
+    let path = std::env::temp_dir().join(format!("amfi-cli-{}.txt", std::process::id()));
+    std::fs::write(&path, "Axis Mutual Fund\n120465;garbage;\n").unwrap();
+    let output = amfi(&["--quiet", "json", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    assert!(output.stderr.is_empty());
+}